@@ -0,0 +1,45 @@
+// src/clanopedia_backend/src/activity.rs
+//
+// Every call that changes state is logged under the caller's principal, so users can audit
+// what their identity has done across collections, including calls made through a leaked
+// Internet Identity delegation. A canister sees only the principal, not which device or
+// session signed the call; II itself lists the devices behind an identity. Each principal
+// keeps its latest entries only, and anonymous calls are not logged.
+
+use candid::Principal;
+
+use crate::{
+    slugs, storage,
+    types::{ActivityEntry, StorageMap},
+};
+
+const MAX_ENTRIES_PER_PRINCIPAL: u64 = 1_000;
+const MAX_ACTIVITY_PAGE: usize = 100;
+
+/// Log a call to `method`. Best effort: nothing is logged once the log is at its cap.
+pub fn record(caller: Principal, method: &str, collection_id: Option<&str>) {
+    if caller == Principal::anonymous()
+        || storage::ensure_capacity(StorageMap::Activity, 1).is_err()
+    {
+        return;
+    }
+    storage::append_activity(
+        &caller,
+        method,
+        collection_id.map(slugs::collection_id),
+        MAX_ENTRIES_PER_PRINCIPAL,
+    );
+}
+
+/// The caller's entries before `before_sequence`, or the latest, newest first
+pub fn get_my_activity(
+    caller: Principal,
+    before_sequence: Option<u64>,
+    limit: u32,
+) -> Vec<ActivityEntry> {
+    storage::list_activity(
+        &caller,
+        before_sequence,
+        (limit as usize).min(MAX_ACTIVITY_PAGE),
+    )
+}
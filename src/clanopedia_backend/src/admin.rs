@@ -0,0 +1,174 @@
+// src/clanopedia_backend/src/admin.rs
+//
+// Canister-wide operations for controllers: inspecting every collection, freezing one that
+// misbehaves, repairing broken Blueband links and clearing up leftover records.
+
+use candid::Principal;
+use ic_cdk::api::time;
+
+use crate::{
+    config_history,
+    external::blueband,
+    proxy::require_controller,
+    storage,
+    types::{
+        ClanopediaError, ClanopediaResult, Collection, CollectionAdminSummary, ConfigSetting,
+        EventKind, EventLogEntry, MapPurge, PauseInfo,
+    },
+};
+
+const MAX_EVENT_PAGE: usize = 100;
+
+/// Paused collections reject writes, searches and governance until a controller resumes them
+pub fn ensure_not_paused(collection: &Collection) -> ClanopediaResult<()> {
+    match &collection.paused {
+        Some(pause) => Err(ClanopediaError::InvalidOperation(format!(
+            "Collection {} is paused: {}",
+            collection.id, pause.reason
+        ))),
+        None => Ok(()),
+    }
+}
+
+pub fn list_collections(caller: Principal) -> ClanopediaResult<Vec<CollectionAdminSummary>> {
+    require_controller(caller)?;
+    Ok(storage::list_collections()
+        .into_iter()
+        .map(|c| CollectionAdminSummary {
+            proposal_count: storage::list_proposals(&c.id).len() as u64,
+            indexed_documents: storage::count_indexed_documents(&c.id),
+            collection_id: c.id,
+            name: c.name,
+            creator: c.creator,
+            admins: c.admins,
+            blueband_collection_id: c.blueband_collection_id,
+            cycles_balance: c.cycles_balance,
+            created_at: c.created_at,
+            updated_at: c.updated_at,
+            trashed: c.trashed.is_some(),
+            paused: c.paused,
+            archived: c.archived.is_some(),
+        })
+        .collect())
+}
+
+pub fn pause_collection(
+    caller: Principal,
+    collection_id: &str,
+    reason: String,
+) -> ClanopediaResult<()> {
+    require_controller(caller)?;
+    let mut collection = storage::get_collection(&collection_id.to_string())?;
+    if collection.paused.is_some() {
+        return Err(ClanopediaError::InvalidOperation(format!(
+            "Collection {} is already paused",
+            collection_id
+        )));
+    }
+
+    collection.paused = Some(PauseInfo {
+        paused_by: caller,
+        paused_at: time(),
+        reason,
+    });
+    storage::update_collection(&collection_id.to_string(), &collection)
+}
+
+pub fn resume_collection(caller: Principal, collection_id: &str) -> ClanopediaResult<()> {
+    require_controller(caller)?;
+    let mut collection = storage::get_collection(&collection_id.to_string())?;
+    if collection.paused.take().is_none() {
+        return Err(ClanopediaError::InvalidOperation(format!(
+            "Collection {} is not paused",
+            collection_id
+        )));
+    }
+    storage::update_collection(&collection_id.to_string(), &collection)
+}
+
+/// Point a collection at a different Blueband collection, which must already exist
+pub async fn set_blueband_collection_id(
+    caller: Principal,
+    collection_id: &str,
+    blueband_collection_id: String,
+) -> ClanopediaResult<()> {
+    require_controller(caller)?;
+    let mut collection = storage::get_collection(&collection_id.to_string())?;
+
+    blueband::get_blueband_collection(&blueband_collection_id)
+        .await
+        .map_err(|e| ClanopediaError::BluebandError(e.to_string()))?
+        .ok_or_else(|| {
+            ClanopediaError::NotFound(format!(
+                "Blueband collection {} not found",
+                blueband_collection_id
+            ))
+        })?;
+
+    collection.blueband_collection_id = blueband_collection_id;
+    collection.updated_at = time();
+    storage::update_collection(&collection_id.to_string(), &collection)
+}
+
+pub fn purge_orphaned_entries(caller: Principal) -> ClanopediaResult<Vec<MapPurge>> {
+    require_controller(caller)?;
+    Ok(storage::purge_orphaned_entries())
+}
+
+/// Health-check a Blueband canister to swap to, returning the current one. Collections keep
+/// their Blueband collection IDs, so it must also hold the same collections.
+pub async fn check_blueband_canister(
+    canister_id: Principal,
+) -> ClanopediaResult<Option<Principal>> {
+    let previous = crate::get_blueband_canister_id().ok();
+    if previous == Some(canister_id) {
+        return Err(ClanopediaError::InvalidOperation(format!(
+            "Blueband canister is already {}",
+            canister_id
+        )));
+    }
+
+    blueband::check_canister(canister_id).await.map_err(|e| {
+        ClanopediaError::BluebandError(format!(
+            "Blueband canister {} failed its health check: {}",
+            canister_id, e
+        ))
+    })?;
+    Ok(previous)
+}
+
+/// Swap the Blueband canister every collection talks to, e.g. after a redeploy, once a
+/// second controller confirmed the request. The canister is health-checked again first.
+pub async fn swap_blueband_canister(
+    requested_by: Principal,
+    confirmed_by: Principal,
+    canister_id: Principal,
+) -> ClanopediaResult<()> {
+    let previous = check_blueband_canister(canister_id).await?;
+    crate::set_blueband_canister_id(canister_id);
+    storage::append_event(
+        confirmed_by,
+        EventKind::BluebandCanisterChanged {
+            previous,
+            new: canister_id,
+        },
+    );
+    config_history::record(
+        requested_by,
+        Some(confirmed_by),
+        ConfigSetting::BluebandCanister(canister_id),
+    )?;
+    Ok(())
+}
+
+pub fn list_events(
+    caller: Principal,
+    after_sequence: Option<u64>,
+    limit: u32,
+) -> ClanopediaResult<Vec<EventLogEntry>> {
+    require_controller(caller)?;
+    Ok(storage::list_events(
+        after_sequence,
+        (limit as usize).min(MAX_EVENT_PAGE),
+    ))
+}
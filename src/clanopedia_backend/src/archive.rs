@@ -0,0 +1,69 @@
+// src/clanopedia_backend/src/archive.rs
+//
+// Archiving is how a clan sunsets a collection without deleting its knowledge. An archived
+// collection stays listed, searchable and readable (and can be forked), but rejects every
+// write and extraction. Only an UnarchiveCollection proposal can still be created, voted
+// on and executed.
+
+use ic_cdk::api::time;
+
+use crate::{
+    storage,
+    types::{ArchiveInfo, ClanopediaError, ClanopediaResult, Collection, ProposalType},
+};
+
+/// Archived collections are read-only until a governance proposal unarchives them
+pub fn ensure_not_archived(collection: &Collection) -> ClanopediaResult<()> {
+    match collection.archived {
+        Some(_) => Err(ClanopediaError::InvalidOperation(format!(
+            "Collection {} is archived and read-only",
+            collection.id
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// Proposals of an archived collection are limited to unarchiving it
+pub fn ensure_proposal_allowed(
+    collection: &Collection,
+    proposal_type: &ProposalType,
+) -> ClanopediaResult<()> {
+    match proposal_type {
+        ProposalType::UnarchiveCollection => Ok(()),
+        _ => ensure_not_archived(collection),
+    }
+}
+
+pub fn validate_archive(collection: &Collection) -> ClanopediaResult<()> {
+    ensure_not_archived(collection)
+}
+
+pub fn validate_unarchive(collection: &Collection) -> ClanopediaResult<()> {
+    match collection.archived {
+        Some(_) => Ok(()),
+        None => Err(ClanopediaError::InvalidOperation(
+            "Collection is not archived".to_string(),
+        )),
+    }
+}
+
+pub fn execute_archive(collection_id: &str, proposal_id: &str) -> ClanopediaResult<()> {
+    let mut collection = storage::get_collection(&collection_id.to_string())?;
+    validate_archive(&collection)?;
+
+    collection.archived = Some(ArchiveInfo {
+        archived_at: time(),
+        proposal_id: proposal_id.to_string(),
+    });
+    collection.updated_at = time();
+    storage::update_collection(&collection.id, &collection)
+}
+
+pub fn execute_unarchive(collection_id: &str) -> ClanopediaResult<()> {
+    let mut collection = storage::get_collection(&collection_id.to_string())?;
+    validate_unarchive(&collection)?;
+
+    collection.archived = None;
+    collection.updated_at = time();
+    storage::update_collection(&collection.id, &collection)
+}
@@ -0,0 +1,151 @@
+// src/clanopedia_backend/src/attachments.rs
+//
+// Evidence files a proposal's creator attaches while it is open, e.g. a screenshot backing a
+// takedown. Files are split into chunks that fit a query response and are dropped together
+// with the proposal once it is archived.
+
+use candid::Principal;
+use ic_cdk::api::time;
+use sha2::{Digest, Sha256};
+
+use crate::{
+    admin, archive, storage, trash,
+    types::{
+        AttachmentChunk, ClanopediaError, ClanopediaResult, ProposalAttachment, ProposalStatus,
+        StorageMap,
+    },
+};
+
+const MAX_ATTACHMENTS_PER_PROPOSAL: usize = 4;
+const MAX_ATTACHMENT_BYTES: usize = 1024 * 1024;
+const CHUNK_BYTES: usize = 256 * 1024;
+const MAX_NAME_CHARS: usize = 200;
+
+// Content types accepted, with the leading bytes each file must start with
+fn matches_content_type(content_type: &str, data: &[u8]) -> bool {
+    match content_type {
+        "image/png" => data.starts_with(b"\x89PNG\r\n\x1a\n"),
+        "image/jpeg" => data.starts_with(&[0xFF, 0xD8, 0xFF]),
+        "image/gif" => data.starts_with(b"GIF87a") || data.starts_with(b"GIF89a"),
+        "image/webp" => data.len() >= 12 && &data[..4] == b"RIFF" && &data[8..12] == b"WEBP",
+        "application/pdf" => data.starts_with(b"%PDF-"),
+        "text/plain" => std::str::from_utf8(data).is_ok(),
+        _ => false,
+    }
+}
+
+pub fn attach(
+    caller: Principal,
+    collection_id: &str,
+    proposal_id: &str,
+    name: String,
+    content_type: String,
+    data: Vec<u8>,
+) -> ClanopediaResult<ProposalAttachment> {
+    let collection = storage::get_collection(&collection_id.to_string())?;
+    trash::ensure_not_trashed(&collection)?;
+    admin::ensure_not_paused(&collection)?;
+
+    let proposal = storage::get_proposal(collection_id, proposal_id)
+        .ok_or_else(|| ClanopediaError::NotFound(format!("Proposal {} not found", proposal_id)))?;
+    if proposal.creator != caller {
+        return Err(ClanopediaError::NotAuthorized);
+    }
+    archive::ensure_proposal_allowed(&collection, &proposal.proposal_type)?;
+    if proposal.executed
+        || !matches!(
+            proposal.status,
+            ProposalStatus::Active | ProposalStatus::Approved
+        )
+    {
+        return Err(ClanopediaError::InvalidProposalState(
+            "Attachments can only be added to open proposals".to_string(),
+        ));
+    }
+
+    let name = name.trim().to_string();
+    if name.is_empty()
+        || name.chars().count() > MAX_NAME_CHARS
+        || name.contains(['/', '\\'])
+        || name.chars().any(char::is_control)
+    {
+        return Err(ClanopediaError::InvalidInput(format!(
+            "Attachment name must be 1 to {} characters without path separators",
+            MAX_NAME_CHARS
+        )));
+    }
+    if data.is_empty() || data.len() > MAX_ATTACHMENT_BYTES {
+        return Err(ClanopediaError::InvalidInput(format!(
+            "Attachments must be between 1 and {} bytes",
+            MAX_ATTACHMENT_BYTES
+        )));
+    }
+    if !matches_content_type(&content_type, &data) {
+        return Err(ClanopediaError::InvalidInput(format!(
+            "Unsupported attachment, or contents do not match {}; PNG, JPEG, GIF, WebP, PDF and plain text are accepted",
+            content_type
+        )));
+    }
+
+    let existing = storage::list_proposal_attachments(collection_id, proposal_id);
+    if existing.len() >= MAX_ATTACHMENTS_PER_PROPOSAL {
+        return Err(ClanopediaError::InvalidOperation(format!(
+            "A proposal can have at most {} attachments",
+            MAX_ATTACHMENTS_PER_PROPOSAL
+        )));
+    }
+    let chunks: Vec<Vec<u8>> = data.chunks(CHUNK_BYTES).map(<[u8]>::to_vec).collect();
+    storage::ensure_capacity(StorageMap::ProposalAttachments, 1)?;
+    storage::ensure_capacity(StorageMap::AttachmentChunks, chunks.len() as u64)?;
+
+    let attachment = ProposalAttachment {
+        index: existing.last().map_or(0, |a| a.index + 1),
+        name,
+        content_type,
+        size: data.len() as u64,
+        sha256: hex::encode(Sha256::digest(&data)),
+        chunk_count: chunks.len() as u32,
+        uploaded_by: caller,
+        uploaded_at: time(),
+    };
+    storage::put_proposal_attachment(collection_id, proposal_id, &attachment, chunks);
+    Ok(attachment)
+}
+
+pub fn list(collection_id: &str, proposal_id: &str) -> ClanopediaResult<Vec<ProposalAttachment>> {
+    storage::get_proposal(collection_id, proposal_id)
+        .ok_or_else(|| ClanopediaError::NotFound(format!("Proposal {} not found", proposal_id)))?;
+    Ok(storage::list_proposal_attachments(
+        collection_id,
+        proposal_id,
+    ))
+}
+
+/// One chunk of an attachment; read `chunk_index` 0 to `chunk_count - 1` and check the
+/// assembled file against `sha256`
+pub fn get_chunk(
+    collection_id: &str,
+    proposal_id: &str,
+    index: u32,
+    chunk_index: u32,
+) -> ClanopediaResult<AttachmentChunk> {
+    let attachment = storage::get_proposal_attachment(collection_id, proposal_id, index)
+        .ok_or_else(|| {
+            ClanopediaError::NotFound(format!(
+                "Attachment {} of proposal {} not found",
+                index, proposal_id
+            ))
+        })?;
+    let data = storage::get_attachment_chunk(collection_id, proposal_id, index, chunk_index)
+        .ok_or_else(|| {
+            ClanopediaError::NotFound(format!(
+                "Chunk {} not found; the attachment has {} chunks",
+                chunk_index, attachment.chunk_count
+            ))
+        })?;
+    Ok(AttachmentChunk {
+        attachment,
+        chunk_index,
+        data,
+    })
+}
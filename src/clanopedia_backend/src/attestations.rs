@@ -0,0 +1,206 @@
+// src/clanopedia_backend/src/attestations.rs
+//
+// ICRC-7 attestations for documents approved through embed proposals. Token ids are derived
+// from the collection and document ids, so a mint whose result was lost is recognised on the
+// next attempt instead of minting a second token.
+
+use candid::{Nat, Principal};
+use futures_util::future::join_all;
+use ic_cdk::api::time;
+use icrc_ledger_types::{icrc::generic_metadata_value::MetadataValue, icrc1::account::Account};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    auth,
+    external::{
+        blueband,
+        nft::{self, MintArg},
+    },
+    logging, storage,
+    types::{
+        ClanopediaError, ClanopediaResult, Collection, DocumentNft, ExecutionReport,
+        ItemExecutionState, NftMintConfig, NftMintState, Proposal, StorageMap,
+    },
+};
+
+const MAX_MINT_BATCH: usize = 10; // Parallel mints per round
+
+pub fn validate_nft_minting(
+    config: &Option<NftMintConfig>,
+    encrypted: bool,
+) -> ClanopediaResult<()> {
+    let Some(config) = config else {
+        return Ok(());
+    };
+
+    // Token metadata is public and would reveal the collection's sources
+    if encrypted {
+        return Err(ClanopediaError::InvalidOperation(
+            "Encrypted collections cannot mint document NFTs".to_string(),
+        ));
+    }
+    if config.nft_canister == Principal::anonymous()
+        || config.mint_to == Some(Principal::anonymous())
+    {
+        return Err(ClanopediaError::InvalidInput(
+            "NFT canister and recipient must not be the anonymous principal".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+pub fn token_id(collection_id: &str, document_id: &str) -> Nat {
+    let digest = Sha256::digest(format!("{}:{}", collection_id, document_id).as_bytes());
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&digest[..8]);
+    Nat::from(u64::from_be_bytes(bytes))
+}
+
+fn text(key: &str, value: impl Into<String>) -> (String, MetadataValue) {
+    (key.to_string(), MetadataValue::Text(value.into()))
+}
+
+// Mint the token a record describes unless an earlier attempt already did, and store the
+// outcome on the record
+async fn mint_record(collection: &Collection, mut record: DocumentNft) -> DocumentNft {
+    record.state = match try_mint(collection, &mut record).await {
+        Ok(()) => NftMintState::Minted { minted_at: time() },
+        Err(e) => {
+            logging::warn(
+                "attestations",
+                Some(&collection.id),
+                format!("Failed to mint NFT for document {}: {}", record.document_id, e),
+            );
+            NftMintState::Failed(e.to_string())
+        }
+    };
+    storage::put_document_nft(&record);
+    record
+}
+
+async fn try_mint(collection: &Collection, record: &mut DocumentNft) -> ClanopediaResult<()> {
+    let metadata = blueband::get_document_metadata(
+        collection.blueband_collection_id.clone(),
+        record.document_id.clone(),
+    )
+    .await
+    .map_err(|e| ClanopediaError::BluebandError(e.to_string()))?
+    .ok_or_else(|| {
+        ClanopediaError::NotFound(format!("Document {} not found", record.document_id))
+    })?;
+    record.checksum = Some(metadata.checksum.clone());
+    record.source_url = metadata.source_url.clone();
+
+    // Minted by an earlier attempt whose result never got recorded
+    if let Some(account) = nft::token_owner(record.nft_canister, record.token_id.clone()).await? {
+        record.owner = account.owner;
+        return Ok(());
+    }
+
+    let mut token_metadata = vec![
+        text("icrc7:name", metadata.title),
+        text("clanopedia:collection_id", record.collection_id.clone()),
+        text("clanopedia:document_id", record.document_id.clone()),
+        text("clanopedia:proposal_id", record.proposal_id.clone()),
+        text("clanopedia:checksum", metadata.checksum),
+    ];
+    if let Some(url) = metadata.source_url {
+        token_metadata.push(text("clanopedia:source_url", url));
+    }
+    nft::mint_nft(
+        record.nft_canister,
+        MintArg {
+            token_id: record.token_id.clone(),
+            owner: Account {
+                owner: record.owner,
+                subaccount: None,
+            },
+            metadata: token_metadata,
+        },
+    )
+    .await?;
+    Ok(())
+}
+
+async fn mint_records(collection: &Collection, records: Vec<DocumentNft>) -> Vec<DocumentNft> {
+    let mut minted = Vec::with_capacity(records.len());
+    for chunk in records.chunks(MAX_MINT_BATCH) {
+        let mints = chunk
+            .iter()
+            .map(|record| mint_record(collection, record.clone()));
+        minted.extend(join_all(mints).await);
+    }
+    minted
+}
+
+/// Mint a token for every document `report` embedded that has no record yet. Failures are
+/// kept on the record for `mint_document_nfts`; they never undo the embedding.
+pub async fn mint_for_report(collection_id: &str, proposal: &Proposal, report: &ExecutionReport) {
+    let Ok(collection) = storage::get_collection(&collection_id.to_string()) else {
+        return;
+    };
+    let Some(config) = collection.nft_minting.clone() else {
+        return;
+    };
+
+    let records: Vec<DocumentNft> = report
+        .items
+        .iter()
+        .filter(|i| i.state == ItemExecutionState::Succeeded)
+        .filter(|i| storage::get_document_nft(collection_id, &i.document_id).is_none())
+        .map(|i| DocumentNft {
+            collection_id: collection_id.to_string(),
+            document_id: i.document_id.clone(),
+            proposal_id: proposal.id.clone(),
+            nft_canister: config.nft_canister,
+            token_id: token_id(collection_id, &i.document_id),
+            owner: config.mint_to.unwrap_or(proposal.creator),
+            checksum: None,
+            source_url: None,
+            state: NftMintState::Pending,
+        })
+        .collect();
+    if records.is_empty() {
+        return;
+    }
+    if let Err(e) = storage::ensure_capacity(StorageMap::DocumentNfts, records.len() as u64) {
+        logging::warn(
+            "attestations",
+            Some(collection_id),
+            format!("Skipping NFT mints: {}", e),
+        );
+        return;
+    }
+
+    // Recorded before the calls so a trap mid-mint leaves them for retry
+    for record in &records {
+        storage::put_document_nft(record);
+    }
+    mint_records(&collection, records).await;
+}
+
+/// Retry every mint of a collection that has not succeeded yet, on the currently configured
+/// NFT canister; admins only
+pub async fn mint_document_nfts(
+    caller: Principal,
+    collection_id: &str,
+) -> ClanopediaResult<Vec<DocumentNft>> {
+    let collection = storage::get_collection(&collection_id.to_string())?;
+    auth::require_admin(&collection, caller)?;
+    let config = collection.nft_minting.clone().ok_or_else(|| {
+        ClanopediaError::InvalidOperation(format!(
+            "Collection {} does not mint document NFTs",
+            collection_id
+        ))
+    })?;
+
+    let records: Vec<DocumentNft> = storage::list_document_nfts(collection_id)
+        .into_iter()
+        .filter(|r| !matches!(r.state, NftMintState::Minted { .. }))
+        .map(|mut r| {
+            r.nft_canister = config.nft_canister;
+            r
+        })
+        .collect();
+    Ok(mint_records(&collection, records).await)
+}
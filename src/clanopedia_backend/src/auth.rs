@@ -0,0 +1,148 @@
+// src/clanopedia_backend/src/auth.rs
+//
+// Who may do what in a collection. Admins may do everything. So that an ingestion bot never
+// holds an admin's identity, an admin can grant another principal a set of capabilities
+// until an expiry. Operators never govern: proposals, votes, execution, the collection
+// config and grants stay with admins. Endpoints check callers here so grants are enforced
+// the same way everywhere.
+
+use candid::Principal;
+use ic_cdk::api::time;
+
+use crate::{
+    archive, storage, trash,
+    types::{Capability, ClanopediaError, ClanopediaResult, Collection, OperatorGrant},
+};
+
+const MAX_OPERATORS: usize = 20;
+const MAX_GRANT_NANOS: u64 = 90 * 24 * 60 * 60 * 1_000_000_000; // 90 days
+
+pub fn is_admin(collection: &Collection, caller: Principal) -> bool {
+    collection.admins.contains(&caller)
+}
+
+pub fn require_admin(collection: &Collection, caller: Principal) -> ClanopediaResult<()> {
+    if is_admin(collection, caller) {
+        Ok(())
+    } else {
+        Err(ClanopediaError::NotAuthorized)
+    }
+}
+
+/// Whether `caller` is an admin, or an operator whose unexpired grant includes `capability`
+pub fn has_capability(collection: &Collection, caller: Principal, capability: Capability) -> bool {
+    is_admin(collection, caller)
+        || collection.operators.iter().flatten().any(|grant| {
+            grant.principal == caller
+                && grant.expires_at > time()
+                && grant.capabilities.contains(&capability)
+        })
+}
+
+pub fn authorize(
+    collection: &Collection,
+    caller: Principal,
+    capability: Capability,
+) -> ClanopediaResult<()> {
+    if has_capability(collection, caller, capability) {
+        Ok(())
+    } else {
+        Err(ClanopediaError::NotAuthorized)
+    }
+}
+
+/// Grant `operator` the given capabilities until `expires_at`, replacing any grant it has
+pub fn grant_operator(
+    caller: Principal,
+    collection_id: &str,
+    operator: Principal,
+    mut capabilities: Vec<Capability>,
+    expires_at: u64,
+) -> ClanopediaResult<()> {
+    let mut collection = storage::get_collection(&collection_id.to_string())?;
+    require_admin(&collection, caller)?;
+    trash::ensure_not_trashed(&collection)?;
+    archive::ensure_not_archived(&collection)?;
+
+    if operator == Principal::anonymous() || is_admin(&collection, operator) {
+        return Err(ClanopediaError::InvalidInput(
+            "Operators must be neither anonymous nor an admin".to_string(),
+        ));
+    }
+    capabilities.sort_by_key(|c| *c as u8);
+    capabilities.dedup();
+    if capabilities.is_empty() {
+        return Err(ClanopediaError::InvalidInput(
+            "Grant at least one capability".to_string(),
+        ));
+    }
+    let now = time();
+    if expires_at <= now || expires_at - now > MAX_GRANT_NANOS {
+        return Err(ClanopediaError::InvalidInput(
+            "Grants must expire in the future and within 90 days".to_string(),
+        ));
+    }
+
+    let mut operators = active_grants(&collection);
+    operators.retain(|grant| grant.principal != operator);
+    if operators.len() >= MAX_OPERATORS {
+        return Err(ClanopediaError::InvalidOperation(format!(
+            "A collection can have at most {} operators",
+            MAX_OPERATORS
+        )));
+    }
+    operators.push(OperatorGrant {
+        principal: operator,
+        capabilities,
+        granted_by: caller,
+        granted_at: now,
+        expires_at,
+    });
+
+    collection.operators = Some(operators);
+    storage::update_collection(&collection.id, &collection)
+}
+
+/// Revoke an operator's grant; also allowed in archived collections
+pub fn revoke_operator(
+    caller: Principal,
+    collection_id: &str,
+    operator: Principal,
+) -> ClanopediaResult<()> {
+    let mut collection = storage::get_collection(&collection_id.to_string())?;
+    require_admin(&collection, caller)?;
+
+    let mut operators = active_grants(&collection);
+    let before = operators.len();
+    operators.retain(|grant| grant.principal != operator);
+    if operators.len() == before {
+        return Err(ClanopediaError::NotFound(format!(
+            "{} has no operator grant in collection {}",
+            operator, collection_id
+        )));
+    }
+
+    collection.operators = (!operators.is_empty()).then_some(operators);
+    storage::update_collection(&collection.id, &collection)
+}
+
+/// The collection's unexpired operator grants; admins only
+pub fn list_operators(
+    caller: Principal,
+    collection_id: &str,
+) -> ClanopediaResult<Vec<OperatorGrant>> {
+    let collection = storage::get_collection(&collection_id.to_string())?;
+    require_admin(&collection, caller)?;
+    Ok(active_grants(&collection))
+}
+
+fn active_grants(collection: &Collection) -> Vec<OperatorGrant> {
+    let now = time();
+    collection
+        .operators
+        .iter()
+        .flatten()
+        .filter(|grant| grant.expires_at > now)
+        .cloned()
+        .collect()
+}
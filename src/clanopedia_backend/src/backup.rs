@@ -0,0 +1,456 @@
+// src/clanopedia_backend/src/backup.rs
+//
+// Scheduled backups of collections, proposals, and the slugs and keyword index built from
+// them, sent in chunks to a backup canister or an asset canister. A backup holds only the
+// entries that changed since the previous one: the digest of every entry as last sent is
+// kept, and an entry whose digest has no entry left is sent as a deletion. Each timer round
+// scans a slice and sends at most one chunk, so large state is backed up over many rounds.
+// Restoring replays the latest full backup and every backup after it, up to the chosen one.
+
+use std::{cell::Cell, collections::BTreeMap};
+
+use candid::Principal;
+use ic_cdk::api::time;
+use sha2::{Digest, Sha256};
+
+use crate::{
+    config_history,
+    external::backup_target::BackupService,
+    logging,
+    proxy::require_controller,
+    storage,
+    types::{
+        BackupChunk, BackupConfig, BackupRecord, BackupRun, BackupSection, BackupStatus,
+        BackupSummary, BackupTarget, ClanopediaError, ClanopediaResult, ConfigSetting, EventKind,
+    },
+};
+
+const SCAN_BATCH: usize = 500; // Entries examined per round
+const MAX_CHUNK_BYTES: u64 = 1024 * 1024; // Well inside the 2 MB message limit
+const MAX_HISTORY: u64 = 200;
+const MAX_INTERVAL_HOURS: u32 = 30 * 24;
+const HOUR_NANOS: u64 = 60 * 60 * 1_000_000_000;
+const STEP_INTERVAL_NANOS: u64 = 5 * 1_000_000_000;
+
+thread_local! {
+    // Set while a chunk is being sent or a restore is reading chunks
+    static BACKUP_BUSY: Cell<bool> = const { Cell::new(false) };
+}
+
+struct BackupGuard;
+
+impl BackupGuard {
+    fn acquire() -> Option<Self> {
+        (!BACKUP_BUSY.with(|b| b.replace(true))).then_some(BackupGuard)
+    }
+}
+
+impl Drop for BackupGuard {
+    fn drop(&mut self) {
+        BACKUP_BUSY.with(|b| b.set(false));
+    }
+}
+
+fn digest_key(section: BackupSection, key: &str) -> String {
+    format!("{:02}:{}", section.index(), key)
+}
+
+fn parse_digest_key(digest_key: &str) -> Option<(BackupSection, &str)> {
+    let (index, key) = digest_key.split_once(':')?;
+    let section = *BackupSection::ALL.get(index.parse::<usize>().ok()?)?;
+    Some((section, key))
+}
+
+// ============================
+// CONFIGURATION
+// ============================
+
+/// Back up every `interval_hours` to the target, or stop with `None`; controllers only
+pub fn set_config(caller: Principal, config: Option<BackupConfig>) -> ClanopediaResult<()> {
+    require_controller(caller)?;
+    if let Some(config) = &config {
+        if config.interval_hours == 0 || config.interval_hours > MAX_INTERVAL_HOURS {
+            return Err(ClanopediaError::InvalidInput(format!(
+                "The interval must be 1 to {} hours",
+                MAX_INTERVAL_HOURS
+            )));
+        }
+        let (BackupTarget::Canister(target) | BackupTarget::AssetCanister(target)) = config.target;
+        if target == Principal::anonymous() || target == ic_cdk::id() {
+            return Err(ClanopediaError::InvalidInput(format!(
+                "{} cannot hold backups",
+                target
+            )));
+        }
+    }
+    let mut state = storage::get_backup_state();
+    if config.is_none() && state.config.is_some() {
+        return Err(ClanopediaError::InvalidOperation(
+            "Turning backups off needs a second controller; use request_config_change".to_string(),
+        ));
+    }
+    state.config = config.clone();
+    storage::set_backup_state(state);
+    crate::timers::schedule();
+    config_history::record(caller, None, ConfigSetting::BackupConfig(config))?;
+    Ok(())
+}
+
+/// Turn backups off once a second controller confirmed the request
+pub fn disable(requested_by: Principal, confirmed_by: Principal) -> ClanopediaResult<()> {
+    let mut state = storage::get_backup_state();
+    state.config = None;
+    storage::set_backup_state(state);
+    crate::timers::schedule();
+    config_history::record(
+        requested_by,
+        Some(confirmed_by),
+        ConfigSetting::BackupConfig(None),
+    )
+}
+
+pub fn get_history(caller: Principal) -> ClanopediaResult<Vec<BackupSummary>> {
+    require_controller(caller)?;
+    Ok(storage::list_backup_summaries())
+}
+
+// ============================
+// BACKING UP
+// ============================
+
+// Begin a backup for the timer to carry out
+fn start(full: bool) -> ClanopediaResult<u64> {
+    let mut state = storage::get_backup_state();
+    let config = state.config.clone().ok_or_else(|| {
+        ClanopediaError::InvalidOperation("No backup target is configured".to_string())
+    })?;
+    if state.running.is_some() {
+        return Err(ClanopediaError::InvalidOperation(
+            "A backup is already running".to_string(),
+        ));
+    }
+    if full {
+        storage::clear_backup_digests();
+    }
+
+    let id = state.next_id;
+    let now = time();
+    state.next_id += 1;
+    state.last_started_at = now;
+    state.running = Some(BackupRun {
+        id,
+        section: 0,
+        after_key: None,
+        chunk: Vec::new(),
+        chunk_bytes: 0,
+    });
+    storage::set_backup_state(state);
+    storage::put_backup_summary(
+        BackupSummary {
+            id,
+            target: config.target,
+            // With no digests every entry counts as changed
+            full: storage::backup_digest_keys(None, 1).is_empty(),
+            started_at: now,
+            finished_at: None,
+            status: BackupStatus::Running,
+            chunks: 0,
+            records: 0,
+            bytes: 0,
+        },
+        MAX_HISTORY,
+    );
+    Ok(id)
+}
+
+/// Start a backup now rather than at the next interval; `full` sends every entry rather
+/// than the changes. Returns the backup's ID.
+pub fn run_now(caller: Principal, full: bool) -> ClanopediaResult<u64> {
+    require_controller(caller)?;
+    let id = start(full)?;
+    crate::timers::schedule();
+    Ok(id)
+}
+
+fn add_record(run: &mut BackupRun, record: BackupRecord) {
+    run.chunk_bytes += (record.key.len() + record.value.as_ref().map_or(0, Vec::len)) as u64;
+    run.chunk.push(record);
+}
+
+// Fill the run's chunk with changed entries, then deletions, until the chunk is full or a
+// batch has been examined
+fn scan(run: &mut BackupRun) {
+    let mut examined = 0;
+    while examined < SCAN_BATCH && run.chunk_bytes < MAX_CHUNK_BYTES {
+        let remaining = SCAN_BATCH - examined;
+        match BackupSection::ALL.get(run.section as usize).copied() {
+            Some(section) => {
+                let entries = storage::backup_entries(section, run.after_key.as_deref(), remaining);
+                if entries.is_empty() {
+                    run.section += 1;
+                    run.after_key = None;
+                    continue;
+                }
+                for (key, value) in entries {
+                    examined += 1;
+                    run.after_key = Some(key.clone());
+                    let digest = Sha256::digest(&value).to_vec();
+                    if storage::get_backup_digest(&digest_key(section, &key)) != Some(digest) {
+                        add_record(
+                            run,
+                            BackupRecord {
+                                section,
+                                key,
+                                value: Some(value),
+                            },
+                        );
+                        if run.chunk_bytes >= MAX_CHUNK_BYTES {
+                            break;
+                        }
+                    }
+                }
+            }
+            None if run.section as usize == BackupSection::ALL.len() => {
+                let keys = storage::backup_digest_keys(run.after_key.as_deref(), remaining);
+                if keys.is_empty() {
+                    run.section += 1;
+                    run.after_key = None;
+                    continue;
+                }
+                for digest_key in keys {
+                    examined += 1;
+                    if let Some((section, key)) = parse_digest_key(&digest_key) {
+                        if !storage::has_backup_entry(section, key) {
+                            add_record(
+                                run,
+                                BackupRecord {
+                                    section,
+                                    key: key.to_string(),
+                                    value: None,
+                                },
+                            );
+                        }
+                    }
+                    run.after_key = Some(digest_key);
+                }
+            }
+            None => break,
+        }
+    }
+}
+
+fn finish(mut summary: BackupSummary, status: BackupStatus) {
+    match &status {
+        BackupStatus::Failed { error } => logging::error(
+            "backup",
+            None,
+            format!("Backup {} failed: {}", summary.id, error),
+        ),
+        _ => logging::info(
+            "backup",
+            None,
+            format!(
+                "Backup {} sent {} entries in {} chunks",
+                summary.id, summary.records, summary.chunks
+            ),
+        ),
+    }
+    summary.status = status;
+    summary.finished_at = Some(time());
+    storage::put_backup_summary(summary, MAX_HISTORY);
+    let mut state = storage::get_backup_state();
+    state.running = None;
+    storage::set_backup_state(state);
+}
+
+// Carry the running backup forward by one round
+async fn step() {
+    let Some(_guard) = BackupGuard::acquire() else {
+        return;
+    };
+    let mut state = storage::get_backup_state();
+    let Some(mut run) = state.running.take() else {
+        return;
+    };
+    let Some(mut summary) = storage::get_backup_summary(run.id) else {
+        storage::set_backup_state(state);
+        return;
+    };
+
+    scan(&mut run);
+    let done = run.section as usize > BackupSection::ALL.len();
+    if run.chunk_bytes < MAX_CHUNK_BYTES && !done {
+        state.running = Some(run);
+        storage::set_backup_state(state);
+        return;
+    }
+
+    let chunk = BackupChunk {
+        source: ic_cdk::id(),
+        backup_id: run.id,
+        index: summary.chunks,
+        records: std::mem::take(&mut run.chunk),
+        last: done,
+    };
+    let bytes = std::mem::take(&mut run.chunk_bytes);
+    state.running = Some(run.clone());
+    storage::set_backup_state(state);
+
+    let service = BackupService::new(summary.target.clone());
+    if let Err(e) = service.store_chunk(&chunk).await {
+        return finish(summary, BackupStatus::Failed { error: e });
+    }
+
+    // Only entries the target holds count as backed up
+    for record in &chunk.records {
+        storage::set_backup_digest(
+            digest_key(record.section, &record.key),
+            record.value.as_ref().map(|v| Sha256::digest(v).to_vec()),
+        );
+    }
+    summary.chunks += 1;
+    summary.records += chunk.records.len() as u64;
+    summary.bytes += bytes;
+    if done {
+        return finish(summary, BackupStatus::Completed);
+    }
+    storage::put_backup_summary(summary, MAX_HISTORY);
+    // Re-read, since the config may have changed during the call
+    let mut state = storage::get_backup_state();
+    state.running = Some(run);
+    storage::set_backup_state(state);
+}
+
+/// When the timer should back up next, if at all
+pub fn next_backup_at() -> Option<u64> {
+    let state = storage::get_backup_state();
+    if state.running.is_some() {
+        return Some(time() + STEP_INTERVAL_NANOS);
+    }
+    state
+        .config
+        .map(|c| state.last_started_at + c.interval_hours as u64 * HOUR_NANOS)
+}
+
+pub async fn run_due() {
+    if storage::get_backup_state().running.is_none() {
+        if next_backup_at().is_none_or(|at| at > time()) {
+            return;
+        }
+        if let Err(e) = start(false) {
+            return logging::warn("backup", None, format!("Starting a backup failed: {:?}", e));
+        }
+    }
+    step().await;
+}
+
+// ============================
+// RESTORING
+// ============================
+
+/// Restore collections, proposals and indexes as of `backup_id`; controllers only. Every
+/// chunk is read before any entry is written, so a failed read changes nothing. The
+/// backed-up sections end up exactly as the backup had them: entries created since are
+/// removed. Entries outside those sections are left as they are. Returns how many entries
+/// were written or removed.
+pub async fn restore(caller: Principal, backup_id: u64) -> ClanopediaResult<u64> {
+    require_controller(caller)?;
+    let Some(_guard) = BackupGuard::acquire() else {
+        return Err(ClanopediaError::InvalidOperation(
+            "A backup is sending a chunk; try again shortly".to_string(),
+        ));
+    };
+    if storage::get_backup_state().running.is_some() {
+        return Err(ClanopediaError::InvalidOperation(
+            "Wait for the running backup to finish".to_string(),
+        ));
+    }
+    storage::get_backup_summary(backup_id)
+        .ok_or_else(|| ClanopediaError::NotFound(format!("Backup {} not found", backup_id)))?;
+
+    // The latest full backup up to `backup_id`, then each backup after it; failed ones too,
+    // since the chunks they sent are not sent again
+    let mut chain = Vec::new();
+    for summary in storage::list_backup_summaries()
+        .into_iter()
+        .filter(|s| s.id <= backup_id)
+    {
+        let full = summary.full;
+        chain.push(summary);
+        if full {
+            break;
+        }
+    }
+    if !chain.last().is_some_and(|s| s.full) {
+        return Err(ClanopediaError::InvalidOperation(format!(
+            "No full backup up to backup {} is on record; the history keeps the latest {}",
+            backup_id, MAX_HISTORY
+        )));
+    }
+    chain.reverse();
+
+    let mut chunks = Vec::new();
+    for summary in &chain {
+        let service = BackupService::new(summary.target.clone());
+        for index in 0..summary.chunks {
+            let chunk = service
+                .get_chunk(ic_cdk::id(), summary.id, index)
+                .await
+                .map_err(ClanopediaError::ExternalCallError)?
+                .filter(|c| c.backup_id == summary.id && c.index == index)
+                .ok_or_else(|| {
+                    ClanopediaError::NotFound(format!(
+                        "Chunk {} of backup {} is missing from the target",
+                        index, summary.id
+                    ))
+                })?;
+            chunks.push(chunk);
+        }
+    }
+
+    // Each entry as of `backup_id`; a later backup's record of it wins
+    let mut restored = BTreeMap::new();
+    for record in chunks.into_iter().flat_map(|c| c.records) {
+        restored.insert((record.section.index(), record.key), record.value);
+    }
+
+    let mut stale = Vec::new();
+    for section in BackupSection::ALL {
+        let mut after_key: Option<String> = None;
+        loop {
+            let page = storage::backup_entries(section, after_key.as_deref(), SCAN_BATCH);
+            let Some((last, _)) = page.last() else {
+                break;
+            };
+            after_key = Some(last.clone());
+            stale.extend(
+                page.into_iter()
+                    .filter(|(key, _)| {
+                        !matches!(restored.get(&(section.index(), key.clone())), Some(Some(_)))
+                    })
+                    .map(|(key, _)| (section, key)),
+            );
+        }
+    }
+
+    let mut records = 0;
+    for (section, key) in stale {
+        storage::restore_backup_entry(section, key, None);
+        records += 1;
+    }
+    for ((section, key), value) in restored {
+        if value.is_some() {
+            storage::restore_backup_entry(BackupSection::ALL[section], key, value);
+            records += 1;
+        }
+    }
+    // The digests no longer match what is stored, so the next backup is a full one
+    storage::clear_backup_digests();
+    storage::append_event(caller, EventKind::BackupRestored { backup_id, records });
+    logging::warn(
+        "backup",
+        None,
+        format!("Restored {} entries as of backup {}", records, backup_id),
+    );
+    Ok(records)
+}
@@ -0,0 +1,333 @@
+// src/clanopedia_backend/src/bonds.rs
+//
+// Proposal bonds in token-based collections: pulled from the creator when a proposal is
+// made, refunded when it is approved and forfeited to the collection's treasury when it is
+// rejected or expires. A transfer that fails is queued; the timer retries it, and admins can
+// with `settle_bond`, until it goes through. While a bond is queued the proposal keeps it as
+// held, and it is not settled a second way. Every transfer of a bond carries the same memo and
+// creation time, so the ledger deduplicates a retry whose earlier attempt did go through.
+
+use std::{cell::RefCell, collections::HashSet};
+
+use candid::Principal;
+use ic_cdk::api::time;
+use sha2::{Digest, Sha256};
+
+use crate::{
+    auth,
+    external::token,
+    governance, logging, storage,
+    types::{
+        BondStatus, ClanopediaError, ClanopediaResult, Collection, GovernanceModel, Proposal,
+        ProposalBond, ProposalStatus, UnsettledBond,
+    },
+};
+
+const RETRY_INTERVAL_NANOS: u64 = 15 * 60 * 1_000_000_000;
+// Timer retries stop after this many, well inside the ledger's deduplication window
+const MAX_ATTEMPTS: u32 = 48;
+// How long the ledger deduplicates transfers by memo and creation time
+const DEDUP_WINDOW_NANOS: u64 = 24 * 60 * 60 * 1_000_000_000;
+
+thread_local! {
+    // "collection_id:proposal_id" of every bond with a transfer in flight
+    static SETTLING: RefCell<HashSet<String>> = RefCell::new(HashSet::new());
+}
+
+// Held while a bond's transfer is awaited, so it is never sent twice
+struct SettlingGuard {
+    key: String,
+}
+
+impl SettlingGuard {
+    fn acquire(collection_id: &str, proposal_id: &str) -> Option<Self> {
+        let key = format!("{}:{}", collection_id, proposal_id);
+        SETTLING
+            .with(|s| s.borrow_mut().insert(key.clone()))
+            .then_some(Self { key })
+    }
+}
+
+impl Drop for SettlingGuard {
+    fn drop(&mut self) {
+        SETTLING.with(|s| {
+            s.borrow_mut().remove(&self.key);
+        });
+    }
+}
+
+/// Pull the configured bond from the creator via ICRC-2 transfer_from
+pub async fn collect(
+    collection: &Collection,
+    creator: Principal,
+) -> ClanopediaResult<Option<ProposalBond>> {
+    if collection.governance_model != GovernanceModel::TokenBased || collection.is_permissionless {
+        return Ok(None);
+    }
+    let (Some(bond_config), Some(token_canister)) =
+        (&collection.proposal_bond, collection.governance_token)
+    else {
+        return Ok(None);
+    };
+
+    let ledger = token::ledger(token_canister);
+    let subaccount = token::escrow_subaccount(&collection.id);
+    token::pull_approved_tokens(&ledger, creator, bond_config.amount.clone(), subaccount)
+        .await
+        .map_err(|e| {
+            ClanopediaError::InvalidOperation(format!(
+                "Proposal bond of {} tokens could not be collected. Approve this canister as spender first: {}",
+                bond_config.amount, e
+            ))
+        })?;
+
+    Ok(Some(ProposalBond {
+        token: token_canister,
+        amount: bond_config.amount.clone(),
+        status: BondStatus::Held,
+        subaccount: Some(subaccount.to_vec()),
+    }))
+}
+
+/// Return a bond collected for a proposal that was never stored
+pub async fn refund_unused(
+    collection_id: &str,
+    proposal_id: &str,
+    creator: Principal,
+    bond: ProposalBond,
+) {
+    settle_as(
+        collection_id,
+        proposal_id,
+        creator,
+        bond,
+        BondStatus::Refunded,
+    )
+    .await;
+}
+
+/// Refund a held bond on approval, forfeit it on rejection or expiry. `proposal` must already
+/// be stored with its outcome.
+pub async fn settle(proposal: &Proposal) {
+    let Some(bond) = proposal.bond.clone() else {
+        return;
+    };
+    if bond.status != BondStatus::Held {
+        return;
+    }
+    let outcome = match proposal.status {
+        ProposalStatus::Approved => BondStatus::Refunded,
+        ProposalStatus::Rejected | ProposalStatus::Expired => BondStatus::Forfeited,
+        _ => return,
+    };
+    settle_as(
+        &proposal.collection_id,
+        &proposal.id,
+        proposal.creator,
+        bond,
+        outcome,
+    )
+    .await;
+}
+
+async fn settle_as(
+    collection_id: &str,
+    proposal_id: &str,
+    creator: Principal,
+    bond: ProposalBond,
+    outcome: BondStatus,
+) {
+    // A queued bond is settled only by its retries
+    if storage::get_unsettled_bond(collection_id, proposal_id).is_some() {
+        return;
+    }
+    let Some(_guard) = SettlingGuard::acquire(collection_id, proposal_id) else {
+        return;
+    };
+
+    let created_at_time = time();
+    match transfer(collection_id, proposal_id, creator, &bond, &outcome, created_at_time).await {
+        Ok(()) => record_settled(collection_id, proposal_id, outcome),
+        Err(e) => {
+            logging::warn(
+                "bonds",
+                Some(collection_id),
+                format!(
+                    "Settling the bond for proposal {} failed and will be retried: {}",
+                    proposal_id, e
+                ),
+            );
+            storage::put_unsettled_bond(UnsettledBond {
+                collection_id: collection_id.to_string(),
+                proposal_id: proposal_id.to_string(),
+                creator,
+                bond,
+                outcome,
+                attempts: 1,
+                parked: is_permanent(&e),
+                last_error: e.to_string(),
+                retry_at: time() + RETRY_INTERVAL_NANOS,
+                created_at_time,
+            });
+            crate::timers::schedule();
+        }
+    }
+}
+
+async fn transfer(
+    collection_id: &str,
+    proposal_id: &str,
+    creator: Principal,
+    bond: &ProposalBond,
+    outcome: &BondStatus,
+    created_at_time: u64,
+) -> ClanopediaResult<()> {
+    let ledger = token::ledger(bond.token);
+    let from = bond
+        .subaccount
+        .as_ref()
+        .and_then(|s| s.as_slice().try_into().ok());
+    let to = match outcome {
+        BondStatus::Refunded => creator,
+        _ => {
+            let treasury = storage::get_collection(&collection_id.to_string())
+                .ok()
+                .and_then(|c| c.proposal_bond)
+                .and_then(|b| b.treasury);
+            match treasury {
+                Some(treasury) => treasury,
+                None => return Ok(()), // Kept in the escrow
+            }
+        }
+    };
+    let memo = Sha256::digest(format!("clanopedia-bond:{}:{}", collection_id, proposal_id))
+        .to_vec();
+    token::send_tokens_once(&ledger, from, to, bond.amount.clone(), memo, created_at_time)
+        .await
+        .map(|_| ())
+}
+
+// Rejected or short of the fee, a transfer fails the same way however often it is retried
+fn is_permanent(error: &ClanopediaError) -> bool {
+    matches!(error, ClanopediaError::InvalidOperation(_))
+}
+
+// Records the outcome on a proposal still in storage; finalized ones have left it
+fn record_settled(collection_id: &str, proposal_id: &str, outcome: BondStatus) {
+    let stored = governance::modify_proposal(collection_id, proposal_id, |proposal| {
+        let Some(bond) = proposal
+            .bond
+            .as_mut()
+            .filter(|bond| bond.status == BondStatus::Held)
+        else {
+            return Err(ClanopediaError::InvalidProposalState(
+                "Bond is already settled".to_string(),
+            ));
+        };
+        bond.status = outcome;
+        Ok(())
+    });
+    if stored.is_ok() {
+        logging::info(
+            "bonds",
+            Some(collection_id),
+            format!("Settled the bond for proposal {}", proposal_id),
+        );
+    }
+}
+
+// Retry a queued settlement; it leaves the queue once its transfer goes through
+async fn retry(mut pending: UnsettledBond) -> ClanopediaResult<()> {
+    let Some(_guard) = SettlingGuard::acquire(&pending.collection_id, &pending.proposal_id) else {
+        return Err(ClanopediaError::InvalidOperation(format!(
+            "The bond for proposal {} is already being settled",
+            pending.proposal_id
+        )));
+    };
+
+    let result = transfer(
+        &pending.collection_id,
+        &pending.proposal_id,
+        pending.creator,
+        &pending.bond,
+        &pending.outcome,
+        pending.created_at_time,
+    )
+    .await;
+    match result {
+        Ok(()) => {
+            storage::remove_unsettled_bond(&pending.collection_id, &pending.proposal_id);
+            record_settled(
+                &pending.collection_id,
+                &pending.proposal_id,
+                pending.outcome,
+            );
+            Ok(())
+        }
+        Err(e) => {
+            pending.attempts += 1;
+            pending.parked = is_permanent(&e) || pending.attempts >= MAX_ATTEMPTS;
+            pending.last_error = e.to_string();
+            pending.retry_at = time() + RETRY_INTERVAL_NANOS;
+            storage::put_unsettled_bond(pending);
+            Err(e)
+        }
+    }
+}
+
+/// Retry every queued settlement that is due and not parked; run by the timer
+pub async fn retry_due() {
+    let now = time();
+    for pending in storage::list_unsettled_bonds() {
+        if pending.parked || pending.retry_at > now {
+            continue;
+        }
+        let (collection_id, proposal_id) =
+            (pending.collection_id.clone(), pending.proposal_id.clone());
+        if let Err(e) = retry(pending).await {
+            logging::warn(
+                "bonds",
+                Some(&collection_id),
+                format!(
+                    "Retrying the bond for proposal {} failed: {}",
+                    proposal_id, e
+                ),
+            );
+        }
+    }
+}
+
+/// When the next queued settlement is due, for the timer
+pub fn next_retry_at() -> Option<u64> {
+    storage::list_unsettled_bonds()
+        .iter()
+        .filter(|b| !b.parked)
+        .map(|b| b.retry_at)
+        .min()
+}
+
+/// Retry a queued settlement now, parked or not. Once the ledger no longer deduplicates the
+/// first attempt, the transfer is sent as a new one.
+pub async fn settle_bond(
+    collection_id: &str,
+    proposal_id: &str,
+    caller: Principal,
+) -> ClanopediaResult<()> {
+    let collection = storage::get_collection(&collection_id.to_string())?;
+    auth::require_admin(&collection, caller)?;
+    let mut pending = storage::get_unsettled_bond(collection_id, proposal_id).ok_or_else(|| {
+        ClanopediaError::NotFound(format!("No unsettled bond for proposal {}", proposal_id))
+    })?;
+    if time().saturating_sub(pending.created_at_time) >= DEDUP_WINDOW_NANOS {
+        pending.created_at_time = time();
+    }
+    pending.attempts = 0;
+    retry(pending).await
+}
+
+pub fn list_unsettled(collection_id: &str) -> Vec<UnsettledBond> {
+    storage::list_unsettled_bonds()
+        .into_iter()
+        .filter(|b| b.collection_id == collection_id)
+        .collect()
+}
@@ -0,0 +1,184 @@
+// src/clanopedia_backend/src/bookmarks.rs
+//
+// Readers keep bookmarks and named reading lists of documents across collections. Each
+// principal's are one stable record, capped so it stays small. Bookmarks only hold IDs;
+// frontends load titles and content from the collections. Bookmarks of a deleted collection
+// are hidden at once and dropped when orphaned entries are purged.
+
+use std::collections::HashMap;
+
+use candid::Principal;
+use ic_cdk::api::time;
+
+use crate::{
+    storage, trash,
+    types::{
+        Bookmark, ClanopediaError, ClanopediaResult, CollectionId, ReaderBookmarks, ReadingList,
+        StorageMap,
+    },
+};
+
+const MAX_BOOKMARKS: usize = 500; // Across bookmarks and all reading lists
+const MAX_READING_LISTS: usize = 20;
+const MAX_LIST_NAME_CHARS: usize = 100;
+const MAX_ID_CHARS: usize = 256;
+
+fn require_reader(caller: Principal) -> ClanopediaResult<()> {
+    if caller == Principal::anonymous() {
+        return Err(ClanopediaError::NotAuthorized);
+    }
+    Ok(())
+}
+
+fn list_name(name: &str) -> ClanopediaResult<String> {
+    let name = name.trim();
+    if name.is_empty()
+        || name.chars().count() > MAX_LIST_NAME_CHARS
+        || name.chars().any(char::is_control)
+    {
+        return Err(ClanopediaError::InvalidInput(format!(
+            "Reading list names must be 1 to {} characters",
+            MAX_LIST_NAME_CHARS
+        )));
+    }
+    Ok(name.to_string())
+}
+
+fn total(bookmarks: &ReaderBookmarks) -> usize {
+    bookmarks.bookmarks.len()
+        + bookmarks
+            .reading_lists
+            .iter()
+            .map(|l| l.items.len())
+            .sum::<usize>()
+}
+
+fn is_same(bookmark: &Bookmark, collection_id: &str, document_id: &str) -> bool {
+    bookmark.collection_id == collection_id && bookmark.document_id == document_id
+}
+
+// The bookmarks, or the named reading list, a document goes into
+fn items_mut<'a>(
+    bookmarks: &'a mut ReaderBookmarks,
+    list: Option<&str>,
+) -> Option<&'a mut Vec<Bookmark>> {
+    match list {
+        None => Some(&mut bookmarks.bookmarks),
+        Some(name) => bookmarks
+            .reading_lists
+            .iter_mut()
+            .find(|l| l.name == name)
+            .map(|l| &mut l.items),
+    }
+}
+
+/// Bookmark a document, or add it to the reading list `list`, creating the list if needed
+pub fn bookmark_document(
+    caller: Principal,
+    collection_id: &str,
+    document_id: &str,
+    list: Option<String>,
+) -> ClanopediaResult<()> {
+    require_reader(caller)?;
+    let collection = storage::get_collection(&collection_id.to_string())?;
+    trash::ensure_not_trashed(&collection)?;
+    if document_id.is_empty() || document_id.len() > MAX_ID_CHARS {
+        return Err(ClanopediaError::InvalidInput(
+            "Invalid document ID".to_string(),
+        ));
+    }
+    let list = list.as_deref().map(list_name).transpose()?;
+
+    let mut bookmarks = storage::get_bookmarks(&caller);
+    if bookmarks == ReaderBookmarks::default() {
+        storage::ensure_capacity(StorageMap::Bookmarks, 1)?;
+    }
+    if total(&bookmarks) >= MAX_BOOKMARKS {
+        return Err(ClanopediaError::InvalidOperation(format!(
+            "At most {} documents can be bookmarked or listed",
+            MAX_BOOKMARKS
+        )));
+    }
+    if let Some(name) = &list {
+        if !bookmarks.reading_lists.iter().any(|l| &l.name == name) {
+            if bookmarks.reading_lists.len() >= MAX_READING_LISTS {
+                return Err(ClanopediaError::InvalidOperation(format!(
+                    "At most {} reading lists are allowed",
+                    MAX_READING_LISTS
+                )));
+            }
+            bookmarks.reading_lists.push(ReadingList {
+                name: name.clone(),
+                items: Vec::new(),
+                created_at: time(),
+            });
+        }
+    }
+
+    let Some(items) = items_mut(&mut bookmarks, list.as_deref()) else {
+        return Ok(());
+    };
+    if items.iter().any(|b| is_same(b, collection_id, document_id)) {
+        return Ok(());
+    }
+    items.push(Bookmark {
+        collection_id: collection_id.to_string(),
+        document_id: document_id.to_string(),
+        added_at: time(),
+    });
+    storage::set_bookmarks(&caller, bookmarks);
+    Ok(())
+}
+
+/// Remove a document from the bookmarks, or from the reading list `list`
+pub fn remove_bookmark(
+    caller: Principal,
+    collection_id: &str,
+    document_id: &str,
+    list: Option<String>,
+) -> ClanopediaResult<()> {
+    require_reader(caller)?;
+    let list = list.as_deref().map(str::trim);
+    let mut bookmarks = storage::get_bookmarks(&caller);
+    let items = items_mut(&mut bookmarks, list).ok_or_else(|| {
+        ClanopediaError::NotFound(format!(
+            "Reading list {} not found",
+            list.unwrap_or_default()
+        ))
+    })?;
+    items.retain(|b| !is_same(b, collection_id, document_id));
+    storage::set_bookmarks(&caller, bookmarks);
+    Ok(())
+}
+
+pub fn delete_reading_list(caller: Principal, name: &str) -> ClanopediaResult<()> {
+    require_reader(caller)?;
+    let mut bookmarks = storage::get_bookmarks(&caller);
+    let before = bookmarks.reading_lists.len();
+    bookmarks.reading_lists.retain(|l| l.name != name.trim());
+    if bookmarks.reading_lists.len() == before {
+        return Err(ClanopediaError::NotFound(format!(
+            "Reading list {} not found",
+            name
+        )));
+    }
+    storage::set_bookmarks(&caller, bookmarks);
+    Ok(())
+}
+
+/// The caller's bookmarks and reading lists, without documents of deleted or trashed
+/// collections
+pub fn get_my_bookmarks(caller: Principal) -> ReaderBookmarks {
+    let mut bookmarks = storage::get_bookmarks(&caller);
+    let mut visible: HashMap<CollectionId, bool> = HashMap::new();
+    let mut is_visible = |b: &Bookmark| {
+        *visible.entry(b.collection_id.clone()).or_insert_with(|| {
+            storage::get_collection(&b.collection_id).is_ok_and(|c| c.trashed.is_none())
+        })
+    };
+    bookmarks.bookmarks.retain(&mut is_visible);
+    for list in bookmarks.reading_lists.iter_mut() {
+        list.items.retain(&mut is_visible);
+    }
+    bookmarks
+}
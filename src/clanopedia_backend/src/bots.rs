@@ -0,0 +1,330 @@
+// src/clanopedia_backend/src/bots.rs
+//
+// Chat bots, such as an OpenChat community bot, that post digests of a collection's
+// proposals, answer searches, and relay votes. An admin registers a bot and receives a
+// signing key once. A voter delegates to the bot for a limited time; the bot then forwards
+// the voter's vote with an HMAC-SHA256 signature over the payload under that key. The bot
+// cannot vote without the voter's delegation, and a leaked payload cannot be replayed,
+// since each one carries a nonce higher than the last and expires within minutes.
+
+use candid::Principal;
+use ic_cdk::api::time;
+use sha2::{Digest, Sha256};
+
+use crate::{
+    activity, auth, governance, rate_limit, secrets, storage, trash,
+    types::{
+        BotDelegation, BotVotePayload, ChatBot, ClanopediaError, ClanopediaResult, Proposal,
+        ProposalStatus, StorageMap, Vote, VoteReceipt,
+    },
+    vault,
+};
+
+/// Prefix of the secrets holding bot signing keys, followed by the bot's principal
+pub const KEY_SECRET_PREFIX: &str = "chat_bot.";
+
+const MAX_BOTS_PER_COLLECTION: usize = 10;
+const MAX_NAME_CHARS: usize = 64;
+const HOUR_NANOS: u64 = 60 * 60 * 1_000_000_000;
+const MAX_DELEGATION_HOURS: u32 = 90 * 24;
+const MAX_PAYLOAD_LIFETIME_NANOS: u64 = 10 * 60 * 1_000_000_000;
+const BOT_VOTES_PER_WINDOW: usize = 60;
+const BOT_VOTE_WINDOW_NANOS: u64 = 60 * 1_000_000_000;
+const HMAC_BLOCK_LEN: usize = 64;
+const MAX_DIGEST_LINES: usize = 10;
+const MAX_DIGEST_DESCRIPTION_CHARS: usize = 80;
+
+fn key_secret(bot: &Principal) -> String {
+    format!("{}{}", KEY_SECRET_PREFIX, bot)
+}
+
+// ============================
+// REGISTRATION
+// ============================
+
+/// Connect a bot to the collection; admins only. Returns the bot's signing key, hex encoded,
+/// which is not shown again. Registering a bot again replaces its key.
+pub async fn register_bot(
+    caller: Principal,
+    collection_id: &str,
+    bot: Principal,
+    name: String,
+) -> ClanopediaResult<String> {
+    let collection = storage::get_collection(&collection_id.to_string())?;
+    auth::require_admin(&collection, caller)?;
+    trash::ensure_not_trashed(&collection)?;
+    if bot == Principal::anonymous() || bot == Principal::management_canister() {
+        return Err(ClanopediaError::InvalidInput(format!(
+            "{} cannot be a bot",
+            bot
+        )));
+    }
+    let name = name.trim().to_string();
+    if name.is_empty() || name.chars().count() > MAX_NAME_CHARS {
+        return Err(ClanopediaError::InvalidInput(format!(
+            "Bot names must be 1 to {} characters",
+            MAX_NAME_CHARS
+        )));
+    }
+    let registered = storage::get_chat_bot(collection_id, &bot).is_some();
+    if !registered {
+        if storage::list_chat_bots(collection_id).len() >= MAX_BOTS_PER_COLLECTION {
+            return Err(ClanopediaError::InvalidOperation(format!(
+                "A collection allows at most {} bots",
+                MAX_BOTS_PER_COLLECTION
+            )));
+        }
+        storage::ensure_capacity(StorageMap::ChatBots, 1)?;
+        storage::ensure_capacity(StorageMap::CollectionSecrets, 1)?;
+    }
+
+    let key = hex::encode(vault::random_bytes().await?);
+    secrets::store_secret(collection_id, &key_secret(&bot), &key, caller).await?;
+    storage::put_chat_bot(
+        collection_id,
+        &ChatBot {
+            bot,
+            name,
+            registered_by: caller,
+            registered_at: time(),
+        },
+    );
+    Ok(key)
+}
+
+/// Disconnect a bot, dropping its key and every delegation to it; admins only
+pub fn unregister_bot(
+    caller: Principal,
+    collection_id: &str,
+    bot: Principal,
+) -> ClanopediaResult<()> {
+    let collection = storage::get_collection(&collection_id.to_string())?;
+    auth::require_admin(&collection, caller)?;
+    if !storage::remove_chat_bot(collection_id, &bot) {
+        return Err(ClanopediaError::NotFound(format!(
+            "{} is not a registered bot",
+            bot
+        )));
+    }
+    storage::remove_secret(collection_id, &key_secret(&bot));
+    Ok(())
+}
+
+pub fn list_bots(collection_id: &str) -> ClanopediaResult<Vec<ChatBot>> {
+    storage::get_collection(&collection_id.to_string())?;
+    Ok(storage::list_chat_bots(collection_id))
+}
+
+// ============================
+// DELEGATIONS
+// ============================
+
+/// Let `bot` vote for the caller in the collection for `hours`, replacing any earlier
+/// delegation to it
+pub fn delegate_to_bot(
+    caller: Principal,
+    collection_id: &str,
+    bot: Principal,
+    hours: u32,
+) -> ClanopediaResult<BotDelegation> {
+    if caller == Principal::anonymous() {
+        return Err(ClanopediaError::NotAuthorized);
+    }
+    storage::get_chat_bot(collection_id, &bot)
+        .ok_or_else(|| ClanopediaError::NotFound(format!("{} is not a registered bot", bot)))?;
+    if hours == 0 || hours > MAX_DELEGATION_HOURS {
+        return Err(ClanopediaError::InvalidInput(format!(
+            "Delegations last 1 to {} hours",
+            MAX_DELEGATION_HOURS
+        )));
+    }
+    let previous = storage::get_bot_delegation(collection_id, &bot, &caller);
+    if previous.is_none() {
+        storage::ensure_capacity(StorageMap::BotDelegations, 1)?;
+    }
+
+    let now = time();
+    let delegation = BotDelegation {
+        voter: caller,
+        bot,
+        granted_at: now,
+        expires_at: now + hours as u64 * HOUR_NANOS,
+        // Keep the nonce, so payloads signed under an earlier delegation stay spent
+        last_nonce: previous.map_or(0, |d| d.last_nonce),
+    };
+    storage::put_bot_delegation(collection_id, &delegation);
+    Ok(delegation)
+}
+
+pub fn revoke_bot_delegation(
+    caller: Principal,
+    collection_id: &str,
+    bot: Principal,
+) -> ClanopediaResult<()> {
+    if !storage::remove_bot_delegation(collection_id, &bot, &caller) {
+        return Err(ClanopediaError::NotFound(format!(
+            "You have not delegated to {}",
+            bot
+        )));
+    }
+    Ok(())
+}
+
+/// The caller's delegations in the collection, expired ones included
+pub fn list_my_delegations(caller: Principal, collection_id: &str) -> Vec<BotDelegation> {
+    storage::list_chat_bots(collection_id)
+        .into_iter()
+        .filter_map(|b| storage::get_bot_delegation(collection_id, &b.bot, &caller))
+        .collect()
+}
+
+// ============================
+// VOTING
+// ============================
+
+fn hmac_sha256(key: &[u8], message: &[u8]) -> Vec<u8> {
+    let mut block = [0u8; HMAC_BLOCK_LEN];
+    if key.len() > HMAC_BLOCK_LEN {
+        block[..32].copy_from_slice(&Sha256::digest(key));
+    } else {
+        block[..key.len()].copy_from_slice(key);
+    }
+    let pad = |byte: u8| block.iter().map(|b| b ^ byte).collect::<Vec<u8>>();
+
+    let mut inner = Sha256::new();
+    inner.update(pad(0x36));
+    inner.update(message);
+    let mut outer = Sha256::new();
+    outer.update(pad(0x5c));
+    outer.update(inner.finalize());
+    outer.finalize().to_vec()
+}
+
+// Compare without stopping at the first differing byte
+fn same_signature(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+/// Cast a vote a bot relays for a voter who delegated to it. The caller must be the bot.
+pub async fn vote_via_bot(
+    caller: Principal,
+    payload: BotVotePayload,
+    signature: Vec<u8>,
+) -> ClanopediaResult<VoteReceipt> {
+    let collection_id = payload.collection_id.clone();
+    if caller != payload.bot || storage::get_chat_bot(&collection_id, &caller).is_none() {
+        return Err(ClanopediaError::NotAuthorized);
+    }
+    let now = time();
+    if payload.expires_at < now || payload.expires_at > now + MAX_PAYLOAD_LIFETIME_NANOS {
+        return Err(ClanopediaError::InvalidInput(
+            "The vote payload has expired or expires too far ahead".to_string(),
+        ));
+    }
+
+    let key = secrets::get_secret(&collection_id, &key_secret(&caller))?
+        .ok_or(ClanopediaError::NotAuthorized)?;
+    let key = hex::decode(key).map_err(|_| ClanopediaError::NotAuthorized)?;
+    let expected = hmac_sha256(&key, payload.signing_text().as_bytes());
+    if !same_signature(&expected, &signature) {
+        return Err(ClanopediaError::NotAuthorized);
+    }
+
+    let mut delegation = storage::get_bot_delegation(&collection_id, &caller, &payload.voter)
+        .filter(|d| d.expires_at >= now)
+        .ok_or(ClanopediaError::NotAuthorized)?;
+    if payload.nonce <= delegation.last_nonce {
+        return Err(ClanopediaError::InvalidInput(format!(
+            "Nonces must be higher than {}",
+            delegation.last_nonce
+        )));
+    }
+    rate_limit::check_rate_limit(
+        "bot_vote",
+        caller,
+        BOT_VOTES_PER_WINDOW,
+        BOT_VOTE_WINDOW_NANOS,
+    )?;
+
+    // Spend the nonce before voting, so a retry during the call cannot vote again
+    delegation.last_nonce = payload.nonce;
+    storage::put_bot_delegation(&collection_id, &delegation);
+    // Log the vote as the voter's too, since it was cast with their delegation
+    activity::record(payload.voter, "vote_via_bot", Some(&collection_id));
+    governance::vote_as(
+        &collection_id,
+        &payload.proposal_id,
+        payload.voter,
+        payload.vote,
+    )
+    .await
+}
+
+// ============================
+// DIGESTS
+// ============================
+
+fn shorten(text: &str, max_chars: usize) -> String {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if text.chars().count() <= max_chars {
+        return text;
+    }
+    let mut short: String = text.chars().take(max_chars - 1).collect();
+    short.push('…');
+    short
+}
+
+fn time_left(expires_at: u64, now: u64) -> String {
+    let hours = expires_at.saturating_sub(now) / HOUR_NANOS;
+    match hours {
+        0 => "closes within the hour".to_string(),
+        1..=47 => format!("closes in {}h", hours),
+        _ => format!("closes in {}d", hours / 24),
+    }
+}
+
+fn digest_line(proposal: &Proposal, now: u64) -> String {
+    let count = |vote: Vote| proposal.votes.values().filter(|v| **v == vote).count();
+    // The variant name, e.g. `AddAdmin`, without its fields
+    let kind = format!("{:?}", proposal.proposal_type);
+    let kind = kind.split([' ', '(', '{']).next().unwrap_or_default();
+    format!(
+        "#{} {}: {} ({} yes, {} no, {} needed; {})",
+        proposal.id,
+        kind,
+        shorten(&proposal.description, MAX_DIGEST_DESCRIPTION_CHARS),
+        count(Vote::Yes),
+        count(Vote::No),
+        proposal.threshold,
+        time_left(proposal.expires_at, now)
+    )
+}
+
+/// Active proposals as plain text, one line each, closing soonest first
+pub fn summarize_active_proposals(collection_id: &str) -> ClanopediaResult<String> {
+    let collection = storage::get_collection(&collection_id.to_string())?;
+    trash::ensure_not_trashed(&collection)?;
+
+    let now = time();
+    let mut active: Vec<Proposal> = storage::list_proposals(collection_id)
+        .into_values()
+        .filter(|p| p.status == ProposalStatus::Active && p.expires_at >= now)
+        .collect();
+    if active.is_empty() {
+        return Ok(format!("{} has no open proposals.", collection.name));
+    }
+    active.sort_by_key(|p| p.expires_at);
+
+    let mut digest = match active.len() {
+        1 => format!("{} has 1 open proposal:", collection.name),
+        n => format!("{} has {} open proposals:", collection.name, n),
+    };
+    for proposal in active.iter().take(MAX_DIGEST_LINES) {
+        digest.push('\n');
+        digest.push_str(&digest_line(proposal, now));
+    }
+    if active.len() > MAX_DIGEST_LINES {
+        digest.push_str(&format!("\n…and {} more", active.len() - MAX_DIGEST_LINES));
+    }
+    Ok(digest)
+}
@@ -0,0 +1,32 @@
+// src/clanopedia_backend/src/changes.rs
+//
+// Cursor-based feed of everything an off-chain mirror needs to refetch: collections,
+// documents and proposal lifecycle. Records carry ids only; indexers read the current state
+// through the regular queries.
+
+use crate::{
+    storage,
+    types::{ChangePage, ClanopediaError, ClanopediaResult},
+};
+
+const MAX_CHANGE_PAGE: usize = 500;
+
+/// Changes after `since_sequence`, or from the oldest one kept when unset. Fails once the
+/// cursor has fallen behind the retained window, since the gap cannot be replayed.
+pub fn get_changes(since_sequence: Option<u64>, limit: u32) -> ClanopediaResult<ChangePage> {
+    let start = since_sequence.map_or(0, |sequence| sequence + 1);
+    if let (Some(_), Some(oldest)) = (since_sequence, storage::first_change_sequence()) {
+        if start < oldest {
+            return Err(ClanopediaError::InvalidOperation(format!(
+                "Changes before {} have been pruned; resync and continue from there",
+                oldest
+            )));
+        }
+    }
+
+    let changes = storage::list_changes(start, (limit as usize).min(MAX_CHANGE_PAGE));
+    Ok(ChangePage {
+        next_sequence: changes.last().map(|c| c.sequence),
+        changes,
+    })
+}
@@ -0,0 +1,64 @@
+// src/clanopedia_backend/src/chunks.rs
+//
+// A document's passages as Blueband chunked and embedded them, so frontends can link a
+// search hit to the exact passage it matched: hits carry the chunk ID, and each chunk has
+// its position and character range in the document. Linked documents are read from the
+// collection that holds their content.
+
+use crate::{
+    crosspost,
+    external::blueband,
+    storage, trash,
+    types::{ClanopediaError, ClanopediaResult, DocumentChunk, DocumentChunkPage},
+};
+
+const DEFAULT_LIMIT: u32 = 50;
+const MAX_LIMIT: u32 = 200;
+
+pub async fn document_chunks(
+    collection_id: &str,
+    document_id: &str,
+    offset: Option<u32>,
+    limit: Option<u32>,
+) -> ClanopediaResult<DocumentChunkPage> {
+    let collection = storage::get_collection(&collection_id.to_string())?;
+    let not_found = || ClanopediaError::NotFound(format!("Document {} not found", document_id));
+    if trash::is_document_trashed(collection_id, document_id)
+        || storage::get_archived_document(collection_id, document_id).is_some()
+    {
+        return Err(not_found());
+    }
+
+    let owner = crosspost::content_collection(&collection, document_id)?;
+    let bb_collection = &owner.blueband_collection_id;
+    let metadata = blueband::get_document_metadata(bb_collection.clone(), document_id.to_string())
+        .await
+        .map_err(ClanopediaError::BluebandError)?
+        .ok_or_else(not_found)?;
+    let mut chunks = blueband::get_document_chunks(bb_collection, document_id)
+        .await
+        .map_err(ClanopediaError::BluebandError)?;
+    chunks.sort_by_key(|chunk| chunk.position);
+
+    let total = chunks.len() as u64;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT) as usize;
+    let chunks = chunks
+        .into_iter()
+        .skip(offset.unwrap_or(0) as usize)
+        .take(limit)
+        .map(|chunk| DocumentChunk {
+            chunk_id: chunk.id,
+            position: chunk.position,
+            text: chunk.text,
+            char_start: chunk.char_start,
+            char_end: chunk.char_end,
+            token_count: chunk.token_count,
+            embedded: metadata.is_embedded,
+        })
+        .collect();
+    Ok(DocumentChunkPage {
+        document_id: document_id.to_string(),
+        chunks,
+        total,
+    })
+}
@@ -0,0 +1,269 @@
+// src/clanopedia_backend/src/cold_storage.rs
+//
+// Archiving rarely read documents out of Blueband to cut its storage and embedding costs.
+// Admins archive a document: its content is stored on the configured backup target, the
+// document is deleted from Blueband and the keyword index, and its overlays, outline and
+// provenance stay in Clanopedia with a pointer to the archived content. Bringing it back
+// goes through governance: an approved `RestoreArchivedDocument` proposal reads the content,
+// checks its hash, adds and embeds it again under a new ID, and moves the records over.
+
+use candid::Principal;
+use ic_cdk::api::time;
+
+use crate::{
+    admin, archive, auth,
+    external::{backup_target::BackupService, blueband},
+    integrity, keyword_index, logging, storage, trash,
+    types::{
+        ArchivedDocument, ChangeKind, ClanopediaError, ClanopediaResult, Collection,
+        DocumentRequest, StorageMap,
+    },
+};
+
+fn archive_key(collection_id: &str, document_id: &str) -> String {
+    format!(
+        "/clanopedia-archive/{}/{}/{}",
+        ic_cdk::id(),
+        collection_id,
+        document_id
+    )
+}
+
+fn ensure_writable(collection: &Collection) -> ClanopediaResult<()> {
+    admin::ensure_not_paused(collection)?;
+    trash::ensure_not_trashed(collection)?;
+    archive::ensure_not_archived(collection)
+}
+
+/// Move a document's content to the backup target and delete it from Blueband; admins only
+pub async fn archive_document(
+    collection_id: &str,
+    document_id: &str,
+    caller: Principal,
+) -> ClanopediaResult<ArchivedDocument> {
+    let collection = storage::get_collection(&collection_id.to_string())?;
+    auth::require_admin(&collection, caller)?;
+    ensure_writable(&collection)?;
+
+    if storage::get_archived_document(collection_id, document_id).is_some() {
+        return Err(ClanopediaError::InvalidOperation(format!(
+            "Document {} is already archived",
+            document_id
+        )));
+    }
+    if storage::get_cross_post(collection_id, document_id).is_some() {
+        return Err(ClanopediaError::InvalidOperation(
+            "Linked documents have no content here to archive".to_string(),
+        ));
+    }
+    if trash::is_document_trashed(collection_id, document_id) {
+        return Err(ClanopediaError::InvalidOperation(format!(
+            "Document {} is in the trash",
+            document_id
+        )));
+    }
+    if storage::list_cross_posts_from(collection_id)
+        .iter()
+        .any(|(_, linked_id)| linked_id == document_id)
+    {
+        return Err(ClanopediaError::InvalidOperation(format!(
+            "Document {} is linked into other collections",
+            document_id
+        )));
+    }
+    let target = storage::get_backup_state()
+        .config
+        .map(|config| config.target)
+        .ok_or_else(|| {
+            ClanopediaError::InvalidOperation(
+                "Configure a backup target before archiving documents".to_string(),
+            )
+        })?;
+    storage::ensure_capacity(StorageMap::ArchivedDocuments, 1)?;
+
+    let bb_collection = &collection.blueband_collection_id;
+    let metadata = blueband::get_document_metadata(bb_collection.clone(), document_id.to_string())
+        .await
+        .map_err(ClanopediaError::BluebandError)?
+        .ok_or_else(|| ClanopediaError::NotFound(format!("Document {} not found", document_id)))?;
+    let content = blueband::get_document_content_from_blueband(bb_collection, document_id)
+        .await
+        .map_err(ClanopediaError::BluebandError)?
+        .ok_or_else(|| {
+            ClanopediaError::NotFound(format!("Document {} has no content", document_id))
+        })?;
+
+    let key = archive_key(collection_id, document_id);
+    let content_sha256 = integrity::content_checksum(&content);
+    let size = content.len() as u64;
+    BackupService::new(target.clone())
+        .store_document(&key, content.into_bytes())
+        .await
+        .map_err(|e| {
+            ClanopediaError::StorageError(format!("Storing the archived content failed: {}", e))
+        })?;
+
+    // The record goes in first so the document is never without its content's pointer
+    let tags = storage::get_indexed_document(collection_id, document_id)
+        .map(|indexed| indexed.tags)
+        .unwrap_or_default();
+    let archived = ArchivedDocument {
+        document_id: document_id.to_string(),
+        title: metadata.title,
+        tags,
+        content_type: metadata.content_type,
+        source_url: metadata.source_url,
+        size,
+        content_sha256,
+        target,
+        key,
+        archived_by: caller,
+        archived_at: time(),
+    };
+    storage::set_archived_document(collection_id, archived.clone());
+
+    if let Err(e) = blueband::delete_document(bb_collection, document_id).await {
+        storage::remove_archived_document(collection_id, document_id);
+        return Err(ClanopediaError::BluebandError(format!(
+            "Deleting document {} from Blueband failed: {}",
+            document_id, e
+        )));
+    }
+    keyword_index::remove_document(collection_id, document_id);
+    storage::remove_document_checksum(collection_id, document_id);
+    storage::remove_document_size(collection_id, document_id);
+    storage::append_change(
+        collection_id,
+        ChangeKind::DocumentRemoved {
+            document_id: document_id.to_string(),
+        },
+    );
+    logging::info(
+        "cold_storage",
+        Some(collection_id),
+        format!("Archived document {} to {}", document_id, archived.key),
+    );
+    Ok(archived)
+}
+
+pub fn list_archived_documents(collection_id: &str) -> ClanopediaResult<Vec<ArchivedDocument>> {
+    storage::get_collection(&collection_id.to_string())?;
+    Ok(storage::list_archived_documents(collection_id))
+}
+
+/// Check a `RestoreArchivedDocument` proposal
+pub fn validate_restore(collection: &Collection, document_id: &str) -> ClanopediaResult<()> {
+    ensure_writable(collection)?;
+    storage::get_archived_document(&collection.id, document_id)
+        .map(|_| ())
+        .ok_or_else(|| {
+            ClanopediaError::NotFound(format!("Document {} is not archived", document_id))
+        })
+}
+
+/// Apply an approved `RestoreArchivedDocument` proposal, returning the document's new ID
+pub async fn execute_restore(collection_id: &str, document_id: &str) -> ClanopediaResult<String> {
+    let collection = storage::get_collection(&collection_id.to_string())?;
+    ensure_writable(&collection)?;
+    let Some(archived) = storage::get_archived_document(collection_id, document_id) else {
+        return Err(ClanopediaError::NotFound(format!(
+            "Document {} is not archived",
+            document_id
+        )));
+    };
+
+    let bytes = BackupService::new(archived.target.clone())
+        .get_document(&archived.key)
+        .await
+        .map_err(|e| {
+            ClanopediaError::StorageError(format!("Reading the archived content failed: {}", e))
+        })?
+        .ok_or_else(|| {
+            ClanopediaError::NotFound(format!("The backup target no longer has {}", archived.key))
+        })?;
+    let content = String::from_utf8(bytes).map_err(|_| {
+        ClanopediaError::StorageError("The archived content is not valid UTF-8".to_string())
+    })?;
+    if integrity::content_checksum(&content) != archived.content_sha256 {
+        return Err(ClanopediaError::StorageError(format!(
+            "The archived content of document {} does not match its hash",
+            document_id
+        )));
+    }
+
+    let overlay = storage::get_document_overlay(collection_id, document_id);
+    let title = overlay
+        .as_ref()
+        .and_then(|o| o.title.clone())
+        .unwrap_or(archived.title.clone());
+    let tags = overlay
+        .as_ref()
+        .and_then(|o| o.tags.clone())
+        .unwrap_or(archived.tags.clone());
+    let bb_collection = &collection.blueband_collection_id;
+    let added = blueband::add_document_to_blueband(
+        bb_collection,
+        DocumentRequest {
+            title: title.clone(),
+            content: content.clone(),
+            content_type: Some(archived.content_type.clone()),
+            source_url: archived.source_url.clone(),
+            author: overlay.as_ref().and_then(|o| o.author.clone()),
+            tags: Some(tags.clone()),
+        },
+    )
+    .await
+    .map_err(ClanopediaError::BluebandError)?;
+    integrity::check_added_document(&collection, &content, &added).await?;
+    // An embedding failure leaves the document added; it can be embedded again later
+    if let Err(e) = blueband::embed_existing_document(bb_collection, &added.id).await {
+        logging::warn(
+            "cold_storage",
+            Some(collection_id),
+            format!("Embedding restored document {} failed: {}", added.id, e),
+        );
+    }
+
+    move_records(&collection, document_id, &added.id, &title, &content, &tags);
+    storage::remove_archived_document(collection_id, document_id);
+    logging::info(
+        "cold_storage",
+        Some(collection_id),
+        format!("Restored archived document {} as {}", document_id, added.id),
+    );
+    Ok(added.id)
+}
+
+// Carry the archived document's Clanopedia records over to its new ID
+fn move_records(
+    collection: &Collection,
+    from: &str,
+    to: &str,
+    title: &str,
+    content: &str,
+    tags: &[String],
+) {
+    let collection_id = &collection.id;
+    let overlay = storage::get_document_overlay(collection_id, from);
+    let outline = storage::get_document_outline(collection_id, from).map(|o| o.entries);
+    let provenance = storage::get_document_provenance(collection_id, from);
+    let mut pinned = storage::list_pinned_documents(collection_id);
+    let was_pinned = pinned.iter().any(|p| p.document_id == from);
+
+    storage::remove_document_records(collection_id, from);
+    crate::record_document_structure(
+        collection, to, title, content, tags, outline, None, provenance,
+    );
+    if let Some(overlay) = overlay {
+        if let Some(slug) = &overlay.slug {
+            storage::set_document_slug(collection_id, slug, to);
+        }
+        storage::set_document_overlay(collection_id, to, overlay);
+    }
+    if was_pinned {
+        for pin in pinned.iter_mut().filter(|p| p.document_id == from) {
+            pin.document_id = to.to_string();
+        }
+        storage::set_pinned_documents(collection_id, &pinned);
+    }
+}
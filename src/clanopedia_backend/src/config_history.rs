@@ -0,0 +1,197 @@
+// src/clanopedia_backend/src/config_history.rs
+//
+// Every change to canister-level configuration, versioned from 1 and chained so no entry can
+// be altered or dropped unnoticed. Hashes are hex SHA-256:
+//
+//   value_hash  = H(candid encoding of the ConfigSetting)
+//   change_hash = H("version|changed_by|confirmed_by|changed_at|value_hash")
+//   chain_hash  = H(previous_hash || change_hash), over the raw 32-byte digests
+//
+// with confirmed_by empty when unset and the first previous_hash all zeroes.
+//
+// Destructive changes, swapping the Blueband canister and turning backups off, take two
+// controllers: one requests the change and a different one confirms it before it expires.
+// A canister with a single controller has to add a second before making them.
+
+use candid::Principal;
+use ic_cdk::api::time;
+use sha2::{Digest, Sha256};
+
+use crate::{
+    admin, backup,
+    proxy::require_controller,
+    storage,
+    types::{ClanopediaError, ClanopediaResult, ConfigChange, ConfigSetting, PendingConfigChange},
+};
+
+const GENESIS_HASH: [u8; 32] = [0; 32];
+const MAX_HISTORY_PAGE: usize = 100;
+const MAX_PENDING: usize = 10;
+const PENDING_TTL_NS: u64 = 24 * 60 * 60 * 1_000_000_000;
+
+fn sha256(data: &[u8]) -> [u8; 32] {
+    Sha256::digest(data).into()
+}
+
+fn head() -> [u8; 32] {
+    storage::last_config_change()
+        .and_then(|change| hex::decode(change.chain_hash).ok())
+        .and_then(|bytes| bytes.try_into().ok())
+        .unwrap_or(GENESIS_HASH)
+}
+
+/// Append a change that has been applied to the history
+pub fn record(
+    changed_by: Principal,
+    confirmed_by: Option<Principal>,
+    setting: ConfigSetting,
+) -> ClanopediaResult<()> {
+    let encoded = candid::encode_one(&setting).map_err(|e| {
+        ClanopediaError::StorageError(format!("Configuration change could not be encoded: {}", e))
+    })?;
+    let version = storage::last_config_change().map_or(1, |last| last.version + 1);
+    let changed_at = time();
+    let value_hash = hex::encode(sha256(&encoded));
+    let change_hash = sha256(
+        format!(
+            "{}|{}|{}|{}|{}",
+            version,
+            changed_by,
+            confirmed_by.map(|p| p.to_text()).unwrap_or_default(),
+            changed_at,
+            value_hash
+        )
+        .as_bytes(),
+    );
+    let previous = head();
+    let mut data = previous.to_vec();
+    data.extend_from_slice(&change_hash);
+
+    storage::append_config_change(&ConfigChange {
+        version,
+        setting,
+        changed_by,
+        confirmed_by,
+        changed_at,
+        value_hash,
+        change_hash: hex::encode(change_hash),
+        previous_hash: hex::encode(previous),
+        chain_hash: hex::encode(sha256(&data)),
+    });
+    Ok(())
+}
+
+pub fn get_history(
+    caller: Principal,
+    after_version: Option<u64>,
+    limit: u32,
+) -> ClanopediaResult<Vec<ConfigChange>> {
+    require_controller(caller)?;
+    Ok(storage::list_config_changes(
+        after_version,
+        (limit as usize).min(MAX_HISTORY_PAGE),
+    ))
+}
+
+fn is_live(pending: &PendingConfigChange) -> bool {
+    pending.expires_at > time()
+}
+
+/// Request a destructive change for another controller to confirm, returning its ID
+pub async fn request_change(caller: Principal, setting: ConfigSetting) -> ClanopediaResult<u64> {
+    require_controller(caller)?;
+    match &setting {
+        ConfigSetting::BluebandCanister(canister_id) => {
+            admin::check_blueband_canister(*canister_id).await?;
+        }
+        ConfigSetting::BackupConfig(None) => {
+            if storage::get_backup_state().config.is_none() {
+                return Err(ClanopediaError::InvalidOperation(
+                    "Backups are not configured".to_string(),
+                ));
+            }
+        }
+        _ => {
+            return Err(ClanopediaError::InvalidInput(
+                "Only destructive changes need confirming; set this one directly".to_string(),
+            ))
+        }
+    }
+
+    // Expired requests are dropped here rather than by a timer
+    let mut live = 0;
+    for pending in storage::list_pending_config_changes() {
+        if is_live(&pending) {
+            live += 1;
+        } else {
+            storage::take_pending_config_change(pending.id);
+        }
+    }
+    if live >= MAX_PENDING {
+        return Err(ClanopediaError::InvalidOperation(format!(
+            "At most {} configuration changes can wait for confirmation",
+            MAX_PENDING
+        )));
+    }
+
+    let now = time();
+    let pending = PendingConfigChange {
+        id: storage::next_pending_config_change_id(),
+        setting,
+        requested_by: caller,
+        requested_at: now,
+        expires_at: now + PENDING_TTL_NS,
+    };
+    storage::put_pending_config_change(&pending);
+    Ok(pending.id)
+}
+
+/// Apply a change another controller requested. The request is used up even if applying
+/// it fails, e.g. on a failed health check.
+pub async fn confirm_change(caller: Principal, id: u64) -> ClanopediaResult<()> {
+    require_controller(caller)?;
+    let not_found = || ClanopediaError::NotFound(format!("Configuration change {} not found", id));
+    let pending = storage::list_pending_config_changes()
+        .into_iter()
+        .find(|pending| pending.id == id)
+        .ok_or_else(not_found)?;
+    if pending.requested_by == caller {
+        return Err(ClanopediaError::Unauthorized(
+            "A different controller must confirm the change".to_string(),
+        ));
+    }
+    storage::take_pending_config_change(id);
+    if !is_live(&pending) {
+        return Err(ClanopediaError::InvalidOperation(format!(
+            "Configuration change {} expired",
+            id
+        )));
+    }
+
+    match pending.setting {
+        ConfigSetting::BluebandCanister(canister_id) => {
+            admin::swap_blueband_canister(pending.requested_by, caller, canister_id).await
+        }
+        ConfigSetting::BackupConfig(None) => backup::disable(pending.requested_by, caller),
+        _ => Err(ClanopediaError::InvalidOperation(format!(
+            "Configuration change {} cannot be applied",
+            id
+        ))),
+    }
+}
+
+/// Withdraw a pending change; any controller may
+pub fn cancel_change(caller: Principal, id: u64) -> ClanopediaResult<()> {
+    require_controller(caller)?;
+    storage::take_pending_config_change(id)
+        .map(|_| ())
+        .ok_or_else(|| ClanopediaError::NotFound(format!("Configuration change {} not found", id)))
+}
+
+pub fn list_pending(caller: Principal) -> ClanopediaResult<Vec<PendingConfigChange>> {
+    require_controller(caller)?;
+    Ok(storage::list_pending_config_changes()
+        .into_iter()
+        .filter(is_live)
+        .collect())
+}
@@ -0,0 +1,97 @@
+// src/clanopedia_backend/src/content_limits.rs
+//
+// Size caps on a collection's content: each document's, and optionally all of it together.
+// Sizes are recorded as documents are added, so the total leaves out documents added before
+// sizes were recorded. Trashed documents count until they are purged.
+
+use crate::{
+    extractor::{
+        types::{ExtractionError, FileExtractionConfig},
+        url_extractor::YOUTUBE_BATCH_SIZE,
+    },
+    storage,
+    types::{
+        ClanopediaResult, Collection, CollectionLimits, DEFAULT_MAX_DOCUMENT_BYTES,
+    },
+    AddDocumentRequest,
+};
+
+pub fn max_document_bytes(collection: &Collection) -> u64 {
+    collection
+        .content_limits
+        .as_ref()
+        .and_then(|l| l.max_document_bytes)
+        .unwrap_or(DEFAULT_MAX_DOCUMENT_BYTES)
+}
+
+fn max_total_bytes(collection: &Collection) -> Option<u64> {
+    collection
+        .content_limits
+        .as_ref()
+        .and_then(|l| l.max_total_bytes)
+}
+
+fn check_document_bytes(max: u64, bytes: u64) -> ClanopediaResult<()> {
+    if bytes > max {
+        return Err(ExtractionError::TooLarge {
+            bytes,
+            max_bytes: max,
+        }
+        .into());
+    }
+    Ok(())
+}
+
+/// Fail if one document's content is over the collection's cap. Collections that cannot be
+/// read get the default cap; the caller's own lookup reports them missing.
+pub fn check_content(collection_id: &str, content: &str) -> ClanopediaResult<()> {
+    let max = storage::get_collection(&collection_id.to_string())
+        .map_or(DEFAULT_MAX_DOCUMENT_BYTES, |c| max_document_bytes(&c));
+    check_document_bytes(max, content.len() as u64)
+}
+
+/// Fail if any document is over the per-document cap, or adding them all would take the
+/// collection past its total cap
+pub fn check_documents(
+    collection: &Collection,
+    documents: &[AddDocumentRequest],
+) -> ClanopediaResult<()> {
+    let max = max_document_bytes(collection);
+    for document in documents {
+        check_document_bytes(max, document.content.len() as u64)?;
+    }
+    let Some(max_total) = max_total_bytes(collection) else {
+        return Ok(());
+    };
+    let adding: u64 = documents.iter().map(|d| d.content.len() as u64).sum();
+    let total = storage::total_document_bytes(&collection.id);
+    if total.saturating_add(adding) > max_total {
+        return Err(ExtractionError::QuotaExceeded(format!(
+            "The collection holds {} of {} bytes; {} more do not fit",
+            total, max_total, adding
+        ))
+        .into());
+    }
+    Ok(())
+}
+
+pub fn get_collection_limits(collection_id: &str) -> ClanopediaResult<CollectionLimits> {
+    let collection = storage::get_collection(&collection_id.to_string())?;
+    Ok(CollectionLimits {
+        max_document_bytes: max_document_bytes(&collection),
+        max_total_bytes: max_total_bytes(&collection),
+        total_bytes: storage::total_document_bytes(collection_id),
+        ..default_limits()
+    })
+}
+
+/// Limits of a collection that sets none of its own
+pub fn default_limits() -> CollectionLimits {
+    CollectionLimits {
+        max_document_bytes: DEFAULT_MAX_DOCUMENT_BYTES,
+        max_total_bytes: None,
+        total_bytes: 0,
+        max_file_bytes: FileExtractionConfig::default().max_file_size,
+        max_playlist_videos: YOUTUBE_BATCH_SIZE,
+    }
+}
@@ -0,0 +1,408 @@
+// src/clanopedia_backend/src/creation.rs
+//
+// Who may create collections, and creating them safely. A collection is created in two
+// places, Clanopedia and Blueband, so each creation first records an intent, then writes the
+// local record, then creates the Blueband collection. A failure in between deletes the local
+// record; a trap leaves the intent behind, and the timer later repairs the collection.
+
+use candid::Principal;
+use ic_cdk::api::time;
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use crate::{
+    config_history,
+    external::{blueband, token},
+    logging, proxy, storage,
+    types::{
+        ClanopediaError, ClanopediaResult, CollectionConfig, CollectionId, ConfigSetting,
+        CreationFee, CreationMode, CreationPolicy, CreationReconciliation, CreationRequest,
+        CreationRequestStatus, PendingCreation, StorageMap,
+    },
+    validation,
+};
+
+// ============================
+// POLICY
+// ============================
+
+fn is_manager(caller: &Principal, policy: &CreationPolicy) -> bool {
+    ic_cdk::api::is_controller(caller) || policy.root_governance.as_ref() == Some(caller)
+}
+
+fn require_manager(caller: Principal) -> ClanopediaResult<CreationPolicy> {
+    let policy = storage::get_creation_policy();
+    if is_manager(&caller, &policy) {
+        Ok(policy)
+    } else {
+        Err(ClanopediaError::NotAuthorized)
+    }
+}
+
+/// Replace the creation policy. Only controllers may hand the root governance role to
+/// someone else.
+pub fn set_policy(caller: Principal, policy: CreationPolicy) -> ClanopediaResult<()> {
+    let current = require_manager(caller)?;
+    if policy.root_governance != current.root_governance {
+        proxy::require_controller(caller)?;
+    }
+
+    if let CreationMode::FeeGated(fee) = &policy.mode {
+        if fee.amount == 0u64 {
+            return Err(ClanopediaError::InvalidInput(
+                "Creation fee must be greater than zero".to_string(),
+            ));
+        }
+    }
+    if policy.max_collections_per_principal == Some(0) {
+        return Err(ClanopediaError::InvalidInput(
+            "Collection cap must be at least 1; use an empty allowlist to stop creation"
+                .to_string(),
+        ));
+    }
+
+    storage::set_creation_policy(policy.clone());
+    config_history::record(caller, None, ConfigSetting::CreationPolicy(policy))?;
+    Ok(())
+}
+
+thread_local! {
+    // Direct creations admitted and still running, per principal. They count against the
+    // cap so concurrent calls cannot all pass it while their fees are collected.
+    static ADMITTED: RefCell<HashMap<Principal, u32>> = RefCell::new(HashMap::new());
+}
+
+/// A principal's place under the collection cap, held until its direct creation returns
+pub struct CreationSlot(Option<Principal>);
+
+impl CreationSlot {
+    fn reserve(principal: Principal) -> Self {
+        ADMITTED.with(|a| *a.borrow_mut().entry(principal).or_default() += 1);
+        CreationSlot(Some(principal))
+    }
+}
+
+impl Drop for CreationSlot {
+    fn drop(&mut self) {
+        let Some(principal) = self.0 else {
+            return;
+        };
+        ADMITTED.with(|a| {
+            let mut admitted = a.borrow_mut();
+            if let Some(count) = admitted.get_mut(&principal) {
+                *count -= 1;
+                if *count == 0 {
+                    admitted.remove(&principal);
+                }
+            }
+        });
+    }
+}
+
+// Collections the principal created, creations it has running and requests it still has
+// pending
+fn collections_held(principal: Principal) -> u32 {
+    let created = storage::list_collections()
+        .into_iter()
+        .filter(|c| c.creator == principal)
+        .count();
+    let pending = storage::list_creation_requests()
+        .into_iter()
+        .filter(|r| r.requester == principal && r.status == CreationRequestStatus::Pending)
+        .count();
+    let running = ADMITTED.with(|a| a.borrow().get(&principal).copied().unwrap_or(0));
+    (created + pending) as u32 + running
+}
+
+fn check_cap(caller: Principal, policy: &CreationPolicy) -> ClanopediaResult<()> {
+    match policy.max_collections_per_principal {
+        Some(max) if collections_held(caller) >= max => Err(ClanopediaError::InvalidOperation(
+            format!("Each principal may create at most {} collections", max),
+        )),
+        _ => Ok(()),
+    }
+}
+
+// ============================
+// DIRECT CREATION
+// ============================
+
+/// Check that `caller` may create a collection right now, collecting the fee when the
+/// policy asks for one. The returned fee must be passed to `settle_fee` afterwards, and the
+/// slot held until the creation returns.
+pub async fn admit(caller: Principal) -> ClanopediaResult<(Option<CreationFee>, CreationSlot)> {
+    if ic_cdk::api::is_controller(&caller) {
+        return Ok((None, CreationSlot(None)));
+    }
+    let policy = storage::get_creation_policy();
+    check_cap(caller, &policy)?;
+    // Taken before the fee is awaited; dropped with it if collecting the fee fails
+    let slot = CreationSlot::reserve(caller);
+
+    match policy.mode {
+        CreationMode::Open => Ok((None, slot)),
+        CreationMode::Allowlist => {
+            if policy.allowlist.contains(&caller) {
+                Ok((None, slot))
+            } else {
+                Err(ClanopediaError::NotAuthorized)
+            }
+        }
+        CreationMode::FeeGated(fee) => {
+            let ledger = token::ledger(fee.ledger);
+            let escrow = token::escrow_subaccount(token::CREATION_ESCROW);
+            token::pull_approved_tokens(&ledger, caller, fee.amount.clone(), escrow)
+                .await
+                .map_err(|e| {
+                    ClanopediaError::InvalidOperation(format!(
+                        "Creation fee of {} tokens could not be collected. Approve this canister as spender first: {}",
+                        fee.amount, e
+                    ))
+                })?;
+            Ok((Some(fee), slot))
+        }
+        CreationMode::ProposalGated => Err(ClanopediaError::InvalidOperation(
+            "Collection creation needs approval; submit request_collection_creation instead"
+                .to_string(),
+        )),
+    }
+}
+
+/// Forward a collected fee to the treasury once the collection exists, or refund it when
+/// creation failed. Ledger fees for the transfer come out of the amount.
+pub async fn settle_fee(fee: Option<CreationFee>, payer: Principal, created: bool) {
+    let Some(fee) = fee else {
+        return;
+    };
+    let recipient = match (created, fee.treasury) {
+        (false, _) => payer,
+        (true, Some(treasury)) => treasury,
+        (true, None) => return,
+    };
+
+    let ledger = token::ledger(fee.ledger);
+    let escrow = token::escrow_subaccount(token::CREATION_ESCROW);
+    if let Err(e) = token::send_tokens(&ledger, Some(escrow), recipient, fee.amount).await {
+        logging::error(
+            "creation",
+            None,
+            format!("Settling creation fee of {} failed: {}", payer, e),
+        );
+    }
+}
+
+// ============================
+// CREATION REQUESTS
+// ============================
+
+pub async fn request_creation(
+    caller: Principal,
+    mut config: CollectionConfig,
+) -> ClanopediaResult<u64> {
+    let policy = storage::get_creation_policy();
+    if policy.mode != CreationMode::ProposalGated {
+        return Err(ClanopediaError::InvalidOperation(
+            "Collections can be created directly; creation requests are not needed".to_string(),
+        ));
+    }
+    check_cap(caller, &policy)?;
+    validation::validate_collection_config(&mut config)?;
+    let sealed_proxy_api_key = proxy::seal_config_key(&mut config).await?;
+
+    // Checked again now that other requests may have been written while sealing
+    check_cap(caller, &storage::get_creation_policy())?;
+    storage::ensure_capacity(StorageMap::CreationRequests, 1)?;
+
+    let request = CreationRequest {
+        id: storage::next_creation_request_id(),
+        requester: caller,
+        config,
+        requested_at: time(),
+        status: CreationRequestStatus::Pending,
+        decided_by: None,
+        sealed_proxy_api_key,
+    };
+    storage::put_creation_request(&request);
+    Ok(request.id)
+}
+
+fn get_pending_request(id: u64) -> ClanopediaResult<CreationRequest> {
+    let request = storage::get_creation_request(id)
+        .ok_or_else(|| ClanopediaError::NotFound(format!("Creation request {} not found", id)))?;
+    if request.status != CreationRequestStatus::Pending {
+        return Err(ClanopediaError::InvalidOperation(format!(
+            "Creation request {} has already been decided",
+            id
+        )));
+    }
+    Ok(request)
+}
+
+/// Create the requested collection on behalf of its requester
+pub async fn approve_request(caller: Principal, id: u64) -> ClanopediaResult<CollectionId> {
+    require_manager(caller)?;
+    let mut request = get_pending_request(id)?;
+    let config = proxy::unseal_config_key(request.config.clone(), &request.sealed_proxy_api_key)?;
+
+    // Claim the request before awaiting so a second approval cannot create it twice
+    request.status = CreationRequestStatus::Approved(String::new());
+    request.decided_by = Some(caller);
+    storage::put_creation_request(&request);
+
+    match crate::create_collection_as(config, request.requester).await {
+        Ok(collection_id) => {
+            request.status = CreationRequestStatus::Approved(collection_id.clone());
+            storage::put_creation_request(&request);
+            Ok(collection_id)
+        }
+        Err(e) => {
+            request.status = CreationRequestStatus::Pending;
+            request.decided_by = None;
+            storage::put_creation_request(&request);
+            Err(e)
+        }
+    }
+}
+
+pub fn reject_request(caller: Principal, id: u64, reason: String) -> ClanopediaResult<()> {
+    require_manager(caller)?;
+    let mut request = get_pending_request(id)?;
+    request.status = CreationRequestStatus::Rejected(reason);
+    request.decided_by = Some(caller);
+    storage::put_creation_request(&request);
+    Ok(())
+}
+
+/// Managers see every request; anyone else only their own
+pub fn list_requests(caller: Principal) -> Vec<CreationRequest> {
+    let manager = is_manager(&caller, &storage::get_creation_policy());
+    storage::list_creation_requests()
+        .into_iter()
+        .filter(|r| manager || r.requester == caller)
+        .collect()
+}
+
+// ============================
+// HALF-CREATED COLLECTIONS
+// ============================
+
+// An intent older than this belongs to a creation that can no longer be running: the call
+// that started it returned or trapped long ago
+const STALE_CREATION_NANOS: u64 = 15 * 60 * 1_000_000_000;
+
+/// Record that a collection is about to be created, before either side is written
+pub fn begin(collection_id: &CollectionId, creator: Principal) {
+    storage::put_pending_creation(&PendingCreation {
+        collection_id: collection_id.clone(),
+        creator,
+        started_at: time(),
+    });
+}
+
+/// Both sides have the collection
+pub fn finish(collection_id: &str) {
+    storage::remove_pending_creation(collection_id);
+}
+
+/// Undo the local half of a creation whose Blueband half failed
+pub fn abort(collection_id: &CollectionId) {
+    if storage::get_collection(collection_id).is_ok() {
+        if let Err(e) = storage::delete_collection(collection_id) {
+            logging::error(
+                "creation",
+                Some(collection_id),
+                format!("Rolling back a failed creation failed: {:?}", e),
+            );
+            return; // Leave the intent for reconciliation
+        }
+    }
+    storage::remove_pending_creation(collection_id);
+}
+
+/// When the oldest intent becomes stale, for the timer
+pub fn next_reconcile_at() -> Option<u64> {
+    storage::list_pending_creations()
+        .iter()
+        .map(|p| p.started_at + STALE_CREATION_NANOS)
+        .min()
+}
+
+// Bring one stale creation to a consistent state: linked when both sides exist, otherwise
+// deleted from whichever side has it. Returns whether it was completed.
+async fn repair(pending: &PendingCreation) -> Result<bool, String> {
+    let id = &pending.collection_id;
+    let local = storage::get_collection(id).ok();
+    if local
+        .as_ref()
+        .is_some_and(|c| !c.blueband_collection_id.is_empty())
+    {
+        finish(id);
+        return Ok(true);
+    }
+
+    let remote = blueband::get_blueband_collection(id).await?;
+    // The collection may have changed while Blueband was asked
+    let local = storage::get_collection(id).ok();
+    let completed = match (local, remote) {
+        (Some(mut collection), Some(remote)) => {
+            collection.blueband_collection_id = remote.id;
+            storage::update_collection(id, &collection).map_err(|e| format!("{:?}", e))?;
+            true
+        }
+        (Some(_), None) => {
+            storage::delete_collection(id).map_err(|e| format!("{:?}", e))?;
+            false
+        }
+        (None, Some(_)) => {
+            blueband::delete_collection(id).await?;
+            false
+        }
+        (None, None) => false,
+    };
+    finish(id);
+    Ok(completed)
+}
+
+/// Repair collections whose creation stopped halfway, e.g. because the canister trapped
+/// between writing its record and hearing back from Blueband. Intents younger than
+/// `STALE_CREATION_NANOS` may still be running and are left alone.
+pub async fn reconcile_stale() -> CreationReconciliation {
+    let mut report = CreationReconciliation::default();
+    let now = time();
+    for pending in storage::list_pending_creations() {
+        if now.saturating_sub(pending.started_at) < STALE_CREATION_NANOS {
+            report.pending.push(pending);
+            continue;
+        }
+        match repair(&pending).await {
+            Ok(true) => report.completed.push(pending.collection_id),
+            Ok(false) => report.rolled_back.push(pending.collection_id),
+            Err(e) => {
+                logging::warn(
+                    "creation",
+                    Some(&pending.collection_id),
+                    format!("Repairing a half-created collection failed: {}", e),
+                );
+                report.pending.push(pending);
+            }
+        }
+    }
+    if !report.completed.is_empty() || !report.rolled_back.is_empty() {
+        logging::info(
+            "creation",
+            None,
+            format!(
+                "Reconciled half-created collections: {} completed, {} rolled back",
+                report.completed.len(),
+                report.rolled_back.len()
+            ),
+        );
+    }
+    report
+}
+
+/// Run reconciliation now; for managers
+pub async fn reconcile(caller: Principal) -> ClanopediaResult<CreationReconciliation> {
+    require_manager(caller)?;
+    Ok(reconcile_stale().await)
+}
@@ -0,0 +1,214 @@
+// src/clanopedia_backend/src/crosspost.rs
+//
+// A document approved in one collection can be linked into others without adding its
+// content to their Blueband indexes again. The link records which collection holds the
+// content; reads and searches in the linking collection are answered from that index,
+// with the linking collection's own overlays and trash applied on top.
+
+use std::collections::{HashMap, HashSet};
+
+use futures_util::future::join_all;
+use ic_cdk::api::time;
+
+use crate::{
+    encryption,
+    external::blueband::{self, VectorMatch},
+    keyword_index, storage, trash,
+    types::{
+        ChangeKind, ClanopediaError, ClanopediaResult, Collection, CollectionId, CrossPost,
+        DocumentId,
+    },
+};
+
+// Blueband's result count when a search gives no limit
+const DEFAULT_SEARCH_LIMIT: u32 = 10;
+
+/// Check that `document_id` of `source_collection` can be linked into `collection`, and
+/// return the source collection
+pub fn validate_link(
+    collection: &Collection,
+    source_collection: &str,
+    document_id: &str,
+) -> ClanopediaResult<Collection> {
+    if source_collection == collection.id {
+        return Err(ClanopediaError::InvalidInput(
+            "A document cannot be linked into its own collection".to_string(),
+        ));
+    }
+    let source = storage::get_collection(&source_collection.to_string())?;
+    trash::ensure_not_trashed(&source)?;
+
+    // Neither side may serve plaintext of, or to, a collection with restricted readers
+    if encryption::is_encrypted(collection) || encryption::is_encrypted(&source) {
+        return Err(ClanopediaError::InvalidOperation(
+            "Encrypted collections cannot share documents".to_string(),
+        ));
+    }
+
+    if let Some(link) = storage::get_cross_post(&source.id, document_id) {
+        return Err(ClanopediaError::InvalidInput(format!(
+            "Document {} is itself linked from collection {}; link it from there",
+            document_id, link.source_collection
+        )));
+    }
+    if trash::is_document_trashed(&source.id, document_id) {
+        return Err(ClanopediaError::NotFound(format!(
+            "Document {} not found in collection {}",
+            document_id, source.id
+        )));
+    }
+    if storage::get_cross_post(&collection.id, document_id).is_some()
+        || storage::get_indexed_document(&collection.id, document_id).is_some()
+    {
+        return Err(ClanopediaError::AlreadyExists(format!(
+            "Document {} is already in collection {}",
+            document_id, collection.id
+        )));
+    }
+
+    Ok(source)
+}
+
+pub async fn execute_link_document(
+    collection_id: &str,
+    source_collection: &str,
+    document_id: &str,
+) -> ClanopediaResult<()> {
+    let collection = storage::get_collection(&collection_id.to_string())?;
+    let source = validate_link(&collection, source_collection, document_id)?;
+
+    let metadata = blueband::get_document_metadata(
+        source.blueband_collection_id.clone(),
+        document_id.to_string(),
+    )
+    .await
+    .map_err(ClanopediaError::BluebandError)?
+    .ok_or_else(|| {
+        ClanopediaError::NotFound(format!(
+            "Document {} not found in collection {}",
+            document_id, source.id
+        ))
+    })?;
+
+    // Indexed under the title and tags the source collection shows
+    let overlay = storage::get_document_overlay(&source.id, document_id);
+    let indexed = storage::get_indexed_document(&source.id, document_id);
+    let title = overlay
+        .as_ref()
+        .and_then(|o| o.title.clone())
+        .unwrap_or(metadata.title);
+    let tags = overlay
+        .and_then(|o| o.tags)
+        .or(indexed.map(|d| d.tags))
+        .unwrap_or_default();
+
+    storage::set_cross_post(
+        collection_id,
+        document_id,
+        CrossPost {
+            source_collection: source.id.clone(),
+            linked_at: time(),
+        },
+    );
+    keyword_index::index_document(collection_id, document_id, &title, &tags);
+    storage::append_change(
+        collection_id,
+        ChangeKind::DocumentAdded {
+            document_id: document_id.to_string(),
+        },
+    );
+
+    ic_cdk::println!(
+        "Linked document {} of collection {} into collection {}",
+        document_id,
+        source.id,
+        collection_id
+    );
+    Ok(())
+}
+
+/// Drop links to content that was purged from `source_collection`: one document, or with
+/// `None` all of them
+pub fn remove_links_from(source_collection: &str, document_id: Option<&str>) {
+    for (collection_id, linked_id) in storage::list_cross_posts_from(source_collection) {
+        if document_id.is_some_and(|id| id != linked_id) {
+            continue;
+        }
+        keyword_index::remove_document(&collection_id, &linked_id);
+        storage::remove_document_records(&collection_id, &linked_id);
+        storage::append_change(
+            &collection_id,
+            ChangeKind::DocumentRemoved {
+                document_id: linked_id,
+            },
+        );
+    }
+}
+
+/// The collection whose Blueband index holds a document's content
+pub fn content_collection(
+    collection: &Collection,
+    document_id: &str,
+) -> ClanopediaResult<Collection> {
+    match storage::get_cross_post(&collection.id, document_id) {
+        Some(link) => storage::get_collection(&link.source_collection),
+        None => Ok(collection.clone()),
+    }
+}
+
+/// Add matches for the collection's linked documents to `matches`, searching each source
+/// collection once, and keep the best `limit` by score. Sources that cannot be searched
+/// are left out rather than failing the search.
+pub async fn merge_linked_matches(
+    collection: &Collection,
+    query: &str,
+    limit: Option<u32>,
+    matches: &mut Vec<VectorMatch>,
+) {
+    let mut sources: HashMap<CollectionId, HashSet<DocumentId>> = HashMap::new();
+    for (document_id, link) in storage::list_cross_posts(&collection.id) {
+        sources
+            .entry(link.source_collection)
+            .or_default()
+            .insert(document_id);
+    }
+    if sources.is_empty() {
+        return;
+    }
+
+    let limit = limit.unwrap_or(DEFAULT_SEARCH_LIMIT);
+    let searches = sources
+        .into_iter()
+        .filter_map(|(source_id, linked)| {
+            let source = storage::get_collection(&source_id).ok()?;
+            (source.trashed.is_none()).then_some((source, linked))
+        })
+        .map(|(source, linked)| async move {
+            let found = blueband::search_collection(
+                &source.blueband_collection_id,
+                query.to_string(),
+                Some(limit),
+            )
+            .await;
+            (source, linked, found)
+        });
+
+    for (source, linked, found) in join_all(searches).await {
+        match found {
+            Ok(mut found) => {
+                found.retain(|m| linked.contains(&m.document_id));
+                crate::apply_overlays(&source.id, &mut found);
+                crate::apply_overlays(&collection.id, &mut found);
+                matches.extend(found);
+            }
+            Err(e) => ic_cdk::println!(
+                "Searching linked documents of collection {} failed: {}",
+                source.id,
+                e
+            ),
+        }
+    }
+
+    matches.sort_by(|a, b| b.score.total_cmp(&a.score));
+    matches.truncate(limit as usize);
+}
@@ -0,0 +1,372 @@
+// src/clanopedia_backend/src/custom_proposals.rs
+//
+// Communities can govern their own infrastructure through Clanopedia. The collection config
+// names custom proposal kinds, each bound to one canister method. A Custom proposal picks a
+// kind and carries a candid-encoded argument that is passed through unread. It is voted on
+// like any other proposal, and executing it calls the method; a rejected call fails the
+// proposal.
+//
+// Controllers keep the canister-wide call allowlist: the canister methods proposals may call
+// at all, each with caps on argument size and attached cycles. A collection's call policy can
+// only pick from that list and tighten its caps. The policy is checked when kinds are defined
+// and again on every call, and each call made is kept in an audit log.
+//
+// Ledgers this canister holds escrowed tokens on cannot be called at all, nor can token
+// transfer and approval methods on any canister, so no proposal can move funds.
+
+use candid::Principal;
+use ic_cdk::api::call::msg_cycles_refunded;
+use ic_cdk::api::time;
+
+use crate::{
+    auth, config_history,
+    external::traced,
+    proxy::require_controller,
+    storage,
+    types::{
+        CallAllowlistEntry, CallPolicy, Capability, ClanopediaError, ClanopediaResult, Collection,
+        ConfigSetting, CreationMode, CustomProposalKind, ExternalCallOutcome, ExternalCallRecord,
+        ProposalFee, StorageMap, MAX_CALL_ARG_BYTES,
+    },
+};
+
+const MAX_CUSTOM_KINDS: usize = 20;
+const MAX_ALLOWED_CALLS: usize = 20;
+const MAX_KIND_NAME_CHARS: usize = 64;
+const MAX_METHOD_CHARS: usize = 128;
+const MAX_CALL_CYCLES: u64 = 1_000_000_000_000; // 1T per call
+const MAX_AUDIT_PAGE: usize = 100;
+// ICRC and legacy ledger methods that move or approve tokens
+const TOKEN_METHODS: [&str; 5] = [
+    "icrc1_transfer",
+    "icrc2_approve",
+    "icrc2_transfer_from",
+    "transfer",
+    "send_dfx",
+];
+
+// Whether `canister` is a ledger holding bonds, proposal fees or creation fees for this
+// canister
+fn holds_funds_on(canister: Principal) -> bool {
+    if matches!(
+        storage::get_creation_policy().mode,
+        CreationMode::FeeGated(fee) if fee.ledger == canister
+    ) {
+        return true;
+    }
+    storage::list_collections().iter().any(|collection| {
+        collection.governance_token == Some(canister)
+            || matches!(
+                collection.spam_protection.as_ref().and_then(|p| p.fee.as_ref()),
+                Some(ProposalFee::Tokens { ledger, .. }) if *ledger == canister
+            )
+    })
+}
+
+fn ensure_no_funds_moved(canister: Principal, method: &str) -> ClanopediaResult<()> {
+    if TOKEN_METHODS.contains(&method) {
+        return Err(ClanopediaError::InvalidInput(format!(
+            "Token method {} cannot be called by proposals",
+            method
+        )));
+    }
+    if holds_funds_on(canister) {
+        return Err(ClanopediaError::InvalidInput(format!(
+            "{} is a ledger this canister holds escrowed tokens on",
+            canister
+        )));
+    }
+    Ok(())
+}
+
+// ============================
+// CALL ALLOWLIST
+// ============================
+
+/// Let proposals call `canister.method`, within the given caps; controllers only
+pub fn add_call_allowlist_entry(
+    caller: Principal,
+    canister: Principal,
+    method: String,
+    max_arg_bytes: Option<u32>,
+    max_cycles: Option<u64>,
+) -> ClanopediaResult<()> {
+    require_controller(caller)?;
+
+    let method = method.trim().to_string();
+    if method.is_empty() || method.len() > MAX_METHOD_CHARS {
+        return Err(ClanopediaError::InvalidInput(format!(
+            "Allowlisted methods must be 1 to {} bytes",
+            MAX_METHOD_CHARS
+        )));
+    }
+    // Calls from Clanopedia itself would bypass the checks its own endpoints make
+    if canister == ic_cdk::id()
+        || canister == Principal::management_canister()
+        || canister == Principal::anonymous()
+    {
+        return Err(ClanopediaError::InvalidInput(
+            "This canister, the management canister and the anonymous principal cannot be \
+             allowlisted"
+                .to_string(),
+        ));
+    }
+    ensure_no_funds_moved(canister, &method)?;
+    let max_arg_bytes = max_arg_bytes.unwrap_or(MAX_CALL_ARG_BYTES);
+    if max_arg_bytes > MAX_CALL_ARG_BYTES {
+        return Err(ClanopediaError::InvalidInput(format!(
+            "max_arg_bytes must be at most {}",
+            MAX_CALL_ARG_BYTES
+        )));
+    }
+    let max_cycles = max_cycles.unwrap_or(0);
+    if max_cycles > MAX_CALL_CYCLES {
+        return Err(ClanopediaError::InvalidInput(format!(
+            "max_cycles must be at most {}",
+            MAX_CALL_CYCLES
+        )));
+    }
+
+    if storage::get_call_allowlist_entry(&canister, &method).is_none() {
+        storage::ensure_capacity(StorageMap::CallAllowlist, 1)?;
+    }
+    storage::put_call_allowlist_entry(CallAllowlistEntry {
+        canister,
+        method,
+        max_arg_bytes,
+        max_cycles,
+        added_by: caller,
+        added_at: time(),
+    });
+    config_history::record(
+        caller,
+        None,
+        ConfigSetting::CallAllowlist(storage::list_call_allowlist()),
+    )?;
+    Ok(())
+}
+
+/// Stop proposals calling `canister.method`. Kinds bound to it stay defined but fail at
+/// execution.
+pub fn remove_call_allowlist_entry(
+    caller: Principal,
+    canister: Principal,
+    method: &str,
+) -> ClanopediaResult<()> {
+    require_controller(caller)?;
+    if storage::remove_call_allowlist_entry(&canister, method).is_some() {
+        config_history::record(
+            caller,
+            None,
+            ConfigSetting::CallAllowlist(storage::list_call_allowlist()),
+        )?;
+    }
+    Ok(())
+}
+
+fn allowlist_entry(canister: Principal, method: &str) -> ClanopediaResult<CallAllowlistEntry> {
+    storage::get_call_allowlist_entry(&canister, method).ok_or_else(|| {
+        ClanopediaError::InvalidInput(format!(
+            "{}.{} is not on the canister's call allowlist",
+            canister, method
+        ))
+    })
+}
+
+// ============================
+// CALL POLICIES
+// ============================
+
+pub fn validate_call_policy(policy: &Option<CallPolicy>) -> ClanopediaResult<()> {
+    let Some(policy) = policy else {
+        return Ok(());
+    };
+    if policy.allowed.len() > MAX_ALLOWED_CALLS {
+        return Err(ClanopediaError::InvalidInput(format!(
+            "At most {} methods can be allowlisted",
+            MAX_ALLOWED_CALLS
+        )));
+    }
+    for allowed in &policy.allowed {
+        allowlist_entry(allowed.canister, &allowed.method)?;
+        ensure_no_funds_moved(allowed.canister, &allowed.method)?;
+    }
+    if policy
+        .max_arg_bytes
+        .is_some_and(|max| max > MAX_CALL_ARG_BYTES)
+    {
+        return Err(ClanopediaError::InvalidInput(format!(
+            "max_arg_bytes must be at most {}",
+            MAX_CALL_ARG_BYTES
+        )));
+    }
+    if policy.max_cycles.is_some_and(|max| max > MAX_CALL_CYCLES) {
+        return Err(ClanopediaError::InvalidInput(format!(
+            "max_cycles must be at most {}",
+            MAX_CALL_CYCLES
+        )));
+    }
+    Ok(())
+}
+
+/// Check the kinds themselves and that the policy allows each of them
+pub fn validate_custom_kinds(
+    kinds: &Option<Vec<CustomProposalKind>>,
+    policy: &Option<CallPolicy>,
+) -> ClanopediaResult<()> {
+    let Some(kinds) = kinds else {
+        return Ok(());
+    };
+    if kinds.len() > MAX_CUSTOM_KINDS {
+        return Err(ClanopediaError::InvalidInput(format!(
+            "At most {} custom proposal kinds can be defined",
+            MAX_CUSTOM_KINDS
+        )));
+    }
+    for (i, kind) in kinds.iter().enumerate() {
+        if kind.name.trim().is_empty() || kind.name.chars().count() > MAX_KIND_NAME_CHARS {
+            return Err(ClanopediaError::InvalidInput(format!(
+                "Custom proposal kind names must be 1 to {} characters",
+                MAX_KIND_NAME_CHARS
+            )));
+        }
+        if kinds[..i].iter().any(|k| k.name == kind.name) {
+            return Err(ClanopediaError::InvalidInput(format!(
+                "Custom proposal kind {} is defined twice",
+                kind.name
+            )));
+        }
+        ensure_call_allowed(policy, kind)?;
+    }
+    Ok(())
+}
+
+fn ensure_call_allowed(
+    policy: &Option<CallPolicy>,
+    kind: &CustomProposalKind,
+) -> ClanopediaResult<()> {
+    let allowed = policy
+        .iter()
+        .flat_map(|p| &p.allowed)
+        .any(|allowed| allowed.canister == kind.canister && allowed.method == kind.method);
+    if !allowed {
+        return Err(ClanopediaError::NotAuthorized);
+    }
+    let entry = allowlist_entry(kind.canister, &kind.method)?;
+    ensure_no_funds_moved(kind.canister, &kind.method)?;
+    let max_cycles = policy
+        .as_ref()
+        .and_then(|p| p.max_cycles)
+        .unwrap_or(0)
+        .min(entry.max_cycles);
+    if kind.cycles.unwrap_or(0) > max_cycles {
+        return Err(ClanopediaError::InvalidInput(format!(
+            "Custom proposal kind {} attaches more than the {} cycles its call may carry",
+            kind.name, max_cycles
+        )));
+    }
+    Ok(())
+}
+
+/// Check that `kind` is defined and allowed by the collection and the payload fits, and
+/// return it
+pub fn validate_custom(
+    collection: &Collection,
+    kind: &str,
+    payload: &[u8],
+) -> ClanopediaResult<CustomProposalKind> {
+    let kind = collection
+        .custom_proposals
+        .iter()
+        .flatten()
+        .find(|k| k.name == kind)
+        .cloned()
+        .ok_or_else(|| {
+            ClanopediaError::NotFound(format!(
+                "Collection {} defines no custom proposal kind {}",
+                collection.id, kind
+            ))
+        })?;
+    ensure_call_allowed(&collection.call_policy, &kind).map_err(|e| match e {
+        ClanopediaError::NotAuthorized => ClanopediaError::InvalidOperation(format!(
+            "The call policy of collection {} does not allow {}.{}",
+            collection.id, kind.canister, kind.method
+        )),
+        e => e,
+    })?;
+
+    let max_arg_bytes = collection
+        .call_policy
+        .as_ref()
+        .and_then(|p| p.max_arg_bytes)
+        .unwrap_or(MAX_CALL_ARG_BYTES)
+        .min(allowlist_entry(kind.canister, &kind.method)?.max_arg_bytes);
+    if payload.len() > max_arg_bytes as usize {
+        return Err(ClanopediaError::InvalidInput(format!(
+            "Payload too large: {} bytes (max: {} bytes)",
+            payload.len(),
+            max_arg_bytes
+        )));
+    }
+    Ok(kind)
+}
+
+pub async fn execute_custom(
+    collection: &Collection,
+    proposal_id: &str,
+    kind: &str,
+    payload: &[u8],
+) -> ClanopediaResult<()> {
+    let kind = validate_custom(collection, kind, payload)?;
+    let cycles = kind.cycles.unwrap_or(0);
+    if cycles > 0 && ic_cdk::api::canister_balance128() < cycles as u128 {
+        return Err(ClanopediaError::InsufficientCycles(format!(
+            "Custom proposal kind {} attaches {} cycles",
+            kind.name, cycles
+        )));
+    }
+
+    let called_at = time();
+    let result = traced::call_raw(kind.canister, &kind.method, payload, cycles).await;
+    let outcome = match &result {
+        Ok(reply) => ExternalCallOutcome::Replied {
+            reply_bytes: reply.len() as u64,
+            cycles_refunded: msg_cycles_refunded(),
+        },
+        Err((code, message)) => ExternalCallOutcome::Rejected(format!("{:?}: {}", code, message)),
+    };
+    storage::record_external_call(
+        &collection.id,
+        ExternalCallRecord {
+            proposal_id: proposal_id.to_string(),
+            kind: kind.name.clone(),
+            canister: kind.canister,
+            method: kind.method.clone(),
+            arg_bytes: payload.len() as u64,
+            cycles,
+            called_at,
+            outcome: outcome.clone(),
+        },
+    );
+
+    match outcome {
+        ExternalCallOutcome::Replied { .. } => Ok(()),
+        ExternalCallOutcome::Rejected(reason) => Err(ClanopediaError::ExternalCallError(format!(
+            "{}.{} rejected the call: {}",
+            kind.canister, kind.method, reason
+        ))),
+    }
+}
+
+/// The collection's most recent external calls, newest first; admins only
+pub fn get_external_calls(
+    caller: Principal,
+    collection_id: &str,
+) -> ClanopediaResult<Vec<ExternalCallRecord>> {
+    let collection = storage::get_collection(&collection_id.to_string())?;
+    auth::authorize(&collection, caller, Capability::ViewAnalytics)?;
+    let mut calls = storage::list_external_calls(collection_id);
+    calls.reverse();
+    calls.truncate(MAX_AUDIT_PAGE);
+    Ok(calls)
+}
@@ -3,6 +3,8 @@
 use crate::types::*;
 use candid::{CandidType, Principal};
 use serde::{Serialize, Deserialize};
+use std::cell::RefCell;
+use std::collections::HashMap;
 
 
 // predictions for operation costs
@@ -28,6 +30,7 @@ pub struct CyclesStatus {
     pub clanopedia_healthy: bool,
     pub blueband_healthy: bool,
     pub can_transfer_safely: bool,
+    pub reserved_cycles: u64, // Held by embed executions in flight
 }
 
 pub async fn check_cycles_status() -> ClanopediaResult<CyclesStatus> {
@@ -41,9 +44,38 @@ pub async fn check_cycles_status() -> ClanopediaResult<CyclesStatus> {
         clanopedia_healthy,
         blueband_healthy: true,
         can_transfer_safely,
+        reserved_cycles: reserved_cycles(),
     })
 }
 
+// ============================
+// CYCLES RESERVATIONS
+// ============================
+
+thread_local! {
+    // Estimated cost of each embed execution in flight, by reservation ID
+    static RESERVATIONS: RefCell<HashMap<u64, u64>> = RefCell::new(HashMap::new());
+    static NEXT_RESERVATION: RefCell<u64> = const { RefCell::new(0) };
+}
+
+pub fn reserved_cycles() -> u64 {
+    RESERVATIONS.with(|r| r.borrow().values().sum())
+}
+
+/// Cycles set aside for one embed execution. Dropping it releases them, whether the
+/// execution completed, failed, or its future was dropped after a trap.
+pub struct CyclesReservation {
+    id: u64,
+}
+
+impl Drop for CyclesReservation {
+    fn drop(&mut self) {
+        RESERVATIONS.with(|r| {
+            r.borrow_mut().remove(&self.id);
+        });
+    }
+}
+
 // ============================
 //  OPERATION VALIDATION
 // ============================
@@ -59,13 +91,53 @@ pub fn validate_clanopedia_operation() -> ClanopediaResult<()> {
     Ok(())
 }
 
-pub async fn can_execute_embed_proposal(
+/// Check that spending `cost` cycles still leaves the minimum balance plus the safety buffer
+pub fn validate_optional_spend(cost: u128) -> ClanopediaResult<()> {
+    let balance = ic_cdk::api::canister_balance128();
+    let required = cost + (MIN_CLANOPEDIA_BALANCE + SAFETY_BUFFER) as u128;
+    if balance < required {
+        return Err(ClanopediaError::InsufficientCycles(format!(
+            "Balance {} does not cover {} cycles plus the reserved buffer",
+            balance, cost
+        )));
+    }
+    Ok(())
+}
+
+/// Reserve the estimated cost of embedding `documents`. Executions already in flight have
+/// reserved theirs, so two proposals cannot both pass against the same balance.
+pub async fn reserve_embed_cycles(
+    proposal: &Proposal,
+    documents: Vec<String>,
+) -> ClanopediaResult<CyclesReservation> {
+    // No awaits between the balance check and the reservation
+    let (can_execute, message, cost) = can_execute_embed_proposal(proposal, documents).await?;
+    if !can_execute {
+        return Err(ClanopediaError::InsufficientCycles(message));
+    }
+
+    let id = NEXT_RESERVATION.with(|n| {
+        let mut n = n.borrow_mut();
+        *n += 1;
+        *n
+    });
+    RESERVATIONS.with(|r| r.borrow_mut().insert(id, cost));
+    Ok(CyclesReservation { id })
+}
+
+async fn can_execute_embed_proposal(
     _proposal: &Proposal,
     documents: Vec<String>,
-) -> ClanopediaResult<(bool, String)> {
-    let cycles_status = check_cycles_status().await?;
+) -> ClanopediaResult<(bool, String, u64)> {
+    let mut cycles_status = check_cycles_status().await?;
     let cost = estimate_embedding_cost(documents).await?;
 
+    // Cycles reserved by other executions are as good as spent
+    cycles_status.clanopedia_balance = cycles_status
+        .clanopedia_balance
+        .saturating_sub(cycles_status.reserved_cycles);
+    cycles_status.clanopedia_healthy = cycles_status.clanopedia_balance >= MIN_CLANOPEDIA_BALANCE;
+
     if !cycles_status.clanopedia_healthy || !cycles_status.blueband_healthy {
         let message = format!(
             "Insufficient cycles. Clanopedia: {} ({}), Blueband: {} ({}), Required: {}",
@@ -75,18 +147,18 @@ pub async fn can_execute_embed_proposal(
             if cycles_status.blueband_healthy { "✅" } else { "⚠️" },
             cost.total_cost
         );
-        return Ok((false, message));
+        return Ok((false, message, cost.total_cost));
     }
 
     if cycles_status.clanopedia_balance < cost.total_cost {
         let message = format!(
-            "Insufficient cycles for operation. Balance: {}, Required: {}",
-            cycles_status.clanopedia_balance, cost.total_cost
+            "Insufficient cycles for operation. Available: {} ({} reserved), Required: {}",
+            cycles_status.clanopedia_balance, cycles_status.reserved_cycles, cost.total_cost
         );
-        return Ok((false, message));
+        return Ok((false, message, cost.total_cost));
     }
 
-    Ok((true, "Sufficient cycles available".to_string()))
+    Ok((true, "Sufficient cycles available".to_string(), cost.total_cost))
 }
 
 // ============================
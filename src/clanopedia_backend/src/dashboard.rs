@@ -0,0 +1,159 @@
+// src/clanopedia_backend/src/dashboard.rs
+
+use std::collections::HashMap;
+
+use crate::{
+    cycles,
+    external::blueband,
+    governance, health, reactions, review, stats, storage,
+    types::{
+        AnalyticsRange, ClanopediaResult, CollectionDashboard, CollectionEvent,
+        CollectionEventKind, DashboardCounts, EmbeddingStatus, ItemExecutionState, Proposal,
+        ProposalDependency, ProposalStatus, ProposalSummary, Vote,
+    },
+};
+
+const RECENT_EVENT_LIMIT: usize = 20;
+
+/// Collect everything a collection page needs. Blueband metrics are best effort:
+/// a failed call is reported in `embedding.blueband_error` rather than failing the dashboard.
+pub async fn get_collection_dashboard(
+    collection_id: &str,
+) -> ClanopediaResult<CollectionDashboard> {
+    let mut collection = storage::get_collection(&collection_id.to_string())?;
+    collection.proposals.clear();
+    let proposals = storage::list_proposals(collection_id);
+    let totals = stats::get_collection_analytics(
+        collection_id,
+        AnalyticsRange {
+            start: 0,
+            end: None,
+        },
+    )?;
+
+    let mut active_proposals: Vec<ProposalSummary> = proposals
+        .values()
+        .filter(|p| p.status == ProposalStatus::Active)
+        .map(summarize)
+        .collect();
+    active_proposals.sort_by_key(|p| p.expires_at);
+
+    let counts = DashboardCounts {
+        admins: collection.admins.len() as u32,
+        readers: collection
+            .encryption
+            .as_ref()
+            .map_or(0, |e| e.readers.len() as u32),
+        proposals: proposals.len() as u64,
+        active_proposals: active_proposals.len() as u32,
+        documents_added: totals.documents_added,
+        votes: totals.votes,
+        searches: totals.searches,
+    };
+
+    let recent_events = recent_events(&proposals);
+    let mut embedding = embedding_status(&proposals, collection.embedding_proxy_url.clone());
+    let cycles = cycles::check_cycles_status().await?;
+
+    match blueband::get_collection_metrics(&collection.blueband_collection_id).await {
+        Ok(metrics) => {
+            embedding.document_count = Some(metrics.document_count);
+            embedding.search_count = Some(metrics.search_count);
+        }
+        Err(e) => embedding.blueband_error = Some(e),
+    }
+
+    let dependencies = health::collection_dependencies(&collection);
+    Ok(CollectionDashboard {
+        collection,
+        counts,
+        active_proposals,
+        recent_events,
+        embedding,
+        cycles,
+        dependencies,
+    })
+}
+
+fn summarize(proposal: &Proposal) -> ProposalSummary {
+    let count = |vote: Vote| proposal.votes.values().filter(|v| **v == vote).count() as u32;
+    ProposalSummary {
+        id: proposal.id.clone(),
+        proposal_type: proposal.proposal_type.clone(),
+        description: proposal.description.clone(),
+        creator: proposal.creator,
+        created_at: proposal.created_at,
+        expires_at: proposal.expires_at,
+        yes_votes: count(Vote::Yes),
+        no_votes: count(Vote::No),
+        threshold: proposal.threshold,
+        depends_on: proposal
+            .depends_on
+            .iter()
+            .flatten()
+            .map(|dependency| ProposalDependency {
+                proposal_id: dependency.clone(),
+                status: governance::dependency_status(&proposal.collection_id, dependency),
+            })
+            .collect(),
+        review: proposal.review.as_ref().map(review::status),
+        reactions: reactions::proposal_reactions(&proposal.collection_id, &proposal.id),
+    }
+}
+
+// Proposals are the collection's event history; newest first
+fn recent_events(proposals: &HashMap<String, Proposal>) -> Vec<CollectionEvent> {
+    let mut events = Vec::new();
+    for proposal in proposals.values() {
+        let event = |kind, timestamp| CollectionEvent {
+            kind,
+            proposal_id: proposal.id.clone(),
+            timestamp,
+        };
+
+        events.push(event(
+            CollectionEventKind::ProposalCreated,
+            proposal.created_at,
+        ));
+        if let Some(executed_at) = proposal.executed_at {
+            events.push(event(CollectionEventKind::ProposalExecuted, executed_at));
+        }
+        if proposal.status == ProposalStatus::Expired {
+            events.push(event(
+                CollectionEventKind::ProposalExpired,
+                proposal.expires_at,
+            ));
+        }
+    }
+
+    events.sort_by_key(|e| std::cmp::Reverse(e.timestamp));
+    events.truncate(RECENT_EVENT_LIMIT);
+    events
+}
+
+fn embedding_status(
+    proposals: &HashMap<String, Proposal>,
+    proxy_url: Option<String>,
+) -> EmbeddingStatus {
+    let mut status = EmbeddingStatus {
+        document_count: None,
+        search_count: None,
+        blueband_error: None,
+        pending_items: 0,
+        failed_items: 0,
+        proxy_url,
+    };
+
+    for item in proposals
+        .values()
+        .filter_map(|p| p.execution_report.as_ref())
+        .flat_map(|report| report.items.iter())
+    {
+        match item.state {
+            ItemExecutionState::Pending => status.pending_items += 1,
+            ItemExecutionState::Failed(_) => status.failed_items += 1,
+            ItemExecutionState::Succeeded => {}
+        }
+    }
+    status
+}
@@ -0,0 +1,176 @@
+// src/clanopedia_backend/src/encryption.rs
+
+use candid::Principal;
+use ic_cdk::api::time;
+
+use crate::{
+    external::vetkd,
+    storage,
+    types::{
+        ClanopediaError, ClanopediaResult, Collection, CollectionEncryption,
+        EncryptedCollectionKey, EncryptionConfig,
+    },
+};
+
+/// Content sent to an encrypted collection must start with `clanopedia-enc:v1:<key_epoch>:`
+pub const ENCRYPTED_CONTENT_PREFIX: &str = "clanopedia-enc:v1:";
+
+const VETKD_CONTEXT: &[u8] = b"clanopedia-collection-key";
+
+pub fn is_encrypted(collection: &Collection) -> bool {
+    collection.encryption.is_some()
+}
+
+pub fn new_collection_encryption(config: Option<EncryptionConfig>) -> Option<CollectionEncryption> {
+    config.map(|config| CollectionEncryption {
+        readers: config.readers,
+        key_epoch: 0,
+        rotated_at: time(),
+    })
+}
+
+// Admins can always read; readers are listed explicitly
+fn can_read(collection: &Collection, principal: &Principal) -> bool {
+    collection.admins.contains(principal)
+        || collection
+            .encryption
+            .as_ref()
+            .is_some_and(|e| e.readers.contains(principal))
+}
+
+fn encryption_of(collection: &Collection) -> ClanopediaResult<&CollectionEncryption> {
+    collection.encryption.as_ref().ok_or_else(|| {
+        ClanopediaError::InvalidOperation(format!("Collection {} is not encrypted", collection.id))
+    })
+}
+
+// Each collection and key epoch derives a distinct key
+fn derivation_input(collection_id: &str, key_epoch: u32) -> Vec<u8> {
+    let mut input = collection_id.as_bytes().to_vec();
+    input.push(b':');
+    input.extend(key_epoch.to_be_bytes());
+    input
+}
+
+// ============================
+// KEY ACCESS
+// ============================
+
+/// Derive a collection key for a reader, encrypted to their transport key.
+/// Older epochs stay available so documents sealed before a rotation can still be read.
+pub async fn get_encrypted_collection_key(
+    collection_id: &str,
+    caller: Principal,
+    transport_public_key: Vec<u8>,
+    key_epoch: Option<u32>,
+) -> ClanopediaResult<EncryptedCollectionKey> {
+    let collection = storage::get_collection(&collection_id.to_string())?;
+    let encryption = encryption_of(&collection)?;
+
+    if !can_read(&collection, &caller) {
+        return Err(ClanopediaError::NotAuthorized);
+    }
+
+    let key_epoch = key_epoch.unwrap_or(encryption.key_epoch);
+    if key_epoch > encryption.key_epoch {
+        return Err(ClanopediaError::InvalidInput(format!(
+            "Key epoch {} does not exist yet (current: {})",
+            key_epoch, encryption.key_epoch
+        )));
+    }
+
+    let input = derivation_input(collection_id, key_epoch);
+    let encrypted_key =
+        vetkd::derive_encrypted_key(VETKD_CONTEXT.to_vec(), input.clone(), transport_public_key)
+            .await?;
+
+    Ok(EncryptedCollectionKey {
+        encrypted_key,
+        key_epoch,
+        derivation_input: input,
+    })
+}
+
+/// Public verification key clients use to check derived collection keys
+pub async fn get_verification_key() -> ClanopediaResult<Vec<u8>> {
+    vetkd::get_public_key(VETKD_CONTEXT.to_vec()).await
+}
+
+/// Reject plaintext uploads to encrypted collections
+pub fn validate_encrypted_content(collection: &Collection, content: &str) -> ClanopediaResult<()> {
+    let encryption = encryption_of(collection)?;
+
+    let key_epoch = content
+        .strip_prefix(ENCRYPTED_CONTENT_PREFIX)
+        .and_then(|rest| rest.split_once(':'))
+        .and_then(|(epoch, _)| epoch.parse::<u32>().ok())
+        .ok_or_else(|| {
+            ClanopediaError::InvalidInput(format!(
+                "Encrypted collections only accept content sealed as {}<key_epoch>:<ciphertext>",
+                ENCRYPTED_CONTENT_PREFIX
+            ))
+        })?;
+
+    if key_epoch != encryption.key_epoch {
+        return Err(ClanopediaError::InvalidInput(format!(
+            "Content is sealed with key epoch {} but the current epoch is {}",
+            key_epoch, encryption.key_epoch
+        )));
+    }
+    Ok(())
+}
+
+// ============================
+// GOVERNANCE OPERATIONS
+// ============================
+
+pub fn validate_add_reader(collection: &Collection, reader: &Principal) -> ClanopediaResult<()> {
+    if encryption_of(collection)?.readers.contains(reader) {
+        return Err(ClanopediaError::AlreadyExists("Reader already exists".to_string()));
+    }
+    Ok(())
+}
+
+pub fn validate_remove_reader(collection: &Collection, reader: &Principal) -> ClanopediaResult<()> {
+    if !encryption_of(collection)?.readers.contains(reader) {
+        return Err(ClanopediaError::NotFound("Reader not found".to_string()));
+    }
+    Ok(())
+}
+
+pub fn execute_add_reader(collection_id: &str, reader: Principal) -> ClanopediaResult<()> {
+    update_encryption(collection_id, |encryption| {
+        encryption.readers.push(reader);
+    })
+}
+
+/// Removing a reader also rotates the key, so content added afterwards is out of their reach
+pub fn execute_remove_reader(collection_id: &str, reader: Principal) -> ClanopediaResult<()> {
+    update_encryption(collection_id, |encryption| {
+        encryption.readers.retain(|r| r != &reader);
+        rotate(encryption);
+    })
+}
+
+pub fn execute_rotate_key(collection_id: &str) -> ClanopediaResult<()> {
+    update_encryption(collection_id, rotate)
+}
+
+fn rotate(encryption: &mut CollectionEncryption) {
+    encryption.key_epoch += 1;
+    encryption.rotated_at = time();
+}
+
+fn update_encryption(
+    collection_id: &str,
+    update: impl FnOnce(&mut CollectionEncryption),
+) -> ClanopediaResult<()> {
+    let mut collection = storage::get_collection(&collection_id.to_string())?;
+    let encryption = collection.encryption.as_mut().ok_or_else(|| {
+        ClanopediaError::InvalidOperation(format!("Collection {} is not encrypted", collection_id))
+    })?;
+
+    update(encryption);
+    collection.updated_at = time();
+    storage::update_collection(&collection_id.to_string(), &collection)
+}
@@ -0,0 +1,121 @@
+// src/clanopedia_backend/src/execution_receipts.rs
+//
+// A receipt of each attempt to execute a proposal, for post-mortems of failed or disputed
+// executions: who executed it, when, how it ended, and every Blueband, ledger, SNS, NFT and
+// custom call it made, with a hash of the arguments, the result, the cycles spent and how
+// long the call took. Calls are attributed through the execution's future: while it is
+// polled it is the current trace, so calls made by messages that interleave with it at an
+// await are not mixed in. Receipts outlive the proposal and go with the collection.
+
+use std::cell::RefCell;
+use std::future::Future;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::task::{Context, Poll};
+
+use candid::Principal;
+use ic_cdk::api::time;
+
+use crate::{
+    storage,
+    types::{CallTrace, ClanopediaError, ClanopediaResult, ExecutionAttempt, ExecutionReceipt},
+};
+
+// Beyond these, calls are only counted and the oldest attempts are dropped
+const MAX_TRACED_CALLS: usize = 500;
+const MAX_ATTEMPTS: usize = 10;
+
+#[derive(Default)]
+struct CallLog {
+    calls: Vec<CallTrace>,
+    dropped: u32,
+}
+
+thread_local! {
+    static CURRENT: RefCell<Option<Rc<RefCell<CallLog>>>> = const { RefCell::new(None) };
+}
+
+pub fn is_tracing() -> bool {
+    CURRENT.with(|c| c.borrow().is_some())
+}
+
+/// Add a call to the trace of the execution being polled
+pub fn record_call(trace: CallTrace) {
+    CURRENT.with(|c| {
+        if let Some(log) = c.borrow().as_ref() {
+            let mut log = log.borrow_mut();
+            if log.calls.len() < MAX_TRACED_CALLS {
+                log.calls.push(trace);
+            } else {
+                log.dropped += 1;
+            }
+        }
+    });
+}
+
+// Makes `log` the current trace whenever the inner future is polled
+struct Traced<F> {
+    log: Rc<RefCell<CallLog>>,
+    inner: Pin<Box<F>>,
+}
+
+impl<F: Future> Future for Traced<F> {
+    type Output = F::Output;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<F::Output> {
+        let previous = CURRENT.with(|c| c.replace(Some(self.log.clone())));
+        let result = self.inner.as_mut().poll(cx);
+        CURRENT.with(|c| *c.borrow_mut() = previous);
+        result
+    }
+}
+
+/// Run `execution` of a proposal, recording its calls and outcome as an attempt in the
+/// proposal's receipt
+pub async fn trace<T>(
+    collection_id: &str,
+    proposal_id: &str,
+    executor: Principal,
+    execution: impl Future<Output = ClanopediaResult<T>>,
+) -> ClanopediaResult<T> {
+    let started_at = time();
+    let log = Rc::new(RefCell::new(CallLog::default()));
+    let result = Traced {
+        log: log.clone(),
+        inner: Box::pin(execution),
+    }
+    .await;
+
+    let log = log.take();
+    let mut receipt =
+        storage::get_execution_receipt(collection_id, proposal_id).unwrap_or(ExecutionReceipt {
+            collection_id: collection_id.to_string(),
+            proposal_id: proposal_id.to_string(),
+            attempts: Vec::new(),
+        });
+    receipt.attempts.push(ExecutionAttempt {
+        executor,
+        started_at,
+        finished_at: time(),
+        error: result.as_ref().err().map(ToString::to_string),
+        calls: log.calls,
+        calls_dropped: log.dropped,
+    });
+    let excess = receipt.attempts.len().saturating_sub(MAX_ATTEMPTS);
+    receipt.attempts.drain(..excess);
+    storage::put_execution_receipt(collection_id, proposal_id, receipt);
+    result
+}
+
+pub fn get_execution_receipt(
+    collection_id: &str,
+    proposal_id: &str,
+) -> ClanopediaResult<ExecutionReceipt> {
+    storage::get_collection(&collection_id.to_string())?;
+    storage::get_execution_receipt(collection_id, proposal_id).ok_or_else(|| {
+        ClanopediaError::NotFound(format!(
+            "Proposal {} has not been executed in collection {}",
+            proposal_id, collection_id
+        ))
+    })
+}
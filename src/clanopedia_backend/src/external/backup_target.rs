@@ -0,0 +1,151 @@
+// src/clanopedia_backend/src/external/backup_target.rs
+use crate::types::*;
+use candid::{CandidType, Deserialize, Nat, Principal};
+use ic_cdk::call;
+
+// ============================
+// ASSET CANISTER INTERFACE
+// ============================
+
+#[derive(CandidType, Deserialize, Clone, Debug)]
+struct StoreArg {
+    key: String,
+    content_type: String,
+    content_encoding: String,
+    content: Vec<u8>,
+    sha256: Option<Vec<u8>>,
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug)]
+struct GetArg {
+    key: String,
+    accept_encodings: Vec<String>,
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug)]
+struct EncodedAsset {
+    content: Vec<u8>,
+    content_type: String,
+    content_encoding: String,
+    total_length: Nat,
+    sha256: Option<Vec<u8>>,
+}
+
+// ============================
+// BACKUP TARGETS
+// ============================
+
+pub struct BackupService {
+    target: BackupTarget,
+}
+
+impl BackupService {
+    pub fn new(target: BackupTarget) -> Self {
+        Self { target }
+    }
+
+    // Where an asset canister keeps a chunk; chunks of one backup sort in order
+    fn asset_key(source: Principal, backup_id: u64, index: u32) -> String {
+        format!(
+            "/clanopedia-backups/{}/{:020}/{:06}",
+            source, backup_id, index
+        )
+    }
+
+    pub async fn store_chunk(&self, chunk: &BackupChunk) -> Result<(), String> {
+        let result: Result<(), _> = match &self.target {
+            BackupTarget::Canister(canister) => {
+                call(*canister, "store_backup_chunk", (chunk,)).await
+            }
+            BackupTarget::AssetCanister(canister) => {
+                let content = candid::encode_one(chunk).map_err(|e| e.to_string())?;
+                let arg = StoreArg {
+                    key: Self::asset_key(chunk.source, chunk.backup_id, chunk.index),
+                    content_type: "application/octet-stream".to_string(),
+                    content_encoding: "identity".to_string(),
+                    content,
+                    sha256: None,
+                };
+                call(*canister, "store", (arg,)).await
+            }
+        };
+        result.map_err(|(_, e)| format!("Call failed: {}", e))
+    }
+
+    /// Store the content of an archived document under `key`. A backup canister implements
+    /// `store_archived_document(text, blob)`.
+    pub async fn store_document(&self, key: &str, content: Vec<u8>) -> Result<(), String> {
+        let result: Result<(), _> = match &self.target {
+            BackupTarget::Canister(canister) => {
+                call(*canister, "store_archived_document", (key, content)).await
+            }
+            BackupTarget::AssetCanister(canister) => {
+                let arg = StoreArg {
+                    key: key.to_string(),
+                    content_type: "text/plain; charset=utf-8".to_string(),
+                    content_encoding: "identity".to_string(),
+                    content,
+                    sha256: None,
+                };
+                call(*canister, "store", (arg,)).await
+            }
+        };
+        result.map_err(|(_, e)| format!("Call failed: {}", e))
+    }
+
+    /// Read back an archived document. A backup canister implements
+    /// `get_archived_document(text) -> (opt blob)`.
+    pub async fn get_document(&self, key: &str) -> Result<Option<Vec<u8>>, String> {
+        match &self.target {
+            BackupTarget::Canister(canister) => {
+                let result: Result<(Option<Vec<u8>>,), _> =
+                    call(*canister, "get_archived_document", (key,)).await;
+                result
+                    .map(|(content,)| content)
+                    .map_err(|(_, e)| format!("Call failed: {}", e))
+            }
+            BackupTarget::AssetCanister(canister) => {
+                let arg = GetArg {
+                    key: key.to_string(),
+                    accept_encodings: vec!["identity".to_string()],
+                };
+                let result: Result<(EncodedAsset,), _> = call(*canister, "get", (arg,)).await;
+                let (asset,) = result.map_err(|(_, e)| format!("Call failed: {}", e))?;
+                // Content beyond the first chunk would need `get_chunk`
+                if asset.total_length != asset.content.len() {
+                    return Err(format!("Asset {} is stored in more than one chunk", key));
+                }
+                Ok(Some(asset.content))
+            }
+        }
+    }
+
+    pub async fn get_chunk(
+        &self,
+        source: Principal,
+        backup_id: u64,
+        index: u32,
+    ) -> Result<Option<BackupChunk>, String> {
+        match &self.target {
+            BackupTarget::Canister(canister) => {
+                let result: Result<(Option<BackupChunk>,), _> =
+                    call(*canister, "get_backup_chunk", (backup_id, index)).await;
+                result
+                    .map(|(chunk,)| chunk)
+                    .map_err(|(_, e)| format!("Call failed: {}", e))
+            }
+            BackupTarget::AssetCanister(canister) => {
+                let arg = GetArg {
+                    key: Self::asset_key(source, backup_id, index),
+                    accept_encodings: vec!["identity".to_string()],
+                };
+                // The asset canister rejects keys it does not have
+                let result: Result<(EncodedAsset,), _> = call(*canister, "get", (arg,)).await;
+                let (asset,) = result.map_err(|(_, e)| format!("Call failed: {}", e))?;
+                candid::decode_one(&asset.content).map(Some).map_err(|e| {
+                    format!("Chunk {} of backup {} is invalid: {}", index, backup_id, e)
+                })
+            }
+        }
+    }
+}
@@ -1,7 +1,7 @@
 // src/clanopedia_backend/src/external/blueband.rs
 use crate::types::*;
 use candid::{CandidType, Deserialize, Principal};
-use ic_cdk::call;
+use super::traced::call;
 use serde::Serialize;
 use std::result::Result;
 
@@ -12,6 +12,8 @@ use std::result::Result;
 // Generic result type for Blueband operations
 pub type BluebandResult<T> = Result<T, String>;
 
+pub const DEFAULT_PROXY_URL: &str = "https://us-central1-blueband-db-442d8.cloudfunctions.net/proxy";
+
 #[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
 pub struct AddDocumentRequest {
     pub collection_id: CollectionId,
@@ -21,6 +23,10 @@ pub struct AddDocumentRequest {
     pub source_url: Option<String>,
     pub author: Option<String>,
     pub tags: Option<Vec<String>>,
+    pub outline: Option<Vec<OutlineEntry>>, // Kept by Clanopedia; always None when forwarded to Blueband
+    pub summary: Option<String>, // Kept by Clanopedia; always None when forwarded to Blueband
+    pub license: Option<License>, // Kept by Clanopedia; always None when forwarded to Blueband
+    pub provenance: Option<DocumentProvenance>, // Kept by Clanopedia; always None when forwarded to Blueband
 }
 
 #[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
@@ -60,6 +66,7 @@ pub struct CollectionSettings {
     pub auto_embed: bool,
     pub proxy_url: String,
     pub chunk_size: u32,
+    pub proxy_api_key: Option<String>, // Forwarded by the proxy to the embedding provider
 }
 
 #[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
@@ -122,6 +129,9 @@ pub struct VectorMatch {
     pub chunk_id: String,
     pub score: f64,
     pub chunk_text: Option<String>,
+    pub summary: Option<String>, // Filled in by Clanopedia from its metadata overlay
+    pub highlight: Option<SearchHighlight>, // Filled in by Clanopedia from chunk_text
+    pub applied_aliases: Option<Vec<AliasExpansion>>, // Filled in by Clanopedia from the query
 }
 
 #[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
@@ -154,8 +164,108 @@ impl BluebandService {
         Self { canister_id }
     }
 
+    pub async fn find_similar_documents(
+        &self,
+        document_id: String,
+        collection_id: String,
+        limit: Option<u32>,
+        min_score: Option<f64>,
+    ) -> BluebandResult<Vec<VectorMatch>> {
+        let result: Result<(BluebandResult<Vec<VectorMatch>>,), _> = call(
+            self.canister_id,
+            "find_similar_documents",
+            (document_id, collection_id, limit, min_score),
+        )
+        .await;
+
+        match result {
+            Ok((result,)) => result,
+            Err((_, e)) => Err(format!("Call failed: {}", e)),
+        }
+    }
+
+    pub async fn bulk_embed_collection(
+        &self,
+        collection_id: String,
+    ) -> BluebandResult<BulkEmbedResult> {
+        let result: Result<(BluebandResult<BulkEmbedResult>,), _> =
+            call(self.canister_id, "bulk_embed_collection", (collection_id,)).await;
+
+        match result {
+            Ok((result,)) => result,
+            Err((_, e)) => Err(format!("Call failed: {}", e)),
+        }
+    }
+}
+
+/// The Blueband calls Clanopedia makes. `BluebandService` talks to the deployed canister;
+/// builds with the `mock-blueband` feature use an in-memory stand-in instead.
+pub trait BluebandApi {
+    async fn create_collection(
+        &self,
+        request: CreateCollectionRequest,
+    ) -> BluebandResult<Collection>;
+    async fn get_collection(&self, collection_id: String) -> BluebandResult<Option<Collection>>;
+    async fn list_collections(&self) -> BluebandResult<Vec<Collection>>;
+    async fn update_collection_settings(
+        &self,
+        collection_id: String,
+        settings: CollectionSettings,
+    ) -> BluebandResult<()>;
+    async fn add_document(&self, request: AddDocumentRequest) -> BluebandResult<DocumentMetadata>;
+    async fn get_document(
+        &self,
+        collection_id: String,
+        document_id: String,
+    ) -> BluebandResult<Option<DocumentMetadata>>;
+    async fn get_document_content(
+        &self,
+        collection_id: String,
+        document_id: String,
+    ) -> BluebandResult<Option<String>>;
+    async fn embed_existing_document(
+        &self,
+        collection_id: String,
+        document_id: String,
+    ) -> BluebandResult<u32>;
+    async fn get_document_chunks(
+        &self,
+        collection_id: String,
+        document_id: String,
+    ) -> BluebandResult<Vec<SemanticChunk>>;
+    async fn search(&self, request: SearchRequest) -> BluebandResult<Vec<VectorMatch>>;
+    async fn transfer_genesis_admin(
+        &self,
+        collection_id: String,
+        new_admin: String,
+    ) -> BluebandResult<()>;
+    async fn add_collection_admin(
+        &self,
+        collection_id: String,
+        admin: String,
+    ) -> BluebandResult<()>;
+    async fn remove_collection_admin(
+        &self,
+        collection_id: String,
+        admin: String,
+    ) -> BluebandResult<()>;
+    async fn delete_document(
+        &self,
+        collection_id: String,
+        document_id: String,
+    ) -> BluebandResult<()>;
+    async fn delete_collection(&self, collection_id: String) -> BluebandResult<()>;
+    async fn get_canister_cycles(&self) -> u64;
+    async fn wallet_receive(&self) -> u64;
+    async fn get_collection_metrics(
+        &self,
+        collection_id: String,
+    ) -> BluebandResult<CollectionMetrics>;
+}
+
+impl BluebandApi for BluebandService {
     // Collection operations
-    pub async fn create_collection(
+    async fn create_collection(
         &self,
         request: CreateCollectionRequest,
     ) -> BluebandResult<Collection> {
@@ -168,10 +278,7 @@ impl BluebandService {
         }
     }
 
-    pub async fn get_collection(
-        &self,
-        collection_id: String,
-    ) -> BluebandResult<Option<Collection>> {
+    async fn get_collection(&self, collection_id: String) -> BluebandResult<Option<Collection>> {
         let result: Result<(BluebandResult<Option<Collection>>,), _> =
             call(self.canister_id, "get_collection", (collection_id,)).await;
 
@@ -181,11 +288,36 @@ impl BluebandService {
         }
     }
 
-    // Document operations
-    pub async fn add_document(
+    async fn list_collections(&self) -> BluebandResult<Vec<Collection>> {
+        let result: Result<(Vec<Collection>,), _> =
+            call(self.canister_id, "list_collections", ()).await;
+
+        match result {
+            Ok((collections,)) => Ok(collections),
+            Err((_, e)) => Err(format!("Call failed: {}", e)),
+        }
+    }
+
+    async fn update_collection_settings(
         &self,
-        request: AddDocumentRequest,
-    ) -> BluebandResult<DocumentMetadata> {
+        collection_id: String,
+        settings: CollectionSettings,
+    ) -> BluebandResult<()> {
+        let result: Result<(BluebandResult<()>,), _> = call(
+            self.canister_id,
+            "update_collection_settings",
+            (collection_id, settings),
+        )
+        .await;
+
+        match result {
+            Ok((result,)) => result,
+            Err((_, e)) => Err(format!("Call failed: {}", e)),
+        }
+    }
+
+    // Document operations
+    async fn add_document(&self, request: AddDocumentRequest) -> BluebandResult<DocumentMetadata> {
         let result: Result<(BluebandResult<DocumentMetadata>,), _> =
             call(self.canister_id, "add_document", (request,)).await;
 
@@ -195,7 +327,7 @@ impl BluebandService {
         }
     }
 
-    pub async fn get_document(
+    async fn get_document(
         &self,
         collection_id: String,
         document_id: String,
@@ -213,7 +345,7 @@ impl BluebandService {
         }
     }
 
-    pub async fn get_document_content(
+    async fn get_document_content(
         &self,
         collection_id: String,
         document_id: String,
@@ -231,7 +363,7 @@ impl BluebandService {
         }
     }
 
-    pub async fn embed_existing_document(
+    async fn embed_existing_document(
         &self,
         collection_id: String,
         document_id: String,
@@ -249,10 +381,17 @@ impl BluebandService {
         }
     }
 
-    // Search operations
-    pub async fn search(&self, request: SearchRequest) -> BluebandResult<Vec<VectorMatch>> {
-        let result: Result<(BluebandResult<Vec<VectorMatch>>,), _> =
-            call(self.canister_id, "search", (request,)).await;
+    async fn get_document_chunks(
+        &self,
+        collection_id: String,
+        document_id: String,
+    ) -> BluebandResult<Vec<SemanticChunk>> {
+        let result: Result<(BluebandResult<Vec<SemanticChunk>>,), _> = call(
+            self.canister_id,
+            "get_document_chunks",
+            (collection_id, document_id),
+        )
+        .await;
 
         match result {
             Ok((result,)) => result,
@@ -260,19 +399,10 @@ impl BluebandService {
         }
     }
 
-    pub async fn find_similar_documents(
-        &self,
-        document_id: String,
-        collection_id: String,
-        limit: Option<u32>,
-        min_score: Option<f64>,
-    ) -> BluebandResult<Vec<VectorMatch>> {
-        let result: Result<(BluebandResult<Vec<VectorMatch>>,), _> = call(
-            self.canister_id,
-            "find_similar_documents",
-            (document_id, collection_id, limit, min_score),
-        )
-        .await;
+    // Search operations
+    async fn search(&self, request: SearchRequest) -> BluebandResult<Vec<VectorMatch>> {
+        let result: Result<(BluebandResult<Vec<VectorMatch>>,), _> =
+            call(self.canister_id, "search", (request,)).await;
 
         match result {
             Ok((result,)) => result,
@@ -280,16 +410,15 @@ impl BluebandService {
         }
     }
 
-    // Admin operations
-    pub async fn add_collection_admin(
+    async fn transfer_genesis_admin(
         &self,
         collection_id: String,
-        admin: String,
+        new_admin: String,
     ) -> BluebandResult<()> {
         let result: Result<(BluebandResult<()>,), _> = call(
             self.canister_id,
-            "add_collection_admin",
-            (collection_id, admin),
+            "transfer_genesis_admin",
+            (collection_id, new_admin),
         )
         .await;
 
@@ -299,14 +428,14 @@ impl BluebandService {
         }
     }
 
-    pub async fn remove_collection_admin(
+    async fn add_collection_admin(
         &self,
         collection_id: String,
         admin: String,
     ) -> BluebandResult<()> {
         let result: Result<(BluebandResult<()>,), _> = call(
             self.canister_id,
-            "remove_collection_admin",
+            "add_collection_admin",
             (collection_id, admin),
         )
         .await;
@@ -317,15 +446,15 @@ impl BluebandService {
         }
     }
 
-    pub async fn transfer_genesis_admin(
+    async fn remove_collection_admin(
         &self,
         collection_id: String,
-        new_admin: String,
+        admin: String,
     ) -> BluebandResult<()> {
         let result: Result<(BluebandResult<()>,), _> = call(
             self.canister_id,
-            "transfer_genesis_admin",
-            (collection_id, new_admin),
+            "remove_collection_admin",
+            (collection_id, admin),
         )
         .await;
 
@@ -335,7 +464,7 @@ impl BluebandService {
         }
     }
 
-    pub async fn delete_document(
+    async fn delete_document(
         &self,
         collection_id: String,
         document_id: String,
@@ -353,7 +482,7 @@ impl BluebandService {
         }
     }
 
-    pub async fn delete_collection(&self, collection_id: String) -> BluebandResult<()> {
+    async fn delete_collection(&self, collection_id: String) -> BluebandResult<()> {
         let result: Result<(BluebandResult<()>,), _> =
             call(self.canister_id, "delete_collection", (collection_id,)).await;
 
@@ -364,7 +493,7 @@ impl BluebandService {
     }
 
     // Cycles and stats
-    pub async fn get_canister_cycles(&self) -> u64 {
+    async fn get_canister_cycles(&self) -> u64 {
         let result: Result<(u64,), _> = call(self.canister_id, "get_canister_cycles", ()).await;
 
         match result {
@@ -373,7 +502,7 @@ impl BluebandService {
         }
     }
 
-    pub async fn wallet_receive(&self) -> u64 {
+    async fn wallet_receive(&self) -> u64 {
         let result: Result<(u64,), _> = call(self.canister_id, "wallet_receive", ()).await;
 
         match result {
@@ -382,21 +511,7 @@ impl BluebandService {
         }
     }
 
-    // Bulk operations
-    pub async fn bulk_embed_collection(
-        &self,
-        collection_id: String,
-    ) -> BluebandResult<BulkEmbedResult> {
-        let result: Result<(BluebandResult<BulkEmbedResult>,), _> =
-            call(self.canister_id, "bulk_embed_collection", (collection_id,)).await;
-
-        match result {
-            Ok((result,)) => result,
-            Err((_, e)) => Err(format!("Call failed: {}", e)),
-        }
-    }
-
-    pub async fn get_collection_metrics(
+    async fn get_collection_metrics(
         &self,
         collection_id: String,
     ) -> BluebandResult<CollectionMetrics> {
@@ -414,20 +529,41 @@ impl BluebandService {
 // BLUEBAND CLIENT FUNCTIONS
 // ============================
 
-// Get Blueband canister ID from global state
-fn get_blueband_canister() -> ClanopediaResult<Principal> {
-    super::super::get_blueband_canister_id()
+// Client for the Blueband canister ID held in global state
+#[cfg(not(feature = "mock-blueband"))]
+fn client() -> BluebandResult<BluebandService> {
+    let blueband_canister = super::super::get_blueband_canister_id()
+        .map_err(|e| format!("Blueband canister not configured: {:?}", e))?;
+    Ok(BluebandService::new(blueband_canister))
+}
+
+#[cfg(feature = "mock-blueband")]
+fn client() -> BluebandResult<super::mock_blueband::MockBlueband> {
+    Ok(super::mock_blueband::MockBlueband)
+}
+
+// Health check for a Blueband canister that is not configured yet
+#[cfg(not(feature = "mock-blueband"))]
+pub async fn check_canister(canister_id: Principal) -> BluebandResult<()> {
+    let result: Result<(u64,), _> = call(canister_id, "get_canister_cycles", ()).await;
+    result
+        .map(|_| ())
+        .map_err(|(_, e)| format!("Call failed: {}", e))
+}
+
+#[cfg(feature = "mock-blueband")]
+pub async fn check_canister(_canister_id: Principal) -> BluebandResult<()> {
+    Ok(())
 }
 
 pub async fn create_blueband_collection(
     collection_id: String,
     name: String,
     description: String,
+    proxy_url: Option<String>,
+    proxy_api_key: Option<String>,
 ) -> BluebandResult<Collection> {
-    let blueband_canister = get_blueband_canister()
-        .map_err(|e| format!("Blueband canister not configured: {:?}", e))?;
-
-    let service = BluebandService::new(blueband_canister);
+    let service = client()?;
 
     let request = CreateCollectionRequest {
         id: collection_id,
@@ -438,22 +574,42 @@ pub async fn create_blueband_collection(
             max_documents: None,
             embedding_model: "text-embedding-3-small".to_string(),
             auto_embed: true,
-            proxy_url: "https://us-central1-blueband-db-442d8.cloudfunctions.net/proxy".to_string(),
+            proxy_url: proxy_url.unwrap_or_else(|| DEFAULT_PROXY_URL.to_string()),
             chunk_size: 300,
+            proxy_api_key,
         }),
     };
 
     service.create_collection(request).await
 }
 
+// Point an existing collection at a different embedding proxy, keeping its other settings
+pub async fn update_collection_proxy(
+    collection_id: &str,
+    proxy_url: Option<String>,
+    proxy_api_key: Option<String>,
+) -> BluebandResult<()> {
+    let service = client()?;
+
+    let collection = service
+        .get_collection(collection_id.to_string())
+        .await?
+        .ok_or_else(|| format!("Blueband collection {} not found", collection_id))?;
+
+    let mut settings = collection.settings;
+    settings.proxy_url = proxy_url.unwrap_or_else(|| DEFAULT_PROXY_URL.to_string());
+    settings.proxy_api_key = proxy_api_key;
+
+    service
+        .update_collection_settings(collection_id.to_string(), settings)
+        .await
+}
+
 pub async fn add_document_to_blueband(
     collection_id: &str,
     document: DocumentRequest,
 ) -> BluebandResult<DocumentMetadata> {
-    let blueband_canister = get_blueband_canister()
-        .map_err(|e| format!("Blueband canister not configured: {:?}", e))?;
-
-    let service = BluebandService::new(blueband_canister);
+    let service = client()?;
 
     let request = AddDocumentRequest {
         title: document.title,
@@ -463,6 +619,10 @@ pub async fn add_document_to_blueband(
         source_url: document.source_url,
         author: document.author,
         tags: document.tags,
+        outline: None,
+        summary: None,
+        license: None,
+        provenance: None,
     };
 
     service.add_document(request).await
@@ -472,44 +632,43 @@ pub async fn embed_existing_document(
     collection_id: &str,
     document_id: &str,
 ) -> BluebandResult<u32> {
-    let blueband_canister = get_blueband_canister()
-        .map_err(|e| format!("Blueband canister not configured: {:?}", e))?;
-
-    let service = BluebandService::new(blueband_canister);
+    let service = client()?;
     service
         .embed_existing_document(collection_id.to_string(), document_id.to_string())
         .await
 }
 
 pub async fn delete_document(collection_id: &str, document_id: &str) -> BluebandResult<()> {
-    let blueband_canister = get_blueband_canister().map_err(|e| format!("{:?}", e))?;
-    let service = BluebandService::new(blueband_canister);
+    let service = client()?;
     service
         .delete_document(collection_id.to_string(), document_id.to_string())
         .await
 }
 
 pub async fn delete_collection(collection_id: &str) -> BluebandResult<()> {
-    let blueband_canister = get_blueband_canister().map_err(|e| format!("{:?}", e))?;
-    let service = BluebandService::new(blueband_canister);
+    let service = client()?;
     service.delete_collection(collection_id.to_string()).await
 }
 
+pub async fn get_blueband_collection(collection_id: &str) -> BluebandResult<Option<Collection>> {
+    let service = client()?;
+    service.get_collection(collection_id.to_string()).await
+}
+
+pub async fn list_blueband_collections() -> BluebandResult<Vec<Collection>> {
+    let service = client()?;
+    service.list_collections().await
+}
+
 pub async fn get_blueband_cycles_balance() -> u64 {
-    match get_blueband_canister() {
-        Ok(blueband_canister) => {
-            let service = BluebandService::new(blueband_canister);
-            service.get_canister_cycles().await
-        }
+    match client() {
+        Ok(service) => service.get_canister_cycles().await,
         Err(_) => 0,
     }
 }
 
 pub async fn fund_blueband_cycles(_cycles_amount: u64) -> BluebandResult<u64> {
-    let blueband_canister = get_blueband_canister()
-        .map_err(|e| format!("Blueband canister not configured: {:?}", e))?;
-
-    let service = BluebandService::new(blueband_canister);
+    let service = client()?;
     Ok(service.wallet_receive().await)
 }
 
@@ -518,9 +677,7 @@ pub async fn get_document_content_from_blueband(
     collection_id: &str,
     document_id: &str,
 ) -> BluebandResult<Option<String>> {
-    let blueband_canister = get_blueband_canister()
-        .map_err(|e| format!("Blueband canister not configured: {:?}", e))?;
-    let service = BluebandService::new(blueband_canister);
+    let service = client()?;
     service
         .get_document_content(collection_id.to_string(), document_id.to_string())
         .await
@@ -530,26 +687,70 @@ pub async fn get_document_metadata(
     collection_id: String,
     document_id: String,
 ) -> BluebandResult<Option<DocumentMetadata>> {
-    let blueband_canister = get_blueband_canister().map_err(|e| format!("{:?}", e))?;
-    let service = BluebandService::new(blueband_canister);
+    let service = client()?;
     service.get_document(collection_id, document_id).await
 }
 
+pub async fn get_document_chunks(
+    collection_id: &str,
+    document_id: &str,
+) -> BluebandResult<Vec<SemanticChunk>> {
+    let service = client()?;
+    service
+        .get_document_chunks(collection_id.to_string(), document_id.to_string())
+        .await
+}
+
 pub async fn transfer_genesis_admin(
     collection_id: &str,
     new_admin: candid::Principal,
 ) -> BluebandResult<()> {
-    let blueband_canister = get_blueband_canister()
-        .map_err(|e| format!("Blueband canister not configured: {:?}", e))?;
-    let service = BluebandService::new(blueband_canister);
+    let service = client()?;
     service
         .transfer_genesis_admin(collection_id.to_string(), new_admin.to_string())
         .await
 }
 
+pub async fn add_collection_admin(
+    collection_id: &str,
+    admin: candid::Principal,
+) -> BluebandResult<()> {
+    let service = client()?;
+    service
+        .add_collection_admin(collection_id.to_string(), admin.to_string())
+        .await
+}
+
+pub async fn remove_collection_admin(
+    collection_id: &str,
+    admin: candid::Principal,
+) -> BluebandResult<()> {
+    let service = client()?;
+    service
+        .remove_collection_admin(collection_id.to_string(), admin.to_string())
+        .await
+}
+
 pub async fn get_collection_metrics(collection_id: &str) -> BluebandResult<CollectionMetrics> {
-    let blueband_canister = get_blueband_canister()
-        .map_err(|e| format!("Blueband canister not configured: {:?}", e))?;
-    let service = BluebandService::new(blueband_canister);
-    service.get_collection_metrics(collection_id.to_string()).await
+    let service = client()?;
+    service
+        .get_collection_metrics(collection_id.to_string())
+        .await
+}
+
+pub async fn search_collection(
+    collection_id: &str,
+    query: String,
+    limit: Option<u32>,
+) -> BluebandResult<Vec<VectorMatch>> {
+    let service = client()?;
+    service
+        .search(SearchRequest {
+            collection_id: collection_id.to_string(),
+            query,
+            limit,
+            filter: None,
+            min_score: None,
+        })
+        .await
 }
@@ -0,0 +1,197 @@
+// src/clanopedia_backend/src/external/fakes.rs
+//
+// In-memory ICRC ledgers and SNS governance for `mock-services` builds and unit tests. Each
+// fake is keyed by the canister ID it stands in for, so collections pointing at different
+// ledgers stay separate. Allowances are not modelled: `icrc2_transfer_from` only checks the
+// balance. State lives on the heap and is lost on upgrade.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use candid::{Nat, Principal};
+use icrc_ledger_types::{
+    icrc1::account::Account,
+    icrc1::transfer::{TransferArg, TransferError},
+    icrc2::transfer_from::{TransferFromArgs, TransferFromError},
+};
+
+use super::sns_integration::{
+    ListNeurons, ListNeuronsResponse, SnsApi, SnsNeuron, SnsProposalData, SnsTally,
+};
+use super::token::{TokenApi, TokenResult, TransferFromResult};
+use crate::types::ClanopediaResult;
+
+const DEFAULT_FEE: u64 = 10_000;
+
+#[derive(Default)]
+struct LedgerState {
+    balances: HashMap<Account, Nat>,
+    blocks: u64,
+}
+
+#[derive(Default)]
+struct SnsState {
+    proposals: HashMap<u64, SnsProposalData>,
+    neurons: HashMap<Principal, Vec<SnsNeuron>>,
+}
+
+thread_local! {
+    static LEDGERS: RefCell<HashMap<Principal, LedgerState>> = RefCell::new(HashMap::new());
+    static SNS: RefCell<HashMap<Principal, SnsState>> = RefCell::new(HashMap::new());
+}
+
+// ============================
+// LEDGER
+// ============================
+
+pub struct FakeLedger {
+    canister_id: Principal,
+}
+
+impl FakeLedger {
+    pub fn new(canister_id: Principal) -> Self {
+        Self { canister_id }
+    }
+
+    fn with_state<R>(&self, f: impl FnOnce(&mut LedgerState) -> R) -> R {
+        LEDGERS.with(|l| f(l.borrow_mut().entry(self.canister_id).or_default()))
+    }
+}
+
+pub fn set_balance(ledger: Principal, owner: Principal, amount: Nat) {
+    FakeLedger::new(ledger).with_state(|s| {
+        s.balances.insert(Account::from(owner), amount);
+    });
+}
+
+// Move `amount` plus the fee out of `from`; the fee is burned
+fn debit_and_credit(
+    state: &mut LedgerState,
+    from: Account,
+    to: Account,
+    amount: Nat,
+) -> Result<Nat, Nat> {
+    let balance = state.balances.get(&from).cloned().unwrap_or_default();
+    let debit = amount.clone() + Nat::from(DEFAULT_FEE);
+    if balance < debit {
+        return Err(balance);
+    }
+    state.balances.insert(from, balance - debit);
+    *state.balances.entry(to).or_default() += amount;
+    state.blocks += 1;
+    Ok(Nat::from(state.blocks))
+}
+
+impl TokenApi for FakeLedger {
+    async fn icrc1_balance_of(&self, account: Account) -> TokenResult<Nat> {
+        Ok(self.with_state(|s| s.balances.get(&account).cloned().unwrap_or_default()))
+    }
+
+    async fn icrc1_total_supply(&self) -> TokenResult<Nat> {
+        Ok(self.with_state(|s| {
+            s.balances
+                .values()
+                .fold(Nat::from(0u64), |acc, balance| acc + balance.clone())
+        }))
+    }
+
+    async fn icrc1_fee(&self) -> TokenResult<Nat> {
+        Ok(Nat::from(DEFAULT_FEE))
+    }
+
+    async fn icrc1_transfer(&self, transfer_arg: TransferArg) -> TokenResult<Nat> {
+        self.with_state(|s| {
+            let from = Account {
+                owner: ic_cdk::api::id(),
+                subaccount: transfer_arg.from_subaccount,
+            };
+            debit_and_credit(s, from, transfer_arg.to, transfer_arg.amount)
+            .map_err(|balance| TransferError::InsufficientFunds { balance })
+        })
+    }
+
+    async fn icrc2_transfer_from(&self, args: TransferFromArgs) -> TransferFromResult<Nat> {
+        self.with_state(|s| {
+            debit_and_credit(s, args.from, args.to, args.amount)
+                .map_err(|balance| TransferFromError::InsufficientFunds { balance })
+        })
+    }
+}
+
+// ============================
+// SNS GOVERNANCE
+// ============================
+
+pub struct FakeSnsGovernance {
+    canister_id: Principal,
+}
+
+impl FakeSnsGovernance {
+    pub fn new(canister_id: Principal) -> Self {
+        Self { canister_id }
+    }
+
+    fn with_state<R>(&self, f: impl FnOnce(&mut SnsState) -> R) -> R {
+        SNS.with(|s| f(s.borrow_mut().entry(self.canister_id).or_default()))
+    }
+}
+
+/// Record an SNS proposal decided at `decided_at_seconds` with the given tally; it counts as
+/// adopted when `yes > no`
+pub fn set_proposal_tally(
+    sns_governance: Principal,
+    proposal_id: u64,
+    yes: u64,
+    no: u64,
+    decided_at_seconds: u64,
+) {
+    let proposal = SnsProposalData {
+        id: Some(proposal_id),
+        proposer: None,
+        reject_cost_e8s: 0,
+        proposal: None,
+        ballots: Vec::new(),
+        initial_voting_period: 0,
+        current_voting_period: 0,
+        decided_timestamp_seconds: decided_at_seconds,
+        executed_timestamp_seconds: 0,
+        failed_timestamp_seconds: 0,
+        failure_reason: None,
+        latest_tally: Some(SnsTally {
+            yes,
+            no,
+            total: yes + no,
+            timestamp_seconds: decided_at_seconds,
+        }),
+        reward_event_round: 0,
+        is_eligible_for_rewards: false,
+    };
+    FakeSnsGovernance::new(sns_governance).with_state(|s| {
+        s.proposals.insert(proposal_id, proposal);
+    });
+}
+
+pub fn set_neuron_stake(sns_governance: Principal, owner: Principal, stake_e8s: u64) {
+    let neuron = SnsNeuron {
+        id: None,
+        cached_neuron_stake_e8s: stake_e8s,
+        neuron_fees_e8s: 0,
+    };
+    FakeSnsGovernance::new(sns_governance).with_state(|s| {
+        s.neurons.insert(owner, vec![neuron]);
+    });
+}
+
+impl SnsApi for FakeSnsGovernance {
+    async fn get_proposal(&self, proposal_id: u64) -> ClanopediaResult<Option<SnsProposalData>> {
+        Ok(self.with_state(|s| s.proposals.get(&proposal_id).cloned()))
+    }
+
+    async fn list_neurons(&self, request: ListNeurons) -> ClanopediaResult<ListNeuronsResponse> {
+        let neurons = request
+            .of_principal
+            .and_then(|owner| self.with_state(|s| s.neurons.get(&owner).cloned()))
+            .unwrap_or_default();
+        Ok(ListNeuronsResponse { neurons })
+    }
+}
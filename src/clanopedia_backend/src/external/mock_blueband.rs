@@ -0,0 +1,396 @@
+// src/clanopedia_backend/src/external/mock_blueband.rs
+//
+// In-memory Blueband used by `mock-blueband` builds, so integration tests can run Clanopedia
+// without deploying Blueband. Embedding is simulated: every document is one chunk and search
+// scores by the share of query terms a document contains. State lives on the heap and is
+// lost on upgrade.
+
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+
+use ic_cdk::api::time;
+use sha2::{Digest, Sha256};
+
+use super::blueband::{
+    AddDocumentRequest, BluebandApi, BluebandResult, Collection, CollectionMetrics,
+    CollectionSettings, ContentType, CreateCollectionRequest, DocumentMetadata, SearchRequest,
+    SemanticChunk, VectorMatch, DEFAULT_PROXY_URL,
+};
+
+const DEFAULT_SEARCH_LIMIT: usize = 10;
+const MOCK_CYCLES: u64 = 1_000_000_000_000;
+
+struct StoredDocument {
+    metadata: DocumentMetadata,
+    content: String,
+}
+
+#[derive(Default)]
+struct MockState {
+    collections: BTreeMap<String, Collection>,
+    documents: BTreeMap<(String, String), StoredDocument>, // (collection_id, document_id)
+    searches: HashMap<String, u64>,
+    next_document: u64,
+}
+
+thread_local! {
+    static STATE: RefCell<MockState> = RefCell::new(MockState::default());
+}
+
+pub struct MockBlueband;
+
+fn missing_collection(collection_id: &str) -> String {
+    format!("Collection {} not found", collection_id)
+}
+
+fn default_settings() -> CollectionSettings {
+    CollectionSettings {
+        chunk_overlap: 25,
+        max_documents: None,
+        embedding_model: "mock".to_string(),
+        auto_embed: true,
+        proxy_url: DEFAULT_PROXY_URL.to_string(),
+        chunk_size: 300,
+        proxy_api_key: None,
+    }
+}
+
+fn terms(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|t| !t.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+fn score(query_terms: &[String], document: &StoredDocument) -> f64 {
+    if query_terms.is_empty() {
+        return 0.0;
+    }
+    let text = format!("{} {}", document.metadata.title, document.content);
+    let document_terms = terms(&text);
+    let hits = query_terms
+        .iter()
+        .filter(|t| document_terms.contains(t))
+        .count();
+    hits as f64 / query_terms.len() as f64
+}
+
+fn vector_match(document: &StoredDocument, score: f64) -> VectorMatch {
+    VectorMatch {
+        document_id: document.metadata.id.clone(),
+        document_title: Some(document.metadata.title.clone()),
+        chunk_id: format!("{}_chunk_0", document.metadata.id),
+        score,
+        chunk_text: Some(document.content.clone()),
+        summary: None,
+        highlight: None,
+        applied_aliases: None,
+    }
+}
+
+fn rank(
+    state: &MockState,
+    collection_id: &str,
+    query_terms: &[String],
+    limit: usize,
+    min_score: f64,
+) -> Vec<VectorMatch> {
+    let mut matches: Vec<VectorMatch> = state
+        .documents
+        .iter()
+        .filter(|((c, _), doc)| c == collection_id && doc.metadata.is_embedded)
+        .map(|(_, doc)| (doc, score(query_terms, doc)))
+        .filter(|(_, score)| *score > 0.0 && *score >= min_score)
+        .map(|(doc, score)| vector_match(doc, score))
+        .collect();
+    matches.sort_by(|a, b| {
+        b.score
+            .total_cmp(&a.score)
+            .then_with(|| a.document_id.cmp(&b.document_id))
+    });
+    matches.truncate(limit);
+    matches
+}
+
+impl BluebandApi for MockBlueband {
+    async fn create_collection(
+        &self,
+        request: CreateCollectionRequest,
+    ) -> BluebandResult<Collection> {
+        STATE.with(|s| {
+            let mut s = s.borrow_mut();
+            if s.collections.contains_key(&request.id) {
+                return Err(format!("Collection {} already exists", request.id));
+            }
+            // Blueband sees the Clanopedia canister as the caller
+            let caller = ic_cdk::id().to_string();
+            let now = time();
+            let collection = Collection {
+                id: request.id.clone(),
+                updated_at: now,
+                genesis_admin: caller.clone(),
+                name: request.name,
+                description: request.description,
+                created_at: now,
+                settings: request.settings.unwrap_or_else(default_settings),
+                admins: vec![caller],
+            };
+            s.collections.insert(request.id, collection.clone());
+            Ok(collection)
+        })
+    }
+
+    async fn get_collection(&self, collection_id: String) -> BluebandResult<Option<Collection>> {
+        Ok(STATE.with(|s| s.borrow().collections.get(&collection_id).cloned()))
+    }
+
+    async fn list_collections(&self) -> BluebandResult<Vec<Collection>> {
+        Ok(STATE.with(|s| s.borrow().collections.values().cloned().collect()))
+    }
+
+    async fn update_collection_settings(
+        &self,
+        collection_id: String,
+        settings: CollectionSettings,
+    ) -> BluebandResult<()> {
+        STATE.with(|s| {
+            let mut s = s.borrow_mut();
+            let collection = s
+                .collections
+                .get_mut(&collection_id)
+                .ok_or_else(|| missing_collection(&collection_id))?;
+            collection.settings = settings;
+            collection.updated_at = time();
+            Ok(())
+        })
+    }
+
+    async fn add_document(&self, request: AddDocumentRequest) -> BluebandResult<DocumentMetadata> {
+        STATE.with(|s| {
+            let mut s = s.borrow_mut();
+            let auto_embed = s
+                .collections
+                .get(&request.collection_id)
+                .ok_or_else(|| missing_collection(&request.collection_id))?
+                .settings
+                .auto_embed;
+
+            s.next_document += 1;
+            let metadata = DocumentMetadata {
+                id: format!("doc_{}", s.next_document),
+                total_chunks: 1,
+                title: request.title,
+                size: request.content.len() as u64,
+                content_type: request.content_type.unwrap_or(ContentType::PlainText),
+                collection_id: request.collection_id.clone(),
+                is_embedded: auto_embed,
+                source_url: request.source_url,
+                timestamp: time(),
+                checksum: hex::encode(Sha256::digest(request.content.as_bytes())),
+            };
+            s.documents.insert(
+                (request.collection_id, metadata.id.clone()),
+                StoredDocument {
+                    metadata: metadata.clone(),
+                    content: request.content,
+                },
+            );
+            Ok(metadata)
+        })
+    }
+
+    async fn get_document(
+        &self,
+        collection_id: String,
+        document_id: String,
+    ) -> BluebandResult<Option<DocumentMetadata>> {
+        Ok(STATE.with(|s| {
+            s.borrow()
+                .documents
+                .get(&(collection_id, document_id))
+                .map(|d| d.metadata.clone())
+        }))
+    }
+
+    async fn get_document_content(
+        &self,
+        collection_id: String,
+        document_id: String,
+    ) -> BluebandResult<Option<String>> {
+        Ok(STATE.with(|s| {
+            s.borrow()
+                .documents
+                .get(&(collection_id, document_id))
+                .map(|d| d.content.clone())
+        }))
+    }
+
+    async fn embed_existing_document(
+        &self,
+        collection_id: String,
+        document_id: String,
+    ) -> BluebandResult<u32> {
+        STATE.with(|s| {
+            let mut s = s.borrow_mut();
+            let document = s
+                .documents
+                .get_mut(&(collection_id, document_id.clone()))
+                .ok_or_else(|| format!("Document {} not found", document_id))?;
+            document.metadata.is_embedded = true;
+            Ok(document.metadata.total_chunks)
+        })
+    }
+
+    async fn get_document_chunks(
+        &self,
+        collection_id: String,
+        document_id: String,
+    ) -> BluebandResult<Vec<SemanticChunk>> {
+        STATE.with(|s| {
+            let s = s.borrow();
+            let document = s
+                .documents
+                .get(&(collection_id, document_id.clone()))
+                .ok_or_else(|| format!("Document {} not found", document_id))?;
+            Ok(vec![SemanticChunk {
+                id: format!("{}_chunk_0", document_id),
+                document_id,
+                text: document.content.clone(),
+                token_count: None,
+                char_start: 0,
+                char_end: document.content.chars().count() as u64,
+                position: 0,
+            }])
+        })
+    }
+
+    async fn search(&self, request: SearchRequest) -> BluebandResult<Vec<VectorMatch>> {
+        STATE.with(|s| {
+            let mut s = s.borrow_mut();
+            if !s.collections.contains_key(&request.collection_id) {
+                return Err(missing_collection(&request.collection_id));
+            }
+            *s.searches.entry(request.collection_id.clone()).or_default() += 1;
+            let limit = request
+                .limit
+                .map_or(DEFAULT_SEARCH_LIMIT, |limit| limit as usize);
+            Ok(rank(
+                &s,
+                &request.collection_id,
+                &terms(&request.query),
+                limit,
+                request.min_score.unwrap_or(0.0),
+            ))
+        })
+    }
+
+    async fn transfer_genesis_admin(
+        &self,
+        collection_id: String,
+        new_admin: String,
+    ) -> BluebandResult<()> {
+        STATE.with(|s| {
+            let mut s = s.borrow_mut();
+            let collection = s
+                .collections
+                .get_mut(&collection_id)
+                .ok_or_else(|| missing_collection(&collection_id))?;
+            if !collection.admins.contains(&new_admin) {
+                collection.admins.push(new_admin.clone());
+            }
+            collection.genesis_admin = new_admin;
+            Ok(())
+        })
+    }
+
+    async fn add_collection_admin(
+        &self,
+        collection_id: String,
+        admin: String,
+    ) -> BluebandResult<()> {
+        STATE.with(|s| {
+            let mut s = s.borrow_mut();
+            let collection = s
+                .collections
+                .get_mut(&collection_id)
+                .ok_or_else(|| missing_collection(&collection_id))?;
+            if !collection.admins.contains(&admin) {
+                collection.admins.push(admin);
+            }
+            Ok(())
+        })
+    }
+
+    async fn remove_collection_admin(
+        &self,
+        collection_id: String,
+        admin: String,
+    ) -> BluebandResult<()> {
+        STATE.with(|s| {
+            let mut s = s.borrow_mut();
+            let collection = s
+                .collections
+                .get_mut(&collection_id)
+                .ok_or_else(|| missing_collection(&collection_id))?;
+            if collection.genesis_admin == admin {
+                return Err("Cannot remove the genesis admin".to_string());
+            }
+            collection.admins.retain(|a| *a != admin);
+            Ok(())
+        })
+    }
+
+    async fn delete_document(
+        &self,
+        collection_id: String,
+        document_id: String,
+    ) -> BluebandResult<()> {
+        STATE.with(|s| {
+            s.borrow_mut()
+                .documents
+                .remove(&(collection_id, document_id.clone()))
+                .map(|_| ())
+                .ok_or_else(|| format!("Document {} not found", document_id))
+        })
+    }
+
+    async fn delete_collection(&self, collection_id: String) -> BluebandResult<()> {
+        STATE.with(|s| {
+            let mut s = s.borrow_mut();
+            s.collections
+                .remove(&collection_id)
+                .ok_or_else(|| missing_collection(&collection_id))?;
+            s.documents.retain(|(c, _), _| *c != collection_id);
+            s.searches.remove(&collection_id);
+            Ok(())
+        })
+    }
+
+    async fn get_canister_cycles(&self) -> u64 {
+        MOCK_CYCLES
+    }
+
+    async fn wallet_receive(&self) -> u64 {
+        0
+    }
+
+    async fn get_collection_metrics(
+        &self,
+        collection_id: String,
+    ) -> BluebandResult<CollectionMetrics> {
+        STATE.with(|s| {
+            let s = s.borrow();
+            if !s.collections.contains_key(&collection_id) {
+                return Err(missing_collection(&collection_id));
+            }
+            Ok(CollectionMetrics {
+                document_count: s
+                    .documents
+                    .keys()
+                    .filter(|(c, _)| *c == collection_id)
+                    .count() as u64,
+                search_count: s.searches.get(&collection_id).copied().unwrap_or(0),
+            })
+        })
+    }
+}
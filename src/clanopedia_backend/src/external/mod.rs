@@ -1,7 +1,15 @@
 // src/clanopedia_backend/src/external/mod.rs
+pub mod backup_target;
 pub mod blueband;
+#[cfg(any(test, feature = "mock-services"))]
+pub mod fakes;
+#[cfg(feature = "mock-blueband")]
+pub mod mock_blueband;
+pub mod nft;
 pub mod token;
 pub mod sns_integration;
+pub mod traced;
+pub mod vetkd;
 
 pub use blueband::{
     add_document_to_blueband, create_blueband_collection, delete_collection, delete_document,
@@ -11,4 +19,5 @@ pub use blueband::{
     VectorMatch,
 };
 
+pub use sns_integration::SnsGovernanceService;
 pub use token::{get_token_balance, get_token_total_supply, TokenResult, TokenService};
@@ -0,0 +1,113 @@
+// src/clanopedia_backend/src/external/nft.rs
+use crate::types::*;
+use candid::{CandidType, Deserialize, Nat, Principal};
+use super::traced::call;
+use icrc_ledger_types::icrc::generic_metadata_value::MetadataValue;
+use icrc_ledger_types::icrc1::account::Account;
+
+// ============================
+// ICRC-7 NFT INTERFACE
+// ============================
+
+/// ICRC-7 leaves minting to each implementation; this is the `mint` shape the common
+/// reference ledgers expose, with Clanopedia as the minting account
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct MintArg {
+    pub token_id: Nat,
+    pub owner: Account,
+    pub metadata: Vec<(String, MetadataValue)>,
+}
+
+pub struct NftService {
+    canister_id: Principal,
+}
+
+impl NftService {
+    pub fn new(canister_id: Principal) -> Self {
+        Self { canister_id }
+    }
+
+    pub async fn icrc7_balance_of(&self, accounts: Vec<Account>) -> Result<Vec<Nat>, String> {
+        let result: Result<(Vec<Nat>,), _> =
+            call(self.canister_id, "icrc7_balance_of", (accounts,)).await;
+
+        match result {
+            Ok((balances,)) => Ok(balances),
+            Err((_, e)) => Err(format!("Call failed: {}", e)),
+        }
+    }
+
+    pub async fn icrc7_owner_of(&self, token_ids: Vec<Nat>) -> Result<Vec<Option<Account>>, String> {
+        let result: Result<(Vec<Option<Account>>,), _> =
+            call(self.canister_id, "icrc7_owner_of", (token_ids,)).await;
+
+        match result {
+            Ok((owners,)) => Ok(owners),
+            Err((_, e)) => Err(format!("Call failed: {}", e)),
+        }
+    }
+
+    pub async fn mint(&self, args: Vec<MintArg>) -> Result<Vec<Result<Nat, String>>, String> {
+        let result: Result<(Vec<Result<Nat, String>>,), _> =
+            call(self.canister_id, "mint", (args,)).await;
+
+        match result {
+            Ok((results,)) => Ok(results),
+            Err((_, e)) => Err(format!("Call failed: {}", e)),
+        }
+    }
+}
+
+// ============================
+// NFT CLIENT FUNCTIONS
+// ============================
+
+// Check whether `owner` holds any token of the collection, or a specific token when given
+pub async fn holds_nft(
+    nft_canister: Principal,
+    owner: Principal,
+    token_id: Option<Nat>,
+) -> ClanopediaResult<bool> {
+    let service = NftService::new(nft_canister);
+    let account = Account {
+        owner,
+        subaccount: None,
+    };
+
+    match token_id {
+        Some(token_id) => {
+            let owners = service
+                .icrc7_owner_of(vec![token_id])
+                .await
+                .map_err(|e| ClanopediaError::ExternalCallError(format!("NFT owner check failed: {}", e)))?;
+            Ok(owners.into_iter().next().flatten() == Some(account))
+        }
+        None => {
+            let balances = service
+                .icrc7_balance_of(vec![account])
+                .await
+                .map_err(|e| ClanopediaError::ExternalCallError(format!("NFT balance check failed: {}", e)))?;
+            Ok(balances.into_iter().next().is_some_and(|b| b > 0u64))
+        }
+    }
+}
+
+pub async fn token_owner(nft_canister: Principal, token_id: Nat) -> ClanopediaResult<Option<Account>> {
+    let owners = NftService::new(nft_canister)
+        .icrc7_owner_of(vec![token_id])
+        .await
+        .map_err(|e| ClanopediaError::ExternalCallError(format!("NFT owner check failed: {}", e)))?;
+    Ok(owners.into_iter().next().flatten())
+}
+
+// Mint one token; an already minted id comes back as an error from the NFT canister
+pub async fn mint_nft(nft_canister: Principal, arg: MintArg) -> ClanopediaResult<Nat> {
+    NftService::new(nft_canister)
+        .mint(vec![arg])
+        .await
+        .map_err(|e| ClanopediaError::ExternalCallError(format!("NFT mint failed: {}", e)))?
+        .into_iter()
+        .next()
+        .ok_or_else(|| ClanopediaError::ExternalCallError("NFT mint returned no result".to_string()))?
+        .map_err(|e| ClanopediaError::ExternalCallError(format!("NFT mint rejected: {}", e)))
+}
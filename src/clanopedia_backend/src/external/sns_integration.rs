@@ -1,7 +1,7 @@
 // src/clanopedia_backend/src/sns_integration.rs
 use crate::types::{ClanopediaError, ClanopediaResult, ProposalType};
 use candid::{CandidType, Deserialize, Principal};
-use ic_cdk::api::call::call;
+use super::traced::call;
 
 #[derive(CandidType, Deserialize, Clone, Debug)]
 pub struct MakeProposalRequest {
@@ -88,20 +88,72 @@ pub enum SnsProposalStatus {
     Failed,
 }
 
+// ============================
+// SNS GOVERNANCE SERVICE
+// ============================
+
+/// The SNS governance calls Clanopedia makes. `SnsGovernanceService` talks to the SNS
+/// governance canister; unit tests and `mock-services` builds use an in-memory one.
+pub trait SnsApi {
+    async fn get_proposal(&self, proposal_id: u64) -> ClanopediaResult<Option<SnsProposalData>>;
+    async fn list_neurons(&self, request: ListNeurons) -> ClanopediaResult<ListNeuronsResponse>;
+}
+
+pub struct SnsGovernanceService {
+    canister_id: Principal,
+}
+
+impl SnsGovernanceService {
+    pub fn new(canister_id: Principal) -> Self {
+        Self { canister_id }
+    }
+}
+
+impl SnsApi for SnsGovernanceService {
+    async fn get_proposal(&self, proposal_id: u64) -> ClanopediaResult<Option<SnsProposalData>> {
+        let request = GetProposalRequest { proposal_id };
+        let (response,): (Option<SnsProposalData>,) =
+            call(self.canister_id, "get_proposal", (request,))
+                .await
+                .map_err(|e| {
+                    ic_cdk::println!("SNS proposal status check failed: {:?}", e);
+                    ClanopediaError::ExternalCallError(format!("SNS call failed: {:?}", e))
+                })?;
+        Ok(response)
+    }
+
+    async fn list_neurons(&self, request: ListNeurons) -> ClanopediaResult<ListNeuronsResponse> {
+        let (response,): (ListNeuronsResponse,) =
+            call(self.canister_id, "list_neurons", (request,))
+                .await
+                .map_err(|e| {
+                    ic_cdk::println!("SNS neuron lookup failed: {:?}", e);
+                    ClanopediaError::ExternalCallError(format!("SNS call failed: {:?}", e))
+                })?;
+        Ok(response)
+    }
+}
+
+#[cfg(not(any(test, feature = "mock-services")))]
+pub fn sns_governance(sns_governance_canister: Principal) -> SnsGovernanceService {
+    SnsGovernanceService::new(sns_governance_canister)
+}
+
+#[cfg(any(test, feature = "mock-services"))]
+pub fn sns_governance(sns_governance_canister: Principal) -> super::fakes::FakeSnsGovernance {
+    super::fakes::FakeSnsGovernance::new(sns_governance_canister)
+}
+
+// ============================
+// SNS CLIENT FUNCTIONS
+// ============================
+
 // Enhanced SNS proposal status checking
 pub async fn check_sns_proposal_status(
-    sns_governance_canister: Principal,
+    sns: &impl SnsApi,
     proposal_id: u64,
 ) -> ClanopediaResult<SnsProposalStatus> {
-    let request = GetProposalRequest { proposal_id };
-
-    let (response,): (Option<SnsProposalData>,) =
-        call(sns_governance_canister, "get_proposal", (request,))
-            .await
-            .map_err(|e| {
-                ic_cdk::println!("SNS proposal status check failed: {:?}", e);
-                ClanopediaError::ExternalCallError(format!("SNS call failed: {:?}", e))
-            })?;
+    let response = sns.get_proposal(proposal_id).await?;
 
     if let Some(proposal_data) = response {
         // Determine status based on SNS proposal data
@@ -133,9 +185,55 @@ pub async fn check_sns_proposal_status(
 
 // Check if SNS proposal is approved (for backward compatibility)
 pub async fn check_sns_proposal_approved(
-    sns_governance_canister: Principal,
+    sns: &impl SnsApi,
     proposal_id: u64,
 ) -> ClanopediaResult<bool> {
-    let status = check_sns_proposal_status(sns_governance_canister, proposal_id).await?;
+    let status = check_sns_proposal_status(sns, proposal_id).await?;
     Ok(status == SnsProposalStatus::Adopted || status == SnsProposalStatus::Executed)
 }
+
+// SNS neuron lookup (only the fields Clanopedia needs)
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct NeuronId {
+    pub id: Vec<u8>,
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct ListNeurons {
+    pub of_principal: Option<Principal>,
+    pub limit: u32,
+    pub start_page_at: Option<NeuronId>,
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct SnsNeuron {
+    pub id: Option<NeuronId>,
+    pub cached_neuron_stake_e8s: u64,
+    pub neuron_fees_e8s: u64,
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct ListNeuronsResponse {
+    pub neurons: Vec<SnsNeuron>,
+}
+
+// Largest effective stake among the neurons controlled by `principal`
+pub async fn get_max_neuron_stake(
+    sns: &impl SnsApi,
+    principal: Principal,
+) -> ClanopediaResult<u64> {
+    let request = ListNeurons {
+        of_principal: Some(principal),
+        limit: 100,
+        start_page_at: None,
+    };
+
+    let response = sns.list_neurons(request).await?;
+
+    Ok(response
+        .neurons
+        .iter()
+        .map(|n| n.cached_neuron_stake_e8s.saturating_sub(n.neuron_fees_e8s))
+        .max()
+        .unwrap_or(0))
+}
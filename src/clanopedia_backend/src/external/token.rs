@@ -1,11 +1,14 @@
 // src/clanopedia_backend/src/external/token.rs
 use candid::{Principal, Nat};
-use ic_cdk::call;
+use super::traced::call;
+use std::future::Future;
 use std::result::Result;
 use crate::types::*;
+use sha2::{Digest, Sha256};
 use icrc_ledger_types::{
-    icrc1::account::Account,
-    icrc1::transfer::{TransferArg, TransferError},
+    icrc1::account::{Account, Subaccount},
+    icrc1::transfer::{Memo, TransferArg, TransferError},
+    icrc2::transfer_from::{TransferFromArgs, TransferFromError},
 };
 
 // ============================
@@ -13,6 +16,7 @@ use icrc_ledger_types::{
 // ============================
 
 pub type TokenResult<T> = Result<T, TransferError>;
+pub type TransferFromResult<T> = Result<T, TransferFromError>;
 
 pub struct TokenService {
     canister_id: Principal,
@@ -22,8 +26,20 @@ impl TokenService {
     pub fn new(canister_id: Principal) -> Self {
         Self { canister_id }
     }
+}
+
+/// The ICRC ledger calls Clanopedia makes. `TokenService` talks to the ledger canister;
+/// unit tests and builds with the `mock-services` feature use an in-memory ledger instead.
+pub trait TokenApi {
+    fn icrc1_balance_of(&self, account: Account) -> impl Future<Output = TokenResult<Nat>>;
+    fn icrc1_total_supply(&self) -> impl Future<Output = TokenResult<Nat>>;
+    fn icrc1_fee(&self) -> impl Future<Output = TokenResult<Nat>>;
+    fn icrc1_transfer(&self, transfer_arg: TransferArg) -> impl Future<Output = TokenResult<Nat>>;
+    fn icrc2_transfer_from(&self, args: TransferFromArgs) -> impl Future<Output = TransferFromResult<Nat>>;
+}
 
-    pub async fn icrc1_balance_of(&self, account: Account) -> TokenResult<Nat> {
+impl TokenApi for TokenService {
+    async fn icrc1_balance_of(&self, account: Account) -> TokenResult<Nat> {
         let result: Result<(Nat,), _> = call(
             self.canister_id,
             "icrc1_balance_of",
@@ -36,7 +52,7 @@ impl TokenService {
         }
     }
 
-    pub async fn icrc1_total_supply(&self) -> TokenResult<Nat> {
+    async fn icrc1_total_supply(&self) -> TokenResult<Nat> {
         let result: Result<(Nat,), _> = call(
             self.canister_id,
             "icrc1_total_supply",
@@ -49,7 +65,20 @@ impl TokenService {
         }
     }
 
-    pub async fn icrc1_transfer(&self, transfer_arg: TransferArg) -> TokenResult<Nat> {
+    async fn icrc1_fee(&self) -> TokenResult<Nat> {
+        let result: Result<(Nat,), _> = call(
+            self.canister_id,
+            "icrc1_fee",
+            (),
+        ).await;
+
+        match result {
+            Ok((fee,)) => Ok(fee),
+            Err((_, e)) => Err(TransferError::GenericError { error_code: Nat::from(1u64), message: format!("Call failed: {}", e) }),
+        }
+    }
+
+    async fn icrc1_transfer(&self, transfer_arg: TransferArg) -> TokenResult<Nat> {
         let result: Result<(TokenResult<Nat>,), _> = call(
             self.canister_id,
             "icrc1_transfer",
@@ -61,14 +90,36 @@ impl TokenService {
             Err((_, e)) => Err(TransferError::GenericError { error_code: Nat::from(1u64), message: format!("Call failed: {}", e) }),
         }
     }
+
+    async fn icrc2_transfer_from(&self, args: TransferFromArgs) -> TransferFromResult<Nat> {
+        let result: Result<(TransferFromResult<Nat>,), _> = call(
+            self.canister_id,
+            "icrc2_transfer_from",
+            (args,),
+        ).await;
+
+        match result {
+            Ok((result,)) => result,
+            Err((_, e)) => Err(TransferFromError::GenericError { error_code: Nat::from(1u64), message: format!("Call failed: {}", e) }),
+        }
+    }
 }
 
 // ============================
 // TOKEN CLIENT FUNCTIONS
 // ============================
 
-pub async fn get_token_balance(token_canister: Principal, owner: Principal) -> ClanopediaResult<Nat> {
-    let service = TokenService::new(token_canister);
+#[cfg(not(any(test, feature = "mock-services")))]
+pub fn ledger(token_canister: Principal) -> TokenService {
+    TokenService::new(token_canister)
+}
+
+#[cfg(any(test, feature = "mock-services"))]
+pub fn ledger(token_canister: Principal) -> super::fakes::FakeLedger {
+    super::fakes::FakeLedger::new(token_canister)
+}
+
+pub async fn get_token_balance(service: &impl TokenApi, owner: Principal) -> ClanopediaResult<Nat> {
     let account = Account {
         owner,
         subaccount: None,
@@ -78,11 +129,115 @@ pub async fn get_token_balance(token_canister: Principal, owner: Principal) -> C
         .map_err(|e| ClanopediaError::ExternalCallError(format!("Token balance check failed: {:?}", e)))
 }
 
-pub async fn get_token_total_supply(token_canister: Principal) -> ClanopediaResult<Nat> {
-    let service = TokenService::new(token_canister);
+pub async fn get_token_total_supply(service: &impl TokenApi) -> ClanopediaResult<Nat> {
     service.icrc1_total_supply()
         .await
         .map_err(|e| ClanopediaError::ExternalCallError(format!("Token total supply check failed: {:?}", e)))
-   
+
+}
+
+/// The subaccount of this canister that holds the tokens escrowed for `scope`: a collection
+/// ID for its bonds and proposal fees, or `CREATION_ESCROW` for creation fees. Keeping each
+/// scope apart means no collection's funds can pay out another's.
+pub fn escrow_subaccount(scope: &str) -> Subaccount {
+    Sha256::digest(format!("clanopedia-escrow:{}", scope).as_bytes()).into()
+}
+
+pub const CREATION_ESCROW: &str = "collection-creation";
+
+// Pull an ICRC-2 approved amount from `from` into this canister's escrow `subaccount`
+pub async fn pull_approved_tokens(
+    service: &impl TokenApi,
+    from: Principal,
+    amount: Nat,
+    subaccount: Subaccount,
+) -> ClanopediaResult<Nat> {
+    let args = TransferFromArgs {
+        spender_subaccount: None,
+        from: Account { owner: from, subaccount: None },
+        to: Account { owner: ic_cdk::api::id(), subaccount: Some(subaccount) },
+        amount,
+        fee: None,
+        memo: None,
+        created_at_time: None,
+    };
+    service.icrc2_transfer_from(args)
+        .await
+        .map_err(|e| ClanopediaError::ExternalCallError(format!("Token transfer_from failed: {}", e)))
+}
+
+// Send tokens held in this canister's `from_subaccount` to `to`, deducting the ledger fee
+// from the amount. `None` is the default account, where escrow was held before it was
+// split per collection.
+pub async fn send_tokens(
+    service: &impl TokenApi,
+    from_subaccount: Option<Subaccount>,
+    to: Principal,
+    amount: Nat,
+) -> ClanopediaResult<Nat> {
+    let fee = fee_below(service, &amount).await?;
+
+    let transfer_arg = TransferArg {
+        from_subaccount,
+        to: Account { owner: to, subaccount: None },
+        fee: Some(fee.clone()),
+        created_at_time: None,
+        memo: None,
+        amount: amount - fee,
+    };
+    service.icrc1_transfer(transfer_arg)
+        .await
+        .map_err(|e| ClanopediaError::ExternalCallError(format!("Token transfer failed: {:?}", e)))
+}
+
+/// Like `send_tokens`, but the ledger deduplicates it: sent again with the same `memo` and
+/// `created_at_time` inside the ledger's deduplication window it cannot pay twice, and a
+/// repeat of a transfer that went through counts as sent. Errors that retrying cannot fix
+/// are `InvalidOperation`.
+pub async fn send_tokens_once(
+    service: &impl TokenApi,
+    from_subaccount: Option<Subaccount>,
+    to: Principal,
+    amount: Nat,
+    memo: Vec<u8>,
+    created_at_time: u64,
+) -> ClanopediaResult<Nat> {
+    let fee = fee_below(service, &amount).await?;
+
+    let transfer_arg = TransferArg {
+        from_subaccount,
+        to: Account { owner: to, subaccount: None },
+        fee: Some(fee.clone()),
+        created_at_time: Some(created_at_time),
+        memo: Some(Memo::from(memo)),
+        amount: amount - fee,
+    };
+    match service.icrc1_transfer(transfer_arg).await {
+        Ok(block) | Err(TransferError::Duplicate { duplicate_of: block }) => Ok(block),
+        Err(
+            e @ (TransferError::InsufficientFunds { .. }
+            | TransferError::TooOld
+            | TransferError::BadBurn { .. }),
+        ) => Err(ClanopediaError::InvalidOperation(format!("Token transfer failed: {}", e))),
+        Err(e) => Err(ClanopediaError::ExternalCallError(format!("Token transfer failed: {}", e))),
+    }
+}
+
+// The ledger fee, which `amount` must exceed
+async fn fee_below(service: &impl TokenApi, amount: &Nat) -> ClanopediaResult<Nat> {
+    let fee = ledger_fee(service).await?;
+    if *amount <= fee {
+        return Err(ClanopediaError::InvalidOperation(format!(
+            "Amount {} does not cover ledger fee {}",
+            amount, fee
+        )));
+    }
+    Ok(fee)
 }
 
+/// The ledger fee, for checking configured amounts against it
+pub async fn ledger_fee(service: &impl TokenApi) -> ClanopediaResult<Nat> {
+    service.icrc1_fee()
+        .await
+        .map_err(|e| ClanopediaError::ExternalCallError(format!("Token fee lookup failed: {:?}", e)))
+}
@@ -0,0 +1,70 @@
+// src/clanopedia_backend/src/external/traced.rs
+//
+// Inter-canister calls that are added to the execution receipt of the proposal being
+// executed, if any; see `execution_receipts`. Outside an execution they are plain calls.
+
+use candid::utils::{ArgumentDecoder, ArgumentEncoder};
+use candid::{decode_args, encode_args, Principal};
+use ic_cdk::api::call::{self, msg_cycles_refunded, CallResult, RejectionCode};
+use ic_cdk::api::time;
+use sha2::{Digest, Sha256};
+
+use crate::execution_receipts;
+use crate::types::{CallTrace, ExternalCallOutcome};
+
+/// `ic_cdk::call`, traced
+pub async fn call<T: ArgumentEncoder, R: for<'a> ArgumentDecoder<'a>>(
+    id: Principal,
+    method: &str,
+    args: T,
+) -> CallResult<R> {
+    if !execution_receipts::is_tracing() {
+        return call::call(id, method, args).await;
+    }
+    let args = encode_args(args).map_err(|e| {
+        (
+            RejectionCode::CanisterError,
+            format!("failed to encode arguments: {}", e),
+        )
+    })?;
+    let reply = call_raw(id, method, &args, 0).await?;
+    decode_args(&reply).map_err(|e| {
+        (
+            RejectionCode::CanisterError,
+            format!("failed to decode the reply of {}: {}", method, e),
+        )
+    })
+}
+
+/// `ic_cdk::api::call::call_raw`, traced
+pub async fn call_raw(
+    id: Principal,
+    method: &str,
+    args: &[u8],
+    payment: u64,
+) -> CallResult<Vec<u8>> {
+    let started_at = time();
+    let result = call::call_raw(id, method, args, payment).await;
+    if execution_receipts::is_tracing() {
+        let refunded = msg_cycles_refunded();
+        let outcome = match &result {
+            Ok(reply) => ExternalCallOutcome::Replied {
+                reply_bytes: reply.len() as u64,
+                cycles_refunded: refunded,
+            },
+            Err((code, message)) => {
+                ExternalCallOutcome::Rejected(format!("{:?}: {}", code, message))
+            }
+        };
+        execution_receipts::record_call(CallTrace {
+            canister: id,
+            method: method.to_string(),
+            args_hash: hex::encode(Sha256::digest(args)),
+            cycles: payment.saturating_sub(refunded),
+            started_at,
+            duration_nanos: time().saturating_sub(started_at),
+            outcome,
+        });
+    }
+    result
+}
@@ -0,0 +1,113 @@
+// src/clanopedia_backend/src/external/vetkd.rs
+use crate::types::*;
+use candid::{CandidType, Deserialize, Principal};
+use ic_cdk::api::call::{call, call_with_payment128};
+use serde::Serialize;
+
+// ============================
+// VETKD MANAGEMENT CANISTER INTERFACE
+// ============================
+
+// "key_1" on mainnet; local replicas expose "dfx_test_key"
+pub const VETKD_KEY_NAME: &str = "key_1";
+
+// Fee for vetkd_derive_key with key_1 on a 34-node subnet
+const VETKD_DERIVE_KEY_CYCLES: u128 = 26_153_846_153;
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub enum VetKDCurve {
+    #[serde(rename = "bls12_381_g2")]
+    Bls12_381G2,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct VetKDKeyId {
+    pub curve: VetKDCurve,
+    pub name: String,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct VetKDPublicKeyArgs {
+    pub canister_id: Option<Principal>,
+    pub context: Vec<u8>,
+    pub key_id: VetKDKeyId,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct VetKDPublicKeyResult {
+    pub public_key: Vec<u8>,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct VetKDDeriveKeyArgs {
+    pub input: Vec<u8>,
+    pub context: Vec<u8>,
+    pub transport_public_key: Vec<u8>,
+    pub key_id: VetKDKeyId,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct VetKDDeriveKeyResult {
+    pub encrypted_key: Vec<u8>,
+}
+
+fn key_id() -> VetKDKeyId {
+    VetKDKeyId {
+        curve: VetKDCurve::Bls12_381G2,
+        name: VETKD_KEY_NAME.to_string(),
+    }
+}
+
+// ============================
+// VETKD CLIENT FUNCTIONS
+// ============================
+
+/// Verification key for keys derived under `context`, used by clients to check derived keys
+pub async fn get_public_key(context: Vec<u8>) -> ClanopediaResult<Vec<u8>> {
+    let args = VetKDPublicKeyArgs {
+        canister_id: None,
+        context,
+        key_id: key_id(),
+    };
+
+    let result: Result<(VetKDPublicKeyResult,), _> =
+        call(Principal::management_canister(), "vetkd_public_key", (args,)).await;
+
+    match result {
+        Ok((result,)) => Ok(result.public_key),
+        Err((_, e)) => Err(ClanopediaError::ExternalCallError(format!(
+            "vetkd_public_key failed: {}",
+            e
+        ))),
+    }
+}
+
+/// Derive the key for `input` under `context`, encrypted to the caller's transport key
+pub async fn derive_encrypted_key(
+    context: Vec<u8>,
+    input: Vec<u8>,
+    transport_public_key: Vec<u8>,
+) -> ClanopediaResult<Vec<u8>> {
+    let args = VetKDDeriveKeyArgs {
+        input,
+        context,
+        transport_public_key,
+        key_id: key_id(),
+    };
+
+    let result: Result<(VetKDDeriveKeyResult,), _> = call_with_payment128(
+        Principal::management_canister(),
+        "vetkd_derive_key",
+        (args,),
+        VETKD_DERIVE_KEY_CYCLES,
+    )
+    .await;
+
+    match result {
+        Ok((result,)) => Ok(result.encrypted_key),
+        Err((_, e)) => Err(ClanopediaError::ExternalCallError(format!(
+            "vetkd_derive_key failed: {}",
+            e
+        ))),
+    }
+}
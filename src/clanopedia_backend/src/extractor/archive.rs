@@ -0,0 +1,148 @@
+// src/extractor/archive.rs
+//
+// A zip upload is a batch of files: each entry goes through the same type detection and
+// extraction as a single upload. Entries that cannot be used are skipped and reported,
+// so one bad file does not reject the archive. Sizes are checked against both the entry
+// headers and the bytes actually inflated, since headers can understate them.
+
+use std::io::{Cursor, Read};
+
+use zip::ZipArchive;
+
+use crate::extractor::file_extractor::extract_file_content;
+use crate::extractor::types::{
+    ArchiveEntryReport, ArchiveEntryStatus, ExtractionError, FileExtractionConfig, FileType,
+};
+use crate::types::{ClanopediaError, ClanopediaResult};
+use crate::AddDocumentRequest;
+
+const MAX_ARCHIVE_ENTRIES: usize = 500;
+const MAX_UNCOMPRESSED_BYTES: u64 = 50 * 1024 * 1024; // 50MB across all entries
+
+/// Extract every supported file in a zip archive, with a report line per file entry
+pub fn extract_archive(
+    file_data: &[u8],
+    collection_id: &str,
+) -> ClanopediaResult<(Vec<AddDocumentRequest>, Vec<ArchiveEntryReport>)> {
+    let mut archive = ZipArchive::new(Cursor::new(file_data)).map_err(invalid_archive)?;
+
+    if archive.len() > MAX_ARCHIVE_ENTRIES {
+        return Err(ClanopediaError::InvalidInput(format!(
+            "Archive has too many entries: {} (max: {})",
+            archive.len(),
+            MAX_ARCHIVE_ENTRIES
+        )));
+    }
+
+    let mut declared = 0u64;
+    let mut names = Vec::with_capacity(archive.len());
+    for index in 0..archive.len() {
+        let entry = archive.by_index_raw(index).map_err(invalid_archive)?;
+        declared = declared.saturating_add(entry.size());
+        names.push(entry.name().to_string());
+    }
+    if declared > MAX_UNCOMPRESSED_BYTES {
+        return Err(ExtractionError::TooLarge {
+            bytes: declared,
+            max_bytes: MAX_UNCOMPRESSED_BYTES,
+        }
+        .into());
+    }
+
+    let max_file_size = FileExtractionConfig::default().max_file_size;
+    let mut inflated = 0u64;
+    let mut documents = Vec::new();
+    let mut reports = Vec::new();
+
+    for (index, name) in names.iter().enumerate() {
+        let mut entry = match archive.by_index(index) {
+            Ok(entry) => entry,
+            Err(e) => {
+                reports.push(ArchiveEntryReport::failed(name.clone(), 0, e.to_string()));
+                continue;
+            }
+        };
+        if entry.is_dir() {
+            continue;
+        }
+
+        let path = entry.name().to_string();
+        let size = entry.size();
+        let filename = path.rsplit('/').next().unwrap_or(&path).to_string();
+
+        let skip_reason = if filename.starts_with('.') || path.starts_with("__MACOSX/") {
+            Some("Hidden or system file".to_string())
+        } else if filename.to_lowercase().ends_with(".zip") {
+            Some("Nested archives are not extracted".to_string())
+        } else if FileType::from_filename(&filename) == FileType::Unknown {
+            Some("Unsupported file type".to_string())
+        } else if size > max_file_size {
+            Some(format!(
+                "File too large: {} bytes (max: {} bytes)",
+                size, max_file_size
+            ))
+        } else {
+            None
+        };
+        if let Some(reason) = skip_reason {
+            reports.push(ArchiveEntryReport::skipped(path, size, reason));
+            continue;
+        }
+
+        // Read one byte past the limit so an understated header is caught, not truncated
+        let mut data = Vec::with_capacity(size as usize);
+        let read = (&mut entry).take(max_file_size + 1).read_to_end(&mut data);
+        drop(entry);
+        if let Err(e) = read {
+            reports.push(ArchiveEntryReport::failed(path, size, e.to_string()));
+            continue;
+        }
+
+        inflated += data.len() as u64;
+        if inflated > MAX_UNCOMPRESSED_BYTES {
+            return Err(ExtractionError::TooLarge {
+                bytes: inflated,
+                max_bytes: MAX_UNCOMPRESSED_BYTES,
+            }
+            .into());
+        }
+
+        let size = data.len() as u64;
+        match extract_file_content(data, filename, collection_id.to_string()) {
+            Ok(extracted) => {
+                reports.push(ArchiveEntryReport {
+                    path,
+                    size,
+                    documents: extracted.len() as u32,
+                    status: ArchiveEntryStatus::Extracted,
+                    error: None,
+                });
+                documents.extend(extracted);
+            }
+            Err(e) => reports.push(ArchiveEntryReport {
+                error: e.extraction_error(),
+                ..ArchiveEntryReport::failed(path, size, error_message(e))
+            }),
+        }
+    }
+
+    ic_cdk::println!(
+        "Extracted {} documents from {} archive entries",
+        documents.len(),
+        reports.len()
+    );
+
+    Ok((documents, reports))
+}
+
+fn error_message(error: ClanopediaError) -> String {
+    match error {
+        ClanopediaError::InvalidInput(message) => message,
+        ClanopediaError::ExtractionFailed(error) => error.to_string(),
+        other => format!("{:?}", other),
+    }
+}
+
+fn invalid_archive(error: zip::result::ZipError) -> ClanopediaError {
+    ExtractionError::parse("zip", format!("Invalid zip archive: {}", error)).into()
+}
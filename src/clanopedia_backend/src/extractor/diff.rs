@@ -0,0 +1,152 @@
+// src/extractor/diff.rs
+//
+// A dry run of re-ingesting a source. Each extracted document is matched to an existing one
+// by the URL its content was fetched from, or failing that by title, and matched documents
+// are compared by content checksum and title. Existing documents fetched from under the
+// same source that the extraction no longer produces are reported as removed. Nothing is
+// written.
+
+use crate::extractor::types::{ExtractionChange, ExtractionDiff, ExtractionDiffEntry};
+use crate::{integrity, storage, trash, AddDocumentRequest, DocumentId};
+
+struct ExistingDocument {
+    id: DocumentId,
+    title: String,
+    source_url: Option<String>,
+    checksum: Option<String>,
+    matched: bool,
+}
+
+/// Classify `documents`, already run through the collection's pipeline, against the
+/// collection's current documents
+pub fn diff_documents(collection_id: &str, documents: &[AddDocumentRequest]) -> ExtractionDiff {
+    let mut existing = existing_documents(collection_id);
+    let mut entries = Vec::with_capacity(documents.len());
+
+    for document in documents {
+        let source_url = fetched_url(document);
+        let matched = source_url
+            .as_ref()
+            .and_then(|url| {
+                existing
+                    .iter()
+                    .position(|e| !e.matched && e.source_url.as_ref() == Some(url))
+            })
+            .or_else(|| {
+                existing
+                    .iter()
+                    .position(|e| !e.matched && e.title == document.title)
+            });
+
+        let Some(index) = matched else {
+            entries.push(ExtractionDiffEntry {
+                change: ExtractionChange::Added,
+                document_id: None,
+                title: document.title.clone(),
+                source_url,
+            });
+            continue;
+        };
+
+        let current = &mut existing[index];
+        current.matched = true;
+        // Without a recorded checksum the stored content cannot be compared
+        let same_content = current.checksum.as_deref()
+            == Some(integrity::content_checksum(&document.content).as_str());
+        let change = if same_content && current.title == document.title {
+            ExtractionChange::Unchanged
+        } else {
+            ExtractionChange::Changed
+        };
+        entries.push(ExtractionDiffEntry {
+            change,
+            document_id: Some(current.id.clone()),
+            title: document.title.clone(),
+            source_url,
+        });
+    }
+
+    if let Some(prefix) = source_prefix(documents) {
+        for document in existing.into_iter().filter(|e| !e.matched) {
+            if document
+                .source_url
+                .as_ref()
+                .is_some_and(|url| url.starts_with(&prefix))
+            {
+                entries.push(ExtractionDiffEntry {
+                    change: ExtractionChange::Removed,
+                    document_id: Some(document.id),
+                    title: document.title,
+                    source_url: document.source_url,
+                });
+            }
+        }
+    }
+
+    let count = |change: ExtractionChange| entries.iter().filter(|e| e.change == change).count();
+    ExtractionDiff {
+        added: count(ExtractionChange::Added) as u32,
+        changed: count(ExtractionChange::Changed) as u32,
+        unchanged: count(ExtractionChange::Unchanged) as u32,
+        removed: count(ExtractionChange::Removed) as u32,
+        entries,
+    }
+}
+
+fn fetched_url(document: &AddDocumentRequest) -> Option<String> {
+    document
+        .provenance
+        .as_ref()
+        .and_then(|p| p.source_url.clone())
+        .or_else(|| document.source_url.clone())
+}
+
+// The collection's own documents; linked and trashed ones are left out
+fn existing_documents(collection_id: &str) -> Vec<ExistingDocument> {
+    storage::list_known_document_ids(collection_id)
+        .into_iter()
+        .filter(|id| {
+            storage::get_cross_post(collection_id, id).is_none()
+                && !trash::is_document_trashed(collection_id, id)
+        })
+        .filter_map(|id| {
+            let title = storage::get_indexed_document(collection_id, &id)?.title;
+            Some(ExistingDocument {
+                source_url: storage::get_document_provenance(collection_id, &id)
+                    .and_then(|p| p.source_url),
+                checksum: storage::get_document_checksum(collection_id, &id),
+                title,
+                id,
+                matched: false,
+            })
+        })
+        .collect()
+}
+
+// The URL prefix, up to a path separator, shared by everything the extraction fetched. A
+// single document is its own source.
+fn source_prefix(documents: &[AddDocumentRequest]) -> Option<String> {
+    let mut urls = documents.iter().filter_map(fetched_url);
+    let first = urls.next()?;
+    let mut shared = first.len();
+    let mut others = 0;
+    for url in urls {
+        shared = first
+            .bytes()
+            .zip(url.bytes())
+            .take(shared)
+            .take_while(|(a, b)| a == b)
+            .count();
+        others += 1;
+    }
+    if others == 0 {
+        return Some(first);
+    }
+    let end = first.as_bytes()[..shared]
+        .iter()
+        .rposition(|b| *b == b'/')?
+        + 1;
+    let prefix = &first[..end];
+    // A prefix that stops inside the scheme or host covers unrelated documents
+    (prefix.matches('/').count() > 3).then(|| prefix.to_string())
+}
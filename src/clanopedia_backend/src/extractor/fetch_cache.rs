@@ -0,0 +1,86 @@
+// src/extractor/fetch_cache.rs
+//
+// Recent GitHub, GitLab and Bitbucket responses, so extracting the same file again within
+// the TTL reads stable memory instead of paying for another outcall. Caching is best effort: a full cache map
+// or an oversized body only means the next extraction fetches again.
+
+use ic_cdk::api::time;
+use sha2::{Digest, Sha256};
+
+use super::{CachedFetch, FetchProvenance};
+use crate::{storage, DocumentProvenance, StorageMap};
+
+const CACHE_TTL_NANOS: u64 = 60 * 60 * 1_000_000_000; // 1 hour
+const MAX_CACHED_FETCHES: usize = 100;
+const MAX_CACHED_BODY_BYTES: usize = 1024 * 1024;
+
+// Entries cached before provenance was recorded are treated as stale
+fn is_fresh(entry: &CachedFetch, now: u64) -> bool {
+    entry.provenance.is_some() && now.saturating_sub(entry.fetched_at) < CACHE_TTL_NANOS
+}
+
+/// The cached body for `url` and the provenance of its fetch, if fetched within the TTL
+pub fn get(url: &str) -> Option<(String, DocumentProvenance)> {
+    let entry = storage::get_cached_fetch(url)?;
+    if !is_fresh(&entry, time()) {
+        storage::remove_cached_fetch(url);
+        return None;
+    }
+    Some((storage::get_cached_body(url)?, entry.provenance?))
+}
+
+pub fn is_cached(url: &str) -> bool {
+    storage::get_cached_fetch(url).is_some_and(|entry| is_fresh(&entry, time()))
+}
+
+pub fn put(url: &str, body: &str, provenance: &DocumentProvenance) {
+    if body.len() > MAX_CACHED_BODY_BYTES {
+        return;
+    }
+
+    // Expired entries go first, then the oldest until there is room
+    let now = time();
+    let mut entries = storage::list_cached_fetches();
+    entries.retain(|entry| {
+        let fresh = is_fresh(entry, now);
+        if !fresh {
+            storage::remove_cached_fetch(&entry.url);
+        }
+        fresh && entry.url != url
+    });
+    entries.sort_by_key(|entry| entry.fetched_at);
+    let excess = (entries.len() + 1).saturating_sub(MAX_CACHED_FETCHES);
+    for entry in entries.iter().take(excess) {
+        storage::remove_cached_fetch(&entry.url);
+    }
+
+    if storage::ensure_capacity(StorageMap::FetchCache, 1).is_err()
+        || storage::ensure_capacity(StorageMap::FetchCacheBodies, 1).is_err()
+    {
+        return;
+    }
+    storage::put_cached_fetch(
+        CachedFetch {
+            url: url.to_string(),
+            checksum: hex::encode(Sha256::digest(body.as_bytes())),
+            size: body.len() as u64,
+            fetched_at: now,
+            provenance: Some(provenance.clone()),
+        },
+        body.to_string(),
+    );
+}
+
+/// Cache entries for `urls` as an extraction that started at `started_at` left them;
+/// entries older than the extraction were served from the cache
+pub fn provenance(urls: &[String], started_at: u64) -> Vec<FetchProvenance> {
+    urls.iter()
+        .filter_map(|url| storage::get_cached_fetch(url))
+        .map(|entry| FetchProvenance {
+            from_cache: entry.fetched_at < started_at,
+            url: entry.url,
+            checksum: entry.checksum,
+            fetched_at: entry.fetched_at,
+        })
+        .collect()
+}
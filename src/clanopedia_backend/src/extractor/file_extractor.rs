@@ -2,20 +2,25 @@
 
 use crate::external::blueband::ContentType;
 use crate::extractor::types::{
-    ExtractionMetadata, ExtractionResult, FileExtractionConfig, FileType,
+    ExtractionError, ExtractionMetadata, ExtractionResult, FileExtractionConfig, FileType,
+    PageExtractionError,
 };
-use crate::extractor::{sanitize_content, validate_content_size};
-use crate::types::{ClanopediaError, ClanopediaResult};
+use crate::extractor::outline::{char_to_byte, markdown_outline, pdf_outline};
+use crate::extractor::{archive, sanitize_content, upload_provenance, validate_content_size};
+use crate::types::{ClanopediaError, ClanopediaResult, OutlineEntry};
 use crate::AddDocumentRequest;
 
 // File parsing libraries
 use chrono::DateTime;
 use encoding_rs::{Encoding, UTF_8};
-use lopdf::Document;
+use lopdf::{Document, Object};
 use quick_xml::{events::Event, Reader};
 use std::io::{Cursor, Read};
 use zip::ZipArchive;
 
+/// A long PDF without bookmarks is split into sections of this many pages
+const PDF_PAGES_PER_SECTION: usize = 20;
+
 /// Extract content from uploaded file buffer
 pub fn extract_file_content(
     file_data: Vec<u8>,
@@ -26,21 +31,18 @@ pub fn extract_file_content(
 
     // Validate file size
     if file_data.len() as u64 > config.max_file_size {
-        return Err(ClanopediaError::InvalidInput(format!(
-            "File too large: {} bytes (max: {} bytes)",
-            file_data.len(),
-            config.max_file_size
-        )));
+        return Err(ExtractionError::TooLarge {
+            bytes: file_data.len() as u64,
+            max_bytes: config.max_file_size,
+        }
+        .into());
     }
 
-    let file_type = FileType::from_filename(&filename);
+    let file_type = FileType::from_file(&filename, &file_data);
 
     // Check if file type is supported
     if !config.supported_types.contains(&file_type) {
-        return Err(ClanopediaError::InvalidInput(format!(
-            "Unsupported file type: {:?}",
-            file_type
-        )));
+        return Err(ExtractionError::UnsupportedType(format!("{:?}", file_type)).into());
     }
 
     ic_cdk::println!(
@@ -50,76 +52,118 @@ pub fn extract_file_content(
         file_data.len()
     );
 
-    let extraction_result = match file_type {
-        FileType::PlainText => extract_text_file(&file_data, &filename)?,
-        FileType::Markdown => extract_markdown_file(&file_data, &filename)?,
+    let extraction_results = match file_type {
+        FileType::PlainText => vec![extract_text_file(&file_data, &filename)?],
+        FileType::Markdown => vec![extract_markdown_file(&file_data, &filename)?],
         FileType::Pdf => extract_pdf_file(&file_data, &filename)?,
-        FileType::DocX => extract_docx_file(&file_data, &filename)?,
+        FileType::DocX => vec![extract_docx_file(&file_data, &filename)?],
+        FileType::Rtf => vec![extract_rtf_file(&file_data, &filename)?],
+        FileType::Zip => {
+            return archive::extract_archive(&file_data, &collection_id)
+                .map(|(documents, _)| documents);
+        }
+        FileType::Doc => {
+            return Err(ExtractionError::UnsupportedType(
+                "Legacy Word (.doc) files are not supported. Save the document as DOCX and upload it again."
+                    .to_string(),
+            )
+            .into());
+        }
         FileType::Unknown => {
-            return Err(ClanopediaError::InvalidInput(
+            return Err(ExtractionError::UnsupportedType(
                 "Cannot extract content from unknown file type".to_string(),
-            ));
+            )
+            .into());
         }
     };
 
-    // Validate extracted content size
-    validate_content_size(&extraction_result.content)?;
-
-    // Create AddDocumentRequest
-    let document_request = AddDocumentRequest {
-        collection_id,
-        title: extraction_result.title,
-        content: extraction_result.content,
-        content_type: Some(extraction_result.content_type),
-        source_url: extraction_result.source_url,
-        author: extraction_result
-            .metadata
-            .as_ref()
-            .and_then(|m| m.author.clone()),
-        tags: extraction_result
-            .metadata
-            .as_ref()
-            .and_then(|m| m.tags.clone()),
-    };
+    let mut documents = Vec::with_capacity(extraction_results.len());
+    for extraction_result in extraction_results {
+        // Validate extracted content size
+        validate_content_size(&collection_id, &extraction_result.content)?;
+
+        documents.push(AddDocumentRequest {
+            collection_id: collection_id.clone(),
+            title: extraction_result.title,
+            content: extraction_result.content,
+            content_type: Some(extraction_result.content_type),
+            source_url: extraction_result.source_url,
+            author: extraction_result
+                .metadata
+                .as_ref()
+                .and_then(|m| m.author.clone()),
+            tags: extraction_result
+                .metadata
+                .as_ref()
+                .and_then(|m| m.tags.clone()),
+            outline: extraction_result.outline,
+            summary: None,
+            license: None,
+            provenance: Some(upload_provenance(&format!("{:?}", file_type).to_lowercase())),
+        });
+    }
 
     ic_cdk::println!(
-        "Successfully extracted content: {} characters",
-        document_request.content.len()
+        "Successfully extracted content: {} characters in {} documents",
+        documents.iter().map(|d| d.content.len()).sum::<usize>(),
+        documents.len()
     );
 
-    Ok(vec![document_request])
+    Ok(documents)
 }
 
-/// Extract content from PDF files using lopdf
-fn extract_pdf_file(file_data: &[u8], filename: &str) -> ClanopediaResult<ExtractionResult> {
-    // Load PDF document from memory using lopdf
-    let doc = Document::load_mem(file_data)
-        .map_err(|e| ClanopediaError::InvalidInput(format!("Invalid PDF file: {}", e)))?;
-
-    // Extract text from all pages
-    let mut text = String::new();
+/// Extract content from PDF files using lopdf, one result per section when the PDF has
+/// top-level bookmarks or is longer than `PDF_PAGES_PER_SECTION` pages
+fn extract_pdf_file(file_data: &[u8], filename: &str) -> ClanopediaResult<Vec<ExtractionResult>> {
+    let doc = load_pdf(file_data)?;
+
+    // The content is the sanitized pages joined by newlines, so each page's offset is
+    // where it starts in the stored content
+    let mut content = String::new();
+    let mut content_chars = 0u64;
+    let mut page_offsets = Vec::new();
+    let mut page_errors = Vec::new();
     let pages = doc.get_pages();
 
     for (page_num, _) in pages.iter() {
         match doc.extract_text(&[*page_num]) {
             Ok(page_text) => {
-                if !page_text.trim().is_empty() {
-                    text.push_str(&page_text);
-                    text.push('\n');
+                let page_sanitized = sanitize_content(&page_text);
+                if !page_sanitized.is_empty() {
+                    if !content.is_empty() {
+                        content.push('\n');
+                        content_chars += 1;
+                    }
+                    page_offsets.push((*page_num, content_chars));
+                    content_chars += page_sanitized.chars().count() as u64;
+                    content.push_str(&page_sanitized);
                 }
             }
-            Err(_) => {
-                // Skip pages that can't be extracted (images, etc.)
-                continue;
+            Err(e) => {
+                // Pages without a text layer (images, etc.) are skipped but reported
+                page_errors.push(PageExtractionError {
+                    page: *page_num,
+                    error: e.to_string(),
+                });
             }
         }
     }
 
-    if text.trim().is_empty() {
-        return Err(ClanopediaError::InvalidInput(
-            "No extractable text found in PDF. This may be an image-based PDF, encrypted, or contains only graphics."
-                .to_string(),
-        ));
+    if !page_errors.is_empty() {
+        ic_cdk::println!(
+            "PDF {}: text of {} of {} pages could not be extracted",
+            filename,
+            page_errors.len(),
+            pages.len()
+        );
+    }
+
+    if content.is_empty() {
+        return Err(ExtractionError::parse(
+            "pdf",
+            "No extractable text found in PDF. This may be an image-based PDF, or contains only graphics.",
+        )
+        .into());
     }
 
     let title = get_filename_without_extension(filename);
@@ -127,34 +171,226 @@ fn extract_pdf_file(file_data: &[u8], filename: &str) -> ClanopediaResult<Extrac
     // Extract metadata using lopdf
     let metadata = extract_pdf_metadata_lopdf(&doc, file_data);
 
-    Ok(ExtractionResult {
-        title: metadata.title.unwrap_or(title),
-        content: sanitize_content(&text),
-        content_type: ContentType::PlainText,
-        source_url: None,
-        metadata: Some(ExtractionMetadata {
-            file_size: Some(file_data.len() as u64),
-            page_count: Some(pages.len() as u32),
-            author: metadata.author,
-            created_at: Some(ic_cdk::api::time()),
-            tags: None,
-        }),
-    })
+    // Bookmarks are optional; a PDF without an outline simply yields none
+    let bookmarks: Vec<(usize, String, u32)> = doc
+        .get_toc()
+        .map(|toc| {
+            toc.toc
+                .into_iter()
+                .map(|entry| (entry.level, entry.title, entry.page as u32))
+                .collect()
+        })
+        .unwrap_or_default();
+    let outline = pdf_outline(&bookmarks, &page_offsets, &content);
+
+    let title = metadata.title.unwrap_or(title);
+    let file_metadata = ExtractionMetadata {
+        file_size: Some(file_data.len() as u64),
+        page_count: Some(pages.len() as u32),
+        author: metadata.author,
+        created_at: Some(ic_cdk::api::time()),
+        tags: None,
+        page_errors,
+    };
+
+    let sections = pdf_sections(&outline, &page_offsets);
+    if sections.len() < 2 {
+        return Ok(vec![ExtractionResult {
+            title,
+            content,
+            content_type: ContentType::PlainText,
+            source_url: None,
+            outline: Some(outline),
+            metadata: Some(file_metadata),
+        }]);
+    }
+
+    let content_chars = content.chars().count() as u64;
+    let mut results = Vec::with_capacity(sections.len());
+    for (i, (section_title, start)) in sections.iter().enumerate() {
+        let end = sections.get(i + 1).map_or(content_chars, |(_, next)| *next);
+        let (Some(start_byte), Some(end_byte)) =
+            (char_to_byte(&content, *start), char_to_byte(&content, end))
+        else {
+            continue;
+        };
+        let section_content = content[start_byte..end_byte].trim_end().to_string();
+        if section_content.is_empty() {
+            continue;
+        }
+        let section_outline = outline
+            .iter()
+            .filter(|entry| entry.offset >= *start && entry.offset < end)
+            .map(|entry| OutlineEntry {
+                offset: entry.offset - start,
+                ..entry.clone()
+            })
+            .collect();
+
+        results.push(ExtractionResult {
+            title: format!("{} - {}", title, section_title),
+            content: section_content,
+            content_type: ContentType::PlainText,
+            source_url: None,
+            outline: Some(section_outline),
+            metadata: Some(file_metadata.clone()),
+        });
+    }
+
+    Ok(results)
+}
+
+/// Load a PDF document from memory using lopdf, decrypting it when it only has an owner
+/// password
+fn load_pdf(file_data: &[u8]) -> ClanopediaResult<Document> {
+    let mut doc = Document::load_mem(file_data).map_err(|e| {
+        if file_data
+            .windows(b"/Encrypt".len())
+            .any(|w| w == b"/Encrypt")
+        {
+            encrypted_pdf_error()
+        } else {
+            ExtractionError::parse("pdf", format!("Invalid PDF file: {}", e)).into()
+        }
+    })?;
+
+    // Owner-password PDFs open with an empty user password; anything else cannot be read
+    if doc.is_encrypted() && doc.decrypt("").is_err() {
+        return Err(encrypted_pdf_error());
+    }
+    Ok(doc)
+}
+
+/// An embedded page image in a format OCR services accept as-is
+pub struct PageImage {
+    pub page: u32,
+    pub mime: &'static str,
+    pub data: Vec<u8>,
+}
+
+/// The page count of a PDF and the largest JPEG or JPEG 2000 image on each page, for OCR.
+/// Images in other encodings would need re-encoding, so their pages are left out.
+pub fn pdf_page_images(file_data: &[u8]) -> ClanopediaResult<(u32, Vec<PageImage>)> {
+    let doc = load_pdf(file_data)?;
+    let pages = doc.get_pages();
+    let mut images = Vec::new();
+
+    for (page_num, page_id) in pages.iter() {
+        let (direct, resource_ids) = doc.get_page_resources(*page_id);
+        let resources = direct.into_iter().chain(
+            resource_ids
+                .iter()
+                .filter_map(|id| doc.get_dictionary(*id).ok()),
+        );
+
+        let mut largest: Option<PageImage> = None;
+        for resource in resources {
+            let Ok((_, xobjects)) = resource
+                .get(b"XObject")
+                .and_then(|object| doc.dereference(object))
+            else {
+                continue;
+            };
+            let Ok(xobjects) = xobjects.as_dict() else {
+                continue;
+            };
+
+            for (_, object) in xobjects.iter() {
+                let Ok((_, Object::Stream(stream))) = doc.dereference(object) else {
+                    continue;
+                };
+                if stream.dict.get(b"Subtype").and_then(Object::as_name).ok() != Some(b"Image") {
+                    continue;
+                }
+                let filter = stream
+                    .dict
+                    .get(b"Filter")
+                    .ok()
+                    .and_then(|filter| match filter {
+                        Object::Array(filters) if filters.len() == 1 => filters[0].as_name().ok(),
+                        filter => filter.as_name().ok(),
+                    });
+                let mime = match filter {
+                    Some(b"DCTDecode") => "image/jpeg",
+                    Some(b"JPXDecode") => "image/jp2",
+                    _ => continue,
+                };
+                if largest
+                    .as_ref()
+                    .is_none_or(|image| stream.content.len() > image.data.len())
+                {
+                    largest = Some(PageImage {
+                        page: *page_num,
+                        mime,
+                        data: stream.content.clone(),
+                    });
+                }
+            }
+        }
+        images.extend(largest);
+    }
+
+    Ok((pages.len() as u32, images))
+}
+
+fn encrypted_pdf_error() -> ClanopediaError {
+    ExtractionError::AuthRequired(
+        "PDF is encrypted with a password. Remove the password protection and upload it again."
+            .to_string(),
+    )
+    .into()
+}
+
+/// `(title, start offset)` of each section a PDF is split into: its top-level bookmarks,
+/// else runs of `PDF_PAGES_PER_SECTION` pages. The first section always starts at 0 so no
+/// text before the first bookmark is lost. Fewer than two entries means no split.
+fn pdf_sections(outline: &[OutlineEntry], page_offsets: &[(u32, u64)]) -> Vec<(String, u64)> {
+    let mut sections: Vec<(String, u64)> = Vec::new();
+
+    if let Some(top_level) = outline.iter().map(|entry| entry.level).min() {
+        let mut top: Vec<&OutlineEntry> = outline
+            .iter()
+            .filter(|entry| entry.level == top_level)
+            .collect();
+        top.sort_by_key(|entry| entry.offset);
+        for entry in top {
+            if sections
+                .last()
+                .is_none_or(|(_, offset)| *offset < entry.offset)
+            {
+                sections.push((entry.title.clone(), entry.offset));
+            }
+        }
+    }
+
+    if sections.len() < 2 && page_offsets.len() > PDF_PAGES_PER_SECTION {
+        sections = page_offsets
+            .chunks(PDF_PAGES_PER_SECTION)
+            .map(|chunk| {
+                let (first, offset) = chunk[0];
+                let last = chunk[chunk.len() - 1].0;
+                (format!("pages {}-{}", first, last), offset)
+            })
+            .collect();
+    }
+
+    if let Some(first) = sections.first_mut() {
+        first.1 = 0;
+    }
+    sections
 }
 
 /// Extract content from DOCX files
 fn extract_docx_file(file_data: &[u8], filename: &str) -> ClanopediaResult<ExtractionResult> {
     let cursor = Cursor::new(file_data);
     let mut archive = ZipArchive::new(cursor)
-        .map_err(|e| ClanopediaError::InvalidInput(format!("Invalid DOCX file: {}", e)))?;
+        .map_err(|e| ExtractionError::parse("docx", format!("Invalid DOCX file: {}", e)))?;
 
     let document_xml = read_document_xml(&mut archive)?;
     let text = parse_docx_xml(&document_xml)?;
 
     if text.trim().is_empty() {
-        return Err(ClanopediaError::InvalidInput(
-            "No text content found in DOCX".to_string(),
-        ));
+        return Err(ExtractionError::parse("docx", "No text content found in DOCX").into());
     }
 
     let title = get_filename_without_extension(filename);
@@ -165,12 +401,14 @@ fn extract_docx_file(file_data: &[u8], filename: &str) -> ClanopediaResult<Extra
         content: sanitize_content(&text),
         content_type: ContentType::PlainText,
         source_url: None,
+        outline: None,
         metadata: Some(ExtractionMetadata {
             file_size: Some(file_data.len() as u64),
             page_count: None,
             author: metadata.author,
             created_at: metadata.created_at,
             tags: metadata.tags,
+            page_errors: Vec::new(),
         }),
     })
 }
@@ -203,9 +441,7 @@ fn extract_markdown_file(file_data: &[u8], filename: &str) -> ClanopediaResult<E
     let sanitized_content = sanitize_content(&content);
 
     if sanitized_content.trim().is_empty() {
-        return Err(ClanopediaError::InvalidInput(
-            "Markdown file is empty".to_string(),
-        ));
+        return Err(ExtractionError::parse("markdown", "Markdown file is empty").into());
     }
 
     let markdown_metadata = parse_markdown_metadata(&content);
@@ -213,17 +449,21 @@ fn extract_markdown_file(file_data: &[u8], filename: &str) -> ClanopediaResult<E
         .title
         .unwrap_or_else(|| get_filename_without_extension(filename));
 
+    let outline = markdown_outline(&sanitized_content);
+
     Ok(ExtractionResult {
         title,
         content: sanitized_content,
         content_type: ContentType::Markdown,
         source_url: None,
+        outline: Some(outline),
         metadata: Some(ExtractionMetadata {
             file_size: Some(file_data.len() as u64),
             page_count: None,
             author: markdown_metadata.author,
             created_at: Some(ic_cdk::api::time()),
             tags: markdown_metadata.tags,
+            page_errors: Vec::new(),
         }),
     })
 }
@@ -235,11 +475,11 @@ fn extract_markdown_file(file_data: &[u8], filename: &str) -> ClanopediaResult<E
 fn read_document_xml(archive: &mut ZipArchive<Cursor<&[u8]>>) -> ClanopediaResult<String> {
     let mut file = archive
         .by_name("word/document.xml")
-        .map_err(|_| ClanopediaError::InvalidInput("No document.xml found in DOCX".to_string()))?;
+        .map_err(|_| ExtractionError::parse("docx", "No document.xml found in DOCX"))?;
 
     let mut document_xml = String::new();
     file.read_to_string(&mut document_xml).map_err(|e| {
-        ClanopediaError::InvalidInput(format!("Failed to read document.xml: {}", e))
+        ExtractionError::parse("docx", format!("Failed to read document.xml: {}", e))
     })?;
 
     Ok(document_xml)
@@ -274,17 +514,16 @@ fn parse_docx_xml(xml_content: &str) -> ClanopediaResult<String> {
             Ok(Event::Text(e)) => {
                 if in_text_element {
                     let text = e.unescape().map_err(|e| {
-                        ClanopediaError::InvalidInput(format!("XML parsing error: {}", e))
+                        ExtractionError::parse("docx", format!("XML parsing error: {}", e))
                     })?;
                     text_content.push_str(&text);
                 }
             }
             Ok(Event::Eof) => break,
             Err(e) => {
-                return Err(ClanopediaError::InvalidInput(format!(
-                    "XML parsing error: {}",
-                    e
-                )))
+                return Err(
+                    ExtractionError::parse("docx", format!("XML parsing error: {}", e)).into(),
+                )
             }
             _ => {}
         }
@@ -292,9 +531,7 @@ fn parse_docx_xml(xml_content: &str) -> ClanopediaResult<String> {
     }
 
     if text_content.trim().is_empty() {
-        return Err(ClanopediaError::InvalidInput(
-            "No text content found in Word document".to_string(),
-        ));
+        return Err(ExtractionError::parse("docx", "No text content found in Word document").into());
     }
 
     let cleaned_text = text_content
@@ -412,6 +649,254 @@ fn parse_keywords(xml_content: &str) -> Option<Vec<String>> {
     None
 }
 
+// ================================
+// RTF processing functions
+// ================================
+
+/// Extract content from RTF files
+fn extract_rtf_file(file_data: &[u8], filename: &str) -> ClanopediaResult<ExtractionResult> {
+    let rtf = parse_rtf(file_data);
+
+    if rtf.text.trim().is_empty() {
+        return Err(ExtractionError::parse("rtf", "No text content found in RTF").into());
+    }
+
+    Ok(ExtractionResult {
+        title: rtf
+            .title
+            .unwrap_or_else(|| get_filename_without_extension(filename)),
+        content: sanitize_content(&rtf.text),
+        content_type: ContentType::PlainText,
+        source_url: None,
+        outline: None,
+        metadata: Some(ExtractionMetadata {
+            file_size: Some(file_data.len() as u64),
+            page_count: None,
+            author: rtf.author,
+            created_at: Some(ic_cdk::api::time()),
+            tags: None,
+            page_errors: Vec::new(),
+        }),
+    })
+}
+
+#[derive(Debug, Default)]
+struct RtfDocument {
+    text: String,
+    title: Option<String>,
+    author: Option<String>,
+}
+
+/// Where the text of the current RTF group goes
+#[derive(Clone, Copy, PartialEq)]
+enum RtfDestination {
+    Body,
+    Title,
+    Author,
+    Info, // Document properties; only the title and author are read
+    Skip, // Font tables, pictures, headers and other groups without document text
+}
+
+/// Destinations whose text is not part of the document body
+const RTF_SKIPPED_DESTINATIONS: [&str; 15] = [
+    "fonttbl",
+    "colortbl",
+    "stylesheet",
+    "listtable",
+    "listoverridetable",
+    "rsidtbl",
+    "generator",
+    "pict",
+    "object",
+    "header",
+    "footer",
+    "headerl",
+    "headerr",
+    "footerl",
+    "footerr",
+];
+
+/// A small RTF reader: keeps body text, paragraph breaks and tabs, decodes `\'hh` (as
+/// Windows-1252) and `\uN` escapes, and reads the title and author from `\info`. Formatting
+/// is dropped.
+fn parse_rtf(data: &[u8]) -> RtfDocument {
+    let mut document = RtfDocument::default();
+    let mut title = String::new();
+    let mut author = String::new();
+    // (destination, \ucN skip count) for each open group
+    let mut stack: Vec<(RtfDestination, usize)> = Vec::new();
+    let mut destination = RtfDestination::Body;
+    let mut unicode_skip = 1usize;
+    let mut pending_skip = 0usize;
+    let mut group_start = false;
+    let mut bytes = Vec::new(); // Raw 8-bit text, decoded as Windows-1252 when flushed
+
+    let flush = |bytes: &mut Vec<u8>, out: &mut String| {
+        if !bytes.is_empty() {
+            let (decoded, _, _) = encoding_rs::WINDOWS_1252.decode(bytes);
+            out.push_str(&decoded);
+            bytes.clear();
+        }
+    };
+
+    let mut i = 0;
+    while i < data.len() {
+        let target: Option<&mut String> = match destination {
+            RtfDestination::Body => Some(&mut document.text),
+            RtfDestination::Title => Some(&mut title),
+            RtfDestination::Author => Some(&mut author),
+            RtfDestination::Info | RtfDestination::Skip => None,
+        };
+
+        match data[i] {
+            b'{' => {
+                if let Some(out) = target {
+                    flush(&mut bytes, out);
+                }
+                stack.push((destination, unicode_skip));
+                group_start = true;
+                i += 1;
+                continue;
+            }
+            b'}' => {
+                if let Some(out) = target {
+                    flush(&mut bytes, out);
+                }
+                (destination, unicode_skip) = stack.pop().unwrap_or((RtfDestination::Body, 1));
+                pending_skip = 0;
+                i += 1;
+            }
+            b'\\' if i + 1 < data.len() => {
+                let next = data[i + 1];
+                if next.is_ascii_alphabetic() {
+                    let start = i + 1;
+                    let mut end = start;
+                    while end < data.len() && data[end].is_ascii_alphabetic() {
+                        end += 1;
+                    }
+                    let word = std::str::from_utf8(&data[start..end]).unwrap_or("");
+                    let mut param_end = end;
+                    if param_end < data.len() && data[param_end] == b'-' {
+                        param_end += 1;
+                    }
+                    while param_end < data.len() && data[param_end].is_ascii_digit() {
+                        param_end += 1;
+                    }
+                    let param = std::str::from_utf8(&data[end..param_end])
+                        .ok()
+                        .and_then(|p| p.parse::<i32>().ok());
+                    i = param_end;
+                    // A single space delimits the control word and is not text
+                    if i < data.len() && data[i] == b' ' {
+                        i += 1;
+                    }
+
+                    if group_start {
+                        destination = match (destination, word) {
+                            (RtfDestination::Info, "title") => RtfDestination::Title,
+                            (RtfDestination::Info, "author") => RtfDestination::Author,
+                            (_, "info") => RtfDestination::Info,
+                            (_, word) if RTF_SKIPPED_DESTINATIONS.contains(&word) => {
+                                RtfDestination::Skip
+                            }
+                            (destination, _) => destination,
+                        };
+                    }
+                    group_start = false;
+
+                    let out = match destination {
+                        RtfDestination::Body => &mut document.text,
+                        RtfDestination::Title => &mut title,
+                        RtfDestination::Author => &mut author,
+                        RtfDestination::Info | RtfDestination::Skip => continue,
+                    };
+                    match word {
+                        "par" | "line" | "sect" | "page" => {
+                            flush(&mut bytes, out);
+                            out.push('\n');
+                        }
+                        "tab" | "cell" => {
+                            flush(&mut bytes, out);
+                            out.push('\t');
+                        }
+                        "row" => {
+                            flush(&mut bytes, out);
+                            out.push('\n');
+                        }
+                        "uc" => unicode_skip = param.unwrap_or(1).max(0) as usize,
+                        "u" => {
+                            flush(&mut bytes, out);
+                            // Negative values wrap: \u-3913 is U+F0B7
+                            let code = param.unwrap_or(0) as i64;
+                            let code = if code < 0 { code + 65_536 } else { code };
+                            out.extend(char::from_u32(code as u32));
+                            pending_skip = unicode_skip;
+                        }
+                        _ => {}
+                    }
+                    continue;
+                }
+
+                group_start = false;
+                match next {
+                    b'*' => {
+                        // An ignorable destination this reader does not know
+                        destination = RtfDestination::Skip;
+                        i += 2;
+                    }
+                    b'\'' => {
+                        let hex = data
+                            .get(i + 2..i + 4)
+                            .and_then(|hex| std::str::from_utf8(hex).ok())
+                            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+                        if let Some(byte) = hex {
+                            if pending_skip > 0 {
+                                pending_skip -= 1;
+                            } else if target.is_some() {
+                                bytes.push(byte);
+                            }
+                        }
+                        i += 4;
+                    }
+                    b'~' => {
+                        bytes.push(0xA0); // Non-breaking space
+                        i += 2;
+                    }
+                    b'\\' | b'{' | b'}' => {
+                        if target.is_some() {
+                            bytes.push(next);
+                        }
+                        i += 2;
+                    }
+                    _ => i += 2, // \-, \_ and other control symbols carry no text
+                }
+                continue;
+            }
+            b'\r' | b'\n' => i += 1,
+            byte => {
+                if pending_skip > 0 {
+                    pending_skip -= 1;
+                } else if target.is_some() {
+                    bytes.push(byte);
+                }
+                i += 1;
+            }
+        }
+        group_start = false;
+    }
+
+    let out = match destination {
+        RtfDestination::Title => &mut title,
+        RtfDestination::Author => &mut author,
+        _ => &mut document.text,
+    };
+    flush(&mut bytes, out);
+
+    document.title = Some(title.trim().to_string()).filter(|t| !t.is_empty());
+    document.author = Some(author.trim().to_string()).filter(|a| !a.is_empty());
+    document
+}
+
 // ================================
 // PDF metadata extraction using lopdf
 // ================================
@@ -598,9 +1083,7 @@ fn create_text_result(content: String, filename: &str) -> ClanopediaResult<Extra
     let sanitized_content = sanitize_content(&content);
 
     if sanitized_content.trim().is_empty() {
-        return Err(ClanopediaError::InvalidInput(
-            "Text file is empty".to_string(),
-        ));
+        return Err(ExtractionError::parse("text", "Text file is empty").into());
     }
 
     let title = get_filename_without_extension(filename);
@@ -610,12 +1093,14 @@ fn create_text_result(content: String, filename: &str) -> ClanopediaResult<Extra
         content: sanitized_content,
         content_type: ContentType::PlainText,
         source_url: None,
+        outline: None,
         metadata: Some(ExtractionMetadata {
             file_size: Some(content.len() as u64),
             page_count: None,
             author: None,
             created_at: Some(ic_cdk::api::time()),
             tags: None,
+            page_errors: Vec::new(),
         }),
     })
 }
@@ -0,0 +1,254 @@
+// src/extractor/google_docs.rs
+//
+// Google Docs shared with "anyone with the link" are fetched from their plain-text export;
+// documents "published to the web" only serve HTML, which is reduced to text here. Private
+// documents redirect to a sign-in page, which the outcall reports as a non-2xx response.
+
+use ic_cdk::api::management_canister::http_request::{HttpResponse, TransformArgs};
+use ic_cdk_macros::query;
+
+use crate::external::blueband::ContentType;
+use crate::extractor::url_extractor::http_get;
+use crate::extractor::{http_provenance, ExtractionError, sanitize_content, validate_content_size};
+use crate::outcall_budget;
+use crate::{AddDocumentRequest, ClanopediaError, ClanopediaResult};
+
+const DOCS_BASE: &str = "https://docs.google.com/document/d/";
+
+/// Where a Google Docs URL's text is served from
+enum DocsExport {
+    /// `/document/d/{id}/...`: the plain-text export
+    Text(String),
+    /// `/document/d/e/{id}/pub`: the published HTML page
+    Published(String),
+}
+
+impl DocsExport {
+    fn from_url(url: &str) -> ClanopediaResult<Self> {
+        let path = url
+            .split_once("docs.google.com/document/d/")
+            .map(|(_, path)| path)
+            .ok_or_else(|| {
+                ClanopediaError::InvalidInput(
+                    "Only Google Docs documents are supported. Expected docs.google.com/document/d/..."
+                        .to_string(),
+                )
+            })?;
+        let mut segments = path
+            .split(['/', '?', '#'])
+            .filter(|segment| !segment.is_empty());
+
+        let export = match segments.next() {
+            Some("e") => segments
+                .next()
+                .map(|id| DocsExport::Published(id.to_string())),
+            Some(id) => Some(DocsExport::Text(id.to_string())),
+            None => None,
+        };
+        export.ok_or_else(|| {
+            ClanopediaError::InvalidInput(
+                "Could not extract document ID from Google Docs URL".to_string(),
+            )
+        })
+    }
+
+    fn fetch_url(&self) -> String {
+        match self {
+            DocsExport::Text(id) => format!("{}{}/export?format=txt", DOCS_BASE, id),
+            DocsExport::Published(id) => format!("{}e/{}/pub", DOCS_BASE, id),
+        }
+    }
+}
+
+/// Extract the text of a Google Docs document shared by link or published to the web
+pub async fn extract_google_docs_content(
+    url: &str,
+    collection_id: &str,
+) -> ClanopediaResult<Vec<AddDocumentRequest>> {
+    let export = DocsExport::from_url(url)?;
+    outcall_budget::spend(collection_id, 1)?;
+
+    let fetch_url = export.fetch_url();
+    ic_cdk::println!("Fetching Google Docs export from: {}", fetch_url);
+
+    let accept = match export {
+        DocsExport::Text(_) => "text/plain",
+        DocsExport::Published(_) => "text/html",
+    };
+    let response = http_get(
+        &fetch_url,
+        accept,
+        "transform_google_docs_response",
+        "Google Docs",
+    )
+    .await?;
+    let body = String::from_utf8_lossy(&response.body).into_owned();
+
+    let (title, content) = match export {
+        DocsExport::Text(_) => (
+            disposition_filename(&response).map(|name| strip_extension(&name)),
+            body.trim_start_matches('\u{feff}').to_string(),
+        ),
+        DocsExport::Published(_) => (html_title(&body), html_to_text(&body)),
+    };
+
+    if content.trim().is_empty() {
+        return Err(ExtractionError::parse("text", "Google Docs document is empty").into());
+    }
+
+    validate_content_size(collection_id, &content)?;
+
+    let document = AddDocumentRequest {
+        collection_id: collection_id.to_string(),
+        title: title
+            .filter(|title| !title.trim().is_empty())
+            .unwrap_or_else(|| "google_docs_document".to_string()),
+        content: sanitize_content(&content),
+        content_type: Some(ContentType::PlainText),
+        source_url: Some(url.to_string()),
+        author: None,
+        tags: Some(vec!["google-docs".to_string()]),
+        outline: None,
+        summary: None,
+        license: None,
+        provenance: Some(http_provenance("google-docs", &fetch_url, &response)),
+    };
+
+    ic_cdk::println!(
+        "Successfully extracted Google Docs content: {} characters",
+        content.len()
+    );
+
+    Ok(vec![document])
+}
+
+/// The export's file name from `Content-Disposition`, preferring the UTF-8 `filename*` form
+fn disposition_filename(response: &HttpResponse) -> Option<String> {
+    let disposition = response
+        .headers
+        .iter()
+        .find(|header| header.name.eq_ignore_ascii_case("content-disposition"))?;
+
+    let mut plain = None;
+    for param in disposition.value.split(';').map(str::trim) {
+        if let Some(encoded) = param.strip_prefix("filename*=") {
+            let encoded = encoded.split_once("''").map_or(encoded, |(_, value)| value);
+            return Some(percent_decode(encoded));
+        }
+        if let Some(value) = param.strip_prefix("filename=") {
+            plain = Some(value.trim_matches('"').to_string());
+        }
+    }
+    plain
+}
+
+fn strip_extension(filename: &str) -> String {
+    filename
+        .rsplit_once('.')
+        .map_or(filename, |(stem, _)| stem)
+        .to_string()
+}
+
+fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = (bytes[i] == b'%')
+            .then(|| value.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match hex {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+fn html_title(html: &str) -> Option<String> {
+    let start = html.find("<title>")? + "<title>".len();
+    let end = start + html[start..].find("</title>")?;
+    let title = decode_entities(html[start..end].trim());
+    Some(title.trim_end_matches(" - Google Docs").to_string())
+}
+
+/// Reduce an HTML page to its text: block elements become line breaks, script and style
+/// bodies are dropped and common entities are decoded
+fn html_to_text(html: &str) -> String {
+    const BLOCK_TAGS: [&str; 12] = [
+        "p", "div", "br", "li", "tr", "h1", "h2", "h3", "h4", "h5", "h6", "table",
+    ];
+
+    let body = html.find("<body").map_or(html, |start| &html[start..]);
+    let mut text = String::new();
+    let mut rest = body;
+    let mut skip_until: Option<&str> = None;
+
+    while let Some(open) = rest.find('<') {
+        if skip_until.is_none() {
+            text.push_str(&decode_entities(&rest[..open]));
+        }
+        let Some(close) = rest[open..].find('>') else {
+            break;
+        };
+        let tag = rest[open + 1..open + close].trim().to_ascii_lowercase();
+        rest = &rest[open + close + 1..];
+
+        let name = tag
+            .trim_start_matches('/')
+            .split(|c: char| c.is_whitespace() || c == '/')
+            .next()
+            .unwrap_or("");
+        if let Some(end) = skip_until {
+            if tag.starts_with('/') && name == end {
+                skip_until = None;
+            }
+            continue;
+        }
+        match name {
+            "script" if !tag.starts_with('/') => skip_until = Some("script"),
+            "style" if !tag.starts_with('/') => skip_until = Some("style"),
+            _ if BLOCK_TAGS.contains(&name) && !text.ends_with('\n') => text.push('\n'),
+            _ => {}
+        }
+    }
+    if skip_until.is_none() {
+        text.push_str(&decode_entities(rest));
+    }
+
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn decode_entities(text: &str) -> String {
+    text.replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&")
+}
+
+/// Transform function for Google Docs responses. Google sets per-request headers (CSP
+/// nonces, cookies) that differ between replicas, so only the content headers are kept.
+#[query]
+fn transform_google_docs_response(args: TransformArgs) -> HttpResponse {
+    let mut response = args.response;
+
+    response.headers.retain(|header| {
+        let name_lower = header.name.to_lowercase();
+        name_lower == "content-type" || name_lower == "content-disposition"
+    });
+
+    response
+}
@@ -0,0 +1,556 @@
+// src/extractor/jobs.rs
+//
+// Queued URL extractions. `enqueue` returns at once; the global timer runs one slice at a
+// time, and a slice is one call to the URL extractor, which fetches a single batch and
+// records where to resume in `ExtractionProgress`. Active jobs take turns, least recently
+// run first.
+//
+// Document additions use the same queue: the documents wait in the job's document list and
+// each slice adds a few of them to Blueband, recording how each went, until the last one
+// raises the embed proposal.
+
+use std::cell::Cell;
+
+use candid::Principal;
+use ic_cdk::api::time;
+
+use super::{
+    retry, url_extractor, AdditionError, AdditionOutcome, DocumentAddition, ExtractionJob,
+    ExtractionJobState, ExtractionJobStatus, ExtractionSource, ExtractionStatus, Extractor,
+    JobProgress,
+};
+use crate::{
+    admin, archive, auth, content_limits, encryption, governance, licensing, logging, secrets,
+    source_policy,
+    stats::{self, StatEvent},
+    storage, trash, validation, AddDocumentRequest, Capability, ClanopediaError,
+    ClanopediaResult, Collection, ProposalType, StorageMap,
+};
+
+const MAX_ACTIVE_JOBS: usize = 20;
+const MAX_ACTIVE_JOBS_PER_COLLECTION: usize = 3;
+const MAX_DOCUMENT_PAGE: u32 = 50;
+const SLICE_INTERVAL_NANOS: u64 = 1_000_000_000;
+const MAX_ADDITION_DOCUMENTS: usize = 500;
+const ADDITION_BATCH: usize = 5; // Documents added per slice
+const MAX_OUTCOME_ERROR_CHARS: usize = 500;
+
+thread_local! {
+    // Set while a slice is awaiting its outcall, so an early timer cannot start another
+    static SLICE_RUNNING: Cell<bool> = const { Cell::new(false) };
+}
+
+struct SliceGuard;
+
+impl SliceGuard {
+    fn acquire() -> Option<Self> {
+        (!SLICE_RUNNING.with(|r| r.replace(true))).then_some(SliceGuard)
+    }
+}
+
+impl Drop for SliceGuard {
+    fn drop(&mut self) {
+        SLICE_RUNNING.with(|r| r.set(false));
+    }
+}
+
+fn active_jobs() -> Vec<ExtractionJob> {
+    let mut jobs: Vec<ExtractionJob> = storage::list_extraction_jobs()
+        .into_iter()
+        .filter(ExtractionJob::is_active)
+        .collect();
+    jobs.sort_by_key(|j| (j.updated_at, j.id));
+    jobs
+}
+
+// The job's requester, the collection's admins and operators who could have queued it may
+// see and manage it
+fn get_authorized_job(caller: Principal, job_id: u64) -> ClanopediaResult<ExtractionJob> {
+    let job = storage::get_extraction_job(job_id)
+        .ok_or_else(|| ClanopediaError::NotFound(format!("Extraction job {} not found", job_id)))?;
+    let is_operator = storage::get_collection(&job.collection_id)
+        .map(|c| auth::has_capability(&c, caller, job.capability()))
+        .unwrap_or(false);
+    if job.requested_by != caller && !is_operator {
+        return Err(ClanopediaError::NotAuthorized);
+    }
+    Ok(job)
+}
+
+// ============================
+// QUEUEING
+// ============================
+
+pub fn enqueue(
+    caller: Principal,
+    collection_id: String,
+    source: ExtractionSource,
+) -> ClanopediaResult<u64> {
+    let collection = storage::get_collection(&collection_id)?;
+    auth::authorize(&collection, caller, Capability::Extract)?;
+    trash::ensure_not_trashed(&collection)?;
+    admin::ensure_not_paused(&collection)?;
+    archive::ensure_not_archived(&collection)?;
+
+    let ExtractionSource::Url {
+        url,
+        api_key_secret,
+    } = source
+    else {
+        return Err(ClanopediaError::InvalidInput(
+            "File sources are extracted immediately; use extract_from_file".to_string(),
+        ));
+    };
+    source_policy::check_url(&collection, &url)?;
+    // Fail now rather than in the first slice if the secret is missing
+    secrets::resolve_secret(&collection_id, api_key_secret.as_deref())?;
+
+    let active = active_jobs();
+    if active
+        .iter()
+        .any(|j| j.collection_id == collection_id && j.url == url)
+    {
+        return Err(ClanopediaError::AlreadyExists(format!(
+            "An extraction job for {} is already queued",
+            url
+        )));
+    }
+    ensure_queue_room(&active, &collection_id)?;
+    storage::ensure_capacity(StorageMap::ExtractionJobs, 1)?;
+    storage::ensure_capacity(StorageMap::ExtractionProgress, 1)?;
+
+    // A finished extraction of the same URL starts over instead of resuming past the end
+    if Extractor::get_progress(&collection_id, &url)
+        .is_some_and(|p| p.status == ExtractionStatus::Completed)
+    {
+        Extractor::remove_progress(&collection_id, &url);
+    }
+
+    let now = time();
+    let job = ExtractionJob {
+        id: storage::next_extraction_job_id(),
+        collection_id,
+        url,
+        api_key_secret,
+        requested_by: caller,
+        state: ExtractionJobState::Queued,
+        created_at: now,
+        updated_at: now,
+        slices: 0,
+        documents: 0,
+        addition: None,
+        error: None,
+        retry_at: None,
+        deferred_retries: None,
+    };
+    storage::put_extraction_job(&job);
+    crate::timers::schedule();
+    Ok(job.id)
+}
+
+fn ensure_queue_room(active: &[ExtractionJob], collection_id: &str) -> ClanopediaResult<()> {
+    if active.len() >= MAX_ACTIVE_JOBS
+        || active
+            .iter()
+            .filter(|j| j.collection_id == collection_id)
+            .count()
+            >= MAX_ACTIVE_JOBS_PER_COLLECTION
+    {
+        return Err(ClanopediaError::RateLimited(
+            "Extraction queue is full; try again once queued jobs finish".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Queue documents to be added to Blueband in the background. They are checked now as
+/// `add_extracted_documents` would check them; follow the job with `get_progress`.
+pub fn enqueue_addition(
+    caller: Principal,
+    collection_id: String,
+    mut documents: Vec<AddDocumentRequest>,
+) -> ClanopediaResult<u64> {
+    let collection = storage::get_collection(&collection_id)?;
+    auth::authorize(&collection, caller, Capability::AddDocuments)?;
+    trash::ensure_not_trashed(&collection)?;
+    admin::ensure_not_paused(&collection)?;
+    archive::ensure_not_archived(&collection)?;
+
+    if documents.is_empty() {
+        return Err(ClanopediaError::InvalidInput(
+            "No documents to add".to_string(),
+        ));
+    }
+    if documents.len() > MAX_ADDITION_DOCUMENTS {
+        return Err(ClanopediaError::InvalidInput(format!(
+            "Too many documents: {} (max: {})",
+            documents.len(),
+            MAX_ADDITION_DOCUMENTS
+        )));
+    }
+    validation::validate_documents(&mut documents)?;
+    licensing::check_documents(&collection, &documents)?;
+    content_limits::check_documents(&collection, &documents)?;
+    if encryption::is_encrypted(&collection) {
+        for document in &documents {
+            encryption::validate_encrypted_content(&collection, &document.content)?;
+        }
+    }
+
+    ensure_queue_room(&active_jobs(), &collection_id)?;
+    let total = documents.len() as u32;
+    crate::ensure_document_capacity(total as u64)?;
+    storage::ensure_capacity(StorageMap::ExtractionJobs, 1)?;
+    storage::ensure_capacity(StorageMap::JobDocuments, total as u64)?;
+    storage::ensure_capacity(StorageMap::JobOutcomes, total as u64)?;
+
+    let now = time();
+    let job = ExtractionJob {
+        id: storage::next_extraction_job_id(),
+        collection_id,
+        url: String::new(),
+        api_key_secret: None,
+        requested_by: caller,
+        state: ExtractionJobState::Queued,
+        created_at: now,
+        updated_at: now,
+        slices: 0,
+        documents: total,
+        addition: Some(DocumentAddition {
+            total,
+            processed: 0,
+            failed: 0,
+            current_title: None,
+            proposal_id: None,
+        }),
+        error: None,
+        retry_at: None,
+        deferred_retries: None,
+    };
+    storage::append_job_documents(&job.collection_id, job.id, 0, documents);
+    storage::put_extraction_job(&job);
+    crate::timers::schedule();
+    Ok(job.id)
+}
+
+pub fn get_status(caller: Principal, job_id: u64) -> ClanopediaResult<ExtractionJobStatus> {
+    let job = get_authorized_job(caller, job_id)?;
+    let queue_position = job.is_active().then(|| {
+        active_jobs()
+            .iter()
+            .position(|j| j.id == job.id)
+            .unwrap_or(0) as u32
+    });
+    Ok(ExtractionJobStatus {
+        progress: Extractor::get_progress(&job.collection_id, &job.url),
+        job,
+        queue_position,
+    })
+}
+
+/// Where a document addition job has got: the document being added, and the IDs and errors
+/// of those already processed
+pub fn get_progress(caller: Principal, job_id: u64) -> ClanopediaResult<JobProgress> {
+    let job = get_authorized_job(caller, job_id)?;
+    let Some(addition) = job.addition.clone() else {
+        return Err(ClanopediaError::InvalidInput(format!(
+            "Job {} extracts a URL; use get_job_status",
+            job_id
+        )));
+    };
+
+    let mut document_ids = Vec::new();
+    let mut errors = Vec::new();
+    for (index, outcome) in storage::list_job_outcomes(&job.collection_id, job.id) {
+        match outcome {
+            AdditionOutcome::Added(document_id) => document_ids.push(document_id),
+            AdditionOutcome::Failed(error) => errors.push(AdditionError {
+                index,
+                title: storage::list_job_documents(&job.collection_id, job.id, index as usize, 1)
+                    .pop()
+                    .map(|d| d.title)
+                    .unwrap_or_default(),
+                error,
+            }),
+        }
+    }
+
+    let queue_position = job.is_active().then(|| {
+        active_jobs()
+            .iter()
+            .position(|j| j.id == job.id)
+            .unwrap_or(0) as u32
+    });
+    Ok(JobProgress {
+        job_id: job.id,
+        state: job.state,
+        total: addition.total,
+        processed: addition.processed,
+        current_title: addition.current_title,
+        document_ids,
+        errors,
+        proposal_id: addition.proposal_id,
+        queue_position,
+    })
+}
+
+/// Documents a job has extracted so far, ready for `add_extracted_documents`
+pub fn get_documents(
+    caller: Principal,
+    job_id: u64,
+    offset: u32,
+    limit: u32,
+) -> ClanopediaResult<Vec<AddDocumentRequest>> {
+    let job = get_authorized_job(caller, job_id)?;
+    Ok(storage::list_job_documents(
+        &job.collection_id,
+        job.id,
+        offset as usize,
+        limit.min(MAX_DOCUMENT_PAGE) as usize,
+    ))
+}
+
+pub fn cancel(caller: Principal, job_id: u64) -> ClanopediaResult<()> {
+    let mut job = get_authorized_job(caller, job_id)?;
+    if !job.is_active() {
+        return Err(ClanopediaError::InvalidOperation(format!(
+            "Extraction job {} has already finished",
+            job_id
+        )));
+    }
+    job.state = ExtractionJobState::Cancelled;
+    job.updated_at = time();
+    storage::put_extraction_job(&job);
+    Ok(())
+}
+
+/// Drop a finished job and the documents it holds
+pub fn clear(caller: Principal, job_id: u64) -> ClanopediaResult<()> {
+    let job = get_authorized_job(caller, job_id)?;
+    if job.is_active() {
+        return Err(ClanopediaError::InvalidOperation(format!(
+            "Extraction job {} is still running; cancel it first",
+            job_id
+        )));
+    }
+    storage::remove_job_documents(&job.collection_id, job.id);
+    storage::remove_extraction_job(job.id);
+    Ok(())
+}
+
+// ============================
+// WORKER
+// ============================
+
+/// When the worker should run next, if any job is waiting
+pub fn next_slice_at() -> Option<u64> {
+    let earliest = time() + SLICE_INTERVAL_NANOS;
+    storage::list_extraction_jobs()
+        .iter()
+        .filter(|j| j.is_active())
+        .map(|j| j.retry_at.map_or(earliest, |at| at.max(earliest)))
+        .min()
+}
+
+fn finish(mut job: ExtractionJob, state: ExtractionJobState) {
+    job.state = state;
+    job.updated_at = time();
+    storage::put_extraction_job(&job);
+}
+
+// Messages are cut short so the job stays within its storage bound
+fn fail(mut job: ExtractionJob, error: ClanopediaError) {
+    job.error = error
+        .extraction_error()
+        .map(|e| e.truncated(MAX_OUTCOME_ERROR_CHARS));
+    let message = error.to_string().chars().take(MAX_OUTCOME_ERROR_CHARS).collect();
+    finish(job, ExtractionJobState::Failed(message))
+}
+
+/// Run one batch of the job that has waited longest
+pub async fn run_slice() {
+    let Some(_guard) = SliceGuard::acquire() else {
+        return;
+    };
+    // Jobs waiting out a transient failure give their turn to the others
+    let now = time();
+    let Some(mut job) = active_jobs()
+        .into_iter()
+        .find(|j| j.retry_at.is_none_or(|at| at <= now))
+    else {
+        return;
+    };
+
+    let collection = match storage::get_collection(&job.collection_id) {
+        Ok(collection) => collection,
+        Err(e) => return fail(job, e),
+    };
+    if job.addition.is_some() {
+        return run_addition_slice(job, collection).await;
+    }
+    // The requester may have lost its operator grant, or the source policy changed, since
+    // queueing the job
+    let checked = auth::authorize(&collection, job.requested_by, Capability::Extract)
+        .and_then(|_| trash::ensure_not_trashed(&collection))
+        .and_then(|_| admin::ensure_not_paused(&collection))
+        .and_then(|_| archive::ensure_not_archived(&collection))
+        .and_then(|_| source_policy::check_url(&collection, &job.url))
+        .and_then(|_| secrets::resolve_secret(&job.collection_id, job.api_key_secret.as_deref()));
+    let api_key = match checked {
+        Ok(api_key) => api_key,
+        Err(e) => return fail(job, e),
+    };
+
+    job.state = ExtractionJobState::Running;
+    job.updated_at = time();
+    job.retry_at = None;
+    storage::put_extraction_job(&job);
+
+    let result =
+        url_extractor::extract_url_content(job.url.clone(), job.collection_id.clone(), api_key)
+            .await;
+
+    // The job may have been cancelled while the batch was being fetched
+    if storage::get_extraction_job(job.id).is_none_or(|j| !j.is_active()) {
+        return;
+    }
+    match result {
+        Ok(documents) => {
+            let (documents, _, _) = crate::run_collection_pipeline(&collection, documents);
+            let added = documents.len() as u32;
+            storage::append_job_documents(&job.collection_id, job.id, job.documents, documents);
+            job.documents += added;
+            job.slices += 1;
+
+            let has_more = Extractor::get_progress(&job.collection_id, &job.url)
+                .is_some_and(|p| p.status == ExtractionStatus::Paused);
+            let state = if has_more {
+                ExtractionJobState::Running
+            } else {
+                ExtractionJobState::Completed
+            };
+            finish(job, state);
+        }
+        // Out of outcall budget; wait behind the other jobs instead of failing
+        Err(ClanopediaError::RateLimited(_)) => finish(job, ExtractionJobState::Running),
+        Err(e) => {
+            let deferred = job.deferred_retries.unwrap_or(0);
+            match retry::retry_at(&job.collection_id, &e, deferred, job.id) {
+                Some(at) => {
+                    job.retry_at = Some(at);
+                    job.deferred_retries = Some(deferred + 1);
+                    finish(job, ExtractionJobState::Running)
+                }
+                None => fail(job, e),
+            }
+        }
+    }
+}
+
+// Add the job's next few documents, then raise the embed proposal once none are left
+async fn run_addition_slice(mut job: ExtractionJob, collection: Collection) {
+    // The requester may have lost its operator grant since queueing the job
+    let checked = auth::authorize(&collection, job.requested_by, Capability::AddDocuments)
+        .and_then(|_| trash::ensure_not_trashed(&collection))
+        .and_then(|_| admin::ensure_not_paused(&collection))
+        .and_then(|_| archive::ensure_not_archived(&collection));
+    if let Err(e) = checked {
+        return fail(job, e);
+    }
+
+    job.state = ExtractionJobState::Running;
+    job.slices += 1;
+    let first = job.addition.as_ref().map_or(0, |a| a.processed);
+    let documents =
+        storage::list_job_documents(&job.collection_id, job.id, first as usize, ADDITION_BATCH);
+
+    for (index, document) in (first..).zip(documents) {
+        if let Some(addition) = job.addition.as_mut() {
+            addition.current_title = Some(document.title.clone());
+        }
+        job.updated_at = time();
+        storage::put_extraction_job(&job);
+
+        let outcome = match crate::stage_documents(&collection, vec![document]).await {
+            Ok(mut ids) => match ids.pop() {
+                Some(document_id) => AdditionOutcome::Added(document_id),
+                None => AdditionOutcome::Failed("Blueband returned no document".to_string()),
+            },
+            Err(e) => {
+                let error: String = e.to_string().chars().take(MAX_OUTCOME_ERROR_CHARS).collect();
+                logging::warn(
+                    "jobs",
+                    Some(&job.collection_id),
+                    format!("Adding document {} of job {} failed: {}", index, job.id, error),
+                );
+                AdditionOutcome::Failed(error)
+            }
+        };
+        let failed = matches!(outcome, AdditionOutcome::Failed(_));
+        storage::set_job_outcome(&job.collection_id, job.id, index, outcome);
+
+        // The job may have been cancelled while the document was being added
+        let Some(stored) = storage::get_extraction_job(job.id) else {
+            return;
+        };
+        job.state = stored.state;
+        if let Some(addition) = job.addition.as_mut() {
+            addition.processed += 1;
+            addition.failed += failed as u32;
+            addition.current_title = None;
+        }
+        job.updated_at = time();
+        storage::put_extraction_job(&job);
+        if !job.is_active() {
+            return;
+        }
+    }
+
+    if job.addition.as_ref().is_some_and(|a| a.processed >= a.total) {
+        complete_addition(job).await;
+    }
+}
+
+async fn complete_addition(mut job: ExtractionJob) {
+    let document_ids: Vec<String> = storage::list_job_outcomes(&job.collection_id, job.id)
+        .into_iter()
+        .filter_map(|(_, outcome)| match outcome {
+            AdditionOutcome::Added(document_id) => Some(document_id),
+            AdditionOutcome::Failed(_) => None,
+        })
+        .collect();
+    if document_ids.is_empty() {
+        return finish(
+            job,
+            ExtractionJobState::Failed("No documents could be added".to_string()),
+        );
+    }
+
+    stats::record(
+        &job.collection_id,
+        StatEvent::DocumentsAdded(document_ids.len() as u64),
+    );
+    let description = format!(
+        "Embed {} extracted documents into the collection. Documents: [{}]",
+        document_ids.len(),
+        document_ids
+            .iter()
+            .take(3)
+            .cloned()
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+    let proposal_type = ProposalType::BatchEmbed { document_ids };
+    let created =
+        governance::create_proposal(&job.collection_id, proposal_type, job.requested_by, description)
+            .await;
+    match created {
+        Ok(proposal_id) => {
+            if let Some(addition) = job.addition.as_mut() {
+                addition.proposal_id = Some(proposal_id);
+            }
+            finish(job, ExtractionJobState::Completed)
+        }
+        Err(e) => fail(job, e),
+    }
+}
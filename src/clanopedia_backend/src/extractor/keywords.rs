@@ -0,0 +1,248 @@
+// src/extractor/keywords.rs
+
+use std::collections::HashMap;
+
+use crate::{
+    extractor::{DocumentTagSuggestions, KeywordSuggestion},
+    AddDocumentRequest, AutoTagConfig, ClanopediaError, ClanopediaResult,
+};
+
+const SUGGESTION_LIMIT: usize = 10;
+const MAX_PHRASE_WORDS: usize = 3;
+const MIN_WORD_CHARS: usize = 3;
+
+// The most frequent function words of each language, used both to guess the language
+// and to keep those words out of inferred tags
+pub const LANGUAGE_STOPWORDS: &[(&str, &[&str])] = &[
+    (
+        "en",
+        &[
+            "the", "and", "of", "to", "is", "in", "that", "it", "for", "with", "as", "was", "on",
+            "are", "this", "be",
+        ],
+    ),
+    (
+        "es",
+        &[
+            "el", "la", "de", "que", "y", "en", "los", "las", "del", "se", "por", "un", "una",
+            "para", "es", "con",
+        ],
+    ),
+    (
+        "fr",
+        &[
+            "le", "la", "les", "de", "des", "et", "est", "un", "une", "du", "en", "que", "pour",
+            "dans", "pas", "sur",
+        ],
+    ),
+    (
+        "de",
+        &[
+            "der", "die", "und", "das", "ist", "nicht", "ein", "eine", "zu", "den", "mit", "von",
+            "sich", "auf", "dem", "des",
+        ],
+    ),
+    (
+        "pt",
+        &[
+            "o", "a", "os", "as", "de", "que", "e", "do", "da", "em", "um", "uma", "para", "com",
+            "não", "por",
+        ],
+    ),
+    (
+        "it",
+        &[
+            "il", "la", "di", "che", "e", "un", "una", "per", "non", "sono", "del", "della", "con",
+            "gli", "le", "è",
+        ],
+    ),
+    (
+        "nl",
+        &[
+            "de", "het", "een", "en", "van", "is", "dat", "niet", "op", "te", "zijn", "met",
+            "voor", "die", "ook", "maar",
+        ],
+    ),
+];
+
+// Common English words not covered above that should end a keyword phrase
+const EXTRA_STOPWORDS: &[&str] = &[
+    "a", "about", "after", "all", "also", "an", "any", "at", "been", "being", "but", "by", "can",
+    "could", "did", "do", "does", "each", "from", "had", "has", "have", "he", "her", "here", "his",
+    "how", "i", "if", "into", "its", "just", "may", "me", "more", "most", "my", "no", "not", "one",
+    "only", "or", "other", "our", "out", "over", "she", "should", "so", "some", "such", "than",
+    "their", "them", "then", "there", "these", "they", "those", "up", "us", "very", "we", "were",
+    "what", "when", "where", "which", "who", "why", "will", "would", "you", "your",
+];
+
+pub fn validate_auto_tag(config: &Option<AutoTagConfig>) -> ClanopediaResult<()> {
+    match config {
+        Some(config) if !(1..=SUGGESTION_LIMIT as u32).contains(&config.max_tags) => {
+            Err(ClanopediaError::InvalidInput(format!(
+                "Auto-tagging needs max_tags between 1 and {}",
+                SUGGESTION_LIMIT
+            )))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Suggest tags for extracted documents. With `auto_tag` set, the top `max_tags`
+/// suggestions are also added to each document's tags.
+pub fn suggest_tags(
+    documents: &mut [AddDocumentRequest],
+    auto_tag: &Option<AutoTagConfig>,
+) -> Vec<DocumentTagSuggestions> {
+    let contents: Vec<&str> = documents.iter().map(|d| d.content.as_str()).collect();
+    // Over-fetch so that dropping tags a document already has still leaves a full list
+    let suggestions = suggest_keywords(&contents, SUGGESTION_LIMIT * 2);
+
+    documents
+        .iter_mut()
+        .zip(suggestions)
+        .enumerate()
+        .map(|(i, (document, keywords))| {
+            let tags = document.tags.get_or_insert_with(Vec::new);
+            let keywords: Vec<KeywordSuggestion> = keywords
+                .into_iter()
+                .filter(|k| !tags.iter().any(|t| t.eq_ignore_ascii_case(&k.keyword)))
+                .take(SUGGESTION_LIMIT)
+                .collect();
+
+            if let Some(auto_tag) = auto_tag {
+                tags.extend(
+                    keywords
+                        .iter()
+                        .take(auto_tag.max_tags as usize)
+                        .map(|k| k.keyword.clone()),
+                );
+            }
+
+            DocumentTagSuggestions {
+                document_index: i as u32,
+                title: document.title.clone(),
+                keywords,
+            }
+        })
+        .collect()
+}
+
+/// Suggest keywords for each document in a batch, best first.
+///
+/// Candidates are scored RAKE-style within each document: content is split into phrases at
+/// stopwords and punctuation, each word scores its co-occurrence degree over its frequency,
+/// and a phrase scores the sum of its words times how often it occurs. Scores are then
+/// weighted by inverse document frequency across the batch, so phrases that appear in every
+/// document (boilerplate, site names) fall behind ones specific to a document.
+pub fn suggest_keywords(contents: &[&str], limit: usize) -> Vec<Vec<KeywordSuggestion>> {
+    let scored: Vec<HashMap<String, f64>> = contents.iter().map(|c| rake_scores(c)).collect();
+
+    let mut document_frequency: HashMap<&str, usize> = HashMap::new();
+    for scores in &scored {
+        for phrase in scores.keys() {
+            *document_frequency.entry(phrase.as_str()).or_default() += 1;
+        }
+    }
+
+    let total = contents.len() as f64;
+    scored
+        .iter()
+        .map(|scores| {
+            let mut suggestions: Vec<KeywordSuggestion> = scores
+                .iter()
+                .map(|(phrase, score)| {
+                    let df = document_frequency[phrase.as_str()] as f64;
+                    KeywordSuggestion {
+                        keyword: phrase.clone(),
+                        score: score * (1.0 + (total / df).ln()),
+                    }
+                })
+                .collect();
+            suggestions.sort_by(|a, b| {
+                b.score
+                    .total_cmp(&a.score)
+                    .then_with(|| a.keyword.cmp(&b.keyword))
+            });
+            suggestions.truncate(limit);
+            suggestions
+        })
+        .collect()
+}
+
+fn rake_scores(content: &str) -> HashMap<String, f64> {
+    let phrases = candidate_phrases(content);
+
+    let mut frequency: HashMap<&str, f64> = HashMap::new();
+    let mut degree: HashMap<&str, f64> = HashMap::new();
+    for phrase in &phrases {
+        for word in phrase {
+            *frequency.entry(word.as_str()).or_default() += 1.0;
+            *degree.entry(word.as_str()).or_default() += phrase.len() as f64;
+        }
+    }
+
+    let mut scores: HashMap<String, (f64, f64)> = HashMap::new();
+    for phrase in &phrases {
+        let score: f64 = phrase
+            .iter()
+            .map(|w| degree[w.as_str()] / frequency[w.as_str()])
+            .sum();
+        let entry = scores.entry(phrase.join(" ")).or_insert((score, 0.0));
+        entry.1 += 1.0;
+    }
+
+    scores
+        .into_iter()
+        .map(|(phrase, (score, occurrences))| (phrase, score * occurrences))
+        .collect()
+}
+
+// Runs of non-stopwords between punctuation, keeping only short phrases of real words
+fn candidate_phrases(content: &str) -> Vec<Vec<String>> {
+    let mut phrases = Vec::new();
+    let segments = content.split(|c: char| !(c.is_alphanumeric() || c.is_whitespace() || c == '-'));
+
+    for segment in segments {
+        let mut current: Vec<String> = Vec::new();
+        for word in segment
+            .split_whitespace()
+            .map(|w| w.trim_matches('-').to_lowercase())
+        {
+            if is_stopword(&word) || !is_keyword_word(&word) {
+                push_phrase(&mut phrases, std::mem::take(&mut current));
+            } else {
+                current.push(word);
+            }
+        }
+        push_phrase(&mut phrases, current);
+    }
+
+    phrases
+}
+
+fn push_phrase(phrases: &mut Vec<Vec<String>>, phrase: Vec<String>) {
+    if !phrase.is_empty() && phrase.len() <= MAX_PHRASE_WORDS {
+        phrases.push(phrase);
+    }
+}
+
+fn is_keyword_word(word: &str) -> bool {
+    word.chars().count() >= MIN_WORD_CHARS
+        && word.chars().any(|c| c.is_alphabetic())
+        && word.chars().all(|c| c.is_alphanumeric() || c == '-')
+}
+
+pub fn is_stopword(word: &str) -> bool {
+    EXTRA_STOPWORDS.contains(&word)
+        || LANGUAGE_STOPWORDS
+            .iter()
+            .any(|(_, stopwords)| stopwords.contains(&word))
+}
+
+/// Lowercased alphanumeric words of `content`
+pub fn words(content: &str) -> impl Iterator<Item = String> + '_ {
+    content
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(|w| w.to_lowercase())
+}
@@ -0,0 +1,148 @@
+// src/extractor/links.rs
+
+use std::collections::HashMap;
+
+use crate::storage;
+use crate::types::{DocumentLinks, LinkEdge, LinkGraph, LinkNode};
+
+// A document's links must fit DocumentLinks' 256KB bound: at most 4 bytes a character, the
+// targets take at most about 205KB
+const MAX_OUTGOING_LINKS: usize = 256;
+const MAX_LINK_TARGET_CHARS: usize = 200;
+
+/// Extract inter-document link targets from `[[wiki-style]]` and relative markdown links.
+/// Targets are normalized with `normalize_link_target`; external URLs are ignored. Only the
+/// first `MAX_OUTGOING_LINKS` targets are kept, and overlong ones are dropped.
+pub fn extract_links(content: &str) -> Vec<String> {
+    let mut targets = Vec::new();
+
+    // [[Target]] and [[Target|alias]]
+    let mut rest = content;
+    while let Some(start) = rest.find("[[") {
+        let after = &rest[start + 2..];
+        match after.find("]]") {
+            Some(end) => {
+                let inner = &after[..end];
+                let target = inner.split('|').next().unwrap_or(inner);
+                push_target(&mut targets, target);
+                rest = &after[end + 2..];
+            }
+            None => break,
+        }
+    }
+
+    // [text](target.md)
+    let mut rest = content;
+    while let Some(start) = rest.find("](") {
+        let after = &rest[start + 2..];
+        match after.find(')') {
+            Some(end) => {
+                let target = after[..end].split_whitespace().next().unwrap_or("");
+                if !target.contains("://") && !target.starts_with('#') && !target.starts_with("mailto:") {
+                    push_target(&mut targets, target);
+                }
+                rest = &after[end + 1..];
+            }
+            None => break,
+        }
+    }
+
+    targets
+}
+
+/// Normalize a link target or document title to a comparable key:
+/// drop anchors, directories and markdown extensions, lowercase, collapse separators.
+pub fn normalize_link_target(target: &str) -> String {
+    let target = target.split('#').next().unwrap_or("");
+    let target = target.rsplit('/').next().unwrap_or(target);
+    let target = target
+        .strip_suffix(".md")
+        .or_else(|| target.strip_suffix(".markdown"))
+        .unwrap_or(target);
+
+    target
+        .to_lowercase()
+        .split(|c: char| c.is_whitespace() || c == '-' || c == '_')
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn push_target(targets: &mut Vec<String>, raw: &str) {
+    if targets.len() >= MAX_OUTGOING_LINKS {
+        return;
+    }
+    let normalized = normalize_link_target(raw.trim());
+    if !normalized.is_empty()
+        && normalized.chars().count() <= MAX_LINK_TARGET_CHARS
+        && !targets.contains(&normalized)
+    {
+        targets.push(normalized);
+    }
+}
+
+// ============================
+// LINK GRAPH
+// ============================
+
+/// Record the outgoing links of a newly added document
+pub fn record_document_links(collection_id: &str, document_id: &str, title: &str, content: &str) {
+    let links = DocumentLinks {
+        title: title.to_string(),
+        key: normalize_link_target(title),
+        outgoing: extract_links(content),
+        updated_at: ic_cdk::api::time(),
+    };
+    storage::set_document_links(collection_id, document_id, links);
+}
+
+/// Build the collection's link graph, resolving targets by normalized title or document ID
+pub fn link_graph(collection_id: &str) -> LinkGraph {
+    let documents = storage::list_document_links(collection_id);
+
+    let nodes = documents
+        .iter()
+        .map(|(document_id, links)| LinkNode {
+            document_id: document_id.clone(),
+            title: links.title.clone(),
+        })
+        .collect();
+
+    // Normalized titles and lowercased IDs; the first document to claim a key keeps it
+    let mut by_key: HashMap<String, &String> = HashMap::new();
+    for (document_id, links) in &documents {
+        by_key.entry(links.key.clone()).or_insert(document_id);
+        by_key.entry(document_id.to_lowercase()).or_insert(document_id);
+    }
+    let resolve = |target: &str| by_key.get(target).map(|document_id| (*document_id).clone());
+
+    let edges = documents
+        .iter()
+        .flat_map(|(source, links)| {
+            links.outgoing.iter().map(|target| LinkEdge {
+                source: source.clone(),
+                target: resolve(target),
+                target_name: target.clone(),
+            })
+        })
+        .collect();
+
+    LinkGraph { nodes, edges }
+}
+
+/// Documents in the collection that link to `document_id`
+pub fn backlinks(collection_id: &str, document_id: &str) -> Vec<LinkNode> {
+    let graph = link_graph(collection_id);
+    let sources: Vec<&String> = graph
+        .edges
+        .iter()
+        .filter(|edge| edge.target.as_deref() == Some(document_id) && edge.source != document_id)
+        .map(|edge| &edge.source)
+        .collect();
+
+    graph
+        .nodes
+        .into_iter()
+        .filter(|node| sources.contains(&&node.document_id))
+        .collect()
+}
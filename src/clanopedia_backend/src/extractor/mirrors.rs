@@ -0,0 +1,182 @@
+// src/extractor/mirrors.rs
+//
+// Alternate origins for GitHub, GitLab and Bitbucket files, for hosts that some subnets
+// intermittently cannot reach. A repository file is fetched from its own host first; while
+// that fails with a network error, the controller-configured mirrors for its host are tried in
+// order, each spending outcall budget. The mirror that served a file is recorded in its
+// provenance. Auth and quota errors are the host's answer and end the fallback.
+
+use candid::Principal;
+use ic_cdk::api::management_canister::http_request::{HttpResponse, TransformArgs};
+use ic_cdk_macros::query;
+
+use crate::extractor::types::{ExtractionError, UrlType};
+use crate::proxy::require_controller;
+use crate::types::ConfigSetting;
+use crate::{config_history, storage};
+use crate::{ClanopediaError, ClanopediaResult, OutcallMirrors};
+
+const MAX_MIRRORS: usize = 5;
+const PLACEHOLDERS: [&str; 4] = ["{owner}", "{repo}", "{ref}", "{path}"];
+
+/// The parts of a raw repository file URL that mirror templates are filled from
+struct RepositoryFile {
+    owner: String,
+    repo: String,
+    reference: String,
+    path: String,
+}
+
+impl RepositoryFile {
+    fn from_raw_url(url: &str) -> Option<Self> {
+        let url = url.split(['?', '#']).next()?;
+        let (owner, repo, rest) = match UrlType::from_url(url) {
+            // raw.githubusercontent.com/owner/repo/refs/heads/branch/path
+            UrlType::GitHub => {
+                let rest = url.split_once("raw.githubusercontent.com/")?.1;
+                let mut parts = rest.splitn(3, '/');
+                let (owner, repo, rest) = (parts.next()?, parts.next()?, parts.next()?);
+                let rest = rest
+                    .strip_prefix("refs/heads/")
+                    .or_else(|| rest.strip_prefix("refs/tags/"))
+                    .unwrap_or(rest);
+                (owner, repo, rest)
+            }
+            // gitlab.com/group/subgroup/project/-/raw/branch/path
+            UrlType::GitLab => {
+                let (project, rest) = url.split_once("/-/raw/")?;
+                let project = project.split_once("://").map_or(project, |(_, p)| p);
+                let (owner, repo) = project.split_once('/')?.1.rsplit_once('/')?;
+                (owner, repo, rest)
+            }
+            // bitbucket.org/workspace/repo/raw/branch/path
+            UrlType::Bitbucket => {
+                let rest = url.split_once("bitbucket.org/")?.1;
+                let mut parts = rest.splitn(4, '/');
+                let (owner, repo) = (parts.next()?, parts.next()?);
+                if parts.next()? != "raw" {
+                    return None;
+                }
+                (owner, repo, parts.next()?)
+            }
+            _ => return None,
+        };
+        let (reference, path) = rest.split_once('/')?;
+        if [owner, repo, reference, path].iter().any(|part| part.is_empty()) {
+            return None;
+        }
+        Some(Self {
+            owner: owner.to_string(),
+            repo: repo.to_string(),
+            reference: reference.to_string(),
+            path: path.to_string(),
+        })
+    }
+
+    fn render(&self, template: &str) -> String {
+        template
+            .replace("{owner}", &self.owner)
+            .replace("{repo}", &self.repo)
+            .replace("{ref}", &self.reference)
+            .replace("{path}", &self.path)
+    }
+}
+
+// `https://host` of a validated template
+fn origin(template: &str) -> String {
+    let host = template.trim_start_matches("https://").split('/').next().unwrap_or("");
+    format!("https://{}", host)
+}
+
+/// The mirrors to try, in order, for a raw repository file URL, as `(origin, url)` pairs
+pub fn mirror_urls(raw_url: &str) -> Vec<(String, String)> {
+    let Some(file) = RepositoryFile::from_raw_url(raw_url) else {
+        return Vec::new();
+    };
+    let mirrors = storage::get_outcall_mirrors();
+    let templates = match UrlType::from_url(raw_url) {
+        UrlType::GitHub => mirrors.github,
+        UrlType::GitLab => mirrors.gitlab,
+        UrlType::Bitbucket => mirrors.bitbucket,
+        _ => return Vec::new(),
+    };
+    templates
+        .iter()
+        .map(|template| (origin(template), file.render(template)))
+        .filter(|(_, url)| url != raw_url)
+        .collect()
+}
+
+/// Whether a failed fetch may succeed through a mirror
+pub fn should_fall_back(error: &ClanopediaError) -> bool {
+    matches!(
+        error,
+        ClanopediaError::ExtractionFailed(ExtractionError::NetworkFailure { .. })
+    )
+}
+
+pub fn get_mirrors() -> OutcallMirrors {
+    storage::get_outcall_mirrors()
+}
+
+/// Replace the mirror lists; controllers only. A list may be empty to disable fallback for
+/// that host.
+pub fn set_mirrors(caller: Principal, mirrors: OutcallMirrors) -> ClanopediaResult<()> {
+    require_controller(caller)?;
+
+    let normalize = |source: &str, list: Vec<String>| -> ClanopediaResult<Vec<String>> {
+        if list.len() > MAX_MIRRORS {
+            return Err(ClanopediaError::InvalidInput(format!(
+                "At most {} {} mirrors are allowed",
+                MAX_MIRRORS, source
+            )));
+        }
+        let mut normalized: Vec<String> = Vec::new();
+        for template in list {
+            let template = template.trim().to_string();
+            let host = template.strip_prefix("https://").unwrap_or("");
+            let host = host.split('/').next().unwrap_or("");
+            let unknown = PLACEHOLDERS
+                .iter()
+                .fold(template.clone(), |t, placeholder| t.replace(placeholder, ""))
+                .contains(['{', '}']);
+            if host.is_empty() || host.contains('{') || !template.contains("{path}") || unknown {
+                return Err(ClanopediaError::InvalidInput(format!(
+                    "{} mirror {} must be an https URL template with {{path}}, using only \
+                     {{owner}}, {{repo}}, {{ref}} and {{path}}",
+                    source, template
+                )));
+            }
+            if normalized.contains(&template) {
+                return Err(ClanopediaError::InvalidInput(format!(
+                    "{} mirror {} is listed more than once",
+                    source, template
+                )));
+            }
+            normalized.push(template);
+        }
+        Ok(normalized)
+    };
+
+    let mirrors = OutcallMirrors {
+        github: normalize("GitHub", mirrors.github)?,
+        gitlab: normalize("GitLab", mirrors.gitlab)?,
+        bitbucket: normalize("Bitbucket", mirrors.bitbucket)?,
+    };
+    storage::set_outcall_mirrors(mirrors.clone());
+    config_history::record(caller, None, ConfigSetting::OutcallMirrors(mirrors))?;
+    Ok(())
+}
+
+/// Transform function for mirror responses. CDNs add per-edge headers (cache status, ray ids,
+/// timings) that differ between replicas, so only the content type is kept.
+#[query]
+fn transform_mirror_response(args: TransformArgs) -> HttpResponse {
+    let mut response = args.response;
+
+    response
+        .headers
+        .retain(|header| header.name.eq_ignore_ascii_case("content-type"));
+
+    response
+}
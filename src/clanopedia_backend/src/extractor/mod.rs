@@ -1,33 +1,33 @@
 // src/extractor/mod.rs
 
+pub mod archive;
+pub mod diff;
+pub mod fetch_cache;
 pub mod file_extractor;
+pub mod google_docs;
+pub mod jobs;
+pub mod keywords;
+pub mod links;
+pub mod mirrors;
+pub mod outline;
+pub mod permaweb;
+pub mod pipeline;
+pub mod records;
+pub mod retry;
 pub mod url_extractor;
 pub mod types;
+pub mod youtube_cost;
 
 pub use types::*;
-use crate::{AddDocumentRequest, ClanopediaResult, ClanopediaError};
+use crate::memory::{self, Memory};
+use crate::{auth, secrets, source_policy, storage, AddDocumentRequest, Capability, ClanopediaResult, ClanopediaError, DocumentProvenance};
+use ic_cdk::api::management_canister::http_request::HttpResponse;
 use ic_cdk::api::time;
-use ic_stable_structures::{
-    memory_manager::{MemoryManager, MemoryId},
-    DefaultMemoryImpl, StableBTreeMap,
-};
+use ic_stable_structures::{Memory as _, StableBTreeMap};
 use ic_stable_structures::storable::Storable;
 use std::cell::RefCell;
 
-// Memory ID for extraction progress storage
-const EXTRACTION_PROGRESS_MEMORY_ID: MemoryId = MemoryId::new(10);
-
-// Memory manager for stable storage
-thread_local! {
-    static MEMORY_MANAGER: RefCell<MemoryManager<DefaultMemoryImpl>> = RefCell::new(
-        MemoryManager::init(DefaultMemoryImpl::default())
-    );
-}
-
-/// Get memory for extraction progress storage
-fn get_extraction_memory() -> ic_stable_structures::memory_manager::VirtualMemory<DefaultMemoryImpl> {
-    MEMORY_MANAGER.with(|m| m.borrow().get(EXTRACTION_PROGRESS_MEMORY_ID))
-}
+const PROGRESS_RETENTION_NANOS: u64 = 7 * 24 * 60 * 60 * 1_000_000_000; // 7 days
 
 // Key for the progress map: (collection_id, url)
 #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
@@ -61,8 +61,79 @@ impl Storable for ProgressKey {
 
 // Global stable storage for extraction progress
 thread_local! {
-    static EXTRACTION_PROGRESS: RefCell<StableBTreeMap<ProgressKey, ExtractionProgress, ic_stable_structures::memory_manager::VirtualMemory<DefaultMemoryImpl>>> = 
-        RefCell::new(StableBTreeMap::init(get_extraction_memory()));
+    static EXTRACTION_PROGRESS: RefCell<StableBTreeMap<ProgressKey, ExtractionProgress, Memory>> =
+        RefCell::new(StableBTreeMap::init(memory::get(memory::EXTRACTION_PROGRESS)));
+}
+
+/// Move progress written while the extractor kept its own memory manager into the shared
+/// one, leaving out stale entries, and empty the old map. A no-op once drained, and when
+/// the old memory holds no map (its buckets may have been handed out twice).
+pub fn migrate_legacy_progress() {
+    let legacy_memory = memory::get(memory::LEGACY_EXTRACTION_PROGRESS);
+    let mut magic = [0u8; 3];
+    if legacy_memory.size() == 0 {
+        return;
+    }
+    legacy_memory.read(0, &mut magic);
+    if &magic != b"BTR" {
+        ic_cdk::println!("Legacy extraction progress memory holds no map; skipping migration");
+        return;
+    }
+
+    let mut legacy: StableBTreeMap<ProgressKey, ExtractionProgress, Memory> =
+        StableBTreeMap::init(legacy_memory);
+    if legacy.is_empty() {
+        return;
+    }
+    let now = time();
+    let mut moved = 0u64;
+    let total = legacy.len();
+    EXTRACTION_PROGRESS.with(|progress| {
+        let mut map = progress.borrow_mut();
+        for (key, entry) in legacy.iter() {
+            if !is_stale(&entry, now) && !map.contains_key(&key) {
+                map.insert(key, entry);
+                moved += 1;
+            }
+        }
+    });
+    legacy.clear_new();
+    ic_cdk::println!(
+        "Migrated {} of {} legacy extraction progress entries",
+        moved,
+        total
+    );
+}
+
+/// Drop progress that can no longer be resumed or read usefully: finished entries past
+/// their retention, and entries of collections that no longer exist
+pub fn collect_stale_progress() -> u32 {
+    let now = time();
+    EXTRACTION_PROGRESS.with(|progress| {
+        let mut map = progress.borrow_mut();
+        let stale: Vec<ProgressKey> = map
+            .iter()
+            .filter(|(_, entry)| is_stale(entry, now))
+            .map(|(key, _)| key)
+            .collect();
+        for key in &stale {
+            map.remove(key);
+        }
+        stale.len() as u32
+    })
+}
+
+fn is_stale(progress: &ExtractionProgress, now: u64) -> bool {
+    let finished = matches!(
+        progress.status,
+        ExtractionStatus::Completed | ExtractionStatus::Failed(_)
+    );
+    (finished && progress.last_updated < now.saturating_sub(PROGRESS_RETENTION_NANOS))
+        || storage::get_collection(&progress.collection_id).is_err()
+}
+
+pub fn progress_entry_count() -> u64 {
+    EXTRACTION_PROGRESS.with(|p| p.borrow().len())
 }
 
 pub struct Extractor;
@@ -77,7 +148,7 @@ impl Extractor {
         file_extractor::extract_file_content(file_data, filename, collection_id)
     }
 
-    /// Extract content from URL (YouTube, GitHub, etc.)
+    /// Extract content from URL (YouTube, GitHub, Google Docs, etc.)
     pub async fn extract_from_url(
         url: String,
         collection_id: String,
@@ -98,7 +169,9 @@ impl Extractor {
                 ExtractionSource::File { data, filename } => {
                     Self::extract_from_file(data, filename, collection_id.clone())?
                 }
-                ExtractionSource::Url { url, api_key } => {
+                ExtractionSource::Url { url, api_key_secret } => {
+                    let api_key =
+                        secrets::resolve_secret(&collection_id, api_key_secret.as_deref())?;
                     Self::extract_from_url(url, collection_id.clone(), api_key).await?
                 }
             };
@@ -144,14 +217,28 @@ impl Extractor {
         ExtractionResponse {
             documents,
             extraction_info,
+            pipeline: Vec::new(),
+            tag_suggestions: Vec::new(),
+            summarization: None,
+            ocr: None,
+            cache: Vec::new(),
+            record_errors: Vec::new(),
+            files: Vec::new(),
         }
     }
 
     /// Create a failed response
-    pub fn create_failed_response(error_message: String) -> ExtractionResponse {
+    pub fn create_failed_response(error: ExtractionError) -> ExtractionResponse {
         ExtractionResponse {
             documents: Vec::new(),
-            extraction_info: ExtractionInfo::for_failed_extraction(error_message),
+            extraction_info: ExtractionInfo::for_failed_extraction(error),
+            pipeline: Vec::new(),
+            tag_suggestions: Vec::new(),
+            summarization: None,
+            ocr: None,
+            cache: Vec::new(),
+            record_errors: Vec::new(),
+            files: Vec::new(),
         }
     }
 
@@ -172,6 +259,64 @@ impl Extractor {
     }
 }
 
+/// Recorded in each document's provenance; follows the canister's package version
+pub const EXTRACTOR_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+// Response headers kept in provenance; anything else is noise or varies per request
+const PROVENANCE_HEADERS: [&str; 5] = [
+    "content-type",
+    "content-length",
+    "content-disposition",
+    "etag",
+    "last-modified",
+];
+const MAX_PROVENANCE_HEADER_CHARS: usize = 256;
+
+/// Provenance for content read from `url`; pass the URL actually requested, never one
+/// carrying an API key
+pub fn http_provenance(extractor: &str, url: &str, response: &HttpResponse) -> DocumentProvenance {
+    let headers = response
+        .headers
+        .iter()
+        .filter(|header| {
+            PROVENANCE_HEADERS
+                .iter()
+                .any(|name| header.name.eq_ignore_ascii_case(name))
+        })
+        .map(|header| {
+            let mut header = header.clone();
+            header.name = header.name.to_lowercase();
+            header.value = header.value.chars().take(MAX_PROVENANCE_HEADER_CHARS).collect();
+            header
+        })
+        .collect();
+
+    DocumentProvenance {
+        source_url: Some(url.to_string()),
+        retrieved_at: time(),
+        http_status: response.status.to_string().parse().ok(),
+        headers,
+        extractor: extractor.to_string(),
+        extractor_version: EXTRACTOR_VERSION.to_string(),
+        retries: Some(0),
+        mirror: None,
+    }
+}
+
+/// Provenance for content the caller uploaded
+pub fn upload_provenance(extractor: &str) -> DocumentProvenance {
+    DocumentProvenance {
+        source_url: None,
+        retrieved_at: time(),
+        http_status: None,
+        headers: Vec::new(),
+        extractor: extractor.to_string(),
+        extractor_version: EXTRACTOR_VERSION.to_string(),
+        retries: None,
+        mirror: None,
+    }
+}
+
 pub fn sanitize_content(content: &str) -> String {
     // Remove excessive whitespace, normalize line endings
     content
@@ -182,16 +327,9 @@ pub fn sanitize_content(content: &str) -> String {
         .join("\n")
 }
 
-pub fn validate_content_size(content: &str) -> ClanopediaResult<()> {
-    const MAX_SIZE: usize = 10 * 1024 * 1024; // 10MB limit for Blueband
-    
-    if content.len() > MAX_SIZE {
-        return Err(ClanopediaError::InvalidInput(
-            format!("Content too large: {} bytes (max: {} bytes)", content.len(), MAX_SIZE)
-        ));
-    }
-    
-    Ok(())
+/// Fail if the content is over the collection's per-document cap
+pub fn validate_content_size(collection_id: &str, content: &str) -> ClanopediaResult<()> {
+    crate::content_limits::check_content(collection_id, content)
 }
 
 /// Helper function to get extraction statistics
@@ -214,29 +352,7 @@ pub fn get_extraction_stats() -> (u64, u64, u64) {
 /// Helper function to clean up old completed extractions
 #[ic_cdk::update]
 pub fn cleanup_old_extractions() -> u32 {
-    let cutoff_time = time() - (7 * 24 * 60 * 60 * 1_000_000_000); // 7 days ago
-    let mut cleaned = 0u32;
-    
-    EXTRACTION_PROGRESS.with(|progress| {
-        let mut map = progress.borrow_mut();
-        let keys_to_remove: Vec<ProgressKey> = map.iter()
-            .filter_map(|(key, prog)| {
-                if matches!(prog.status, ExtractionStatus::Completed | ExtractionStatus::Failed(_)) 
-                   && prog.last_updated < cutoff_time {
-                    Some(key)
-                } else {
-                    None
-                }
-            })
-            .collect();
-        
-        for key in keys_to_remove {
-            map.remove(&key);
-            cleaned += 1;
-        }
-    });
-    
-    cleaned
+    collect_stale_progress()
 }
 
 /// Resume extraction from where it left off
@@ -244,8 +360,15 @@ pub fn cleanup_old_extractions() -> u32 {
 pub async fn resume_extraction(
     collection_id: String,
     url: String,
-    api_key: Option<String>,
+    api_key_secret: Option<String>,
 ) -> ClanopediaResult<Vec<AddDocumentRequest>> {
+    // Resuming may unseal a collection secret, so it is limited to admins and operators
+    let collection = storage::get_collection(&collection_id)?;
+    auth::authorize(&collection, ic_cdk::caller(), Capability::Extract)?;
+    // The policy may have changed since the extraction started
+    source_policy::check_url(&collection, &url)?;
+    let api_key = secrets::resolve_secret(&collection_id, api_key_secret.as_deref())?;
+
     let progress = EXTRACTION_PROGRESS.with(|p| {
         p.borrow().get(&ProgressKey::new(collection_id.clone(), url.clone()))
     }).ok_or_else(|| 
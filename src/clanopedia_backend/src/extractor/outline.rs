@@ -0,0 +1,83 @@
+// src/extractor/outline.rs
+
+use crate::types::OutlineEntry;
+
+/// Build an outline from markdown ATX headings (`#` to `######`), skipping fenced code blocks.
+/// Offsets are char offsets into `content`.
+pub fn markdown_outline(content: &str) -> Vec<OutlineEntry> {
+    let mut entries = Vec::new();
+    let mut in_code_block = false;
+    let mut offset = 0u64;
+
+    for line in content.split('\n') {
+        let trimmed = line.trim_start();
+
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_code_block = !in_code_block;
+        } else if !in_code_block {
+            let level = trimmed.chars().take_while(|c| *c == '#').count();
+            let rest = &trimmed[level..];
+
+            if (1..=6).contains(&level) && (rest.is_empty() || rest.starts_with(' ')) {
+                let title = rest.trim().trim_end_matches('#').trim();
+                if !title.is_empty() {
+                    entries.push(OutlineEntry {
+                        level: level as u8,
+                        title: title.to_string(),
+                        offset,
+                    });
+                }
+            }
+        }
+
+        offset += line.chars().count() as u64 + 1;
+    }
+
+    entries
+}
+
+/// Build an outline from PDF bookmarks given as `(level, title, page)`.
+/// `page_offsets` maps page numbers to the char offset where that page's text starts in `content`;
+/// each entry points at the first occurrence of its title on or after its page, or the page start.
+pub fn pdf_outline(
+    bookmarks: &[(usize, String, u32)],
+    page_offsets: &[(u32, u64)],
+    content: &str,
+) -> Vec<OutlineEntry> {
+    let lowered = content.to_lowercase();
+
+    bookmarks
+        .iter()
+        .filter(|(_, title, _)| !title.trim().is_empty())
+        .map(|(level, title, page)| {
+            let page_start = page_offsets
+                .iter()
+                .rev()
+                .find(|(p, _)| p <= page)
+                .map(|(_, offset)| *offset)
+                .unwrap_or(0);
+
+            let offset = char_to_byte(&lowered, page_start)
+                .and_then(|start| {
+                    lowered[start..]
+                        .find(&title.trim().to_lowercase())
+                        .map(|found| lowered[..start + found].chars().count() as u64)
+                })
+                .unwrap_or(page_start);
+
+            OutlineEntry {
+                level: (*level).clamp(1, u8::MAX as usize) as u8,
+                title: title.trim().to_string(),
+                offset,
+            }
+        })
+        .collect()
+}
+
+pub(crate) fn char_to_byte(content: &str, char_offset: u64) -> Option<usize> {
+    content
+        .char_indices()
+        .map(|(i, _)| i)
+        .chain(std::iter::once(content.len()))
+        .nth(char_offset as usize)
+}
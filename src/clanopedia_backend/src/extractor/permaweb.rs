@@ -0,0 +1,425 @@
+// src/extractor/permaweb.rs
+//
+// IPFS and Arweave content fetched through controller-configured gateways, falling back to
+// the next gateway when one fails or serves bytes that do not match the address. IPFS CIDs
+// are checked where they can be recomputed from the bytes alone: raw-codec CIDs, and dag-pb
+// CIDs of files small enough to be a single block. Arweave transaction ids hash the
+// signature rather than the data, so Arweave content is taken on the gateway's word.
+
+use candid::Principal;
+use ic_cdk::api::management_canister::http_request::{HttpResponse, TransformArgs};
+use ic_cdk_macros::query;
+use sha2::{Digest, Sha256};
+
+use crate::external::blueband::ContentType;
+use crate::extractor::outline::markdown_outline;
+use crate::extractor::url_extractor::http_get;
+use crate::extractor::{
+    http_provenance, sanitize_content, validate_content_size, ExtractionError,
+};
+use crate::proxy::require_controller;
+use crate::types::ConfigSetting;
+use crate::{config_history, outcall_budget, storage};
+use crate::{AddDocumentRequest, ClanopediaError, ClanopediaResult, PermawebGateways};
+
+const MAX_GATEWAYS: usize = 5;
+const CODEC_RAW: u64 = 0x55;
+const CODEC_DAG_PB: u64 = 0x70;
+const HASH_SHA2_256: u64 = 0x12;
+// Files above the default chunk size are split into several blocks
+const MAX_SINGLE_BLOCK_BYTES: usize = 256 * 1024;
+
+enum ContentAddress {
+    Ipfs { cid: String, path: String },
+    Arweave { tx: String, path: String },
+}
+
+enum Verification {
+    Verified,
+    Mismatch,
+    Unverifiable,
+}
+
+impl ContentAddress {
+    fn from_url(url: &str) -> ClanopediaResult<Self> {
+        let ipfs = url
+            .strip_prefix("ipfs://")
+            .or_else(|| url.split_once("/ipfs/").map(|(_, rest)| rest));
+        if let Some(rest) = ipfs {
+            let (cid, path) = split_address(rest);
+            if cid.is_empty() || !cid.chars().all(|c| c.is_ascii_alphanumeric()) {
+                return Err(ClanopediaError::InvalidInput(
+                    "Could not extract CID from IPFS URL".to_string(),
+                ));
+            }
+            return Ok(ContentAddress::Ipfs { cid, path });
+        }
+
+        let arweave = url
+            .strip_prefix("ar://")
+            .or_else(|| url.split_once("arweave.net/").map(|(_, rest)| rest));
+        if let Some(rest) = arweave {
+            let (tx, path) = split_address(rest);
+            let valid = tx.len() == 43
+                && tx
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+            if !valid {
+                return Err(ClanopediaError::InvalidInput(
+                    "Could not extract transaction ID from Arweave URL".to_string(),
+                ));
+            }
+            return Ok(ContentAddress::Arweave { tx, path });
+        }
+
+        Err(ClanopediaError::InvalidInput(
+            "Expected an ipfs://, ar:// or gateway URL".to_string(),
+        ))
+    }
+
+    fn network(&self) -> &'static str {
+        match self {
+            ContentAddress::Ipfs { .. } => "IPFS",
+            ContentAddress::Arweave { .. } => "Arweave",
+        }
+    }
+
+    /// The CID or transaction id, and the path below it (empty for the item itself)
+    fn id_and_path(&self) -> (&str, &str) {
+        match self {
+            ContentAddress::Ipfs { cid, path } => (cid, path),
+            ContentAddress::Arweave { tx, path } => (tx, path),
+        }
+    }
+
+    /// `id/path` as both the gateway and the `ipfs://`/`ar://` forms address it
+    fn locator(&self) -> String {
+        match self.id_and_path() {
+            (id, "") => id.to_string(),
+            (id, path) => format!("{}/{}", id, path),
+        }
+    }
+
+    /// The gateway-independent address recorded as the document's source
+    fn uri(&self) -> String {
+        match self {
+            ContentAddress::Ipfs { .. } => format!("ipfs://{}", self.locator()),
+            ContentAddress::Arweave { .. } => format!("ar://{}", self.locator()),
+        }
+    }
+
+    fn gateways(&self, gateways: PermawebGateways) -> Vec<String> {
+        match self {
+            ContentAddress::Ipfs { .. } => gateways.ipfs,
+            ContentAddress::Arweave { .. } => gateways.arweave,
+        }
+    }
+
+    fn gateway_url(&self, gateway: &str) -> String {
+        match self {
+            ContentAddress::Ipfs { .. } => format!("{}/ipfs/{}", gateway, self.locator()),
+            ContentAddress::Arweave { .. } => format!("{}/{}", gateway, self.locator()),
+        }
+    }
+
+    fn verify(&self, bytes: &[u8]) -> Verification {
+        match self {
+            // With a path the CID names the enclosing directory, not these bytes
+            ContentAddress::Ipfs { cid, path } if path.is_empty() => verify_cid(cid, bytes),
+            _ => Verification::Unverifiable,
+        }
+    }
+}
+
+/// `(id, path)` from what follows the scheme or `/ipfs/`, dropping any query or fragment
+fn split_address(rest: &str) -> (String, String) {
+    let rest = rest.split(['?', '#']).next().unwrap_or("");
+    let (id, path) = rest.split_once('/').unwrap_or((rest, ""));
+    (id.to_string(), path.trim_end_matches('/').to_string())
+}
+
+/// Fetch IPFS or Arweave content through the configured gateways, in order
+pub async fn extract_permaweb_content(
+    url: &str,
+    collection_id: &str,
+) -> ClanopediaResult<Vec<AddDocumentRequest>> {
+    let address = ContentAddress::from_url(url)?;
+    let network = address.network();
+    let mut failures = Vec::new();
+
+    for gateway in address.gateways(storage::get_permaweb_gateways()) {
+        outcall_budget::spend(collection_id, 1)?;
+        let gateway_url = address.gateway_url(&gateway);
+        ic_cdk::println!("Fetching {} content from: {}", network, gateway_url);
+
+        let response = match http_get(
+            &gateway_url,
+            "text/markdown, text/plain, */*",
+            "transform_permaweb_response",
+            network,
+        )
+        .await
+        {
+            Ok(response) => response,
+            Err(e) => {
+                failures.push(format!("{}: {}", gateway, e));
+                continue;
+            }
+        };
+
+        let verified = match address.verify(&response.body) {
+            Verification::Verified => true,
+            Verification::Unverifiable => false,
+            Verification::Mismatch => {
+                failures.push(format!(
+                    "{}: content does not match {}",
+                    gateway,
+                    address.uri()
+                ));
+                continue;
+            }
+        };
+
+        return permaweb_document(&address, &gateway_url, response, verified, collection_id)
+            .map(|document| vec![document]);
+    }
+
+    // Gateways that failed now may serve the content later
+    Err(ExtractionError::network(
+        true,
+        format!(
+            "No {} gateway served {}: {}",
+            network,
+            address.uri(),
+            failures.join("; ")
+        ),
+    )
+    .into())
+}
+
+fn permaweb_document(
+    address: &ContentAddress,
+    gateway_url: &str,
+    response: HttpResponse,
+    verified: bool,
+    collection_id: &str,
+) -> ClanopediaResult<AddDocumentRequest> {
+    let (id, path) = address.id_and_path();
+    let is_markdown = path.ends_with(".md")
+        || path.ends_with(".markdown")
+        || response.headers.iter().any(|header| {
+            header.name.eq_ignore_ascii_case("content-type") && header.value.contains("markdown")
+        });
+
+    let provenance = http_provenance(&address.network().to_lowercase(), gateway_url, &response);
+    let content = String::from_utf8(response.body).map_err(|_| {
+        ExtractionError::parse("text", format!("{} content is not UTF-8 text", address.network()))
+    })?;
+    if content.trim().is_empty() {
+        let message = format!("{} content is empty", address.network());
+        return Err(ExtractionError::parse("text", message).into());
+    }
+
+    validate_content_size(collection_id, &content)?;
+
+    let sanitized_content = sanitize_content(&content);
+    let title = path
+        .rsplit('/')
+        .next()
+        .filter(|name| !name.is_empty())
+        .unwrap_or(id)
+        .to_string();
+    let mut tags = vec![address.network().to_lowercase()];
+    if verified {
+        tags.push("verified".to_string());
+    }
+
+    Ok(AddDocumentRequest {
+        collection_id: collection_id.to_string(),
+        title,
+        outline: is_markdown.then(|| markdown_outline(&sanitized_content)),
+        content: sanitized_content,
+        content_type: Some(if is_markdown {
+            ContentType::Markdown
+        } else {
+            ContentType::PlainText
+        }),
+        source_url: Some(address.uri()),
+        author: None,
+        tags: Some(tags),
+        summary: None,
+        license: None,
+        provenance: Some(provenance),
+    })
+}
+
+fn verify_cid(cid: &str, bytes: &[u8]) -> Verification {
+    let Some((codec, digest)) = decode_cid(cid) else {
+        return Verification::Unverifiable;
+    };
+    let computed = match codec {
+        CODEC_RAW => Sha256::digest(bytes),
+        CODEC_DAG_PB if bytes.len() <= MAX_SINGLE_BLOCK_BYTES => {
+            Sha256::digest(dag_pb_file_block(bytes))
+        }
+        _ => return Verification::Unverifiable,
+    };
+    if computed.as_slice() == digest.as_slice() {
+        Verification::Verified
+    } else {
+        Verification::Mismatch
+    }
+}
+
+/// The codec and SHA-256 digest of a CIDv0 or base32 CIDv1; None for any other encoding
+/// or hash function
+fn decode_cid(cid: &str) -> Option<(u64, Vec<u8>)> {
+    let (codec, multihash) = if cid.starts_with("Qm") {
+        (CODEC_DAG_PB, base58_decode(cid)?)
+    } else {
+        let bytes = base32_decode(cid.strip_prefix('b')?)?;
+        let (version, rest) = read_varint(&bytes)?;
+        let (codec, rest) = read_varint(rest)?;
+        if version != 1 {
+            return None;
+        }
+        (codec, rest.to_vec())
+    };
+
+    let (hash, rest) = read_varint(&multihash)?;
+    let (length, digest) = read_varint(rest)?;
+    (hash == HASH_SHA2_256 && length == 32 && digest.len() == 32).then(|| (codec, digest.to_vec()))
+}
+
+/// The dag-pb block `ipfs add` writes for a file that fits in one chunk: a UnixFS `File`
+/// node holding the bytes inline, with no links
+fn dag_pb_file_block(bytes: &[u8]) -> Vec<u8> {
+    let mut unixfs = vec![0x08, 0x02]; // Type = File
+    unixfs.push(0x12); // Data
+    write_varint(&mut unixfs, bytes.len() as u64);
+    unixfs.extend_from_slice(bytes);
+    unixfs.push(0x18); // filesize
+    write_varint(&mut unixfs, bytes.len() as u64);
+
+    let mut block = vec![0x0a]; // PBNode.Data
+    write_varint(&mut block, unixfs.len() as u64);
+    block.extend_from_slice(&unixfs);
+    block
+}
+
+fn read_varint(bytes: &[u8]) -> Option<(u64, &[u8])> {
+    let mut value = 0u64;
+    for (i, byte) in bytes.iter().enumerate().take(9) {
+        value |= ((byte & 0x7f) as u64) << (7 * i);
+        if byte & 0x80 == 0 {
+            return Some((value, &bytes[i + 1..]));
+        }
+    }
+    None
+}
+
+fn write_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+fn base58_decode(input: &str) -> Option<Vec<u8>> {
+    const ALPHABET: &[u8] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+    let mut bytes: Vec<u8> = Vec::new();
+    for c in input.bytes() {
+        let mut carry = ALPHABET.iter().position(|&a| a == c)? as u32;
+        for byte in bytes.iter_mut().rev() {
+            carry += (*byte as u32) * 58;
+            *byte = carry as u8;
+            carry >>= 8;
+        }
+        while carry > 0 {
+            bytes.insert(0, carry as u8);
+            carry >>= 8;
+        }
+    }
+    let leading_zeros = input.bytes().take_while(|&c| c == b'1').count();
+    let mut decoded = vec![0; leading_zeros];
+    decoded.extend(bytes);
+    Some(decoded)
+}
+
+fn base32_decode(input: &str) -> Option<Vec<u8>> {
+    const ALPHABET: &[u8] = b"abcdefghijklmnopqrstuvwxyz234567";
+
+    let mut decoded = Vec::with_capacity(input.len() * 5 / 8);
+    let mut buffer = 0u32;
+    let mut bits = 0;
+    for c in input.bytes() {
+        buffer = (buffer << 5) | ALPHABET.iter().position(|&a| a == c)? as u32;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            decoded.push((buffer >> bits) as u8);
+        }
+    }
+    Some(decoded)
+}
+
+pub fn get_gateways() -> PermawebGateways {
+    storage::get_permaweb_gateways()
+}
+
+/// Replace the gateway lists; controllers only
+pub fn set_gateways(caller: Principal, gateways: PermawebGateways) -> ClanopediaResult<()> {
+    require_controller(caller)?;
+
+    let normalize = |network: &str, list: Vec<String>| -> ClanopediaResult<Vec<String>> {
+        if list.is_empty() || list.len() > MAX_GATEWAYS {
+            return Err(ClanopediaError::InvalidInput(format!(
+                "Between 1 and {} {} gateways are required",
+                MAX_GATEWAYS, network
+            )));
+        }
+        let mut normalized: Vec<String> = Vec::new();
+        for gateway in list {
+            let gateway = gateway.trim().trim_end_matches('/').to_string();
+            let host = gateway.strip_prefix("https://").unwrap_or("");
+            if host.is_empty() || host.contains('/') {
+                return Err(ClanopediaError::InvalidInput(format!(
+                    "{} gateway {} must be an https origin such as https://example.com",
+                    network, gateway
+                )));
+            }
+            if normalized.contains(&gateway) {
+                return Err(ClanopediaError::InvalidInput(format!(
+                    "{} gateway {} is listed more than once",
+                    network, gateway
+                )));
+            }
+            normalized.push(gateway);
+        }
+        Ok(normalized)
+    };
+
+    let gateways = PermawebGateways {
+        ipfs: normalize("IPFS", gateways.ipfs)?,
+        arweave: normalize("Arweave", gateways.arweave)?,
+    };
+    storage::set_permaweb_gateways(gateways.clone());
+    config_history::record(caller, None, ConfigSetting::PermawebGateways(gateways))?;
+    Ok(())
+}
+
+/// Transform function for gateway responses. Gateways add per-request headers (cache
+/// status, request ids, resolved roots) that differ between replicas, so only the content
+/// type is kept.
+#[query]
+fn transform_permaweb_response(args: TransformArgs) -> HttpResponse {
+    let mut response = args.response;
+
+    response
+        .headers
+        .retain(|header| header.name.eq_ignore_ascii_case("content-type"));
+
+    response
+}
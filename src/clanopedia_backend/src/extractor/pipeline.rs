@@ -0,0 +1,336 @@
+// src/extractor/pipeline.rs
+
+use std::collections::HashMap;
+
+use sha2::{Digest, Sha256};
+
+use crate::{
+    external::blueband::ContentType,
+    extractor::{
+        keywords::{self, words, LANGUAGE_STOPWORDS},
+        outline::markdown_outline,
+        sanitize_content, DocumentPipelineReport, PipelineStep, PipelineStepRecord, StepOutcome,
+    },
+    AddDocumentRequest, ClanopediaError, ClanopediaResult,
+};
+
+pub const MAX_PIPELINE_STEPS: usize = 8;
+const MIN_CHUNK_CHARS: u32 = 500;
+const MAX_INFERRED_TAGS: u32 = 20;
+
+const LANGUAGE_SAMPLE_WORDS: usize = 1000;
+const MIN_LANGUAGE_HITS: usize = 3;
+const LANGUAGE_TAG_PREFIX: &str = "lang:";
+
+// A document moving through the pipeline along with its report
+struct PipelineItem {
+    document: AddDocumentRequest,
+    report: DocumentPipelineReport,
+}
+
+impl PipelineItem {
+    fn record(&mut self, step: &PipelineStep, outcome: StepOutcome, detail: Option<String>) {
+        self.report.steps.push(PipelineStepRecord {
+            step: step.clone(),
+            outcome,
+            detail,
+        });
+    }
+}
+
+pub fn validate_pipeline(steps: &Option<Vec<PipelineStep>>) -> ClanopediaResult<()> {
+    let Some(steps) = steps else {
+        return Ok(());
+    };
+
+    if steps.len() > MAX_PIPELINE_STEPS {
+        return Err(ClanopediaError::InvalidInput(format!(
+            "Pipelines are limited to {} steps",
+            MAX_PIPELINE_STEPS
+        )));
+    }
+
+    for (i, step) in steps.iter().enumerate() {
+        if steps[..i]
+            .iter()
+            .any(|s| std::mem::discriminant(s) == std::mem::discriminant(step))
+        {
+            return Err(ClanopediaError::InvalidInput(format!(
+                "Pipeline step {:?} is listed more than once",
+                step
+            )));
+        }
+
+        match step {
+            PipelineStep::SplitChunks { max_chars } if *max_chars < MIN_CHUNK_CHARS => {
+                return Err(ClanopediaError::InvalidInput(format!(
+                    "SplitChunks needs max_chars of at least {}",
+                    MIN_CHUNK_CHARS
+                )));
+            }
+            PipelineStep::InferTags { max_tags } if !(1..=MAX_INFERRED_TAGS).contains(max_tags) => {
+                return Err(ClanopediaError::InvalidInput(format!(
+                    "InferTags needs max_tags between 1 and {}",
+                    MAX_INFERRED_TAGS
+                )));
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Run `steps` in order over extracted documents. Returns the documents that survive the
+/// pipeline and a report per document, including dropped ones.
+pub fn run_pipeline(
+    steps: &[PipelineStep],
+    documents: Vec<AddDocumentRequest>,
+) -> (Vec<AddDocumentRequest>, Vec<DocumentPipelineReport>) {
+    if steps.is_empty() {
+        return (documents, Vec::new());
+    }
+
+    let mut items: Vec<PipelineItem> = documents
+        .into_iter()
+        .map(|document| PipelineItem {
+            report: DocumentPipelineReport {
+                title: document.title.clone(),
+                source_url: document.source_url.clone(),
+                steps: Vec::new(),
+                dropped: false,
+            },
+            document,
+        })
+        .collect();
+    let mut dropped = Vec::new();
+
+    for step in steps {
+        match step {
+            PipelineStep::Sanitize => items.iter_mut().for_each(|item| sanitize(step, item)),
+            PipelineStep::DetectLanguage => items
+                .iter_mut()
+                .for_each(|item| detect_language(step, item)),
+            PipelineStep::SplitChunks { max_chars } => {
+                items = items
+                    .into_iter()
+                    .flat_map(|item| split_chunks(step, item, *max_chars as usize))
+                    .collect();
+            }
+            PipelineStep::Dedupe => {
+                let (kept, duplicates) = dedupe(step, items);
+                items = kept;
+                dropped.extend(duplicates);
+            }
+            PipelineStep::InferTags { max_tags } => items
+                .iter_mut()
+                .for_each(|item| infer_tags(step, item, *max_tags as usize)),
+        }
+    }
+
+    let mut reports: Vec<DocumentPipelineReport> =
+        items.iter().map(|item| item.report.clone()).collect();
+    reports.extend(dropped);
+    (
+        items.into_iter().map(|item| item.document).collect(),
+        reports,
+    )
+}
+
+// ============================
+// STEPS
+// ============================
+
+fn sanitize(step: &PipelineStep, item: &mut PipelineItem) {
+    let sanitized = sanitize_content(&item.document.content);
+    if sanitized == item.document.content {
+        item.record(step, StepOutcome::Unchanged, None);
+        return;
+    }
+
+    // Outline offsets point into the old content; markdown outlines can be rebuilt
+    item.document.outline = match item.document.content_type {
+        Some(ContentType::Markdown) => Some(markdown_outline(&sanitized)),
+        _ => None,
+    };
+    let removed = item.document.content.len().saturating_sub(sanitized.len());
+    item.document.content = sanitized;
+    item.record(
+        step,
+        StepOutcome::Changed,
+        Some(format!("{} bytes removed", removed)),
+    );
+}
+
+fn detect_language(step: &PipelineStep, item: &mut PipelineItem) {
+    let words: Vec<String> = words(&item.document.content)
+        .take(LANGUAGE_SAMPLE_WORDS)
+        .collect();
+
+    let best = LANGUAGE_STOPWORDS
+        .iter()
+        .map(|(code, stopwords)| {
+            let hits = words
+                .iter()
+                .filter(|w| stopwords.contains(&w.as_str()))
+                .count();
+            (*code, hits)
+        })
+        .max_by_key(|(_, hits)| *hits)
+        .filter(|(_, hits)| *hits >= MIN_LANGUAGE_HITS);
+
+    let Some((code, _)) = best else {
+        item.record(
+            step,
+            StepOutcome::Unchanged,
+            Some("language not detected".to_string()),
+        );
+        return;
+    };
+
+    let tag = format!("{}{}", LANGUAGE_TAG_PREFIX, code);
+    let tags = item.document.tags.get_or_insert_with(Vec::new);
+    if tags.contains(&tag) {
+        item.record(step, StepOutcome::Unchanged, Some(code.to_string()));
+        return;
+    }
+    tags.retain(|t| !t.starts_with(LANGUAGE_TAG_PREFIX));
+    tags.push(tag);
+    item.record(step, StepOutcome::Changed, Some(code.to_string()));
+}
+
+fn split_chunks(
+    step: &PipelineStep,
+    mut item: PipelineItem,
+    max_chars: usize,
+) -> Vec<PipelineItem> {
+    let parts = split_content(&item.document.content, max_chars);
+    if parts.len() <= 1 {
+        item.record(step, StepOutcome::Unchanged, None);
+        return vec![item];
+    }
+
+    let total = parts.len();
+    let outline = item.document.outline.take();
+    let starts: Vec<u64> = parts.iter().map(|(start, _)| *start as u64).collect();
+
+    parts
+        .into_iter()
+        .enumerate()
+        .map(|(i, (_, content))| {
+            let start = starts[i];
+            let end = starts.get(i + 1).copied().unwrap_or(u64::MAX);
+
+            let mut document = item.document.clone();
+            document.title = format!("{} (part {}/{})", item.document.title, i + 1, total);
+            document.content = content;
+            document.outline = outline.as_ref().map(|entries| {
+                entries
+                    .iter()
+                    .filter(|e| e.offset >= start && e.offset < end)
+                    .map(|e| {
+                        let mut entry = e.clone();
+                        entry.offset -= start;
+                        entry
+                    })
+                    .collect()
+            });
+
+            let mut part = PipelineItem {
+                report: DocumentPipelineReport {
+                    title: document.title.clone(),
+                    ..item.report.clone()
+                },
+                document,
+            };
+            part.record(
+                step,
+                StepOutcome::Changed,
+                Some(format!("part {} of {}", i + 1, total)),
+            );
+            part
+        })
+        .collect()
+}
+
+// Pack lines into parts of at most `max_chars`, hard-splitting lines that are longer.
+// Returns each part with its char offset into `content`.
+fn split_content(content: &str, max_chars: usize) -> Vec<(usize, String)> {
+    let mut parts: Vec<(usize, String, usize)> = Vec::new();
+    let mut offset = 0;
+
+    for line in content.split('\n') {
+        let chars: Vec<char> = line.chars().collect();
+        let pieces: Vec<&[char]> = if chars.is_empty() {
+            vec![&[]]
+        } else {
+            chars.chunks(max_chars).collect()
+        };
+
+        for (i, piece) in pieces.into_iter().enumerate() {
+            let text: String = piece.iter().collect();
+            match parts.last_mut() {
+                Some((_, part, len)) if *len + 1 + piece.len() <= max_chars => {
+                    part.push('\n');
+                    part.push_str(&text);
+                    *len += 1 + piece.len();
+                }
+                _ => parts.push((offset + i * max_chars, text, piece.len())),
+            }
+        }
+        offset += chars.len() + 1;
+    }
+
+    parts
+        .into_iter()
+        .map(|(start, text, _)| (start, text))
+        .collect()
+}
+
+fn dedupe(
+    step: &PipelineStep,
+    items: Vec<PipelineItem>,
+) -> (Vec<PipelineItem>, Vec<DocumentPipelineReport>) {
+    let mut seen: HashMap<Vec<u8>, String> = HashMap::new();
+    let mut kept = Vec::new();
+    let mut dropped = Vec::new();
+
+    for mut item in items {
+        let hash = Sha256::digest(item.document.content.trim().as_bytes()).to_vec();
+        match seen.get(&hash) {
+            Some(original) => {
+                let detail = format!("duplicate of {}", original);
+                item.record(step, StepOutcome::Dropped, Some(detail));
+                item.report.dropped = true;
+                dropped.push(item.report);
+            }
+            None => {
+                seen.insert(hash, item.document.title.clone());
+                item.record(step, StepOutcome::Unchanged, None);
+                kept.push(item);
+            }
+        }
+    }
+
+    (kept, dropped)
+}
+
+fn infer_tags(step: &PipelineStep, item: &mut PipelineItem, max_tags: usize) {
+    let suggestions = keywords::suggest_keywords(&[&item.document.content], max_tags * 2)
+        .pop()
+        .unwrap_or_default();
+
+    let tags = item.document.tags.get_or_insert_with(Vec::new);
+    let added: Vec<String> = suggestions
+        .into_iter()
+        .map(|k| k.keyword)
+        .filter(|keyword| !tags.iter().any(|t| t.eq_ignore_ascii_case(keyword)))
+        .take(max_tags)
+        .collect();
+
+    if added.is_empty() {
+        item.record(step, StepOutcome::Unchanged, None);
+        return;
+    }
+    tags.extend(added.iter().cloned());
+    item.record(step, StepOutcome::Changed, Some(added.join(", ")));
+}
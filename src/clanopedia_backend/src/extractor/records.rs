@@ -0,0 +1,270 @@
+// src/extractor/records.rs
+//
+// Knowledge dumps exported from other tools arrive as a JSON array of records or as JSONL,
+// one record per line. The caller names the fields that hold each record's title, content
+// and tags. A record that fails validation is reported and skipped; the rest of the file
+// is still extracted.
+
+use serde_json::Value;
+
+use crate::external::blueband::ContentType;
+use crate::extractor::types::{
+    ExtractionError, FileExtractionConfig, RecordError, RecordFieldMapping,
+};
+use crate::extractor::{sanitize_content, upload_provenance, validate_content_size};
+use crate::types::{ClanopediaError, ClanopediaResult, FieldError};
+use crate::AddDocumentRequest;
+
+const MAX_RECORDS: usize = 1_000;
+const MAX_TAG_FIELDS: usize = 10;
+
+/// Extract one document per record of a JSON or JSONL file
+pub fn extract_records(
+    file_data: &[u8],
+    filename: &str,
+    collection_id: &str,
+    mapping: &RecordFieldMapping,
+) -> ClanopediaResult<(Vec<AddDocumentRequest>, Vec<RecordError>)> {
+    validate_mapping(mapping)?;
+
+    let max_file_size = FileExtractionConfig::default().max_file_size;
+    if file_data.len() as u64 > max_file_size {
+        return Err(ExtractionError::TooLarge {
+            bytes: file_data.len() as u64,
+            max_bytes: max_file_size,
+        }
+        .into());
+    }
+
+    let text = std::str::from_utf8(file_data)
+        .map_err(|_| ExtractionError::parse("json", "JSON files must be UTF-8"))?
+        .trim_start_matches('\u{feff}');
+
+    let mut errors = Vec::new();
+    let records = if is_jsonl(filename) {
+        parse_lines(text, &mut errors)
+    } else {
+        parse_document(text)?
+    };
+
+    if records.is_empty() && errors.is_empty() {
+        return Err(ExtractionError::parse("json", "File contains no records").into());
+    }
+    if records.len() + errors.len() > MAX_RECORDS {
+        return Err(ClanopediaError::InvalidInput(format!(
+            "Too many records: {} (max: {})",
+            records.len() + errors.len(),
+            MAX_RECORDS
+        )));
+    }
+
+    let mut documents = Vec::with_capacity(records.len());
+    for (index, record) in records {
+        match record_document(&record, collection_id, mapping) {
+            Ok(document) => documents.push(document),
+            Err(field_errors) => errors.push(RecordError {
+                index,
+                errors: field_errors,
+            }),
+        }
+    }
+    errors.sort_by_key(|error| error.index);
+
+    ic_cdk::println!(
+        "Extracted {} documents from {} ({} records skipped)",
+        documents.len(),
+        filename,
+        errors.len()
+    );
+
+    Ok((documents, errors))
+}
+
+fn validate_mapping(mapping: &RecordFieldMapping) -> ClanopediaResult<()> {
+    if mapping.title_field.trim().is_empty() || mapping.content_field.trim().is_empty() {
+        return Err(ClanopediaError::InvalidInput(
+            "title_field and content_field are required".to_string(),
+        ));
+    }
+    if mapping.tag_fields.len() > MAX_TAG_FIELDS {
+        return Err(ClanopediaError::InvalidInput(format!(
+            "At most {} tag fields can be mapped",
+            MAX_TAG_FIELDS
+        )));
+    }
+    Ok(())
+}
+
+fn is_jsonl(filename: &str) -> bool {
+    let lower = filename.to_lowercase();
+    lower.ends_with(".jsonl") || lower.ends_with(".ndjson")
+}
+
+/// A JSON file holds an array of records or a single record; it must parse as a whole
+fn parse_document(text: &str) -> ClanopediaResult<Vec<(u32, Value)>> {
+    let value: Value = serde_json::from_str(text)
+        .map_err(|e| ExtractionError::parse("json", format!("Invalid JSON: {}", e)))?;
+    Ok(match value {
+        Value::Array(records) => records
+            .into_iter()
+            .enumerate()
+            .map(|(index, record)| (index as u32, record))
+            .collect(),
+        record => vec![(0, record)],
+    })
+}
+
+/// Each non-blank line of a JSONL file is a record; a line that does not parse is reported
+fn parse_lines(text: &str, errors: &mut Vec<RecordError>) -> Vec<(u32, Value)> {
+    let mut records = Vec::new();
+    for (index, line) in text.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str(line) {
+            Ok(record) => records.push((index as u32, record)),
+            Err(e) => errors.push(RecordError {
+                index: index as u32,
+                errors: vec![FieldError {
+                    field: String::new(),
+                    message: format!("Invalid JSON: {}", e),
+                }],
+            }),
+        }
+    }
+    records
+}
+
+fn record_document(
+    record: &Value,
+    collection_id: &str,
+    mapping: &RecordFieldMapping,
+) -> Result<AddDocumentRequest, Vec<FieldError>> {
+    if !record.is_object() {
+        return Err(vec![FieldError {
+            field: String::new(),
+            message: "Record is not a JSON object".to_string(),
+        }]);
+    }
+
+    let mut errors = Vec::new();
+    let mut reject = |field: &str, message: &str| {
+        errors.push(FieldError {
+            field: field.to_string(),
+            message: message.to_string(),
+        })
+    };
+
+    let title = match lookup(record, &mapping.title_field) {
+        Some(Value::String(title)) if !title.trim().is_empty() => Some(title.trim().to_string()),
+        Some(Value::Number(number)) => Some(number.to_string()),
+        Some(Value::String(_)) => {
+            reject(&mapping.title_field, "Title is empty");
+            None
+        }
+        Some(_) => {
+            reject(&mapping.title_field, "Title must be a string");
+            None
+        }
+        None => {
+            reject(&mapping.title_field, "Field is missing");
+            None
+        }
+    };
+
+    let content = match lookup(record, &mapping.content_field) {
+        Some(Value::String(content)) => {
+            let content = sanitize_content(content);
+            if content.is_empty() {
+                reject(&mapping.content_field, "Content is empty");
+                None
+            } else if let Err(ClanopediaError::ExtractionFailed(error)) =
+                validate_content_size(collection_id, &content)
+            {
+                reject(&mapping.content_field, &error.to_string());
+                None
+            } else {
+                Some(content)
+            }
+        }
+        Some(_) => {
+            reject(&mapping.content_field, "Content must be a string");
+            None
+        }
+        None => {
+            reject(&mapping.content_field, "Field is missing");
+            None
+        }
+    };
+
+    let mut tags: Vec<String> = Vec::new();
+    for field in &mapping.tag_fields {
+        let values = match lookup(record, field) {
+            None | Some(Value::Null) => continue,
+            Some(Value::String(tag)) => vec![tag.as_str()],
+            Some(Value::Array(items)) if items.iter().all(Value::is_string) => {
+                items.iter().filter_map(Value::as_str).collect()
+            }
+            Some(_) => {
+                reject(field, "Tags must be a string or an array of strings");
+                continue;
+            }
+        };
+        for tag in values
+            .into_iter()
+            .map(str::trim)
+            .filter(|tag| !tag.is_empty())
+        {
+            if !tags.iter().any(|existing| existing == tag) {
+                tags.push(tag.to_string());
+            }
+        }
+    }
+
+    let author = optional_string(record, mapping.author_field.as_deref(), &mut reject);
+    let source_url = optional_string(record, mapping.source_url_field.as_deref(), &mut reject);
+
+    match (title, content) {
+        (Some(title), Some(content)) if errors.is_empty() => Ok(AddDocumentRequest {
+            collection_id: collection_id.to_string(),
+            title,
+            content,
+            content_type: Some(ContentType::PlainText),
+            source_url,
+            author,
+            tags: (!tags.is_empty()).then_some(tags),
+            outline: None,
+            summary: None,
+            license: None,
+            provenance: Some(upload_provenance("json-records")),
+        }),
+        _ => Err(errors),
+    }
+}
+
+/// An optional mapped field; absent or null values are fine, anything but a string is not
+fn optional_string(
+    record: &Value,
+    field: Option<&str>,
+    reject: &mut impl FnMut(&str, &str),
+) -> Option<String> {
+    let field = field?;
+    match lookup(record, field)? {
+        Value::String(value) if !value.trim().is_empty() => Some(value.trim().to_string()),
+        Value::String(_) | Value::Null => None,
+        _ => {
+            reject(field, "Field must be a string");
+            None
+        }
+    }
+}
+
+/// Follow a dotted field path through nested objects and arrays
+fn lookup<'a>(record: &'a Value, path: &str) -> Option<&'a Value> {
+    path.split('.')
+        .try_fold(record, |value, segment| match value {
+            Value::Object(fields) => fields.get(segment),
+            Value::Array(items) => segment.parse::<usize>().ok().and_then(|i| items.get(i)),
+            _ => None,
+        })
+}
@@ -0,0 +1,93 @@
+// src/extractor/retry.rs
+//
+// Retries of extraction outcalls that fail transiently: rejections the subnet reports as
+// transient, timeouts, and 5xx responses. A failed outcall is repeated at once a few times,
+// since each attempt takes seconds anyway. A queued job still failing after that waits on
+// the timer instead, for a delay that doubles with each attempt and is jittered so jobs that
+// failed together do not retry together. Every retry spends outcall budget like a first
+// attempt and is counted in the collection's weekly stats.
+
+use ic_cdk::api::time;
+
+use crate::{
+    logging, outcall_budget,
+    stats::{self, StatEvent},
+    storage,
+    types::{ClanopediaError, RetryPolicy, MAX_RETRY_DELAY_SECS},
+};
+
+use super::ExtractionError;
+
+pub fn policy(collection_id: &str) -> RetryPolicy {
+    storage::get_collection(&collection_id.to_string())
+        .ok()
+        .and_then(|c| c.retry_policy)
+        .unwrap_or_default()
+}
+
+fn is_transient(error: &ClanopediaError) -> bool {
+    matches!(
+        error,
+        ClanopediaError::ExtractionFailed(ExtractionError::NetworkFailure {
+            retryable: true,
+            ..
+        })
+    )
+}
+
+/// Whether to repeat at once an outcall that failed with `error` after `retries` retries.
+/// Spends the outcall budget for the retry and counts it.
+pub fn retry_now(collection_id: &str, error: &ClanopediaError, retries: u32) -> bool {
+    if !is_transient(error) || retries >= policy(collection_id).max_retries {
+        return false;
+    }
+    if outcall_budget::spend(collection_id, 1).is_err() {
+        return false;
+    }
+    stats::record(collection_id, StatEvent::OutcallRetries(1));
+    logging::info(
+        "extractor",
+        Some(collection_id),
+        format!("Retrying outcall (retry {}): {}", retries + 1, error),
+    );
+    true
+}
+
+// Between half and all of the attempt's backoff, so the spread grows with the delay
+fn jittered_delay_nanos(policy: &RetryPolicy, attempt: u32, seed: u64) -> u64 {
+    let backoff_secs = (policy.base_delay_secs as u64)
+        .saturating_mul(1u64 << attempt.min(20))
+        .min(MAX_RETRY_DELAY_SECS as u64);
+    let half = backoff_secs * 1_000_000_000 / 2;
+    let mixed = (seed ^ time()).wrapping_mul(0x9E37_79B9_7F4A_7C15);
+    half + (mixed >> 32) % (half + 1)
+}
+
+/// When a queued job that failed with `error` should run again, given the deferred attempts
+/// it has made so far; None once the policy's attempts are used up
+pub fn retry_at(
+    collection_id: &str,
+    error: &ClanopediaError,
+    deferred: u32,
+    job_id: u64,
+) -> Option<u64> {
+    let policy = policy(collection_id);
+    if !is_transient(error) || deferred >= policy.max_deferred_retries {
+        return None;
+    }
+    let delay = jittered_delay_nanos(&policy, deferred, job_id);
+    stats::record(collection_id, StatEvent::OutcallRetries(1));
+    logging::warn(
+        "extractor",
+        Some(collection_id),
+        format!(
+            "Job {} retries in {}s (attempt {} of {}): {}",
+            job_id,
+            delay / 1_000_000_000,
+            deferred + 1,
+            policy.max_deferred_retries,
+            error
+        ),
+    );
+    Some(time() + delay)
+}
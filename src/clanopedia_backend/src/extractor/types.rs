@@ -1,10 +1,14 @@
 // src/extractor/types.rs
 
 use crate::{external::blueband::ContentType, DocumentId};
-use crate::{AddDocumentRequest, ProposalId};
-use candid::CandidType;
+use crate::{
+    AddDocumentRequest, Capability, DocumentProvenance, DocumentReplacement, FieldError,
+    OcrOutcome, OutlineEntry, ProposalId, SummarizationOutcome,
+};
+use candid::{CandidType, Principal};
 use ic_stable_structures::storable::Storable;
 use serde::{Deserialize, Serialize};
+use std::fmt;
 
 #[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
 pub enum ExtractionSource {
@@ -14,7 +18,7 @@ pub enum ExtractionSource {
     },
     Url {
         url: String,
-        api_key: Option<String>,
+        api_key_secret: Option<String>, // Name of a collection secret, never the key itself
     },
 }
 
@@ -22,8 +26,11 @@ pub enum ExtractionSource {
 pub enum FileType {
     Pdf,
     DocX,
+    Doc, // Legacy binary Word; recognized only to be refused clearly
+    Rtf,
     PlainText,
     Markdown,
+    Zip,
     Unknown,
 }
 
@@ -37,19 +44,46 @@ impl FileType {
 
         match extension.as_str() {
             "pdf" => FileType::Pdf,
-            "docx" | "doc" => FileType::DocX,
+            "docx" => FileType::DocX,
+            "doc" => FileType::Doc,
+            "rtf" => FileType::Rtf,
             "txt" => FileType::PlainText,
             "md" | "markdown" => FileType::Markdown,
+            "zip" => FileType::Zip,
             _ => FileType::Unknown,
         }
     }
 
+    /// The type by extension, corrected by the file's signature for the Word formats,
+    /// which are often saved under each other's extensions
+    pub fn from_file(filename: &str, data: &[u8]) -> Self {
+        const OLE_SIGNATURE: &[u8] = &[0xD0, 0xCF, 0x11, 0xE0, 0xA1, 0xB1, 0x1A, 0xE1];
+        const ZIP_SIGNATURE: &[u8] = b"PK\x03\x04";
+
+        let file_type = Self::from_filename(filename);
+        if !matches!(file_type, FileType::Doc | FileType::DocX | FileType::Rtf) {
+            return file_type;
+        }
+        if data.starts_with(OLE_SIGNATURE) {
+            FileType::Doc
+        } else if data.starts_with(b"{\\rtf") {
+            FileType::Rtf
+        } else if data.starts_with(ZIP_SIGNATURE) {
+            FileType::DocX
+        } else {
+            file_type
+        }
+    }
+
     pub fn to_content_type(&self) -> ContentType {
         match self {
             FileType::Pdf => ContentType::PlainText,
             FileType::DocX => ContentType::PlainText,
+            FileType::Doc => ContentType::PlainText,
+            FileType::Rtf => ContentType::PlainText,
             FileType::PlainText => ContentType::PlainText,
             FileType::Markdown => ContentType::Markdown,
+            FileType::Zip => ContentType::PlainText,
             FileType::Unknown => ContentType::PlainText,
         }
     }
@@ -59,6 +93,11 @@ impl FileType {
 pub enum UrlType {
     YouTube,
     GitHub,
+    GitLab,
+    Bitbucket,
+    GoogleDocs,
+    Ipfs,
+    Arweave,
     Unknown,
 }
 
@@ -68,6 +107,16 @@ impl UrlType {
             UrlType::YouTube
         } else if url.contains("github.com") {
             UrlType::GitHub
+        } else if url.contains("gitlab.com") {
+            UrlType::GitLab
+        } else if url.contains("bitbucket.org") {
+            UrlType::Bitbucket
+        } else if url.contains("docs.google.com") {
+            UrlType::GoogleDocs
+        } else if url.starts_with("ipfs://") || url.contains("/ipfs/") {
+            UrlType::Ipfs
+        } else if url.starts_with("ar://") || url.contains("arweave.net/") {
+            UrlType::Arweave
         } else {
             UrlType::Unknown
         }
@@ -80,6 +129,7 @@ pub struct ExtractionResult {
     pub content: String,
     pub content_type: ContentType,
     pub source_url: Option<String>,
+    pub outline: Option<Vec<OutlineEntry>>,
     pub metadata: Option<ExtractionMetadata>,
 }
 
@@ -90,6 +140,70 @@ pub struct ExtractionMetadata {
     pub author: Option<String>,
     pub created_at: Option<u64>,
     pub tags: Option<Vec<String>>,
+    pub page_errors: Vec<PageExtractionError>, // PDF pages whose text could not be read
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct PageExtractionError {
+    pub page: u32,
+    pub error: String,
+}
+
+/// Which fields of each JSON record become a document. Fields are keys of the record or
+/// dotted paths into nested objects (`meta.title`); array elements are addressed by index.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct RecordFieldMapping {
+    pub title_field: String,
+    pub content_field: String,
+    pub tag_fields: Vec<String>, // Each may hold a string or an array of strings
+    pub author_field: Option<String>,
+    pub source_url_field: Option<String>,
+}
+
+/// A record that was skipped, by its 0-based position in the array (or line in JSONL)
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct RecordError {
+    pub index: u32,
+    pub errors: Vec<FieldError>,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub enum ArchiveEntryStatus {
+    Extracted,
+    Skipped(String), // Not a file the extractor reads, so it was never opened
+    Failed(String),
+}
+
+/// What became of one file in an uploaded zip archive
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct ArchiveEntryReport {
+    pub path: String,
+    pub size: u64, // Uncompressed bytes
+    pub documents: u32,
+    pub status: ArchiveEntryStatus,
+    pub error: Option<ExtractionError>, // Why extracting the file failed, when known
+}
+
+impl ArchiveEntryReport {
+    pub fn skipped(path: String, size: u64, reason: String) -> Self {
+        Self {
+            path,
+            size,
+            documents: 0,
+            status: ArchiveEntryStatus::Skipped(reason),
+            error: None,
+        }
+    }
+
+    pub fn failed(path: String, size: u64, error: String) -> Self {
+        Self {
+            path,
+            size,
+            documents: 0,
+            status: ArchiveEntryStatus::Failed(error),
+            error: None,
+        }
+    }
 }
 
 #[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
@@ -116,8 +230,11 @@ impl Default for FileExtractionConfig {
             supported_types: vec![
                 FileType::Pdf,
                 FileType::DocX,
+                FileType::Doc,
+                FileType::Rtf,
                 FileType::PlainText,
                 FileType::Markdown,
+                FileType::Zip,
             ],
             extract_metadata: true,
         }
@@ -135,6 +252,7 @@ pub struct ExtractionProgress {
     pub processed_videos: u32,
     pub last_updated: u64,
     pub status: ExtractionStatus,
+    pub error: Option<ExtractionError>, // Set with a `Failed` status
 }
 
 #[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq)]
@@ -145,6 +263,166 @@ pub enum ExtractionStatus {
     Paused,
 }
 
+/// Why an extraction failed, so frontends can suggest a remedy instead of showing a message
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum ExtractionError {
+    UnsupportedType(String), // The file type or URL that cannot be extracted
+    TooLarge { bytes: u64, max_bytes: u64 },
+    NetworkFailure { retryable: bool, message: String },
+    ParseFailure { format: String, message: String }, // e.g. "pdf", "docx", "json"
+    QuotaExceeded(String), // A source's API quota, or the collection's content cap
+    AuthRequired(String),  // The source wants credentials that are missing or were refused
+}
+
+impl ExtractionError {
+    pub fn network(retryable: bool, message: impl Into<String>) -> Self {
+        Self::NetworkFailure {
+            retryable,
+            message: message.into(),
+        }
+    }
+
+    pub fn parse(format: &str, message: impl Into<String>) -> Self {
+        Self::ParseFailure {
+            format: format.to_string(),
+            message: message.into(),
+        }
+    }
+
+    /// The same error with its text cut to `max_chars`, for records with a storage bound
+    pub fn truncated(self, max_chars: usize) -> Self {
+        let cut = |text: String| text.chars().take(max_chars).collect();
+        match self {
+            Self::UnsupportedType(what) => Self::UnsupportedType(cut(what)),
+            Self::NetworkFailure { retryable, message } => Self::NetworkFailure {
+                retryable,
+                message: cut(message),
+            },
+            Self::ParseFailure { format, message } => Self::ParseFailure {
+                format,
+                message: cut(message),
+            },
+            Self::QuotaExceeded(message) => Self::QuotaExceeded(cut(message)),
+            Self::AuthRequired(message) => Self::AuthRequired(cut(message)),
+            too_large @ Self::TooLarge { .. } => too_large,
+        }
+    }
+}
+
+impl fmt::Display for ExtractionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExtractionError::UnsupportedType(what) => write!(f, "Unsupported type: {}", what),
+            ExtractionError::TooLarge { bytes, max_bytes } => {
+                write!(f, "Too large: {} bytes (max: {} bytes)", bytes, max_bytes)
+            }
+            ExtractionError::NetworkFailure { retryable, message } => {
+                let retry = if *retryable { " (consider retry)" } else { "" };
+                write!(f, "Network failure{}: {}", retry, message)
+            }
+            ExtractionError::ParseFailure { format, message } => {
+                write!(f, "Could not read {}: {}", format, message)
+            }
+            ExtractionError::QuotaExceeded(message) => write!(f, "Quota exceeded: {}", message),
+            ExtractionError::AuthRequired(message) => {
+                write!(f, "Authentication required: {}", message)
+            }
+        }
+    }
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum ExtractionJobState {
+    Queued,
+    Running, // At least one slice has run and more content is left
+    Completed,
+    Failed(String),
+    Cancelled,
+}
+
+/// A URL extraction worked through by the timer, one batch per slice, with its documents
+/// kept until the requester collects them. Jobs that add documents instead have `addition`
+/// set and an empty `url`.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct ExtractionJob {
+    pub id: u64,
+    pub collection_id: String,
+    pub url: String,
+    pub api_key_secret: Option<String>, // Resolved again for every slice
+    pub requested_by: Principal,
+    pub state: ExtractionJobState,
+    pub created_at: u64,
+    pub updated_at: u64,
+    pub slices: u32,
+    pub documents: u32, // Extracted so far, after the collection's pipeline
+    pub addition: Option<DocumentAddition>,
+    pub error: Option<ExtractionError>, // Why a failed job failed, when extraction was the cause
+    pub retry_at: Option<u64>, // Set while waiting to retry a transient failure
+    pub deferred_retries: Option<u32>, // Slices rerun after transient failures so far
+}
+
+impl ExtractionJob {
+    pub fn is_active(&self) -> bool {
+        matches!(
+            self.state,
+            ExtractionJobState::Queued | ExtractionJobState::Running
+        )
+    }
+
+    /// What the requester must still be allowed to do for the job to run
+    pub fn capability(&self) -> Capability {
+        match self.addition {
+            Some(_) => Capability::AddDocuments,
+            None => Capability::Extract,
+        }
+    }
+}
+
+/// Progress of a job adding queued documents to Blueband, a few per slice. The outcome of
+/// each document is stored apart from the job.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct DocumentAddition {
+    pub total: u32,
+    pub processed: u32,
+    pub failed: u32,
+    pub current_title: Option<String>, // Set while the document is being added
+    pub proposal_id: Option<ProposalId>, // The embed proposal raised once all are processed
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub enum AdditionOutcome {
+    Added(DocumentId),
+    Failed(String),
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct AdditionError {
+    pub index: u32,
+    pub title: String,
+    pub error: String,
+}
+
+/// How far a document addition job has got
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct JobProgress {
+    pub job_id: u64,
+    pub state: ExtractionJobState,
+    pub total: u32,
+    pub processed: u32,
+    pub current_title: Option<String>,
+    pub document_ids: Vec<DocumentId>,
+    pub errors: Vec<AdditionError>,
+    pub proposal_id: Option<ProposalId>,
+    pub queue_position: Option<u32>,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct ExtractionJobStatus {
+    pub job: ExtractionJob,
+    pub progress: Option<ExtractionProgress>, // The playlist position the next slice resumes from
+    pub queue_position: Option<u32>,          // Active jobs ahead of this one, if it is active
+}
+
 #[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
 pub struct AddDocumentsResult {
     pub document_ids: Vec<DocumentId>,
@@ -159,6 +437,74 @@ pub enum DocumentAction {
     ProposalCreated,  // Governance proposal was created
 }
 
+/// A repository file response kept for reuse by later extractions of the same URL; the
+/// body is stored on its own
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct CachedFetch {
+    pub url: String,
+    pub checksum: String, // Hex SHA-256 of the body
+    pub size: u64,
+    pub fetched_at: u64,
+    pub provenance: Option<DocumentProvenance>, // None for entries cached by older versions
+}
+
+/// Which cached repository responses an extraction read, and whether they were already cached
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct FetchProvenance {
+    pub url: String,
+    pub checksum: String,
+    pub fetched_at: u64,
+    pub from_cache: bool,
+}
+
+impl Storable for CachedFetch {
+    fn to_bytes(&self) -> std::borrow::Cow<'_, [u8]> {
+        std::borrow::Cow::Owned(candid::encode_one(self).unwrap())
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<'_, [u8]>) -> Self {
+        candid::decode_one(&bytes).unwrap()
+    }
+
+    const BOUND: ic_stable_structures::storable::Bound =
+        ic_stable_structures::storable::Bound::Bounded {
+            max_size: 16 * 1024, // Room for the provenance of the fetch
+            is_fixed_size: false,
+        };
+}
+
+impl Storable for AdditionOutcome {
+    fn to_bytes(&self) -> std::borrow::Cow<'_, [u8]> {
+        std::borrow::Cow::Owned(candid::encode_one(self).unwrap())
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<'_, [u8]>) -> Self {
+        candid::decode_one(&bytes).unwrap()
+    }
+
+    const BOUND: ic_stable_structures::storable::Bound =
+        ic_stable_structures::storable::Bound::Bounded {
+            max_size: 1024, // Errors are truncated to fit
+            is_fixed_size: false,
+        };
+}
+
+impl Storable for ExtractionJob {
+    fn to_bytes(&self) -> std::borrow::Cow<'_, [u8]> {
+        std::borrow::Cow::Owned(candid::encode_one(self).unwrap())
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<'_, [u8]>) -> Self {
+        candid::decode_one(&bytes).unwrap()
+    }
+
+    const BOUND: ic_stable_structures::storable::Bound =
+        ic_stable_structures::storable::Bound::Bounded {
+            max_size: 4 * 1024,
+            is_fixed_size: false,
+        };
+}
+
 impl Storable for ExtractionProgress {
     fn to_bytes(&self) -> std::borrow::Cow<[u8]> {
         std::borrow::Cow::Owned(candid::encode_one(self).unwrap())
@@ -174,6 +520,7 @@ impl Storable for ExtractionProgress {
             processed_videos: 0,
             last_updated: 0,
             status: ExtractionStatus::Failed("Failed to deserialize".to_string()),
+            error: None,
         })
     }
 
@@ -184,11 +531,142 @@ impl Storable for ExtractionProgress {
         };
 }
 
+/// What an extraction would ingest, without the full content
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct ExtractionPreview {
+    pub title: String,
+    pub content_type: Option<ContentType>,
+    pub source_url: Option<String>,
+    pub size: u64,
+    pub excerpt: String,
+}
+
+impl ExtractionPreview {
+    pub fn from_document(document: &AddDocumentRequest, excerpt_len: usize) -> Self {
+        Self {
+            title: document.title.clone(),
+            content_type: document.content_type.clone(),
+            source_url: document.source_url.clone(),
+            size: document.content.len() as u64,
+            excerpt: document.content.chars().take(excerpt_len).collect(),
+        }
+    }
+}
+
+/// What extracting a YouTube playlist is expected to cost in outcall cycles, from the page
+/// sizes and charges observed since the last upgrade
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct YouTubeCostEstimate {
+    pub total_videos: Option<u32>,
+    pub remaining_pages: Option<u32>, // None while the playlist size is unknown
+    pub max_response_bytes: u64,      // Requested per page
+    pub cycles_per_page: u64,
+    pub total_cycles: Option<u64>,
+    pub multiplier: f64, // Attached cycles over the published fee
+    pub observed_pages: u32,
+}
+
+/// A preview's documents and, for YouTube playlists, what the full extraction would cost
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct ExtractionPreviewResult {
+    pub documents: Vec<ExtractionPreview>,
+    pub youtube_cost: Option<YouTubeCostEstimate>,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum ExtractionChange {
+    Added,
+    Changed,
+    Unchanged,
+    Removed,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct ExtractionDiffEntry {
+    pub change: ExtractionChange,
+    pub document_id: Option<DocumentId>, // The existing document; None for additions
+    pub title: String,
+    pub source_url: Option<String>,
+}
+
+/// What re-ingesting a source would change in a collection
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct ExtractionDiff {
+    pub entries: Vec<ExtractionDiffEntry>,
+    pub added: u32,
+    pub changed: u32,
+    pub unchanged: u32,
+    pub removed: u32,
+}
+
+/// The diff of a re-sync and the proposal that embeds its new and changed documents;
+/// `proposal_id` is unset when nothing at the source changed
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct ResyncResult {
+    pub diff: ExtractionDiff,
+    pub replacements: Vec<DocumentReplacement>,
+    pub proposal_id: Option<ProposalId>,
+}
+
 /// Enhanced response structure that includes extraction info
 #[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
 pub struct ExtractionResponse {
     pub documents: Vec<AddDocumentRequest>,
     pub extraction_info: ExtractionInfo,
+    pub pipeline: Vec<DocumentPipelineReport>, // Empty when the collection has no pipeline
+    pub tag_suggestions: Vec<DocumentTagSuggestions>,
+    pub summarization: Option<SummarizationOutcome>, // None unless the collection summarizes
+    pub ocr: Option<OcrOutcome>, // Set when an image-only PDF was read through OCR
+    pub cache: Vec<FetchProvenance>,
+    pub record_errors: Vec<RecordError>, // JSON records that did not become documents
+    pub files: Vec<ArchiveEntryReport>, // One line per file of an uploaded zip archive
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct KeywordSuggestion {
+    pub keyword: String,
+    pub score: f64,
+}
+
+/// Suggested tags for `documents[document_index]`, best first, excluding tags it already has
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct DocumentTagSuggestions {
+    pub document_index: u32,
+    pub title: String,
+    pub keywords: Vec<KeywordSuggestion>,
+}
+
+/// A processing step run over extracted documents, in the order the collection lists them
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum PipelineStep {
+    Sanitize,
+    DetectLanguage,                  // Tags documents with `lang:<code>`
+    SplitChunks { max_chars: u32 },  // Splits long documents into numbered parts
+    Dedupe,                          // Drops documents whose content repeats earlier in the batch
+    InferTags { max_tags: u32 },
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum StepOutcome {
+    Changed,
+    Unchanged,
+    Dropped,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct PipelineStepRecord {
+    pub step: PipelineStep,
+    pub outcome: StepOutcome,
+    pub detail: Option<String>,
+}
+
+/// What each pipeline step did to one extracted document
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct DocumentPipelineReport {
+    pub title: String,
+    pub source_url: Option<String>,
+    pub steps: Vec<PipelineStepRecord>,
+    pub dropped: bool,
 }
 
 /// Information about the extraction process
@@ -200,6 +678,7 @@ pub struct ExtractionInfo {
     pub has_more: bool,
     pub can_resume: bool,
     pub summary_message: String,
+    pub error: Option<ExtractionError>, // Set with a `Failed` status
 }
 
 impl ExtractionInfo {
@@ -249,18 +728,22 @@ impl ExtractionInfo {
             has_more,
             can_resume,
             summary_message,
+            error: None,
         }
     }
 
     /// Create extraction info from progress
     pub fn from_progress(progress: &ExtractionProgress) -> Self {
         let has_more = matches!(progress.status, ExtractionStatus::Paused);
-        Self::new(
-            progress.status.clone(),
-            progress.processed_videos,
-            progress.total_videos,
-            has_more,
-        )
+        Self {
+            error: progress.error.clone(),
+            ..Self::new(
+                progress.status.clone(),
+                progress.processed_videos,
+                progress.total_videos,
+                has_more,
+            )
+        }
     }
 
     /// Create extraction info for file extraction (no pagination)
@@ -274,8 +757,11 @@ impl ExtractionInfo {
     }
 
     /// Create extraction info for failed extraction
-    pub fn for_failed_extraction(error_message: String) -> Self {
-        Self::new(ExtractionStatus::Failed(error_message), 0, None, false)
+    pub fn for_failed_extraction(error: ExtractionError) -> Self {
+        Self {
+            error: Some(error.clone()),
+            ..Self::new(ExtractionStatus::Failed(error.to_string()), 0, None, false)
+        }
     }
 }
 
@@ -293,6 +779,10 @@ impl Storable for AddDocumentRequest {
             source_url: None,
             author: None,
             tags: None,
+            outline: None,
+            summary: None,
+            license: None,
+            provenance: None,
         })
     }
 
@@ -2,16 +2,28 @@
 
 use chrono::DateTime;
 use ic_cdk::api::management_canister::http_request::{
-    http_request, CanisterHttpRequestArgument, HttpHeader, HttpMethod, HttpResponse, TransformArgs,
+    CanisterHttpRequestArgument, HttpHeader, HttpMethod, HttpResponse, TransformArgs,
     TransformContext, TransformFunc,
 };
+use ic_cdk::api::call::RejectionCode;
 use ic_cdk_macros::query;
 use serde_json::Value;
 
 use crate::external::blueband::ContentType;
-use crate::extractor::types::{ExtractionProgress, ExtractionStatus, UrlType, YouTubeVideoInfo};
-use crate::extractor::{sanitize_content, validate_content_size, Extractor};
-use crate::{AddDocumentRequest, ClanopediaError, ClanopediaResult};
+use crate::extractor::types::{
+    ExtractionError, ExtractionProgress, ExtractionStatus, UrlType, YouTubeCostEstimate,
+    YouTubeVideoInfo,
+};
+use crate::extractor::google_docs::extract_google_docs_content;
+use crate::extractor::outline::markdown_outline;
+use crate::extractor::permaweb::extract_permaweb_content;
+use crate::extractor::youtube_cost::{self, CallPlan};
+use crate::extractor::{
+    fetch_cache, mirrors, retry, http_provenance, sanitize_content, validate_content_size, Extractor,
+};
+use crate::{logging, outcall_budget};
+use crate::outcall_pricing::{self, http_request};
+use crate::{AddDocumentRequest, ClanopediaError, ClanopediaResult, DocumentProvenance, License};
 
 /// Structure to track YouTube playlist pagination state
 #[derive(Debug, Clone)]
@@ -61,7 +73,7 @@ impl YouTubePaginationState {
 }
 
 /// Maximum number of videos to extract in a single batch
-const YOUTUBE_BATCH_SIZE: u32 = 50;
+pub const YOUTUBE_BATCH_SIZE: u32 = 50;
 
 /// Extract content from URL (YouTube, GitHub, etc.)
 pub async fn extract_url_content(
@@ -75,33 +87,96 @@ pub async fn extract_url_content(
             if let Some(api_key) = api_key {
                 extract_youtube_content(&url, &collection_id, &api_key).await?
             } else {
-                return Err(ClanopediaError::InvalidInput(
-                    "YouTube API key is required".to_string(),
-                ));
+                return Err(api_key_required());
             }
         }
-        UrlType::GitHub => extract_github_content(&url, &collection_id).await?,
-        UrlType::Unknown => {
-            return Err(ClanopediaError::InvalidInput(
-                "Unsupported URL type".to_string(),
-            ))
+        UrlType::GitHub | UrlType::GitLab | UrlType::Bitbucket => {
+            let documents = extract_repository_content(&url, &collection_id).await?;
+            record_single_fetch(&url, &collection_id, documents.len());
+            documents
+        }
+        UrlType::GoogleDocs => {
+            let documents = extract_google_docs_content(&url, &collection_id).await?;
+            record_single_fetch(&url, &collection_id, documents.len());
+            documents
+        }
+        UrlType::Ipfs | UrlType::Arweave => {
+            let documents = extract_permaweb_content(&url, &collection_id).await?;
+            record_single_fetch(&url, &collection_id, documents.len());
+            documents
         }
+        UrlType::Unknown => return Err(unsupported_url(&url)),
     };
 
-    // Update extraction progress to completed
+    Ok(documents)
+}
+
+fn api_key_required() -> ClanopediaError {
+    ExtractionError::AuthRequired("YouTube API key is required".to_string()).into()
+}
+
+fn unsupported_url(url: &str) -> ClanopediaError {
+    ExtractionError::UnsupportedType(format!("URL {}", url)).into()
+}
+
+/// YouTube extraction keeps its own progress; a single-file fetch is always complete
+fn record_single_fetch(url: &str, collection_id: &str, documents: usize) {
     let progress = ExtractionProgress {
-        url: url.clone(),
-        collection_id: collection_id.clone(),
+        url: url.to_string(),
+        collection_id: collection_id.to_string(),
         playlist_id: String::new(),
         next_page_token: None,
         total_videos: None,
-        processed_videos: documents.len() as u32,
+        processed_videos: documents as u32,
         last_updated: ic_cdk::api::time(),
         status: ExtractionStatus::Completed,
+        error: None,
     };
     Extractor::update_progress(progress);
+}
 
-    Ok(documents)
+/// Extract the first batch of content from a URL without recording any progress
+pub async fn preview_url_content(
+    url: &str,
+    collection_id: &str,
+    api_key: Option<String>,
+) -> ClanopediaResult<Vec<AddDocumentRequest>> {
+    preview_url_content_with_cost(url, collection_id, api_key)
+        .await
+        .map(|(documents, _)| documents)
+}
+
+/// Like `preview_url_content`, with what extracting a whole YouTube playlist would cost
+pub async fn preview_url_content_with_cost(
+    url: &str,
+    collection_id: &str,
+    api_key: Option<String>,
+) -> ClanopediaResult<(Vec<AddDocumentRequest>, Option<YouTubeCostEstimate>)> {
+    let documents = match UrlType::from_url(url) {
+        UrlType::YouTube => {
+            let api_key = api_key.ok_or_else(api_key_required)?;
+            let playlist_id = extract_youtube_playlist_id(url)?;
+            let mut pagination_state = YouTubePaginationState::new(playlist_id);
+            outcall_budget::spend(collection_id, 1)?;
+            let (videos, provenance) =
+                fetch_youtube_batch(&mut pagination_state, &api_key, collection_id).await?;
+            let documents = videos
+                .into_iter()
+                .map(|video| youtube_video_to_document(video, collection_id, &provenance))
+                .collect::<ClanopediaResult<Vec<_>>>()?;
+            // The extraction itself starts again from the first page
+            let cost =
+                youtube_cost::estimate(pagination_state.total_videos, 0, YOUTUBE_BATCH_SIZE);
+            return Ok((documents, Some(cost)));
+        }
+        UrlType::GitHub | UrlType::GitLab | UrlType::Bitbucket => {
+            extract_repository_content(url, collection_id).await
+        }
+        UrlType::GoogleDocs => extract_google_docs_content(url, collection_id).await,
+        UrlType::Ipfs | UrlType::Arweave => extract_permaweb_content(url, collection_id).await,
+        UrlType::Unknown => Err(unsupported_url(url)),
+    }?;
+    Ok((documents, None))
 }
 
 /// Extract YouTube content with pagination support and progress tracking
@@ -111,6 +186,8 @@ async fn extract_youtube_content(
     api_key: &str,
 ) -> ClanopediaResult<Vec<AddDocumentRequest>> {
     let playlist_id = extract_youtube_playlist_id(url)?;
+    // Before any progress is written, so an exhausted budget leaves the extraction resumable
+    outcall_budget::spend(collection_id, 1)?;
 
     // Check if there's existing progress for this URL/collection
     let mut pagination_state =
@@ -140,12 +217,14 @@ async fn extract_youtube_content(
         processed_videos: pagination_state.processed_videos,
         last_updated: ic_cdk::api::time(),
         status: ExtractionStatus::InProgress,
+        error: None,
     };
     Extractor::update_progress(progress);
 
     // Fetch videos (single batch for now - 50 videos max)
-    let videos = match fetch_youtube_batch(&mut pagination_state, api_key).await {
-        Ok(videos) => videos,
+    let fetched = fetch_youtube_batch(&mut pagination_state, api_key, collection_id).await;
+    let (videos, provenance) = match fetched {
+        Ok(batch) => batch,
         Err(e) => {
             // Update progress to failed
             let failed_progress = ExtractionProgress {
@@ -157,6 +236,7 @@ async fn extract_youtube_content(
                 processed_videos: pagination_state.processed_videos,
                 last_updated: ic_cdk::api::time(),
                 status: ExtractionStatus::Failed(e.to_string()),
+                error: e.extraction_error(),
             };
             Extractor::update_progress(failed_progress);
             return Err(e);
@@ -174,6 +254,7 @@ async fn extract_youtube_content(
             processed_videos: pagination_state.processed_videos,
             last_updated: ic_cdk::api::time(),
             status: ExtractionStatus::Failed("No videos found".to_string()),
+            error: None,
         };
         Extractor::update_progress(final_progress);
 
@@ -185,7 +266,7 @@ async fn extract_youtube_content(
     // Transform videos to documents
     let mut documents = Vec::new();
     for video in videos {
-        let document = youtube_video_to_document(video, collection_id)?;
+        let document = youtube_video_to_document(video, collection_id, &provenance)?;
         documents.push(document);
     }
 
@@ -206,6 +287,7 @@ async fn extract_youtube_content(
         processed_videos: pagination_state.processed_videos,
         last_updated: ic_cdk::api::time(),
         status: final_status,
+        error: None,
     };
     Extractor::update_progress(final_progress);
 
@@ -220,27 +302,69 @@ async fn extract_youtube_content(
     Ok(documents)
 }
 
-/// Fetch a single batch of YouTube videos (up to YOUTUBE_BATCH_SIZE)
+/// Fetch a single batch of YouTube videos (up to YOUTUBE_BATCH_SIZE), retrying transient
+/// failures under the collection's retry policy
 async fn fetch_youtube_batch(
     state: &mut YouTubePaginationState,
     api_key: &str,
-) -> ClanopediaResult<Vec<YouTubeVideoInfo>> {
-    let url = format!(
-        "https://www.googleapis.com/youtube/v3/playlistItems?part=snippet&playlistId={}&maxResults={}&key={}{}",
+    collection_id: &str,
+) -> ClanopediaResult<(Vec<YouTubeVideoInfo>, DocumentProvenance)> {
+    // Ask for no more items than the playlist has left, so the response limit can be smaller
+    let max_results = state.total_videos.map_or(YOUTUBE_BATCH_SIZE, |total| {
+        total
+            .saturating_sub(state.processed_videos)
+            .clamp(1, YOUTUBE_BATCH_SIZE)
+    });
+    let plan = youtube_cost::plan(max_results);
+    let mut retries = 0;
+    let (videos, mut provenance) = loop {
+        let result = match fetch_youtube_page(state, api_key, plan).await {
+            // Items larger than observed so far; once more at the largest response allowed
+            Err(ClanopediaError::ExtractionFailed(ExtractionError::NetworkFailure {
+                message, ..
+            })) if is_size_limit_error(&message)
+                    && plan.max_response_bytes < youtube_cost::MAX_RESPONSE_BYTES =>
+            {
+                fetch_youtube_page(state, api_key, youtube_cost::widened(plan)).await
+            }
+            result => result,
+        };
+        match result {
+            Err(e) if retry::retry_now(collection_id, &e, retries) => retries += 1,
+            result => break result?,
+        }
+    };
+    provenance.retries = Some(retries);
+    Ok((videos, provenance))
+}
+
+fn is_size_limit_error(message: &str) -> bool {
+    let message = message.to_lowercase();
+    message.contains("size limit") || message.contains("exceeds")
+}
+
+async fn fetch_youtube_page(
+    state: &mut YouTubePaginationState,
+    api_key: &str,
+    plan: CallPlan,
+) -> ClanopediaResult<(Vec<YouTubeVideoInfo>, DocumentProvenance)> {
+    // Provenance records the request without the API key
+    let public_url = format!(
+        "https://www.googleapis.com/youtube/v3/playlistItems?part=snippet&playlistId={}&maxResults={}{}",
         state.playlist_id,
-        YOUTUBE_BATCH_SIZE,
-        api_key,
+        plan.max_results,
         state.next_page_token.as_ref()
             .map(|token| format!("&pageToken={}", token))
             .unwrap_or_default()
     );
+    let url = format!("{}&key={}", public_url, api_key);
 
-    let cycles_needed = calculate_youtube_api_cycles();
+    let cycles_needed = plan.cycles;
     let request = CanisterHttpRequestArgument {
         url: url.clone(),
         method: HttpMethod::GET,
         body: None,
-        max_response_bytes: Some(1_000_000),
+        max_response_bytes: Some(plan.max_response_bytes),
         transform: Some(TransformContext {
             function: TransformFunc(candid::Func {
                 principal: ic_cdk::api::id(),
@@ -254,94 +378,112 @@ async fn fetch_youtube_batch(
         }],
     };
 
-    match http_request(request, cycles_needed).await {
+    let result = http_request(request, cycles_needed).await;
+    if result.is_ok() {
+        let refunded = ic_cdk::api::call::msg_cycles_refunded128();
+        youtube_cost::record_charge(&plan, cycles_needed.saturating_sub(refunded));
+    }
+
+    match result {
         Ok((response,)) => {
             let status = response.status.to_string().parse::<u32>().unwrap_or(0);
             if !(200..300).contains(&status) {
-                return Err(ClanopediaError::ExternalCallError(format!(
-                    "YouTube API error {}: {}",
-                    response.status,
-                    String::from_utf8_lossy(&response.body)
-                )));
+                return Err(status_error(
+                    "YouTube API",
+                    status,
+                    &String::from_utf8_lossy(&response.body),
+                ));
             }
 
-            let json: Value = serde_json::from_slice(&response.body).map_err(|e| {
-                ClanopediaError::ExternalCallError(format!("JSON parse error: {}", e))
-            })?;
+            let json: Value = serde_json::from_slice(&response.body)
+                .map_err(|e| ExtractionError::parse("json", e.to_string()))?;
 
             // Check for API errors
             if let Some(error) = json.get("error") {
-                return Err(ClanopediaError::ExternalCallError(format!(
-                    "YouTube API error: {}",
-                    error
-                )));
+                return Err(youtube_api_error(error));
+            }
+
+            if let Some(items) = json.get("items").and_then(|v| v.as_array()) {
+                youtube_cost::record_page(items.len() as u32, response.body.len() as u64);
             }
 
             // Update pagination state BEFORE parsing videos
             state.update_from_response(&json);
 
             // Parse and return videos
-            parse_youtube_response(&response.body)
+            let provenance = http_provenance("youtube", &public_url, &response);
+            parse_youtube_response(&response.body).map(|videos| (videos, provenance))
         }
         Err((rejection_code, message)) => {
-            if message.contains("cycles") || message.contains("OutOfCycles") {
-                Err(ClanopediaError::ExternalCallError(format!(
-                    "Insufficient cycles: sent {} cycles but need more. Error: {}",
-                    cycles_needed, message
-                )))
-            } else if message.contains("SysTransient") || message.contains("timeout") {
-                Err(ClanopediaError::ExternalCallError(format!(
-                    "Network error (consider retry): {:?} - {}",
-                    rejection_code, message
-                )))
-            } else {
-                Err(ClanopediaError::ExternalCallError(format!(
-                    "HTTP request failed: {:?} - {}",
-                    rejection_code, message
-                )))
-            }
+            Err(rejection_error(rejection_code, message, cycles_needed))
         }
     }
 }
 
-/// Extract content from GitHub URL (for markdown files)
-async fn extract_github_content(
+/// Extract a markdown file hosted on GitHub, GitLab or Bitbucket
+async fn extract_repository_content(
     url: &str,
     collection_id: &str,
 ) -> ClanopediaResult<Vec<AddDocumentRequest>> {
-    // Convert GitHub URL to raw content URL
-    let raw_url = convert_github_url_to_raw(url)?;
+    let url_type = UrlType::from_url(url);
+    let (host, tag) = match url_type {
+        UrlType::GitHub => ("GitHub", "github"),
+        UrlType::GitLab => ("GitLab", "gitlab"),
+        UrlType::Bitbucket => ("Bitbucket", "bitbucket"),
+        _ => return Err(unsupported_url(url)),
+    };
+    let raw_url = convert_repository_url_to_raw(url)?;
+
+    // Only responses missing from the cache cost an outcall
+    let outcalls = repository_fetch_urls(url)
+        .iter()
+        .filter(|u| !fetch_cache::is_cached(u))
+        .count() as u32;
+    if outcalls > 0 {
+        outcall_budget::spend(collection_id, outcalls)?;
+    }
 
-    ic_cdk::println!("Fetching GitHub content from: {}", raw_url);
+    ic_cdk::println!("Fetching {} content from: {}", host, raw_url);
 
     // Fetch raw content
-    let content = fetch_github_raw_content(&raw_url).await?;
+    let (content, provenance) = fetch_raw_content(&raw_url, tag, collection_id).await?;
 
     if content.trim().is_empty() {
-        return Err(ClanopediaError::InvalidInput(
-            "GitHub file is empty".to_string(),
-        ));
+        return Err(ExtractionError::parse("markdown", format!("{} file is empty", host)).into());
     }
 
     // Validate content size
-    validate_content_size(&content)?;
+    validate_content_size(collection_id, &content)?;
 
     // Extract filename from URL
-    let filename = extract_filename_from_url(url).unwrap_or_else(|| "github_document".to_string());
+    let filename = extract_filename_from_url(url).unwrap_or_else(|| format!("{}_document", tag));
+
+    let sanitized_content = sanitize_content(&content);
+    let outline = markdown_outline(&sanitized_content);
+    // Only GitHub reports a detected license
+    let license = match url_type {
+        UrlType::GitHub => fetch_github_license(url).await,
+        _ => None,
+    };
 
     // Create document
     let document = AddDocumentRequest {
         collection_id: collection_id.to_string(),
         title: filename.clone(),
-        content: sanitize_content(&content),
+        content: sanitized_content,
         content_type: Some(ContentType::Markdown),
         source_url: Some(url.to_string()),
         author: None,
-        tags: Some(vec!["github".to_string()]),
+        tags: Some(vec![tag.to_string()]),
+        outline: Some(outline),
+        summary: None,
+        license,
+        provenance: Some(provenance),
     };
 
     ic_cdk::println!(
-        "Successfully extracted GitHub content: {} characters",
+        "Successfully extracted {} content: {} characters",
+        host,
         content.len()
     );
 
@@ -376,24 +518,20 @@ fn extract_youtube_playlist_id(url: &str) -> ClanopediaResult<String> {
 
 /// Parse YouTube API response
 fn parse_youtube_response(response_body: &[u8]) -> ClanopediaResult<Vec<YouTubeVideoInfo>> {
-    let body_str = String::from_utf8(response_body.to_vec()).map_err(|e| {
-        ClanopediaError::ExternalCallError(format!("Invalid UTF-8 response: {}", e))
-    })?;
+    let body_str = String::from_utf8(response_body.to_vec())
+        .map_err(|e| ExtractionError::parse("json", format!("Invalid UTF-8 response: {}", e)))?;
 
     let json: Value = serde_json::from_str(&body_str)
-        .map_err(|e| ClanopediaError::ExternalCallError(format!("JSON parse error: {}", e)))?;
+        .map_err(|e| ExtractionError::parse("json", e.to_string()))?;
 
     // Check for API errors
     if let Some(error) = json.get("error") {
-        return Err(ClanopediaError::ExternalCallError(format!(
-            "YouTube API error: {}",
-            error
-        )));
+        return Err(youtube_api_error(error));
     }
 
     let items = json["items"]
         .as_array()
-        .ok_or_else(|| ClanopediaError::ExternalCallError("Missing items array".to_string()))?;
+        .ok_or_else(|| ExtractionError::parse("json", "Missing items array"))?;
 
     let mut videos = Vec::new();
 
@@ -436,6 +574,7 @@ fn parse_youtube_response(response_body: &[u8]) -> ClanopediaResult<Vec<YouTubeV
 fn youtube_video_to_document(
     video: YouTubeVideoInfo,
     collection_id: &str,
+    provenance: &DocumentProvenance,
 ) -> ClanopediaResult<AddDocumentRequest> {
     // Use description as content, or create basic content from title
     let content = video.description.clone().unwrap_or_else(|| {
@@ -448,7 +587,7 @@ fn youtube_video_to_document(
     });
 
     // Validate content size
-    validate_content_size(&content)?;
+    validate_content_size(collection_id, &content)?;
 
     let source_url = format!("https://www.youtube.com/watch?v={}", video.video_id);
 
@@ -460,6 +599,10 @@ fn youtube_video_to_document(
         source_url: Some(source_url),
         author: video.creator,
         tags: Some(vec!["youtube".to_string(), "video".to_string()]),
+        outline: None,
+        summary: None,
+        license: None,
+        provenance: Some(provenance.clone()),
     })
 }
 
@@ -483,8 +626,198 @@ fn convert_github_url_to_raw(url: &str) -> ClanopediaResult<String> {
     }
 }
 
-/// Fetch raw content from GitHub
-async fn fetch_github_raw_content(url: &str) -> ClanopediaResult<String> {
+/// Convert GitLab URL to raw content URL
+fn convert_gitlab_url_to_raw(url: &str) -> ClanopediaResult<String> {
+    if url.contains("/-/blob/") {
+        // Convert from: https://gitlab.com/group/project/-/blob/branch/file.md
+        // To: https://gitlab.com/group/project/-/raw/branch/file.md
+        Ok(url.replace("/-/blob/", "/-/raw/"))
+    } else if url.contains("/-/raw/") {
+        // Already a raw URL
+        Ok(url.to_string())
+    } else {
+        Err(ClanopediaError::InvalidInput(
+            "Invalid GitLab URL format. Expected gitlab.com/group/project/-/blob/branch/file.md"
+                .to_string(),
+        ))
+    }
+}
+
+/// Convert Bitbucket URL to raw content URL
+fn convert_bitbucket_url_to_raw(url: &str) -> ClanopediaResult<String> {
+    let Some((host, path)) = url.split_once("bitbucket.org/") else {
+        return Err(ClanopediaError::InvalidInput(
+            "Invalid Bitbucket URL".to_string(),
+        ));
+    };
+    // Convert from: https://bitbucket.org/workspace/repo/src/branch/file.md
+    // To: https://bitbucket.org/workspace/repo/raw/branch/file.md
+    let segments: Vec<&str> = path.splitn(4, '/').collect();
+    match segments.as_slice() {
+        [workspace, repo, "src" | "raw", rest] if !rest.is_empty() => Ok(format!(
+            "{}bitbucket.org/{}/{}/raw/{}",
+            host, workspace, repo, rest
+        )),
+        _ => Err(ClanopediaError::InvalidInput(
+            "Invalid Bitbucket URL format. Expected bitbucket.org/workspace/repo/src/branch/file.md"
+                .to_string(),
+        )),
+    }
+}
+
+fn convert_repository_url_to_raw(url: &str) -> ClanopediaResult<String> {
+    match UrlType::from_url(url) {
+        UrlType::GitHub => convert_github_url_to_raw(url),
+        UrlType::GitLab => convert_gitlab_url_to_raw(url),
+        UrlType::Bitbucket => convert_bitbucket_url_to_raw(url),
+        _ => Err(unsupported_url(url)),
+    }
+}
+
+/// "owner/repo" from a github.com or raw.githubusercontent.com URL
+fn github_repository(url: &str) -> Option<String> {
+    let path = url
+        .split_once("github.com/")
+        .or_else(|| url.split_once("githubusercontent.com/"))?
+        .1;
+    let mut segments = path.split('/').filter(|s| !s.is_empty());
+    Some(format!("{}/{}", segments.next()?, segments.next()?))
+}
+
+fn github_license_url(url: &str) -> Option<String> {
+    github_repository(url)
+        .map(|repository| format!("https://api.github.com/repos/{}/license", repository))
+}
+
+/// Every URL a repository extraction of `url` fetches: the raw file, and for GitHub the
+/// repository's license when the URL names a repository
+pub fn repository_fetch_urls(url: &str) -> Vec<String> {
+    let license_url = match UrlType::from_url(url) {
+        UrlType::GitHub => github_license_url(url),
+        UrlType::GitLab | UrlType::Bitbucket => None,
+        _ => return Vec::new(),
+    };
+    convert_repository_url_to_raw(url)
+        .ok()
+        .into_iter()
+        .chain(license_url)
+        .collect()
+}
+
+/// The repository's license as GitHub detected it. Repositories without one, or whose
+/// license GitHub could not identify, give None.
+async fn fetch_github_license(url: &str) -> Option<License> {
+    let api_url = github_license_url(url)?;
+    let body = match fetch_raw(&api_url, "application/vnd.github+json", "github").await {
+        Ok((body, _)) => body,
+        Err(e) => {
+            ic_cdk::println!("No license found for {}: {}", url, e);
+            return None;
+        }
+    };
+
+    let spdx_id = serde_json::from_str::<Value>(&body)
+        .ok()?
+        .pointer("/license/spdx_id")?
+        .as_str()?
+        .to_string();
+    (spdx_id != "NOASSERTION").then_some(License::Spdx(spdx_id))
+}
+
+/// Fetch raw content from GitHub, GitLab or Bitbucket, falling back through the configured
+/// mirrors while the host cannot be reached
+async fn fetch_raw_content(
+    url: &str,
+    extractor: &str,
+    collection_id: &str,
+) -> ClanopediaResult<(String, DocumentProvenance)> {
+    if let Some(cached) = fetch_cache::get(url) {
+        return Ok(cached);
+    }
+    let mut result = fetch_uncached(
+        url,
+        "text/plain",
+        "transform_github_response",
+        extractor,
+        Some(collection_id),
+    )
+    .await;
+    for (origin, mirror_url) in mirrors::mirror_urls(url) {
+        let Err(e) = &result else { break };
+        if !mirrors::should_fall_back(e) || outcall_budget::spend(collection_id, 1).is_err() {
+            break;
+        }
+        logging::warn(
+            "extractor",
+            Some(collection_id),
+            format!("Fetching {} from mirror {} after: {}", url, origin, e),
+        );
+        result = fetch_uncached(
+            &mirror_url,
+            "text/plain",
+            "transform_mirror_response",
+            extractor,
+            Some(collection_id),
+        )
+        .await
+        .map(|(body, mut provenance)| {
+            provenance.mirror = Some(origin);
+            (body, provenance)
+        });
+    }
+    // Cached under the file's own URL, whichever mirror served it
+    let (body, provenance) = result?;
+    fetch_cache::put(url, &body, &provenance);
+    Ok((body, provenance))
+}
+
+// A cached body keeps the provenance of the fetch that filled the cache
+async fn fetch_raw(
+    url: &str,
+    accept: &str,
+    extractor: &str,
+) -> ClanopediaResult<(String, DocumentProvenance)> {
+    if let Some(cached) = fetch_cache::get(url) {
+        return Ok(cached);
+    }
+    let (body, provenance) =
+        fetch_uncached(url, accept, "transform_github_response", extractor, None).await?;
+    fetch_cache::put(url, &body, &provenance);
+    Ok((body, provenance))
+}
+
+// Transient failures are retried under the policy of `retry_for`, if given
+async fn fetch_uncached(
+    url: &str,
+    accept: &str,
+    transform: &str,
+    extractor: &str,
+    retry_for: Option<&str>,
+) -> ClanopediaResult<(String, DocumentProvenance)> {
+    let mut retries = 0;
+    let response = loop {
+        match http_get(url, accept, transform, "Repository").await {
+            Err(e) if retry_for.is_some_and(|c| retry::retry_now(c, &e, retries)) => {
+                retries += 1
+            }
+            result => break result?,
+        }
+    };
+    let mut provenance = http_provenance(extractor, url, &response);
+    provenance.retries = Some(retries);
+    let body = String::from_utf8(response.body)
+        .map_err(|e| ExtractionError::parse("text", format!("Invalid UTF-8 content: {}", e)))?;
+    Ok((body, provenance))
+}
+
+/// GET `url` through an outcall whose response passes through the `transform` query.
+/// `source` names the service in errors; non-2xx responses are errors.
+pub(super) async fn http_get(
+    url: &str,
+    accept: &str,
+    transform: &str,
+    source: &str,
+) -> ClanopediaResult<HttpResponse> {
     let cycles_needed = calculate_github_fetch_cycles();
 
     let request = CanisterHttpRequestArgument {
@@ -495,7 +828,7 @@ async fn fetch_github_raw_content(url: &str) -> ClanopediaResult<String> {
         transform: Some(TransformContext {
             function: TransformFunc(candid::Func {
                 principal: ic_cdk::api::id(),
-                method: "transform_github_response".to_string(),
+                method: transform.to_string(),
             }),
             context: vec![],
         }),
@@ -506,7 +839,7 @@ async fn fetch_github_raw_content(url: &str) -> ClanopediaResult<String> {
             },
             HttpHeader {
                 name: "Accept".to_string(),
-                value: "text/plain".to_string(),
+                value: accept.to_string(),
             },
         ],
     };
@@ -515,79 +848,83 @@ async fn fetch_github_raw_content(url: &str) -> ClanopediaResult<String> {
         Ok((response,)) => {
             let status = response.status.to_string().parse::<u32>().unwrap_or(0);
             if !(200..300).contains(&status) {
-                return Err(ClanopediaError::ExternalCallError(format!(
-                    "GitHub fetch error {}: {}",
-                    response.status,
-                    String::from_utf8_lossy(&response.body)
-                )));
+                return Err(status_error(
+                    source,
+                    status,
+                    &String::from_utf8_lossy(&response.body),
+                ));
             }
 
-            String::from_utf8(response.body).map_err(|e| {
-                ClanopediaError::ExternalCallError(format!("Invalid UTF-8 content: {}", e))
-            })
+            Ok(response)
         }
         Err((rejection_code, message)) => {
-            if message.contains("cycles") || message.contains("OutOfCycles") {
-                Err(ClanopediaError::ExternalCallError(format!(
-                    "Insufficient cycles: sent {} cycles but need more. Error: {}",
-                    cycles_needed, message
-                )))
-            } else if message.contains("SysTransient") || message.contains("timeout") {
-                Err(ClanopediaError::ExternalCallError(format!(
-                    "Network error (consider retry): {:?} - {}",
-                    rejection_code, message
-                )))
-            } else {
-                Err(ClanopediaError::ExternalCallError(format!(
-                    "HTTP request failed: {:?} - {}",
-                    rejection_code, message
-                )))
-            }
+            Err(rejection_error(rejection_code, message, cycles_needed))
         }
     }
 }
 
-/// Extract filename from URL
-fn extract_filename_from_url(url: &str) -> Option<String> {
-    url.split('/').next_back().map(|s| s.to_string())
+/// Classify a non-2xx response: credentials and quotas have their own remedies, and server
+/// errors may pass on a retry
+fn status_error(source: &str, status: u32, body: &str) -> ClanopediaError {
+    let message = format!("{} error {}: {}", source, status, body);
+    let error = match status {
+        // Google reports exhausted quotas as 403s
+        403 if is_quota_error(body) => ExtractionError::QuotaExceeded(message),
+        401 | 403 => ExtractionError::AuthRequired(message),
+        429 => ExtractionError::QuotaExceeded(message),
+        408 | 500..=599 => ExtractionError::network(true, message),
+        _ => ExtractionError::network(false, message),
+    };
+    error.into()
 }
 
-/// Calculate cycles needed for YouTube API call
-fn calculate_youtube_api_cycles() -> u128 {
-    let n = 13u128; // 13-node subnet
-    let base_fee = (3_000_000 + 60_000 * n) * n;
-    
-    // Much more conservative estimates for YouTube API
-    let request_size = 1000; // Increased estimate for URL + headers + query params
-    let request_fee = 400 * n * request_size;
-    
-    // YouTube API responses can be large with video metadata
-    let response_size = 500_000; // Increased to 500KB for playlist data
-    let response_fee = 800 * n * response_size;
+fn is_quota_error(body: &str) -> bool {
+    ["quotaExceeded", "rateLimitExceeded", "dailyLimitExceeded"]
+        .iter()
+        .any(|reason| body.contains(reason))
+}
 
-    let total_calculated = base_fee + request_fee + response_fee;
-    
-    // Use 4x buffer for YouTube API (more conservative)
-    let with_buffer = (total_calculated as f64 * 4.0) as u128;
-    
-    // Ensure minimum of 10B cycles for YouTube API
-    with_buffer.max(10_000_000_000)
+// An error object in a 2xx YouTube response, e.g. `{"code": 403, "message": ...}`
+fn youtube_api_error(error: &Value) -> ClanopediaError {
+    let status = error["code"].as_u64().unwrap_or(0) as u32;
+    status_error("YouTube API", status, &error.to_string())
+}
+
+/// Classify a rejected outcall; the canister running short of cycles is not the source's fault
+fn rejection_error(
+    rejection_code: RejectionCode,
+    message: String,
+    cycles_needed: u128,
+) -> ClanopediaError {
+    if message.contains("cycles") || message.contains("OutOfCycles") {
+        ClanopediaError::ExternalCallError(format!(
+            "Insufficient cycles: sent {} cycles but need more. Error: {}",
+            cycles_needed, message
+        ))
+    } else {
+        let retryable = message.contains("SysTransient") || message.contains("timeout");
+        ExtractionError::network(
+            retryable,
+            format!("HTTP request failed: {:?} - {}", rejection_code, message),
+        )
+        .into()
+    }
+}
+
+/// Extract filename from URL
+fn extract_filename_from_url(url: &str) -> Option<String> {
+    url.split('/').next_back().map(|s| s.to_string())
 }
 
 /// Calculate cycles needed for GitHub fetch
 fn calculate_github_fetch_cycles() -> u128 {
-    let n = 13u128; // 13-node subnet
-    let base_fee = (3_000_000 + 60_000 * n) * n;
-    
     // Conservative estimates for GitHub
     let request_size = 500; // URL + headers
-    let request_fee = 400 * n * request_size;
-    
+
     // GitHub responses are typically smaller
     let response_size = 200_000; // 200KB for markdown files
-    let response_fee = 800 * n * response_size;
 
-    let total_calculated = base_fee + request_fee + response_fee;
+    let total_calculated = outcall_pricing::http_fee(request_size, response_size);
     
     // Use 3x buffer for GitHub (less conservative than YouTube)
     let with_buffer = (total_calculated as f64 * 3.0) as u128;
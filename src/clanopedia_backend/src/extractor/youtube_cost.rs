@@ -0,0 +1,133 @@
+// src/extractor/youtube_cost.rs
+//
+// Cycles for YouTube playlist outcalls, sized from what a request can return instead of a
+// fixed 500KB guess. The replica charges for `max_response_bytes`, so that limit follows
+// the number of items requested and the bytes per item seen on earlier pages. The cycles
+// attached carry a multiplier over the published fee formula that is tuned from what
+// earlier calls were actually charged. Observations live on the heap and start over after
+// an upgrade.
+
+use std::cell::RefCell;
+
+use crate::extractor::types::YouTubeCostEstimate;
+use crate::outcall_pricing;
+
+const REQUEST_BYTES: u128 = 1_000; // URL, query parameters and headers
+const PAGE_OVERHEAD_BYTES: f64 = 2_000.0; // Page info and tokens around the items
+const DEFAULT_ITEM_BYTES: f64 = 6_000.0; // Generous until a page has been seen
+const RESPONSE_HEADROOM: f64 = 1.5;
+const MIN_RESPONSE_BYTES: u64 = 16 * 1024;
+pub const MAX_RESPONSE_BYTES: u64 = 2_000_000; // The replica's own limit
+const ITEM_SMOOTHING: f64 = 0.3;
+
+const DEFAULT_MULTIPLIER: f64 = 4.0;
+const MIN_MULTIPLIER: f64 = 1.1;
+const MULTIPLIER_SAFETY: f64 = 1.2; // Kept over the highest ratio recently charged
+const MULTIPLIER_DECAY: f64 = 0.8; // Share of the old multiplier kept after a cheaper call
+
+struct Observations {
+    item_bytes: Option<f64>,
+    multiplier: f64,
+    pages: u32,
+}
+
+thread_local! {
+    static OBSERVATIONS: RefCell<Observations> = const {
+        RefCell::new(Observations {
+            item_bytes: None,
+            multiplier: DEFAULT_MULTIPLIER,
+            pages: 0,
+        })
+    };
+}
+
+/// What one playlist page request asks for and pays
+#[derive(Clone, Copy, Debug)]
+pub struct CallPlan {
+    pub max_results: u32,
+    pub max_response_bytes: u64,
+    pub cycles: u128,
+}
+
+// The HTTPS outcall fee for a response limit of `max_response_bytes`
+fn fee(max_response_bytes: u64) -> u128 {
+    outcall_pricing::http_fee(REQUEST_BYTES, max_response_bytes)
+}
+
+/// Plan a request for `max_results` playlist items
+pub fn plan(max_results: u32) -> CallPlan {
+    let (item_bytes, multiplier) = OBSERVATIONS.with(|o| {
+        let o = o.borrow();
+        (o.item_bytes.unwrap_or(DEFAULT_ITEM_BYTES), o.multiplier)
+    });
+    let expected = PAGE_OVERHEAD_BYTES + max_results as f64 * item_bytes;
+    let max_response_bytes =
+        ((expected * RESPONSE_HEADROOM) as u64).clamp(MIN_RESPONSE_BYTES, MAX_RESPONSE_BYTES);
+    CallPlan {
+        max_results,
+        max_response_bytes,
+        cycles: (fee(max_response_bytes) as f64 * multiplier) as u128,
+    }
+}
+
+/// The same request with the largest response the replica allows, after a page did not fit
+pub fn widened(plan: CallPlan) -> CallPlan {
+    let multiplier = OBSERVATIONS.with(|o| o.borrow().multiplier);
+    CallPlan {
+        max_response_bytes: MAX_RESPONSE_BYTES,
+        cycles: (fee(MAX_RESPONSE_BYTES) as f64 * multiplier) as u128,
+        ..plan
+    }
+}
+
+/// Learn the size of playlist items from a page of `items` that took `response_bytes`
+pub fn record_page(items: u32, response_bytes: u64) {
+    if items == 0 {
+        return;
+    }
+    let per_item = (response_bytes as f64 - PAGE_OVERHEAD_BYTES).max(0.0) / items as f64;
+    OBSERVATIONS.with(|o| {
+        let mut o = o.borrow_mut();
+        o.item_bytes = Some(match o.item_bytes {
+            Some(current) => current + ITEM_SMOOTHING * (per_item - current),
+            None => per_item,
+        });
+        o.pages += 1;
+    });
+}
+
+/// Tune the multiplier from what a planned call was actually charged: it rises at once to
+/// cover the charge and falls back slowly
+pub fn record_charge(plan: &CallPlan, charged: u128) {
+    let ratio = charged as f64 / fee(plan.max_response_bytes) as f64;
+    let needed = (ratio * MULTIPLIER_SAFETY).clamp(MIN_MULTIPLIER, DEFAULT_MULTIPLIER);
+    OBSERVATIONS.with(|o| {
+        let mut o = o.borrow_mut();
+        let decayed = MULTIPLIER_DECAY * o.multiplier + (1.0 - MULTIPLIER_DECAY) * needed;
+        o.multiplier = decayed.max(needed);
+    });
+}
+
+/// What extracting the rest of a playlist is expected to cost, page by page
+pub fn estimate(
+    total_videos: Option<u32>,
+    processed_videos: u32,
+    page_size: u32,
+) -> YouTubeCostEstimate {
+    let remaining = total_videos.map(|total| total.saturating_sub(processed_videos));
+    let remaining_pages = remaining.map(|r| r.div_ceil(page_size.max(1)));
+    let page = plan(remaining.map_or(page_size, |r| r.clamp(1, page_size)));
+    let (multiplier, observed_pages) = OBSERVATIONS.with(|o| {
+        let o = o.borrow();
+        (o.multiplier, o.pages)
+    });
+    YouTubeCostEstimate {
+        total_videos,
+        remaining_pages,
+        max_response_bytes: page.max_response_bytes,
+        cycles_per_page: page.cycles as u64,
+        total_cycles: remaining_pages.map(|pages| (page.cycles * pages as u128) as u64),
+        multiplier,
+        observed_pages,
+    }
+}
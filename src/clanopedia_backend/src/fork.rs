@@ -0,0 +1,313 @@
+// src/clanopedia_backend/src/fork.rs
+
+use std::collections::{BTreeSet, HashMap};
+
+use candid::Principal;
+use ic_cdk::api::time;
+
+use crate::{
+    admin, crosspost, encryption,
+    external::blueband,
+    governance, integrity, keyword_index, licensing, rate_limit,
+    stats::{self, StatEvent},
+    storage, trash,
+    types::{
+        ClanopediaError, ClanopediaResult, Collection, CollectionConfig, CollectionId,
+        DocumentRequest, ForkConfig, ForkOrigin, ForkResult, ForkedDocument, GovernanceModel,
+        ProposalType,
+    },
+};
+
+// Forks copy documents one Blueband call at a time; larger collections need several forks
+// of narrower scope, or a later BatchEmbed of the remainder
+const MAX_FORK_DOCUMENTS: usize = 100;
+const FORK_CALLS_PER_WINDOW: usize = 3;
+const FORK_WINDOW_NANOS: u64 = 60 * 60 * 1_000_000_000;
+
+/// Fork `source_collection_id` into a new multisig collection owned by the caller.
+///
+/// Document metadata (titles, tags, outlines, summaries, links) is always copied. With
+/// `copy_content` each document is re-added to the fork's own Blueband collection under a
+/// new ID and a BatchEmbed proposal is raised for it; otherwise the metadata keeps the
+/// source document IDs and documents must be re-added before they can be read or searched.
+pub async fn fork_collection(
+    source_collection_id: &CollectionId,
+    fork: ForkConfig,
+    caller: Principal,
+) -> ClanopediaResult<ForkResult> {
+    if caller == Principal::anonymous() {
+        return Err(ClanopediaError::NotAuthorized);
+    }
+    rate_limit::check_rate_limit(
+        "fork_collection",
+        caller,
+        FORK_CALLS_PER_WINDOW,
+        FORK_WINDOW_NANOS,
+    )?;
+
+    let source = storage::get_collection(source_collection_id)?;
+    trash::ensure_not_trashed(&source)?;
+    admin::ensure_not_paused(&source)?;
+    if encryption::is_encrypted(&source) {
+        return Err(ClanopediaError::InvalidOperation(
+            "Encrypted collections cannot be forked".to_string(),
+        ));
+    }
+
+    let document_ids = known_documents(&source);
+    if document_ids.len() > MAX_FORK_DOCUMENTS {
+        return Err(ClanopediaError::InvalidOperation(format!(
+            "Collection has {} documents; forks are limited to {}",
+            document_ids.len(),
+            MAX_FORK_DOCUMENTS
+        )));
+    }
+
+    let config = fork_config(&source, &fork, caller);
+    let collection_id = crate::create_collection_endpoint(config).await?;
+    let mut collection = storage::get_collection(&collection_id)?;
+    collection.forked_from = Some(ForkOrigin {
+        collection_id: source.id.clone(),
+        forked_by: caller,
+        forked_at: time(),
+    });
+    storage::update_collection(&collection_id, &collection)?;
+
+    let documents = if fork.copy_content {
+        copy_documents(&source, &collection, &document_ids).await
+    } else {
+        copy_metadata(&source, &collection, &document_ids)
+    };
+
+    let copied: Vec<String> = documents
+        .iter()
+        .filter_map(|d| d.document_id.clone())
+        .collect();
+    let embed_proposal_id = if copied.is_empty() {
+        None
+    } else {
+        stats::record(
+            &collection_id,
+            StatEvent::DocumentsAdded(copied.len() as u64),
+        );
+        let description = format!(
+            "Embed {} documents forked from {}",
+            copied.len(),
+            source.name
+        );
+        let proposal_type = ProposalType::BatchEmbed {
+            document_ids: copied,
+        };
+        Some(governance::create_proposal(&collection_id, proposal_type, caller, description).await?)
+    };
+
+    Ok(ForkResult {
+        collection_id,
+        documents,
+        embed_proposal_id,
+    })
+}
+
+fn fork_config(source: &Collection, fork: &ForkConfig, caller: Principal) -> CollectionConfig {
+    let mut config = CollectionConfig {
+        name: fork
+            .name
+            .clone()
+            .unwrap_or_else(|| format!("{} (fork)", source.name)),
+        description: fork
+            .description
+            .clone()
+            .unwrap_or_else(|| source.description.clone()),
+        admins: vec![caller.to_string()],
+        threshold: 1,
+        governance_token: None,
+        sns_governance_canister: None,
+        governance_model: GovernanceModel::Multisig,
+        quorum_threshold: 0,
+        is_permissionless: false,
+        proposal_bond: None,
+        proposer_eligibility: None,
+        embed_concurrency: None,
+        embedding_proxy: None, // Proxy credentials are sealed to the source collection
+        encryption: None,
+        pipeline: None,
+        auto_tag: None,
+        summarization: None, // Refers to the source collection's secrets
+        ocr: None,           // Refers to the source collection's secrets
+        nft_minting: None,   // Attestations belong to the source community
+        license: None,
+        vote_reminder_hours: source.vote_reminder_hours,
+        timelock_hours: source.timelock_hours,
+        custom_proposals: None, // Targets belong to the source community
+        call_policy: None,
+        review_policy: None, // Reviewers belong to the source community
+        content_limits: None,
+        retry_policy: None,
+    };
+
+    if fork.copy_settings {
+        config.pipeline = source.pipeline.clone();
+        config.auto_tag = source.auto_tag.clone();
+        config.embed_concurrency = source.embed_concurrency;
+        config.license = source.license.clone();
+        config.content_limits = source.content_limits.clone();
+        config.retry_policy = source.retry_policy.clone();
+    }
+    config
+}
+
+// Documents with Clanopedia-side records, plus everything embed proposals have named;
+// archived documents have no content to copy
+fn known_documents(source: &Collection) -> Vec<String> {
+    let mut ids: BTreeSet<String> = storage::list_known_document_ids(&source.id)
+        .into_iter()
+        .collect();
+    for proposal in storage::list_proposals(&source.id).values() {
+        match &proposal.proposal_type {
+            ProposalType::EmbedDocument { documents } => ids.extend(documents.iter().cloned()),
+            ProposalType::BatchEmbed { document_ids } => ids.extend(document_ids.iter().cloned()),
+            ProposalType::ResyncDocuments { replacements } => {
+                ids.extend(replacements.iter().map(|r| r.document_id.clone()))
+            }
+            _ => {}
+        }
+    }
+    ids.into_iter()
+        .filter(|id| !trash::is_document_trashed(&source.id, id))
+        .filter(|id| storage::get_archived_document(&source.id, id).is_none())
+        .collect()
+}
+
+async fn copy_documents(
+    source: &Collection,
+    fork: &Collection,
+    document_ids: &[String],
+) -> Vec<ForkedDocument> {
+    let mut documents = Vec::new();
+    for source_document_id in document_ids {
+        let mut forked = ForkedDocument {
+            source_document_id: source_document_id.clone(),
+            document_id: None,
+            title: source_document_id.clone(),
+            error: None,
+        };
+        match copy_document(source, fork, source_document_id, &mut forked.title).await {
+            Ok(document_id) => forked.document_id = Some(document_id),
+            Err(e) => forked.error = Some(e),
+        }
+        documents.push(forked);
+    }
+    documents
+}
+
+// Re-add one document to the fork and carry its metadata over to the new ID
+async fn copy_document(
+    source: &Collection,
+    fork: &Collection,
+    source_document_id: &str,
+    title: &mut String,
+) -> Result<String, String> {
+    // Linked documents are read from the collection that holds their content
+    let owner = crosspost::content_collection(source, source_document_id)
+        .map_err(|e| e.to_string())?;
+    let metadata = blueband::get_document_metadata(
+        owner.blueband_collection_id.clone(),
+        source_document_id.to_string(),
+    )
+    .await?
+    .ok_or_else(|| "Document not found in Blueband".to_string())?;
+    let content = blueband::get_document_content_from_blueband(
+        &owner.blueband_collection_id,
+        source_document_id,
+    )
+    .await?
+    .ok_or_else(|| "Document has no content".to_string())?;
+
+    let overlay = storage::get_document_overlay(&source.id, source_document_id);
+    let indexed = storage::get_indexed_document(&source.id, source_document_id);
+    *title = overlay
+        .as_ref()
+        .and_then(|o| o.title.clone())
+        .unwrap_or(metadata.title);
+    let tags = overlay
+        .as_ref()
+        .and_then(|o| o.tags.clone())
+        .or(indexed.map(|d| d.tags))
+        .unwrap_or_default();
+
+    let added = blueband::add_document_to_blueband(
+        &fork.blueband_collection_id,
+        DocumentRequest {
+            title: title.clone(),
+            content: content.clone(),
+            content_type: Some(metadata.content_type),
+            source_url: metadata.source_url,
+            author: overlay.as_ref().and_then(|o| o.author.clone()),
+            tags: Some(tags.clone()),
+        },
+    )
+    .await?;
+    integrity::check_added_document(fork, &content, &added)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let outline = storage::get_document_outline(&source.id, source_document_id)
+        .map(|outline| outline.entries);
+    // The copy keeps the provenance of the original retrieval
+    let provenance = storage::get_document_provenance(&source.id, source_document_id);
+    crate::record_document_structure(
+        fork, &added.id, title, &content, &tags, outline, None, provenance,
+    );
+    let license = licensing::document_license(source, overlay.as_ref());
+    if let Some(mut overlay) = overlay {
+        overlay.slug = None; // Slugs are registered per collection
+        overlay.updated_at = time();
+        storage::set_document_overlay(&fork.id, &added.id, overlay);
+    }
+    licensing::record_document_license(&fork.id, &added.id, license);
+    Ok(added.id)
+}
+
+// Copy metadata records under the source document IDs
+fn copy_metadata(
+    source: &Collection,
+    fork: &Collection,
+    document_ids: &[String],
+) -> Vec<ForkedDocument> {
+    let mut links: HashMap<String, _> = storage::list_document_links(&source.id)
+        .into_iter()
+        .collect();
+
+    document_ids
+        .iter()
+        .map(|document_id| {
+            let overlay = storage::get_document_overlay(&source.id, document_id);
+            let indexed = storage::get_indexed_document(&source.id, document_id);
+            let title = overlay
+                .as_ref()
+                .and_then(|o| o.title.clone())
+                .or(indexed.as_ref().map(|d| d.title.clone()))
+                .unwrap_or_else(|| document_id.clone());
+
+            if let Some(indexed) = indexed {
+                keyword_index::index_document(&fork.id, document_id, &indexed.title, &indexed.tags);
+            }
+            if let Some(overlay) = overlay {
+                storage::set_document_overlay(&fork.id, document_id, overlay);
+            }
+            if let Some(outline) = storage::get_document_outline(&source.id, document_id) {
+                storage::set_document_outline(&fork.id, document_id, outline);
+            }
+            if let Some(links) = links.remove(document_id) {
+                storage::set_document_links(&fork.id, document_id, links);
+            }
+
+            ForkedDocument {
+                source_document_id: document_id.clone(),
+                document_id: None,
+                title,
+                error: None,
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,195 @@
+// src/clanopedia_backend/src/glossary.rs
+//
+// Per-collection aliases that widen search queries, e.g. "WCHL" for "World Computer Hacker
+// League". Aliases are set and removed by `SetAliases` proposals. Before a query reaches
+// Blueband, each alias it mentions as whole words, ignoring case, has its expansion appended;
+// the expansions applied are reported on every match, so frontends can show what was searched.
+
+use ic_cdk::api::time;
+
+use crate::{
+    storage, trash,
+    types::{
+        AliasExpansion, AliasUpdate, ClanopediaError, ClanopediaResult, Collection, HybridMatch,
+        ProposalId, QueryAlias, StorageMap,
+    },
+    VectorMatch,
+};
+
+const MAX_ALIASES: usize = 500;
+const MAX_ALIAS_UPDATES: usize = 50;
+const MAX_ALIAS_CHARS: usize = 64;
+const MAX_EXPANSION_CHARS: usize = 200;
+const MAX_APPLIED_EXPANSIONS: usize = 5;
+
+fn words(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+// Aliases are stored under their words, so case and punctuation do not make a second alias
+fn alias_key(alias: &str) -> String {
+    words(alias).join(" ")
+}
+
+fn contains_phrase(text: &[String], phrase: &[String]) -> bool {
+    !phrase.is_empty() && text.windows(phrase.len()).any(|window| window == phrase)
+}
+
+/// Check a `SetAliases` proposal against the collection's current aliases
+pub fn validate_set_aliases(
+    collection: &Collection,
+    updates: &[AliasUpdate],
+) -> ClanopediaResult<()> {
+    if updates.is_empty() || updates.len() > MAX_ALIAS_UPDATES {
+        return Err(ClanopediaError::InvalidInput(format!(
+            "Between 1 and {} alias changes are required",
+            MAX_ALIAS_UPDATES
+        )));
+    }
+
+    let mut keys = storage::list_query_aliases(&collection.id)
+        .into_iter()
+        .map(|(key, _)| key)
+        .collect::<Vec<_>>();
+    let mut changed: Vec<String> = Vec::new();
+    for update in updates {
+        let key = alias_key(&update.alias);
+        if key.is_empty() || update.alias.trim().chars().count() > MAX_ALIAS_CHARS {
+            return Err(ClanopediaError::InvalidInput(format!(
+                "Alias {} must have a letter or digit and at most {} characters",
+                update.alias, MAX_ALIAS_CHARS
+            )));
+        }
+        if changed.contains(&key) {
+            return Err(ClanopediaError::InvalidInput(format!(
+                "Alias {} is changed more than once",
+                update.alias
+            )));
+        }
+        changed.push(key.clone());
+
+        match &update.expansion {
+            Some(expansion) => {
+                let expansion = expansion.trim();
+                if expansion.is_empty() || expansion.chars().count() > MAX_EXPANSION_CHARS {
+                    return Err(ClanopediaError::InvalidInput(format!(
+                        "The expansion of {} must have 1 to {} characters",
+                        update.alias, MAX_EXPANSION_CHARS
+                    )));
+                }
+                if alias_key(expansion) == key {
+                    return Err(ClanopediaError::InvalidInput(format!(
+                        "Alias {} expands to itself",
+                        update.alias
+                    )));
+                }
+                if !keys.contains(&key) {
+                    keys.push(key);
+                }
+            }
+            None => {
+                let before = keys.len();
+                keys.retain(|k| k != &key);
+                if keys.len() == before {
+                    return Err(ClanopediaError::NotFound(format!(
+                        "Alias {} not found",
+                        update.alias
+                    )));
+                }
+            }
+        }
+    }
+
+    if keys.len() > MAX_ALIASES {
+        return Err(ClanopediaError::InvalidOperation(format!(
+            "A collection can have at most {} aliases",
+            MAX_ALIASES
+        )));
+    }
+    Ok(())
+}
+
+/// Apply an approved `SetAliases` proposal
+pub fn execute_set_aliases(
+    collection_id: &str,
+    proposal_id: &ProposalId,
+    updates: &[AliasUpdate],
+) -> ClanopediaResult<()> {
+    let collection = storage::get_collection(&collection_id.to_string())?;
+    // Other proposals may have changed the aliases since this one was created
+    validate_set_aliases(&collection, updates)?;
+    let added = updates.iter().filter(|u| u.expansion.is_some()).count();
+    storage::ensure_capacity(StorageMap::QueryAliases, added as u64)?;
+
+    let now = time();
+    for update in updates {
+        let key = alias_key(&update.alias);
+        match &update.expansion {
+            Some(expansion) => storage::put_query_alias(
+                collection_id,
+                &key,
+                QueryAlias {
+                    alias: update.alias.trim().to_string(),
+                    expansion: expansion.trim().to_string(),
+                    updated_at: now,
+                    proposal_id: proposal_id.clone(),
+                },
+            ),
+            None => storage::remove_query_alias(collection_id, &key),
+        }
+    }
+    Ok(())
+}
+
+pub fn get_aliases(collection_id: &str) -> ClanopediaResult<Vec<QueryAlias>> {
+    let collection = storage::get_collection(&collection_id.to_string())?;
+    trash::ensure_not_trashed(&collection)?;
+    Ok(storage::list_query_aliases(collection_id)
+        .into_iter()
+        .map(|(_, alias)| alias)
+        .collect())
+}
+
+/// The query with the expansions of the aliases it mentions appended, and those expansions.
+/// Expansions the query already spells out are not appended again.
+pub fn expand_query(collection_id: &str, query: &str) -> (String, Vec<AliasExpansion>) {
+    let query_words = words(query);
+    let mut expanded = query.to_string();
+    let mut applied = Vec::new();
+    for (key, alias) in storage::list_query_aliases(collection_id) {
+        if applied.len() >= MAX_APPLIED_EXPANSIONS {
+            break;
+        }
+        let key_words: Vec<String> = key.split(' ').map(str::to_string).collect();
+        if !contains_phrase(&query_words, &key_words)
+            || contains_phrase(&query_words, &words(&alias.expansion))
+        {
+            continue;
+        }
+        expanded.push(' ');
+        expanded.push_str(&alias.expansion);
+        applied.push(AliasExpansion {
+            alias: alias.alias,
+            expansion: alias.expansion,
+        });
+    }
+    (expanded, applied)
+}
+
+pub fn annotate(matches: &mut [VectorMatch], applied: &[AliasExpansion]) {
+    if applied.is_empty() {
+        return;
+    }
+    for m in matches {
+        m.applied_aliases = Some(applied.to_vec());
+    }
+}
+
+pub fn annotate_hybrid(results: &mut [HybridMatch], applied: &[AliasExpansion]) {
+    for result in results {
+        result.applied_aliases = applied.to_vec();
+    }
+}
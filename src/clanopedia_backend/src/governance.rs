@@ -1,37 +1,44 @@
 // src/clanopedia_backend/src/governance.rs -
 
-use crate::external::sns_integration;
+use crate::external::{
+    nft,
+    sns_integration::{self, SnsApi},
+};
 use candid::{Nat, Principal};
+use futures_util::future::join_all;
 use getrandom::getrandom;
 use ic_cdk::api::caller;
 use ic_cdk::api::time;
-use ic_stable_structures::memory_manager::MemoryManager;
-use ic_stable_structures::{DefaultMemoryImpl, StableBTreeMap};
 use std::cell::RefCell;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::str;
 
 use crate::{
-    cycles,
-    external::{blueband, token},
-    storage,
+    admin, archive, attestations, auth, bonds, cold_storage, crosspost, custom_proposals, cycles,
+    encryption,
+    execution_receipts,
+    external::{
+        blueband,
+        token::{self, TokenApi},
+    },
+    extractor::{keywords, pipeline},
+    glossary, keyword_index, logging, notifications, ocr,
+    pins, proxy, receipts, resync, review, sealed_votes, slugs, source_policy, spam,
+    subscriptions,
+    stats::{self, StatEvent},
+    storage, summarize, timelock, timers, trash,
     types::{
-        ClanopediaError, ClanopediaResult, Collection, CollectionConfig, CollectionId,
-        GovernanceModel, Proposal, ProposalStatus, ProposalType, Vote, PROPOSAL_DURATION_NANOS,
+        ArchiveInfo, ChangeKind, ClanopediaError, ClanopediaResult, Collection, CollectionConfig, CollectionId,
+        DependencyStatus,
+        DocumentMetadataOverlay, ExecutionReport, GovernanceModel, ItemExecutionState, License, Proposal,
+        ProposalBondConfig, ProposalEvent, ProposalId, ProposalStatus, ProposalType, ProposerEligibility, Vote, VoteReceipt,
+        DEFAULT_EMBED_CONCURRENCY, MAX_DOCUMENT_OVERLAY_BYTES, MAX_EMBED_CONCURRENCY,
+        MAX_PROPOSAL_DEPENDENCIES,
+        PROPOSAL_DURATION_NANOS,
     },
+    validation,
 };
 
-// Stable memory management for proposals lookup
-thread_local! {
-    static MEMORY_MANAGER: RefCell<MemoryManager<DefaultMemoryImpl>> = RefCell::new(
-        MemoryManager::init(DefaultMemoryImpl::default())
-    );
-
-    static PROPOSALS: RefCell<StableBTreeMap<String, Proposal, DefaultMemoryImpl>> = RefCell::new(
-        StableBTreeMap::init(DefaultMemoryImpl::default())
-    );
-}
-
 // Helper function to get current time in nanoseconds
 fn current_time_ns() -> u64 {
     ic_cdk::api::time()
@@ -67,29 +74,28 @@ impl ExecutionPlan {
 // ============================
 
 pub async fn execute_proposal(collection_id: &str, proposal_id: &str) -> ClanopediaResult<()> {
+    // Held until this function returns; taken before the first await
+    let _lock = ExecutionLock::acquire(collection_id, proposal_id)?;
+
     // Phase 1: Load and validate basic state (read-only)
     let executor = caller();
     let collection = storage::get_collection(&collection_id.to_string())?;
-
-    // Get proposal directly from collection's proposals and clone it
-    let proposal = collection
-        .proposals
-        .get(proposal_id)
-        .ok_or_else(|| {
-            ClanopediaError::NotFound(format!(
-                "Proposal {} not found in collection {}",
-                proposal_id, collection_id
-            ))
-        })?
-        .clone();
+    admin::ensure_not_paused(&collection)?;
+    trash::ensure_not_trashed(&collection)?;
+
+    let proposal = storage::get_proposal(collection_id, proposal_id).ok_or_else(|| {
+        ClanopediaError::NotFound(format!(
+            "Proposal {} not found in collection {}",
+            proposal_id, collection_id
+        ))
+    })?;
+    archive::ensure_proposal_allowed(&collection, &proposal.proposal_type)?;
 
     // Phase 2: Pre-execution validation (no state changes)
     let mut execution_plan = ExecutionPlan::new();
 
     // Validate executor authorization
-    if !collection.admins.contains(&executor) {
-        return Err(ClanopediaError::NotAuthorized);
-    }
+    auth::require_admin(&collection, executor)?;
 
     // Validate proposal state
     if proposal.status != ProposalStatus::Approved {
@@ -97,12 +103,12 @@ pub async fn execute_proposal(collection_id: &str, proposal_id: &str) -> Clanope
             "Proposal must be approved to execute".to_string(),
         ));
     }
+    timelock::ensure_unlocked(&proposal)?;
 
     if proposal.expires_at < time() {
-        // Mark as expired but don't save yet - we'll do all saves atomically
-        let mut expired_proposal = proposal.clone();
-        expired_proposal.status = ProposalStatus::Expired;
-        storage::update_proposal_in_storage(&collection_id.to_string(), &expired_proposal)?;
+        if let Ok(expired) = expire_proposal(collection_id, proposal_id).await {
+            subscriptions::publish(collection_id, &expired, ProposalEvent::Expired);
+        }
         return Err(ClanopediaError::ProposalExpired);
     }
 
@@ -112,6 +118,9 @@ pub async fn execute_proposal(collection_id: &str, proposal_id: &str) -> Clanope
         ));
     }
 
+    ensure_dependencies_executed(collection_id, &proposal)?;
+    review::ensure_signed_off(&proposal)?;
+
     execution_plan.validation_passed = true;
 
     // Phase 3: Check threshold (read-only)
@@ -122,22 +131,123 @@ pub async fn execute_proposal(collection_id: &str, proposal_id: &str) -> Clanope
 
     execution_plan.prerequisites_met = true;
 
-    // Phase 4: Pre-execution cycles and resource validation (read-only)
+    // Phase 4: Pre-execution cycles and resource validation. Embed proposals reserve
+    // their estimated cycles until this function returns.
+    let mut _cycles_reservation = None;
     match &proposal.proposal_type {
         ProposalType::EmbedDocument { documents } => {
-            let (can_execute, message) =
-                cycles::can_execute_embed_proposal(&proposal, documents.clone()).await?;
-            if !can_execute {
-                return Err(ClanopediaError::InsufficientCycles(message));
-            }
+            _cycles_reservation =
+                Some(reserve_cycles(collection_id, &proposal, documents.clone()).await?);
         }
         ProposalType::BatchEmbed { document_ids } => {
-            let (can_execute, message) =
-                cycles::can_execute_embed_proposal(&proposal, document_ids.clone()).await?;
-            if !can_execute {
-                return Err(ClanopediaError::InsufficientCycles(message));
+            _cycles_reservation =
+                Some(reserve_cycles(collection_id, &proposal, document_ids.clone()).await?);
+        }
+        ProposalType::ResyncDocuments { replacements } => {
+            let document_ids = resync::document_ids(replacements);
+            _cycles_reservation =
+                Some(reserve_cycles(collection_id, &proposal, document_ids).await?);
+        }
+        ProposalType::Composite { actions } => {
+            validate_composite(collection_id, &collection, actions)?;
+        }
+        other => validate_operation(collection_id, &collection, other)?,
+    }
+
+    execution_plan.cycles_check_passed = true;
+
+    // Phase 5: Final safety check
+    if !execution_plan.is_ready_for_execution() {
+        return Err(ClanopediaError::InvalidOperation(
+            "Proposal execution prerequisites not met".to_string(),
+        ));
+    }
+
+    // Phase 6: ATOMIC EXECUTION - All external calls and state changes happen here
+    let proposal_id = proposal.id.clone();
+    let execution = run_execution(collection_id, proposal, executor);
+    execution_receipts::trace(collection_id, &proposal_id, executor, execution).await
+}
+
+// From this point on, we either succeed completely or fail completely
+// (embed proposals excepted: they record per-document state and can be resumed)
+async fn run_execution(
+    collection_id: &str,
+    proposal: Proposal,
+    executor: Principal,
+) -> ClanopediaResult<()> {
+    let execution_result = execute_proposal_operation(&proposal, collection_id).await;
+
+    match execution_result {
+        Ok(None) => {
+            // SUCCESS: Update proposal status atomically
+            let executed_proposal = store_outcome(collection_id, &proposal.id, |stored| {
+                stored.status = ProposalStatus::Executed;
+                stored.executed = true;
+                stored.executed_at = Some(time());
+                stored.executed_by = Some(executor);
+            })?;
+            notifications::proposal_executed(collection_id, &executed_proposal);
+            subscriptions::publish(collection_id, &executed_proposal, ProposalEvent::Executed);
+            Ok(())
+        }
+        Ok(Some(report)) => {
+            attestations::mint_for_report(collection_id, &proposal, &report).await;
+            finish_embed_execution(collection_id, proposal, report, executor)
+        }
+        Err(e) => {
+            // FAILURE: Mark proposal as failed but don't execute
+            let failed = store_outcome(collection_id, &proposal.id, |stored| {
+                stored.status = ProposalStatus::Rejected;
+            });
+            if let Ok(failed_proposal) = failed {
+                notifications::proposal_failed(collection_id, &failed_proposal, e.to_string());
+                subscriptions::publish(collection_id, &failed_proposal, ProposalEvent::Failed);
             }
+            notifications::resource_alert(collection_id, &e);
+            Err(e)
+        }
+    }
+}
+
+// Record an execution's outcome on the stored proposal, which a veto or expiry during the
+// execution's awaits may have settled; then the outcome is not recorded over it
+fn store_outcome(
+    collection_id: &str,
+    proposal_id: &str,
+    change: impl FnOnce(&mut Proposal),
+) -> ClanopediaResult<Proposal> {
+    let settled = || {
+        ClanopediaError::InvalidProposalState(format!(
+            "Proposal {} was vetoed or settled while it was executing",
+            proposal_id
+        ))
+    };
+    modify_proposal(collection_id, proposal_id, |stored| {
+        if stored.executed
+            || !matches!(
+                stored.status,
+                ProposalStatus::Approved | ProposalStatus::PartiallyExecuted
+            )
+        {
+            return Err(settled());
         }
+        change(stored);
+        Ok(())
+    })
+    .map_err(|e| match e {
+        ClanopediaError::NotFound(_) => settled(),
+        e => e,
+    })
+}
+
+// Phase 4 checks of one operation against the collection it will run on
+fn validate_operation(
+    collection_id: &str,
+    collection: &Collection,
+    proposal_type: &ProposalType,
+) -> ClanopediaResult<()> {
+    match proposal_type {
         ProposalType::AddAdmin { admin } => {
             if collection.admins.contains(admin) {
                 return Err(ClanopediaError::AlreadyExists(
@@ -171,8 +281,9 @@ pub async fn execute_proposal(collection_id: &str, proposal_id: &str) -> Clanope
             }
         }
         ProposalType::DeleteCollection => {
-            if !collection.proposals.is_empty() {
-                let active_count = collection.proposals.len();
+            let proposals = storage::list_proposals(collection_id);
+            if !proposals.is_empty() {
+                let active_count = proposals.len();
                 if active_count > 1 {
                     // More than just this proposal
                     return Err(ClanopediaError::InvalidOperation(format!(
@@ -182,60 +293,187 @@ pub async fn execute_proposal(collection_id: &str, proposal_id: &str) -> Clanope
                 }
             }
         }
+        ProposalType::UpdateDocumentMetadata {
+            title,
+            tags,
+            author,
+            license,
+            ..
+        } => {
+            if title.is_none() && tags.is_none() && author.is_none() && license.is_none() {
+                return Err(ClanopediaError::InvalidInput(
+                    "Metadata update must change at least one field".to_string(),
+                ));
+            }
+            if title.as_ref().is_some_and(|t| t.trim().is_empty()) {
+                return Err(ClanopediaError::InvalidInput(
+                    "Document title cannot be empty".to_string(),
+                ));
+            }
+        }
+        ProposalType::AddReader { reader } => {
+            encryption::validate_add_reader(collection, reader)?;
+        }
+        ProposalType::RemoveReader { reader } => {
+            encryption::validate_remove_reader(collection, reader)?;
+        }
+        ProposalType::RotateEncryptionKey if !encryption::is_encrypted(collection) => {
+            return Err(ClanopediaError::InvalidOperation(
+                "Collection is not encrypted".to_string(),
+            ));
+        }
+        ProposalType::LinkDocument {
+            source_collection,
+            document_id,
+        } => {
+            crosspost::validate_link(collection, source_collection, document_id)?;
+        }
+        ProposalType::ArchiveCollection => archive::validate_archive(collection)?,
+        ProposalType::UnarchiveCollection => archive::validate_unarchive(collection)?,
+        ProposalType::VetoProposal { proposal_id } => {
+            timelock::validate_veto(collection_id, proposal_id)?;
+        }
+        ProposalType::Custom { kind, payload } => {
+            custom_proposals::validate_custom(collection, kind, payload)?;
+        }
+        ProposalType::SetSlug { document_id, slug } => {
+            slugs::validate_set_slug(collection, document_id.as_ref(), slug.as_ref())?;
+        }
+        ProposalType::SetPinnedDocuments { document_ids } => {
+            pins::validate_set_pinned(collection, document_ids)?;
+        }
+        ProposalType::SetAliases { aliases } => {
+            glossary::validate_set_aliases(collection, aliases)?;
+        }
+        ProposalType::RestoreArchivedDocument { document_id } => {
+            cold_storage::validate_restore(collection, document_id)?;
+        }
+        ProposalType::SetSourcePolicy { policy } => {
+            source_policy::validate_set_source_policy(policy.as_ref())?;
+        }
         _ => {} // Other proposal types validated in their execution functions
     }
+    Ok(())
+}
 
-    execution_plan.cycles_check_passed = true;
+// Admins hear about executions that stalled on cycles
+async fn reserve_cycles(
+    collection_id: &str,
+    proposal: &Proposal,
+    documents: Vec<String>,
+) -> ClanopediaResult<cycles::CyclesReservation> {
+    cycles::reserve_embed_cycles(proposal, documents)
+        .await
+        .inspect_err(|e| notifications::resource_alert(collection_id, e))
+}
 
-    // Phase 5: Final safety check
-    if !execution_plan.is_ready_for_execution() {
-        return Err(ClanopediaError::InvalidOperation(
-            "Proposal execution prerequisites not met".to_string(),
-        ));
-    }
+thread_local! {
+    // "collection_id:proposal_id" of every proposal with an execution in flight
+    static EXECUTING: RefCell<HashSet<String>> = RefCell::new(HashSet::new());
+}
 
-    // Phase 6: ATOMIC EXECUTION - All external calls and state changes happen here
-    // From this point on, we either succeed completely or fail completely
-    let execution_result = execute_proposal_operation(&proposal.proposal_type, collection_id).await;
+/// Marks a proposal as executing so a second `execute_proposal` or retry cannot run
+/// alongside it. Released on drop, including when the future is dropped after a trap.
+struct ExecutionLock {
+    key: String,
+}
 
-    match execution_result {
-        Ok(()) => {
-            // SUCCESS: Update proposal status atomically
-            let mut executed_proposal = proposal;
-            executed_proposal.status = ProposalStatus::Executed;
-            executed_proposal.executed = true;
-            executed_proposal.executed_at = Some(time());
-            executed_proposal.executed_by = Some(executor);
-            storage::update_proposal_in_storage(&collection_id.to_string(), &executed_proposal)?;
-            Ok(())
-        }
-        Err(e) => {
-            // FAILURE: Mark proposal as failed but don't execute
-            let mut failed_proposal = proposal;
-            failed_proposal.status = ProposalStatus::Rejected;
-            storage::update_proposal_in_storage(&collection_id.to_string(), &failed_proposal)?;
-            Err(e)
+impl ExecutionLock {
+    fn acquire(collection_id: &str, proposal_id: &str) -> ClanopediaResult<Self> {
+        let key = format!("{}:{}", collection_id, proposal_id);
+        if !EXECUTING.with(|e| e.borrow_mut().insert(key.clone())) {
+            return Err(ClanopediaError::ExecutionInProgress(format!(
+                "Proposal {} is already being executed",
+                proposal_id
+            )));
         }
+        Ok(Self { key })
+    }
+}
+
+impl Drop for ExecutionLock {
+    fn drop(&mut self) {
+        EXECUTING.with(|e| {
+            e.borrow_mut().remove(&self.key);
+        });
+    }
+}
+
+// Executed when every item embedded, otherwise kept as PartiallyExecuted for retry
+fn finish_embed_execution(
+    collection_id: &str,
+    proposal: Proposal,
+    report: ExecutionReport,
+    executor: Principal,
+) -> ClanopediaResult<()> {
+    if let ProposalType::ResyncDocuments { replacements } = &proposal.proposal_type {
+        resync::retire_replaced(collection_id, replacements, &report, executor);
+    }
+    let complete = report.is_complete();
+    let (succeeded, failed) = (report.succeeded, report.failed);
+
+    let proposal = store_outcome(collection_id, &proposal.id, |stored| {
+        stored.status = if complete {
+            ProposalStatus::Executed
+        } else {
+            ProposalStatus::PartiallyExecuted
+        };
+        stored.executed = complete;
+        stored.executed_at = Some(time());
+        stored.executed_by = Some(executor);
+        stored.execution_report = Some(report);
+    })?;
+    if complete {
+        notifications::proposal_executed(collection_id, &proposal);
+        subscriptions::publish(collection_id, &proposal, ProposalEvent::Executed);
+    } else {
+        subscriptions::publish(collection_id, &proposal, ProposalEvent::Failed);
+        notifications::proposal_failed(
+            collection_id,
+            &proposal,
+            format!("{} of {} documents failed to embed", failed, succeeded + failed),
+        );
     }
+
+    if succeeded == 0 && failed > 0 {
+        return Err(ClanopediaError::BluebandError(format!(
+            "All {} documents failed to embed; use retry_failed_items to try again",
+            failed
+        )));
+    }
+    Ok(())
 }
 
 // ============================
 // ATOMIC OPERATION EXECUTOR
 // ============================
 
+// Embed proposals return their execution report; all other operations return None
 pub async fn execute_proposal_operation(
-    proposal_type: &ProposalType,
+    proposal: &Proposal,
     collection_id: &str,
-) -> ClanopediaResult<()> {
-    match proposal_type {
+) -> ClanopediaResult<Option<ExecutionReport>> {
+    let result = match &proposal.proposal_type {
         ProposalType::EmbedDocument { documents } => {
-            execute_embed_document(collection_id, documents).await
+            return execute_embed_document(collection_id, &proposal.id, documents)
+                .await
+                .map(Some)
         }
         ProposalType::BatchEmbed { document_ids } => {
-            execute_batch_embed(collection_id, document_ids).await
+            return execute_batch_embed(collection_id, &proposal.id, document_ids)
+                .await
+                .map(Some)
+        }
+        ProposalType::ResyncDocuments { replacements } => {
+            let document_ids = resync::document_ids(replacements);
+            return execute_batch_embed(collection_id, &proposal.id, &document_ids)
+                .await
+                .map(Some);
         }
         ProposalType::UpdateCollection { config } => {
-            execute_update_collection(collection_id, config.clone()).await
+            let config =
+                proxy::unseal_config_key((**config).clone(), &proposal.sealed_proxy_api_key)?;
+            execute_update_collection(collection_id, config).await
         }
         ProposalType::ChangeGovernanceModel { model } => {
             execute_change_governance_model(collection_id, model.clone()).await
@@ -249,7 +487,291 @@ pub async fn execute_proposal_operation(
             execute_update_quorum(collection_id, *new_percentage).await
         }
         ProposalType::DeleteCollection => execute_delete_collection(collection_id).await,
+        ProposalType::UpdateDocumentMetadata {
+            document_id,
+            title,
+            tags,
+            author,
+            license,
+        } => execute_update_document_metadata(
+            collection_id,
+            document_id,
+            title.clone(),
+            tags.clone(),
+            author.clone(),
+            license.clone(),
+        ),
+        ProposalType::AddReader { reader } => encryption::execute_add_reader(collection_id, *reader),
+        ProposalType::RemoveReader { reader } => {
+            encryption::execute_remove_reader(collection_id, *reader)
+        }
+        ProposalType::RotateEncryptionKey => encryption::execute_rotate_key(collection_id),
+        ProposalType::LinkDocument {
+            source_collection,
+            document_id,
+        } => {
+            crosspost::execute_link_document(collection_id, source_collection, document_id).await
+        }
+        ProposalType::ArchiveCollection => archive::execute_archive(collection_id, &proposal.id),
+        ProposalType::UnarchiveCollection => archive::execute_unarchive(collection_id),
+        ProposalType::Composite { actions } => {
+            execute_composite(proposal, collection_id, actions).await
+        }
+        ProposalType::VetoProposal { proposal_id } => {
+            // Keeps the vetoed proposal from executing meanwhile
+            let _lock = ExecutionLock::acquire(collection_id, proposal_id)?;
+            timelock::execute_veto(collection_id, proposal, proposal_id)
+        }
+        ProposalType::Custom { kind, payload } => {
+            let collection = storage::get_collection(&collection_id.to_string())?;
+            custom_proposals::execute_custom(&collection, &proposal.id, kind, payload).await
+        }
+        ProposalType::SetSlug { document_id, slug } => {
+            slugs::execute_set_slug(collection_id, document_id.as_ref(), slug.as_ref())
+        }
+        ProposalType::SetPinnedDocuments { document_ids } => {
+            pins::execute_set_pinned(collection_id, &proposal.id, document_ids)
+        }
+        ProposalType::SetAliases { aliases } => {
+            glossary::execute_set_aliases(collection_id, &proposal.id, aliases)
+        }
+        ProposalType::RestoreArchivedDocument { document_id } => {
+            cold_storage::execute_restore(collection_id, document_id).await.map(|_| ())
+        }
+        ProposalType::SetSourcePolicy { policy } => {
+            source_policy::execute_set_source_policy(collection_id, policy.as_ref())
+        }
+    };
+    result.map(|_| None)
+}
+
+// ============================
+// COMPOSITE PROPOSALS
+// ============================
+
+const MAX_COMPOSITE_ACTIONS: usize = 10;
+
+// Actions whose effects a failed composite can put back: Clanopedia's own state, plus the
+// admin list Blueband mirrors. Embeds, deletion, links and config updates change Blueband
+// in ways that cannot be undone.
+fn is_composable(action: &ProposalType) -> bool {
+    matches!(
+        action,
+        ProposalType::AddAdmin { .. }
+            | ProposalType::RemoveAdmin { .. }
+            | ProposalType::ChangeThreshold { .. }
+            | ProposalType::UpdateQuorum { .. }
+            | ProposalType::ChangeGovernanceModel { .. }
+            | ProposalType::UpdateDocumentMetadata { .. }
+            | ProposalType::AddReader { .. }
+            | ProposalType::RemoveReader { .. }
+            | ProposalType::RotateEncryptionKey
+            | ProposalType::ArchiveCollection
+            | ProposalType::SetSourcePolicy { .. }
+    )
+}
+
+/// Validate each action against the collection as the actions before it leave it, so an
+/// admin added by one action counts toward the threshold set by the next
+fn validate_composite(
+    collection_id: &str,
+    collection: &Collection,
+    actions: &[ProposalType],
+) -> ClanopediaResult<()> {
+    if actions.is_empty() || actions.len() > MAX_COMPOSITE_ACTIONS {
+        return Err(ClanopediaError::InvalidInput(format!(
+            "A composite proposal needs between 1 and {} actions",
+            MAX_COMPOSITE_ACTIONS
+        )));
     }
+
+    let mut simulated = collection.clone();
+    for (i, action) in actions.iter().enumerate() {
+        if !is_composable(action) {
+            return Err(ClanopediaError::InvalidInput(format!(
+                "Action {} cannot be part of a composite proposal; only admin, threshold, \
+                 quorum, governance model, reader, key, archive, source policy and metadata \
+                 changes can",
+                i + 1
+            )));
+        }
+        validate_operation(collection_id, &simulated, action)
+            .map_err(|e| ClanopediaError::InvalidInput(format!("Action {}: {}", i + 1, e)))?;
+        simulate(&mut simulated, action);
+    }
+    Ok(())
+}
+
+// The collection-level effect of an action, as its execute function applies it
+fn simulate(collection: &mut Collection, action: &ProposalType) {
+    match action {
+        ProposalType::AddAdmin { admin } if !collection.admins.contains(admin) => {
+            collection.admins.push(*admin);
+        }
+        ProposalType::RemoveAdmin { admin } => collection.admins.retain(|a| a != admin),
+        ProposalType::ChangeThreshold { new_threshold } => collection.threshold = *new_threshold,
+        ProposalType::UpdateQuorum { new_percentage } => {
+            collection.quorum_threshold = *new_percentage;
+        }
+        ProposalType::ChangeGovernanceModel { model } => {
+            collection.governance_model = model.clone();
+        }
+        ProposalType::AddReader { reader } => {
+            if let Some(encryption) = collection.encryption.as_mut() {
+                encryption.readers.push(*reader);
+            }
+        }
+        ProposalType::RemoveReader { reader } => {
+            if let Some(encryption) = collection.encryption.as_mut() {
+                encryption.readers.retain(|r| r != reader);
+            }
+        }
+        ProposalType::ArchiveCollection => {
+            collection.archived = Some(ArchiveInfo {
+                archived_at: time(),
+                proposal_id: String::new(),
+            });
+        }
+        _ => {}
+    }
+}
+
+/// Run the actions in order. If one fails, the collection fields, Blueband's admin list and
+/// the document metadata touched by the composite are restored to their state before the
+/// first action; the rest of the collection keeps any change made meanwhile.
+async fn execute_composite(
+    proposal: &Proposal,
+    collection_id: &str,
+    actions: &[ProposalType],
+) -> ClanopediaResult<()> {
+    let snapshot = storage::get_collection(&collection_id.to_string())?;
+    let documents: Vec<_> = actions
+        .iter()
+        .filter_map(|action| match action {
+            ProposalType::UpdateDocumentMetadata { document_id, .. } => Some(document_id),
+            _ => None,
+        })
+        .map(|document_id| {
+            (
+                document_id.clone(),
+                storage::get_document_overlay(collection_id, document_id),
+                storage::get_indexed_document(collection_id, document_id),
+            )
+        })
+        .collect();
+
+    for (i, action) in actions.iter().enumerate() {
+        let step = Proposal {
+            proposal_type: action.clone(),
+            ..proposal.clone()
+        };
+        if let Err(e) = Box::pin(execute_proposal_operation(&step, collection_id)).await {
+            let current = storage::get_collection(&collection_id.to_string())?;
+            let mut restored = current.clone();
+            for action in actions {
+                restore_field(&mut restored, &snapshot, action);
+            }
+            storage::update_collection(&collection_id.to_string(), &restored)?;
+            if current.admins != restored.admins {
+                restore_blueband_admins(&current, &restored.admins).await;
+            }
+            for (document_id, overlay, indexed) in &documents {
+                match overlay {
+                    Some(overlay) => {
+                        storage::set_document_overlay(collection_id, document_id, overlay.clone())
+                    }
+                    None => storage::remove_document_overlay(collection_id, document_id),
+                }
+                if let Some(indexed) = indexed {
+                    keyword_index::index_document(
+                        collection_id,
+                        document_id,
+                        &indexed.title,
+                        &indexed.tags,
+                    );
+                }
+            }
+            return Err(ClanopediaError::InvalidOperation(format!(
+                "Action {} failed and earlier actions were rolled back: {}",
+                i + 1,
+                e
+            )));
+        }
+    }
+    Ok(())
+}
+
+// Put back the collection field an action changes, as `snapshot` had it
+fn restore_field(collection: &mut Collection, snapshot: &Collection, action: &ProposalType) {
+    match action {
+        ProposalType::AddAdmin { .. } | ProposalType::RemoveAdmin { .. } => {
+            collection.admins = snapshot.admins.clone();
+        }
+        ProposalType::ChangeThreshold { .. } => collection.threshold = snapshot.threshold,
+        ProposalType::UpdateQuorum { .. } => {
+            collection.quorum_threshold = snapshot.quorum_threshold;
+        }
+        ProposalType::ChangeGovernanceModel { .. } => {
+            collection.governance_model = snapshot.governance_model.clone();
+        }
+        ProposalType::AddReader { .. }
+        | ProposalType::RemoveReader { .. }
+        | ProposalType::RotateEncryptionKey => {
+            collection.encryption = snapshot.encryption.clone();
+        }
+        ProposalType::ArchiveCollection => collection.archived = snapshot.archived.clone(),
+        ProposalType::SetSourcePolicy { .. } => {
+            collection.source_policy = snapshot.source_policy.clone();
+        }
+        _ => {}
+    }
+}
+
+// Re-run only the failed documents of a partially executed embed proposal
+pub async fn retry_failed_items(collection_id: &str, proposal_id: &str) -> ClanopediaResult<()> {
+    let _lock = ExecutionLock::acquire(collection_id, proposal_id)?;
+    let executor = caller();
+    let collection = storage::get_collection(&collection_id.to_string())?;
+    auth::require_admin(&collection, executor)?;
+    archive::ensure_not_archived(&collection)?;
+    admin::ensure_not_paused(&collection)?;
+    trash::ensure_not_trashed(&collection)?;
+
+    let proposal = get_proposal(collection_id, proposal_id)?;
+    if proposal.status != ProposalStatus::PartiallyExecuted {
+        return Err(ClanopediaError::InvalidProposalState(
+            "Only partially executed proposals can be retried".to_string(),
+        ));
+    }
+    if proposal.expires_at < time() {
+        if let Ok(expired) = expire_proposal(collection_id, proposal_id).await {
+            subscriptions::publish(collection_id, &expired, ProposalEvent::Expired);
+        }
+        return Err(ClanopediaError::ProposalExpired);
+    }
+    let mut report = proposal.execution_report.clone().ok_or_else(|| {
+        ClanopediaError::InvalidProposalState("Proposal has no execution report".to_string())
+    })?;
+
+    let failed_ids: Vec<String> = report
+        .items
+        .iter_mut()
+        .filter(|i| matches!(i.state, ItemExecutionState::Failed(_)))
+        .map(|i| {
+            i.state = ItemExecutionState::Pending;
+            i.document_id.clone()
+        })
+        .collect();
+
+    let _cycles_reservation = reserve_cycles(collection_id, &proposal, failed_ids).await?;
+
+    let retry = async {
+        embed_pending_items(collection_id, proposal_id, &mut report).await?;
+        let proposal = get_proposal(collection_id, proposal_id)?;
+        attestations::mint_for_report(collection_id, &proposal, &report).await;
+        finish_embed_execution(collection_id, proposal, report, executor)
+    };
+    execution_receipts::trace(collection_id, proposal_id, executor, retry).await
 }
 
 // Vote on proposals 
@@ -257,9 +779,20 @@ pub async fn vote_on_proposal(
     collection_id: &str,
     proposal_id: &str,
     vote: Vote,
-) -> ClanopediaResult<()> {
-    let mut proposal = get_proposal(collection_id, proposal_id)?;
-    let voter = caller();
+) -> ClanopediaResult<VoteReceipt> {
+    vote_as(collection_id, proposal_id, caller(), vote).await
+}
+
+/// Cast `voter`'s vote; callers other than `vote_on_proposal` must have checked that they
+/// may act for `voter`
+pub async fn vote_as(
+    collection_id: &str,
+    proposal_id: &str,
+    voter: Principal,
+    vote: Vote,
+) -> ClanopediaResult<VoteReceipt> {
+    let proposal = get_proposal(collection_id, proposal_id)?;
+    receipts::ensure_capacity()?;
 
     // Check proposal state
     if proposal.status != ProposalStatus::Active {
@@ -267,10 +800,12 @@ pub async fn vote_on_proposal(
             "Proposal is not active".to_string(),
         ));
     }
+    sealed_votes::ensure_open_ballot(&proposal)?;
 
     if proposal.expires_at < time() {
-        proposal.status = ProposalStatus::Expired;
-        storage::update_proposal_in_storage(&collection_id.to_string(), &proposal)?;
+        if let Ok(expired) = expire_proposal(collection_id, proposal_id).await {
+            subscriptions::publish(collection_id, &expired, ProposalEvent::Expired);
+        }
         return Err(ClanopediaError::ProposalExpired);
     }
 
@@ -282,54 +817,85 @@ pub async fn vote_on_proposal(
 
     // Validate voter based on governance model
     let collection = storage::get_collection(&collection_id.to_string())?;
-    validate_voter(&collection, &voter, &vote).await?;
+    admin::ensure_not_paused(&collection)?;
+    archive::ensure_proposal_allowed(&collection, &proposal.proposal_type)?;
+    let ledger = collection.governance_token.map(token::ledger);
+    validate_voter(&collection, ledger.as_ref(), &voter).await?;
+
+    let weight = match (&collection.governance_model, &ledger) {
+        (GovernanceModel::TokenBased, Some(ledger)) => {
+            Some(token::get_token_balance(ledger, voter).await?)
+        }
+        _ => None,
+    };
 
-    match collection.governance_model {
-        GovernanceModel::TokenBased => {
-            // Prevent double voting
-            if proposal.votes.contains_key(&voter) {
-                return Err(ClanopediaError::InvalidOperation(
-                    "You have already voted on this proposal".to_string(),
-                ));
-            }
-            if let Some(token_canister) = collection.governance_token {
-                let balance = token::get_token_balance(token_canister, voter).await?;
-                proposal.token_votes.insert(voter, balance);
-                proposal.votes.insert(voter, vote); // Also record the vote
-            }
+    // Other votes may have been stored during the awaits; only this voter's entries change
+    let proposal = modify_proposal(collection_id, proposal_id, |proposal| {
+        if proposal.status != ProposalStatus::Active || proposal.executed {
+            return Err(ClanopediaError::InvalidProposalState(
+                "Proposal is not active".to_string(),
+            ));
         }
-        _ => {
-            // Prevent double voting for other models as well
-            if proposal.votes.contains_key(&voter) {
-                return Err(ClanopediaError::InvalidOperation(
-                    "You have already voted on this proposal".to_string(),
-                ));
-            }
-            proposal.votes.insert(voter, vote);
+        // Prevent double voting
+        if proposal.votes.contains_key(&voter) {
+            return Err(ClanopediaError::InvalidOperation(
+                "You have already voted on this proposal".to_string(),
+            ));
         }
-    }
+        if let Some(weight) = weight {
+            proposal.token_votes.insert(voter, weight);
+        }
+        proposal.votes.insert(voter, vote.clone());
+        Ok(())
+    })?;
+
+    tally_vote(&collection, proposal, voter, vote).await
+}
 
-    // After voting, check if threshold is met
-    let threshold_met = check_threshold(collection_id, &proposal).await?;
-    if threshold_met {
-        proposal.status = ProposalStatus::Approved;
-        proposal.threshold_met = true;
+/// Approve `proposal` if `voter`'s vote, already stored, met the threshold, and issue the
+/// vote's receipt
+pub async fn tally_vote(
+    collection: &Collection,
+    proposal: Proposal,
+    voter: Principal,
+    vote: Vote,
+) -> ClanopediaResult<VoteReceipt> {
+    let collection_id = collection.id.as_str();
+    let mut proposal = proposal;
+    if check_threshold(collection_id, &proposal).await? {
+        // Approve the stored proposal, which may have changed while the threshold was checked
+        let mut approved = false;
+        proposal = modify_proposal(collection_id, &proposal.id, |stored| {
+            if stored.status == ProposalStatus::Active && !stored.executed {
+                stored.status = ProposalStatus::Approved;
+                stored.threshold_met = true;
+                timelock::on_approved(collection, stored);
+                sealed_votes::on_approved(stored);
+                approved = true;
+            }
+            Ok(())
+        })?;
+
+        if approved {
+            notifications::proposal_approved(collection_id, &proposal);
+            subscriptions::publish(collection_id, &proposal, ProposalEvent::Approved);
+            bonds::settle(&proposal).await;
+        }
     }
 
-    // Update proposal
-    storage::update_proposal_in_storage(&collection_id.to_string(), &proposal)?;
-    Ok(())
+    stats::record(collection_id, StatEvent::Vote(voter));
+    Ok(receipts::issue(collection_id, &proposal, voter, vote))
 }
 
-async fn validate_voter(
+pub async fn validate_voter(
     collection: &Collection,
+    ledger: Option<&impl TokenApi>,
     voter: &Principal,
-    _vote: &Vote,
 ) -> ClanopediaResult<()> {
     match collection.governance_model {
         GovernanceModel::TokenBased => {
-            if let Some(token_canister) = collection.governance_token {
-                let balance = token::get_token_balance(token_canister, *voter).await?;
+            if let Some(ledger) = ledger {
+                let balance = token::get_token_balance(ledger, *voter).await?;
                 if balance == 0u64 {
                     return Err(ClanopediaError::NotAuthorized);
                 }
@@ -364,7 +930,27 @@ async fn validate_voter(
 // Check if voting threshold is met - Made async to handle token holder count
 pub async fn check_threshold(collection_id: &str, proposal: &Proposal) -> ClanopediaResult<bool> {
     let collection = storage::get_collection(&collection_id.to_string())?;
+    let ledger = collection.governance_token.map(token::ledger);
+    let sns = collection
+        .sns_governance_canister
+        .map(sns_integration::sns_governance);
+    threshold_met(&collection, proposal, ledger.as_ref(), sns.as_ref()).await
+}
+
+/// The yes weight that approves a token-based proposal: `quorum_threshold` percent of the
+/// supply
+pub fn token_quorum(total_supply: &Nat, quorum_threshold: u32) -> Nat {
+    (total_supply.clone() * Nat::from(quorum_threshold)) / Nat::from(100u32)
+}
 
+/// Threshold rules for each governance model. The ledger and SNS governance are passed in
+/// so the rules can run against in-memory fakes.
+pub async fn threshold_met(
+    collection: &Collection,
+    proposal: &Proposal,
+    ledger: Option<&impl TokenApi>,
+    sns: Option<&impl SnsApi>,
+) -> ClanopediaResult<bool> {
     match collection.governance_model {
         GovernanceModel::Permissionless => {
             // Permissionless should execute immediately, not go through voting
@@ -375,26 +961,23 @@ pub async fn check_threshold(collection_id: &str, proposal: &Proposal) -> Clanop
             Ok(yes_votes >= collection.threshold)
         }
         GovernanceModel::TokenBased => {
-            if let Some(token_canister) = collection.governance_token {
-                let total_supply = token::get_token_total_supply(token_canister).await?;
+            if let Some(ledger) = ledger {
+                let total_supply = token::get_token_total_supply(ledger).await?;
                 let total_yes_tokens = proposal
                     .token_votes
                     .iter()
                     .filter(|(principal, _)| proposal.votes.get(principal) == Some(&Vote::Yes))
                     .fold(Nat::from(0u64), |acc, (_, amount)| acc + amount.clone());
 
-                let threshold_amount = (total_supply.clone()
-                    * Nat::from(collection.quorum_threshold))
-                    / Nat::from(100u32);
-                Ok(total_yes_tokens >= threshold_amount)
+                Ok(total_yes_tokens >= token_quorum(&total_supply, collection.quorum_threshold))
             } else {
                 Ok(false)
             }
         }
         GovernanceModel::SnsIntegrated => {
-            if let Some(sns_governance) = collection.sns_governance_canister {
+            if let Some(sns) = sns {
                 if let Some(sns_proposal_id) = proposal.sns_proposal_id {
-                    sns_integration::check_sns_proposal_approved(sns_governance, sns_proposal_id)
+                    sns_integration::check_sns_proposal_approved(sns, sns_proposal_id)
                         .await
                         .map_err(|e| ClanopediaError::SnsError(e.to_string()))
                 } else {
@@ -410,45 +993,154 @@ pub async fn check_threshold(collection_id: &str, proposal: &Proposal) -> Clanop
 // Proposal execution functions
 pub async fn execute_embed_document(
     collection_id: &str,
+    proposal_id: &str,
     documents: &[String],
-) -> ClanopediaResult<()> {
-    let collection = storage::get_collection(&collection_id.to_string())?;
-
-    // Call Blueband to embed existing documents
-    for document_id in documents {
-        blueband::embed_existing_document(&collection.blueband_collection_id, document_id)
-            .await
-            .map_err(ClanopediaError::BluebandError)?;
-    }
-
-    Ok(())
+) -> ClanopediaResult<ExecutionReport> {
+    let mut report = resume_report(collection_id, proposal_id, documents);
+    embed_pending_items(collection_id, proposal_id, &mut report).await?;
+    Ok(report)
 }
 
 pub async fn execute_batch_embed(
     collection_id: &str,
+    proposal_id: &str,
+    document_ids: &[String],
+) -> ClanopediaResult<ExecutionReport> {
+    let mut report = resume_report(collection_id, proposal_id, document_ids);
+    embed_pending_items(collection_id, proposal_id, &mut report).await?;
+    Ok(report)
+}
+
+// The report saved by an execution that stopped part-way, if there is one, with everything
+// not yet embedded pending again; documents already embedded are not sent twice
+fn resume_report(
+    collection_id: &str,
+    proposal_id: &str,
     document_ids: &[String],
+) -> ExecutionReport {
+    let Some(mut report) = get_proposal(collection_id, proposal_id)
+        .ok()
+        .and_then(|p| p.execution_report)
+    else {
+        return ExecutionReport::new(document_ids, time());
+    };
+    for item in report.items.iter_mut() {
+        if item.state != ItemExecutionState::Succeeded {
+            item.state = ItemExecutionState::Pending;
+        }
+    }
+    let missing: Vec<String> = document_ids
+        .iter()
+        .filter(|id| !report.items.iter().any(|item| &item.document_id == *id))
+        .cloned()
+        .collect();
+    report
+        .items
+        .extend(ExecutionReport::new(&missing, report.started_at).items);
+    report.refresh_counts();
+    report
+}
+
+// Embed pending items in windows of `embed_concurrency` parallel Blueband calls,
+// persisting the report after each window so a trap mid-batch loses nothing
+async fn embed_pending_items(
+    collection_id: &str,
+    proposal_id: &str,
+    report: &mut ExecutionReport,
 ) -> ClanopediaResult<()> {
     let collection = storage::get_collection(&collection_id.to_string())?;
+    let window = collection
+        .embed_concurrency
+        .unwrap_or(DEFAULT_EMBED_CONCURRENCY)
+        .clamp(1, MAX_EMBED_CONCURRENCY) as usize;
 
-    // Call Blueband for each document (could be optimized with batch API)
-    for document_id in document_ids {
-        blueband::embed_existing_document(&collection.blueband_collection_id, document_id)
-            .await
-            .map_err(ClanopediaError::BluebandError)?;
+    let pending: Vec<usize> = report
+        .items
+        .iter()
+        .enumerate()
+        .filter(|(_, item)| item.state == ItemExecutionState::Pending)
+        .map(|(index, _)| index)
+        .collect();
+
+    for chunk in pending.chunks(window) {
+        let calls = chunk.iter().map(|&index| {
+            blueband::embed_existing_document(
+                &collection.blueband_collection_id,
+                &report.items[index].document_id,
+            )
+        });
+        let outcomes = join_all(calls).await;
+
+        for (&index, outcome) in chunk.iter().zip(outcomes) {
+            let item = &mut report.items[index];
+            item.attempts += 1;
+            item.state = match outcome {
+                Ok(_) => {
+                    logging::debug(
+                        "governance",
+                        Some(collection_id),
+                        format!("Embedded document {}", item.document_id),
+                    );
+                    ItemExecutionState::Succeeded
+                }
+                Err(e) => {
+                    logging::warn(
+                        "governance",
+                        Some(collection_id),
+                        format!("Failed to embed document {}: {}", item.document_id, e),
+                    );
+                    ItemExecutionState::Failed(e)
+                }
+            };
+        }
+
+        report.last_attempt_at = time();
+        report.refresh_counts();
+        save_execution_report(collection_id, proposal_id, report)?;
     }
 
     Ok(())
 }
 
+fn save_execution_report(
+    collection_id: &str,
+    proposal_id: &str,
+    report: &ExecutionReport,
+) -> ClanopediaResult<()> {
+    let mut proposal = get_proposal(collection_id, proposal_id)?;
+    proposal.execution_report = Some(report.clone());
+    storage::update_proposal_in_storage(&collection_id.to_string(), &proposal)
+}
+
+/// Add an admin here and in Blueband. If Blueband refuses, the admin is taken out again so
+/// the two lists do not drift apart.
 pub async fn execute_add_admin(collection_id: &str, new_admin: Principal) -> ClanopediaResult<()> {
     let mut collection = storage::get_collection(&collection_id.to_string())?;
-    if !collection.admins.contains(&new_admin) {
-        collection.admins.push(new_admin);
+    if collection.admins.contains(&new_admin) {
+        return Ok(());
+    }
+    collection.admins.push(new_admin);
+    storage::update_collection(&collection_id.to_string(), &collection)?;
+
+    if collection.blueband_collection_id.is_empty() {
+        return Ok(());
+    }
+    if let Err(e) =
+        blueband::add_collection_admin(&collection.blueband_collection_id, new_admin).await
+    {
+        // Re-read, since other changes may have landed during the call
+        let mut collection = storage::get_collection(&collection_id.to_string())?;
+        collection.admins.retain(|&admin| admin != new_admin);
         storage::update_collection(&collection_id.to_string(), &collection)?;
+        return Err(ClanopediaError::BluebandError(format!(
+            "Blueband did not add the admin, so the change was rolled back: {}",
+            e
+        )));
     }
     Ok(())
 }
 
+/// Remove an admin here and in Blueband, restoring the admin if Blueband refuses
 pub async fn execute_remove_admin(
     collection_id: &str,
     admin_to_remove: Principal,
@@ -461,12 +1153,60 @@ pub async fn execute_remove_admin(
             "Cannot remove the last admin".into(),
         ));
     }
+    if !collection.admins.contains(&admin_to_remove) {
+        return Ok(());
+    }
 
     collection.admins.retain(|&admin| admin != admin_to_remove);
     storage::update_collection(&collection_id.to_string(), &collection)?;
+
+    if collection.blueband_collection_id.is_empty() {
+        return Ok(());
+    }
+    if let Err(e) =
+        blueband::remove_collection_admin(&collection.blueband_collection_id, admin_to_remove)
+            .await
+    {
+        let mut collection = storage::get_collection(&collection_id.to_string())?;
+        if !collection.admins.contains(&admin_to_remove) {
+            collection.admins.push(admin_to_remove);
+            storage::update_collection(&collection_id.to_string(), &collection)?;
+        }
+        return Err(ClanopediaError::BluebandError(format!(
+            "Blueband did not remove the admin, so the change was rolled back: {}",
+            e
+        )));
+    }
     Ok(())
 }
 
+// Bring Blueband's admin list back to `before` after a failed composite restored the local
+// one. Best effort: a failure here is logged, since the composite has already failed.
+async fn restore_blueband_admins(collection: &Collection, before: &[Principal]) {
+    if collection.blueband_collection_id.is_empty() {
+        return;
+    }
+    let blueband_id = &collection.blueband_collection_id;
+    for admin in collection.admins.iter().filter(|a| !before.contains(a)) {
+        if let Err(e) = blueband::remove_collection_admin(blueband_id, *admin).await {
+            logging::error(
+                "governance",
+                Some(&collection.id),
+                format!("Could not remove admin {} from Blueband: {}", admin, e),
+            );
+        }
+    }
+    for admin in before.iter().filter(|a| !collection.admins.contains(a)) {
+        if let Err(e) = blueband::add_collection_admin(blueband_id, *admin).await {
+            logging::error(
+                "governance",
+                Some(&collection.id),
+                format!("Could not restore admin {} in Blueband: {}", admin, e),
+            );
+        }
+    }
+}
+
 async fn execute_change_threshold(collection_id: &str, new_threshold: u32) -> ClanopediaResult<()> {
     let mut collection = storage::get_collection(&collection_id.to_string())?;
     let max_threshold = collection.admins.len() as u32;
@@ -520,6 +1260,11 @@ pub async fn execute_update_collection(
     mut config: CollectionConfig,
 ) -> ClanopediaResult<()> {
     let mut collection = storage::get_collection(&collection_id.to_string())?;
+    if config.encryption.is_some() != collection.encryption.is_some() {
+        return Err(ClanopediaError::InvalidOperation(
+            "Encryption can only be chosen when a collection is created".to_string(),
+        ));
+    }
 
     // Convert string representations to Principal objects for validation
     let admins: Result<Vec<Principal>, _> =
@@ -547,22 +1292,110 @@ pub async fn execute_update_collection(
     collection.governance_model = config.governance_model;
     collection.quorum_threshold = config.quorum_threshold;
     collection.is_permissionless = config.is_permissionless;
+    validate_proposal_bond(&config.proposal_bond, &collection.governance_token).await?;
+    validate_proposer_eligibility(
+        &config.proposer_eligibility,
+        &collection.governance_token,
+        &collection.sns_governance_canister,
+    )?;
+    collection.proposal_bond = config.proposal_bond;
+    collection.proposer_eligibility = config.proposer_eligibility;
+    collection.embed_concurrency = config.embed_concurrency;
+    pipeline::validate_pipeline(&config.pipeline)?;
+    collection.pipeline = config.pipeline;
+    keywords::validate_auto_tag(&config.auto_tag)?;
+    collection.auto_tag = config.auto_tag;
+    summarize::validate_summarization(&config.summarization, collection.encryption.is_some())?;
+    collection.summarization = config.summarization;
+    ocr::validate_ocr(&config.ocr, collection.encryption.is_some())?;
+    collection.ocr = config.ocr;
+    attestations::validate_nft_minting(&config.nft_minting, collection.encryption.is_some())?;
+    collection.nft_minting = config.nft_minting;
+    collection.license = config.license;
+    collection.vote_reminder_hours = config.vote_reminder_hours;
+    collection.timelock_hours = config.timelock_hours;
+    custom_proposals::validate_call_policy(&config.call_policy)?;
+    custom_proposals::validate_custom_kinds(&config.custom_proposals, &config.call_policy)?;
+    collection.custom_proposals = config.custom_proposals;
+    collection.call_policy = config.call_policy;
+    collection.review_policy = config.review_policy;
+    collection.content_limits = config.content_limits;
+    collection.retry_policy = config.retry_policy;
+    proxy::update_collection_proxy(&mut collection, &config.embedding_proxy, caller()).await?;
     collection.updated_at = time();
 
     storage::update_collection(&collection_id.to_string(), &collection)?;
     Ok(())
 }
 
+// Deletion goes through the trash; the collection is purged after the retention window
 pub async fn execute_delete_collection(collection_id: &str) -> ClanopediaResult<()> {
-    let collection = storage::get_collection(&collection_id.to_string())?;
+    trash::trash_collection(collection_id, caller())
+}
 
-    // Call Blueband to delete the collection
-    blueband::delete_collection(&collection.blueband_collection_id)
-        .await
-        .map_err(ClanopediaError::BluebandError)?;
+// Fields left as None keep their current overlay value
+fn execute_update_document_metadata(
+    collection_id: &str,
+    document_id: &str,
+    title: Option<String>,
+    tags: Option<Vec<String>>,
+    author: Option<String>,
+    license: Option<License>,
+) -> ClanopediaResult<()> {
+    if !storage::list_known_document_ids(collection_id).contains(&document_id.to_string()) {
+        return Err(ClanopediaError::NotFound(format!(
+            "Document {} not found in collection {}",
+            document_id, collection_id
+        )));
+    }
+
+    let mut overlay = storage::get_document_overlay(collection_id, document_id).unwrap_or(
+        DocumentMetadataOverlay {
+            title: None,
+            tags: None,
+            author: None,
+            summary: None,
+            license: None,
+            slug: None,
+            updated_at: 0,
+        },
+    );
+
+    if title.is_some() {
+        overlay.title = title.clone();
+    }
+    if tags.is_some() {
+        overlay.tags = tags.clone();
+    }
+    if author.is_some() {
+        overlay.author = author;
+    }
+    if license.is_some() {
+        overlay.license = license;
+    }
+    overlay.updated_at = time();
 
-    // Remove from Clanopedia storage
-    storage::delete_collection(&collection_id.to_string())?;
+    // Checked before anything is written; a larger overlay cannot be stored
+    let size = candid::encode_one(&overlay)
+        .map_err(|e| ClanopediaError::InvalidInput(format!("Invalid metadata: {}", e)))?
+        .len();
+    if size > MAX_DOCUMENT_OVERLAY_BYTES {
+        return Err(ClanopediaError::InvalidInput(format!(
+            "Metadata for document {} would be {} bytes, the limit is {}",
+            document_id, size, MAX_DOCUMENT_OVERLAY_BYTES
+        )));
+    }
+
+    if title.is_some() || tags.is_some() {
+        keyword_index::update_document(collection_id, document_id, title.as_ref(), tags.as_ref());
+    }
+    storage::set_document_overlay(collection_id, document_id, overlay);
+    storage::append_change(
+        collection_id,
+        ChangeKind::DocumentUpdated {
+            document_id: document_id.to_string(),
+        },
+    );
     Ok(())
 }
 
@@ -573,8 +1406,8 @@ pub fn can_execute_directly(collection_id: &CollectionId) -> ClanopediaResult<bo
 }
 
 pub fn get_proposals(collection_id: &str) -> ClanopediaResult<Vec<Proposal>> {
-    let collection = storage::get_collection(&collection_id.to_string())?;
-    Ok(collection.proposals.values().cloned().collect())
+    storage::get_collection(&collection_id.to_string())?;
+    Ok(storage::list_proposals(collection_id).into_values().collect())
 }
 
 pub fn get_proposal_status(
@@ -587,23 +1420,27 @@ pub fn get_proposal_status(
 
 // Add cleanup function for expired proposals and associated documents
 pub async fn cleanup_expired_proposals(collection_id: &str) -> ClanopediaResult<u32> {
-    let mut collection = storage::get_collection(&collection_id.to_string())?;
+    storage::get_collection(&collection_id.to_string())?;
     let current_time = time();
     let mut cleaned = 0u32;
 
-    let expired_proposal_ids: Vec<String> = collection
-        .proposals
-        .iter()
-        .filter(|(_, proposal)| proposal.expires_at < current_time)
-        .map(|(id, _)| id.clone())
+    let expired_proposals: Vec<Proposal> = storage::list_proposals(collection_id)
+        .into_values()
+        .filter(|proposal| proposal.expires_at < current_time)
         .collect();
 
-    for id in expired_proposal_ids {
-        collection.proposals.remove(&id);
+    for proposal in expired_proposals {
+        let was_active = proposal.status == ProposalStatus::Active;
+        // Skipped if it was executed or settled while an earlier bond was settled
+        let Ok(expired) = expire_proposal(collection_id, &proposal.id).await else {
+            continue;
+        };
+        if was_active {
+            subscriptions::publish(collection_id, &expired, ProposalEvent::Expired);
+        }
         cleaned += 1;
     }
 
-    storage::update_collection(&collection_id.to_string(), &collection)?;
     Ok(cleaned)
 }
 
@@ -612,20 +1449,74 @@ pub async fn delete_collection(collection_id: &str, caller: Principal) -> Clanop
     let collection = storage::get_collection(&collection_id.to_string())?;
 
     // Verify caller is an admin
-    if !collection.admins.contains(&caller) {
+    if !auth::is_admin(&collection, caller) {
         return Err(ClanopediaError::Unauthorized(
             "Only admins can delete collections".to_string(),
         ));
     }
 
-    // Call Blueband to delete the collection
-    blueband::delete_collection(&collection.blueband_collection_id)
-        .await
-        .map_err(ClanopediaError::BluebandError)?;
+    trash::trash_collection(collection_id, caller)
+}
 
-    // Remove from Clanopedia storage
-    storage::delete_collection(&collection_id.to_string())?;
+// Dependencies point at proposals that already exist, so they cannot form a cycle
+fn validate_dependencies(collection_id: &str, depends_on: &[ProposalId]) -> ClanopediaResult<()> {
+    if depends_on.len() > MAX_PROPOSAL_DEPENDENCIES {
+        return Err(ClanopediaError::InvalidInput(format!(
+            "A proposal can depend on at most {} others",
+            MAX_PROPOSAL_DEPENDENCIES
+        )));
+    }
+    for (i, dependency) in depends_on.iter().enumerate() {
+        if depends_on[..i].contains(dependency) {
+            return Err(ClanopediaError::InvalidInput(format!(
+                "Dependency {} is listed twice",
+                dependency
+            )));
+        }
+        if dependency_status(collection_id, dependency) == DependencyStatus::Unavailable {
+            return Err(ClanopediaError::NotFound(format!(
+                "Proposal {} is not open or executed in collection {}",
+                dependency, collection_id
+            )));
+        }
+    }
+    Ok(())
+}
 
+pub fn dependency_status(collection_id: &str, proposal_id: &str) -> DependencyStatus {
+    if storage::is_proposal_executed(collection_id, proposal_id) {
+        return DependencyStatus::Executed;
+    }
+    match storage::get_proposal(collection_id, proposal_id) {
+        Some(proposal) if proposal.status == ProposalStatus::Executed => DependencyStatus::Executed,
+        Some(proposal)
+            if proposal.status != ProposalStatus::Rejected
+                && proposal.status != ProposalStatus::Expired =>
+        {
+            DependencyStatus::Pending(proposal.status)
+        }
+        _ => DependencyStatus::Unavailable,
+    }
+}
+
+fn ensure_dependencies_executed(collection_id: &str, proposal: &Proposal) -> ClanopediaResult<()> {
+    for dependency in proposal.depends_on.iter().flatten() {
+        match dependency_status(collection_id, dependency) {
+            DependencyStatus::Executed => {}
+            DependencyStatus::Pending(status) => {
+                return Err(ClanopediaError::InvalidProposalState(format!(
+                    "Depends on proposal {}, which is {:?} and must be executed first",
+                    dependency, status
+                )))
+            }
+            DependencyStatus::Unavailable => {
+                return Err(ClanopediaError::InvalidProposalState(format!(
+                    "Depends on proposal {}, which was rejected or expired",
+                    dependency
+                )))
+            }
+        }
+    }
     Ok(())
 }
 
@@ -635,7 +1526,75 @@ pub async fn create_proposal(
     creator: Principal,
     description: String,
 ) -> ClanopediaResult<String> {
+    create_proposal_with_dependencies(
+        collection_id,
+        proposal_type,
+        creator,
+        description,
+        Vec::new(),
+    )
+    .await
+}
+
+/// Create a proposal that can only execute after every proposal in `depends_on` has
+pub async fn create_proposal_with_dependencies(
+    collection_id: &str,
+    proposal_type: ProposalType,
+    creator: Principal,
+    description: String,
+    depends_on: Vec<ProposalId>,
+) -> ClanopediaResult<String> {
+    create_proposal_with_options(
+        collection_id,
+        proposal_type,
+        creator,
+        description,
+        depends_on,
+        false,
+    )
+    .await
+}
+
+/// Create a proposal voted on by commit–reveal; see `sealed_votes`
+pub async fn create_sealed_proposal(
+    collection_id: &str,
+    proposal_type: ProposalType,
+    creator: Principal,
+    description: String,
+) -> ClanopediaResult<String> {
+    create_proposal_with_options(
+        collection_id,
+        proposal_type,
+        creator,
+        description,
+        Vec::new(),
+        true,
+    )
+    .await
+}
+
+async fn create_proposal_with_options(
+    collection_id: &str,
+    mut proposal_type: ProposalType,
+    creator: Principal,
+    mut description: String,
+    depends_on: Vec<ProposalId>,
+    sealed: bool,
+) -> ClanopediaResult<String> {
+    validation::validate_proposal(&mut proposal_type, &mut description)?;
     let collection = storage::get_collection(&collection_id.to_string())?;
+    trash::ensure_not_trashed(&collection)?;
+    admin::ensure_not_paused(&collection)?;
+    if sealed {
+        sealed_votes::ensure_supported(&collection)?;
+    }
+    archive::ensure_proposal_allowed(&collection, &proposal_type)?;
+    if let ProposalType::Custom { kind, payload } = &proposal_type {
+        custom_proposals::validate_custom(&collection, kind, payload)?;
+    }
+    validate_dependencies(collection_id, &depends_on)?;
+    spam::check_proposer(&collection, creator)?;
+    storage::ensure_stable_memory()?;
 
     // Generate a random number using getrandom
     let mut random_bytes = [0u8; 4];
@@ -657,6 +1616,38 @@ pub async fn create_proposal(
     let random_hex = format!("{:04x}", random_number % 0xFFFF);
     let proposal_id = format!("prop_{}_{}_{}", collection_id, timestamp_short, random_hex);
 
+    check_proposer_eligibility(&collection, creator).await?;
+    let sealed_proxy_api_key = proxy::seal_proposal_key(&mut proposal_type).await?;
+
+    // Pull the proposal bond, then the fee, before anything is written. The collection may
+    // have been deleted, trashed or paused during each await; what was taken goes back if so.
+    let bond = bonds::collect(&collection, creator).await?;
+    let mut fee = None;
+    let collected = async {
+        let collection = writable_collection(collection_id)?;
+        fee = spam::collect_fee(&collection, creator).await?;
+        writable_collection(collection_id)
+    }
+    .await;
+    let collection = match collected {
+        Ok(collection) => {
+            if let Some(fee) = &fee {
+                spam::accept_fee(fee);
+            }
+            collection
+        }
+        Err(e) => {
+            if let Some(bond) = bond {
+                bonds::refund_unused(collection_id, &proposal_id, creator, bond).await;
+            }
+            if let Some(fee) = fee {
+                spam::refund_fee(collection_id, creator, fee).await;
+            }
+            return Err(e);
+        }
+    };
+
+    let sealed_voting = sealed.then(|| sealed_votes::new_sealed_voting(current_time_ns()));
     let mut proposal = Proposal {
         id: proposal_id.clone(),
         collection_id: collection_id.to_string(),
@@ -664,7 +1655,11 @@ pub async fn create_proposal(
         creator,
         description: description.clone(),
         created_at: current_time_ns(),
-        expires_at: current_time_ns() + PROPOSAL_DURATION_NANOS,
+        // Sealed proposals stay open until their votes are revealed
+        expires_at: sealed_voting.as_ref().map_or(
+            current_time_ns() + PROPOSAL_DURATION_NANOS,
+            |sealed| sealed.reveal_ends_at,
+        ),
         status: ProposalStatus::Active,
         votes: HashMap::new(),
         threshold_met: false,
@@ -674,48 +1669,205 @@ pub async fn create_proposal(
         executed_by: None,
         token_votes: HashMap::new(),
         sns_proposal_id: None,
+        bond,
+        execution_report: None,
+        reminded_at: None,
+        depends_on: (!depends_on.is_empty()).then_some(depends_on),
+        executable_at: None,
+        review: review::new_review(&collection, &proposal_type, creator),
+        sealed_voting,
+        sealed_proxy_api_key,
     };
 
-    // Update collection with new proposal
-    let mut updated_collection = collection;
-    updated_collection
-        .proposals
-        .insert(proposal_id.clone(), proposal.clone());
-    storage::update_collection(&collection_id.to_string(), &updated_collection)?;
+    storage::put_proposal(collection_id, &proposal);
+    notifications::proposal_created(&collection, &proposal);
+    subscriptions::publish(collection_id, &proposal, ProposalEvent::Created);
+    if let Some(review) = &proposal.review {
+        notifications::review_requested(&collection, &proposal, &review.assigned);
+    }
+    timers::schedule(); // For its vote reminder
 
     // For permissionless collections, auto-approve but don't execute
-    if updated_collection.is_permissionless
-        || matches!(
-            updated_collection.governance_model,
-            GovernanceModel::Permissionless
-        )
+    if collection.is_permissionless
+        || matches!(collection.governance_model, GovernanceModel::Permissionless)
     {
         // Mark proposal as approved but not executed
         let mut approved_proposal = proposal;
         approved_proposal.status = ProposalStatus::Approved;
         approved_proposal.threshold_met = true;
-        updated_collection
-            .proposals
-            .insert(proposal_id.clone(), approved_proposal);
-        storage::update_collection(&collection_id.to_string(), &updated_collection)?;
+        timelock::on_approved(&collection, &mut approved_proposal);
+        storage::put_proposal(collection_id, &approved_proposal);
+        subscriptions::publish(collection_id, &approved_proposal, ProposalEvent::Approved);
     }
 
+    stats::record(collection_id, StatEvent::ProposalCreated);
+    if let Some(fee) = fee {
+        spam::forward_fee(collection_id, creator, fee).await;
+    }
     Ok(proposal_id)
 }
 
-pub fn get_proposal(collection_id: &str, proposal_id: &str) -> ClanopediaResult<Proposal> {
+// The collection, if proposals can still be written to it
+fn writable_collection(collection_id: &str) -> ClanopediaResult<Collection> {
     let collection = storage::get_collection(&collection_id.to_string())?;
+    trash::ensure_not_trashed(&collection)?;
+    admin::ensure_not_paused(&collection)?;
+    Ok(collection)
+}
 
-    collection
-        .proposals
-        .get(proposal_id)
-        .cloned()
-        .ok_or_else(|| {
-            ClanopediaError::NotFound(format!(
-                "Proposal {} not found in collection {}",
-                proposal_id, collection_id
+// ============================
+// PROPOSER ELIGIBILITY
+// ============================
+
+pub fn validate_proposer_eligibility(
+    eligibility: &Option<ProposerEligibility>,
+    governance_token: &Option<Principal>,
+    sns_governance_canister: &Option<Principal>,
+) -> ClanopediaResult<()> {
+    match eligibility {
+        Some(ProposerEligibility::MinTokenBalance { .. }) if governance_token.is_none() => {
+            Err(ClanopediaError::InvalidInput(
+                "Minimum token balance eligibility requires a governance token".to_string(),
             ))
-        })
+        }
+        Some(ProposerEligibility::SnsNeuron { .. }) if sns_governance_canister.is_none() => {
+            Err(ClanopediaError::InvalidInput(
+                "SNS neuron eligibility requires an SNS governance canister".to_string(),
+            ))
+        }
+        _ => Ok(()),
+    }
+}
+
+// Admins are always eligible so curation flows keep working
+async fn check_proposer_eligibility(
+    collection: &Collection,
+    creator: Principal,
+) -> ClanopediaResult<()> {
+    let Some(eligibility) = &collection.proposer_eligibility else {
+        return Ok(());
+    };
+    if auth::is_admin(collection, creator) {
+        return Ok(());
+    }
+
+    match eligibility {
+        ProposerEligibility::MinTokenBalance { amount } => {
+            let token_canister = collection.governance_token.ok_or_else(|| {
+                ClanopediaError::InvalidOperation(
+                    "Token balance eligibility requires a governance token".to_string(),
+                )
+            })?;
+            let balance = token::get_token_balance(&token::ledger(token_canister), creator).await?;
+            if balance < *amount {
+                return Err(ClanopediaError::Unauthorized(format!(
+                    "Creating proposals requires at least {} governance tokens (balance: {})",
+                    amount, balance
+                )));
+            }
+        }
+        ProposerEligibility::NftHolder {
+            nft_canister,
+            token_id,
+        } => {
+            if !nft::holds_nft(*nft_canister, creator, token_id.clone()).await? {
+                let requirement = match token_id {
+                    Some(id) => format!("NFT #{} from {}", id, nft_canister),
+                    None => format!("an NFT from {}", nft_canister),
+                };
+                return Err(ClanopediaError::Unauthorized(format!(
+                    "Creating proposals requires holding {}",
+                    requirement
+                )));
+            }
+        }
+        ProposerEligibility::SnsNeuron { min_stake_e8s } => {
+            let sns_governance = collection
+                .sns_governance_canister
+                .ok_or(ClanopediaError::SnsNotConfigured)?;
+            let sns = sns_integration::sns_governance(sns_governance);
+            let stake = sns_integration::get_max_neuron_stake(&sns, creator).await?;
+            if stake < *min_stake_e8s {
+                return Err(ClanopediaError::Unauthorized(format!(
+                    "Creating proposals requires an SNS neuron with at least {} e8s staked (largest: {})",
+                    min_stake_e8s, stake
+                )));
+            }
+        }
+    }
+    Ok(())
+}
+
+// ============================
+// PROPOSAL BONDS
+// ============================
+
+/// A bond is paid back out through the ledger, so it must exceed the ledger fee
+pub async fn validate_proposal_bond(
+    bond: &Option<ProposalBondConfig>,
+    governance_token: &Option<Principal>,
+) -> ClanopediaResult<()> {
+    if let Some(bond) = bond {
+        let Some(token_canister) = governance_token else {
+            return Err(ClanopediaError::InvalidInput(
+                "Proposal bonds require a governance token".to_string(),
+            ));
+        };
+        let fee = token::ledger_fee(&token::ledger(*token_canister)).await?;
+        if bond.amount <= fee {
+            return Err(ClanopediaError::InvalidInput(format!(
+                "Proposal bond amount must exceed the ledger fee of {}",
+                fee
+            )));
+        }
+    }
+    Ok(())
+}
+
+pub fn get_proposal(collection_id: &str, proposal_id: &str) -> ClanopediaResult<Proposal> {
+    storage::get_collection(&collection_id.to_string())?;
+
+    storage::get_proposal(collection_id, proposal_id).ok_or_else(|| {
+        ClanopediaError::NotFound(format!(
+            "Proposal {} not found in collection {}",
+            proposal_id, collection_id
+        ))
+    })
+}
+
+/// Apply `change` to the proposal as it is stored now and store the result. Calls that await
+/// before writing a proposal go through here, so they never write back a copy read before
+/// the await over what other calls stored in the meantime.
+pub fn modify_proposal(
+    collection_id: &str,
+    proposal_id: &str,
+    change: impl FnOnce(&mut Proposal) -> ClanopediaResult<()>,
+) -> ClanopediaResult<Proposal> {
+    let mut proposal = get_proposal(collection_id, proposal_id)?;
+    change(&mut proposal)?;
+    storage::update_proposal_in_storage(&collection_id.to_string(), &proposal)?;
+    Ok(proposal)
+}
+
+// Mark the stored proposal expired, unless it was executed or settled meanwhile, and settle
+// its bond
+async fn expire_proposal(collection_id: &str, proposal_id: &str) -> ClanopediaResult<Proposal> {
+    let expired = modify_proposal(collection_id, proposal_id, |proposal| {
+        if proposal.executed
+            || matches!(
+                proposal.status,
+                ProposalStatus::Executed | ProposalStatus::Rejected | ProposalStatus::Expired
+            )
+        {
+            return Err(ClanopediaError::InvalidProposalState(
+                "Proposal is no longer open".to_string(),
+            ));
+        }
+        proposal.status = ProposalStatus::Expired;
+        Ok(())
+    })?;
+    bonds::settle(&expired).await;
+    Ok(expired)
 }
 
 
@@ -726,17 +1878,13 @@ pub fn link_sns_proposal_id(
     sns_proposal_id: u64,
     caller: Principal,
 ) -> ClanopediaResult<()> {
-    let mut collection = storage::get_collection(&collection_id.to_string())?;
+    let collection = storage::get_collection(&collection_id.to_string())?;
     // Only admin can link
-    if !collection.admins.contains(&caller) {
-        return Err(ClanopediaError::NotAuthorized);
-    }
-    let proposal = collection
-        .proposals
-        .get_mut(proposal_id)
+    auth::require_admin(&collection, caller)?;
+    let mut proposal = storage::get_proposal(collection_id, proposal_id)
         .ok_or_else(|| ClanopediaError::NotFound(format!("Proposal {} not found", proposal_id)))?;
     proposal.sns_proposal_id = Some(sns_proposal_id);
-    storage::update_collection(&collection_id.to_string(), &collection)?;
+    storage::put_proposal(collection_id, &proposal);
     Ok(())
 }
 
@@ -745,26 +1893,260 @@ pub async fn sync_sns_proposal_status_and_update(
     collection_id: &str,
     proposal_id: &str,
 ) -> ClanopediaResult<()> {
-    let mut collection = storage::get_collection(&collection_id.to_string())?;
-    let proposal = collection
-        .proposals
-        .get_mut(proposal_id)
+    let collection = storage::get_collection(&collection_id.to_string())?;
+    let mut proposal = storage::get_proposal(collection_id, proposal_id)
         .ok_or_else(|| ClanopediaError::NotFound(format!("Proposal {} not found", proposal_id)))?;
     if collection.governance_model == GovernanceModel::SnsIntegrated {
         if let Some(sns_governance) = collection.sns_governance_canister {
             if let Some(sns_proposal_id) = proposal.sns_proposal_id {
-                let is_approved = crate::external::sns_integration::check_sns_proposal_approved(
-                    sns_governance,
-                    sns_proposal_id,
-                )
-                .await?;
+                let sns = sns_integration::sns_governance(sns_governance);
+                let is_approved =
+                    sns_integration::check_sns_proposal_approved(&sns, sns_proposal_id).await?;
                 if is_approved && proposal.status == ProposalStatus::Active {
                     proposal.status = ProposalStatus::Approved;
                     proposal.threshold_met = true;
-                    storage::update_collection(&collection_id.to_string(), &collection)?;
+                    timelock::on_approved(&collection, &mut proposal);
+                    storage::put_proposal(collection_id, &proposal);
+                    notifications::proposal_approved(collection_id, &proposal);
+                    subscriptions::publish(collection_id, &proposal, ProposalEvent::Approved);
                 }
             }
         }
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::external::fakes::{self, FakeLedger, FakeSnsGovernance};
+    use futures_util::FutureExt;
+
+    // The fakes answer without waiting, so their futures complete on the first poll
+    fn ready<T>(future: impl std::future::Future<Output = T>) -> T {
+        future.now_or_never().expect("fakes never wait")
+    }
+
+    fn principal(n: u8) -> Principal {
+        Principal::from_slice(&[n; 29])
+    }
+
+    fn collection(model: GovernanceModel, admins: u8, threshold: u32) -> Collection {
+        Collection {
+            id: "col_test".to_string(),
+            admins: (1..=admins).map(principal).collect(),
+            threshold,
+            quorum_threshold: 50,
+            governance_model: model,
+            ..Collection::default()
+        }
+    }
+
+    fn proposal(votes: &[(u8, Vote, u64)]) -> Proposal {
+        Proposal {
+            id: "prop_test".to_string(),
+            collection_id: "col_test".to_string(),
+            proposal_type: ProposalType::BatchEmbed {
+                document_ids: vec![],
+            },
+            creator: principal(1),
+            description: String::new(),
+            created_at: 0,
+            expires_at: PROPOSAL_DURATION_NANOS,
+            status: ProposalStatus::Active,
+            votes: votes.iter().map(|(p, v, _)| (principal(*p), v.clone())).collect(),
+            token_votes: votes
+                .iter()
+                .map(|(p, _, weight)| (principal(*p), Nat::from(*weight)))
+                .collect(),
+            executed: false,
+            executed_at: None,
+            executed_by: None,
+            threshold: 1,
+            threshold_met: false,
+            sns_proposal_id: None,
+            bond: None,
+            execution_report: None,
+            reminded_at: None,
+            depends_on: None,
+            executable_at: None,
+            review: None,
+            sealed_voting: None,
+            sealed_proxy_api_key: None,
+        }
+    }
+
+    fn met(collection: &Collection, proposal: &Proposal) -> ClanopediaResult<bool> {
+        ready(threshold_met(
+            collection,
+            proposal,
+            None::<&FakeLedger>,
+            None::<&FakeSnsGovernance>,
+        ))
+    }
+
+    #[test]
+    fn token_quorum_rounds_down() {
+        assert_eq!(token_quorum(&Nat::from(1_000u64), 50), 500u64);
+        assert_eq!(token_quorum(&Nat::from(999u64), 50), 499u64);
+        assert_eq!(token_quorum(&Nat::from(1_000u64), 0), 0u64);
+        assert_eq!(token_quorum(&Nat::from(1_000u64), 100), 1_000u64);
+    }
+
+    #[test]
+    fn permissionless_needs_no_votes() {
+        let collection = collection(GovernanceModel::Permissionless, 1, 1);
+        assert!(met(&collection, &proposal(&[])).unwrap());
+    }
+
+    #[test]
+    fn multisig_counts_only_yes_votes() {
+        let collection = collection(GovernanceModel::Multisig, 3, 2);
+        let one_yes = proposal(&[(1, Vote::Yes, 0), (2, Vote::No, 0), (3, Vote::Abstain, 0)]);
+        assert!(!met(&collection, &one_yes).unwrap());
+        let two_yes = proposal(&[(1, Vote::Yes, 0), (2, Vote::Yes, 0)]);
+        assert!(met(&collection, &two_yes).unwrap());
+    }
+
+    #[test]
+    fn token_based_weighs_yes_votes_against_supply() {
+        let ledger_id = principal(90);
+        let collection = collection(GovernanceModel::TokenBased, 1, 1);
+        fakes::set_balance(ledger_id, principal(1), Nat::from(400u64));
+        fakes::set_balance(ledger_id, principal(2), Nat::from(300u64));
+        fakes::set_balance(ledger_id, principal(3), Nat::from(300u64));
+        let ledger = FakeLedger::new(ledger_id);
+        let check = |proposal: &Proposal| {
+            ready(threshold_met(
+                &collection,
+                proposal,
+                Some(&ledger),
+                None::<&FakeSnsGovernance>,
+            ))
+            .unwrap()
+        };
+
+        // 400 of 1000 is short of the 50% quorum; weight voted No does not count
+        assert!(!check(&proposal(&[(1, Vote::Yes, 400), (2, Vote::No, 300)])));
+        assert!(check(&proposal(&[(1, Vote::Yes, 400), (2, Vote::Yes, 300)])));
+        // Without a ledger the quorum cannot be checked
+        assert!(!met(&collection, &proposal(&[(1, Vote::Yes, 1_000)])).unwrap());
+    }
+
+    #[test]
+    fn sns_integrated_follows_the_sns_decision() {
+        let sns_id = principal(91);
+        let collection = collection(GovernanceModel::SnsIntegrated, 1, 1);
+        let sns = FakeSnsGovernance::new(sns_id);
+        let check = |proposal: &Proposal| {
+            ready(threshold_met(
+                &collection,
+                proposal,
+                None::<&FakeLedger>,
+                Some(&sns),
+            ))
+        };
+
+        assert!(matches!(
+            met(&collection, &proposal(&[])),
+            Err(ClanopediaError::SnsNotConfigured)
+        ));
+        let mut linked = proposal(&[]);
+        assert!(!check(&linked).unwrap());
+
+        linked.sns_proposal_id = Some(7);
+        assert!(matches!(check(&linked), Err(ClanopediaError::SnsError(_))));
+        fakes::set_proposal_tally(sns_id, 7, 10, 3, 1);
+        assert!(check(&linked).unwrap());
+        fakes::set_proposal_tally(sns_id, 7, 3, 10, 1);
+        assert!(!check(&linked).unwrap());
+    }
+
+    #[test]
+    fn proposers_need_the_token_balance_or_neuron_stake() {
+        let ledger_id = principal(92);
+        let sns_id = principal(93);
+        let mut collection = collection(GovernanceModel::TokenBased, 1, 1);
+        collection.governance_token = Some(ledger_id);
+        collection.sns_governance_canister = Some(sns_id);
+        fakes::set_balance(ledger_id, principal(5), Nat::from(99u64));
+        fakes::set_balance(ledger_id, principal(6), Nat::from(100u64));
+        fakes::set_neuron_stake(sns_id, principal(5), 1_000);
+
+        collection.proposer_eligibility = Some(ProposerEligibility::MinTokenBalance {
+            amount: Nat::from(100u64),
+        });
+        let eligible = |collection: &Collection, proposer: u8| {
+            ready(check_proposer_eligibility(collection, principal(proposer)))
+        };
+        assert!(matches!(
+            eligible(&collection, 5),
+            Err(ClanopediaError::Unauthorized(_))
+        ));
+        assert!(eligible(&collection, 6).is_ok());
+        // Admins may always propose
+        assert!(eligible(&collection, 1).is_ok());
+
+        collection.proposer_eligibility = Some(ProposerEligibility::SnsNeuron {
+            min_stake_e8s: 1_000,
+        });
+        assert!(eligible(&collection, 5).is_ok());
+        assert!(eligible(&collection, 6).is_err());
+    }
+
+    #[test]
+    fn composite_validates_each_action_after_the_ones_before_it() {
+        let collection = collection(GovernanceModel::Multisig, 2, 1);
+        let added_then_raised = [
+            ProposalType::AddAdmin {
+                admin: principal(3),
+            },
+            ProposalType::ChangeThreshold { new_threshold: 3 },
+        ];
+        assert!(validate_composite("col_test", &collection, &added_then_raised).is_ok());
+
+        let raised_alone = [ProposalType::ChangeThreshold { new_threshold: 3 }];
+        assert!(validate_composite("col_test", &collection, &raised_alone).is_err());
+
+        let removed_twice = [
+            ProposalType::RemoveAdmin {
+                admin: principal(1),
+            },
+            ProposalType::RemoveAdmin {
+                admin: principal(2),
+            },
+        ];
+        assert!(validate_composite("col_test", &collection, &removed_twice).is_err());
+    }
+
+    #[test]
+    fn composite_refuses_actions_that_cannot_be_undone() {
+        let collection = collection(GovernanceModel::Multisig, 2, 1);
+        assert!(validate_composite("col_test", &collection, &[]).is_err());
+        let with_delete = [
+            ProposalType::ChangeThreshold { new_threshold: 2 },
+            ProposalType::DeleteCollection,
+        ];
+        assert!(validate_composite("col_test", &collection, &with_delete).is_err());
+    }
+
+    #[test]
+    fn simulate_applies_collection_level_effects() {
+        let mut collection = collection(GovernanceModel::Multisig, 2, 1);
+        simulate(
+            &mut collection,
+            &ProposalType::AddAdmin {
+                admin: principal(3),
+            },
+        );
+        simulate(&mut collection, &ProposalType::UpdateQuorum { new_percentage: 75 });
+        simulate(
+            &mut collection,
+            &ProposalType::RemoveAdmin {
+                admin: principal(1),
+            },
+        );
+        assert_eq!(collection.admins, vec![principal(2), principal(3)]);
+        assert_eq!(collection.quorum_threshold, 75);
+    }
+}
@@ -0,0 +1,138 @@
+// src/clanopedia_backend/src/health.rs
+
+use std::cell::RefCell;
+use std::collections::BTreeSet;
+
+use candid::Principal;
+use futures_util::future::{join3, join_all};
+use ic_cdk::api::time;
+
+use crate::{
+    external::{
+        blueband,
+        sns_integration::{self, SnsApi},
+        token::{self, TokenApi},
+    },
+    proxy::require_controller,
+    storage,
+    types::{ClanopediaResult, Collection, DependencyHealth, DependencyKind, ExternalHealth},
+};
+
+thread_local! {
+    // Latest probe results; lost on upgrade until the next check
+    static LAST_HEALTH: RefCell<Option<ExternalHealth>> = const { RefCell::new(None) };
+}
+
+fn probe_result(
+    kind: DependencyKind,
+    canister_id: Principal,
+    started_at: u64,
+    result: Result<(), String>,
+) -> DependencyHealth {
+    let checked_at = time();
+    DependencyHealth {
+        kind,
+        canister_id,
+        healthy: result.is_ok(),
+        latency_nanos: checked_at - started_at,
+        error: result.err(),
+        checked_at,
+    }
+}
+
+async fn probe_blueband() -> DependencyHealth {
+    let started_at = time();
+    let canister_id = match crate::get_blueband_canister_id() {
+        Ok(canister_id) => canister_id,
+        Err(e) => {
+            return probe_result(
+                DependencyKind::Blueband,
+                Principal::anonymous(),
+                started_at,
+                Err(e.to_string()),
+            )
+        }
+    };
+    let result = blueband::check_canister(canister_id).await;
+    probe_result(DependencyKind::Blueband, canister_id, started_at, result)
+}
+
+async fn probe_ledger(canister_id: Principal) -> DependencyHealth {
+    let started_at = time();
+    let result = token::ledger(canister_id)
+        .icrc1_fee()
+        .await
+        .map(|_| ())
+        .map_err(|e| e.to_string());
+    probe_result(DependencyKind::TokenLedger, canister_id, started_at, result)
+}
+
+async fn probe_sns(canister_id: Principal) -> DependencyHealth {
+    let started_at = time();
+    let result = sns_integration::sns_governance(canister_id)
+        .get_proposal(0)
+        .await
+        .map(|_| ())
+        .map_err(|e| e.to_string());
+    probe_result(
+        DependencyKind::SnsGovernance,
+        canister_id,
+        started_at,
+        result,
+    )
+}
+
+/// Ping Blueband and every ledger and SNS governance canister a collection points at, and
+/// cache the results for the status and dashboard queries
+pub async fn check_external_health(caller: Principal) -> ClanopediaResult<ExternalHealth> {
+    require_controller(caller)?;
+
+    let collections = storage::list_collections();
+    let ledgers: BTreeSet<Principal> = collections
+        .iter()
+        .filter_map(|c| c.governance_token)
+        .collect();
+    let sns: BTreeSet<Principal> = collections
+        .iter()
+        .filter_map(|c| c.sns_governance_canister)
+        .collect();
+
+    let (blueband, ledgers, sns) = join3(
+        probe_blueband(),
+        join_all(ledgers.into_iter().map(probe_ledger)),
+        join_all(sns.into_iter().map(probe_sns)),
+    )
+    .await;
+
+    let mut dependencies = vec![blueband];
+    dependencies.extend(ledgers);
+    dependencies.extend(sns);
+    let health = ExternalHealth {
+        dependencies,
+        checked_at: time(),
+    };
+    LAST_HEALTH.with(|h| *h.borrow_mut() = Some(health.clone()));
+    Ok(health)
+}
+
+pub fn last_health() -> Option<ExternalHealth> {
+    LAST_HEALTH.with(|h| h.borrow().clone())
+}
+
+/// Cached results for the dependencies one collection relies on
+pub fn collection_dependencies(collection: &Collection) -> Vec<DependencyHealth> {
+    let Some(health) = last_health() else {
+        return Vec::new();
+    };
+    health
+        .dependencies
+        .into_iter()
+        .filter(|d| match d.kind {
+            DependencyKind::Blueband => true,
+            DependencyKind::TokenLedger => collection.governance_token == Some(d.canister_id),
+            DependencyKind::SnsGovernance => {
+                collection.sns_governance_canister == Some(d.canister_id)
+            }
+        })
+        .collect()
+}
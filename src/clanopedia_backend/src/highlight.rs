@@ -0,0 +1,187 @@
+// src/clanopedia_backend/src/highlight.rs
+//
+// Excerpts for search hits. Query terms are matched against the chunk text Blueband returns,
+// ignoring case and accents, so frontends can bold matches in a short snippet without
+// fetching the document.
+
+use crate::{
+    keyword_index,
+    types::{HighlightRange, SearchHighlight},
+    VectorMatch,
+};
+
+const MAX_SNIPPET_CHARS: usize = 240;
+const SNIPPET_LEAD_CHARS: usize = 60; // Context kept before the first highlighted match
+
+// Latin letters with their accents removed; text is lowercased before this is applied
+fn strip_accent(c: char) -> char {
+    match c {
+        'à'..='å' | 'ā' | 'ă' | 'ą' => 'a',
+        'ç' | 'ć' | 'ĉ' | 'ċ' | 'č' => 'c',
+        'ď' | 'đ' => 'd',
+        'è'..='ë' | 'ē' | 'ĕ' | 'ė' | 'ę' | 'ě' => 'e',
+        'ĝ' | 'ğ' | 'ġ' | 'ģ' => 'g',
+        'ĥ' | 'ħ' => 'h',
+        'ì'..='ï' | 'ĩ' | 'ī' | 'ĭ' | 'į' | 'ı' => 'i',
+        'ĵ' => 'j',
+        'ķ' => 'k',
+        'ĺ' | 'ļ' | 'ľ' | 'ŀ' | 'ł' => 'l',
+        'ñ' | 'ń' | 'ņ' | 'ň' => 'n',
+        'ò'..='ö' | 'ø' | 'ō' | 'ŏ' | 'ő' => 'o',
+        'ŕ' | 'ŗ' | 'ř' => 'r',
+        'ś' | 'ŝ' | 'ş' | 'š' => 's',
+        'ţ' | 'ť' | 'ŧ' => 't',
+        'ù'..='ü' | 'ũ' | 'ū' | 'ŭ' | 'ů' | 'ű' | 'ų' => 'u',
+        'ŵ' => 'w',
+        'ý' | 'ÿ' | 'ŷ' => 'y',
+        'ź' | 'ż' | 'ž' => 'z',
+        _ => c,
+    }
+}
+
+// Accents typed as separate combining characters
+fn is_combining_mark(c: char) -> bool {
+    matches!(c, '\u{0300}'..='\u{036F}')
+}
+
+// The folded form of `text`, with the index of the original character each folded
+// character came from
+fn fold(text: &[char]) -> (Vec<char>, Vec<usize>) {
+    let mut folded = Vec::with_capacity(text.len());
+    let mut origin = Vec::with_capacity(text.len());
+    for (i, c) in text.iter().enumerate() {
+        if is_combining_mark(*c) {
+            continue;
+        }
+        for lower in c.to_lowercase() {
+            folded.push(strip_accent(lower));
+            origin.push(i);
+        }
+    }
+    (folded, origin)
+}
+
+/// Character ranges of `text` where a query term starts a word, merged where they overlap
+fn find_matches(text: &[char], query: &str) -> Vec<HighlightRange> {
+    let terms: Vec<Vec<char>> = keyword_index::tokenize(query)
+        .iter()
+        .map(|t| fold(&t.chars().collect::<Vec<_>>()).0)
+        .filter(|t| !t.is_empty())
+        .collect();
+    let (folded, origin) = fold(text);
+
+    let mut ranges = Vec::new();
+    for term in &terms {
+        for start in 0..folded.len() {
+            let at_word_start = start == 0 || !folded[start - 1].is_alphanumeric();
+            if !at_word_start || !folded[start..].starts_with(term) {
+                continue;
+            }
+            // Keep combining accents that follow the last matched letter
+            let mut end = origin[start + term.len() - 1] + 1;
+            while end < text.len() && is_combining_mark(text[end]) {
+                end += 1;
+            }
+            ranges.push((origin[start], end));
+        }
+    }
+    ranges.sort_unstable();
+
+    let mut merged: Vec<HighlightRange> = Vec::new();
+    for (start, end) in ranges {
+        match merged.last_mut() {
+            Some(last) if start <= last.end as usize => {
+                last.end = last.end.max(end as u32);
+            }
+            _ => merged.push(HighlightRange {
+                start: start as u32,
+                end: end as u32,
+            }),
+        }
+    }
+    merged
+}
+
+// The window of at most MAX_SNIPPET_CHARS holding the most matches, widened to word
+// boundaries where that does not cut a match
+fn snippet_window(text: &[char], ranges: &[HighlightRange]) -> (usize, usize) {
+    if text.len() <= MAX_SNIPPET_CHARS {
+        return (0, text.len());
+    }
+    let reach = MAX_SNIPPET_CHARS - SNIPPET_LEAD_CHARS;
+    let anchor = (0..ranges.len())
+        .max_by_key(|&i| {
+            let covered = ranges[i..]
+                .iter()
+                .take_while(|r| r.end <= ranges[i].start + reach as u32)
+                .count();
+            (covered, std::cmp::Reverse(i))
+        })
+        .map_or(0, |i| ranges[i].start as usize);
+
+    let mut start = anchor.saturating_sub(SNIPPET_LEAD_CHARS);
+    let mut end = (start + MAX_SNIPPET_CHARS).min(text.len());
+    start = end.saturating_sub(MAX_SNIPPET_CHARS);
+
+    let first_kept = ranges
+        .iter()
+        .find(|r| r.start as usize >= start)
+        .map_or(end, |r| (r.start as usize).min(end));
+    if start > 0 && !text[start - 1].is_whitespace() {
+        if let Some(space) = text[start..first_kept]
+            .iter()
+            .position(|c| c.is_whitespace())
+        {
+            start += space + 1;
+        }
+    }
+    let last_kept = ranges
+        .iter()
+        .filter(|r| r.end as usize <= end)
+        .map(|r| r.end as usize)
+        .max()
+        .unwrap_or(start);
+    if end < text.len() && !text[end].is_whitespace() {
+        if let Some(space) = text[last_kept.max(start)..end]
+            .iter()
+            .rposition(|c| c.is_whitespace())
+        {
+            end = last_kept.max(start) + space;
+        }
+    }
+    (start, end)
+}
+
+/// A snippet of `text` around the densest cluster of query matches
+pub fn highlight(text: &str, query: &str) -> SearchHighlight {
+    let chars: Vec<char> = text.chars().collect();
+    let ranges = find_matches(&chars, query);
+    let (mut start, mut end) = snippet_window(&chars, &ranges);
+    while start < end && chars[start].is_whitespace() {
+        start += 1;
+    }
+    while end > start && chars[end - 1].is_whitespace() {
+        end -= 1;
+    }
+
+    SearchHighlight {
+        snippet: chars[start..end].iter().collect(),
+        ranges: ranges
+            .into_iter()
+            .filter(|r| r.start as usize >= start && r.end as usize <= end)
+            .map(|r| HighlightRange {
+                start: r.start - start as u32,
+                end: r.end - start as u32,
+            })
+            .collect(),
+        truncated_start: chars[..start].iter().any(|c| !c.is_whitespace()),
+        truncated_end: chars[end..].iter().any(|c| !c.is_whitespace()),
+    }
+}
+
+/// Fill in the highlight of every match that came back with its chunk text
+pub fn apply(query: &str, matches: &mut [VectorMatch]) {
+    for m in matches.iter_mut() {
+        m.highlight = m.chunk_text.as_deref().map(|text| highlight(text, query));
+    }
+}
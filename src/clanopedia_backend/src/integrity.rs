@@ -0,0 +1,119 @@
+// src/clanopedia_backend/src/integrity.rs
+//
+// Clanopedia hashes each document's content before sending it to Blueband and keeps the
+// hash. Blueband's returned checksum must match it, and the stored hashes can later be
+// re-checked against the content Blueband serves.
+
+use futures_util::future::join_all;
+use sha2::{Digest, Sha256};
+
+use crate::{
+    external::blueband::{self, DocumentMetadata},
+    logging, storage,
+    types::{ClanopediaError, ClanopediaResult, Collection, DocumentId, IntegrityReport},
+};
+
+const MAX_VERIFY_BATCH: usize = 10; // Parallel content reads per round
+
+/// Hex SHA-256 of a document's content, the form Blueband reports its checksum in
+pub fn content_checksum(content: &str) -> String {
+    hex::encode(Sha256::digest(content.as_bytes()))
+}
+
+/// Compare Blueband's checksum for a just-added document with the content that was sent,
+/// and store it with the content's size. A document Blueband stored differently is deleted
+/// again.
+pub async fn check_added_document(
+    collection: &Collection,
+    content: &str,
+    metadata: &DocumentMetadata,
+) -> ClanopediaResult<()> {
+    let checksum = content_checksum(content);
+
+    // An empty checksum comes from a Blueband version that does not compute one
+    if !metadata.checksum.is_empty() && !metadata.checksum.eq_ignore_ascii_case(&checksum) {
+        if let Err(e) =
+            blueband::delete_document(&collection.blueband_collection_id, &metadata.id).await
+        {
+            logging::error(
+                "integrity",
+                Some(&collection.id),
+                format!("Removing mismatched document {} failed: {}", metadata.id, e),
+            );
+        }
+        return Err(ClanopediaError::BluebandError(format!(
+            "Checksum mismatch for document {}: sent {}, Blueband stored {}",
+            metadata.id, checksum, metadata.checksum
+        )));
+    }
+
+    storage::set_document_checksum(&collection.id, &metadata.id, checksum);
+    storage::set_document_size(&collection.id, &metadata.id, content.len() as u64);
+    Ok(())
+}
+
+enum Check {
+    Verified,
+    Mismatched,
+    Missing,
+    Failed,
+}
+
+/// Re-read every document with a stored checksum from Blueband and hash its content again
+pub async fn verify_collection(collection: &Collection) -> IntegrityReport {
+    let checksums = storage::list_document_checksums(&collection.id);
+    let mut report = IntegrityReport {
+        unrecorded: storage::list_known_document_ids(&collection.id)
+            .iter()
+            .filter(|id| !checksums.iter().any(|(recorded, _)| recorded == *id))
+            .filter(|id| storage::get_cross_post(&collection.id, id).is_none())
+            .count() as u32,
+        ..Default::default()
+    };
+
+    for batch in checksums.chunks(MAX_VERIFY_BATCH) {
+        let checks = join_all(
+            batch
+                .iter()
+                .map(|(document_id, checksum)| check_document(collection, document_id, checksum)),
+        )
+        .await;
+
+        for ((document_id, _), check) in batch.iter().zip(checks) {
+            report.checked += 1;
+            match check {
+                Check::Verified => report.verified += 1,
+                Check::Mismatched => report.mismatched.push(document_id.clone()),
+                Check::Missing => report.missing.push(document_id.clone()),
+                Check::Failed => report.failed.push(document_id.clone()),
+            }
+        }
+    }
+
+    report
+}
+
+async fn check_document(
+    collection: &Collection,
+    document_id: &DocumentId,
+    checksum: &str,
+) -> Check {
+    match blueband::get_document_content_from_blueband(
+        &collection.blueband_collection_id,
+        document_id,
+    )
+    .await
+    {
+        Ok(Some(content)) if content_checksum(&content) == checksum => Check::Verified,
+        Ok(Some(_)) => Check::Mismatched,
+        Ok(None) => Check::Missing,
+        Err(e) => {
+            logging::warn(
+                "integrity",
+                Some(&collection.id),
+                format!("Reading document {} for verification failed: {}", document_id, e),
+            );
+            Check::Failed
+        }
+    }
+}
@@ -0,0 +1,204 @@
+// src/clanopedia_backend/src/keyword_index.rs
+
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    extractor::keywords::is_stopword,
+    site, storage,
+    types::{DocumentId, HybridMatch, IndexedDocument},
+    VectorMatch,
+};
+
+// Terms this common say little about a document; a shard lists no further documents
+// under them
+const MAX_POSTINGS_PER_SHARD: usize = 2_000;
+const MIN_TERM_CHARS: usize = 2;
+const MAX_TERM_CHARS: usize = 64;
+
+// Standard reciprocal rank fusion constant; damps the influence of top ranks
+const RRF_K: f64 = 60.0;
+
+/// Split text into index terms. Identifiers such as `E0382` or `net-timeout_2` are kept whole
+/// and their parts are indexed as well.
+pub fn tokenize(text: &str) -> Vec<String> {
+    let is_separator = |c: char| matches!(c, '-' | '_' | '.');
+    let mut terms = Vec::new();
+    let mut seen = HashSet::new();
+
+    let mut push = |term: &str| {
+        let len = term.chars().count();
+        if (MIN_TERM_CHARS..=MAX_TERM_CHARS).contains(&len)
+            && !is_stopword(term)
+            && seen.insert(term.to_string())
+        {
+            terms.push(term.to_string());
+        }
+    };
+
+    for token in text
+        .split(|c: char| !(c.is_alphanumeric() || is_separator(c)))
+        .map(|t| t.trim_matches(is_separator).to_lowercase())
+        .filter(|t| !t.is_empty())
+    {
+        push(&token);
+        if token.contains(is_separator) {
+            token.split(is_separator).for_each(&mut push);
+        }
+    }
+
+    terms
+}
+
+fn document_terms(document: &IndexedDocument) -> HashSet<String> {
+    std::iter::once(document.title.as_str())
+        .chain(document.tags.iter().map(String::as_str))
+        .flat_map(tokenize)
+        .collect()
+}
+
+// ============================
+// INDEXING
+// ============================
+
+/// Index a document's title and tags, replacing whatever was indexed for it before
+pub fn index_document(collection_id: &str, document_id: &str, title: &str, tags: &[String]) {
+    let previous = storage::get_indexed_document(collection_id, document_id)
+        .map(|d| document_terms(&d))
+        .unwrap_or_default();
+
+    let document = IndexedDocument {
+        title: title.to_string(),
+        tags: tags.to_vec(),
+    };
+    let current = document_terms(&document);
+
+    for term in previous.difference(&current) {
+        storage::remove_keyword_posting(collection_id, term, document_id);
+    }
+
+    for term in current.difference(&previous) {
+        storage::add_keyword_posting(collection_id, term, document_id, MAX_POSTINGS_PER_SHARD);
+    }
+
+    storage::set_indexed_document(collection_id, document_id, document);
+    site::index_document(collection_id, document_id, title);
+}
+
+/// Re-index after a metadata edit; fields left as `None` keep their indexed values
+pub fn update_document(
+    collection_id: &str,
+    document_id: &str,
+    title: Option<&String>,
+    tags: Option<&Vec<String>>,
+) {
+    let Some(indexed) = storage::get_indexed_document(collection_id, document_id) else {
+        return;
+    };
+
+    index_document(
+        collection_id,
+        document_id,
+        title.unwrap_or(&indexed.title),
+        tags.unwrap_or(&indexed.tags),
+    );
+}
+
+pub fn remove_document(collection_id: &str, document_id: &str) {
+    let Some(indexed) = storage::get_indexed_document(collection_id, document_id) else {
+        return;
+    };
+
+    for term in document_terms(&indexed) {
+        storage::remove_keyword_posting(collection_id, &term, document_id);
+    }
+    storage::remove_indexed_document(collection_id, document_id);
+    site::remove_document(collection_id, document_id);
+}
+
+// ============================
+// SEARCH
+// ============================
+
+/// Documents matching any query term, ranked by how many distinct terms they match
+pub fn keyword_matches(collection_id: &str, query: &str) -> Vec<(DocumentId, Vec<String>)> {
+    let mut matches: HashMap<DocumentId, Vec<String>> = HashMap::new();
+    for term in tokenize(query) {
+        if let Some(postings) = storage::get_keyword_postings(collection_id, &term) {
+            for document_id in postings.document_ids {
+                matches.entry(document_id).or_default().push(term.clone());
+            }
+        }
+    }
+
+    // Trashed documents stay indexed so a restore needs no re-indexing
+    matches.retain(|document_id, _| {
+        storage::get_trashed_document(collection_id, document_id).is_none()
+    });
+
+    let mut ranked: Vec<(DocumentId, Vec<String>)> = matches.into_iter().collect();
+    ranked.sort_by(|(a, a_terms), (b, b_terms)| {
+        b_terms.len().cmp(&a_terms.len()).then_with(|| a.cmp(b))
+    });
+    ranked
+}
+
+/// Merge vector and keyword rankings with reciprocal rank fusion: each list contributes
+/// `1 / (RRF_K + rank)` for every document it contains.
+pub fn fuse_results(
+    collection_id: &str,
+    vector_matches: Vec<VectorMatch>,
+    keyword_matches: Vec<(DocumentId, Vec<String>)>,
+    limit: usize,
+) -> Vec<HybridMatch> {
+    let mut fused: Vec<HybridMatch> = Vec::new();
+    let mut positions: HashMap<DocumentId, usize> = HashMap::new();
+
+    // Blueband returns chunks; a document ranks at its best chunk
+    let mut vector_rank = 0;
+    for vector_match in vector_matches {
+        if positions.contains_key(&vector_match.document_id) {
+            continue;
+        }
+        vector_rank += 1;
+        positions.insert(vector_match.document_id.clone(), fused.len());
+        fused.push(HybridMatch {
+            document_id: vector_match.document_id.clone(),
+            document_title: vector_match.document_title.clone(),
+            score: 1.0 / (RRF_K + vector_rank as f64),
+            vector_match: Some(vector_match),
+            matched_terms: Vec::new(),
+            applied_aliases: Vec::new(),
+        });
+    }
+
+    for (rank, (document_id, terms)) in keyword_matches.into_iter().enumerate() {
+        let score = 1.0 / (RRF_K + (rank + 1) as f64);
+        match positions.get(&document_id) {
+            Some(&i) => {
+                fused[i].score += score;
+                fused[i].matched_terms = terms;
+            }
+            None => {
+                let document_title =
+                    storage::get_indexed_document(collection_id, &document_id).map(|d| d.title);
+                positions.insert(document_id.clone(), fused.len());
+                fused.push(HybridMatch {
+                    document_id,
+                    document_title,
+                    score,
+                    vector_match: None,
+                    matched_terms: terms,
+                    applied_aliases: Vec::new(),
+                });
+            }
+        }
+    }
+
+    fused.sort_by(|a, b| {
+        b.score
+            .total_cmp(&a.score)
+            .then_with(|| a.document_id.cmp(&b.document_id))
+    });
+    fused.truncate(limit);
+    fused
+}
@@ -5,27 +5,117 @@ use getrandom::getrandom;
 use ic_cdk::api::caller;
 use ic_cdk::api::management_canister::http_request::{HttpResponse, TransformArgs};
 use ic_cdk::api::time;
-use ic_cdk::{query, update};
-use ic_stable_structures::{
-    memory_manager::{MemoryId, MemoryManager},
-    DefaultMemoryImpl, StableBTreeMap,
-};
+use ic_cdk::{post_upgrade, query, update};
+use ic_stable_structures::StableBTreeMap;
+use stats::StatEvent;
 use std::cell::RefCell;
 
+mod activity;
+mod admin;
+mod archive;
+mod attachments;
+mod attestations;
+mod auth;
+mod backup;
+mod bonds;
+mod bookmarks;
+mod bots;
+mod changes;
+mod chunks;
+mod cold_storage;
+mod config_history;
+mod content_limits;
+mod creation;
+mod crosspost;
+mod custom_proposals;
 mod cycles;
+mod dashboard;
+mod encryption;
+mod execution_receipts;
 mod external;
 mod extractor;
+mod fork;
+mod glossary;
 mod governance;
+mod health;
+mod highlight;
+mod integrity;
+mod keyword_index;
+mod licensing;
+mod logging;
+mod memory;
+mod notifications;
+mod ocr;
+mod outcall_budget;
+mod outcall_pricing;
+mod pins;
+mod proposal_search;
+mod proxy;
+mod rate_limit;
+mod reactions;
+mod rendering;
+mod receipts;
+mod reconcile;
+mod reminders;
+mod replica;
+mod resync;
+mod review;
+mod sealed_votes;
+mod secrets;
+mod site;
+mod slugs;
+mod source_policy;
+mod spam;
+mod stats;
+mod status;
 mod storage;
+mod subscriptions;
+mod summarize;
+mod templates;
+mod timelock;
+mod timers;
+mod trash;
 mod types;
 mod utils;
+mod validation;
+mod vault;
+mod views;
+mod vote_projection;
 
 // Re-export specific types and functions
 pub use types::{
-    BluebandConfig, BluebandDocument, ClanopediaError, ClanopediaResult, Collection,
-    CollectionConfig, CollectionId, DocumentId, DocumentRequest, GovernanceModel,
-    GovernanceModelConfig, Proposal, ProposalId, ProposalStatus, ProposalType, SearchResult, Vote,
-    PROPOSAL_DURATION_NANOS,
+    ActivityEntry, AliasExpansion, AliasUpdate, AnalyticsBucket, ArchivedDocument, CallTrace,
+    BackupChunk, BackupConfig, BackupRecord, BackupSection,
+    BackupStatus, BackupSummary, BackupTarget, AnalyticsRange, AttachmentChunk, AutoTagConfig, BluebandConfig, Bookmark,
+    BluebandDocument, BluebandReconcileState, BluebandReconciliation, BluebandRepair, BondStatus, BotDelegation, BotSearchHit, BotVotePayload, CallAllowlistEntry, CanisterRuntimeStatus, Capability, ChangeKind, ChatBot, ChangePage, ClanopediaError,
+    ClanopediaResult, Collection, CollectionAdminSummary, CollectionAnalytics, CollectionConfig,
+    CollectionLimits, ContentLimits,
+    CollectionDashboard, CollectionEncryption, CollectionEvent, CollectionEventKind, CollectionId,
+    CollectionTemplate, CollectionTemplateInfo, CreationFee, CreationMode, CreationPolicy,
+    CreationReconciliation, CreationRequest, CreationRequestStatus, DashboardCounts, DependencyHealth, DependencyKind,
+    CodeBlock, ConfigChange, ConfigSetting, ContentLink, ContentStructure, DocumentChunk, DocumentChunkPage, DocumentId,
+    DocumentLinks, DocumentMetadataOverlay, DocumentNft, DocumentOutline, DocumentProvenance, DocumentReplacement,
+    DocumentRequest, DocumentView, EmbeddingProxyConfig, EmbeddingStatus, EncryptedCollectionKey,
+    EncryptionConfig, EventKind, EventLogEntry, ExecutionAttempt, ExecutionItem, ExecutionReceipt,
+    ExecutionReport, ExpiringProposal, ExternalCallRecord, ExternalHealth,
+    FieldError, ForkConfig, ForkOrigin, ForkResult, ForkedDocument, GatewayRequest, GatewayResponse, GovernanceModel,
+    GovernanceModelConfig, GovernanceSubscriber, HighlightRange, HybridMatch, IndexedDocument, IntegrityReport, ItemExecutionState,
+    KeywordPostings, License, LicensePolicy, LinkEdge, LinkGraph, LinkNode, LogEntry, LogFilter,
+    LogLevel, MapCap, MapPurge,
+    MapUsage, MigrationProgress, NotificationPage, OcrConfig, OcrOutcome, OperatorGrant, OutcallBudget, OutcallBudgetStatus, OutcallMirrors,
+    OutlineEntry,
+    PauseInfo, PendingConfigChange, PermawebGateways, PinnedDocument,
+    Proposal, ProposalAttachment, ProposalBond, ProposalBondConfig, ProposalEvent,
+    ProposalFee, ProposalFilter, ProposalId, ProposalSearchPage,
+    ProjectedOutcome, ProposalStatus, ProposalSummary, ProposalType, ProposerEligibility, QueryAlias,
+    QueryLogEntry, QueryStat, Reaction, ReactionCount, ReactionSummary, ReactionTarget,
+    ReadReplica, RenderedDocument, ReplicaDeltaPage, ReplicaEntry, ReplicaRecord, ReplicaSection, ReplicaSnapshotPage,
+    Review, ReviewDecision, ReviewPolicy, ReviewStatus, RetryPolicy, ProposalReview, ReaderBookmarks, ReadingList,
+    SealedSecret, SealedVoting, SearchHighlight, SearchResult, SecretInfo, SelfStatus, ShardUsage, SiteConfig,
+    SourcePolicy, SourcePolicyViolation, SpamProtection, StorageCaps, StorageMap, SubnetSize,
+    StorageReport, SummarizationConfig, SummarizationOutcome, TemplateOverrides, TrashInfo,
+    TrashedDocument, TrendingDocument, TrendingWindow, UnsettledBond, Vote, VoteCommitment, VoteProjection, VoteReceipt, VoteReceiptProof, VoteTally, VoterCount, WeeklyRollup,
+    MAX_TIMELOCK_HOURS, MAX_VOTE_REMINDER_HOURS, PROPOSAL_DURATION_NANOS,
 };
 
 pub use external::blueband::{get_collection_metrics, CollectionMetrics};
@@ -34,13 +124,16 @@ pub use external::{
     embed_existing_document, fund_blueband_cycles, get_blueband_cycles_balance,
     get_document_content_from_blueband, get_document_metadata, get_token_balance,
     get_token_total_supply, transfer_genesis_admin, BluebandResult, BluebandService,
-    DocumentMetadata, MemorySearchResult, SearchRequest, TokenResult, TokenService, VectorMatch,
+    DocumentMetadata, MemorySearchResult, SearchRequest, SnsGovernanceService, TokenResult,
+    TokenService, VectorMatch,
 };
 
 pub use extractor::{
-    AddDocumentsResult, DocumentAction, ExtractionInfo, ExtractionProgress, ExtractionResponse,
-    ExtractionResult, ExtractionSource, ExtractionStatus, Extractor, FileExtractionConfig,
-    FileType, UrlType, YouTubeVideoInfo,
+    AddDocumentsResult, DocumentAction, DocumentPipelineReport, DocumentTagSuggestions, KeywordSuggestion, ExtractionDiff, ExtractionInfo, ExtractionPreview, ExtractionPreviewResult,
+    ExtractionJob, ExtractionJobState, ExtractionJobStatus, ExtractionProgress, ExtractionResponse,
+    ExtractionResult, ExtractionSource, ExtractionStatus, JobProgress, ResyncResult,
+    Extractor, FileExtractionConfig, FileType, PipelineStep, PipelineStepRecord, RecordFieldMapping, StepOutcome,
+    UrlType, YouTubeCostEstimate, YouTubeVideoInfo,
 };
 
 pub use cycles::{estimate_embedding_cost, CyclesStatus};
@@ -49,20 +142,11 @@ use crate::external::blueband::AddDocumentRequest;
 
 // use crate::extractor::{};
 
-type Memory = ic_stable_structures::memory_manager::VirtualMemory<DefaultMemoryImpl>;
-
-// Memory manager for stable storage
-thread_local! {
-    static MEMORY_MANAGER: RefCell<MemoryManager<DefaultMemoryImpl>> = RefCell::new(
-        MemoryManager::init(DefaultMemoryImpl::default())
-    );
-}
-
 // Global state for Blueband canister ID
 thread_local! {
-    static BLUEBAND_CANISTER_ID: RefCell<StableBTreeMap<(), Principal, Memory>> = RefCell::new(
+    static BLUEBAND_CANISTER_ID: RefCell<StableBTreeMap<(), Principal, memory::Memory>> = RefCell::new(
         StableBTreeMap::init(
-            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(2)))
+            memory::get(memory::BLUEBAND_CANISTER_ID)
         )
     );
 }
@@ -84,7 +168,7 @@ pub fn get_blueband_canister_id() -> ClanopediaResult<Principal> {
 // Helper function to check if a user is an admin of a collection
 fn is_admin(collection_id: &str, user: Principal) -> bool {
     match storage::get_collection(&collection_id.to_string()) {
-        Ok(collection) => collection.admins.contains(&user),
+        Ok(collection) => auth::is_admin(&collection, user),
         Err(_) => false,
     }
 }
@@ -95,30 +179,69 @@ fn is_admin(collection_id: &str, user: Principal) -> bool {
 
 #[update]
 fn configure_blueband_canister(canister_id: Principal) -> ClanopediaResult<()> {
-    // Only allow if not already set (for safety)
+    activity::record(ic_cdk::caller(), "configure_blueband_canister", None);
+    // Only allow if not already set; later changes go through the health-checked path
     if BLUEBAND_CANISTER_ID.with(|id| id.borrow().contains_key(&())) {
         return Err(ClanopediaError::InvalidOperation(
-            "Blueband canister already configured".to_string(),
+            "Blueband canister already configured; use update_blueband_canister to change it"
+                .to_string(),
         ));
     }
 
     set_blueband_canister_id(canister_id);
+    storage::append_event(
+        ic_cdk::caller(),
+        EventKind::BluebandCanisterChanged {
+            previous: None,
+            new: canister_id,
+        },
+    );
+    config_history::record(
+        ic_cdk::caller(),
+        None,
+        ConfigSetting::BluebandCanister(canister_id),
+    )?;
     Ok(())
 }
 
 #[query]
 fn get_collection(collection_id: String) -> ClanopediaResult<Collection> {
-    storage::get_collection(&collection_id)
+    let collection_id = slugs::collection_id(&collection_id);
+    storage::get_collection(&collection_id).map(with_proposals)
 }
 
 #[query]
 fn list_collections() -> ClanopediaResult<Vec<Collection>> {
-    Ok(storage::list_collections())
+    Ok(storage::list_collections()
+        .into_iter()
+        .filter(|c| c.trashed.is_none())
+        .map(with_proposals)
+        .collect())
+}
+
+// Callers still read proposals off the collection; fill in the ones already migrated
+fn with_proposals(mut collection: Collection) -> Collection {
+    collection.proposals = storage::list_proposals(&collection.id);
+    collection
 }
 
 #[update]
 async fn create_collection_endpoint(config: CollectionConfig) -> ClanopediaResult<CollectionId> {
+    activity::record(ic_cdk::caller(), "create_collection_endpoint", None);
     let caller = ic_cdk::caller();
+    let (fee, _slot) = creation::admit(caller).await?;
+    let result = create_collection_as(config, caller).await;
+    creation::settle_fee(fee, caller, result.is_ok()).await;
+    result
+}
+
+// Create a collection owned by `caller` without checking the creation policy
+pub(crate) async fn create_collection_as(
+    mut config: CollectionConfig,
+    caller: Principal,
+) -> ClanopediaResult<CollectionId> {
+    validation::validate_collection_config(&mut config)?;
+    storage::ensure_capacity(StorageMap::Collections, 1)?;
 
     // Generate a random number using getrandom
     let mut random_bytes = [0u8; 4];
@@ -142,14 +265,29 @@ async fn create_collection_endpoint(config: CollectionConfig) -> ClanopediaResul
     let random_hex = format!("{:04x}", random_number % 0xFFFF);
     let collection_id = format!("col_{}_{}_{}", caller_short, timestamp_short, random_hex);
 
-    // Create collection in Blueband first
-    let blueband_collection = create_blueband_collection(
-        collection_id.clone(),
-        config.name.clone(),
-        config.description.clone(),
-    )
-    .await
-    .map_err(|e| ClanopediaError::BluebandError(e.to_string()))?;
+    let token_principal = config
+        .governance_token
+        .as_ref()
+        .and_then(|t| Principal::from_text(t).ok());
+    let sns_principal = config
+        .sns_governance_canister
+        .as_ref()
+        .and_then(|s| Principal::from_text(s).ok());
+    governance::validate_proposal_bond(&config.proposal_bond, &token_principal).await?;
+    governance::validate_proposer_eligibility(
+        &config.proposer_eligibility,
+        &token_principal,
+        &sns_principal,
+    )?;
+
+    extractor::pipeline::validate_pipeline(&config.pipeline)?;
+    extractor::keywords::validate_auto_tag(&config.auto_tag)?;
+    summarize::validate_summarization(&config.summarization, config.encryption.is_some())?;
+    ocr::validate_ocr(&config.ocr, config.encryption.is_some())?;
+    attestations::validate_nft_minting(&config.nft_minting, config.encryption.is_some())?;
+    custom_proposals::validate_call_policy(&config.call_policy)?;
+    custom_proposals::validate_custom_kinds(&config.custom_proposals, &config.call_policy)?;
+    let (proxy_url, proxy_api_key) = proxy::resolve_proxy_config(&config.embedding_proxy)?;
 
     // Convert string representations to Principal objects for validation
     let admins: Result<Vec<Principal>, _> = config
@@ -167,7 +305,9 @@ async fn create_collection_endpoint(config: CollectionConfig) -> ClanopediaResul
             ClanopediaError::InvalidInput(format!("Invalid governance token principal: {}", e))
         })?;
 
-    // Create collection in Clanopedia storage with Blueband ID
+    // Create collection in Clanopedia storage first, then in Blueband
+    let name = config.name.clone();
+    let description = config.description.clone();
     let mut collection_config = config;
 
     // If any principal is invalid, use just the caller
@@ -183,26 +323,129 @@ async fn create_collection_endpoint(config: CollectionConfig) -> ClanopediaResul
     // Update the config with validated principals
     collection_config.governance_token = governance_token.map(|p| p.to_string());
 
-    storage::create_collection(&collection_id, collection_config, caller)?;
+    creation::begin(&collection_id, caller);
+    if let Err(e) = storage::create_collection(&collection_id, collection_config, caller) {
+        // Nothing was written; an existing collection with this ID must not be rolled back
+        creation::finish(&collection_id);
+        return Err(e);
+    }
+    if let Err(e) = proxy::store_proxy_api_key(&collection_id, proxy_api_key.clone(), caller).await
+    {
+        creation::abort(&collection_id);
+        return Err(e);
+    }
+
+    let blueband_collection = match create_blueband_collection(
+        collection_id.clone(),
+        name,
+        description,
+        proxy_url,
+        proxy_api_key,
+    )
+    .await
+    {
+        Ok(blueband_collection) => blueband_collection,
+        Err(e) => {
+            creation::abort(&collection_id);
+            return Err(ClanopediaError::BluebandError(e.to_string()));
+        }
+    };
 
     // Update the collection with Blueband ID
     let mut collection = storage::get_collection(&collection_id)?;
     collection.blueband_collection_id = blueband_collection.id;
     storage::update_collection(&collection_id, &collection)?;
+    creation::finish(&collection_id);
 
     Ok(collection_id)
 }
 
+// ============================
+// COLLECTION CREATION POLICY
+// ============================
+
+#[query]
+fn get_creation_policy() -> CreationPolicy {
+    storage::get_creation_policy()
+}
+
+#[update]
+fn set_creation_policy(policy: CreationPolicy) -> ClanopediaResult<()> {
+    activity::record(ic_cdk::caller(), "set_creation_policy", None);
+    creation::set_policy(ic_cdk::caller(), policy)
+}
+
+#[update]
+async fn request_collection_creation(config: CollectionConfig) -> ClanopediaResult<u64> {
+    activity::record(ic_cdk::caller(), "request_collection_creation", None);
+    creation::request_creation(ic_cdk::caller(), config).await
+}
+
+#[update]
+async fn approve_creation_request(request_id: u64) -> ClanopediaResult<CollectionId> {
+    activity::record(ic_cdk::caller(), "approve_creation_request", None);
+    creation::approve_request(ic_cdk::caller(), request_id).await
+}
+
+#[update]
+fn reject_creation_request(request_id: u64, reason: String) -> ClanopediaResult<()> {
+    activity::record(ic_cdk::caller(), "reject_creation_request", None);
+    creation::reject_request(ic_cdk::caller(), request_id, reason)
+}
+
+#[query]
+fn list_creation_requests() -> Vec<CreationRequest> {
+    creation::list_requests(ic_cdk::caller())
+}
+
+#[update]
+async fn reconcile_collection_creations() -> ClanopediaResult<CreationReconciliation> {
+    activity::record(ic_cdk::caller(), "reconcile_collection_creations", None);
+    creation::reconcile(ic_cdk::caller()).await
+}
+
+#[query]
+fn list_collection_templates() -> Vec<CollectionTemplateInfo> {
+    templates::list_templates()
+}
+
+#[update]
+async fn create_collection_from_template(
+    template: CollectionTemplate,
+    overrides: TemplateOverrides,
+) -> ClanopediaResult<CollectionId> {
+    activity::record(ic_cdk::caller(), "create_collection_from_template", None);
+    let config = templates::template_config(template, overrides)?;
+    create_collection_endpoint(config).await
+}
+
+#[update]
+async fn fork_collection(
+    source_collection_id: CollectionId,
+    new_config: ForkConfig,
+) -> ClanopediaResult<ForkResult> {
+    activity::record(ic_cdk::caller(), "fork_collection", Some(&source_collection_id));
+    fork::fork_collection(&source_collection_id, new_config, ic_cdk::caller()).await
+}
+
 #[update]
 async fn update_collection(
     collection_id: CollectionId,
     mut config: CollectionConfig,
 ) -> ClanopediaResult<()> {
+    activity::record(ic_cdk::caller(), "update_collection", Some(&collection_id));
     let caller = ic_cdk::caller();
     let collection = storage::get_collection(&collection_id)?;
 
-    if !collection.admins.contains(&caller) {
-        return Err(ClanopediaError::NotAuthorized);
+    auth::require_admin(&collection, caller)?;
+    trash::ensure_not_trashed(&collection)?;
+    admin::ensure_not_paused(&collection)?;
+    archive::ensure_not_archived(&collection)?;
+    validation::validate_collection_config(&mut config)?;
+    if config.encryption.is_some() != collection.encryption.is_some() {
+        return Err(ClanopediaError::InvalidOperation(
+            "Encryption can only be chosen when a collection is created".to_string(),
+        ));
     }
 
     // Convert string representations to Principal objects for validation
@@ -235,6 +478,37 @@ async fn update_collection(
     updated_collection.governance_model = config.governance_model;
     updated_collection.quorum_threshold = config.quorum_threshold;
     updated_collection.is_permissionless = config.is_permissionless;
+    governance::validate_proposal_bond(&config.proposal_bond, &updated_collection.governance_token).await?;
+    governance::validate_proposer_eligibility(
+        &config.proposer_eligibility,
+        &updated_collection.governance_token,
+        &updated_collection.sns_governance_canister,
+    )?;
+    updated_collection.proposal_bond = config.proposal_bond;
+    updated_collection.proposer_eligibility = config.proposer_eligibility;
+    updated_collection.embed_concurrency = config.embed_concurrency;
+    extractor::pipeline::validate_pipeline(&config.pipeline)?;
+    updated_collection.pipeline = config.pipeline;
+    extractor::keywords::validate_auto_tag(&config.auto_tag)?;
+    updated_collection.auto_tag = config.auto_tag;
+    summarize::validate_summarization(&config.summarization, collection.encryption.is_some())?;
+    updated_collection.summarization = config.summarization;
+    ocr::validate_ocr(&config.ocr, collection.encryption.is_some())?;
+    updated_collection.ocr = config.ocr;
+    attestations::validate_nft_minting(&config.nft_minting, collection.encryption.is_some())?;
+    updated_collection.nft_minting = config.nft_minting;
+    updated_collection.license = config.license;
+    updated_collection.vote_reminder_hours = config.vote_reminder_hours;
+    updated_collection.timelock_hours = config.timelock_hours;
+    custom_proposals::validate_call_policy(&config.call_policy)?;
+    custom_proposals::validate_custom_kinds(&config.custom_proposals, &config.call_policy)?;
+    updated_collection.custom_proposals = config.custom_proposals;
+    updated_collection.call_policy = config.call_policy;
+    updated_collection.review_policy = config.review_policy;
+    updated_collection.content_limits = config.content_limits;
+    updated_collection.retry_policy = config.retry_policy;
+    timelock::ensure_direct_update_allowed(&collection, &updated_collection)?;
+    proxy::update_collection_proxy(&mut updated_collection, &config.embedding_proxy, caller).await?;
     updated_collection.updated_at = time();
 
     storage::update_collection(&collection_id, &updated_collection)?;
@@ -242,233 +516,1161 @@ async fn update_collection(
 }
 
 #[update]
-async fn delete_collection_endpoint(collection_id: CollectionId) -> ClanopediaResult<()> {
-    let caller = ic_cdk::caller();
-    governance::delete_collection(&collection_id, caller).await
+fn add_proxy_allowlist_entry(url_prefix: String) -> ClanopediaResult<()> {
+    activity::record(ic_cdk::caller(), "add_proxy_allowlist_entry", None);
+    proxy::add_allowlist_entry(ic_cdk::caller(), url_prefix)
+}
+
+#[update]
+fn remove_proxy_allowlist_entry(url_prefix: String) -> ClanopediaResult<()> {
+    activity::record(ic_cdk::caller(), "remove_proxy_allowlist_entry", None);
+    proxy::remove_allowlist_entry(ic_cdk::caller(), &url_prefix)
+}
+
+#[query]
+fn get_proxy_allowlist() -> Vec<String> {
+    storage::list_proxy_allowlist()
+}
+
+#[update]
+fn add_call_allowlist_entry(
+    canister: Principal,
+    method: String,
+    max_arg_bytes: Option<u32>,
+    max_cycles: Option<u64>,
+) -> ClanopediaResult<()> {
+    activity::record(ic_cdk::caller(), "add_call_allowlist_entry", None);
+    custom_proposals::add_call_allowlist_entry(
+        ic_cdk::caller(),
+        canister,
+        method,
+        max_arg_bytes,
+        max_cycles,
+    )
+}
+
+#[update]
+fn remove_call_allowlist_entry(canister: Principal, method: String) -> ClanopediaResult<()> {
+    activity::record(ic_cdk::caller(), "remove_call_allowlist_entry", None);
+    custom_proposals::remove_call_allowlist_entry(ic_cdk::caller(), canister, &method)
+}
+
+#[query]
+fn get_call_allowlist() -> Vec<CallAllowlistEntry> {
+    storage::list_call_allowlist()
 }
 
 // ============================
-// DOCUMENT OPERATIONS
+// ENCRYPTED COLLECTIONS
 // ============================
 
 #[update]
-async fn get_document_endpoint(
+async fn get_encrypted_collection_key(
     collection_id: CollectionId,
-    document_id: DocumentId,
-) -> ClanopediaResult<Option<String>> {
-    let collection = storage::get_collection(&collection_id)?;
-    get_document_content_from_blueband(&collection.blueband_collection_id, &document_id)
-        .await
-        .map_err(ClanopediaError::BluebandError)
+    transport_public_key: Vec<u8>,
+    key_epoch: Option<u32>,
+) -> ClanopediaResult<EncryptedCollectionKey> {
+    activity::record(ic_cdk::caller(), "get_encrypted_collection_key", Some(&collection_id));
+    encryption::get_encrypted_collection_key(
+        &collection_id,
+        ic_cdk::caller(),
+        transport_public_key,
+        key_epoch,
+    )
+    .await
+}
+
+#[update]
+async fn get_collection_verification_key() -> ClanopediaResult<Vec<u8>> {
+    encryption::get_verification_key().await
 }
 
 // ============================
-// GOVERNANCE OPERATIONS
+// COLLECTION SECRETS
 // ============================
 
+#[update]
+async fn set_collection_secret(
+    collection_id: CollectionId,
+    name: String,
+    value: String,
+) -> ClanopediaResult<()> {
+    activity::record(ic_cdk::caller(), "set_collection_secret", Some(&collection_id));
+    secrets::set_secret(&collection_id, ic_cdk::caller(), name, value).await
+}
+
+#[update]
+fn delete_collection_secret(collection_id: CollectionId, name: String) -> ClanopediaResult<()> {
+    activity::record(ic_cdk::caller(), "delete_collection_secret", Some(&collection_id));
+    secrets::delete_secret(&collection_id, ic_cdk::caller(), &name)
+}
+
 #[query]
-fn get_proposals_endpoint(collection_id: String) -> ClanopediaResult<Vec<Proposal>> {
-    governance::get_proposals(&collection_id)
+fn list_collection_secrets(collection_id: CollectionId) -> ClanopediaResult<Vec<SecretInfo>> {
+    let collection_id = slugs::collection_id(&collection_id);
+    secrets::list_secrets(&collection_id, ic_cdk::caller())
 }
 
 #[update]
-async fn create_proposal(
-    collection_id: String,
-    proposal_type: ProposalType,
-    description: String,
-) -> ClanopediaResult<ProposalId> {
+async fn delete_collection_endpoint(collection_id: CollectionId) -> ClanopediaResult<()> {
+    activity::record(ic_cdk::caller(), "delete_collection_endpoint", Some(&collection_id));
     let caller = ic_cdk::caller();
-    governance::create_proposal(&collection_id, proposal_type, caller, description).await
+    governance::delete_collection(&collection_id, caller).await
 }
 
 #[update]
-async fn vote_on_proposal_endpoint(
-    collection_id: String,
-    proposal_id: String,
-    vote: Vote,
-) -> ClanopediaResult<()> {
-    governance::vote_on_proposal(&collection_id, &proposal_id, vote).await
+fn trash_document(collection_id: CollectionId, document_id: DocumentId) -> ClanopediaResult<()> {
+    activity::record(ic_cdk::caller(), "trash_document", Some(&collection_id));
+    trash::trash_document(&collection_id, &document_id, ic_cdk::caller())
 }
 
 #[update]
-async fn execute_proposal_endpoint(
-    collection_id: String,
-    proposal_id: String,
+fn restore_from_trash(
+    collection_id: CollectionId,
+    document_id: Option<DocumentId>,
 ) -> ClanopediaResult<()> {
-    governance::execute_proposal(&collection_id, &proposal_id).await
+    activity::record(ic_cdk::caller(), "restore_from_trash", Some(&collection_id));
+    trash::restore_from_trash(&collection_id, document_id.as_deref(), ic_cdk::caller())
 }
 
 #[query]
-fn get_proposal_status_endpoint(
-    collection_id: String,
-    proposal_id: String,
-) -> ClanopediaResult<ProposalStatus> {
-    governance::get_proposal_status(&collection_id, proposal_id)
+fn list_trash(collection_id: CollectionId) -> ClanopediaResult<Vec<TrashedDocument>> {
+    let collection_id = slugs::collection_id(&collection_id);
+    trash::list_trash(&collection_id)
 }
 
-#[query]
-fn can_execute_directly_endpoint(collection_id: String) -> ClanopediaResult<bool> {
-    governance::can_execute_directly(&collection_id)
+/// Move a rarely read document's content to the backup target and out of Blueband
+#[update]
+async fn archive_document(
+    collection_id: CollectionId,
+    document_id: DocumentId,
+) -> ClanopediaResult<ArchivedDocument> {
+    activity::record(ic_cdk::caller(), "archive_document", Some(&collection_id));
+    cold_storage::archive_document(&collection_id, &document_id, ic_cdk::caller()).await
 }
 
-// ============================
-// ADMIN OPERATIONS
-// ============================
-
+/// Propose replacing the collection's allow and deny lists of extraction sources; no policy
+/// lifts every restriction
 #[update]
-async fn create_admin_proposal(
-    collection_id: String,
-    new_admin: Principal,
+async fn propose_source_policy(
+    collection_id: CollectionId,
+    policy: Option<SourcePolicy>,
 ) -> ClanopediaResult<ProposalId> {
+    activity::record(ic_cdk::caller(), "propose_source_policy", Some(&collection_id));
     let caller = caller();
-    let proposal_type = ProposalType::AddAdmin { admin: new_admin };
-    governance::create_proposal(
-        &collection_id,
-        proposal_type,
-        caller,
-        "Add new admin".to_string(),
-    )
-    .await
+    source_policy::validate_set_source_policy(policy.as_ref())?;
+    let description = match &policy {
+        Some(policy) => format!(
+            "Restrict extraction sources: {} allowed, {} denied",
+            policy.allow.len(),
+            policy.deny.len()
+        ),
+        None => "Lift the extraction source policy".to_string(),
+    };
+    let proposal_type = ProposalType::SetSourcePolicy { policy };
+    governance::create_proposal(&collection_id, proposal_type, caller, description).await
 }
 
+#[query]
+fn get_source_policy(collection_id: CollectionId) -> ClanopediaResult<Option<SourcePolicy>> {
+    source_policy::get_source_policy(&slugs::collection_id(&collection_id))
+}
+
+/// Propose adding an archived document back to Blueband; it is embedded under a new ID
 #[update]
-async fn create_remove_admin_proposal(
-    collection_id: String,
-    admin_to_remove: Principal,
+async fn restore_document_from_archive(
+    collection_id: CollectionId,
+    document_id: DocumentId,
 ) -> ClanopediaResult<ProposalId> {
+    activity::record(ic_cdk::caller(), "restore_document_from_archive", Some(&collection_id));
     let caller = caller();
-    let proposal_type = ProposalType::RemoveAdmin {
-        admin: admin_to_remove,
-    };
-    governance::create_proposal(
-        &collection_id,
-        proposal_type,
-        caller,
-        "Remove admin".to_string(),
-    )
-    .await
+    let collection = storage::get_collection(&collection_id)?;
+    cold_storage::validate_restore(&collection, &document_id)?;
+    let description = format!("Restore archived document {}", document_id);
+    let proposal_type = ProposalType::RestoreArchivedDocument { document_id };
+    governance::create_proposal(&collection_id, proposal_type, caller, description).await
+}
+
+#[query]
+fn list_archived_documents(collection_id: CollectionId) -> ClanopediaResult<Vec<ArchivedDocument>> {
+    let collection_id = slugs::collection_id(&collection_id);
+    cold_storage::list_archived_documents(&collection_id)
+}
+
+// Timers are cleared on upgrade
+#[post_upgrade]
+fn post_upgrade() {
+    extractor::migrate_legacy_progress();
+    extractor::collect_stale_progress();
+    receipts::certify_head();
+    site::backfill();
+    vault::migrate_legacy_secrets();
+    proxy::seal_legacy_keys();
+    timers::schedule();
+}
+
+#[export_name = "canister_global_timer"]
+extern "C" fn canister_global_timer() {
+    ic_cdk::setup();
+    ic_cdk::spawn(timers::run());
 }
 
 // ============================
-//  EXTRACTOR OPERATIONS
+// DOCUMENT OPERATIONS
 // ============================
 
 #[update]
-async fn extract_from_file(
-    file_data: Vec<u8>,
-    filename: String,
-    collection_id: String,
-) -> ClanopediaResult<ExtractionResponse> {
-    let caller = ic_cdk::caller();
-
-    // Verify the caller is an admin of the collection
+async fn get_document_endpoint(
+    collection_id: CollectionId,
+    document_id: DocumentId,
+) -> ClanopediaResult<Option<String>> {
+    let collection_id = slugs::collection_id(&collection_id);
+    let document_id = slugs::document_id(&collection_id, &document_id);
     let collection = storage::get_collection(&collection_id)?;
-    if !collection.admins.contains(&caller) {
-        return Err(ClanopediaError::NotAuthorized);
+    if trash::is_document_trashed(&collection_id, &document_id) {
+        return Ok(None);
     }
+    let owner = crosspost::content_collection(&collection, &document_id)?;
+    let content = get_document_content_from_blueband(&owner.blueband_collection_id, &document_id)
+        .await
+        .map_err(ClanopediaError::BluebandError)?;
+    if content.is_some() {
+        views::count_view(&collection_id, &document_id, ic_cdk::caller());
+    }
+    Ok(content)
+}
 
-    ic_cdk::println!(
-        "File extraction request from {}: {} ({} bytes) -> {}",
-        caller,
-        filename,
-        file_data.len(),
-        collection_id
-    );
-
-    // Extract content
-    let documents = extractor::Extractor::extract_from_file(file_data, filename, collection_id)?;
+/// The document's content with its headings, code blocks and links, parsed once and cached
+#[update]
+async fn get_rendered_document(
+    collection_id: CollectionId,
+    document_id: DocumentId,
+) -> ClanopediaResult<Option<RenderedDocument>> {
+    let collection_id = slugs::collection_id(&collection_id);
+    let document_id = slugs::document_id(&collection_id, &document_id);
+    rendering::read_document(&collection_id, &document_id).await
+}
 
-    // File extraction is always complete (no pagination)
-    let extraction_info = ExtractionInfo::for_file_extraction(documents.len() as u32);
+#[update]
+async fn get_document_metadata_endpoint(
+    collection_id: CollectionId,
+    document_id: DocumentId,
+) -> ClanopediaResult<Option<DocumentView>> {
+    let collection_id = slugs::collection_id(&collection_id);
+    let document_id = slugs::document_id(&collection_id, &document_id);
+    let collection = storage::get_collection(&collection_id)?;
+    if trash::is_document_trashed(&collection_id, &document_id) {
+        return Ok(None);
+    }
+    let owner = crosspost::content_collection(&collection, &document_id)?;
+    let metadata = get_document_metadata(owner.blueband_collection_id.clone(), document_id.clone())
+        .await
+        .map_err(ClanopediaError::BluebandError)?;
+
+    Ok(metadata.map(|mut metadata| {
+        // A linked document shows the source collection's edits unless this one has its own,
+        // but a slug only names it in the collection it was set in
+        let own = storage::get_document_overlay(&collection_id, &document_id);
+        let slug = own.as_ref().and_then(|o| o.slug.clone());
+        let overlay = own.or_else(|| storage::get_document_overlay(&owner.id, &document_id));
+        if let Some(title) = overlay.as_ref().and_then(|o| o.title.clone()) {
+            metadata.title = title;
+        }
+        DocumentView {
+            metadata,
+            license: licensing::document_license(&collection, overlay.as_ref()),
+            tags: overlay.as_ref().and_then(|o| o.tags.clone()),
+            author: overlay.as_ref().and_then(|o| o.author.clone()),
+            summary: overlay.and_then(|o| o.summary),
+            slug,
+            reactions: reactions::document_reactions(&collection_id, &document_id),
+        }
+    }))
+}
 
-    Ok(ExtractionResponse {
-        documents,
-        extraction_info,
-    })
+/// A page of the document's chunks in reading order, to link search hits to exact passages
+#[update]
+async fn get_document_chunks(
+    collection_id: CollectionId,
+    document_id: DocumentId,
+    offset: Option<u32>,
+    limit: Option<u32>,
+) -> ClanopediaResult<DocumentChunkPage> {
+    let collection_id = slugs::collection_id(&collection_id);
+    let document_id = slugs::document_id(&collection_id, &document_id);
+    chunks::document_chunks(&collection_id, &document_id, offset, limit).await
 }
 
 #[update]
-async fn extract_from_url(
-    url: String,
-    collection_id: String,
-    api_key: Option<String>,
-) -> ClanopediaResult<ExtractionResponse> {
-    let caller = ic_cdk::caller();
+async fn search_collection(
+    collection_id: CollectionId,
+    query: String,
+    limit: Option<u32>,
+) -> ClanopediaResult<Vec<VectorMatch>> {
+    let collection_id = slugs::collection_id(&collection_id);
+    let collection = storage::get_collection(&collection_id)?;
+    trash::ensure_not_trashed(&collection)?;
+    admin::ensure_not_paused(&collection)?;
+    if encryption::is_encrypted(&collection) {
+        return Err(ClanopediaError::InvalidOperation(
+            "Semantic search is not available for encrypted collections".to_string(),
+        ));
+    }
+    let (expanded, applied) = glossary::expand_query(&collection_id, &query);
+    let mut matches = external::blueband::search_collection(
+        &collection.blueband_collection_id,
+        expanded.clone(),
+        limit,
+    )
+    .await
+    .map_err(ClanopediaError::BluebandError)?;
+    apply_overlays(&collection_id, &mut matches);
+    crosspost::merge_linked_matches(&collection, &expanded, limit, &mut matches).await;
+    highlight::apply(&expanded, &mut matches);
+    glossary::annotate(&mut matches, &applied);
+
+    stats::record(&collection_id, StatEvent::Search);
+    stats::record_search_query(&collection_id, &query, ic_cdk::caller(), matches.len());
+    Ok(matches)
+}
 
-    // Add detailed logging for debugging
-    ic_cdk::println!(
-        "URL extraction request - Caller: {}, Collection: {}, URL: {}",
-        caller,
-        collection_id,
-        url
-    );
+const DEFAULT_HYBRID_LIMIT: u32 = 10;
 
+/// Vector search merged with exact title/tag keyword hits, so identifiers
+/// like error codes are found even when embeddings miss them
+#[update]
+async fn search_collection_hybrid(
+    collection_id: CollectionId,
+    query: String,
+    limit: Option<u32>,
+) -> ClanopediaResult<Vec<HybridMatch>> {
+    let collection_id = slugs::collection_id(&collection_id);
     let collection = storage::get_collection(&collection_id)?;
+    trash::ensure_not_trashed(&collection)?;
+    admin::ensure_not_paused(&collection)?;
+    if encryption::is_encrypted(&collection) {
+        return Err(ClanopediaError::InvalidOperation(
+            "Semantic search is not available for encrypted collections".to_string(),
+        ));
+    }
 
-    // Log collection admins and caller for debugging
-    ic_cdk::println!(
-        "Collection admins: {:?}, Caller: {}",
-        collection.admins,
-        caller
+    let limit = limit.unwrap_or(DEFAULT_HYBRID_LIMIT);
+    let (expanded, applied) = glossary::expand_query(&collection_id, &query);
+    let mut vector_matches = external::blueband::search_collection(
+        &collection.blueband_collection_id,
+        expanded.clone(),
+        Some(limit),
+    )
+    .await
+    .map_err(ClanopediaError::BluebandError)?;
+    apply_overlays(&collection_id, &mut vector_matches);
+    crosspost::merge_linked_matches(&collection, &expanded, Some(limit), &mut vector_matches)
+        .await;
+    highlight::apply(&expanded, &mut vector_matches);
+    glossary::annotate(&mut vector_matches, &applied);
+
+    let keyword_matches = keyword_index::keyword_matches(&collection_id, &expanded);
+    let mut results = keyword_index::fuse_results(
+        &collection_id,
+        vector_matches,
+        keyword_matches,
+        limit as usize,
     );
+    glossary::annotate_hybrid(&mut results, &applied);
 
-    if !collection.admins.contains(&caller) {
-        ic_cdk::println!(
-            "Authorization failed - Caller {} not in admins list: {:?}",
-            caller,
-            collection.admins
-        );
-        return Err(ClanopediaError::NotAuthorized);
+    stats::record(&collection_id, StatEvent::Search);
+    stats::record_search_query(&collection_id, &query, ic_cdk::caller(), results.len());
+    Ok(results)
+}
+
+// Drop trashed documents and apply locally edited titles and stored summaries
+fn apply_overlays(collection_id: &str, matches: &mut Vec<VectorMatch>) {
+    matches.retain(|m| !trash::is_document_trashed(collection_id, &m.document_id));
+    for m in matches.iter_mut() {
+        if let Some(overlay) = storage::get_document_overlay(collection_id, &m.document_id) {
+            if let Some(title) = overlay.title {
+                m.document_title = Some(title);
+            }
+            m.summary = overlay.summary;
+        }
     }
+}
 
-    ic_cdk::println!(
-        "Authorization successful - proceeding with extraction for {}",
-        caller
-    );
+#[query]
+fn get_popular_queries(
+    collection_id: CollectionId,
+    limit: Option<u32>,
+) -> ClanopediaResult<Vec<QueryStat>> {
+    let collection_id = slugs::collection_id(&collection_id);
+    stats::get_popular_queries(&collection_id, ic_cdk::caller(), limit)
+}
 
-    let documents =
-        extractor::Extractor::extract_from_url(url.clone(), collection_id.clone(), api_key).await?;
+#[query]
+fn get_zero_result_queries(
+    collection_id: CollectionId,
+    limit: Option<u32>,
+) -> ClanopediaResult<Vec<QueryStat>> {
+    let collection_id = slugs::collection_id(&collection_id);
+    stats::get_zero_result_queries(&collection_id, ic_cdk::caller(), limit)
+}
 
-    let progress = extractor::Extractor::get_progress(&collection_id, &url);
+#[query]
+fn get_collection_analytics(
+    collection_id: CollectionId,
+    range: AnalyticsRange,
+) -> ClanopediaResult<CollectionAnalytics> {
+    let collection_id = slugs::collection_id(&collection_id);
+    stats::get_collection_analytics(&collection_id, range)
+}
 
-    let extraction_info = if let Some(progress) = progress {
-        ExtractionInfo::from_progress(&progress)
-    } else {
-        ExtractionInfo::for_file_extraction(documents.len() as u32)
-    };
+/// One-call bootstrap for collection pages. A composite query so it can read Blueband metrics
+/// without going through consensus.
+#[query(composite = true)]
+async fn get_collection_dashboard(
+    collection_id: CollectionId,
+) -> ClanopediaResult<CollectionDashboard> {
+    let collection_id = slugs::collection_id(&collection_id);
+    dashboard::get_collection_dashboard(&collection_id).await
+}
 
-    Ok(ExtractionResponse {
-        documents,
-        extraction_info,
-    })
+// ============================
+// GOVERNANCE OPERATIONS
+// ============================
+
+#[query]
+fn get_proposals_endpoint(collection_id: String) -> ClanopediaResult<Vec<Proposal>> {
+    let collection_id = slugs::collection_id(&collection_id);
+    governance::get_proposals(&collection_id)
+}
+
+/// The collection's proposals of the given types, creator, status and creation time, newest
+/// first, e.g. every `AddAdmin` and `RemoveAdmin` created in a quarter
+#[query]
+fn search_proposals(
+    collection_id: CollectionId,
+    filter: ProposalFilter,
+) -> ClanopediaResult<ProposalSearchPage> {
+    let collection_id = slugs::collection_id(&collection_id);
+    proposal_search::search_proposals(&collection_id, filter)
 }
 
 #[update]
-async fn add_extracted_documents(
+async fn create_proposal(
     collection_id: String,
-    documents: Vec<AddDocumentRequest>,
-) -> ClanopediaResult<AddDocumentsResult> {
+    proposal_type: ProposalType,
+    description: String,
+) -> ClanopediaResult<ProposalId> {
+    activity::record(ic_cdk::caller(), "create_proposal", Some(&collection_id));
+    let caller = ic_cdk::caller();
+    governance::create_proposal(&collection_id, proposal_type, caller, description).await
+}
+
+/// Like `create_proposal`, but execution waits until every proposal in `depends_on` has
+/// been executed, e.g. adding an admin before raising the threshold
+#[update]
+async fn create_proposal_with_dependencies(
+    collection_id: String,
+    proposal_type: ProposalType,
+    description: String,
+    depends_on: Vec<ProposalId>,
+) -> ClanopediaResult<ProposalId> {
+    activity::record(ic_cdk::caller(), "create_proposal_with_dependencies", Some(&collection_id));
+    let caller = ic_cdk::caller();
+    governance::create_proposal_with_dependencies(
+        &collection_id,
+        proposal_type,
+        caller,
+        description,
+        depends_on,
+    )
+    .await
+}
+
+/// Like `create_proposal`, but votes are sealed: voters commit a hash of their vote with
+/// `commit_vote` while voting is open and reveal it with `reveal_vote` afterwards
+#[update]
+async fn create_sealed_proposal(
+    collection_id: String,
+    proposal_type: ProposalType,
+    description: String,
+) -> ClanopediaResult<ProposalId> {
+    activity::record(ic_cdk::caller(), "create_sealed_proposal", Some(&collection_id));
+    let caller = ic_cdk::caller();
+    governance::create_sealed_proposal(&collection_id, proposal_type, caller, description).await
+}
+
+#[update]
+async fn vote_on_proposal_endpoint(
+    collection_id: String,
+    proposal_id: String,
+    vote: Vote,
+) -> ClanopediaResult<VoteReceipt> {
+    activity::record(ic_cdk::caller(), "vote_on_proposal_endpoint", Some(&collection_id));
+    governance::vote_on_proposal(&collection_id, &proposal_id, vote).await
+}
+
+/// Commit to a vote on a sealed proposal: sha256 of the caller's principal bytes, then
+/// "Yes", "No" or "Abstain", then a secret salt of at least 16 bytes. Committing again
+/// replaces the commitment until voting closes.
+#[update]
+async fn commit_vote(
+    collection_id: String,
+    proposal_id: String,
+    commitment: Vec<u8>,
+) -> ClanopediaResult<()> {
+    activity::record(ic_cdk::caller(), "commit_vote", Some(&collection_id));
+    sealed_votes::commit_vote(&collection_id, &proposal_id, ic_cdk::caller(), commitment).await
+}
+
+/// Reveal a committed vote once voting on the sealed proposal has closed
+#[update]
+async fn reveal_vote(
+    collection_id: String,
+    proposal_id: String,
+    vote: Vote,
+    salt: Vec<u8>,
+) -> ClanopediaResult<VoteReceipt> {
+    activity::record(ic_cdk::caller(), "reveal_vote", Some(&collection_id));
     let caller = ic_cdk::caller();
+    sealed_votes::reveal_vote(&collection_id, &proposal_id, caller, vote, salt).await
+}
+
+/// Retry a proposal bond refund or forfeit whose transfer failed; the timer also retries it
+#[update]
+async fn settle_bond(collection_id: String, proposal_id: String) -> ClanopediaResult<()> {
+    activity::record(ic_cdk::caller(), "settle_bond", Some(&collection_id));
+    bonds::settle_bond(&collection_id, &proposal_id, ic_cdk::caller()).await
+}
+
+/// Bond transfers in the collection that failed and are waiting to be retried
+#[query]
+fn get_unsettled_bonds(collection_id: String) -> Vec<UnsettledBond> {
+    bonds::list_unsettled(&collection_id)
+}
+
+/// Every recorded attempt to execute the proposal, with the calls each made
+#[query]
+fn get_execution_receipt(
+    collection_id: CollectionId,
+    proposal_id: ProposalId,
+) -> ClanopediaResult<ExecutionReceipt> {
+    let collection_id = slugs::collection_id(&collection_id);
+    execution_receipts::get_execution_receipt(&collection_id, &proposal_id)
+}
+
+/// A token-based proposal's yes, no and remaining weight against the quorum, and whether
+/// the outcome is already decided
+#[update]
+async fn project_vote_outcome(
+    collection_id: CollectionId,
+    proposal_id: ProposalId,
+) -> ClanopediaResult<VoteProjection> {
+    let collection_id = slugs::collection_id(&collection_id);
+    vote_projection::project_vote_outcome(&collection_id, &proposal_id).await
+}
+
+/// A stored vote receipt with the chain links and certificate needed to verify it
+#[query]
+fn get_vote_receipt_proof(sequence: u64) -> ClanopediaResult<VoteReceiptProof> {
+    receipts::get_receipt_proof(sequence)
+}
+
+#[query]
+fn list_vote_receipt_hashes(after_sequence: u64, limit: u32) -> Vec<String> {
+    receipts::list_receipt_hashes(after_sequence, limit)
+}
+
+#[update]
+async fn execute_proposal_endpoint(
+    collection_id: String,
+    proposal_id: String,
+) -> ClanopediaResult<()> {
+    activity::record(ic_cdk::caller(), "execute_proposal_endpoint", Some(&collection_id));
+    governance::execute_proposal(&collection_id, &proposal_id).await
+}
+
+#[update]
+async fn retry_failed_items(collection_id: String, proposal_id: String) -> ClanopediaResult<()> {
+    activity::record(ic_cdk::caller(), "retry_failed_items", Some(&collection_id));
+    governance::retry_failed_items(&collection_id, &proposal_id).await
+}
+
+/// Replace the reviewers assigned to an open proposal; admins only
+#[update]
+fn assign_reviewers(
+    collection_id: String,
+    proposal_id: String,
+    reviewers: Vec<Principal>,
+) -> ClanopediaResult<()> {
+    activity::record(ic_cdk::caller(), "assign_reviewers", Some(&collection_id));
+    review::assign_reviewers(ic_cdk::caller(), &collection_id, &proposal_id, reviewers)
+}
+
+/// Approve, request changes or comment, with a note; verdicts from assigned reviewers only
+#[update]
+fn submit_review(
+    collection_id: String,
+    proposal_id: String,
+    decision: ReviewDecision,
+    note: String,
+) -> ClanopediaResult<ReviewStatus> {
+    activity::record(ic_cdk::caller(), "submit_review", Some(&collection_id));
+    review::submit_review(ic_cdk::caller(), &collection_id, &proposal_id, decision, note)
+}
+
+/// Attach an evidence file to an open proposal; its creator only
+#[update]
+fn attach_to_proposal(
+    collection_id: String,
+    proposal_id: String,
+    name: String,
+    content_type: String,
+    data: Vec<u8>,
+) -> ClanopediaResult<ProposalAttachment> {
+    activity::record(ic_cdk::caller(), "attach_to_proposal", Some(&collection_id));
+    attachments::attach(ic_cdk::caller(), &collection_id, &proposal_id, name, content_type, data)
+}
+
+#[query]
+fn list_proposal_attachments(
+    collection_id: String,
+    proposal_id: String,
+) -> ClanopediaResult<Vec<ProposalAttachment>> {
+    let collection_id = slugs::collection_id(&collection_id);
+    attachments::list(&collection_id, &proposal_id)
+}
+
+#[query]
+fn get_proposal_attachment(
+    collection_id: String,
+    proposal_id: String,
+    index: u32,
+    chunk_index: u32,
+) -> ClanopediaResult<AttachmentChunk> {
+    let collection_id = slugs::collection_id(&collection_id);
+    attachments::get_chunk(&collection_id, &proposal_id, index, chunk_index)
+}
+
+#[query]
+fn get_proposal_status_endpoint(
+    collection_id: String,
+    proposal_id: String,
+) -> ClanopediaResult<ProposalStatus> {
+    let collection_id = slugs::collection_id(&collection_id);
+    governance::get_proposal_status(&collection_id, proposal_id)
+}
+
+#[query]
+fn can_execute_directly_endpoint(collection_id: String) -> ClanopediaResult<bool> {
+    let collection_id = slugs::collection_id(&collection_id);
+    governance::can_execute_directly(&collection_id)
+}
+
+// ============================
+// ADMIN OPERATIONS
+// ============================
+
+#[update]
+async fn create_admin_proposal(
+    collection_id: String,
+    new_admin: Principal,
+) -> ClanopediaResult<ProposalId> {
+    activity::record(ic_cdk::caller(), "create_admin_proposal", Some(&collection_id));
+    let caller = caller();
+    let proposal_type = ProposalType::AddAdmin { admin: new_admin };
+    governance::create_proposal(
+        &collection_id,
+        proposal_type,
+        caller,
+        "Add new admin".to_string(),
+    )
+    .await
+}
+
+#[update]
+async fn create_remove_admin_proposal(
+    collection_id: String,
+    admin_to_remove: Principal,
+) -> ClanopediaResult<ProposalId> {
+    activity::record(ic_cdk::caller(), "create_remove_admin_proposal", Some(&collection_id));
+    let caller = caller();
+    let proposal_type = ProposalType::RemoveAdmin {
+        admin: admin_to_remove,
+    };
+    governance::create_proposal(
+        &collection_id,
+        proposal_type,
+        caller,
+        "Remove admin".to_string(),
+    )
+    .await
+}
 
-    // Verify the caller is an admin of the collection
+/// Propose showing a document of `source_collection` in this collection. Its content stays
+/// in the source's Blueband index and is not embedded again.
+#[update]
+async fn link_document(
+    collection_id: String,
+    source_collection: String,
+    document_id: DocumentId,
+) -> ClanopediaResult<ProposalId> {
+    activity::record(ic_cdk::caller(), "link_document", Some(&collection_id));
+    let caller = caller();
     let collection = storage::get_collection(&collection_id)?;
-    if !collection.admins.contains(&caller) {
-        return Err(ClanopediaError::NotAuthorized);
-    }
+    crosspost::validate_link(&collection, &source_collection, &document_id)?;
+    let description = format!(
+        "Link document {} from collection {}",
+        document_id, source_collection
+    );
+    let proposal_type = ProposalType::LinkDocument {
+        source_collection,
+        document_id,
+    };
+    governance::create_proposal(&collection_id, proposal_type, caller, description).await
+}
 
-    if documents.is_empty() {
-        return Err(ClanopediaError::InvalidInput(
-            "No documents to add".to_string(),
-        ));
-    }
+/// Propose cancelling an approved proposal while its timelock holds it
+#[update]
+async fn veto_proposal(
+    collection_id: String,
+    proposal_id: ProposalId,
+) -> ClanopediaResult<ProposalId> {
+    activity::record(ic_cdk::caller(), "veto_proposal", Some(&collection_id));
+    let caller = caller();
+    timelock::validate_veto(&collection_id, &proposal_id)?;
+    let description = format!("Veto proposal {}", proposal_id);
+    let proposal_type = ProposalType::VetoProposal { proposal_id };
+    governance::create_proposal(&collection_id, proposal_type, caller, description).await
+}
+
+// ============================
+//  EXTRACTOR OPERATIONS
+// ============================
+
+#[update]
+async fn extract_from_file(
+    file_data: Vec<u8>,
+    filename: String,
+    collection_id: String,
+) -> ClanopediaResult<ExtractionResponse> {
+    activity::record(ic_cdk::caller(), "extract_from_file", Some(&collection_id));
+    let caller = ic_cdk::caller();
+
+    let collection = storage::get_collection(&collection_id)?;
+    auth::authorize(&collection, caller, Capability::Extract)?;
+    archive::ensure_not_archived(&collection)?;
 
     ic_cdk::println!(
-        "Adding {} extracted documents to collection {}",
-        documents.len(),
+        "File extraction request from {}: {} ({} bytes) -> {}",
+        caller,
+        filename,
+        file_data.len(),
         collection_id
     );
 
-    let total_docs = documents.len();
+    // Archives report each file; image-only PDFs fall back to the collection's OCR proxy
+    let (documents, ocr, files) = if FileType::from_file(&filename, &file_data) == FileType::Zip {
+        let (documents, files) = extractor::archive::extract_archive(&file_data, &collection_id)?;
+        (documents, None, files)
+    } else {
+        let ocr_input = collection.ocr.is_some().then(|| file_data.clone());
+        match extractor::Extractor::extract_from_file(file_data, filename.clone(), collection_id) {
+            Ok(documents) => (documents, None, Vec::new()),
+            Err(e) => {
+                let data = ocr_input.unwrap_or_default();
+                match ocr::ocr_fallback(&collection, &data, &filename).await {
+                    Some(result) => {
+                        let (documents, outcome) = result?;
+                        (documents, Some(outcome), Vec::new())
+                    }
+                    None => return Err(e),
+                }
+            }
+        }
+    };
+    let (mut documents, pipeline, tag_suggestions) =
+        run_collection_pipeline(&collection, documents);
+    // Fail now rather than when the documents are added
+    content_limits::check_documents(&collection, &documents)?;
+    let summarization = summarize::summarize_documents(&collection, &mut documents).await;
+
+    // File extraction is always complete (no pagination)
+    let extraction_info = ExtractionInfo::for_file_extraction(documents.len() as u32);
+
+    Ok(ExtractionResponse {
+        documents,
+        extraction_info,
+        pipeline,
+        tag_suggestions,
+        summarization,
+        ocr,
+        cache: Vec::new(),
+        record_errors: Vec::new(),
+        files,
+    })
+}
+
+/// Extract a JSON or JSONL knowledge dump, one document per record. Records that fail the
+/// mapping are listed in `record_errors` instead of failing the whole file.
+#[update]
+async fn extract_from_records(
+    file_data: Vec<u8>,
+    filename: String,
+    collection_id: String,
+    mapping: RecordFieldMapping,
+) -> ClanopediaResult<ExtractionResponse> {
+    activity::record(ic_cdk::caller(), "extract_from_records", Some(&collection_id));
+    let caller = ic_cdk::caller();
+
+    let collection = storage::get_collection(&collection_id)?;
+    auth::authorize(&collection, caller, Capability::Extract)?;
+    archive::ensure_not_archived(&collection)?;
+
+    ic_cdk::println!(
+        "Record extraction request from {}: {} ({} bytes) -> {}",
+        caller,
+        filename,
+        file_data.len(),
+        collection_id
+    );
+
+    let (documents, record_errors) =
+        extractor::records::extract_records(&file_data, &filename, &collection_id, &mapping)?;
+    let (mut documents, pipeline, tag_suggestions) =
+        run_collection_pipeline(&collection, documents);
+    // Fail now rather than when the documents are added
+    content_limits::check_documents(&collection, &documents)?;
+    let summarization = summarize::summarize_documents(&collection, &mut documents).await;
+
+    let extraction_info = ExtractionInfo::for_file_extraction(documents.len() as u32);
+
+    Ok(ExtractionResponse {
+        documents,
+        extraction_info,
+        pipeline,
+        tag_suggestions,
+        summarization,
+        ocr: None,
+        cache: Vec::new(),
+        record_errors,
+        files: Vec::new(),
+    })
+}
+
+const PREVIEW_EXCERPT_CHARS: usize = 500;
+const PREVIEW_MAX_FILE_BYTES: usize = 2 * 1024 * 1024;
+const PREVIEW_CALLS_PER_WINDOW: usize = 10;
+const PREVIEW_WINDOW_NANOS: u64 = 60 * 60 * 1_000_000_000; // 1 hour
+
+// Runs the extractor without persisting anything. Open to any signed-in principal, so it is
+// rate limited; URL previews spend the collection's outcall budget and may use its stored
+// API keys, so only admins and extract operators may run them.
+#[update]
+async fn preview_extraction(
+    collection_id: CollectionId,
+    source: ExtractionSource,
+) -> ClanopediaResult<ExtractionPreviewResult> {
+    let caller = ic_cdk::caller();
+    if caller == Principal::anonymous() {
+        return Err(ClanopediaError::NotAuthorized);
+    }
+    rate_limit::check_rate_limit(
+        "preview_extraction",
+        caller,
+        PREVIEW_CALLS_PER_WINDOW,
+        PREVIEW_WINDOW_NANOS,
+    )?;
+
+    let collection = storage::get_collection(&collection_id)?;
+
+    let (documents, youtube_cost) = match source {
+        ExtractionSource::File { data, filename } => {
+            if data.len() > PREVIEW_MAX_FILE_BYTES {
+                return Err(ClanopediaError::InvalidInput(format!(
+                    "Preview files are limited to {} bytes",
+                    PREVIEW_MAX_FILE_BYTES
+                )));
+            }
+            let documents =
+                extractor::Extractor::extract_from_file(data, filename, collection_id)?;
+            (documents, None)
+        }
+        ExtractionSource::Url {
+            url,
+            api_key_secret,
+        } => {
+            auth::authorize(&collection, caller, Capability::Extract)?;
+            let api_key = secrets::resolve_secret(&collection_id, api_key_secret.as_deref())?;
+            extractor::url_extractor::preview_url_content_with_cost(&url, &collection_id, api_key)
+                .await?
+        }
+    };
+
+    let (documents, _, _) = run_collection_pipeline(&collection, documents);
+    Ok(ExtractionPreviewResult {
+        documents: documents
+            .iter()
+            .map(|document| ExtractionPreview::from_document(document, PREVIEW_EXCERPT_CHARS))
+            .collect(),
+        youtube_cost,
+    })
+}
+
+/// What re-ingesting `url` would add, change and remove in the collection, without writing
+/// anything. Limited like previews, and to admins and extract operators.
+#[update]
+async fn diff_extraction(
+    collection_id: CollectionId,
+    url: String,
+    api_key_secret: Option<String>,
+) -> ClanopediaResult<ExtractionDiff> {
+    let caller = ic_cdk::caller();
+    let collection = storage::get_collection(&collection_id)?;
+    auth::authorize(&collection, caller, Capability::Extract)?;
+    trash::ensure_not_trashed(&collection)?;
+    // Stored checksums are of ciphertext the canister cannot reproduce
+    if encryption::is_encrypted(&collection) {
+        return Err(ClanopediaError::InvalidOperation(
+            "Encrypted collections cannot be diffed".to_string(),
+        ));
+    }
+    rate_limit::check_rate_limit(
+        "diff_extraction",
+        caller,
+        PREVIEW_CALLS_PER_WINDOW,
+        PREVIEW_WINDOW_NANOS,
+    )?;
+
+    let api_key = secrets::resolve_secret(&collection_id, api_key_secret.as_deref())?;
+    let documents =
+        extractor::url_extractor::preview_url_content(&url, &collection_id, api_key).await?;
+    let (documents, _, _) = run_collection_pipeline(&collection, documents);
+    Ok(extractor::diff::diff_documents(&collection_id, &documents))
+}
+
+/// Re-ingest `url` and stage its new and changed documents, each changed one as a
+/// replacement for the document it updates, under a single embed proposal
+#[update]
+async fn resync_extraction(
+    collection_id: CollectionId,
+    url: String,
+    api_key_secret: Option<String>,
+) -> ClanopediaResult<ResyncResult> {
+    activity::record(ic_cdk::caller(), "resync_extraction", Some(&collection_id));
+    let caller = ic_cdk::caller();
+    let collection = storage::get_collection(&collection_id)?;
+    auth::authorize(&collection, caller, Capability::Extract)?;
+    auth::authorize(&collection, caller, Capability::AddDocuments)?;
+    trash::ensure_not_trashed(&collection)?;
+    admin::ensure_not_paused(&collection)?;
+    archive::ensure_not_archived(&collection)?;
+    if encryption::is_encrypted(&collection) {
+        return Err(ClanopediaError::InvalidOperation(
+            "Encrypted collections cannot be re-synced".to_string(),
+        ));
+    }
+    source_policy::check_url(&collection, &url)?;
+    rate_limit::check_rate_limit(
+        "resync_extraction",
+        caller,
+        PREVIEW_CALLS_PER_WINDOW,
+        PREVIEW_WINDOW_NANOS,
+    )?;
+
+    let api_key = secrets::resolve_secret(&collection_id, api_key_secret.as_deref())?;
+    let documents =
+        extractor::url_extractor::preview_url_content(&url, &collection_id, api_key).await?;
+    let (documents, _, _) = run_collection_pipeline(&collection, documents);
+    let diff = extractor::diff::diff_documents(&collection_id, &documents);
+
+    let (mut staged, replaces): (Vec<_>, Vec<_>) =
+        resync::staged_documents(&diff, documents).into_iter().unzip();
+    if staged.is_empty() {
+        return Ok(ResyncResult {
+            diff,
+            replacements: Vec::new(),
+            proposal_id: None,
+        });
+    }
+    validation::validate_documents(&mut staged)?;
+    licensing::check_documents(&collection, &staged)?;
+    content_limits::check_documents(&collection, &staged)?;
+    ensure_document_capacity(staged.len() as u64)?;
+
+    let document_ids = stage_documents(&collection, staged).await?;
+    stats::record(&collection_id, StatEvent::DocumentsAdded(document_ids.len() as u64));
+    let replacements: Vec<DocumentReplacement> = document_ids
+        .into_iter()
+        .zip(replaces)
+        .map(|(document_id, replaces)| DocumentReplacement {
+            document_id,
+            replaces,
+        })
+        .collect();
+
+    let proposal_type = ProposalType::ResyncDocuments {
+        replacements: replacements.clone(),
+    };
+    let description = resync::describe(&url, &diff);
+    let proposal_id =
+        governance::create_proposal(&collection_id, proposal_type, caller, description).await?;
+
+    Ok(ResyncResult {
+        diff,
+        replacements,
+        proposal_id: Some(proposal_id),
+    })
+}
+
+#[update]
+async fn extract_from_url(
+    url: String,
+    collection_id: String,
+    api_key_secret: Option<String>,
+) -> ClanopediaResult<ExtractionResponse> {
+    activity::record(ic_cdk::caller(), "extract_from_url", Some(&collection_id));
+    let caller = ic_cdk::caller();
+
+    // Add detailed logging for debugging
+    ic_cdk::println!(
+        "URL extraction request - Caller: {}, Collection: {}, URL: {}",
+        caller,
+        collection_id,
+        url
+    );
+
+    let collection = storage::get_collection(&collection_id)?;
+
+    // Log collection admins and caller for debugging
+    ic_cdk::println!(
+        "Collection admins: {:?}, Caller: {}",
+        collection.admins,
+        caller
+    );
+
+    if !auth::has_capability(&collection, caller, Capability::Extract) {
+        ic_cdk::println!(
+            "Authorization failed - Caller {} is neither an admin nor an extract operator",
+            caller
+        );
+        return Err(ClanopediaError::NotAuthorized);
+    }
+    archive::ensure_not_archived(&collection)?;
+    source_policy::check_url(&collection, &url)?;
+
+    ic_cdk::println!(
+        "Authorization successful - proceeding with extraction for {}",
+        caller
+    );
+
+    storage::ensure_capacity(StorageMap::ExtractionProgress, 1)?;
+    let api_key = secrets::resolve_secret(&collection_id, api_key_secret.as_deref())?;
+    let started_at = ic_cdk::api::time();
+    let documents =
+        extractor::Extractor::extract_from_url(url.clone(), collection_id.clone(), api_key).await?;
+
+    let progress = extractor::Extractor::get_progress(&collection_id, &url);
+
+    // Progress counts source items, so it is read before the pipeline reshapes documents
+    let extraction_info = if let Some(progress) = progress {
+        ExtractionInfo::from_progress(&progress)
+    } else {
+        ExtractionInfo::for_file_extraction(documents.len() as u32)
+    };
+    let (mut documents, pipeline, tag_suggestions) =
+        run_collection_pipeline(&collection, documents);
+    // Fail now rather than when the documents are added
+    content_limits::check_documents(&collection, &documents)?;
+    let summarization = summarize::summarize_documents(&collection, &mut documents).await;
+
+    Ok(ExtractionResponse {
+        documents,
+        extraction_info,
+        pipeline,
+        tag_suggestions,
+        summarization,
+        ocr: None,
+        cache: extractor::fetch_cache::provenance(
+            &extractor::url_extractor::repository_fetch_urls(&url),
+            started_at,
+        ),
+        record_errors: Vec::new(),
+        files: Vec::new(),
+    })
+}
+
+/// Queue a URL extraction to run in the background, one batch per timer slice
+#[update]
+fn enqueue_extraction(collection_id: String, source: ExtractionSource) -> ClanopediaResult<u64> {
+    activity::record(ic_cdk::caller(), "enqueue_extraction", Some(&collection_id));
+    extractor::jobs::enqueue(ic_cdk::caller(), collection_id, source)
+}
+
+/// Queue documents to be added in the background, a few per timer slice, instead of in one
+/// call; the embed proposal is raised once all are processed
+#[update]
+fn enqueue_document_addition(
+    collection_id: String,
+    documents: Vec<AddDocumentRequest>,
+) -> ClanopediaResult<u64> {
+    activity::record(ic_cdk::caller(), "enqueue_document_addition", Some(&collection_id));
+    extractor::jobs::enqueue_addition(ic_cdk::caller(), collection_id, documents)
+}
+
+#[query]
+fn get_job_status(job_id: u64) -> ClanopediaResult<ExtractionJobStatus> {
+    extractor::jobs::get_status(ic_cdk::caller(), job_id)
+}
+
+/// Documents added so far by a document addition job, the one in flight, and any errors
+#[query]
+fn get_job_progress(job_id: u64) -> ClanopediaResult<JobProgress> {
+    extractor::jobs::get_progress(ic_cdk::caller(), job_id)
+}
+
+#[query]
+fn get_job_documents(
+    job_id: u64,
+    offset: u32,
+    limit: u32,
+) -> ClanopediaResult<Vec<AddDocumentRequest>> {
+    extractor::jobs::get_documents(ic_cdk::caller(), job_id, offset, limit)
+}
+
+#[update]
+fn cancel_extraction_job(job_id: u64) -> ClanopediaResult<()> {
+    activity::record(ic_cdk::caller(), "cancel_extraction_job", None);
+    extractor::jobs::cancel(ic_cdk::caller(), job_id)
+}
+
+#[update]
+fn clear_extraction_job(job_id: u64) -> ClanopediaResult<()> {
+    activity::record(ic_cdk::caller(), "clear_extraction_job", None);
+    extractor::jobs::clear(ic_cdk::caller(), job_id)
+}
+
+// Run the collection's pipeline, then suggest tags (applying them if the collection opted in)
+fn run_collection_pipeline(
+    collection: &Collection,
+    documents: Vec<AddDocumentRequest>,
+) -> (
+    Vec<AddDocumentRequest>,
+    Vec<DocumentPipelineReport>,
+    Vec<DocumentTagSuggestions>,
+) {
+    let steps = collection.pipeline.as_deref().unwrap_or(&[]);
+    let (mut documents, pipeline) = extractor::pipeline::run_pipeline(steps, documents);
+    let suggestions = extractor::keywords::suggest_tags(&mut documents, &collection.auto_tag);
+    (documents, pipeline, suggestions)
+}
+
+// Add documents to the collection's Blueband index, unembedded, recording what Clanopedia
+// keeps alongside each; embedding them is left to a proposal
+async fn stage_documents(
+    collection: &Collection,
+    documents: Vec<AddDocumentRequest>,
+) -> ClanopediaResult<Vec<DocumentId>> {
+    let total_docs = documents.len();
     let mut document_ids = Vec::new();
     let mut processed_count = 0;
 
@@ -477,6 +1679,15 @@ async fn add_extracted_documents(
         let title = doc_request.title.clone();
         ic_cdk::println!("Adding document: {}", title);
 
+        // Earlier documents of the batch count toward the total
+        content_limits::check_documents(collection, std::slice::from_ref(&doc_request))?;
+        let content = doc_request.content.clone();
+        let outline = doc_request.outline.clone();
+        let summary = doc_request.summary.clone();
+        let license = doc_request.license.clone();
+        let provenance = doc_request.provenance.clone();
+        let tags = doc_request.tags.clone().unwrap_or_default();
+
         // Convert AddDocumentRequest to DocumentRequest
         let document_request = DocumentRequest {
             title: doc_request.title,
@@ -487,227 +1698,1133 @@ async fn add_extracted_documents(
             tags: doc_request.tags,
         };
 
-        let metadata =
-            add_document_to_blueband(&collection.blueband_collection_id, document_request)
-                .await
-                .map_err(|e| {
-                    ic_cdk::println!("Error adding document {}: {}", title, e);
-                    ClanopediaError::BluebandError(e)
-                })?;
+        let metadata =
+            add_document_to_blueband(&collection.blueband_collection_id, document_request)
+                .await
+                .map_err(|e| {
+                    ic_cdk::println!("Error adding document {}: {}", title, e);
+                    ClanopediaError::BluebandError(e)
+                })?;
+        integrity::check_added_document(collection, &content, &metadata).await?;
+
+        record_document_structure(
+            collection,
+            &metadata.id,
+            &title,
+            &content,
+            &tags,
+            outline,
+            summary,
+            provenance,
+        );
+        licensing::record_document_license(&collection.id, &metadata.id, license);
+        document_ids.push(metadata.id.clone());
+        processed_count += 1;
+        ic_cdk::println!(
+            "Successfully added document: {} ({}/{})",
+            metadata.id,
+            processed_count,
+            total_docs
+        );
+    }
+
+    Ok(document_ids)
+}
+
+#[update]
+async fn add_extracted_documents(
+    collection_id: String,
+    mut documents: Vec<AddDocumentRequest>,
+) -> ClanopediaResult<AddDocumentsResult> {
+    activity::record(ic_cdk::caller(), "add_extracted_documents", Some(&collection_id));
+    let caller = ic_cdk::caller();
+
+    let collection = storage::get_collection(&collection_id)?;
+    auth::authorize(&collection, caller, Capability::AddDocuments)?;
+
+    if documents.is_empty() {
+        return Err(ClanopediaError::InvalidInput(
+            "No documents to add".to_string(),
+        ));
+    }
+    trash::ensure_not_trashed(&collection)?;
+    admin::ensure_not_paused(&collection)?;
+    archive::ensure_not_archived(&collection)?;
+    validation::validate_documents(&mut documents)?;
+    licensing::check_documents(&collection, &documents)?;
+    content_limits::check_documents(&collection, &documents)?;
+    ensure_document_capacity(documents.len() as u64)?;
+
+    if encryption::is_encrypted(&collection) {
+        for document in &documents {
+            encryption::validate_encrypted_content(&collection, &document.content)?;
+        }
+    }
+
+    ic_cdk::println!(
+        "Adding {} extracted documents to collection {}",
+        documents.len(),
+        collection_id
+    );
+
+    let document_ids = stage_documents(&collection, documents).await?;
+    stats::record(&collection_id, StatEvent::DocumentsAdded(document_ids.len() as u64));
+
+    // Create proposal for embedding
+    let doc_count = document_ids.len();
+    let proposal_type = ProposalType::BatchEmbed {
+        document_ids: document_ids.clone(),
+    };
+
+    let description = format!(
+        "Embed {} extracted documents into the collection. Documents: [{}]",
+        doc_count,
+        document_ids
+            .iter()
+            .take(3) // Show first 3 IDs
+            .cloned()
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+
+    let proposal_id = governance::create_proposal(
+        &collection_id,
+        proposal_type,
+        caller,
+        description
+    ).await?;
+
+    // Clone proposal_id for the message
+    let proposal_id_clone = proposal_id.clone();
+
+    // Get governance type for the message
+    let governance_type = match collection.governance_model {
+        GovernanceModel::Permissionless => "permissionless",
+        GovernanceModel::Multisig => "multisig",
+        GovernanceModel::TokenBased => "token-based",
+        GovernanceModel::SnsIntegrated => "SNS-integrated",
+    };
+
+    // Create the result with the cloned proposal_id
+    let result = AddDocumentsResult {
+        document_ids,
+        proposal_id: Some(proposal_id_clone.clone()),
+        action: DocumentAction::ProposalCreated,
+        message: format!(
+            "Successfully added {} documents. Proposal {} created for {} governance approval",
+            doc_count, proposal_id_clone, governance_type
+        ),
+    };
+
+    Ok(result)
+}
+
+#[update]
+fn cleanup_extraction_progress_endpoint(
+    collection_id: String,
+    url: String,
+) -> ClanopediaResult<()> {
+    activity::record(
+        ic_cdk::caller(),
+        "cleanup_extraction_progress_endpoint",
+        Some(&collection_id),
+    );
+    let caller = ic_cdk::caller();
+
+    let collection = storage::get_collection(&collection_id)?;
+    auth::authorize(&collection, caller, Capability::Extract)?;
+
+    // Only allow cleanup of completed/failed extractions
+    if let Some(progress) = extractor::Extractor::get_progress(&collection_id, &url) {
+        match progress.status {
+            ExtractionStatus::Completed | ExtractionStatus::Failed(_) => {
+                extractor::Extractor::remove_progress(&collection_id, &url);
+                Ok(())
+            }
+            _ => Err(ClanopediaError::InvalidOperation(
+                "Cannot cleanup active or paused extractions".to_string(),
+            )),
+        }
+    } else {
+        Err(ClanopediaError::InvalidInput(
+            "No extraction found for this URL and collection".to_string(),
+        ))
+    }
+}
+
+// ============================
+// EXTRACTION STATUS ENDPOINTS
+// ============================
+
+/// Get extraction progress for a specific URL/collection
+#[query]
+fn get_extraction_progress(collection_id: String, url: String) -> Option<ExtractionProgress> {
+    let collection_id = slugs::collection_id(&collection_id);
+    extractor::Extractor::get_progress(&collection_id, &url)
+}
+
+/// Get all active extractions for a collection
+#[query]
+fn get_collection_extractions_endpoint(collection_id: String) -> Vec<ExtractionProgress> {
+    let collection_id = slugs::collection_id(&collection_id);
+    extractor::Extractor::get_collection_extractions(collection_id)
+}
+
+/// Get extraction statistics
+#[query]
+fn get_extraction_stats_endpoint() -> (u64, u64, u64) {
+    extractor::get_extraction_stats()
+}
+
+/// Clean up old completed extractions (system maintenance)
+#[update]
+fn cleanup_old_extractions_endpoint() -> u32 {
+    activity::record(ic_cdk::caller(), "cleanup_old_extractions_endpoint", None);
+    extractor::cleanup_old_extractions()
+}
+
+// ============================
+// EXTRACTION INFO ENDPOINTS
+// ============================
+
+#[query]
+fn get_supported_file_types() -> Vec<String> {
+    vec![
+        "txt".to_string(),
+        "md".to_string(),
+        "markdown".to_string(),
+        "pdf".to_string(),
+        "docx".to_string(),
+        "rtf".to_string(),
+        "zip".to_string(),
+    ]
+}
+
+#[query]
+fn get_supported_url_types() -> Vec<String> {
+    vec![
+        "YouTube playlists".to_string(),
+        "GitHub markdown files".to_string(),
+        "GitLab markdown files".to_string(),
+        "Bitbucket markdown files".to_string(),
+        "Google Docs shared or published documents".to_string(),
+        "IPFS and Arweave content".to_string(),
+    ]
+}
+
+#[query]
+fn get_extraction_limits() -> String {
+    let limits = content_limits::default_limits();
+    format!(
+        "File size limit: {} MB\nContent size limit: {} MB\nYouTube playlist limit: {} videos per batch\nCollections may set lower content limits; see get_collection_limits",
+        limits.max_file_bytes / (1024 * 1024),
+        limits.max_document_bytes / (1024 * 1024),
+        limits.max_playlist_videos,
+    )
+}
+
+/// Size caps in force for the collection's content, and the bytes it holds so far
+#[query]
+fn get_collection_limits(collection_id: String) -> ClanopediaResult<CollectionLimits> {
+    content_limits::get_collection_limits(&slugs::collection_id(&collection_id))
+}
+
+#[update]
+async fn sync_sns_proposal_status_and_update_endpoint(
+    collection_id: String,
+    proposal_id: String,
+) -> ClanopediaResult<()> {
+    activity::record(
+        ic_cdk::caller(),
+        "sync_sns_proposal_status_and_update_endpoint",
+        Some(&collection_id),
+    );
+    crate::governance::sync_sns_proposal_status_and_update(&collection_id, &proposal_id).await
+}
+
+#[query]
+fn is_sns_integrated_endpoint(collection_id: String) -> ClanopediaResult<bool> {
+    let collection_id = slugs::collection_id(&collection_id);
+    let collection = storage::get_collection(&collection_id)?;
+    Ok(collection.governance_model == GovernanceModel::SnsIntegrated)
+}
+
+#[query]
+fn get_sns_governance_canister_endpoint(collection_id: String) -> ClanopediaResult<Option<Principal>> {
+    let collection_id = slugs::collection_id(&collection_id);
+    let collection = storage::get_collection(&collection_id)?;
+    
+    if collection.governance_model == GovernanceModel::SnsIntegrated {
+        Ok(collection.sns_governance_canister)
+    } else {
+        Ok(None)
+    }
+}
+
+#[update]
+fn link_sns_proposal_id_endpoint(
+    collection_id: String,
+    proposal_id: String,
+    sns_proposal_id: u64,
+) -> ClanopediaResult<()> {
+    activity::record(ic_cdk::caller(), "link_sns_proposal_id_endpoint", Some(&collection_id));
+    let caller = ic_cdk::caller();
+    crate::governance::link_sns_proposal_id(&collection_id, &proposal_id, sns_proposal_id, caller)
+}
+
+#[query]
+fn is_admin_check(collection_id: CollectionId, user: Principal) -> bool {
+    let collection_id = slugs::collection_id(&collection_id);
+    is_admin(&collection_id, user)
+}
+
+#[update]
+async fn embed_single_document(
+    collection_id: String,
+    mut document: AddDocumentRequest,
+) -> ClanopediaResult<DocumentMetadata> {
+    activity::record(ic_cdk::caller(), "embed_single_document", Some(&collection_id));
+    let caller = ic_cdk::caller();
+    let collection = storage::get_collection(&collection_id)?;
+    auth::authorize(&collection, caller, Capability::AddDocuments)?;
+    trash::ensure_not_trashed(&collection)?;
+    admin::ensure_not_paused(&collection)?;
+    archive::ensure_not_archived(&collection)?;
+    validation::validate_document(&mut document)?;
+    licensing::check_documents(&collection, std::slice::from_ref(&document))?;
+    content_limits::check_documents(&collection, std::slice::from_ref(&document))?;
+    ensure_document_capacity(1)?;
+    if encryption::is_encrypted(&collection) {
+        encryption::validate_encrypted_content(&collection, &document.content)?;
+    }
+    let title = document.title.clone();
+    let content = document.content.clone();
+    let outline = document.outline.clone();
+    let summary = document.summary.clone();
+    let license = document.license.clone();
+    let provenance = document.provenance.clone();
+    let tags = document.tags.clone().unwrap_or_default();
+    // Convert AddDocumentRequest to DocumentRequest
+    let document_request = DocumentRequest {
+        title: document.title,
+        content: document.content,
+        content_type: document.content_type,
+        source_url: document.source_url,
+        author: document.author,
+        tags: document.tags,
+    };
+    // Add document to Blueband
+    let metadata = add_document_to_blueband(&collection.blueband_collection_id, document_request)
+        .await
+        .map_err(ClanopediaError::BluebandError)?;
+    integrity::check_added_document(&collection, &content, &metadata).await?;
+
+    record_document_structure(
+        &collection,
+        &metadata.id,
+        &title,
+        &content,
+        &tags,
+        outline,
+        summary,
+        provenance,
+    );
+    licensing::record_document_license(&collection.id, &metadata.id, license);
+    stats::record(&collection_id, StatEvent::DocumentsAdded(1));
+    Ok(metadata)
+}
+
+// Room for the per-document records written by record_document_structure
+fn ensure_document_capacity(documents: u64) -> ClanopediaResult<()> {
+    for map in [
+        StorageMap::DocumentLinks,
+        StorageMap::KeywordDocuments,
+        StorageMap::DocumentOutlines,
+        StorageMap::DocumentOverlays,
+        StorageMap::DocumentChecksums,
+        StorageMap::DocumentSizes,
+        StorageMap::DocumentProvenance,
+    ] {
+        storage::ensure_capacity(map, documents)?;
+    }
+    Ok(())
+}
+
+// Index links, keywords, outline, summary and provenance of a new document; skipped for
+// encrypted collections, where they would leak the plaintext structure
+#[allow(clippy::too_many_arguments)]
+fn record_document_structure(
+    collection: &Collection,
+    document_id: &str,
+    title: &str,
+    content: &str,
+    tags: &[String],
+    outline: Option<Vec<OutlineEntry>>,
+    summary: Option<String>,
+    provenance: Option<DocumentProvenance>,
+) {
+    if encryption::is_encrypted(collection) {
+        return;
+    }
+
+    storage::append_change(
+        &collection.id,
+        ChangeKind::DocumentAdded {
+            document_id: document_id.to_string(),
+        },
+    );
+    extractor::links::record_document_links(&collection.id, document_id, title, content);
+    keyword_index::index_document(&collection.id, document_id, title, tags);
+    if let Some(entries) = outline {
+        storage::set_document_outline(&collection.id, document_id, DocumentOutline { entries });
+    }
+    if let Some(provenance) = provenance {
+        storage::set_document_provenance(&collection.id, document_id, provenance);
+    }
+    if let Some(summary) = summary {
+        let mut overlay = storage::get_document_overlay(&collection.id, document_id).unwrap_or(
+            DocumentMetadataOverlay {
+                title: None,
+                tags: None,
+                author: None,
+                summary: None,
+                license: None,
+                slug: None,
+                updated_at: 0,
+            },
+        );
+        overlay.summary = Some(summary);
+        overlay.updated_at = time();
+        storage::set_document_overlay(&collection.id, document_id, overlay);
+    }
+}
+
+/// Re-check every stored content hash of a collection against the content in Blueband
+#[update]
+async fn verify_collection_integrity(
+    collection_id: CollectionId,
+) -> ClanopediaResult<IntegrityReport> {
+    let collection = storage::get_collection(&collection_id)?;
+    auth::authorize(&collection, ic_cdk::caller(), Capability::ViewAnalytics)?;
+    Ok(integrity::verify_collection(&collection).await)
+}
+
+/// Where and when a document's content was retrieved, if it was added from an extraction
+#[query]
+fn get_document_provenance(
+    collection_id: CollectionId,
+    document_id: DocumentId,
+) -> ClanopediaResult<Option<DocumentProvenance>> {
+    let collection_id = slugs::collection_id(&collection_id);
+    let document_id = slugs::document_id(&collection_id, &document_id);
+    storage::get_collection(&collection_id)?;
+    Ok(storage::get_document_provenance(&collection_id, &document_id))
+}
+
+#[query]
+fn get_document_outline(
+    collection_id: CollectionId,
+    document_id: DocumentId,
+) -> ClanopediaResult<Vec<OutlineEntry>> {
+    let collection_id = slugs::collection_id(&collection_id);
+    let document_id = slugs::document_id(&collection_id, &document_id);
+    storage::get_collection(&collection_id)?;
+    Ok(storage::get_document_outline(&collection_id, &document_id)
+        .map(|outline| outline.entries)
+        .unwrap_or_default())
+}
+
+#[query]
+fn get_link_graph(collection_id: CollectionId) -> ClanopediaResult<LinkGraph> {
+    let collection_id = slugs::collection_id(&collection_id);
+    storage::get_collection(&collection_id)?;
+    Ok(extractor::links::link_graph(&collection_id))
+}
+
+#[query]
+fn get_backlinks(
+    collection_id: CollectionId,
+    document_id: DocumentId,
+) -> ClanopediaResult<Vec<LinkNode>> {
+    let collection_id = slugs::collection_id(&collection_id);
+    let document_id = slugs::document_id(&collection_id, &document_id);
+    storage::get_collection(&collection_id)?;
+    Ok(extractor::links::backlinks(&collection_id, &document_id))
+}
+
+#[query]
+fn estimate_summarization_cost(
+    collection_id: CollectionId,
+    document_count: u32,
+) -> ClanopediaResult<u64> {
+    let collection_id = slugs::collection_id(&collection_id);
+    let collection = storage::get_collection(&collection_id)?;
+    let config = collection.summarization.as_ref().ok_or_else(|| {
+        ClanopediaError::InvalidOperation(format!(
+            "Summarization is not enabled for collection {}",
+            collection_id
+        ))
+    })?;
+    Ok(summarize::estimate_cost(config, document_count) as u64)
+}
+
+/// Per-map entry counts and memory use; controllers only
+#[query]
+fn get_storage_report() -> ClanopediaResult<StorageReport> {
+    proxy::require_controller(ic_cdk::caller())?;
+    Ok(storage::storage_report())
+}
+
+#[query]
+fn get_migration_progress() -> MigrationProgress {
+    storage::proposal_migration_progress()
+}
+
+#[update]
+fn set_storage_caps(caps: StorageCaps) -> ClanopediaResult<()> {
+    activity::record(ic_cdk::caller(), "set_storage_caps", None);
+    proxy::require_controller(ic_cdk::caller())?;
+    storage::set_storage_caps(caps.clone());
+    config_history::record(ic_cdk::caller(), None, ConfigSetting::StorageCaps(caps))?;
+    Ok(())
+}
+
+// ============================
+// CONTROLLER CONSOLE
+// ============================
+
+#[query]
+fn admin_list_collections() -> ClanopediaResult<Vec<CollectionAdminSummary>> {
+    admin::list_collections(ic_cdk::caller())
+}
+
+#[update]
+fn admin_pause_collection(collection_id: CollectionId, reason: String) -> ClanopediaResult<()> {
+    activity::record(ic_cdk::caller(), "admin_pause_collection", Some(&collection_id));
+    admin::pause_collection(ic_cdk::caller(), &collection_id, reason)
+}
+
+#[update]
+fn admin_resume_collection(collection_id: CollectionId) -> ClanopediaResult<()> {
+    activity::record(ic_cdk::caller(), "admin_resume_collection", Some(&collection_id));
+    admin::resume_collection(ic_cdk::caller(), &collection_id)
+}
+
+#[update]
+async fn admin_set_blueband_collection_id(
+    collection_id: CollectionId,
+    blueband_collection_id: String,
+) -> ClanopediaResult<()> {
+    activity::record(ic_cdk::caller(), "admin_set_blueband_collection_id", Some(&collection_id));
+    admin::set_blueband_collection_id(ic_cdk::caller(), &collection_id, blueband_collection_id)
+        .await
+}
+
+#[update]
+fn admin_purge_orphaned_entries() -> ClanopediaResult<Vec<MapPurge>> {
+    activity::record(ic_cdk::caller(), "admin_purge_orphaned_entries", None);
+    admin::purge_orphaned_entries(ic_cdk::caller())
+}
+
+/// Request swapping the Blueband canister after a health check of the new one; another
+/// controller confirms it with `confirm_config_change`
+#[update]
+async fn update_blueband_canister(new_id: Principal) -> ClanopediaResult<u64> {
+    activity::record(ic_cdk::caller(), "update_blueband_canister", None);
+    let setting = ConfigSetting::BluebandCanister(new_id);
+    config_history::request_change(ic_cdk::caller(), setting).await
+}
+
+/// Every change to canister-level configuration, oldest first; controllers only
+#[query]
+fn get_config_history(after_version: Option<u64>, limit: u32) -> ClanopediaResult<Vec<ConfigChange>> {
+    config_history::get_history(ic_cdk::caller(), after_version, limit)
+}
+
+/// Request a destructive configuration change for another controller to confirm
+#[update]
+async fn request_config_change(setting: ConfigSetting) -> ClanopediaResult<u64> {
+    activity::record(ic_cdk::caller(), "request_config_change", None);
+    config_history::request_change(ic_cdk::caller(), setting).await
+}
+
+#[update]
+async fn confirm_config_change(id: u64) -> ClanopediaResult<()> {
+    activity::record(ic_cdk::caller(), "confirm_config_change", None);
+    config_history::confirm_change(ic_cdk::caller(), id).await
+}
+
+#[update]
+fn cancel_config_change(id: u64) -> ClanopediaResult<()> {
+    activity::record(ic_cdk::caller(), "cancel_config_change", None);
+    config_history::cancel_change(ic_cdk::caller(), id)
+}
+
+#[query]
+fn list_pending_config_changes() -> ClanopediaResult<Vec<PendingConfigChange>> {
+    config_history::list_pending(ic_cdk::caller())
+}
+
+#[update]
+async fn reconcile_with_blueband() -> ClanopediaResult<BluebandReconciliation> {
+    activity::record(ic_cdk::caller(), "reconcile_with_blueband", None);
+    reconcile::reconcile_with_blueband(ic_cdk::caller()).await
+}
+
+#[query]
+fn get_blueband_reconciliation() -> ClanopediaResult<BluebandReconcileState> {
+    reconcile::get_state(ic_cdk::caller())
+}
+
+#[update]
+fn set_blueband_reconcile_interval(interval_hours: Option<u32>) -> ClanopediaResult<()> {
+    activity::record(ic_cdk::caller(), "set_blueband_reconcile_interval", None);
+    reconcile::set_interval(ic_cdk::caller(), interval_hours)
+}
+
+#[update]
+async fn repair_blueband_link(repair: BluebandRepair) -> ClanopediaResult<()> {
+    activity::record(ic_cdk::caller(), "repair_blueband_link", None);
+    reconcile::repair(ic_cdk::caller(), repair).await
+}
+
+#[update]
+fn set_backup_config(config: Option<BackupConfig>) -> ClanopediaResult<()> {
+    activity::record(ic_cdk::caller(), "set_backup_config", None);
+    backup::set_config(ic_cdk::caller(), config)
+}
+
+/// Start a backup now; `full` sends every entry rather than the changes since the last one
+#[update]
+fn run_backup_now(full: bool) -> ClanopediaResult<u64> {
+    activity::record(ic_cdk::caller(), "run_backup_now", None);
+    backup::run_now(ic_cdk::caller(), full)
+}
+
+#[query]
+fn get_backup_history() -> ClanopediaResult<Vec<BackupSummary>> {
+    backup::get_history(ic_cdk::caller())
+}
+
+/// Restore collections, proposals and indexes as of a backup; controllers only
+#[update]
+async fn restore_from_backup(backup_id: u64) -> ClanopediaResult<u64> {
+    activity::record(ic_cdk::caller(), "restore_from_backup", None);
+    backup::restore(ic_cdk::caller(), backup_id).await
+}
+
+// ============================
+// READ REPLICAS
+// ============================
+
+#[update]
+fn add_read_replica(canister: Principal) -> ClanopediaResult<()> {
+    activity::record(ic_cdk::caller(), "add_read_replica", None);
+    replica::add_replica(ic_cdk::caller(), canister)
+}
+
+#[update]
+fn remove_read_replica(canister: Principal) -> ClanopediaResult<()> {
+    activity::record(ic_cdk::caller(), "remove_read_replica", None);
+    replica::remove_replica(ic_cdk::caller(), canister)
+}
+
+#[query]
+fn list_read_replicas() -> ClanopediaResult<Vec<ReadReplica>> {
+    replica::list_replicas(ic_cdk::caller())
+}
+
+/// One page of a section for a read replica's initial copy; see `replica.rs` for the protocol
+#[query]
+fn replica_snapshot(
+    section: ReplicaSection,
+    after_key: Option<String>,
+    limit: u32,
+) -> ClanopediaResult<ReplicaSnapshotPage> {
+    replica::snapshot(ic_cdk::caller(), section, after_key, limit)
+}
+
+/// Entries changed after a read replica's cursor
+#[query]
+fn replica_deltas(since_cursor: Option<u64>, limit: u32) -> ClanopediaResult<ReplicaDeltaPage> {
+    replica::deltas(ic_cdk::caller(), since_cursor, limit)
+}
+
+#[query]
+fn get_event_log(after_sequence: Option<u64>, limit: u32) -> ClanopediaResult<Vec<EventLogEntry>> {
+    admin::list_events(ic_cdk::caller(), after_sequence, limit)
+}
+
+/// Structured log entries after `since_sequence`; controllers, or admins of the filtered
+/// collection
+#[query]
+fn get_logs(
+    filter: LogFilter,
+    since_sequence: Option<u64>,
+    limit: u32,
+) -> ClanopediaResult<Vec<LogEntry>> {
+    logging::get_logs(ic_cdk::caller(), filter, since_sequence, limit)
+}
+
+#[query]
+fn get_log_level() -> LogLevel {
+    storage::get_log_level()
+}
+
+/// Entries below `level` are neither kept nor printed; controllers only
+#[update]
+fn set_log_level(level: LogLevel) -> ClanopediaResult<()> {
+    activity::record(ic_cdk::caller(), "set_log_level", None);
+    logging::set_log_level(ic_cdk::caller(), level)
+}
+
+/// Ordered change records for off-chain indexers; pass the returned `next_sequence` back
+#[query]
+fn get_changes(since_sequence: Option<u64>, limit: u32) -> ClanopediaResult<ChangePage> {
+    changes::get_changes(since_sequence, limit)
+}
+
+/// Retry the collection's pending and failed document NFT mints; admins only
+#[update]
+async fn mint_document_nfts(collection_id: String) -> ClanopediaResult<Vec<DocumentNft>> {
+    activity::record(ic_cdk::caller(), "mint_document_nfts", Some(&collection_id));
+    attestations::mint_document_nfts(ic_cdk::caller(), &collection_id).await
+}
+
+#[query]
+fn get_document_nft(collection_id: String, document_id: String) -> Option<DocumentNft> {
+    let collection_id = slugs::collection_id(&collection_id);
+    let document_id = slugs::document_id(&collection_id, &document_id);
+    storage::get_document_nft(&collection_id, &document_id)
+}
+
+#[query]
+fn list_document_nfts(collection_id: String) -> Vec<DocumentNft> {
+    let collection_id = slugs::collection_id(&collection_id);
+    storage::list_document_nfts(&collection_id)
+}
+
+/// Let `canister` subscribe to the collection's governance events; admins only
+#[update]
+fn allow_governance_subscriber(
+    collection_id: CollectionId,
+    canister: Principal,
+) -> ClanopediaResult<()> {
+    activity::record(ic_cdk::caller(), "allow_governance_subscriber", Some(&collection_id));
+    subscriptions::allow_subscriber(ic_cdk::caller(), &collection_id, canister)
+}
+
+#[update]
+fn disallow_governance_subscriber(
+    collection_id: CollectionId,
+    canister: Principal,
+) -> ClanopediaResult<()> {
+    activity::record(ic_cdk::caller(), "disallow_governance_subscriber", Some(&collection_id));
+    subscriptions::disallow_subscriber(ic_cdk::caller(), &collection_id, canister)
+}
+
+#[query]
+fn list_governance_subscribers(
+    collection_id: CollectionId,
+) -> ClanopediaResult<Vec<GovernanceSubscriber>> {
+    let collection_id = slugs::collection_id(&collection_id);
+    subscriptions::list_subscribers(ic_cdk::caller(), &collection_id)
+}
+
+#[update]
+fn subscribe_to_governance(
+    collection_id: CollectionId,
+    events: Vec<ProposalEvent>,
+    method: String,
+) -> ClanopediaResult<()> {
+    activity::record(ic_cdk::caller(), "subscribe_to_governance", Some(&collection_id));
+    subscriptions::subscribe(ic_cdk::caller(), &collection_id, events, method)
+}
+
+#[update]
+fn unsubscribe_from_governance(collection_id: CollectionId) -> ClanopediaResult<()> {
+    activity::record(ic_cdk::caller(), "unsubscribe_from_governance", Some(&collection_id));
+    subscriptions::unsubscribe(ic_cdk::caller(), &collection_id)
+}
+
+// ============================
+// CHAT BOTS
+// ============================
+
+/// Connect a chat bot to the collection; returns its signing key, which is not shown again
+#[update]
+async fn register_chat_bot(
+    collection_id: CollectionId,
+    bot: Principal,
+    name: String,
+) -> ClanopediaResult<String> {
+    activity::record(ic_cdk::caller(), "register_chat_bot", Some(&collection_id));
+    bots::register_bot(ic_cdk::caller(), &collection_id, bot, name).await
+}
+
+#[update]
+fn unregister_chat_bot(collection_id: CollectionId, bot: Principal) -> ClanopediaResult<()> {
+    activity::record(ic_cdk::caller(), "unregister_chat_bot", Some(&collection_id));
+    bots::unregister_bot(ic_cdk::caller(), &collection_id, bot)
+}
+
+#[query]
+fn list_chat_bots(collection_id: CollectionId) -> ClanopediaResult<Vec<ChatBot>> {
+    let collection_id = slugs::collection_id(&collection_id);
+    bots::list_bots(&collection_id)
+}
+
+#[update]
+fn delegate_to_bot(
+    collection_id: CollectionId,
+    bot: Principal,
+    hours: u32,
+) -> ClanopediaResult<BotDelegation> {
+    activity::record(ic_cdk::caller(), "delegate_to_bot", Some(&collection_id));
+    bots::delegate_to_bot(ic_cdk::caller(), &collection_id, bot, hours)
+}
+
+#[update]
+fn revoke_bot_delegation(collection_id: CollectionId, bot: Principal) -> ClanopediaResult<()> {
+    activity::record(ic_cdk::caller(), "revoke_bot_delegation", Some(&collection_id));
+    bots::revoke_bot_delegation(ic_cdk::caller(), &collection_id, bot)
+}
+
+#[query]
+fn list_my_bot_delegations(collection_id: CollectionId) -> Vec<BotDelegation> {
+    let collection_id = slugs::collection_id(&collection_id);
+    bots::list_my_delegations(ic_cdk::caller(), &collection_id)
+}
+
+/// A vote relayed by a chat bot for a voter who delegated to it
+#[update]
+async fn vote_via_bot(
+    payload: BotVotePayload,
+    signature: Vec<u8>,
+) -> ClanopediaResult<VoteReceipt> {
+    activity::record(ic_cdk::caller(), "vote_via_bot", None);
+    bots::vote_via_bot(ic_cdk::caller(), payload, signature).await
+}
+
+/// Open proposals as a short plain-text digest for posting in chat
+#[query]
+fn summarize_active_proposals(collection_id: CollectionId) -> ClanopediaResult<String> {
+    let collection_id = slugs::collection_id(&collection_id);
+    bots::summarize_active_proposals(&collection_id)
+}
 
-        document_ids.push(metadata.id.clone());
-        processed_count += 1;
-        ic_cdk::println!(
-            "Successfully added document: {} ({}/{})",
-            metadata.id,
-            processed_count,
-            total_docs
-        );
-    }
+const DEFAULT_BOT_SEARCH_LIMIT: u32 = 5;
+const MAX_BOT_SEARCH_LIMIT: u32 = 10;
+const MAX_BOT_SNIPPET_CHARS: usize = 200;
 
-    // Create proposal for embedding
-    let doc_count = document_ids.len();
-    let proposal_type = ProposalType::BatchEmbed {
-        document_ids: document_ids.clone(),
-    };
+/// Semantic search trimmed to titles and one short snippet per hit, for chat replies
+#[update]
+async fn search_for_chat(
+    collection_id: CollectionId,
+    query: String,
+    limit: Option<u32>,
+) -> ClanopediaResult<Vec<BotSearchHit>> {
+    let limit = limit
+        .unwrap_or(DEFAULT_BOT_SEARCH_LIMIT)
+        .clamp(1, MAX_BOT_SEARCH_LIMIT);
+    let mut seen = std::collections::HashSet::new();
+    Ok(search_collection(collection_id, query, Some(limit))
+        .await?
+        .into_iter()
+        .filter(|m| seen.insert(m.document_id.clone())) // One hit per document
+        .take(limit as usize)
+        .map(|m| {
+            let snippet = m
+                .highlight
+                .map(|h| h.snippet)
+                .or(m.summary)
+                .or(m.chunk_text)
+                .unwrap_or_default();
+            let snippet = snippet.split_whitespace().collect::<Vec<_>>().join(" ");
+            BotSearchHit {
+                title: m.document_title.unwrap_or_else(|| m.document_id.clone()),
+                snippet: snippet.chars().take(MAX_BOT_SNIPPET_CHARS).collect(),
+                document_id: m.document_id,
+            }
+        })
+        .collect())
+}
 
-    let description = format!(
-        "Embed {} extracted documents into the collection. Documents: [{}]",
-        doc_count,
-        document_ids
-            .iter()
-            .take(3) // Show first 3 IDs
-            .cloned()
-            .collect::<Vec<_>>()
-            .join(", ")
-    );
+/// What the caller's principal changed, newest first; pass the last sequence seen as
+/// `before_sequence` for the next page
+#[query]
+fn get_my_activity(before_sequence: Option<u64>, limit: u32) -> Vec<ActivityEntry> {
+    activity::get_my_activity(ic_cdk::caller(), before_sequence, limit)
+}
 
-    let proposal_id = governance::create_proposal(
+/// The caller's inbox, newest first; page with the lowest id seen as `before_id`
+#[query]
+fn get_my_notifications(before_id: Option<u64>, limit: u32, unread_only: bool) -> NotificationPage {
+    notifications::get_my_notifications(ic_cdk::caller(), before_id, limit, unread_only)
+}
+
+/// Active proposals expiring within `within_hours`, with the admins who have not voted
+/// and whether the caller has
+#[query]
+fn get_expiring_proposals(within_hours: u32) -> ClanopediaResult<Vec<ExpiringProposal>> {
+    reminders::get_expiring_proposals(ic_cdk::caller(), within_hours)
+}
+
+/// Let `operator` use the given capabilities in the collection until `expires_at`;
+/// replaces any grant it already has. Admins only.
+#[update]
+fn grant_operator(
+    collection_id: CollectionId,
+    operator: Principal,
+    capabilities: Vec<Capability>,
+    expires_at: u64,
+) -> ClanopediaResult<()> {
+    activity::record(ic_cdk::caller(), "grant_operator", Some(&collection_id));
+    auth::grant_operator(
+        ic_cdk::caller(),
         &collection_id,
-        proposal_type,
-        caller,
-        description
-    ).await?;
+        operator,
+        capabilities,
+        expires_at,
+    )
+}
 
-    // Clone proposal_id for the message
-    let proposal_id_clone = proposal_id.clone();
+#[update]
+fn revoke_operator(collection_id: CollectionId, operator: Principal) -> ClanopediaResult<()> {
+    activity::record(ic_cdk::caller(), "revoke_operator", Some(&collection_id));
+    auth::revoke_operator(ic_cdk::caller(), &collection_id, operator)
+}
 
-    // Get governance type for the message
-    let governance_type = match collection.governance_model {
-        GovernanceModel::Permissionless => "permissionless",
-        GovernanceModel::Multisig => "multisig",
-        GovernanceModel::TokenBased => "token-based",
-        GovernanceModel::SnsIntegrated => "SNS-integrated",
-    };
+/// Replace the collection's proposal throttles: the hourly limit per principal, the fee in
+/// permissionless collections and the blocklist. Admins only.
+#[update]
+fn set_spam_protection(
+    collection_id: CollectionId,
+    protection: SpamProtection,
+) -> ClanopediaResult<()> {
+    activity::record(ic_cdk::caller(), "set_spam_protection", Some(&collection_id));
+    spam::set_spam_protection(ic_cdk::caller(), &collection_id, protection)
+}
 
-    // Create the result with the cloned proposal_id
-    let result = AddDocumentsResult {
-        document_ids,
-        proposal_id: Some(proposal_id_clone.clone()),
-        action: DocumentAction::ProposalCreated,
-        message: format!(
-            "Successfully added {} documents. Proposal {} created for {} governance approval",
-            doc_count, proposal_id_clone, governance_type
-        ),
-    };
+/// The collection's proposal throttles, so proposers can see the fee before paying it
+#[query]
+fn get_spam_protection(collection_id: CollectionId) -> ClanopediaResult<Option<SpamProtection>> {
+    let collection_id = slugs::collection_id(&collection_id);
+    spam::get_spam_protection(&collection_id)
+}
 
-    Ok(result)
+/// The collection's unexpired operator grants; admins only
+#[query]
+fn list_operators(collection_id: CollectionId) -> ClanopediaResult<Vec<OperatorGrant>> {
+    let collection_id = slugs::collection_id(&collection_id);
+    auth::list_operators(ic_cdk::caller(), &collection_id)
+}
+
+/// Calls the collection's proposals made to other canisters, newest first; admins only
+#[query]
+fn get_external_calls(collection_id: String) -> ClanopediaResult<Vec<ExternalCallRecord>> {
+    let collection_id = slugs::collection_id(&collection_id);
+    custom_proposals::get_external_calls(ic_cdk::caller(), &collection_id)
 }
 
+/// Mark notifications read; an empty list marks the whole inbox
 #[update]
-fn cleanup_extraction_progress_endpoint(
-    collection_id: String,
-    url: String,
+fn mark_read(ids: Vec<u64>) -> ClanopediaResult<u64> {
+    activity::record(ic_cdk::caller(), "mark_read", None);
+    notifications::mark_read(ic_cdk::caller(), ids)
+}
+
+/// Bookmark a document, or add it to the named reading list, creating the list if needed
+#[update]
+fn bookmark_document(
+    collection_id: CollectionId,
+    document_id: DocumentId,
+    list: Option<String>,
 ) -> ClanopediaResult<()> {
-    let caller = ic_cdk::caller();
+    activity::record(ic_cdk::caller(), "bookmark_document", Some(&collection_id));
+    bookmarks::bookmark_document(ic_cdk::caller(), &collection_id, &document_id, list)
+}
 
-    // Verify the caller is an admin of the collection
-    let collection = storage::get_collection(&collection_id)?;
-    if !collection.admins.contains(&caller) {
-        return Err(ClanopediaError::NotAuthorized);
-    }
+#[update]
+fn remove_bookmark(
+    collection_id: CollectionId,
+    document_id: DocumentId,
+    list: Option<String>,
+) -> ClanopediaResult<()> {
+    activity::record(ic_cdk::caller(), "remove_bookmark", Some(&collection_id));
+    bookmarks::remove_bookmark(ic_cdk::caller(), &collection_id, &document_id, list)
+}
 
-    // Only allow cleanup of completed/failed extractions
-    if let Some(progress) = extractor::Extractor::get_progress(&collection_id, &url) {
-        match progress.status {
-            ExtractionStatus::Completed | ExtractionStatus::Failed(_) => {
-                extractor::Extractor::remove_progress(&collection_id, &url);
-                Ok(())
-            }
-            _ => Err(ClanopediaError::InvalidOperation(
-                "Cannot cleanup active or paused extractions".to_string(),
-            )),
-        }
-    } else {
-        Err(ClanopediaError::InvalidInput(
-            "No extraction found for this URL and collection".to_string(),
-        ))
-    }
+#[update]
+fn delete_reading_list(name: String) -> ClanopediaResult<()> {
+    activity::record(ic_cdk::caller(), "delete_reading_list", None);
+    bookmarks::delete_reading_list(ic_cdk::caller(), &name)
 }
 
-// ============================
-// EXTRACTION STATUS ENDPOINTS
-// ============================
+#[query]
+fn get_my_bookmarks() -> ReaderBookmarks {
+    bookmarks::get_my_bookmarks(ic_cdk::caller())
+}
+
+/// Count a view of a document read without `get_document_endpoint`, e.g. from a cache
+#[update]
+fn record_view(collection_id: CollectionId, document_id: DocumentId) -> ClanopediaResult<()> {
+    views::record_view(ic_cdk::caller(), &collection_id, &document_id)
+}
 
-/// Get extraction progress for a specific URL/collection
 #[query]
-fn get_extraction_progress(collection_id: String, url: String) -> Option<ExtractionProgress> {
-    extractor::Extractor::get_progress(&collection_id, &url)
+fn get_trending_documents(
+    collection_id: CollectionId,
+    window: TrendingWindow,
+) -> ClanopediaResult<Vec<TrendingDocument>> {
+    let collection_id = slugs::collection_id(&collection_id);
+    views::get_trending_documents(&collection_id, window)
 }
 
-/// Get all active extractions for a collection
+/// The aliases search expands in the collection's queries, in alias order
 #[query]
-fn get_collection_extractions_endpoint(collection_id: String) -> Vec<ExtractionProgress> {
-    extractor::Extractor::get_collection_extractions(collection_id)
+fn get_aliases(collection_id: CollectionId) -> ClanopediaResult<Vec<QueryAlias>> {
+    let collection_id = slugs::collection_id(&collection_id);
+    glossary::get_aliases(&collection_id)
 }
 
-/// Get extraction statistics
+/// The collection's curated "start here" documents, in display order
 #[query]
-fn get_extraction_stats_endpoint() -> (u64, u64, u64) {
-    extractor::get_extraction_stats()
+fn get_pinned_documents(collection_id: CollectionId) -> ClanopediaResult<Vec<PinnedDocument>> {
+    let collection_id = slugs::collection_id(&collection_id);
+    pins::get_pinned_documents(&collection_id)
 }
 
-/// Clean up old completed extractions (system maintenance)
+/// React to a document or proposal; reacting again with the same reaction does nothing
 #[update]
-fn cleanup_old_extractions_endpoint() -> u32 {
-    extractor::cleanup_old_extractions()
+fn add_reaction(
+    collection_id: CollectionId,
+    target: ReactionTarget,
+    reaction: Reaction,
+) -> ClanopediaResult<ReactionSummary> {
+    activity::record(ic_cdk::caller(), "add_reaction", Some(&collection_id));
+    reactions::set_reaction(ic_cdk::caller(), &collection_id, target, reaction, true)
 }
 
-// ============================
-// EXTRACTION INFO ENDPOINTS
-// ============================
+#[update]
+fn remove_reaction(
+    collection_id: CollectionId,
+    target: ReactionTarget,
+    reaction: Reaction,
+) -> ClanopediaResult<ReactionSummary> {
+    activity::record(ic_cdk::caller(), "remove_reaction", Some(&collection_id));
+    reactions::set_reaction(ic_cdk::caller(), &collection_id, target, reaction, false)
+}
 
+/// Reaction counts of up to 100 documents or proposals, with the caller's own reactions
 #[query]
-fn get_supported_file_types() -> Vec<String> {
-    vec![
-        "txt".to_string(),
-        "md".to_string(),
-        "markdown".to_string(),
-        "pdf".to_string(),
-        "docx".to_string(),
-    ]
+fn get_reactions(
+    collection_id: CollectionId,
+    targets: Vec<ReactionTarget>,
+) -> ClanopediaResult<Vec<ReactionSummary>> {
+    let collection_id = slugs::collection_id(&collection_id);
+    let targets = targets
+        .into_iter()
+        .map(|target| match target {
+            ReactionTarget::Document { document_id } => ReactionTarget::Document {
+                document_id: slugs::document_id(&collection_id, &document_id),
+            },
+            proposal => proposal,
+        })
+        .collect();
+    reactions::get_reactions(ic_cdk::caller(), &collection_id, targets)
 }
 
+/// Documents and proposals ranked by a reaction, or by all of them; for admins
 #[query]
-fn get_supported_url_types() -> Vec<String> {
-    vec![
-        "YouTube playlists".to_string(),
-        "GitHub markdown files".to_string(),
-    ]
+fn get_reaction_signals(
+    collection_id: CollectionId,
+    reaction: Option<Reaction>,
+    limit: Option<u32>,
+) -> ClanopediaResult<Vec<ReactionSummary>> {
+    let collection_id = slugs::collection_id(&collection_id);
+    reactions::get_reaction_signals(ic_cdk::caller(), &collection_id, reaction, limit)
 }
 
+/// Outcall limits and what is left of them, canister-wide and for one collection if given
 #[query]
-fn get_extraction_limits() -> String {
-    format!(
-        "File size limit: {} MB\nContent size limit: {} MB\nYouTube playlist limit: 50 videos per batch\nGitHub file limit: 2 MB",
-        10,
-        10, 
-    )
+fn get_outcall_budget(collection_id: Option<CollectionId>) -> OutcallBudgetStatus {
+    let collection_id = collection_id.map(|id| slugs::collection_id(&id));
+    outcall_budget::get_status(collection_id)
 }
 
 #[update]
-async fn sync_sns_proposal_status_and_update_endpoint(
-    collection_id: String,
-    proposal_id: String,
-) -> ClanopediaResult<()> {
-    crate::governance::sync_sns_proposal_status_and_update(&collection_id, &proposal_id).await
+fn set_outcall_budget(budget: OutcallBudget) -> ClanopediaResult<()> {
+    activity::record(ic_cdk::caller(), "set_outcall_budget", None);
+    outcall_budget::set_budget(ic_cdk::caller(), budget)
 }
 
 #[query]
-fn is_sns_integrated_endpoint(collection_id: String) -> ClanopediaResult<bool> {
-    let collection = storage::get_collection(&collection_id)?;
-    Ok(collection.governance_model == GovernanceModel::SnsIntegrated)
+fn get_subnet_size() -> SubnetSize {
+    outcall_pricing::get_subnet_size()
+}
+
+/// Price outcalls for a subnet of `nodes`, or clear it to use the detected size; controllers
+/// only
+#[update]
+fn set_subnet_size(nodes: Option<u32>) -> ClanopediaResult<()> {
+    activity::record(ic_cdk::caller(), "set_subnet_size", None);
+    outcall_pricing::set_subnet_size(ic_cdk::caller(), nodes)
 }
 
 #[query]
-fn get_sns_governance_canister_endpoint(collection_id: String) -> ClanopediaResult<Option<Principal>> {
-    let collection = storage::get_collection(&collection_id)?;
-    
-    if collection.governance_model == GovernanceModel::SnsIntegrated {
-        Ok(collection.sns_governance_canister)
-    } else {
-        Ok(None)
-    }
+fn get_permaweb_gateways() -> PermawebGateways {
+    extractor::permaweb::get_gateways()
 }
 
+/// Replace the IPFS and Arweave gateways URL extraction falls back through; controllers only
 #[update]
-fn link_sns_proposal_id_endpoint(
-    collection_id: String,
-    proposal_id: String,
-    sns_proposal_id: u64,
-) -> ClanopediaResult<()> {
-    let caller = ic_cdk::caller();
-    crate::governance::link_sns_proposal_id(&collection_id, &proposal_id, sns_proposal_id, caller)
+fn set_permaweb_gateways(gateways: PermawebGateways) -> ClanopediaResult<()> {
+    activity::record(ic_cdk::caller(), "set_permaweb_gateways", None);
+    extractor::permaweb::set_gateways(ic_cdk::caller(), gateways)
 }
 
 #[query]
-fn is_admin_check(collection_id: CollectionId, user: Principal) -> bool {
-    is_admin(&collection_id, user)
+fn get_outcall_mirrors() -> OutcallMirrors {
+    extractor::mirrors::get_mirrors()
 }
 
+/// Replace the mirrors repository extraction falls back through; controllers only
 #[update]
-async fn embed_single_document(
-    collection_id: String,
-    document: AddDocumentRequest,
-) -> ClanopediaResult<DocumentMetadata> {
-    let caller = ic_cdk::caller();
-    let collection = storage::get_collection(&collection_id)?;
-    if !collection.admins.contains(&caller) {
-        return Err(ClanopediaError::NotAuthorized);
-    }
-    // Convert AddDocumentRequest to DocumentRequest
-    let document_request = DocumentRequest {
-        title: document.title,
-        content: document.content,
-        content_type: document.content_type,
-        source_url: document.source_url,
-        author: document.author,
-        tags: document.tags,
-    };
-    // Add document to Blueband
-    add_document_to_blueband(&collection.blueband_collection_id, document_request)
-        .await
-        .map_err(ClanopediaError::BluebandError)
+fn set_outcall_mirrors(mirrors: OutcallMirrors) -> ClanopediaResult<()> {
+    activity::record(ic_cdk::caller(), "set_outcall_mirrors", None);
+    extractor::mirrors::set_mirrors(ic_cdk::caller(), mirrors)
+}
+
+#[query]
+fn get_site_config() -> SiteConfig {
+    site::get_site_config()
+}
+
+/// Set the frontend URL and site name the sitemap and OpenSearch descriptions use;
+/// controllers only
+#[update]
+fn set_site_config(config: SiteConfig) -> ClanopediaResult<()> {
+    activity::record(ic_cdk::caller(), "set_site_config", None);
+    site::set_site_config(ic_cdk::caller(), config)
+}
+
+/// Serve `/sitemap.xml`, per-collection sitemaps and OpenSearch descriptions to crawlers
+#[query]
+fn http_request(request: GatewayRequest) -> GatewayResponse {
+    site::http_request(request)
+}
+
+/// Ping external dependencies and cache the results; controllers only
+#[update]
+async fn check_external_health() -> ClanopediaResult<ExternalHealth> {
+    health::check_external_health(ic_cdk::caller()).await
+}
+
+/// Canister health for operators; controllers only
+#[update]
+async fn get_self_status() -> ClanopediaResult<SelfStatus> {
+    status::get_self_status(ic_cdk::caller()).await
 }
 
 #[update]
@@ -720,6 +2837,55 @@ async fn get_collection_metrics_endpoint(
         .map_err(ClanopediaError::BluebandError)
 }
 
+// ============================
+// MOCK SERVICE SEEDING
+// ============================
+
+// Only present in `mock-services` builds, where ledgers and SNS governance are fakes
+
+#[cfg(feature = "mock-services")]
+#[update]
+fn mock_set_token_balance(
+    ledger: Principal,
+    owner: Principal,
+    amount: candid::Nat,
+) -> ClanopediaResult<()> {
+    proxy::require_controller(ic_cdk::caller())?;
+    external::fakes::set_balance(ledger, owner, amount);
+    Ok(())
+}
+
+#[cfg(feature = "mock-services")]
+#[update]
+fn mock_set_sns_proposal_tally(
+    sns_governance: Principal,
+    proposal_id: u64,
+    yes: u64,
+    no: u64,
+) -> ClanopediaResult<()> {
+    proxy::require_controller(ic_cdk::caller())?;
+    external::fakes::set_proposal_tally(
+        sns_governance,
+        proposal_id,
+        yes,
+        no,
+        ic_cdk::api::time() / 1_000_000_000,
+    );
+    Ok(())
+}
+
+#[cfg(feature = "mock-services")]
+#[update]
+fn mock_set_neuron_stake(
+    sns_governance: Principal,
+    owner: Principal,
+    stake_e8s: u64,
+) -> ClanopediaResult<()> {
+    proxy::require_controller(ic_cdk::caller())?;
+    external::fakes::set_neuron_stake(sns_governance, owner, stake_e8s);
+    Ok(())
+}
+
 // Export candid interface
 ic_cdk::export_candid!();
 
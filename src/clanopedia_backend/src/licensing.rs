@@ -0,0 +1,73 @@
+// src/clanopedia_backend/src/licensing.rs
+//
+// Documents may declare their own license; otherwise the collection's default applies. A
+// document's own license lives on its metadata overlay, so the default can change later
+// without rewriting every document.
+
+use crate::{
+    storage,
+    types::{ClanopediaError, ClanopediaResult, Collection, DocumentMetadataOverlay, License},
+    AddDocumentRequest,
+};
+
+/// Fail when any of `documents` would enter `collection` without a license and its policy
+/// rejects unknown licenses
+pub fn check_documents(
+    collection: &Collection,
+    documents: &[AddDocumentRequest],
+) -> ClanopediaResult<()> {
+    let Some(policy) = &collection.license else {
+        return Ok(());
+    };
+    if !policy.reject_unknown || policy.default_license.is_some() {
+        return Ok(());
+    }
+
+    let unlicensed: Vec<&str> = documents
+        .iter()
+        .filter(|d| d.license.is_none())
+        .map(|d| d.title.as_str())
+        .collect();
+    if unlicensed.is_empty() {
+        return Ok(());
+    }
+    Err(ClanopediaError::InvalidOperation(format!(
+        "Collection {} only accepts licensed documents; no license given for: {}",
+        collection.id,
+        unlicensed.join(", ")
+    )))
+}
+
+/// Keep a document's own license on its overlay
+pub fn record_document_license(collection_id: &str, document_id: &str, license: Option<License>) {
+    let Some(license) = license else {
+        return;
+    };
+    let mut overlay = storage::get_document_overlay(collection_id, document_id).unwrap_or(
+        DocumentMetadataOverlay {
+            title: None,
+            tags: None,
+            author: None,
+            summary: None,
+            license: None,
+            slug: None,
+            updated_at: 0,
+        },
+    );
+    overlay.license = Some(license);
+    overlay.updated_at = ic_cdk::api::time();
+    storage::set_document_overlay(collection_id, document_id, overlay);
+}
+
+/// The license a document is published under: its own, else the collection's default
+pub fn document_license(
+    collection: &Collection,
+    overlay: Option<&DocumentMetadataOverlay>,
+) -> Option<License> {
+    overlay.and_then(|o| o.license.clone()).or_else(|| {
+        collection
+            .license
+            .as_ref()
+            .and_then(|p| p.default_license.clone())
+    })
+}
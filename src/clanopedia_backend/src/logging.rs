@@ -0,0 +1,104 @@
+// src/clanopedia_backend/src/logging.rs
+//
+// `ic_cdk::println!` output is only visible in replica logs, and only for a while. Entries
+// at or above the configured level are also kept in a bounded stable ring buffer, with the
+// module and collection they concern. Controllers read every entry and set the level; a
+// collection's admins read the entries of their collection.
+
+use candid::Principal;
+use ic_cdk::api::time;
+
+use crate::{
+    auth, config_history,
+    proxy::require_controller,
+    storage,
+    types::{ClanopediaError, ClanopediaResult, ConfigSetting, LogEntry, LogFilter, LogLevel},
+};
+
+const MAX_LOG_PAGE: u32 = 200;
+const MAX_MESSAGE_BYTES: usize = 2 * 1024;
+
+pub fn log(level: LogLevel, module: &str, collection_id: Option<&str>, message: impl Into<String>) {
+    if level < storage::get_log_level() {
+        return;
+    }
+    let mut message = message.into();
+    if message.len() > MAX_MESSAGE_BYTES {
+        let mut end = MAX_MESSAGE_BYTES;
+        while !message.is_char_boundary(end) {
+            end -= 1;
+        }
+        message.truncate(end);
+    }
+
+    ic_cdk::println!(
+        "[{:?}] {}{}: {}",
+        level,
+        module,
+        collection_id.map_or(String::new(), |id| format!(" ({})", id)),
+        message
+    );
+    storage::append_log(LogEntry {
+        sequence: 0, // Assigned on append
+        timestamp: time(),
+        level,
+        module: module.to_string(),
+        collection_id: collection_id.map(str::to_string),
+        message,
+    });
+}
+
+pub fn debug(module: &str, collection_id: Option<&str>, message: impl Into<String>) {
+    log(LogLevel::Debug, module, collection_id, message)
+}
+
+pub fn info(module: &str, collection_id: Option<&str>, message: impl Into<String>) {
+    log(LogLevel::Info, module, collection_id, message)
+}
+
+pub fn warn(module: &str, collection_id: Option<&str>, message: impl Into<String>) {
+    log(LogLevel::Warn, module, collection_id, message)
+}
+
+pub fn error(module: &str, collection_id: Option<&str>, message: impl Into<String>) {
+    log(LogLevel::Error, module, collection_id, message)
+}
+
+/// Entries after `since_sequence` matching `filter`, oldest first. Callers other than
+/// controllers must name a collection they administer.
+pub fn get_logs(
+    caller: Principal,
+    filter: LogFilter,
+    since_sequence: Option<u64>,
+    limit: u32,
+) -> ClanopediaResult<Vec<LogEntry>> {
+    if require_controller(caller).is_err() {
+        let collection_id = filter.collection_id.as_ref().ok_or_else(|| {
+            ClanopediaError::InvalidInput(
+                "Only controllers can read logs across collections".to_string(),
+            )
+        })?;
+        let collection = storage::get_collection(collection_id)?;
+        auth::require_admin(&collection, caller)?;
+    }
+
+    Ok(storage::list_logs(
+        since_sequence,
+        limit.min(MAX_LOG_PAGE) as usize,
+        |entry| {
+            filter.min_level.is_none_or(|level| entry.level >= level)
+                && filter.module.as_ref().is_none_or(|m| &entry.module == m)
+                && filter
+                    .collection_id
+                    .as_ref()
+                    .is_none_or(|id| entry.collection_id.as_ref() == Some(id))
+        },
+    ))
+}
+
+pub fn set_log_level(caller: Principal, level: LogLevel) -> ClanopediaResult<()> {
+    require_controller(caller)?;
+    storage::set_log_level(level);
+    config_history::record(caller, None, ConfigSetting::LogLevel(level))?;
+    Ok(())
+}
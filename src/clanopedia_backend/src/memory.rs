@@ -0,0 +1,212 @@
+// src/clanopedia_backend/src/memory.rs
+//
+// Every stable structure lives in a virtual memory of the one MemoryManager below. Managers
+// initialized separately over the same stable memory each keep their own bucket table and
+// can hand out the same buckets, so no module may create another. IDs are registered here
+// and checked for duplicates at compile time; a retired ID stays registered so it is never
+// handed to a new structure.
+
+use ic_stable_structures::{
+    memory_manager::{MemoryId, MemoryManager, VirtualMemory},
+    DefaultMemoryImpl,
+};
+use std::cell::RefCell;
+
+pub type Memory = VirtualMemory<DefaultMemoryImpl>;
+
+pub const COLLECTIONS: u8 = 0;
+pub const PROPOSALS: u8 = 1;
+pub const BLUEBAND_CANISTER_ID: u8 = 2;
+pub const DOCUMENT_OVERLAYS: u8 = 3;
+pub const DOCUMENT_LINKS: u8 = 4;
+pub const DOCUMENT_OUTLINES: u8 = 5;
+pub const WEEKLY_ROLLUPS: u8 = 6;
+pub const QUERY_LOG: u8 = 7;
+pub const PROXY_ALLOWLIST: u8 = 8;
+pub const CANISTER_SECRET: u8 = 9;
+// Written through the extractor's former manager; drained into EXTRACTION_PROGRESS on upgrade
+pub const LEGACY_EXTRACTION_PROGRESS: u8 = 10;
+pub const COLLECTION_SECRETS: u8 = 11;
+pub const KEYWORD_POSTINGS: u8 = 12;
+pub const KEYWORD_DOCUMENTS: u8 = 13;
+pub const TRASHED_DOCUMENTS: u8 = 14;
+pub const STORAGE_CAPS: u8 = 15;
+pub const VOTE_RECEIPTS: u8 = 16;
+pub const CREATION_POLICY: u8 = 17;
+pub const CREATION_REQUESTS: u8 = 18;
+pub const EVENT_LOG: u8 = 19;
+pub const EXTRACTION_JOBS: u8 = 20;
+pub const JOB_DOCUMENTS: u8 = 21;
+pub const NOTIFICATIONS: u8 = 22;
+pub const DOCUMENT_NFTS: u8 = 23;
+pub const CHANGE_LOG: u8 = 24;
+pub const PROPOSAL_ATTACHMENTS: u8 = 25;
+pub const ATTACHMENT_CHUNKS: u8 = 26;
+pub const OUTCALL_BUDGET: u8 = 27;
+pub const FETCH_CACHE: u8 = 28;
+pub const FETCH_CACHE_BODIES: u8 = 29;
+pub const PERMAWEB_GATEWAYS: u8 = 30;
+pub const DOCUMENT_CHECKSUMS: u8 = 31;
+pub const DOCUMENT_PROVENANCE: u8 = 32;
+pub const CROSS_POSTS: u8 = 33;
+pub const EXECUTED_PROPOSALS: u8 = 34;
+pub const EXTERNAL_CALLS: u8 = 35;
+pub const EXTRACTION_PROGRESS: u8 = 36;
+pub const LOG_LEVEL: u8 = 37;
+pub const LOGS: u8 = 38;
+pub const JOB_OUTCOMES: u8 = 39;
+pub const SUBNET_SIZE: u8 = 40;
+pub const BOOKMARKS: u8 = 41;
+pub const DOCUMENT_VIEWS: u8 = 42;
+pub const DAILY_VIEWS: u8 = 43;
+pub const SITE_CONFIG: u8 = 44;
+pub const SITEMAP_ENTRIES: u8 = 45;
+pub const COLLECTION_SLUGS: u8 = 46;
+pub const DOCUMENT_SLUGS: u8 = 47;
+pub const REACTION_COUNTS: u8 = 48;
+pub const REACTIONS: u8 = 49;
+pub const PENDING_CREATIONS: u8 = 50;
+pub const BLUEBAND_RECONCILIATION: u8 = 51;
+pub const GOVERNANCE_SUBSCRIBERS: u8 = 52;
+pub const CHAT_BOTS: u8 = 53;
+pub const BOT_DELEGATIONS: u8 = 54;
+pub const ACTIVITY: u8 = 55;
+pub const BACKUP_STATE: u8 = 56;
+pub const BACKUP_HISTORY: u8 = 57;
+pub const BACKUP_DIGESTS: u8 = 58;
+pub const READ_REPLICAS: u8 = 59;
+// Further shards of KEYWORD_DOCUMENTS, KEYWORD_POSTINGS and EVENT_LOG, which are shard 0
+pub const KEYWORD_DOCUMENTS_1: u8 = 60;
+pub const KEYWORD_DOCUMENTS_2: u8 = 61;
+pub const KEYWORD_DOCUMENTS_3: u8 = 62;
+pub const KEYWORD_POSTINGS_1: u8 = 63;
+pub const KEYWORD_POSTINGS_2: u8 = 64;
+pub const KEYWORD_POSTINGS_3: u8 = 65;
+pub const EVENT_LOG_1: u8 = 66;
+pub const EVENT_LOG_2: u8 = 67;
+pub const EVENT_LOG_3: u8 = 68;
+pub const SHARD_REBALANCE: u8 = 69;
+pub const DOCUMENT_SIZES: u8 = 70;
+pub const OUTCALL_MIRRORS: u8 = 71;
+pub const PINNED_DOCUMENTS: u8 = 72;
+pub const QUERY_ALIASES: u8 = 73;
+pub const PROPOSAL_INDEX: u8 = 74;
+pub const PROPOSAL_INDEX_BACKFILL: u8 = 75;
+pub const EXECUTION_RECEIPTS: u8 = 76;
+pub const ARCHIVED_DOCUMENTS: u8 = 77;
+pub const CONTENT_STRUCTURES: u8 = 78;
+pub const CONFIG_HISTORY: u8 = 79;
+pub const PENDING_CONFIG_CHANGES: u8 = 80;
+pub const CALL_ALLOWLIST: u8 = 81;
+pub const UNSETTLED_BONDS: u8 = 82;
+
+const REGISTERED: [u8; 83] = [
+    COLLECTIONS,
+    PROPOSALS,
+    BLUEBAND_CANISTER_ID,
+    DOCUMENT_OVERLAYS,
+    DOCUMENT_LINKS,
+    DOCUMENT_OUTLINES,
+    WEEKLY_ROLLUPS,
+    QUERY_LOG,
+    PROXY_ALLOWLIST,
+    CANISTER_SECRET,
+    LEGACY_EXTRACTION_PROGRESS,
+    COLLECTION_SECRETS,
+    KEYWORD_POSTINGS,
+    KEYWORD_DOCUMENTS,
+    TRASHED_DOCUMENTS,
+    STORAGE_CAPS,
+    VOTE_RECEIPTS,
+    CREATION_POLICY,
+    CREATION_REQUESTS,
+    EVENT_LOG,
+    EXTRACTION_JOBS,
+    JOB_DOCUMENTS,
+    NOTIFICATIONS,
+    DOCUMENT_NFTS,
+    CHANGE_LOG,
+    PROPOSAL_ATTACHMENTS,
+    ATTACHMENT_CHUNKS,
+    OUTCALL_BUDGET,
+    FETCH_CACHE,
+    FETCH_CACHE_BODIES,
+    PERMAWEB_GATEWAYS,
+    DOCUMENT_CHECKSUMS,
+    DOCUMENT_PROVENANCE,
+    CROSS_POSTS,
+    EXECUTED_PROPOSALS,
+    EXTERNAL_CALLS,
+    EXTRACTION_PROGRESS,
+    LOG_LEVEL,
+    LOGS,
+    JOB_OUTCOMES,
+    SUBNET_SIZE,
+    BOOKMARKS,
+    DOCUMENT_VIEWS,
+    DAILY_VIEWS,
+    SITE_CONFIG,
+    SITEMAP_ENTRIES,
+    COLLECTION_SLUGS,
+    DOCUMENT_SLUGS,
+    REACTION_COUNTS,
+    REACTIONS,
+    PENDING_CREATIONS,
+    BLUEBAND_RECONCILIATION,
+    GOVERNANCE_SUBSCRIBERS,
+    CHAT_BOTS,
+    BOT_DELEGATIONS,
+    ACTIVITY,
+    BACKUP_STATE,
+    BACKUP_HISTORY,
+    BACKUP_DIGESTS,
+    READ_REPLICAS,
+    KEYWORD_DOCUMENTS_1,
+    KEYWORD_DOCUMENTS_2,
+    KEYWORD_DOCUMENTS_3,
+    KEYWORD_POSTINGS_1,
+    KEYWORD_POSTINGS_2,
+    KEYWORD_POSTINGS_3,
+    EVENT_LOG_1,
+    EVENT_LOG_2,
+    EVENT_LOG_3,
+    SHARD_REBALANCE,
+    DOCUMENT_SIZES,
+    OUTCALL_MIRRORS,
+    PINNED_DOCUMENTS,
+    QUERY_ALIASES,
+    PROPOSAL_INDEX,
+    PROPOSAL_INDEX_BACKFILL,
+    EXECUTION_RECEIPTS,
+    ARCHIVED_DOCUMENTS,
+    CONTENT_STRUCTURES,
+    CONFIG_HISTORY,
+    PENDING_CONFIG_CHANGES,
+    CALL_ALLOWLIST,
+    UNSETTLED_BONDS,
+];
+
+const _: () = assert_unique(&REGISTERED);
+
+const fn assert_unique(ids: &[u8]) {
+    let mut i = 0;
+    while i < ids.len() {
+        let mut j = i + 1;
+        while j < ids.len() {
+            assert!(ids[i] != ids[j], "memory ID registered twice");
+            j += 1;
+        }
+        i += 1;
+    }
+}
+
+thread_local! {
+    static MEMORY_MANAGER: RefCell<MemoryManager<DefaultMemoryImpl>> = RefCell::new(
+        MemoryManager::init(DefaultMemoryImpl::default())
+    );
+}
+
+/// The virtual memory registered under `id`, one of the constants above
+pub fn get(id: u8) -> Memory {
+    MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(id)))
+}
@@ -0,0 +1,198 @@
+// src/clanopedia_backend/src/notifications.rs
+//
+// Per-principal inboxes, filled as governance moves along so frontends can show unread
+// counts without polling every collection. Delivery is best effort: a full inbox map never
+// fails the vote or execution that triggered it.
+
+use candid::Principal;
+use ic_cdk::api::time;
+
+use crate::{
+    storage,
+    types::{
+        ClanopediaError, ClanopediaResult, Collection, NotificationKind, NotificationPage,
+        Proposal, StorageMap,
+    },
+};
+
+const MAX_NOTIFICATIONS_PER_PRINCIPAL: usize = 200;
+const MAX_NOTIFICATION_PAGE: usize = 50;
+
+fn notify(principal: Principal, collection_id: &str, kind: NotificationKind, message: String) {
+    if principal == Principal::anonymous()
+        || storage::ensure_capacity(StorageMap::Notifications, 1).is_err()
+    {
+        return;
+    }
+    // Repeated alerts collapse into the one still unread
+    let repeated = storage::list_notifications(&principal)
+        .last()
+        .is_some_and(|n| !n.read && n.collection_id == collection_id && n.kind == kind);
+    if repeated {
+        return;
+    }
+    storage::push_notification(
+        &principal,
+        collection_id,
+        kind,
+        message,
+        MAX_NOTIFICATIONS_PER_PRINCIPAL,
+    );
+}
+
+fn notify_admins(
+    collection: &Collection,
+    kind: NotificationKind,
+    message: String,
+    except: Option<Principal>,
+) {
+    for admin in collection.admins.iter().filter(|a| Some(**a) != except) {
+        notify(*admin, &collection.id, kind.clone(), message.clone());
+    }
+}
+
+// ============================
+// GOVERNANCE EVENTS
+// ============================
+
+/// Tell the collection's admins, the voters the canister can enumerate, about a new
+/// proposal. Token holders and SNS neurons follow proposals through their own tooling.
+pub fn proposal_created(collection: &Collection, proposal: &Proposal) {
+    notify_admins(
+        collection,
+        NotificationKind::ProposalCreated {
+            proposal_id: proposal.id.clone(),
+        },
+        format!(
+            "New proposal in {}: {}",
+            collection.name, proposal.description
+        ),
+        Some(proposal.creator),
+    );
+}
+
+pub fn proposal_approved(collection_id: &str, proposal: &Proposal) {
+    notify(
+        proposal.creator,
+        collection_id,
+        NotificationKind::ProposalApproved {
+            proposal_id: proposal.id.clone(),
+        },
+        format!("Your proposal {} was approved", proposal.id),
+    );
+}
+
+pub fn proposal_executed(collection_id: &str, proposal: &Proposal) {
+    notify(
+        proposal.creator,
+        collection_id,
+        NotificationKind::ProposalExecuted {
+            proposal_id: proposal.id.clone(),
+        },
+        format!("Your proposal {} was executed", proposal.id),
+    );
+}
+
+pub fn proposal_failed(collection_id: &str, proposal: &Proposal, error: String) {
+    notify(
+        proposal.creator,
+        collection_id,
+        NotificationKind::ProposalFailed {
+            proposal_id: proposal.id.clone(),
+            error: error.clone(),
+        },
+        format!("Your proposal {} failed: {}", proposal.id, error),
+    );
+}
+
+/// Remind the given admins that a proposal they have not voted on expires soon
+pub fn vote_reminder(collection: &Collection, proposal: &Proposal, voters: &[Principal]) {
+    let hours_left = proposal.expires_at.saturating_sub(time()) / (60 * 60 * 1_000_000_000);
+    for voter in voters {
+        notify(
+            *voter,
+            &collection.id,
+            NotificationKind::VoteReminder {
+                proposal_id: proposal.id.clone(),
+                expires_at: proposal.expires_at,
+            },
+            format!(
+                "Proposal in {} expires in {} hours and still needs your vote: {}",
+                collection.name, hours_left, proposal.description
+            ),
+        );
+    }
+}
+
+/// Ask newly assigned reviewers to review a proposal
+pub fn review_requested(collection: &Collection, proposal: &Proposal, reviewers: &[Principal]) {
+    for reviewer in reviewers {
+        notify(
+            *reviewer,
+            &collection.id,
+            NotificationKind::ReviewRequested {
+                proposal_id: proposal.id.clone(),
+            },
+            format!(
+                "Your review is requested on a proposal in {}: {}",
+                collection.name, proposal.description
+            ),
+        );
+    }
+}
+
+/// Alert a collection's admins when an operation stopped on cycles or storage limits
+pub fn resource_alert(collection_id: &str, error: &ClanopediaError) {
+    let kind = match error {
+        ClanopediaError::InsufficientCycles(_) => NotificationKind::CyclesAlert,
+        ClanopediaError::StorageError(_) => NotificationKind::QuotaAlert,
+        _ => return,
+    };
+    if let Ok(collection) = storage::get_collection(&collection_id.to_string()) {
+        notify_admins(&collection, kind, error.to_string(), None);
+    }
+}
+
+// ============================
+// INBOX
+// ============================
+
+/// The caller's notifications, newest first, starting below `before_id` when given
+pub fn get_my_notifications(
+    caller: Principal,
+    before_id: Option<u64>,
+    limit: u32,
+    unread_only: bool,
+) -> NotificationPage {
+    let all = storage::list_notifications(&caller);
+    let unread_count = all.iter().filter(|n| !n.read).count() as u64;
+    let notifications = all
+        .into_iter()
+        .rev()
+        .filter(|n| before_id.is_none_or(|before| n.id < before))
+        .filter(|n| !unread_only || !n.read)
+        .take((limit as usize).min(MAX_NOTIFICATION_PAGE))
+        .collect();
+    NotificationPage {
+        notifications,
+        unread_count,
+    }
+}
+
+/// Mark the given notifications read, or all of them when `ids` is empty. Returns how
+/// many changed.
+pub fn mark_read(caller: Principal, ids: Vec<u64>) -> ClanopediaResult<u64> {
+    if caller == Principal::anonymous() {
+        return Err(ClanopediaError::NotAuthorized);
+    }
+    let mut marked = 0;
+    for mut notification in storage::list_notifications(&caller) {
+        if notification.read || !(ids.is_empty() || ids.contains(&notification.id)) {
+            continue;
+        }
+        notification.read = true;
+        storage::put_notification(&caller, &notification);
+        marked += 1;
+    }
+    Ok(marked)
+}
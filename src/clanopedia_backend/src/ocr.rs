@@ -0,0 +1,258 @@
+// src/clanopedia_backend/src/ocr.rs
+
+use ic_cdk::api::management_canister::http_request::{
+    CanisterHttpRequestArgument, HttpHeader, HttpMethod, HttpResponse, TransformArgs,
+    TransformContext, TransformFunc,
+};
+use ic_cdk::query;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+use crate::external::blueband::ContentType;
+use crate::{
+    cycles,
+    extractor::{
+        file_extractor, sanitize_content, upload_provenance, validate_content_size,
+        ExtractionError, FileType,
+    },
+    outcall_budget,
+    outcall_pricing::{self, http_request},
+    proxy, secrets,
+    types::{ClanopediaError, ClanopediaResult, Collection, OcrConfig, OcrOutcome},
+    AddDocumentRequest,
+};
+
+pub const DEFAULT_OCR_PAGES: u32 = 20;
+const MAX_OCR_PAGES: u32 = 50;
+const MAX_OCR_IMAGE_BYTES: usize = 1_900_000; // Under the 2MB outcall request limit
+const OCR_MAX_RESPONSE_BYTES: u64 = 64 * 1024;
+
+pub fn validate_ocr(config: &Option<OcrConfig>, encrypted: bool) -> ClanopediaResult<()> {
+    let Some(config) = config else {
+        return Ok(());
+    };
+
+    // The proxy would receive page images the collection is meant to keep private
+    if encrypted {
+        return Err(ClanopediaError::InvalidOperation(
+            "Encrypted collections cannot use OCR".to_string(),
+        ));
+    }
+
+    proxy::validate_proxy_url(config.proxy_url.trim())?;
+
+    if let Some(max) = config.max_pages {
+        if max == 0 || max > MAX_OCR_PAGES {
+            return Err(ClanopediaError::InvalidInput(format!(
+                "max_pages must be between 1 and {}",
+                MAX_OCR_PAGES
+            )));
+        }
+    }
+    Ok(())
+}
+
+fn max_pages(config: &OcrConfig) -> u32 {
+    config.max_pages.unwrap_or(DEFAULT_OCR_PAGES)
+}
+
+/// Cycles for one OCR outcall carrying `image_bytes`, with a 2x buffer
+fn outcall_cycles(image_bytes: usize) -> u128 {
+    let request_size = image_bytes as u128 + 1_000; // Image plus headers
+    outcall_pricing::http_fee(request_size, OCR_MAX_RESPONSE_BYTES) * 2
+}
+
+/// Read an image-only PDF through the collection's OCR proxy. Returns `None` when OCR does
+/// not apply (no OCR configured, or not a PDF) so the caller keeps its original error.
+pub async fn ocr_fallback(
+    collection: &Collection,
+    file_data: &[u8],
+    filename: &str,
+) -> Option<ClanopediaResult<(Vec<AddDocumentRequest>, OcrOutcome)>> {
+    let config = collection.ocr.as_ref()?;
+    if FileType::from_file(filename, file_data) != FileType::Pdf {
+        return None;
+    }
+    Some(ocr_pdf(collection, config, file_data, filename).await)
+}
+
+async fn ocr_pdf(
+    collection: &Collection,
+    config: &OcrConfig,
+    file_data: &[u8],
+    filename: &str,
+) -> ClanopediaResult<(Vec<AddDocumentRequest>, OcrOutcome)> {
+    let (page_count, images) = file_extractor::pdf_page_images(file_data)?;
+    let images: Vec<_> = images
+        .into_iter()
+        .filter(|image| image.data.len() <= MAX_OCR_IMAGE_BYTES)
+        .take(max_pages(config) as usize)
+        .collect();
+    if images.is_empty() {
+        return Err(ExtractionError::parse(
+            "pdf",
+            "No extractable text found in PDF, and no page holds a JPEG or JPEG 2000 image to OCR",
+        )
+        .into());
+    }
+
+    let cost: u128 = images
+        .iter()
+        .map(|image| outcall_cycles(image.data.len()))
+        .sum();
+    cycles::validate_optional_spend(cost)?;
+    let api_key = secrets::resolve_secret(&collection.id, config.api_key_secret.as_deref())?;
+    outcall_budget::spend(&collection.id, images.len() as u32)?;
+
+    let mut outcome = OcrOutcome {
+        recognized_pages: 0,
+        failed_pages: 0,
+        skipped_pages: page_count - images.len() as u32,
+        confidence: 0.0,
+        estimated_cycles: cost as u64,
+        error: None,
+    };
+    let mut text = String::new();
+    let mut weighted_confidence = 0.0f64;
+    let mut recognized_chars = 0.0f64;
+
+    for image in &images {
+        match request_ocr(config, api_key.as_deref(), image).await {
+            Ok((page_text, confidence)) => {
+                let chars = page_text.chars().count() as f64;
+                weighted_confidence += confidence as f64 * chars;
+                recognized_chars += chars;
+                outcome.recognized_pages += 1;
+                text.push_str(&page_text);
+                text.push('\n');
+            }
+            Err(e) => {
+                outcome.failed_pages += 1;
+                outcome.error = Some(format!("Page {}: {}", image.page, e));
+            }
+        }
+    }
+
+    let content = sanitize_content(&text);
+    if content.is_empty() {
+        let message = format!(
+            "OCR recognized no text{}",
+            outcome
+                .error
+                .as_ref()
+                .map(|e| format!(": {}", e))
+                .unwrap_or_default()
+        );
+        return Err(ExtractionError::parse("pdf", message).into());
+    }
+    validate_content_size(&collection.id, &content)?;
+
+    outcome.confidence = (weighted_confidence / recognized_chars.max(1.0)) as f32;
+
+    let document = AddDocumentRequest {
+        collection_id: collection.id.clone(),
+        title: filename
+            .rsplit_once('.')
+            .map_or(filename, |(stem, _)| stem)
+            .to_string(),
+        content,
+        content_type: Some(ContentType::PlainText),
+        source_url: None,
+        author: None,
+        tags: Some(vec![
+            "ocr".to_string(),
+            format!("ocr-confidence:{:.2}", outcome.confidence),
+        ]),
+        outline: None,
+        summary: None,
+        license: None,
+        provenance: Some(upload_provenance("ocr")),
+    };
+
+    Ok((vec![document], outcome))
+}
+
+// Like summaries, the proxy must answer identically on every replica, so the request
+// carries an idempotency key the proxy can cache its first response under
+async fn request_ocr(
+    config: &OcrConfig,
+    api_key: Option<&str>,
+    image: &file_extractor::PageImage,
+) -> ClanopediaResult<(String, f32)> {
+    let mut headers = vec![
+        HttpHeader {
+            name: "Content-Type".to_string(),
+            value: image.mime.to_string(),
+        },
+        HttpHeader {
+            name: "User-Agent".to_string(),
+            value: "IC-Clanopedia/1.0".to_string(),
+        },
+        HttpHeader {
+            name: "Idempotency-Key".to_string(),
+            value: hex::encode(Sha256::digest(&image.data)),
+        },
+    ];
+    if let Some(key) = api_key {
+        headers.push(HttpHeader {
+            name: "Authorization".to_string(),
+            value: format!("Bearer {}", key),
+        });
+    }
+
+    let request = CanisterHttpRequestArgument {
+        url: config.proxy_url.trim().to_string(),
+        method: HttpMethod::POST,
+        body: Some(image.data.clone()),
+        max_response_bytes: Some(OCR_MAX_RESPONSE_BYTES),
+        transform: Some(TransformContext {
+            function: TransformFunc(candid::Func {
+                principal: ic_cdk::api::id(),
+                method: "transform_ocr_response".to_string(),
+            }),
+            context: vec![],
+        }),
+        headers,
+    };
+
+    let (response,) = http_request(request, outcall_cycles(image.data.len()))
+        .await
+        .map_err(|(code, message)| {
+            let retryable = message.contains("SysTransient") || message.contains("timeout");
+            ExtractionError::network(
+                retryable,
+                format!("OCR request failed: {:?} - {}", code, message),
+            )
+        })?;
+
+    let status = response.status.to_string().parse::<u32>().unwrap_or(0);
+    if !(200..300).contains(&status) {
+        return Err(ExtractionError::network(
+            status >= 500,
+            format!("OCR proxy returned {}", response.status),
+        )
+        .into());
+    }
+
+    let json: Value = serde_json::from_slice(&response.body)
+        .map_err(|e| ExtractionError::parse("json", format!("Invalid OCR response: {}", e)))?;
+    let text = json
+        .get("text")
+        .and_then(Value::as_str)
+        .ok_or_else(|| ExtractionError::parse("json", "OCR response has no text"))?
+        .to_string();
+    let confidence = json
+        .get("confidence")
+        .and_then(Value::as_f64)
+        .unwrap_or(0.0)
+        .clamp(0.0, 1.0) as f32;
+    Ok((text, confidence))
+}
+
+/// Only the body is used, so every header is dropped to keep replicas in agreement
+#[query]
+fn transform_ocr_response(args: TransformArgs) -> HttpResponse {
+    let mut response = args.response;
+    response.headers.clear();
+    response
+}
@@ -0,0 +1,176 @@
+// src/clanopedia_backend/src/outcall_budget.rs
+//
+// Token buckets in front of URL extraction's HTTP outcalls, one for the whole canister and
+// one per collection, so an extraction retried in a loop cannot exhaust external API quotas
+// or the cycle balance. Limits are stored; bucket levels live on the heap and start full
+// again after an upgrade.
+
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+
+use candid::Principal;
+use ic_cdk::api::time;
+
+use crate::{
+    config_history,
+    proxy::require_controller,
+    storage,
+    types::{
+        ClanopediaError, ClanopediaResult, CollectionId, ConfigSetting, OutcallBudget,
+        OutcallBudgetStatus,
+    },
+};
+
+const HOUR_NANOS: f64 = 3_600_000_000_000.0;
+const MAX_OUTCALLS_PER_HOUR: u32 = 100_000;
+const MAX_COLLECTION_OVERRIDES: usize = 1_000;
+
+#[derive(Clone, Copy)]
+struct Bucket {
+    tokens: f64,
+    updated_at: u64,
+}
+
+impl Bucket {
+    fn full(per_hour: u32, now: u64) -> Self {
+        Bucket {
+            tokens: per_hour as f64,
+            updated_at: now,
+        }
+    }
+
+    // Top up for the time since the last update; also applies a lowered limit at once
+    fn refill(&mut self, per_hour: u32, now: u64) -> f64 {
+        let elapsed = now.saturating_sub(self.updated_at) as f64;
+        self.tokens = (self.tokens + elapsed * per_hour as f64 / HOUR_NANOS).min(per_hour as f64);
+        self.updated_at = now;
+        self.tokens
+    }
+}
+
+thread_local! {
+    // Keyed by collection id; None is the canister-wide bucket
+    static BUCKETS: RefCell<HashMap<Option<CollectionId>, Bucket>> = RefCell::new(HashMap::new());
+}
+
+fn available(key: Option<&str>, per_hour: u32, now: u64) -> f64 {
+    BUCKETS.with(|b| {
+        b.borrow()
+            .get(&key.map(str::to_string))
+            .copied()
+            .unwrap_or_else(|| Bucket::full(per_hour, now))
+            .refill(per_hour, now)
+    })
+}
+
+fn exhausted(scope: &str, per_hour: u32, tokens: f64, needed: u32) -> ClanopediaError {
+    if needed > per_hour {
+        return ClanopediaError::RateLimited(format!(
+            "{} outcall budget of {} per hour does not cover the {} outcalls this needs",
+            scope, per_hour, needed
+        ));
+    }
+    let wait_nanos = (needed as f64 - tokens) * HOUR_NANOS / per_hour as f64;
+    ClanopediaError::RateLimited(format!(
+        "{} outcall budget of {} per hour is used up; retry in {}s",
+        scope,
+        per_hour,
+        (wait_nanos / 1e9).ceil() as u64
+    ))
+}
+
+/// Take `outcalls` from both the canister-wide and the collection's allowance, or neither
+pub fn spend(collection_id: &str, outcalls: u32) -> ClanopediaResult<()> {
+    let budget = storage::get_outcall_budget();
+    let collection_limit = budget.collection_limit(collection_id);
+    let needed = outcalls as f64;
+    let now = time();
+
+    BUCKETS.with(|b| {
+        let mut buckets = b.borrow_mut();
+        let canister = buckets
+            .entry(None)
+            .or_insert_with(|| Bucket::full(budget.canister_per_hour, now))
+            .refill(budget.canister_per_hour, now);
+        if canister < needed {
+            return Err(exhausted(
+                "Canister",
+                budget.canister_per_hour,
+                canister,
+                outcalls,
+            ));
+        }
+        let collection = buckets
+            .entry(Some(collection_id.to_string()))
+            .or_insert_with(|| Bucket::full(collection_limit, now));
+        if collection.refill(collection_limit, now) < needed {
+            return Err(exhausted(
+                "Collection",
+                collection_limit,
+                collection.tokens,
+                outcalls,
+            ));
+        }
+
+        collection.tokens -= needed;
+        if let Some(canister) = buckets.get_mut(&None) {
+            canister.tokens -= needed;
+        }
+        Ok(())
+    })
+}
+
+pub fn get_status(collection_id: Option<String>) -> OutcallBudgetStatus {
+    let budget = storage::get_outcall_budget();
+    let now = time();
+    let collection_limit = collection_id
+        .as_deref()
+        .map(|id| budget.collection_limit(id));
+    let collection_available = collection_id
+        .as_deref()
+        .zip(collection_limit)
+        .map(|(id, limit)| available(Some(id), limit, now) as u32);
+
+    OutcallBudgetStatus {
+        canister_available: available(None, budget.canister_per_hour, now) as u32,
+        budget,
+        collection_limit,
+        collection_available,
+    }
+}
+
+/// Replace the outcall limits; controllers only
+pub fn set_budget(caller: Principal, budget: OutcallBudget) -> ClanopediaResult<()> {
+    require_controller(caller)?;
+
+    let too_high = [budget.canister_per_hour, budget.collection_per_hour]
+        .into_iter()
+        .chain(budget.collection_overrides.iter().map(|(_, limit)| *limit))
+        .any(|limit| limit > MAX_OUTCALLS_PER_HOUR);
+    if too_high {
+        return Err(ClanopediaError::InvalidInput(format!(
+            "Outcall limits are capped at {} per hour",
+            MAX_OUTCALLS_PER_HOUR
+        )));
+    }
+    if budget.collection_overrides.len() > MAX_COLLECTION_OVERRIDES {
+        return Err(ClanopediaError::InvalidInput(format!(
+            "At most {} collection overrides are allowed",
+            MAX_COLLECTION_OVERRIDES
+        )));
+    }
+    let mut seen = HashSet::new();
+    for (collection_id, _) in &budget.collection_overrides {
+        if !seen.insert(collection_id) {
+            return Err(ClanopediaError::InvalidInput(format!(
+                "Collection {} is overridden more than once",
+                collection_id
+            )));
+        }
+        storage::get_collection(collection_id)?;
+    }
+
+    storage::set_outcall_budget(budget.clone());
+    config_history::record(caller, None, ConfigSetting::OutcallBudget(budget))?;
+    Ok(())
+}
@@ -0,0 +1,124 @@
+// src/clanopedia_backend/src/outcall_pricing.rs
+//
+// HTTPS outcall fees scale with the number of nodes in the subnet, which a canister cannot
+// query. A size set by a controller is used as is. Otherwise every outcall made through
+// `http_request` below solves the published fee formula for the node count from the cycles
+// it was actually charged, and later calls are priced with that. Until then the price
+// assumes a 13-node subnet. The detected size lives on the heap and is learned again after
+// an upgrade.
+
+use std::cell::RefCell;
+
+use candid::Principal;
+use ic_cdk::api::call::{msg_cycles_refunded128, CallResult};
+use ic_cdk::api::management_canister::http_request::{
+    self as management, CanisterHttpRequestArgument, HttpResponse,
+};
+
+use crate::{
+    config_history, logging,
+    proxy::require_controller,
+    storage,
+    types::{ClanopediaError, ClanopediaResult, ConfigSetting, SubnetSize},
+};
+
+pub const DEFAULT_SUBNET_NODES: u32 = 13;
+const MIN_SUBNET_NODES: u32 = 4;
+const MAX_SUBNET_NODES: u32 = 64;
+const DETECTION_TOLERANCE: f64 = 0.1; // How far from a whole node count a solution may be
+
+thread_local! {
+    static DETECTED_NODES: RefCell<Option<u32>> = const { RefCell::new(None) };
+}
+
+/// The node count outcall fees are computed for
+pub fn subnet_nodes() -> u32 {
+    storage::get_subnet_size()
+        .or_else(|| DETECTED_NODES.with(|d| *d.borrow()))
+        .unwrap_or(DEFAULT_SUBNET_NODES)
+}
+
+fn fee_for(nodes: u128, request_bytes: u128, max_response_bytes: u64) -> u128 {
+    let base_fee = (3_000_000 + 60_000 * nodes) * nodes;
+    base_fee + 400 * nodes * request_bytes + 800 * nodes * max_response_bytes as u128
+}
+
+/// The fee for one outcall of `request_bytes` with a response limit of `max_response_bytes`,
+/// before any buffer
+pub fn http_fee(request_bytes: u128, max_response_bytes: u64) -> u128 {
+    fee_for(subnet_nodes() as u128, request_bytes, max_response_bytes)
+}
+
+/// The bytes of `request` the fee is charged on
+pub fn request_bytes(request: &CanisterHttpRequestArgument) -> u128 {
+    let headers: usize = request
+        .headers
+        .iter()
+        .map(|h| h.name.len() + h.value.len())
+        .sum();
+    let transform = request
+        .transform
+        .as_ref()
+        .map_or(0, |t| t.function.0.method.len() + t.context.len());
+    (request.url.len() + headers + request.body.as_ref().map_or(0, Vec::len) + transform) as u128
+}
+
+// The node count whose fee for the request is `charged`, if it comes out whole
+fn solve_nodes(request_bytes: u128, max_response_bytes: u64, charged: u128) -> Option<u32> {
+    // charged = 60_000 n² + (3_000_000 + 400 request + 800 response) n
+    let a = 60_000.0;
+    let b = 3_000_000.0 + 400.0 * request_bytes as f64 + 800.0 * max_response_bytes as f64;
+    let n = (-b + (b * b + 4.0 * a * charged as f64).sqrt()) / (2.0 * a);
+    let whole = n.round();
+    ((n - whole).abs() <= DETECTION_TOLERANCE)
+        .then_some(whole as u32)
+        .filter(|n| (MIN_SUBNET_NODES..=MAX_SUBNET_NODES).contains(n))
+}
+
+/// `http_request` on the management canister, learning the subnet size from what it charged
+pub async fn http_request(
+    request: CanisterHttpRequestArgument,
+    cycles: u128,
+) -> CallResult<(HttpResponse,)> {
+    let bytes = request_bytes(&request);
+    let max_response_bytes = request.max_response_bytes.unwrap_or(2_000_000);
+    let result = management::http_request(request, cycles).await;
+    if result.is_ok() {
+        let charged = cycles.saturating_sub(msg_cycles_refunded128());
+        if let Some(nodes) = solve_nodes(bytes, max_response_bytes, charged) {
+            let previous = DETECTED_NODES.with(|d| d.borrow_mut().replace(nodes));
+            if previous != Some(nodes) {
+                logging::info(
+                    "outcall_pricing",
+                    None,
+                    format!("Outcall fees match a {}-node subnet", nodes),
+                );
+            }
+        }
+    }
+    result
+}
+
+pub fn get_subnet_size() -> SubnetSize {
+    let configured = storage::get_subnet_size();
+    SubnetSize {
+        configured,
+        detected: DETECTED_NODES.with(|d| *d.borrow()),
+        effective: subnet_nodes(),
+    }
+}
+
+/// Set the node count outcalls are priced for, or clear it to use the detected size;
+/// controllers only
+pub fn set_subnet_size(caller: Principal, nodes: Option<u32>) -> ClanopediaResult<()> {
+    require_controller(caller)?;
+    if nodes.is_some_and(|n| !(MIN_SUBNET_NODES..=MAX_SUBNET_NODES).contains(&n)) {
+        return Err(ClanopediaError::InvalidInput(format!(
+            "Subnet size must be between {} and {} nodes",
+            MIN_SUBNET_NODES, MAX_SUBNET_NODES
+        )));
+    }
+    storage::set_subnet_size(nodes);
+    config_history::record(caller, None, ConfigSetting::SubnetSize(nodes))?;
+    Ok(())
+}
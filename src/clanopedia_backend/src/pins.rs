@@ -0,0 +1,97 @@
+// src/clanopedia_backend/src/pins.rs
+//
+// A collection's curated "start here" set: documents pinned in the order frontends show them
+// before any search, as onboarding. The whole set is replaced by a `SetPinnedDocuments`
+// proposal, which goes through the usual vote but is never timelocked and carries nothing but
+// document IDs. A pinned document that is trashed is left out until it is restored, and is
+// unpinned when purged.
+
+use ic_cdk::api::time;
+
+use crate::{
+    storage, trash,
+    types::{
+        ClanopediaError, ClanopediaResult, Collection, DocumentId, PinnedDocument, ProposalId,
+        StorageMap,
+    },
+};
+
+const MAX_PINNED_DOCUMENTS: usize = 20;
+
+/// Check a `SetPinnedDocuments` proposal: at most `MAX_PINNED_DOCUMENTS` distinct documents,
+/// each known to the collection and out of the trash. An empty set unpins everything.
+pub fn validate_set_pinned(
+    collection: &Collection,
+    document_ids: &[DocumentId],
+) -> ClanopediaResult<()> {
+    if document_ids.len() > MAX_PINNED_DOCUMENTS {
+        return Err(ClanopediaError::InvalidInput(format!(
+            "At most {} documents can be pinned",
+            MAX_PINNED_DOCUMENTS
+        )));
+    }
+    let known = storage::list_known_document_ids(&collection.id);
+    for (i, document_id) in document_ids.iter().enumerate() {
+        if document_ids[..i].contains(document_id) {
+            return Err(ClanopediaError::InvalidInput(format!(
+                "Document {} is listed more than once",
+                document_id
+            )));
+        }
+        if !known.contains(document_id) {
+            return Err(ClanopediaError::NotFound(format!(
+                "Document {} not found",
+                document_id
+            )));
+        }
+        if trash::is_document_trashed(&collection.id, document_id) {
+            return Err(ClanopediaError::InvalidOperation(format!(
+                "Document {} is in the trash",
+                document_id
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Apply an approved `SetPinnedDocuments` proposal, replacing the pinned set
+pub fn execute_set_pinned(
+    collection_id: &str,
+    proposal_id: &ProposalId,
+    document_ids: &[DocumentId],
+) -> ClanopediaResult<()> {
+    let collection = storage::get_collection(&collection_id.to_string())?;
+    // Documents may have been trashed since the proposal was created
+    validate_set_pinned(&collection, document_ids)?;
+    storage::ensure_capacity(StorageMap::PinnedDocuments, document_ids.len() as u64)?;
+
+    // Documents already pinned keep when they were first pinned
+    let current = storage::list_pinned_documents(collection_id);
+    let now = time();
+    let pinned: Vec<PinnedDocument> = document_ids
+        .iter()
+        .map(|document_id| {
+            current
+                .iter()
+                .find(|p| &p.document_id == document_id)
+                .cloned()
+                .unwrap_or_else(|| PinnedDocument {
+                    document_id: document_id.clone(),
+                    pinned_at: now,
+                    proposal_id: proposal_id.clone(),
+                })
+        })
+        .collect();
+    storage::set_pinned_documents(collection_id, &pinned);
+    Ok(())
+}
+
+/// The pinned documents in display order, leaving out any in the trash
+pub fn get_pinned_documents(collection_id: &str) -> ClanopediaResult<Vec<PinnedDocument>> {
+    let collection = storage::get_collection(&collection_id.to_string())?;
+    trash::ensure_not_trashed(&collection)?;
+    Ok(storage::list_pinned_documents(collection_id)
+        .into_iter()
+        .filter(|p| !trash::is_document_trashed(collection_id, &p.document_id))
+        .collect())
+}
@@ -0,0 +1,106 @@
+// src/clanopedia_backend/src/proposal_search.rs
+//
+// Finding a collection's proposals by type, creator, status and creation time, e.g. every
+// admin change made in a quarter. Candidates are read from the narrowest index range the
+// filter allows, the creator's, the types' or the status's, or else the creation-time range,
+// and checked against the rest of the filter. Executed, rejected and expired proposals are
+// not kept, so the proposals searched are those `get_proposals_endpoint` returns.
+
+use crate::{
+    storage,
+    types::{
+        ClanopediaError, ClanopediaResult, Proposal, ProposalFilter, ProposalId,
+        ProposalSearchPage, ProposalType,
+    },
+};
+
+const DEFAULT_LIMIT: u32 = 50;
+const MAX_LIMIT: u32 = 200;
+
+fn matches(filter: &ProposalFilter, proposal: &Proposal) -> bool {
+    let name = proposal.proposal_type.name();
+    let created_at = proposal.created_at;
+    (filter.types.is_empty() || filter.types.iter().any(|t| t == name))
+        && filter.creator.is_none_or(|c| c == proposal.creator)
+        && filter.created_after.is_none_or(|after| created_at >= after)
+        && filter
+            .created_before
+            .is_none_or(|before| created_at < before)
+        && filter.status.as_ref().is_none_or(|s| *s == proposal.status)
+}
+
+fn candidate_ids(collection_id: &str, filter: &ProposalFilter) -> Vec<ProposalId> {
+    let (after, before) = (filter.created_after, filter.created_before);
+    if let Some(creator) = filter.creator {
+        let creator = creator.to_text();
+        return storage::indexed_proposal_ids(collection_id, "c", Some(&creator), after, before);
+    }
+    if !filter.types.is_empty() {
+        return filter
+            .types
+            .iter()
+            .flat_map(|t| storage::indexed_proposal_ids(collection_id, "t", Some(t), after, before))
+            .collect();
+    }
+    if let Some(status) = &filter.status {
+        let status = format!("{:?}", status);
+        return storage::indexed_proposal_ids(collection_id, "s", Some(&status), after, before);
+    }
+    storage::indexed_proposal_ids(collection_id, "d", None, after, before)
+}
+
+/// The collection's proposals matching `filter`, newest first
+pub fn search_proposals(
+    collection_id: &str,
+    mut filter: ProposalFilter,
+) -> ClanopediaResult<ProposalSearchPage> {
+    storage::get_collection(&collection_id.to_string())?;
+
+    if let Some(unknown) = filter
+        .types
+        .iter()
+        .find(|t| !ProposalType::NAMES.contains(&t.as_str()))
+    {
+        return Err(ClanopediaError::InvalidInput(format!(
+            "Unknown proposal type {}; expected one of {}",
+            unknown,
+            ProposalType::NAMES.join(", ")
+        )));
+    }
+    filter.types.sort();
+    filter.types.dedup();
+    if let (Some(after), Some(before)) = (filter.created_after, filter.created_before) {
+        if after >= before {
+            return Err(ClanopediaError::InvalidInput(
+                "created_after must be earlier than created_before".to_string(),
+            ));
+        }
+    }
+
+    // Until every stored proposal is indexed, the index could miss some
+    let mut proposals: Vec<Proposal> = if storage::proposal_index_ready() {
+        candidate_ids(collection_id, &filter)
+            .into_iter()
+            .filter_map(|id| storage::get_proposal(collection_id, &id))
+            .collect()
+    } else {
+        storage::list_proposals(collection_id)
+            .into_values()
+            .collect()
+    };
+    proposals.retain(|p| matches(&filter, p));
+    proposals.sort_by(|a, b| {
+        b.created_at
+            .cmp(&a.created_at)
+            .then_with(|| a.id.cmp(&b.id))
+    });
+
+    let total = proposals.len() as u64;
+    let limit = filter.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT) as usize;
+    let proposals = proposals
+        .into_iter()
+        .skip(filter.offset.unwrap_or(0) as usize)
+        .take(limit)
+        .collect();
+    Ok(ProposalSearchPage { proposals, total })
+}
@@ -0,0 +1,342 @@
+// src/clanopedia_backend/src/proxy.rs
+
+use candid::Principal;
+
+use crate::{
+    config_history,
+    external::blueband::{self, DEFAULT_PROXY_URL},
+    logging,
+    secrets::{self, EMBEDDING_PROXY_SECRET},
+    storage,
+    types::{
+        ClanopediaError, ClanopediaResult, Collection, CollectionConfig, ConfigSetting,
+        EmbeddingProxyConfig, ProposalType, StorageMap,
+    },
+    vault,
+};
+
+// ============================
+// ALLOWLIST
+// ============================
+
+// The allowlist is canister-wide, so only controllers may change it
+pub fn require_controller(caller: Principal) -> ClanopediaResult<()> {
+    if ic_cdk::api::is_controller(&caller) {
+        Ok(())
+    } else {
+        Err(ClanopediaError::NotAuthorized)
+    }
+}
+
+/// Allowlist an https origin, optionally narrowed to a path. The entry is stored normalized.
+pub fn add_allowlist_entry(caller: Principal, url_prefix: String) -> ClanopediaResult<()> {
+    require_controller(caller)?;
+
+    let url_prefix = ProxyUrl::parse(url_prefix.trim())
+        .filter(|prefix| !prefix.has_query)
+        .ok_or_else(|| {
+            ClanopediaError::InvalidInput(
+                "Allowlisted proxy prefixes must be https URLs without credentials or a query"
+                    .to_string(),
+            )
+        })?
+        .to_string();
+
+    storage::ensure_capacity(StorageMap::ProxyAllowlist, 1)?;
+    storage::add_proxy_allowlist_entry(url_prefix);
+    config_history::record(
+        caller,
+        None,
+        ConfigSetting::ProxyAllowlist(storage::list_proxy_allowlist()),
+    )
+}
+
+pub fn remove_allowlist_entry(caller: Principal, url_prefix: &str) -> ClanopediaResult<()> {
+    require_controller(caller)?;
+    let before = storage::list_proxy_allowlist();
+    let url_prefix = url_prefix.trim();
+    storage::remove_proxy_allowlist_entry(url_prefix);
+    if let Some(normalized) = ProxyUrl::parse(url_prefix) {
+        storage::remove_proxy_allowlist_entry(&normalized.to_string());
+    }
+    let after = storage::list_proxy_allowlist();
+    if after != before {
+        config_history::record(caller, None, ConfigSetting::ProxyAllowlist(after))?;
+    }
+    Ok(())
+}
+
+// An https URL split into the parts the allowlist compares. Anything with credentials, a
+// backslash or a malformed port is refused rather than guessed at.
+struct ProxyUrl<'a> {
+    host: String,
+    port: u16,
+    path: &'a str,
+    has_query: bool,
+}
+
+impl<'a> ProxyUrl<'a> {
+    fn parse(url: &'a str) -> Option<Self> {
+        let rest = url
+            .get(..8)
+            .filter(|scheme| scheme.eq_ignore_ascii_case("https://"))
+            .map(|_| &url[8..])?;
+        if rest.contains('\\') || rest.chars().any(|c| c.is_whitespace() || c.is_control()) {
+            return None;
+        }
+        let authority_end = rest.find(['/', '?', '#']).unwrap_or(rest.len());
+        let (authority, tail) = rest.split_at(authority_end);
+        if authority.contains('@') {
+            return None;
+        }
+        let (host, port) = match authority.rsplit_once(':') {
+            Some((host, port)) if !port.contains(']') => (host, Some(port.parse::<u16>().ok()?)),
+            _ => (authority, None),
+        };
+        let host = host.trim_end_matches('.').to_ascii_lowercase();
+        if host.is_empty() || (host.contains(':') && !host.starts_with('[')) {
+            return None;
+        }
+        let path_end = tail.find(['?', '#']).unwrap_or(tail.len());
+        let path = match &tail[..path_end] {
+            "" => "/",
+            path => path,
+        };
+        Some(Self {
+            host,
+            port: port.unwrap_or(443),
+            path,
+            has_query: path_end < tail.len(),
+        })
+    }
+
+    // Same origin, and the path is the prefix's or lies below it
+    fn is_under(&self, prefix: &ProxyUrl) -> bool {
+        if self.host != prefix.host || self.port != prefix.port {
+            return false;
+        }
+        match self.path.strip_prefix(prefix.path) {
+            Some(rest) => rest.is_empty() || prefix.path.ends_with('/') || rest.starts_with('/'),
+            None => false,
+        }
+    }
+}
+
+impl std::fmt::Display for ProxyUrl<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.port {
+            443 => write!(f, "https://{}{}", self.host, self.path),
+            port => write!(f, "https://{}:{}{}", self.host, port, self.path),
+        }
+    }
+}
+
+/// A proxy URL is accepted when it is the default proxy or falls under an allowlisted prefix
+pub fn validate_proxy_url(url: &str) -> ClanopediaResult<()> {
+    if url == DEFAULT_PROXY_URL {
+        return Ok(());
+    }
+
+    let Some(parsed) = ProxyUrl::parse(url) else {
+        return Err(ClanopediaError::InvalidInput(
+            "Embedding proxy URL must be a plain https URL".to_string(),
+        ));
+    };
+
+    if storage::list_proxy_allowlist()
+        .iter()
+        .filter_map(|prefix| ProxyUrl::parse(prefix))
+        .any(|prefix| parsed.is_under(&prefix))
+    {
+        Ok(())
+    } else {
+        Err(ClanopediaError::InvalidInput(format!(
+            "Embedding proxy {} is not on the allowlist",
+            url
+        )))
+    }
+}
+
+// ============================
+// COLLECTION PROXIES
+// ============================
+
+/// Validate a requested proxy config and return the URL and credential to hand to Blueband
+pub fn resolve_proxy_config(
+    proxy: &Option<EmbeddingProxyConfig>,
+) -> ClanopediaResult<(Option<String>, Option<String>)> {
+    match proxy {
+        Some(config) => {
+            let url = config.url.trim().to_string();
+            validate_proxy_url(&url)?;
+            let api_key = config.api_key.clone().filter(|k| !k.is_empty());
+            Ok((Some(url), api_key))
+        }
+        None => Ok((None, None)),
+    }
+}
+
+/// Take a non-empty proxy key out of a config that is kept for later, in a proposal or a
+/// creation request, and seal it, so the stored record, and everything that returns or
+/// exports it, never holds it in plaintext. An empty key, which clears the stored one, is
+/// left in the config.
+pub async fn seal_config_key(config: &mut CollectionConfig) -> ClanopediaResult<Option<Vec<u8>>> {
+    match take_config_key(config) {
+        Some(key) => vault::seal(key.as_bytes()).await.map(Some),
+        None => Ok(None),
+    }
+}
+
+pub async fn seal_proposal_key(
+    proposal_type: &mut ProposalType,
+) -> ClanopediaResult<Option<Vec<u8>>> {
+    match proposal_type {
+        ProposalType::UpdateCollection { config } => seal_config_key(config).await,
+        _ => Ok(None),
+    }
+}
+
+fn take_config_key(config: &mut CollectionConfig) -> Option<String> {
+    let proxy = config.embedding_proxy.as_mut()?;
+    match &proxy.api_key {
+        Some(key) if !key.is_empty() => proxy.api_key.take(),
+        _ => None,
+    }
+}
+
+/// Put a sealed proxy key back into the config it was taken from, to apply it
+pub fn unseal_config_key(
+    mut config: CollectionConfig,
+    sealed: &Option<Vec<u8>>,
+) -> ClanopediaResult<CollectionConfig> {
+    if let (Some(sealed), Some(proxy)) = (sealed, config.embedding_proxy.as_mut()) {
+        let key = String::from_utf8(vault::open(sealed)?).map_err(|e| {
+            ClanopediaError::InvalidInput(format!("Sealed proxy key is invalid: {}", e))
+        })?;
+        proxy.api_key = Some(key);
+    }
+    Ok(config)
+}
+
+// Seal a key found in plaintext during an upgrade, or drop it when it cannot be sealed, so
+// the config keeps the collection's current key
+fn seal_legacy_key(key: String, context: &str, collection_id: Option<&str>) -> Option<Vec<u8>> {
+    match vault::seal_in_upgrade(key.as_bytes(), context.as_bytes()) {
+        Ok(sealed) => Some(sealed),
+        Err(e) => {
+            logging::warn(
+                "proxy",
+                collection_id,
+                format!("Dropped the proxy key of {}: {}", context, e),
+            );
+            None
+        }
+    }
+}
+
+/// Seal the plaintext keys of UpdateCollection proposals and creation requests stored
+/// before keys were sealed; run on upgrade
+pub fn seal_legacy_keys() {
+    for collection in storage::list_collections() {
+        for mut proposal in storage::list_proposals(&collection.id).into_values() {
+            let ProposalType::UpdateCollection { config } = &mut proposal.proposal_type else {
+                continue;
+            };
+            let Some(key) = take_config_key(config) else {
+                continue;
+            };
+            let context = format!("proposal:{}:{}", collection.id, proposal.id);
+            proposal.sealed_proxy_api_key = seal_legacy_key(key, &context, Some(&collection.id));
+            storage::put_proposal(&collection.id, &proposal);
+        }
+    }
+    for mut request in storage::list_creation_requests() {
+        let Some(key) = take_config_key(&mut request.config) else {
+            continue;
+        };
+        let context = format!("creation-request:{}", request.id);
+        request.sealed_proxy_api_key = seal_legacy_key(key, &context, None);
+        storage::put_creation_request(&request);
+    }
+}
+
+pub async fn store_proxy_api_key(
+    collection_id: &str,
+    api_key: Option<String>,
+    updated_by: Principal,
+) -> ClanopediaResult<()> {
+    match api_key {
+        Some(key) => {
+            secrets::store_secret(collection_id, EMBEDDING_PROXY_SECRET, &key, updated_by).await
+        }
+        None => {
+            storage::remove_secret(collection_id, EMBEDDING_PROXY_SECRET);
+            Ok(())
+        }
+    }
+}
+
+/// Apply a proxy change to the collection and push it to its Blueband settings.
+/// An `api_key` of `None` keeps the stored credential while the URL is unchanged;
+/// an empty string clears it.
+pub async fn update_collection_proxy(
+    collection: &mut Collection,
+    proxy: &Option<EmbeddingProxyConfig>,
+    updated_by: Principal,
+) -> ClanopediaResult<()> {
+    let new_url = proxy.as_ref().map(|p| p.url.trim().to_string());
+    if let Some(url) = &new_url {
+        validate_proxy_url(url)?;
+    }
+
+    let existing_key = secrets::get_secret(&collection.id, EMBEDDING_PROXY_SECRET)?;
+    let new_key = match proxy.as_ref().and_then(|p| p.api_key.clone()) {
+        Some(key) if key.is_empty() => None,
+        Some(key) => Some(key),
+        None if new_url == collection.embedding_proxy_url => existing_key.clone(),
+        None => None,
+    };
+
+    if new_url == collection.embedding_proxy_url && new_key == existing_key {
+        return Ok(());
+    }
+
+    blueband::update_collection_proxy(
+        &collection.blueband_collection_id,
+        new_url.clone(),
+        new_key.clone(),
+    )
+    .await
+    .map_err(ClanopediaError::BluebandError)?;
+
+    store_proxy_api_key(&collection.id, new_key, updated_by).await?;
+    collection.embedding_proxy_url = new_url;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ProxyUrl;
+
+    fn allowed(url: &str, prefix: &str) -> bool {
+        match (ProxyUrl::parse(url), ProxyUrl::parse(prefix)) {
+            (Some(url), Some(prefix)) => url.is_under(&prefix),
+            _ => false,
+        }
+    }
+
+    #[test]
+    fn proxy_urls_match_by_origin_and_path() {
+        let prefix = "https://api.example.com/v1";
+        assert!(allowed("https://api.example.com/v1", prefix));
+        assert!(allowed("https://API.example.com/v1/embed?x=1", prefix));
+        assert!(allowed("https://api.example.com:443/v1/embed", prefix));
+        assert!(!allowed("https://api.example.com/v1beta", prefix));
+        assert!(!allowed("https://api.example.com.attacker.io/v1", prefix));
+        assert!(!allowed("https://api.example.com@attacker.io/v1", prefix));
+        assert!(!allowed("https://api.example.com:8443/v1", prefix));
+        assert!(!allowed("https://api.example.com\\@attacker.io/v1", prefix));
+        assert!(!allowed("http://api.example.com/v1", prefix));
+        assert!(allowed("https://api.example.com/anything", "https://api.example.com"));
+    }
+}
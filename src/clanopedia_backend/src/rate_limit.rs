@@ -0,0 +1,44 @@
+// src/clanopedia_backend/src/rate_limit.rs
+
+use candid::Principal;
+use ic_cdk::api::time;
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use crate::types::{ClanopediaError, ClanopediaResult};
+
+// Call timestamps per (scope, principal). Kept on the heap: limits reset on upgrade.
+thread_local! {
+    static CALLS: RefCell<HashMap<(String, Principal), Vec<u64>>> = RefCell::new(HashMap::new());
+}
+
+/// Sliding-window limit of `max_calls` per `window_nanos`; records the call when allowed
+pub fn check_rate_limit(
+    scope: &str,
+    principal: Principal,
+    max_calls: usize,
+    window_nanos: u64,
+) -> ClanopediaResult<()> {
+    let now = time();
+    let window_start = now.saturating_sub(window_nanos);
+
+    CALLS.with(|calls| {
+        let mut calls = calls.borrow_mut();
+        let timestamps = calls.entry((scope.to_string(), principal)).or_default();
+        timestamps.retain(|t| *t > window_start);
+
+        if timestamps.len() >= max_calls {
+            let retry_in = timestamps[0] + window_nanos - now;
+            return Err(ClanopediaError::RateLimited(format!(
+                "{} is limited to {} calls per {}s; retry in {}s",
+                scope,
+                max_calls,
+                window_nanos / 1_000_000_000,
+                retry_in.div_ceil(1_000_000_000)
+            )));
+        }
+
+        timestamps.push(now);
+        Ok(())
+    })
+}
@@ -0,0 +1,215 @@
+// src/clanopedia_backend/src/reactions.rs
+//
+// Reactions on documents and proposals from signed-in principals. Each target keeps one
+// small counter record, and each principal a bitmask of the reactions they gave it, so
+// reacting twice changes nothing. Listings show the counters; admins can rank targets by a
+// reaction to gauge interest before writing a proposal. Encrypted collections take no
+// reactions, since the counters would reveal their document IDs.
+
+use candid::Principal;
+
+use crate::{
+    auth, encryption, rate_limit, storage, trash,
+    types::{
+        Capability, ClanopediaError, ClanopediaResult, Collection, Reaction, ReactionCount,
+        ReactionCounts, ReactionSummary, ReactionTarget, StorageMap,
+    },
+};
+
+const MAX_ID_CHARS: usize = 256;
+const MAX_TARGETS_PER_QUERY: usize = 100;
+const DEFAULT_SIGNAL_LIMIT: u32 = 20;
+const MAX_SIGNAL_LIMIT: u32 = 100;
+const REACT_CALLS_PER_WINDOW: usize = 60;
+const REACT_WINDOW_NANOS: u64 = 60 * 1_000_000_000;
+
+fn target_key(target: &ReactionTarget) -> String {
+    match target {
+        ReactionTarget::Document { document_id } => format!("d:{}", document_id),
+        ReactionTarget::Proposal { proposal_id } => format!("p:{}", proposal_id),
+    }
+}
+
+fn parse_target(key: &str) -> Option<ReactionTarget> {
+    match key.split_once(':')? {
+        ("d", id) => Some(ReactionTarget::Document {
+            document_id: id.to_string(),
+        }),
+        ("p", id) => Some(ReactionTarget::Proposal {
+            proposal_id: id.to_string(),
+        }),
+        _ => None,
+    }
+}
+
+fn visible_collection(collection_id: &str) -> ClanopediaResult<Collection> {
+    let collection = storage::get_collection(&collection_id.to_string())?;
+    trash::ensure_not_trashed(&collection)?;
+    if encryption::is_encrypted(&collection) {
+        return Err(ClanopediaError::InvalidOperation(
+            "Encrypted collections do not take reactions".to_string(),
+        ));
+    }
+    Ok(collection)
+}
+
+fn ensure_target_exists(collection_id: &str, target: &ReactionTarget) -> ClanopediaResult<()> {
+    match target {
+        ReactionTarget::Document { document_id } => {
+            if document_id.is_empty()
+                || document_id.len() > MAX_ID_CHARS
+                || trash::is_document_trashed(collection_id, document_id)
+            {
+                return Err(ClanopediaError::NotFound(format!(
+                    "Document {} not found",
+                    document_id
+                )));
+            }
+        }
+        ReactionTarget::Proposal { proposal_id } => {
+            storage::get_proposal(collection_id, proposal_id).ok_or_else(|| {
+                ClanopediaError::NotFound(format!("Proposal {} not found", proposal_id))
+            })?;
+        }
+    }
+    Ok(())
+}
+
+/// Counters of the reactions given at least once, in `Reaction::ALL` order
+pub fn counts_of(counts: &ReactionCounts) -> Vec<ReactionCount> {
+    Reaction::ALL
+        .iter()
+        .zip(counts.counts.iter())
+        .filter(|(_, count)| **count > 0)
+        .map(|(reaction, count)| ReactionCount {
+            reaction: *reaction,
+            count: *count,
+        })
+        .collect()
+}
+
+pub fn document_reactions(collection_id: &str, document_id: &str) -> Vec<ReactionCount> {
+    counts_of(&storage::get_reaction_counts(
+        collection_id,
+        &format!("d:{}", document_id),
+    ))
+}
+
+pub fn proposal_reactions(collection_id: &str, proposal_id: &str) -> Vec<ReactionCount> {
+    counts_of(&storage::get_reaction_counts(
+        collection_id,
+        &format!("p:{}", proposal_id),
+    ))
+}
+
+fn summary(collection_id: &str, target: ReactionTarget, caller: Principal) -> ReactionSummary {
+    let key = target_key(&target);
+    let given = storage::get_given_reactions(collection_id, &key, &caller);
+    ReactionSummary {
+        counts: counts_of(&storage::get_reaction_counts(collection_id, &key)),
+        mine: Reaction::ALL
+            .into_iter()
+            .filter(|r| given & (1 << r.index()) != 0)
+            .collect(),
+        target,
+    }
+}
+
+/// Give or take back a reaction; doing either twice has no further effect
+pub fn set_reaction(
+    caller: Principal,
+    collection_id: &str,
+    target: ReactionTarget,
+    reaction: Reaction,
+    given: bool,
+) -> ClanopediaResult<ReactionSummary> {
+    if caller == Principal::anonymous() {
+        return Err(ClanopediaError::NotAuthorized);
+    }
+    visible_collection(collection_id)?;
+    ensure_target_exists(collection_id, &target)?;
+    rate_limit::check_rate_limit("react", caller, REACT_CALLS_PER_WINDOW, REACT_WINDOW_NANOS)?;
+
+    let key = target_key(&target);
+    let bits = storage::get_given_reactions(collection_id, &key, &caller);
+    let bit = 1 << reaction.index();
+    if (bits & bit != 0) == given {
+        return Ok(summary(collection_id, target, caller));
+    }
+    if given && bits == 0 {
+        storage::ensure_capacity(StorageMap::Reactions, 1)?;
+    }
+
+    let mut counts = storage::get_reaction_counts(collection_id, &key);
+    if counts.counts.is_empty() {
+        storage::ensure_capacity(StorageMap::ReactionCounts, 1)?;
+    }
+    counts.counts.resize(Reaction::ALL.len(), 0);
+    let count = &mut counts.counts[reaction.index()];
+    *count = if given {
+        count.saturating_add(1)
+    } else {
+        count.saturating_sub(1)
+    };
+    storage::set_reaction_counts(collection_id, &key, counts);
+    storage::set_given_reactions(collection_id, &key, &caller, bits ^ bit);
+    Ok(summary(collection_id, target, caller))
+}
+
+/// Reactions on a page's worth of targets, with the caller's own
+pub fn get_reactions(
+    caller: Principal,
+    collection_id: &str,
+    targets: Vec<ReactionTarget>,
+) -> ClanopediaResult<Vec<ReactionSummary>> {
+    visible_collection(collection_id)?;
+    if targets.len() > MAX_TARGETS_PER_QUERY {
+        return Err(ClanopediaError::InvalidInput(format!(
+            "At most {} targets can be read at once",
+            MAX_TARGETS_PER_QUERY
+        )));
+    }
+    Ok(targets
+        .into_iter()
+        .map(|target| summary(collection_id, target, caller))
+        .collect())
+}
+
+/// Targets ranked by how often `reaction` was given, or by all reactions; for admins and
+/// operators who can view analytics
+pub fn get_reaction_signals(
+    caller: Principal,
+    collection_id: &str,
+    reaction: Option<Reaction>,
+    limit: Option<u32>,
+) -> ClanopediaResult<Vec<ReactionSummary>> {
+    let collection = visible_collection(collection_id)?;
+    auth::authorize(&collection, caller, Capability::ViewAnalytics)?;
+
+    let rank = |counts: &ReactionCounts| -> u64 {
+        match reaction {
+            Some(r) => counts.counts.get(r.index()).copied().unwrap_or(0) as u64,
+            None => counts.counts.iter().map(|c| *c as u64).sum(),
+        }
+    };
+    let mut ranked: Vec<(ReactionTarget, ReactionCounts)> =
+        storage::list_reaction_counts(collection_id)
+            .into_iter()
+            .filter(|(_, counts)| rank(counts) > 0)
+            .filter_map(|(key, counts)| Some((parse_target(&key)?, counts)))
+            .filter(|(target, _)| match target {
+                ReactionTarget::Document { document_id } => {
+                    !trash::is_document_trashed(collection_id, document_id)
+                }
+                ReactionTarget::Proposal { .. } => true,
+            })
+            .collect();
+    ranked.sort_by_key(|(_, counts)| std::cmp::Reverse(rank(counts)));
+
+    let limit = limit.unwrap_or(DEFAULT_SIGNAL_LIMIT).min(MAX_SIGNAL_LIMIT) as usize;
+    Ok(ranked
+        .into_iter()
+        .take(limit)
+        .map(|(target, _)| summary(collection_id, target, caller))
+        .collect())
+}
@@ -0,0 +1,171 @@
+// src/clanopedia_backend/src/receipts.rs
+//
+// Vote receipts form a single hash chain across all collections. Hashes are hex SHA-256:
+//
+//   tally_hash   = H("yes_votes|no_votes|yes_weight|no_weight")
+//   receipt_hash = H("sequence|collection_id|proposal_id|voter|vote|weight|timestamp|tally_hash")
+//   chain_hash   = H(previous_hash || receipt_hash), over the raw 32-byte digests
+//
+// with Vote written as Yes, No or Abstain and the first previous_hash all zeroes. The latest
+// chain_hash is the canister's certified data, so a receipt is verified off-chain by
+// recomputing its hashes, folding in the later receipt hashes from `get_vote_receipt_proof`
+// and comparing the result with the certificate.
+
+use candid::{Nat, Principal};
+use ic_cdk::api::time;
+use sha2::{Digest, Sha256};
+
+use crate::{
+    storage,
+    types::{
+        ClanopediaError, ClanopediaResult, Proposal, StorageMap, Vote, VoteReceipt,
+        VoteReceiptProof, VoteTally,
+    },
+};
+
+const GENESIS_HASH: [u8; 32] = [0; 32];
+const MAX_PROOF_LINKS: usize = 1_000; // Receipts this far behind the head need paging
+
+fn sha256(data: &[u8]) -> [u8; 32] {
+    Sha256::digest(data).into()
+}
+
+fn decode_hash(hash: &str) -> [u8; 32] {
+    hex::decode(hash)
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .unwrap_or(GENESIS_HASH)
+}
+
+fn chain(previous: &[u8; 32], receipt_hash: &[u8; 32]) -> [u8; 32] {
+    let mut data = previous.to_vec();
+    data.extend_from_slice(receipt_hash);
+    sha256(&data)
+}
+
+fn head() -> [u8; 32] {
+    storage::last_vote_receipt()
+        .map(|r| decode_hash(&r.chain_hash))
+        .unwrap_or(GENESIS_HASH)
+}
+
+fn tally(proposal: &Proposal) -> VoteTally {
+    let mut tally = VoteTally {
+        yes_votes: 0,
+        no_votes: 0,
+        yes_weight: Nat::from(0u64),
+        no_weight: Nat::from(0u64),
+    };
+    for (voter, vote) in &proposal.votes {
+        let weight = vote_weight(proposal, voter);
+        match vote {
+            Vote::Yes => {
+                tally.yes_votes += 1;
+                tally.yes_weight += weight;
+            }
+            Vote::No => {
+                tally.no_votes += 1;
+                tally.no_weight += weight;
+            }
+            Vote::Abstain => {}
+        }
+    }
+    tally
+}
+
+fn vote_weight(proposal: &Proposal, voter: &Principal) -> Nat {
+    proposal
+        .token_votes
+        .get(voter)
+        .cloned()
+        .unwrap_or_else(|| Nat::from(1u64))
+}
+
+fn tally_hash(tally: &VoteTally) -> [u8; 32] {
+    sha256(
+        format!(
+            "{}|{}|{}|{}",
+            tally.yes_votes, tally.no_votes, tally.yes_weight.0, tally.no_weight.0
+        )
+        .as_bytes(),
+    )
+}
+
+fn receipt_hash(receipt: &VoteReceipt) -> [u8; 32] {
+    sha256(
+        format!(
+            "{}|{}|{}|{}|{:?}|{}|{}|{}",
+            receipt.sequence,
+            receipt.collection_id,
+            receipt.proposal_id,
+            receipt.voter,
+            receipt.vote,
+            receipt.weight.0,
+            receipt.timestamp,
+            receipt.tally_hash
+        )
+        .as_bytes(),
+    )
+}
+
+/// Fail before a vote is recorded if its receipt could not be stored
+pub fn ensure_capacity() -> ClanopediaResult<()> {
+    storage::ensure_capacity(StorageMap::VoteReceipts, 1)
+}
+
+/// Record a receipt for `voter`'s vote, which must already be in `proposal`, and certify
+/// the new chain head
+pub fn issue(collection_id: &str, proposal: &Proposal, voter: Principal, vote: Vote) -> VoteReceipt {
+    let previous = head();
+    let tally = tally(proposal);
+    let mut receipt = VoteReceipt {
+        sequence: storage::last_vote_receipt().map_or(0, |r| r.sequence + 1),
+        collection_id: collection_id.to_string(),
+        proposal_id: proposal.id.clone(),
+        voter,
+        vote,
+        weight: vote_weight(proposal, &voter),
+        timestamp: time(),
+        tally_hash: hex::encode(tally_hash(&tally)),
+        tally,
+        receipt_hash: String::new(),
+        previous_hash: hex::encode(previous),
+        chain_hash: String::new(),
+    };
+    let hash = receipt_hash(&receipt);
+    let chain_hash = chain(&previous, &hash);
+    receipt.receipt_hash = hex::encode(hash);
+    receipt.chain_hash = hex::encode(chain_hash);
+
+    storage::append_vote_receipt(&receipt);
+    ic_cdk::api::set_certified_data(&chain_hash);
+    receipt
+}
+
+/// Certified data does not survive upgrades; set it again from the stored chain
+pub fn certify_head() {
+    ic_cdk::api::set_certified_data(&head());
+}
+
+pub fn get_receipt_proof(sequence: u64) -> ClanopediaResult<VoteReceiptProof> {
+    let receipt = storage::get_vote_receipt(sequence)
+        .ok_or_else(|| ClanopediaError::NotFound(format!("Vote receipt {} not found", sequence)))?;
+    let later_receipt_hashes = storage::list_receipt_hashes_after(sequence, MAX_PROOF_LINKS + 1);
+    if later_receipt_hashes.len() > MAX_PROOF_LINKS {
+        return Err(ClanopediaError::InvalidOperation(format!(
+            "Receipt {} is more than {} receipts behind the head; page the chain with list_vote_receipt_hashes",
+            sequence, MAX_PROOF_LINKS
+        )));
+    }
+
+    Ok(VoteReceiptProof {
+        receipt,
+        later_receipt_hashes,
+        head_hash: hex::encode(head()),
+        certificate: ic_cdk::api::data_certificate(),
+    })
+}
+
+pub fn list_receipt_hashes(after_sequence: u64, limit: u32) -> Vec<String> {
+    storage::list_receipt_hashes_after(after_sequence, (limit as usize).min(MAX_PROOF_LINKS))
+}
@@ -0,0 +1,328 @@
+// src/clanopedia_backend/src/reconcile.rs
+//
+// Local collections checked against the Blueband collections this canister administers.
+// The two drift apart when a creation stops halfway, when a controller relinks a collection
+// by hand, or when the Blueband canister is swapped. A check reports orphans, Blueband
+// collections no local collection links to, and broken links, local collections that reach
+// no Blueband collection; controllers then pick a repair for each. The timer can repeat the
+// check, and the latest report is kept for the dashboard.
+
+use std::collections::HashSet;
+
+use candid::Principal;
+use ic_cdk::api::time;
+
+use crate::{
+    config_history,
+    external::blueband,
+    logging,
+    proxy::require_controller,
+    storage,
+    types::{
+        BluebandLinkProblem, BluebandOrphan, BluebandReconcileState, BluebandReconciliation,
+        BluebandRepair, BrokenBluebandLink, ClanopediaError, ClanopediaResult, CollectionConfig,
+        ConfigSetting, EventKind, GovernanceModel, StorageMap,
+    },
+    validation,
+};
+
+const MAX_FINDINGS: usize = 1_000; // Of each kind, to keep the stored report bounded
+const MAX_INTERVAL_HOURS: u32 = 30 * 24;
+const HOUR_NANOS: u64 = 60 * 60 * 1_000_000_000;
+
+// Whether this canister may manage the Blueband collection
+fn is_reachable(remote: &blueband::Collection) -> bool {
+    let canister = ic_cdk::id().to_string();
+    remote.genesis_admin == canister || remote.admins.contains(&canister)
+}
+
+async fn check() -> ClanopediaResult<BluebandReconciliation> {
+    let remote = blueband::list_blueband_collections()
+        .await
+        .map_err(ClanopediaError::BluebandError)?;
+    let local = storage::list_collections();
+    // Creations still running are reconciled by `creation` once they go stale
+    let pending: HashSet<String> = storage::list_pending_creations()
+        .into_iter()
+        .map(|p| p.collection_id)
+        .collect();
+
+    let linked: HashSet<&str> = local
+        .iter()
+        .map(|c| c.blueband_collection_id.as_str())
+        .filter(|id| !id.is_empty())
+        .collect();
+    let orphans: Vec<BluebandOrphan> = remote
+        .iter()
+        .filter(|r| is_reachable(r) && !linked.contains(r.id.as_str()))
+        .filter(|r| !pending.contains(&r.id))
+        .take(MAX_FINDINGS)
+        .map(|r| BluebandOrphan {
+            blueband_collection_id: r.id.clone(),
+            name: r.name.clone(),
+            created_at: r.created_at,
+        })
+        .collect();
+
+    // Any Blueband collection counts here, since a controller may have linked one this
+    // canister does not administer
+    let remote_ids: HashSet<&str> = remote.iter().map(|r| r.id.as_str()).collect();
+    let orphan_ids: HashSet<&str> = orphans
+        .iter()
+        .map(|o| o.blueband_collection_id.as_str())
+        .collect();
+    let broken_links = local
+        .iter()
+        .filter(|c| !pending.contains(&c.id))
+        .filter_map(|c| {
+            let problem = if c.blueband_collection_id.is_empty() {
+                BluebandLinkProblem::Unlinked
+            } else if !remote_ids.contains(c.blueband_collection_id.as_str()) {
+                BluebandLinkProblem::RemoteMissing
+            } else {
+                return None;
+            };
+            Some(BrokenBluebandLink {
+                collection_id: c.id.clone(),
+                blueband_collection_id: c.blueband_collection_id.clone(),
+                problem,
+                relink_candidate: orphan_ids.contains(c.id.as_str()).then(|| c.id.clone()),
+            })
+        })
+        .take(MAX_FINDINGS)
+        .collect();
+
+    Ok(BluebandReconciliation {
+        checked_at: time(),
+        remote_collections: remote.len() as u32,
+        orphans,
+        broken_links,
+    })
+}
+
+async fn run() -> ClanopediaResult<BluebandReconciliation> {
+    let mut state = storage::get_blueband_reconcile_state();
+    state.last_attempt_at = time();
+    storage::set_blueband_reconcile_state(state);
+
+    let report = check().await?;
+    // Re-read, since the interval may have changed during the call
+    let mut state = storage::get_blueband_reconcile_state();
+    state.last = Some(report.clone());
+    storage::set_blueband_reconcile_state(state);
+    Ok(report)
+}
+
+/// Compare local collections with Blueband's now, keeping the report; controllers only
+pub async fn reconcile_with_blueband(
+    caller: Principal,
+) -> ClanopediaResult<BluebandReconciliation> {
+    require_controller(caller)?;
+    run().await
+}
+
+pub fn get_state(caller: Principal) -> ClanopediaResult<BluebandReconcileState> {
+    require_controller(caller)?;
+    Ok(storage::get_blueband_reconcile_state())
+}
+
+/// Have the timer check every `interval_hours`, or stop it with `None`
+pub fn set_interval(caller: Principal, interval_hours: Option<u32>) -> ClanopediaResult<()> {
+    require_controller(caller)?;
+    if interval_hours.is_some_and(|h| h == 0 || h > MAX_INTERVAL_HOURS) {
+        return Err(ClanopediaError::InvalidInput(format!(
+            "The interval must be 1 to {} hours",
+            MAX_INTERVAL_HOURS
+        )));
+    }
+    let mut state = storage::get_blueband_reconcile_state();
+    state.interval_hours = interval_hours;
+    storage::set_blueband_reconcile_state(state);
+    crate::timers::schedule();
+    config_history::record(
+        caller,
+        None,
+        ConfigSetting::BluebandReconcileInterval(interval_hours),
+    )?;
+    Ok(())
+}
+
+/// When the timer should check next, if it checks at all
+pub fn next_check_at() -> Option<u64> {
+    let state = storage::get_blueband_reconcile_state();
+    state
+        .interval_hours
+        .map(|hours| state.last_attempt_at + hours as u64 * HOUR_NANOS)
+}
+
+pub async fn run_due() {
+    if next_check_at().is_none_or(|at| at > time()) {
+        return;
+    }
+    match run().await {
+        Ok(report) if !report.orphans.is_empty() || !report.broken_links.is_empty() => {
+            logging::warn(
+                "reconcile",
+                None,
+                format!(
+                    "Blueband has {} orphaned collections and {} collections have broken links",
+                    report.orphans.len(),
+                    report.broken_links.len()
+                ),
+            );
+        }
+        Ok(_) => {}
+        Err(e) => logging::warn(
+            "reconcile",
+            None,
+            format!("Checking collections against Blueband failed: {:?}", e),
+        ),
+    }
+}
+
+// The Blueband collection, if this canister administers it and nothing local links to it
+async fn orphan(blueband_collection_id: &str) -> ClanopediaResult<blueband::Collection> {
+    let remote = blueband::get_blueband_collection(blueband_collection_id)
+        .await
+        .map_err(ClanopediaError::BluebandError)?
+        .ok_or_else(|| {
+            ClanopediaError::NotFound(format!(
+                "Blueband collection {} not found",
+                blueband_collection_id
+            ))
+        })?;
+    if !is_reachable(&remote) {
+        return Err(ClanopediaError::InvalidOperation(format!(
+            "Blueband collection {} is not administered by this canister",
+            blueband_collection_id
+        )));
+    }
+    if let Some(linked) = storage::list_collections()
+        .into_iter()
+        .find(|c| c.blueband_collection_id == blueband_collection_id)
+    {
+        return Err(ClanopediaError::InvalidOperation(format!(
+            "Blueband collection {} is linked to collection {}",
+            blueband_collection_id, linked.id
+        )));
+    }
+    Ok(remote)
+}
+
+fn import_config(remote: &blueband::Collection, caller: Principal) -> CollectionConfig {
+    let canister = ic_cdk::id().to_string();
+    let mut admins: Vec<String> = remote
+        .admins
+        .iter()
+        .filter(|a| **a != canister && Principal::from_text(a).is_ok())
+        .cloned()
+        .collect();
+    if admins.is_empty() {
+        admins.push(caller.to_string());
+    }
+    CollectionConfig {
+        name: remote.name.clone(),
+        description: remote.description.clone().unwrap_or_default(),
+        admins,
+        threshold: 1,
+        governance_token: None,
+        sns_governance_canister: None,
+        governance_model: GovernanceModel::Multisig,
+        quorum_threshold: 0,
+        is_permissionless: false,
+        proposal_bond: None,
+        proposer_eligibility: None,
+        embed_concurrency: None,
+        embedding_proxy: None, // The Blueband settings keep whatever proxy it used
+        encryption: None,
+        pipeline: None,
+        auto_tag: None,
+        summarization: None,
+        ocr: None,
+        nft_minting: None,
+        license: None,
+        vote_reminder_hours: None,
+        timelock_hours: None,
+        custom_proposals: None,
+        call_policy: None,
+        review_policy: None,
+        content_limits: None,
+        retry_policy: None,
+    }
+}
+
+// Take a repaired finding out of the kept report
+fn forget(repair: &BluebandRepair) {
+    let mut state = storage::get_blueband_reconcile_state();
+    let Some(report) = state.last.as_mut() else {
+        return;
+    };
+    let (collection_id, blueband_collection_id) = match repair {
+        BluebandRepair::Relink {
+            collection_id,
+            blueband_collection_id,
+        } => (Some(collection_id), blueband_collection_id),
+        BluebandRepair::Import {
+            blueband_collection_id,
+        }
+        | BluebandRepair::DeleteRemote {
+            blueband_collection_id,
+        } => (None, blueband_collection_id),
+    };
+    report
+        .orphans
+        .retain(|o| &o.blueband_collection_id != blueband_collection_id);
+    report
+        .broken_links
+        .retain(|l| Some(&l.collection_id) != collection_id);
+    storage::set_blueband_reconcile_state(state);
+}
+
+/// Apply a fix for an orphan or a broken link; controllers only
+pub async fn repair(caller: Principal, repair: BluebandRepair) -> ClanopediaResult<()> {
+    require_controller(caller)?;
+    match &repair {
+        BluebandRepair::Relink {
+            collection_id,
+            blueband_collection_id,
+        } => {
+            storage::get_collection(collection_id)?;
+            orphan(blueband_collection_id).await?;
+            // Re-read, since the collection may have changed during the call
+            let mut collection = storage::get_collection(collection_id)?;
+            collection.blueband_collection_id = blueband_collection_id.clone();
+            collection.updated_at = time();
+            storage::update_collection(collection_id, &collection)?;
+        }
+        BluebandRepair::Import {
+            blueband_collection_id,
+        } => {
+            let remote = orphan(blueband_collection_id).await?;
+            if storage::get_collection(blueband_collection_id).is_ok() {
+                return Err(ClanopediaError::AlreadyExists(format!(
+                    "Collection {} already exists; relink it instead",
+                    blueband_collection_id
+                )));
+            }
+            storage::ensure_capacity(StorageMap::Collections, 1)?;
+            let mut config = import_config(&remote, caller);
+            validation::validate_collection_config(&mut config)?;
+            storage::create_collection(blueband_collection_id, config, caller)?;
+            let mut collection = storage::get_collection(blueband_collection_id)?;
+            collection.blueband_collection_id = remote.id;
+            storage::update_collection(blueband_collection_id, &collection)?;
+        }
+        BluebandRepair::DeleteRemote {
+            blueband_collection_id,
+        } => {
+            orphan(blueband_collection_id).await?;
+            blueband::delete_collection(blueband_collection_id)
+                .await
+                .map_err(ClanopediaError::BluebandError)?;
+        }
+    }
+
+    forget(&repair);
+    storage::append_event(caller, EventKind::BluebandRepaired { repair });
+    Ok(())
+}
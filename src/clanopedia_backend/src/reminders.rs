@@ -0,0 +1,146 @@
+// src/clanopedia_backend/src/reminders.rs
+//
+// Proposals run out unvoted more often than they are rejected. Some hours before a
+// proposal expires (DEFAULT_VOTE_REMINDER_HOURS unless the collection sets its own) the
+// timer reminds each admin who has not voted, once per proposal. Token holders and SNS
+// neurons cannot be enumerated here, so UIs use `get_expiring_proposals` to check whether
+// the caller still has to vote.
+
+use candid::Principal;
+use ic_cdk::api::time;
+
+use crate::{
+    logging, notifications, sealed_votes, storage,
+    types::{
+        ClanopediaError, ClanopediaResult, Collection, ExpiringProposal, Proposal, ProposalStatus,
+        DEFAULT_VOTE_REMINDER_HOURS, MAX_VOTE_REMINDER_HOURS,
+    },
+};
+
+const HOUR_NANOS: u64 = 60 * 60 * 1_000_000_000;
+const REMINDER_BATCH: usize = 50; // Proposals reminded per timer run
+const MAX_EXPIRING_PROPOSALS: usize = 100;
+
+fn reminder_window(collection: &Collection) -> Option<u64> {
+    match collection
+        .vote_reminder_hours
+        .unwrap_or(DEFAULT_VOTE_REMINDER_HOURS)
+    {
+        0 => None,
+        hours => Some(hours as u64 * HOUR_NANOS),
+    }
+}
+
+fn has_voted(proposal: &Proposal, principal: &Principal) -> bool {
+    proposal.votes.contains_key(principal)
+        || proposal.token_votes.contains_key(principal)
+        || sealed_votes::has_committed(proposal, principal)
+}
+
+fn pending_admins(collection: &Collection, proposal: &Proposal) -> Vec<Principal> {
+    collection
+        .admins
+        .iter()
+        .filter(|admin| !has_voted(proposal, admin))
+        .copied()
+        .collect()
+}
+
+// Collections whose proposals can still be voted on
+fn voting_collections() -> impl Iterator<Item = Collection> {
+    storage::list_collections()
+        .into_iter()
+        .filter(|c| c.trashed.is_none() && c.paused.is_none())
+}
+
+fn awaiting_votes(proposal: &Proposal, now: u64) -> bool {
+    proposal.status == ProposalStatus::Active
+        && !proposal.executed
+        && sealed_votes::voting_ends_at(proposal) > now
+}
+
+// Proposals not yet reminded, with the time their reminder is due
+fn pending_reminders(now: u64) -> Vec<(Collection, Proposal, u64)> {
+    let mut pending = Vec::new();
+    for collection in voting_collections() {
+        let Some(window) = reminder_window(&collection) else {
+            continue;
+        };
+        for proposal in storage::list_proposals(&collection.id).into_values() {
+            if awaiting_votes(&proposal, now) && proposal.reminded_at.is_none() {
+                let due = sealed_votes::voting_ends_at(&proposal).saturating_sub(window);
+                pending.push((collection.clone(), proposal, due));
+            }
+        }
+    }
+    pending
+}
+
+/// When the earliest unsent reminder is due, if any
+pub fn next_reminder_at() -> Option<u64> {
+    pending_reminders(time())
+        .into_iter()
+        .map(|(_, _, due)| due)
+        .min()
+}
+
+/// Remind admins who have not voted on proposals that entered their reminder window
+pub fn send_due_reminders() {
+    let now = time();
+    let mut due: Vec<_> = pending_reminders(now)
+        .into_iter()
+        .filter(|(_, _, due)| *due <= now)
+        .collect();
+    due.sort_by_key(|(_, proposal, _)| sealed_votes::voting_ends_at(proposal));
+
+    for (collection, mut proposal, _) in due.into_iter().take(REMINDER_BATCH) {
+        let pending = pending_admins(&collection, &proposal);
+        notifications::vote_reminder(&collection, &proposal, &pending);
+        proposal.reminded_at = Some(now);
+        if let Err(e) = storage::update_proposal_in_storage(&collection.id, &proposal) {
+            logging::warn(
+                "reminders",
+                Some(&collection.id),
+                format!("Recording reminder for {} failed: {}", proposal.id, e),
+            );
+        }
+    }
+}
+
+/// Active proposals across all collections that expire within `within_hours`, soonest first
+pub fn get_expiring_proposals(
+    caller: Principal,
+    within_hours: u32,
+) -> ClanopediaResult<Vec<ExpiringProposal>> {
+    if within_hours == 0 || within_hours > MAX_VOTE_REMINDER_HOURS {
+        return Err(ClanopediaError::InvalidInput(format!(
+            "within_hours must be between 1 and {}",
+            MAX_VOTE_REMINDER_HOURS
+        )));
+    }
+    let now = time();
+    let deadline = now + within_hours as u64 * HOUR_NANOS;
+
+    let mut expiring = Vec::new();
+    for collection in voting_collections() {
+        for proposal in storage::list_proposals(&collection.id).into_values() {
+            let voting_ends_at = sealed_votes::voting_ends_at(&proposal);
+            if !awaiting_votes(&proposal, now) || voting_ends_at > deadline {
+                continue;
+            }
+            expiring.push(ExpiringProposal {
+                collection_id: collection.id.clone(),
+                collection_name: collection.name.clone(),
+                pending_admins: pending_admins(&collection, &proposal),
+                caller_voted: has_voted(&proposal, &caller),
+                proposal_id: proposal.id,
+                description: proposal.description,
+                expires_at: voting_ends_at,
+            });
+        }
+    }
+
+    expiring.sort_by_key(|p| p.expires_at);
+    expiring.truncate(MAX_EXPIRING_PROPOSALS);
+    Ok(expiring)
+}
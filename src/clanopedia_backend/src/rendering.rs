@@ -0,0 +1,164 @@
+// src/clanopedia_backend/src/rendering.rs
+//
+// Document reads with the content's markdown structure alongside the raw text: headings,
+// fenced code blocks and links, with char offsets into the content, so thin clients need
+// not parse markdown themselves. Blueband never changes a document's content, so the
+// structure is parsed on the first read and cached until the document is removed. Offsets
+// count chars, as outlines do.
+
+use ic_cdk::api::time;
+
+use crate::{
+    crosspost, encryption,
+    external::blueband,
+    extractor::outline::markdown_outline,
+    storage, trash,
+    types::{
+        ClanopediaError, ClanopediaResult, CodeBlock, ContentLink, ContentStructure,
+        RenderedDocument, StorageMap,
+    },
+    views,
+};
+
+/// The document's content and structure, or None if it is missing or in the trash
+pub async fn read_document(
+    collection_id: &str,
+    document_id: &str,
+) -> ClanopediaResult<Option<RenderedDocument>> {
+    let collection = storage::get_collection(&collection_id.to_string())?;
+    if trash::is_document_trashed(collection_id, document_id) {
+        return Ok(None);
+    }
+    let owner = crosspost::content_collection(&collection, document_id)?;
+    let Some(content) =
+        blueband::get_document_content_from_blueband(&owner.blueband_collection_id, document_id)
+            .await
+            .map_err(ClanopediaError::BluebandError)?
+    else {
+        return Ok(None);
+    };
+    views::count_view(collection_id, document_id, ic_cdk::caller());
+
+    // Encrypted content has no structure to parse
+    let structure = (!encryption::is_encrypted(&owner))
+        .then(|| cached_structure(&owner.id, document_id, &content));
+    Ok(Some(RenderedDocument { content, structure }))
+}
+
+fn cached_structure(collection_id: &str, document_id: &str, content: &str) -> ContentStructure {
+    if let Some(structure) = storage::get_content_structure(collection_id, document_id) {
+        return structure;
+    }
+    let structure = parse(content);
+    // A full cache only means parsing again on the next read
+    if storage::ensure_capacity(StorageMap::ContentStructures, 1).is_ok() {
+        storage::set_content_structure(collection_id, document_id, structure.clone());
+    }
+    structure
+}
+
+/// Parse headings, fenced code blocks and links out of markdown `content`
+fn parse(content: &str) -> ContentStructure {
+    let mut code_blocks = Vec::new();
+    let mut links = Vec::new();
+    // The open block's fence, language and the offset where its code starts
+    let mut open: Option<(&str, Option<String>, u64)> = None;
+    let mut offset = 0u64;
+
+    for line in content.split('\n') {
+        let trimmed = line.trim_start();
+        match &open {
+            Some((fence, language, start)) => {
+                if trimmed.starts_with(*fence) {
+                    code_blocks.push(CodeBlock {
+                        language: language.clone(),
+                        offset: *start,
+                        // The newline before the closing fence is not code
+                        length: offset.saturating_sub(*start + 1),
+                    });
+                    open = None;
+                }
+            }
+            None if trimmed.starts_with("```") || trimmed.starts_with("~~~") => {
+                let language = trimmed[3..]
+                    .trim_start_matches(&trimmed[..1])
+                    .split_whitespace()
+                    .next()
+                    .map(str::to_string);
+                open = Some((
+                    &trimmed[..3],
+                    language,
+                    offset + line.chars().count() as u64 + 1,
+                ));
+            }
+            None => line_links(line, offset, &mut links),
+        }
+        offset += line.chars().count() as u64 + 1;
+    }
+
+    // A block left open runs to the end of the content
+    if let Some((_, language, start)) = open {
+        let end = content.chars().count() as u64;
+        code_blocks.push(CodeBlock {
+            language,
+            offset: start.min(end),
+            length: end.saturating_sub(start),
+        });
+    }
+
+    ContentStructure {
+        headings: markdown_outline(content),
+        code_blocks,
+        links,
+        parsed_at: time(),
+    }
+}
+
+// `[text](target)` and `[[target|text]]` links in one line; images are skipped
+fn line_links(line: &str, line_offset: u64, links: &mut Vec<ContentLink>) {
+    let mut from = 0;
+    while let Some(found) = line[from..].find('[') {
+        let start = from + found;
+        let after = &line[start + 1..];
+        let offset = line_offset + line[..start].chars().count() as u64;
+        from = start + 1;
+
+        if let Some(inner) = after.strip_prefix('[') {
+            let Some(end) = inner.find("]]") else {
+                continue;
+            };
+            let mut parts = inner[..end].splitn(2, '|');
+            let target = parts.next().unwrap_or("").trim();
+            if !target.is_empty() {
+                links.push(ContentLink {
+                    text: parts.next().unwrap_or(target).trim().to_string(),
+                    target: target.to_string(),
+                    offset,
+                    external: false,
+                });
+            }
+            from = start + 2 + end + 2;
+            continue;
+        }
+
+        let Some(close) = after.find(']') else {
+            continue;
+        };
+        let Some(rest) = after[close + 1..].strip_prefix('(') else {
+            continue;
+        };
+        let Some(paren) = rest.find(')') else {
+            continue;
+        };
+        let target = rest[..paren].split_whitespace().next().unwrap_or("");
+        if !target.is_empty() && !line[..start].ends_with('!') {
+            links.push(ContentLink {
+                text: after[..close].to_string(),
+                target: target.to_string(),
+                offset,
+                external: target.contains("://") || target.starts_with("mailto:"),
+            });
+        }
+        from = start + 1 + close + 2 + paren + 1;
+    }
+}
@@ -0,0 +1,252 @@
+// src/clanopedia_backend/src/replica.rs
+//
+// Read replicas: companion canisters that serve browse and search traffic from their own
+// copy of collections, proposals, document overlays and keyword-indexed documents, leaving
+// governance on this canister. Controllers register each replica, which then syncs in two
+// steps:
+//
+// 1. Snapshot: page through every `ReplicaSection` with `replica_snapshot`, passing
+//    `next_key` back as `after_key`, and keep the `cursor` of the first page.
+// 2. Deltas: call `replica_deltas` with that cursor, apply the records, and pass the
+//    returned cursor on the next call. Records carry entries as they stand when read, so
+//    replaying a change already seen during the snapshot is harmless.
+//
+// The cursor is a sequence of the public change stream (`get_changes`). Once it falls behind
+// the retained window, deltas fail and the replica starts over from a snapshot. A removed
+// collection means dropping every entry under it. Slugs and keyword postings are not sent;
+// replicas derive them from collections, overlays and indexed documents. Votes that leave a
+// proposal's status unchanged are not in the change stream, so tallies on a replica lag until
+// the proposal closes. Encrypted collections and trashed documents are never exported.
+//
+// Pages are only served in replicated execution, i.e. to canister calls and update calls, so
+// every page a replica applies has been agreed by the subnet.
+
+use std::collections::HashMap;
+
+use candid::Principal;
+use ic_cdk::api::time;
+
+use crate::{
+    changes, config_history, encryption,
+    proxy::require_controller,
+    storage, trash,
+    types::{
+        ChangeKind, ChangeRecord, ClanopediaError, ClanopediaResult, Collection, ConfigSetting,
+        ReadReplica, ReplicaDeltaPage, ReplicaEntry, ReplicaRecord, ReplicaSection, ReplicaSnapshotPage,
+    },
+};
+
+const MAX_REPLICA_PAGE: usize = 200;
+
+// ============================
+// REGISTRATION
+// ============================
+
+/// Let `canister` pull snapshots and deltas; controllers only
+pub fn add_replica(caller: Principal, canister: Principal) -> ClanopediaResult<()> {
+    require_controller(caller)?;
+    if canister == Principal::anonymous() || canister == ic_cdk::id() {
+        return Err(ClanopediaError::InvalidInput(format!(
+            "{} cannot be a read replica",
+            canister
+        )));
+    }
+    if storage::get_read_replica(&canister).is_none() {
+        storage::put_read_replica(ReadReplica {
+            canister,
+            added_by: caller,
+            added_at: time(),
+        });
+        record_replicas(caller)?;
+    }
+    Ok(())
+}
+
+pub fn remove_replica(caller: Principal, canister: Principal) -> ClanopediaResult<()> {
+    require_controller(caller)?;
+    if !storage::remove_read_replica(&canister) {
+        return Err(ClanopediaError::NotFound(format!(
+            "{} is not a read replica",
+            canister
+        )));
+    }
+    record_replicas(caller)
+}
+
+fn record_replicas(caller: Principal) -> ClanopediaResult<()> {
+    let replicas = storage::list_read_replicas()
+        .into_iter()
+        .map(|replica| replica.canister)
+        .collect();
+    config_history::record(caller, None, ConfigSetting::ReadReplicas(replicas))
+}
+
+pub fn list_replicas(caller: Principal) -> ClanopediaResult<Vec<ReadReplica>> {
+    require_controller(caller)?;
+    Ok(storage::list_read_replicas())
+}
+
+fn require_replica(caller: Principal) -> ClanopediaResult<()> {
+    if storage::get_read_replica(&caller).is_none() {
+        return Err(ClanopediaError::NotAuthorized);
+    }
+    if !ic_cdk::api::in_replicated_execution() {
+        return Err(ClanopediaError::InvalidOperation(
+            "Replica pages are only served to update calls, so the subnet certifies them"
+                .to_string(),
+        ));
+    }
+    Ok(())
+}
+
+// ============================
+// EXPORT
+// ============================
+
+// Whether the collection exists and its entries may leave this canister
+fn is_exported(collection_id: &str, cache: &mut HashMap<String, bool>) -> bool {
+    *cache.entry(collection_id.to_string()).or_insert_with(|| {
+        storage::get_collection(&collection_id.to_string())
+            .is_ok_and(|c| !encryption::is_encrypted(&c))
+    })
+}
+
+fn collection_entry(mut collection: Collection) -> ReplicaEntry {
+    collection.proposals.clear(); // Proposals are sent as their own entries
+    ReplicaEntry::Collection(Box::new(collection))
+}
+
+fn split_key(key: &str) -> (&str, &str) {
+    key.split_once(':').unwrap_or((key, ""))
+}
+
+/// One page of a section, in key order
+pub fn snapshot(
+    caller: Principal,
+    section: ReplicaSection,
+    after_key: Option<String>,
+    limit: u32,
+) -> ClanopediaResult<ReplicaSnapshotPage> {
+    require_replica(caller)?;
+    let limit = (limit as usize).clamp(1, MAX_REPLICA_PAGE);
+    // Read first, so the page reflects at least every change up to it
+    let cursor = storage::last_change_sequence();
+    let after = after_key.as_deref();
+    let entries: Vec<(String, ReplicaEntry)> = match section {
+        ReplicaSection::Collections => storage::collections_after(after, limit)
+            .into_iter()
+            .map(|(key, c)| (key, collection_entry(c)))
+            .collect(),
+        ReplicaSection::Proposals => storage::proposals_after(after, limit)
+            .into_iter()
+            .map(|(key, p)| (key, ReplicaEntry::Proposal(Box::new(p))))
+            .collect(),
+        ReplicaSection::DocumentOverlays => storage::document_overlays_after(after, limit)
+            .into_iter()
+            .map(|(key, o)| (key, ReplicaEntry::DocumentOverlay(o)))
+            .collect(),
+        ReplicaSection::IndexedDocuments => storage::indexed_documents_after(after, limit)
+            .into_iter()
+            .map(|(key, d)| (key, ReplicaEntry::IndexedDocument(d)))
+            .collect(),
+    };
+    let next_key = (entries.len() == limit)
+        .then(|| entries.last().map(|(key, _)| key.clone()))
+        .flatten();
+
+    let mut cache = HashMap::new();
+    let records = entries
+        .into_iter()
+        .filter(|(key, _)| match section {
+            ReplicaSection::Collections => is_exported(key, &mut cache),
+            ReplicaSection::Proposals => is_exported(split_key(key).0, &mut cache),
+            ReplicaSection::DocumentOverlays | ReplicaSection::IndexedDocuments => {
+                let (collection_id, document_id) = split_key(key);
+                is_exported(collection_id, &mut cache)
+                    && !trash::is_document_trashed(collection_id, document_id)
+            }
+        })
+        .map(|(key, entry)| ReplicaRecord {
+            section,
+            key,
+            entry: Some(entry),
+        })
+        .collect();
+    Ok(ReplicaSnapshotPage {
+        records,
+        next_key,
+        cursor,
+    })
+}
+
+// The entries a change touched, as they now stand
+fn change_records(change: &ChangeRecord, cache: &mut HashMap<String, bool>) -> Vec<ReplicaRecord> {
+    let collection_id = change.collection_id.as_str();
+    let exported = is_exported(collection_id, cache);
+    match &change.kind {
+        ChangeKind::CollectionCreated
+        | ChangeKind::CollectionUpdated
+        | ChangeKind::CollectionDeleted => vec![ReplicaRecord {
+            section: ReplicaSection::Collections,
+            key: collection_id.to_string(),
+            entry: storage::get_collection(&collection_id.to_string())
+                .ok()
+                .filter(|_| exported)
+                .map(collection_entry),
+        }],
+        ChangeKind::DocumentAdded { document_id }
+        | ChangeKind::DocumentUpdated { document_id }
+        | ChangeKind::DocumentRemoved { document_id } => {
+            let visible = exported && !trash::is_document_trashed(collection_id, document_id);
+            let key = format!("{}:{}", collection_id, document_id);
+            vec![
+                ReplicaRecord {
+                    section: ReplicaSection::DocumentOverlays,
+                    key: key.clone(),
+                    entry: visible
+                        .then(|| storage::get_document_overlay(collection_id, document_id))
+                        .flatten()
+                        .map(ReplicaEntry::DocumentOverlay),
+                },
+                ReplicaRecord {
+                    section: ReplicaSection::IndexedDocuments,
+                    key,
+                    entry: visible
+                        .then(|| storage::get_indexed_document(collection_id, document_id))
+                        .flatten()
+                        .map(ReplicaEntry::IndexedDocument),
+                },
+            ]
+        }
+        ChangeKind::ProposalCreated { proposal_id }
+        | ChangeKind::ProposalStatusChanged { proposal_id, .. } => vec![ReplicaRecord {
+            section: ReplicaSection::Proposals,
+            key: format!("{}:{}", collection_id, proposal_id),
+            entry: storage::get_proposal(collection_id, proposal_id)
+                .filter(|_| exported)
+                .map(|p| ReplicaEntry::Proposal(Box::new(p))),
+        }],
+    }
+}
+
+/// What changed after `since_cursor`, or since the oldest change kept when unset
+pub fn deltas(
+    caller: Principal,
+    since_cursor: Option<u64>,
+    limit: u32,
+) -> ClanopediaResult<ReplicaDeltaPage> {
+    require_replica(caller)?;
+    let page = changes::get_changes(since_cursor, limit.min(MAX_REPLICA_PAGE as u32))?;
+    let mut cache = HashMap::new();
+    let records = page
+        .changes
+        .iter()
+        .flat_map(|change| change_records(change, &mut cache))
+        .collect();
+    let cursor = page.next_sequence.or(since_cursor);
+    Ok(ReplicaDeltaPage {
+        records,
+        caught_up: cursor == storage::last_change_sequence(),
+        cursor,
+    })
+}
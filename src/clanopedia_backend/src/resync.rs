@@ -0,0 +1,110 @@
+// src/clanopedia_backend/src/resync.rs
+//
+// Re-syncing a source turns its extraction diff into a single vote. New and changed
+// documents are staged in Blueband like any extraction and bundled into one
+// ResyncDocuments proposal whose description summarizes the diff. A changed document is
+// staged as a replacement: once its new version is embedded, the version it replaces is
+// moved to the trash, where it can still be restored. Documents no longer at the source
+// are only reported.
+
+use candid::Principal;
+
+use crate::{
+    extractor::types::{ExtractionChange, ExtractionDiff},
+    logging, trash,
+    types::{DocumentId, DocumentReplacement, ExecutionReport, ItemExecutionState},
+    AddDocumentRequest,
+};
+
+const DESCRIBED_TITLES: usize = 5;
+
+/// The documents a re-sync stages, each with the document it replaces. `documents` are
+/// those `diff` was computed from, in the same order.
+pub fn staged_documents(
+    diff: &ExtractionDiff,
+    documents: Vec<AddDocumentRequest>,
+) -> Vec<(AddDocumentRequest, Option<DocumentId>)> {
+    documents
+        .into_iter()
+        .zip(&diff.entries)
+        .filter_map(|(document, entry)| match entry.change {
+            ExtractionChange::Added => Some((document, None)),
+            ExtractionChange::Changed => Some((document, entry.document_id.clone())),
+            ExtractionChange::Unchanged | ExtractionChange::Removed => None,
+        })
+        .collect()
+}
+
+pub fn document_ids(replacements: &[DocumentReplacement]) -> Vec<DocumentId> {
+    replacements.iter().map(|r| r.document_id.clone()).collect()
+}
+
+/// Proposal description: the diff counts, then the titles of what changed
+pub fn describe(url: &str, diff: &ExtractionDiff) -> String {
+    let mut description = format!(
+        "Re-sync {}: {} changed, {} new, {} unchanged, {} no longer at the source.",
+        url, diff.changed, diff.added, diff.unchanged, diff.removed
+    );
+    for (change, label) in [
+        (ExtractionChange::Changed, "Changed"),
+        (ExtractionChange::Added, "New"),
+        (ExtractionChange::Removed, "Gone (not removed)"),
+    ] {
+        let titles: Vec<&str> = diff
+            .entries
+            .iter()
+            .filter(|e| e.change == change)
+            .map(|e| e.title.as_str())
+            .collect();
+        if titles.is_empty() {
+            continue;
+        }
+        let more = titles.len().saturating_sub(DESCRIBED_TITLES);
+        description.push_str(&format!(
+            " {}: [{}]",
+            label,
+            titles
+                .iter()
+                .take(DESCRIBED_TITLES)
+                .copied()
+                .collect::<Vec<_>>()
+                .join(", ")
+        ));
+        if more > 0 {
+            description.push_str(&format!(" and {} more", more));
+        }
+    }
+    description
+}
+
+/// Trash the documents whose replacements are now embedded. Safe to run again after a
+/// retry; documents already in the trash are skipped.
+pub fn retire_replaced(
+    collection_id: &str,
+    replacements: &[DocumentReplacement],
+    report: &ExecutionReport,
+    executor: Principal,
+) {
+    for replacement in replacements {
+        let Some(replaced) = &replacement.replaces else {
+            continue;
+        };
+        let embedded = report.items.iter().any(|item| {
+            item.document_id == replacement.document_id
+                && item.state == ItemExecutionState::Succeeded
+        });
+        if !embedded || trash::is_document_trashed(collection_id, replaced) {
+            continue;
+        }
+        if let Err(e) = trash::move_to_trash(collection_id, replaced, executor) {
+            logging::warn(
+                "resync",
+                Some(collection_id),
+                format!(
+                    "Retiring document {} replaced by {} failed: {}",
+                    replaced, replacement.document_id, e
+                ),
+            );
+        }
+    }
+}
@@ -0,0 +1,214 @@
+// src/clanopedia_backend/src/review.rs
+//
+// A collection with a review policy holds every proposal (except vetoes, which must stay
+// quick) until `required_approvals` of its assigned reviewers sign off with a note. Voting
+// goes on meanwhile; the review only gates execution. A new proposal is assigned the whole
+// reviewer pool except its creator; admins can narrow or change the assignment while the
+// proposal is open. A reviewer can change their verdict, and only the latest one counts.
+
+use candid::Principal;
+use ic_cdk::api::time;
+
+use crate::{
+    admin, archive, auth, notifications, storage, trash,
+    types::{
+        ClanopediaError, ClanopediaResult, Collection, Proposal, ProposalReview, ProposalStatus,
+        ProposalType, Review, ReviewDecision, ReviewStatus,
+    },
+};
+
+const MAX_NOTE_CHARS: usize = 2_000;
+const MAX_REVIEWS_PER_PROPOSAL: usize = 200;
+
+/// The review stage a new proposal starts with, if the collection requires one
+pub fn new_review(
+    collection: &Collection,
+    proposal_type: &ProposalType,
+    creator: Principal,
+) -> Option<ProposalReview> {
+    let policy = collection.review_policy.as_ref()?;
+    if matches!(proposal_type, ProposalType::VetoProposal { .. }) {
+        return None;
+    }
+    Some(ProposalReview {
+        required_approvals: policy.required_approvals,
+        assigned: policy
+            .reviewers
+            .iter()
+            .filter(|r| **r != creator)
+            .copied()
+            .collect(),
+        reviews: Vec::new(),
+    })
+}
+
+// Each assigned reviewer's latest verdict, ignoring comments
+fn latest_verdicts(review: &ProposalReview) -> impl Iterator<Item = ReviewDecision> + '_ {
+    review.assigned.iter().filter_map(|reviewer| {
+        review
+            .reviews
+            .iter()
+            .rev()
+            .find(|r| r.reviewer == *reviewer && r.decision != ReviewDecision::Comment)
+            .map(|r| r.decision)
+    })
+}
+
+pub fn status(review: &ProposalReview) -> ReviewStatus {
+    let count = |decision| latest_verdicts(review).filter(|d| *d == decision).count() as u32;
+    let approvals = count(ReviewDecision::Approve);
+    ReviewStatus {
+        assigned: review.assigned.clone(),
+        required_approvals: review.required_approvals,
+        approvals,
+        changes_requested: count(ReviewDecision::RequestChanges),
+        comments: review
+            .reviews
+            .iter()
+            .filter(|r| r.decision == ReviewDecision::Comment)
+            .count() as u32,
+        signed_off: approvals >= review.required_approvals,
+    }
+}
+
+pub fn ensure_signed_off(proposal: &Proposal) -> ClanopediaResult<()> {
+    let Some(review) = &proposal.review else {
+        return Ok(());
+    };
+    let status = status(review);
+    if status.signed_off {
+        return Ok(());
+    }
+    Err(ClanopediaError::InvalidProposalState(format!(
+        "Proposal {} needs {} reviewer approvals before it can execute and has {}",
+        proposal.id, status.required_approvals, status.approvals
+    )))
+}
+
+// A proposal still open to review, with its collection
+fn open_proposal(
+    collection_id: &str,
+    proposal_id: &str,
+) -> ClanopediaResult<(Collection, Proposal)> {
+    let collection = storage::get_collection(&collection_id.to_string())?;
+    trash::ensure_not_trashed(&collection)?;
+    admin::ensure_not_paused(&collection)?;
+
+    let proposal = storage::get_proposal(collection_id, proposal_id)
+        .ok_or_else(|| ClanopediaError::NotFound(format!("Proposal {} not found", proposal_id)))?;
+    archive::ensure_proposal_allowed(&collection, &proposal.proposal_type)?;
+    if proposal.executed
+        || !matches!(
+            proposal.status,
+            ProposalStatus::Active | ProposalStatus::Approved
+        )
+    {
+        return Err(ClanopediaError::InvalidProposalState(
+            "Only open proposals can be reviewed".to_string(),
+        ));
+    }
+    Ok((collection, proposal))
+}
+
+fn review_stage(proposal: &mut Proposal) -> ClanopediaResult<&mut ProposalReview> {
+    let id = proposal.id.clone();
+    proposal.review.as_mut().ok_or_else(|| {
+        ClanopediaError::InvalidProposalState(format!("Proposal {} has no review stage", id))
+    })
+}
+
+/// Replace the reviewers assigned to a proposal with members of the collection's reviewer
+/// pool; admins only
+pub fn assign_reviewers(
+    caller: Principal,
+    collection_id: &str,
+    proposal_id: &str,
+    mut reviewers: Vec<Principal>,
+) -> ClanopediaResult<()> {
+    let (collection, mut proposal) = open_proposal(collection_id, proposal_id)?;
+    auth::require_admin(&collection, caller)?;
+    let pool = collection
+        .review_policy
+        .as_ref()
+        .map(|p| p.reviewers.as_slice())
+        .unwrap_or_default();
+
+    reviewers.sort();
+    reviewers.dedup();
+    if let Some(outsider) = reviewers.iter().find(|r| !pool.contains(r)) {
+        return Err(ClanopediaError::InvalidInput(format!(
+            "{} is not one of the collection's reviewers",
+            outsider
+        )));
+    }
+    if reviewers.contains(&proposal.creator) {
+        return Err(ClanopediaError::InvalidInput(
+            "A proposal's creator cannot review it".to_string(),
+        ));
+    }
+    let review = review_stage(&mut proposal)?;
+    if reviewers.len() < review.required_approvals as usize {
+        return Err(ClanopediaError::InvalidInput(format!(
+            "Assign at least {} reviewers",
+            review.required_approvals
+        )));
+    }
+
+    let added: Vec<Principal> = reviewers
+        .iter()
+        .filter(|r| !review.assigned.contains(r))
+        .copied()
+        .collect();
+    review.assigned = reviewers;
+    storage::update_proposal_in_storage(&collection_id.to_string(), &proposal)?;
+    notifications::review_requested(&collection, &proposal, &added);
+    Ok(())
+}
+
+/// Record a verdict or a comment. Verdicts come from assigned reviewers; the proposal's
+/// creator and admins may also comment.
+pub fn submit_review(
+    caller: Principal,
+    collection_id: &str,
+    proposal_id: &str,
+    decision: ReviewDecision,
+    note: String,
+) -> ClanopediaResult<ReviewStatus> {
+    let (collection, mut proposal) = open_proposal(collection_id, proposal_id)?;
+    let creator = proposal.creator;
+    let review = review_stage(&mut proposal)?;
+
+    let assigned = review.assigned.contains(&caller);
+    let may_comment = assigned || caller == creator || auth::is_admin(&collection, caller);
+    let allowed = match decision {
+        ReviewDecision::Approve | ReviewDecision::RequestChanges => assigned,
+        ReviewDecision::Comment => may_comment,
+    };
+    if !allowed {
+        return Err(ClanopediaError::NotAuthorized);
+    }
+
+    let note = note.trim().to_string();
+    if note.is_empty() || note.chars().count() > MAX_NOTE_CHARS {
+        return Err(ClanopediaError::InvalidInput(format!(
+            "Review notes must be 1 to {} characters",
+            MAX_NOTE_CHARS
+        )));
+    }
+    if review.reviews.len() >= MAX_REVIEWS_PER_PROPOSAL {
+        return Err(ClanopediaError::InvalidOperation(format!(
+            "Proposal {} already has {} reviews",
+            proposal_id, MAX_REVIEWS_PER_PROPOSAL
+        )));
+    }
+
+    review.reviews.push(Review {
+        reviewer: caller,
+        decision,
+        note,
+        submitted_at: time(),
+    });
+    let status = status(review);
+    storage::update_proposal_in_storage(&collection_id.to_string(), &proposal)?;
+    Ok(status)
+}
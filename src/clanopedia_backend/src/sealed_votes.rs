@@ -0,0 +1,246 @@
+// src/clanopedia_backend/src/sealed_votes.rs
+//
+// Commit–reveal voting, chosen per proposal with `create_sealed_proposal`, so votes cannot be
+// seen, or demanded as proof, while a proposal is open. During the usual voting period voters
+// submit only sha256(voter principal bytes || vote name || salt), and may replace it; once
+// the period ends they reveal the vote and salt, and only revealed votes count toward the
+// threshold. A proposal that is not approved by the end of the reveal window expires. The
+// proposal keeps each commitment with when it was made and revealed. Token weight is the
+// balance when the vote was committed.
+
+use candid::Principal;
+use ic_cdk::api::time;
+use sha2::{Digest, Sha256};
+
+use crate::{
+    admin, archive,
+    external::token,
+    governance, receipts, storage,
+    types::{
+        ClanopediaError, ClanopediaResult, Collection, GovernanceModel, Proposal, ProposalStatus,
+        SealedVoting, Vote, VoteCommitment, VoteReceipt, PROPOSAL_DURATION_NANOS,
+    },
+};
+
+const REVEAL_WINDOW_NANOS: u64 = 48 * 60 * 60 * 1_000_000_000;
+const MIN_SALT_BYTES: usize = 16;
+
+/// Only votes cast in this canister can be sealed
+pub fn ensure_supported(collection: &Collection) -> ClanopediaResult<()> {
+    if collection.is_permissionless
+        || !matches!(
+            collection.governance_model,
+            GovernanceModel::Multisig | GovernanceModel::TokenBased
+        )
+    {
+        return Err(ClanopediaError::InvalidOperation(
+            "Sealed voting needs multisig or token-based governance".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+pub fn new_sealed_voting(now: u64) -> SealedVoting {
+    let commit_ends_at = now + PROPOSAL_DURATION_NANOS;
+    SealedVoting {
+        commit_ends_at,
+        reveal_ends_at: commit_ends_at + REVEAL_WINDOW_NANOS,
+        commitments: Default::default(),
+    }
+}
+
+/// When voters must have voted by; for sealed proposals, when commitments close
+pub fn voting_ends_at(proposal: &Proposal) -> u64 {
+    proposal
+        .sealed_voting
+        .as_ref()
+        .map_or(proposal.expires_at, |sealed| sealed.commit_ends_at)
+}
+
+pub fn has_committed(proposal: &Proposal, voter: &Principal) -> bool {
+    proposal
+        .sealed_voting
+        .as_ref()
+        .is_some_and(|sealed| sealed.commitments.contains_key(voter))
+}
+
+/// Plain votes are refused on sealed proposals
+pub fn ensure_open_ballot(proposal: &Proposal) -> ClanopediaResult<()> {
+    if proposal.sealed_voting.is_some() {
+        return Err(ClanopediaError::InvalidOperation(format!(
+            "Votes on proposal {} are sealed; commit one with commit_vote and reveal it once \
+             voting closes",
+            proposal.id
+        )));
+    }
+    Ok(())
+}
+
+/// A sealed proposal approved during the reveal window gets a full voting period to execute
+pub fn on_approved(proposal: &mut Proposal) {
+    if proposal.sealed_voting.is_some() {
+        proposal.expires_at = proposal.expires_at.max(time() + PROPOSAL_DURATION_NANOS);
+    }
+}
+
+fn vote_name(vote: &Vote) -> &'static str {
+    match vote {
+        Vote::Yes => "Yes",
+        Vote::No => "No",
+        Vote::Abstain => "Abstain",
+    }
+}
+
+fn commitment_hash(voter: Principal, vote: &Vote, salt: &[u8]) -> Vec<u8> {
+    let mut hasher = Sha256::new();
+    hasher.update(voter.as_slice());
+    hasher.update(vote_name(vote).as_bytes());
+    hasher.update(salt);
+    hasher.finalize().to_vec()
+}
+
+// Votes can be committed or revealed only on a sealed proposal that is still active
+fn ensure_sealed_and_active(proposal: &Proposal) -> ClanopediaResult<()> {
+    if proposal.sealed_voting.is_none() {
+        return Err(ClanopediaError::InvalidOperation(format!(
+            "Votes on proposal {} are not sealed; use vote_on_proposal_endpoint",
+            proposal.id
+        )));
+    }
+    if proposal.status != ProposalStatus::Active || proposal.executed {
+        return Err(ClanopediaError::InvalidProposalState(
+            "Proposal is not active".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+// The proposal's collection, open to votes from `voter`. The proposal itself is read again
+// once the checks' awaits are done.
+async fn load(
+    collection_id: &str,
+    proposal_id: &str,
+    voter: Principal,
+) -> ClanopediaResult<Collection> {
+    let proposal = governance::get_proposal(collection_id, proposal_id)?;
+    ensure_sealed_and_active(&proposal)?;
+    let collection = storage::get_collection(&collection_id.to_string())?;
+    admin::ensure_not_paused(&collection)?;
+    archive::ensure_proposal_allowed(&collection, &proposal.proposal_type)?;
+    let ledger = collection.governance_token.map(token::ledger);
+    governance::validate_voter(&collection, ledger.as_ref(), &voter).await?;
+    Ok(collection)
+}
+
+fn ensure_commit_open(proposal: &Proposal) -> ClanopediaResult<()> {
+    if time() >= voting_ends_at(proposal) {
+        return Err(ClanopediaError::InvalidProposalState(format!(
+            "Voting on proposal {} has closed; reveal committed votes instead",
+            proposal.id
+        )));
+    }
+    Ok(())
+}
+
+/// Record `voter`'s commitment, replacing any earlier one, while voting is open
+pub async fn commit_vote(
+    collection_id: &str,
+    proposal_id: &str,
+    voter: Principal,
+    hash: Vec<u8>,
+) -> ClanopediaResult<()> {
+    if hash.len() != 32 {
+        return Err(ClanopediaError::InvalidInput(
+            "A vote commitment is a 32-byte sha256 hash".to_string(),
+        ));
+    }
+    let collection = load(collection_id, proposal_id, voter).await?;
+    ensure_commit_open(&governance::get_proposal(collection_id, proposal_id)?)?;
+
+    let weight = match (&collection.governance_model, collection.governance_token) {
+        (GovernanceModel::TokenBased, Some(ledger)) => {
+            Some(token::get_token_balance(&token::ledger(ledger), voter).await?)
+        }
+        _ => None,
+    };
+
+    // Other voters may have committed during the awaits; only this voter's entries change
+    governance::modify_proposal(collection_id, proposal_id, |proposal| {
+        ensure_sealed_and_active(proposal)?;
+        ensure_commit_open(proposal)?;
+        if let Some(weight) = weight {
+            proposal.token_votes.insert(voter, weight);
+        }
+        if let Some(sealed) = proposal.sealed_voting.as_mut() {
+            sealed.commitments.insert(
+                voter,
+                VoteCommitment {
+                    hash,
+                    committed_at: time(),
+                    revealed_at: None,
+                },
+            );
+        }
+        Ok(())
+    })?;
+    Ok(())
+}
+
+/// Reveal a committed vote after voting closes; it counts toward the threshold from then on
+pub async fn reveal_vote(
+    collection_id: &str,
+    proposal_id: &str,
+    voter: Principal,
+    vote: Vote,
+    salt: Vec<u8>,
+) -> ClanopediaResult<VoteReceipt> {
+    receipts::ensure_capacity()?;
+    if salt.len() < MIN_SALT_BYTES {
+        return Err(ClanopediaError::InvalidInput(format!(
+            "The salt must be at least {} bytes",
+            MIN_SALT_BYTES
+        )));
+    }
+    let collection = load(collection_id, proposal_id, voter).await?;
+
+    // Checked against the proposal as stored after the voter was validated
+    let proposal = governance::modify_proposal(collection_id, proposal_id, |proposal| {
+        ensure_sealed_and_active(proposal)?;
+        let now = time();
+        if now < voting_ends_at(proposal) {
+            return Err(ClanopediaError::InvalidProposalState(format!(
+                "Votes on proposal {} can be revealed once voting closes",
+                proposal_id
+            )));
+        }
+        if now >= proposal.expires_at {
+            return Err(ClanopediaError::ProposalExpired);
+        }
+
+        let Some(commitment) = proposal
+            .sealed_voting
+            .as_mut()
+            .and_then(|sealed| sealed.commitments.get_mut(&voter))
+        else {
+            return Err(ClanopediaError::NotFound(format!(
+                "{} committed no vote on proposal {}",
+                voter, proposal_id
+            )));
+        };
+        if commitment.revealed_at.is_some() {
+            return Err(ClanopediaError::InvalidOperation(
+                "This vote has already been revealed".to_string(),
+            ));
+        }
+        if commitment.hash != commitment_hash(voter, &vote, &salt) {
+            return Err(ClanopediaError::InvalidInput(
+                "The vote and salt do not match the commitment".to_string(),
+            ));
+        }
+        commitment.revealed_at = Some(now);
+        proposal.votes.insert(voter, vote.clone());
+        Ok(())
+    })?;
+
+    governance::tally_vote(&collection, proposal, voter, vote).await
+}
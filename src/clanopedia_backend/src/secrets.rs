@@ -0,0 +1,139 @@
+// src/clanopedia_backend/src/secrets.rs
+
+use candid::Principal;
+use ic_cdk::api::time;
+
+use crate::{
+    auth, bots, storage,
+    types::{ClanopediaError, ClanopediaResult, SealedSecret, SecretInfo, StorageMap},
+    vault,
+};
+
+const MAX_SECRET_NAME_LEN: usize = 64;
+const MAX_SECRET_VALUE_LEN: usize = 4 * 1024;
+
+/// Reserved name under which a collection's embedding proxy API key is kept
+pub const EMBEDDING_PROXY_SECRET: &str = "embedding_proxy_api_key";
+
+fn require_admin(collection_id: &str, caller: Principal) -> ClanopediaResult<()> {
+    let collection = storage::get_collection(&collection_id.to_string())?;
+    auth::require_admin(&collection, caller)
+}
+
+fn validate_secret_name(name: &str) -> ClanopediaResult<()> {
+    if name.is_empty() || name.len() > MAX_SECRET_NAME_LEN {
+        return Err(ClanopediaError::InvalidInput(format!(
+            "Secret names must be 1-{} characters",
+            MAX_SECRET_NAME_LEN
+        )));
+    }
+    if !name
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-' || c == '.')
+    {
+        return Err(ClanopediaError::InvalidInput(
+            "Secret names may only contain letters, digits, '_', '-' and '.'".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+// ============================
+// ADMIN OPERATIONS
+// ============================
+
+pub async fn set_secret(
+    collection_id: &str,
+    caller: Principal,
+    name: String,
+    value: String,
+) -> ClanopediaResult<()> {
+    require_admin(collection_id, caller)?;
+    validate_secret_name(&name)?;
+    if name == EMBEDDING_PROXY_SECRET {
+        return Err(ClanopediaError::InvalidInput(
+            "The embedding proxy key is managed through the collection config".to_string(),
+        ));
+    }
+    if name.starts_with(bots::KEY_SECRET_PREFIX) {
+        return Err(ClanopediaError::InvalidInput(
+            "Bot signing keys are issued by register_chat_bot".to_string(),
+        ));
+    }
+
+    storage::ensure_capacity(StorageMap::CollectionSecrets, 1)?;
+    store_secret(collection_id, &name, &value, caller).await
+}
+
+pub fn delete_secret(collection_id: &str, caller: Principal, name: &str) -> ClanopediaResult<()> {
+    require_admin(collection_id, caller)?;
+    storage::remove_secret(collection_id, name);
+    Ok(())
+}
+
+/// Names and update times only; secret values are never returned
+pub fn list_secrets(collection_id: &str, caller: Principal) -> ClanopediaResult<Vec<SecretInfo>> {
+    require_admin(collection_id, caller)?;
+    Ok(storage::list_secrets(collection_id)
+        .into_iter()
+        .map(|(name, secret)| SecretInfo {
+            name,
+            updated_at: secret.updated_at,
+            updated_by: secret.updated_by,
+        })
+        .collect())
+}
+
+// ============================
+// INTERNAL ACCESS
+// ============================
+
+pub async fn store_secret(
+    collection_id: &str,
+    name: &str,
+    value: &str,
+    updated_by: Principal,
+) -> ClanopediaResult<()> {
+    if value.is_empty() || value.len() > MAX_SECRET_VALUE_LEN {
+        return Err(ClanopediaError::InvalidInput(format!(
+            "Secret values must be 1-{} bytes",
+            MAX_SECRET_VALUE_LEN
+        )));
+    }
+
+    let ciphertext = vault::seal(value.as_bytes()).await?;
+    storage::set_secret(
+        collection_id,
+        name,
+        SealedSecret {
+            ciphertext,
+            updated_at: time(),
+            updated_by,
+        },
+    );
+    Ok(())
+}
+
+pub fn get_secret(collection_id: &str, name: &str) -> ClanopediaResult<Option<String>> {
+    storage::get_secret(collection_id, name)
+        .map(|secret| {
+            let value = vault::open(&secret.ciphertext)?;
+            String::from_utf8(value).map_err(|e| {
+                ClanopediaError::InvalidInput(format!("Stored secret {} is invalid: {}", name, e))
+            })
+        })
+        .transpose()
+}
+
+/// Resolve an optional secret reference, failing if a named secret does not exist
+pub fn resolve_secret(collection_id: &str, name: Option<&str>) -> ClanopediaResult<Option<String>> {
+    match name {
+        Some(name) => get_secret(collection_id, name)?.map(Some).ok_or_else(|| {
+            ClanopediaError::NotFound(format!(
+                "Secret {} not found in collection {}",
+                name, collection_id
+            ))
+        }),
+        None => Ok(None),
+    }
+}
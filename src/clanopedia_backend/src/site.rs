@@ -0,0 +1,377 @@
+// src/clanopedia_backend/src/site.rs
+//
+// What crawlers fetch over the HTTP gateway: `/sitemap.xml`, a sitemap index pointing at one
+// sitemap per public collection, and OpenSearch descriptions for the site and for each
+// collection. Public means neither encrypted nor in the trash. Document pages come from
+// sitemap entries written whenever the keyword index changes, so serving a sitemap is one
+// range read. Responses are not certified, so crawlers must use the canister's raw domain.
+//
+// Frontend routes linked: `/c/{collection}`, `/c/{collection}/d/{document}` and `/search?q=`
+// or `/c/{collection}/search?q=`, where collections and documents go by their slug if they
+// have one. A document without a slug gets its title appended, which pages can ignore.
+
+use candid::Principal;
+use chrono::DateTime;
+use ic_cdk::api::time;
+use quick_xml::escape::escape;
+
+use crate::{
+    config_history,
+    proxy::require_controller,
+    slugs, storage,
+    types::{
+        ClanopediaError, ClanopediaResult, Collection, ConfigSetting, GatewayRequest,
+        GatewayResponse, SiteConfig, SitemapEntry,
+    },
+};
+
+const MAX_URLS_PER_SITEMAP: usize = 50_000; // The sitemap protocol's limit
+const MAX_SLUG_CHARS: usize = 80;
+const MAX_SITE_NAME_CHARS: usize = 16; // OpenSearch ShortName limit
+const MAX_SITE_URL_CHARS: usize = 512;
+const CACHE_CONTROL: &str = "public, max-age=3600";
+
+// ============================
+// SITEMAP ENTRIES
+// ============================
+
+// Percent-encode everything but unreserved characters, for use as one path segment
+fn encode_segment(segment: &str) -> String {
+    let mut encoded = String::with_capacity(segment.len());
+    for byte in segment.bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'.' | b'_' | b'~') {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    encoded
+}
+
+fn decode_segment(segment: &str) -> Option<String> {
+    let bytes = segment.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = std::str::from_utf8(bytes.get(i + 1..i + 3)?).ok()?;
+            decoded.push(u8::from_str_radix(hex, 16).ok()?);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(decoded).ok()
+}
+
+// Lowercase ASCII words of the title joined by hyphens
+fn slug(title: &str) -> String {
+    let mut slug = String::new();
+    for word in title
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|w| !w.is_empty())
+    {
+        if slug.len() + word.len() + 1 > MAX_SLUG_CHARS {
+            break;
+        }
+        if !slug.is_empty() {
+            slug.push('-');
+        }
+        slug.push_str(&word.to_ascii_lowercase());
+    }
+    slug
+}
+
+// A collection's page, under its slug if it has one
+fn collection_path(collection: &Collection) -> String {
+    let segment = collection.slug.as_deref().unwrap_or(&collection.id);
+    format!("/c/{}", encode_segment(segment))
+}
+
+// A document's page relative to its collection's, under its slug if it has one
+fn document_path(collection_id: &str, document_id: &str, title: &str) -> String {
+    if let Some(slug) =
+        storage::get_document_overlay(collection_id, document_id).and_then(|o| o.slug)
+    {
+        return format!("/d/{}", slug);
+    }
+    let path = format!("/d/{}", encode_segment(document_id));
+    match slug(title) {
+        slug if slug.is_empty() => path,
+        slug => format!("{}/{}", path, slug),
+    }
+}
+
+/// List a document in its collection's sitemap, or refresh its entry; called whenever the
+/// keyword index takes a document
+pub fn index_document(collection_id: &str, document_id: &str, title: &str) {
+    storage::set_sitemap_entry(
+        collection_id,
+        document_id,
+        SitemapEntry {
+            path: document_path(collection_id, document_id, title),
+            updated_at: Some(time()),
+        },
+    );
+}
+
+/// Rewrite a document's entry after its slug changed
+pub fn refresh_document(collection_id: &str, document_id: &str) {
+    if let Some(indexed) = storage::get_indexed_document(collection_id, document_id) {
+        index_document(collection_id, document_id, &indexed.title);
+    }
+}
+
+pub fn remove_document(collection_id: &str, document_id: &str) {
+    storage::remove_sitemap_entry(collection_id, document_id);
+}
+
+/// Fill the sitemap from the keyword index when it has never been written, i.e. on the first
+/// upgrade that has it. When those documents last changed is not known.
+pub fn backfill() {
+    if storage::has_sitemap_entries() {
+        return;
+    }
+    for collection in storage::list_collections() {
+        for (document_id, indexed) in storage::list_indexed_documents(&collection.id) {
+            storage::set_sitemap_entry(
+                &collection.id,
+                &document_id,
+                SitemapEntry {
+                    path: document_path(&collection.id, &document_id, &indexed.title),
+                    updated_at: None,
+                },
+            );
+        }
+    }
+}
+
+// ============================
+// SITE CONFIG
+// ============================
+
+pub fn get_site_config() -> SiteConfig {
+    storage::get_site_config()
+}
+
+/// Set where the frontend is served and the name search engines show; controllers only
+pub fn set_site_config(caller: Principal, config: SiteConfig) -> ClanopediaResult<()> {
+    require_controller(caller)?;
+
+    let site_name = config.site_name.trim().to_string();
+    if site_name.is_empty() || site_name.chars().count() > MAX_SITE_NAME_CHARS {
+        return Err(ClanopediaError::InvalidInput(format!(
+            "Site names must be 1 to {} characters",
+            MAX_SITE_NAME_CHARS
+        )));
+    }
+    let site_url = match config.site_url {
+        Some(url) => {
+            let url = url.trim().trim_end_matches('/').to_string();
+            let host = url.strip_prefix("https://").unwrap_or("");
+            if host.is_empty()
+                || host.starts_with('/')
+                || url.len() > MAX_SITE_URL_CHARS
+                || url.contains(|c: char| c.is_whitespace() || matches!(c, '?' | '#'))
+            {
+                return Err(ClanopediaError::InvalidInput(format!(
+                    "Site URL {} must be an https URL such as https://example.com",
+                    url
+                )));
+            }
+            Some(url)
+        }
+        None => None,
+    };
+
+    let config = SiteConfig {
+        site_url,
+        site_name,
+    };
+    storage::set_site_config(config.clone());
+    config_history::record(caller, None, ConfigSetting::SiteConfig(config))?;
+    Ok(())
+}
+
+// ============================
+// HTTP
+// ============================
+
+fn is_public(collection: &Collection) -> bool {
+    collection.encryption.is_none() && collection.trashed.is_none()
+}
+
+fn public_collection(collection_id: &str) -> Option<Collection> {
+    storage::get_collection(&collection_id.to_string())
+        .ok()
+        .filter(is_public)
+}
+
+// The origin the request came in on, for links back to this canister
+fn canister_origin(request: &GatewayRequest) -> String {
+    request
+        .headers
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case("host"))
+        .map(|(_, host)| format!("https://{}", host.trim()))
+        .unwrap_or_else(|| format!("https://{}.raw.icp0.io", ic_cdk::id()))
+}
+
+fn lastmod(nanos: u64) -> String {
+    DateTime::from_timestamp((nanos / 1_000_000_000) as i64, 0)
+        .map(|d| format!("<lastmod>{}</lastmod>", d.format("%Y-%m-%d")))
+        .unwrap_or_default()
+}
+
+fn sitemap_index(origin: &str) -> String {
+    let mut xml = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <sitemapindex xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n",
+    );
+    for collection in storage::list_collections().into_iter().filter(is_public) {
+        xml.push_str(&format!(
+            "<sitemap><loc>{}/sitemaps/{}.xml</loc>{}</sitemap>\n",
+            escape(origin),
+            encode_segment(collection.slug.as_deref().unwrap_or(&collection.id)),
+            lastmod(collection.updated_at)
+        ));
+    }
+    xml.push_str("</sitemapindex>\n");
+    xml
+}
+
+fn collection_sitemap(site: &str, collection: &Collection) -> String {
+    let mut xml = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n",
+    );
+    let page = format!("{}{}", escape(site), collection_path(collection));
+    xml.push_str(&format!(
+        "<url><loc>{}</loc>{}</url>\n",
+        page,
+        lastmod(collection.updated_at)
+    ));
+    for (document_id, entry) in
+        storage::list_sitemap_entries(&collection.id, MAX_URLS_PER_SITEMAP - 1)
+    {
+        if storage::get_trashed_document(&collection.id, &document_id).is_some() {
+            continue;
+        }
+        xml.push_str(&format!(
+            "<url><loc>{}{}</loc>{}</url>\n",
+            page,
+            entry.path,
+            entry.updated_at.map(lastmod).unwrap_or_default()
+        ));
+    }
+    xml.push_str("</urlset>\n");
+    xml
+}
+
+fn opensearch_description(
+    site: &str,
+    config: &SiteConfig,
+    collection: Option<&Collection>,
+) -> String {
+    let (short_name, description, search_path) = match collection {
+        Some(c) => (
+            c.name.chars().take(MAX_SITE_NAME_CHARS).collect::<String>(),
+            c.description.clone(),
+            format!("{}/search", collection_path(c)),
+        ),
+        None => (
+            config.site_name.clone(),
+            format!("Search {}", config.site_name),
+            "/search".to_string(),
+        ),
+    };
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <OpenSearchDescription xmlns=\"http://a9.com/-/spec/opensearch/1.1/\">\n\
+         <ShortName>{}</ShortName>\n\
+         <Description>{}</Description>\n\
+         <InputEncoding>UTF-8</InputEncoding>\n\
+         <Url type=\"text/html\" template=\"{}{}?q={{searchTerms}}\"/>\n\
+         </OpenSearchDescription>\n",
+        escape(short_name.as_str()),
+        escape(description.chars().take(1024).collect::<String>().as_str()),
+        escape(site),
+        search_path
+    )
+}
+
+fn respond(status_code: u16, content_type: &str, body: String) -> GatewayResponse {
+    GatewayResponse {
+        status_code,
+        headers: vec![
+            ("Content-Type".to_string(), content_type.to_string()),
+            ("Cache-Control".to_string(), CACHE_CONTROL.to_string()),
+        ],
+        body: body.into_bytes(),
+    }
+}
+
+fn not_found() -> GatewayResponse {
+    respond(404, "text/plain; charset=utf-8", "Not found".to_string())
+}
+
+/// Serve a gateway request for the sitemap or an OpenSearch description
+pub fn http_request(request: GatewayRequest) -> GatewayResponse {
+    if request.method != "GET" && request.method != "HEAD" {
+        return respond(
+            405,
+            "text/plain; charset=utf-8",
+            "Method not allowed".to_string(),
+        );
+    }
+    let path = request.url.split(['?', '#']).next().unwrap_or_default();
+    let origin = canister_origin(&request);
+    let config = storage::get_site_config();
+    let site = config.site_url.clone().unwrap_or_else(|| origin.clone());
+
+    let collection_in = |prefix: &str, suffix: &str| {
+        path.strip_prefix(prefix)
+            .and_then(|rest| rest.strip_suffix(suffix))
+            .and_then(decode_segment)
+            .and_then(|id_or_slug| public_collection(&slugs::collection_id(&id_or_slug)))
+    };
+
+    let response = if path == "/sitemap.xml" {
+        respond(200, "application/xml", sitemap_index(&origin))
+    } else if path == "/opensearch.xml" {
+        respond(
+            200,
+            "application/opensearchdescription+xml",
+            opensearch_description(&site, &config, None),
+        )
+    } else if path.starts_with("/sitemaps/") {
+        match collection_in("/sitemaps/", ".xml") {
+            Some(collection) => respond(
+                200,
+                "application/xml",
+                collection_sitemap(&site, &collection),
+            ),
+            None => not_found(),
+        }
+    } else if path.starts_with("/opensearch/") {
+        match collection_in("/opensearch/", ".xml") {
+            Some(collection) => respond(
+                200,
+                "application/opensearchdescription+xml",
+                opensearch_description(&site, &config, Some(&collection)),
+            ),
+            None => not_found(),
+        }
+    } else {
+        not_found()
+    };
+
+    if request.method == "HEAD" {
+        return GatewayResponse {
+            body: Vec::new(),
+            ..response
+        };
+    }
+    response
+}
@@ -0,0 +1,157 @@
+// src/clanopedia_backend/src/slugs.rs
+//
+// Readable names for collections and documents, for URLs and anywhere a query takes an ID.
+// Collection slugs are unique across the canister, document slugs within their collection.
+// A slug is lowercase ASCII words joined by single hyphens, so it never matches a generated
+// collection ID, which contains underscores. Slugs are set, changed and cleared by `SetSlug`
+// proposals; the old slug is released when the proposal executes.
+
+use crate::{
+    site, storage, trash,
+    types::{
+        ChangeKind, ClanopediaError, ClanopediaResult, Collection, CollectionId, DocumentId,
+        DocumentMetadataOverlay,
+    },
+};
+
+const MIN_SLUG_CHARS: usize = 3;
+const MAX_SLUG_CHARS: usize = 64;
+
+fn validate_format(slug: &str) -> ClanopediaResult<()> {
+    let valid = (MIN_SLUG_CHARS..=MAX_SLUG_CHARS).contains(&slug.len())
+        && slug.split('-').all(|word| {
+            !word.is_empty()
+                && word
+                    .bytes()
+                    .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit())
+        })
+        && slug.bytes().any(|b| b.is_ascii_lowercase());
+    if !valid {
+        return Err(ClanopediaError::InvalidInput(format!(
+            "Slug {} must be {} to {} lowercase letters, digits and single hyphens, with at least \
+             one letter",
+            slug, MIN_SLUG_CHARS, MAX_SLUG_CHARS
+        )));
+    }
+    Ok(())
+}
+
+/// The collection ID a query was given, whether as the ID itself or as a slug
+pub fn collection_id(id_or_slug: &str) -> CollectionId {
+    storage::get_collection_by_slug(id_or_slug).unwrap_or_else(|| id_or_slug.to_string())
+}
+
+/// The document ID a query was given, whether as the ID itself or as a slug in the collection
+pub fn document_id(collection_id: &str, id_or_slug: &str) -> DocumentId {
+    storage::get_document_by_slug(collection_id, id_or_slug)
+        .unwrap_or_else(|| id_or_slug.to_string())
+}
+
+fn document_slug(collection_id: &str, document_id: &str) -> Option<String> {
+    storage::get_document_overlay(collection_id, document_id).and_then(|o| o.slug)
+}
+
+/// Check a `SetSlug` proposal: the slug must be well formed and free, and a document slug must
+/// not read as the ID of another document in the collection
+pub fn validate_set_slug(
+    collection: &Collection,
+    document_id: Option<&DocumentId>,
+    slug: Option<&String>,
+) -> ClanopediaResult<()> {
+    let current = match document_id {
+        None => collection.slug.clone(),
+        Some(document_id) => {
+            if trash::is_document_trashed(&collection.id, document_id) {
+                return Err(ClanopediaError::InvalidOperation(format!(
+                    "Document {} is in the trash",
+                    document_id
+                )));
+            }
+            document_slug(&collection.id, document_id)
+        }
+    };
+    let Some(slug) = slug else {
+        return match current {
+            Some(_) => Ok(()),
+            None => Err(ClanopediaError::InvalidInput(
+                "There is no slug to clear".to_string(),
+            )),
+        };
+    };
+    validate_format(slug)?;
+    if current.as_ref() == Some(slug) {
+        return Err(ClanopediaError::InvalidInput(format!(
+            "The slug is already {}",
+            slug
+        )));
+    }
+
+    let taken = match document_id {
+        None => storage::get_collection_by_slug(slug).is_some(),
+        Some(_) => {
+            storage::get_document_by_slug(&collection.id, slug).is_some()
+                || storage::list_known_document_ids(&collection.id).contains(slug)
+        }
+    };
+    if taken {
+        return Err(ClanopediaError::AlreadyExists(format!(
+            "Slug {} is already taken",
+            slug
+        )));
+    }
+    Ok(())
+}
+
+/// Apply an approved `SetSlug` proposal, releasing the previous slug
+pub fn execute_set_slug(
+    collection_id: &str,
+    document_id: Option<&DocumentId>,
+    slug: Option<&String>,
+) -> ClanopediaResult<()> {
+    let mut collection = storage::get_collection(&collection_id.to_string())?;
+    // Another proposal may have taken the slug since this one was created
+    validate_set_slug(&collection, document_id, slug)?;
+
+    match document_id {
+        None => {
+            if let Some(old) = collection.slug.take() {
+                storage::remove_collection_slug(&old);
+            }
+            if let Some(slug) = slug {
+                storage::set_collection_slug(slug, collection_id);
+            }
+            collection.slug = slug.cloned();
+            storage::update_collection(&collection_id.to_string(), &collection)?;
+        }
+        Some(document_id) => {
+            let mut overlay = storage::get_document_overlay(collection_id, document_id).unwrap_or(
+                DocumentMetadataOverlay {
+                    title: None,
+                    tags: None,
+                    author: None,
+                    summary: None,
+                    license: None,
+                    slug: None,
+                    updated_at: 0,
+                },
+            );
+            if let Some(old) = overlay.slug.take() {
+                storage::remove_document_slug(collection_id, &old);
+            }
+            if let Some(slug) = slug {
+                storage::set_document_slug(collection_id, slug, document_id);
+            }
+            overlay.slug = slug.cloned();
+            overlay.updated_at = ic_cdk::api::time();
+            storage::set_document_overlay(collection_id, document_id, overlay);
+            site::refresh_document(collection_id, document_id);
+            storage::append_change(
+                collection_id,
+                ChangeKind::DocumentUpdated {
+                    document_id: document_id.clone(),
+                },
+            );
+        }
+    }
+    Ok(())
+}
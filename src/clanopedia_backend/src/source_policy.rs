@@ -0,0 +1,121 @@
+// src/clanopedia_backend/src/source_policy.rs
+//
+// Per-collection allow and deny lists of the sources documents may be extracted from, e.g.
+// only a GitHub organization and one YouTube channel. The policy is set by `SetSourcePolicy`
+// proposals and checked before any URL is fetched for extraction: direct, queued, resumed
+// and re-synced. Refusals are `SourceNotAllowed` errors naming the URL and the rule.
+
+use crate::{
+    storage,
+    types::{ClanopediaError, ClanopediaResult, Collection, SourcePolicy, SourcePolicyViolation},
+};
+
+const MAX_RULES: usize = 100;
+const MAX_RULE_CHARS: usize = 200;
+
+// The lowercased host, without `www.`, and the path of `url`; the scheme is optional
+fn split_url(url: &str) -> (String, String) {
+    let rest = url.trim();
+    let rest = rest.split_once("://").map_or(rest, |(_, rest)| rest);
+    let rest = rest.split(['?', '#']).next().unwrap_or("");
+    let (authority, path) = match rest.find('/') {
+        Some(i) => rest.split_at(i),
+        None => (rest, "/"),
+    };
+    let host = authority.rsplit('@').next().unwrap_or(authority);
+    let host = host.split(':').next().unwrap_or(host).to_lowercase();
+    let host = host.strip_prefix("www.").unwrap_or(&host).to_string();
+    (host, path.to_string())
+}
+
+fn matches_rule(rule: &str, host: &str, path: &str) -> bool {
+    let (rule_host, rule_path) = split_url(rule);
+    let host_matches = match rule_host.strip_prefix("*.") {
+        Some(domain) => host == domain || host.ends_with(&format!(".{}", domain)),
+        None => host == rule_host,
+    };
+    if !host_matches {
+        return false;
+    }
+    match rule_path.strip_suffix('*') {
+        Some(prefix) => path.starts_with(prefix),
+        None => {
+            let rule_path = rule_path.trim_end_matches('/');
+            rule_path.is_empty()
+                || path.trim_end_matches('/') == rule_path
+                || path.starts_with(&format!("{}/", rule_path))
+        }
+    }
+}
+
+/// Refuse `url` if the collection's source policy does not allow it
+pub fn check_url(collection: &Collection, url: &str) -> ClanopediaResult<()> {
+    let Some(policy) = &collection.source_policy else {
+        return Ok(());
+    };
+    let (host, path) = split_url(url);
+    let violation = |rule: Option<&String>| {
+        ClanopediaError::SourceNotAllowed(SourcePolicyViolation {
+            url: url.to_string(),
+            rule: rule.cloned(),
+        })
+    };
+    if let Some(rule) = policy.deny.iter().find(|r| matches_rule(r, &host, &path)) {
+        return Err(violation(Some(rule)));
+    }
+    if !policy.allow.is_empty() && !policy.allow.iter().any(|r| matches_rule(r, &host, &path)) {
+        return Err(violation(None));
+    }
+    Ok(())
+}
+
+/// Check a `SetSourcePolicy` proposal
+pub fn validate_set_source_policy(policy: Option<&SourcePolicy>) -> ClanopediaResult<()> {
+    let Some(policy) = policy else {
+        return Ok(());
+    };
+    if policy.allow.is_empty() && policy.deny.is_empty() {
+        return Err(ClanopediaError::InvalidInput(
+            "A source policy needs at least one rule; propose no policy to lift it".to_string(),
+        ));
+    }
+    for (list, rules) in [("allow", &policy.allow), ("deny", &policy.deny)] {
+        if rules.len() > MAX_RULES {
+            return Err(ClanopediaError::InvalidInput(format!(
+                "The {} list has {} rules, the limit is {}",
+                list,
+                rules.len(),
+                MAX_RULES
+            )));
+        }
+        for rule in rules {
+            let (host, _) = split_url(rule);
+            let domain = host.strip_prefix("*.").unwrap_or(&host);
+            if rule.chars().count() > MAX_RULE_CHARS
+                || !domain.contains('.')
+                || domain.contains('*')
+            {
+                return Err(ClanopediaError::InvalidInput(format!(
+                    "{} rule {} must be a host with an optional path, such as github.com/org/*",
+                    list, rule
+                )));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Apply an approved `SetSourcePolicy` proposal
+pub fn execute_set_source_policy(
+    collection_id: &str,
+    policy: Option<&SourcePolicy>,
+) -> ClanopediaResult<()> {
+    validate_set_source_policy(policy)?;
+    let mut collection = storage::get_collection(&collection_id.to_string())?;
+    collection.source_policy = policy.cloned();
+    storage::update_collection(&collection.id, &collection)
+}
+
+pub fn get_source_policy(collection_id: &str) -> ClanopediaResult<Option<SourcePolicy>> {
+    Ok(storage::get_collection(&collection_id.to_string())?.source_policy)
+}
@@ -0,0 +1,203 @@
+// src/clanopedia_backend/src/spam.rs
+//
+// Throttles on who creates proposals, checked for every proposal in `create_proposal`.
+// Permissionless collections approve proposals without a vote, so anyone could flood them:
+// there each principal gets a default hourly limit unless admins set one, and admins can charge
+// a fee per proposal, in cycles attached to the call or in tokens pulled from an ICRC-2
+// approval. A blocklist and an explicit hourly limit apply under every governance model.
+// Admins are never throttled. Limits are counted on the heap, so an upgrade resets them.
+
+use candid::Principal;
+use ic_cdk::api::call::{msg_cycles_accept128, msg_cycles_available128};
+
+use crate::{
+    archive,
+    auth::{self, require_admin},
+    external::token,
+    logging, rate_limit, storage, trash,
+    types::{
+        ClanopediaError, ClanopediaResult, Collection, GovernanceModel, ProposalFee,
+        SpamProtection,
+    },
+};
+
+const DEFAULT_PERMISSIONLESS_PROPOSALS_PER_HOUR: u32 = 10;
+const HOUR_NANOS: u64 = 60 * 60 * 1_000_000_000;
+const MAX_BLOCKLIST: usize = 500;
+
+fn is_permissionless(collection: &Collection) -> bool {
+    collection.is_permissionless
+        || matches!(collection.governance_model, GovernanceModel::Permissionless)
+}
+
+/// Replace the collection's throttles; admins only
+pub fn set_spam_protection(
+    caller: Principal,
+    collection_id: &str,
+    mut protection: SpamProtection,
+) -> ClanopediaResult<()> {
+    let mut collection = storage::get_collection(&collection_id.to_string())?;
+    require_admin(&collection, caller)?;
+    trash::ensure_not_trashed(&collection)?;
+    archive::ensure_not_archived(&collection)?;
+
+    if protection.max_proposals_per_hour == Some(0) {
+        return Err(ClanopediaError::InvalidInput(
+            "The hourly proposal limit must be at least 1; block principals instead".to_string(),
+        ));
+    }
+    let zero_fee = match &protection.fee {
+        Some(ProposalFee::Cycles { amount }) => *amount == 0,
+        Some(ProposalFee::Tokens { amount, .. }) => *amount == 0u64,
+        None => false,
+    };
+    if zero_fee {
+        return Err(ClanopediaError::InvalidInput(
+            "Proposal fee must be greater than zero".to_string(),
+        ));
+    }
+    protection.blocklist.sort();
+    protection.blocklist.dedup();
+    if protection.blocklist.len() > MAX_BLOCKLIST {
+        return Err(ClanopediaError::InvalidInput(format!(
+            "At most {} principals can be blocked",
+            MAX_BLOCKLIST
+        )));
+    }
+    if let Some(admin) = protection
+        .blocklist
+        .iter()
+        .find(|p| auth::is_admin(&collection, **p))
+    {
+        return Err(ClanopediaError::InvalidInput(format!(
+            "{} is an admin and cannot be blocked",
+            admin
+        )));
+    }
+
+    collection.spam_protection = Some(protection);
+    storage::update_collection(&collection.id, &collection)
+}
+
+pub fn get_spam_protection(collection_id: &str) -> ClanopediaResult<Option<SpamProtection>> {
+    Ok(storage::get_collection(&collection_id.to_string())?.spam_protection)
+}
+
+/// Refuse blocked principals and those over their hourly limit; an allowed proposal counts
+/// toward the limit
+pub fn check_proposer(collection: &Collection, creator: Principal) -> ClanopediaResult<()> {
+    if auth::is_admin(collection, creator) {
+        return Ok(());
+    }
+    let protection = collection.spam_protection.as_ref();
+    if protection.is_some_and(|p| p.blocklist.contains(&creator)) {
+        return Err(ClanopediaError::Unauthorized(format!(
+            "{} is blocked from creating proposals in collection {}",
+            creator, collection.id
+        )));
+    }
+
+    let limit = protection
+        .and_then(|p| p.max_proposals_per_hour)
+        .or(is_permissionless(collection).then_some(DEFAULT_PERMISSIONLESS_PROPOSALS_PER_HOUR));
+    match limit {
+        Some(limit) => rate_limit::check_rate_limit(
+            &format!("Proposal creation in {}", collection.id),
+            creator,
+            limit as usize,
+            HOUR_NANOS,
+        ),
+        None => Ok(()),
+    }
+}
+
+/// Charge the proposal fee of a permissionless collection. Proposals the canister creates on
+/// a principal's behalf, e.g. from queued extraction jobs, are not charged. A token fee is
+/// pulled into the collection's escrow and returned; the caller forwards it with
+/// `forward_fee` once the proposal is stored, or gives it back with `refund_fee`. A cycles
+/// fee is only checked against the attached cycles and returned; the caller takes it with
+/// `accept_fee` once the proposal will be stored, and cycles left unaccepted go back.
+pub async fn collect_fee(
+    collection: &Collection,
+    creator: Principal,
+) -> ClanopediaResult<Option<ProposalFee>> {
+    if auth::is_admin(collection, creator)
+        || !is_permissionless(collection)
+        || ic_cdk::caller() != creator
+    {
+        return Ok(None);
+    }
+    let Some(fee) = collection.spam_protection.as_ref().and_then(|p| p.fee.clone()) else {
+        return Ok(None);
+    };
+
+    match &fee {
+        ProposalFee::Cycles { amount } => {
+            let available = msg_cycles_available128();
+            if available < *amount as u128 {
+                return Err(ClanopediaError::InvalidOperation(format!(
+                    "Creating a proposal costs {} cycles; {} were attached",
+                    amount, available
+                )));
+            }
+            Ok(Some(fee))
+        }
+        ProposalFee::Tokens { ledger, amount, .. } => {
+            let escrow = token::escrow_subaccount(&collection.id);
+            token::pull_approved_tokens(&token::ledger(*ledger), creator, amount.clone(), escrow)
+                .await
+                .map_err(|e| {
+                    ClanopediaError::InvalidOperation(format!(
+                        "Proposal fee of {} tokens could not be collected. Approve this canister as spender first: {}",
+                        amount, e
+                    ))
+                })?;
+            Ok(Some(fee))
+        }
+    }
+}
+
+/// Take a cycles fee checked by `collect_fee`
+pub fn accept_fee(fee: &ProposalFee) {
+    if let ProposalFee::Cycles { amount } = fee {
+        msg_cycles_accept128(*amount as u128);
+    }
+}
+
+/// Send a collected token fee on to the treasury, when one is set; a failed transfer leaves
+/// it with the canister and is logged
+pub async fn forward_fee(collection_id: &str, creator: Principal, fee: ProposalFee) {
+    let ProposalFee::Tokens {
+        ledger,
+        amount,
+        treasury: Some(treasury),
+    } = fee
+    else {
+        return;
+    };
+    let escrow = token::escrow_subaccount(collection_id);
+    if let Err(e) = token::send_tokens(&token::ledger(ledger), Some(escrow), treasury, amount).await
+    {
+        logging::error(
+            "governance",
+            Some(collection_id),
+            format!("Forwarding the proposal fee of {} failed: {}", creator, e),
+        );
+    }
+}
+
+/// Give a collected token fee back to `creator` when their proposal was not stored
+pub async fn refund_fee(collection_id: &str, creator: Principal, fee: ProposalFee) {
+    let ProposalFee::Tokens { ledger, amount, .. } = fee else {
+        return;
+    };
+    let escrow = token::escrow_subaccount(collection_id);
+    if let Err(e) = token::send_tokens(&token::ledger(ledger), Some(escrow), creator, amount).await
+    {
+        logging::error(
+            "governance",
+            Some(collection_id),
+            format!("Refunding the proposal fee of {} failed: {}", creator, e),
+        );
+    }
+}
@@ -0,0 +1,215 @@
+// src/clanopedia_backend/src/stats.rs
+
+use candid::Principal;
+use ic_cdk::api::time;
+use sha2::{Digest, Sha256};
+
+use crate::{
+    auth, storage,
+    types::{
+        AnalyticsBucket, AnalyticsRange, Capability, ClanopediaResult, CollectionAnalytics,
+        QueryLogEntry, QueryStat, StorageMap, VoterCount, WeeklyRollup,
+    },
+};
+
+pub const WEEK_NANOS: u64 = 7 * 24 * 60 * 60 * 1_000_000_000;
+const MAX_LOGGED_QUERY_LEN: usize = 256;
+const MAX_TRACKED_SEARCHERS: usize = 500; // Unique searcher counts saturate beyond this
+const DEFAULT_QUERY_LIMIT: u32 = 20;
+
+/// Activity counted into the weekly rollups
+pub enum StatEvent {
+    DocumentsAdded(u64),
+    ProposalCreated,
+    Vote(Principal),
+    Search,
+    OutcallRetries(u64),
+}
+
+// ============================
+// RECORDING
+// ============================
+
+pub fn record(collection_id: &str, event: StatEvent) {
+    let week = time() / WEEK_NANOS;
+    let mut rollup = storage::get_weekly_rollup(collection_id, week).unwrap_or(WeeklyRollup {
+        week_start: week * WEEK_NANOS,
+        ..Default::default()
+    });
+
+    match event {
+        StatEvent::DocumentsAdded(count) => rollup.documents_added += count,
+        StatEvent::ProposalCreated => rollup.proposals_created += 1,
+        StatEvent::Search => rollup.searches += 1,
+        StatEvent::OutcallRetries(count) => {
+            *rollup.outcall_retries.get_or_insert(0) += count;
+        }
+        StatEvent::Vote(voter) => match rollup.votes.iter_mut().find(|v| v.voter == voter) {
+            Some(entry) => entry.votes += 1,
+            None => rollup.votes.push(VoterCount { voter, votes: 1 }),
+        },
+    }
+
+    storage::set_weekly_rollup(collection_id, week, rollup);
+}
+
+/// Log a search query against the collection. The caller is stored only as a truncated hash.
+pub fn record_search_query(collection_id: &str, query: &str, searcher: Principal, result_count: usize) {
+    let query = normalize_query(query);
+    if query.is_empty() {
+        return;
+    }
+
+    // Logging is best effort: new queries are dropped once the log is at capacity
+    let existing = storage::get_query_log_entry(collection_id, &query);
+    if existing.is_none() && storage::ensure_capacity(StorageMap::QueryLog, 1).is_err() {
+        return;
+    }
+    let mut entry = existing.unwrap_or(QueryLogEntry {
+        query,
+        ..Default::default()
+    });
+
+    entry.count += 1;
+    if result_count == 0 {
+        entry.zero_result_count += 1;
+    }
+    entry.last_searched_at = time();
+
+    let searcher_hash = hash_principal(searcher);
+    if entry.searcher_hashes.len() < MAX_TRACKED_SEARCHERS
+        && !entry.searcher_hashes.contains(&searcher_hash)
+    {
+        entry.searcher_hashes.push(searcher_hash);
+    }
+
+    storage::set_query_log_entry(collection_id, entry);
+}
+
+fn normalize_query(query: &str) -> String {
+    query
+        .to_lowercase()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .chars()
+        .take(MAX_LOGGED_QUERY_LEN)
+        .collect()
+}
+
+fn hash_principal(principal: Principal) -> String {
+    let digest = Sha256::digest(principal.as_slice());
+    hex::encode(&digest[..8])
+}
+
+// ============================
+// QUERIES
+// ============================
+
+/// Most frequent queries, most searched first
+pub fn get_popular_queries(
+    collection_id: &str,
+    caller: Principal,
+    limit: Option<u32>,
+) -> ClanopediaResult<Vec<QueryStat>> {
+    let entries = logged_queries(collection_id, caller)?;
+    Ok(top_queries(entries, limit, |e| e.count))
+}
+
+/// Queries that returned no results, most frequent first
+pub fn get_zero_result_queries(
+    collection_id: &str,
+    caller: Principal,
+    limit: Option<u32>,
+) -> ClanopediaResult<Vec<QueryStat>> {
+    let entries = logged_queries(collection_id, caller)?
+        .into_iter()
+        .filter(|e| e.zero_result_count > 0)
+        .collect();
+    Ok(top_queries(entries, limit, |e| e.zero_result_count))
+}
+
+// The query log is visible to collection admins only
+fn logged_queries(collection_id: &str, caller: Principal) -> ClanopediaResult<Vec<QueryLogEntry>> {
+    let collection = storage::get_collection(&collection_id.to_string())?;
+    auth::authorize(&collection, caller, Capability::ViewAnalytics)?;
+    Ok(storage::list_query_log(collection_id))
+}
+
+fn top_queries(
+    mut entries: Vec<QueryLogEntry>,
+    limit: Option<u32>,
+    rank: impl Fn(&QueryLogEntry) -> u64,
+) -> Vec<QueryStat> {
+    entries.sort_by_key(|e| std::cmp::Reverse((rank(e), e.last_searched_at)));
+    entries
+        .into_iter()
+        .take(limit.unwrap_or(DEFAULT_QUERY_LIMIT) as usize)
+        .map(|e| QueryStat {
+            unique_searchers: e.searcher_hashes.len() as u64,
+            query: e.query,
+            count: e.count,
+            zero_result_count: e.zero_result_count,
+            last_searched_at: e.last_searched_at,
+        })
+        .collect()
+}
+
+pub fn get_collection_analytics(
+    collection_id: &str,
+    range: AnalyticsRange,
+) -> ClanopediaResult<CollectionAnalytics> {
+    storage::get_collection(&collection_id.to_string())?;
+
+    let end = range.end.unwrap_or_else(time);
+    let mut analytics = CollectionAnalytics {
+        collection_id: collection_id.to_string(),
+        buckets: Vec::new(),
+        documents_added: 0,
+        proposals_created: 0,
+        votes: 0,
+        searches: 0,
+        outcall_retries: 0,
+        votes_by_voter: Vec::new(),
+    };
+
+    // A bucket is included when any part of its week overlaps the range
+    for rollup in storage::list_weekly_rollups(collection_id)
+        .into_iter()
+        .filter(|r| r.week_start + WEEK_NANOS > range.start && r.week_start <= end)
+    {
+        let votes: u64 = rollup.votes.iter().map(|v| v.votes).sum();
+
+        analytics.documents_added += rollup.documents_added;
+        analytics.proposals_created += rollup.proposals_created;
+        analytics.votes += votes;
+        analytics.searches += rollup.searches;
+        analytics.outcall_retries += rollup.outcall_retries.unwrap_or(0);
+
+        for count in rollup.votes {
+            match analytics
+                .votes_by_voter
+                .iter_mut()
+                .find(|v| v.voter == count.voter)
+            {
+                Some(entry) => entry.votes += count.votes,
+                None => analytics.votes_by_voter.push(count),
+            }
+        }
+
+        analytics.buckets.push(AnalyticsBucket {
+            week_start: rollup.week_start,
+            documents_added: rollup.documents_added,
+            proposals_created: rollup.proposals_created,
+            votes,
+            searches: rollup.searches,
+            outcall_retries: rollup.outcall_retries.unwrap_or(0),
+        });
+    }
+
+    analytics
+        .votes_by_voter
+        .sort_by_key(|v| std::cmp::Reverse(v.votes));
+
+    Ok(analytics)
+}
@@ -0,0 +1,69 @@
+// src/clanopedia_backend/src/status.rs
+
+use candid::Principal;
+use ic_cdk::api::management_canister::main::{
+    canister_status, CanisterIdRecord, CanisterStatusResponse, CanisterStatusType,
+};
+
+use crate::{
+    health,
+    proxy::require_controller,
+    storage,
+    types::{CanisterRuntimeStatus, ClanopediaResult, Proposal, ProposalStatus, SelfStatus},
+};
+
+/// Operator view of the canister. The management canister only answers `canister_status`
+/// when Clanopedia is among its own controllers; otherwise `runtime` is left empty and
+/// the rejection is reported in `runtime_error`.
+pub async fn get_self_status(caller: Principal) -> ClanopediaResult<SelfStatus> {
+    require_controller(caller)?;
+
+    let canister_id = ic_cdk::id();
+    let (runtime, runtime_error) = match canister_status(CanisterIdRecord { canister_id }).await {
+        Ok((response,)) => (Some(runtime_status(response)), None),
+        Err((code, e)) => (
+            None,
+            Some(format!("canister_status failed ({:?}): {}", code, e)),
+        ),
+    };
+
+    let collections = storage::list_collections();
+    let proposals: Vec<Proposal> = collections
+        .iter()
+        .flat_map(|c| storage::list_proposals(&c.id).into_values())
+        .collect();
+
+    Ok(SelfStatus {
+        canister_id,
+        runtime,
+        runtime_error,
+        cycles_balance: ic_cdk::api::canister_balance(),
+        stable_memory_pages: ic_cdk::api::stable::stable_size(),
+        collections: collections.len() as u64,
+        proposals: proposals.len() as u64,
+        active_proposals: proposals
+            .iter()
+            .filter(|p| p.status == ProposalStatus::Active)
+            .count() as u64,
+        blueband_configured: crate::get_blueband_canister_id().is_ok(),
+        external_health: health::last_health(),
+    })
+}
+
+fn runtime_status(response: CanisterStatusResponse) -> CanisterRuntimeStatus {
+    let status = match response.status {
+        CanisterStatusType::Running => "running",
+        CanisterStatusType::Stopping => "stopping",
+        CanisterStatusType::Stopped => "stopped",
+    };
+
+    CanisterRuntimeStatus {
+        status: status.to_string(),
+        memory_size: response.memory_size,
+        module_hash: response.module_hash.map(hex::encode),
+        freezing_threshold: response.settings.freezing_threshold,
+        cycles: response.cycles,
+        idle_cycles_burned_per_day: response.idle_cycles_burned_per_day,
+        controllers: response.settings.controllers,
+    }
+}
@@ -1,193 +1,3546 @@
 // src/clanopedia_backend/src/storage.rs
 
+use crate::encryption;
+use crate::memory::{self, Memory};
+use crate::{
+    extractor::{AdditionOutcome, CachedFetch, ExtractionJob},
+    AddDocumentRequest,
+};
 use crate::types::*;
 use candid::Principal;
 use ic_cdk::api::time;
-use ic_stable_structures::{
-    memory_manager::{MemoryId, MemoryManager, VirtualMemory},
-    DefaultMemoryImpl, StableBTreeMap,
-};
+use ic_stable_structures::StableBTreeMap;
+use ic_stable_structures::{Memory as _, Storable};
+use std::borrow::Cow;
 use std::cell::RefCell;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::ops::Bound;
+use std::thread::LocalKey;
+
+// ============================
+// STABLE STORAGE
+// ============================
+
+thread_local! {
+    static COLLECTIONS: RefCell<StableBTreeMap<CollectionId, Collection, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            memory::get(memory::COLLECTIONS)
+        )
+    );
+
+    static PROPOSALS: RefCell<StableBTreeMap<String, Proposal, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            memory::get(memory::PROPOSALS)
+        )
+    );
+
+    // Keyed by "collection_id:document_id"
+    static DOCUMENT_OVERLAYS: RefCell<StableBTreeMap<String, DocumentMetadataOverlay, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            memory::get(memory::DOCUMENT_OVERLAYS)
+        )
+    );
+
+    // Keyed by "collection_id:document_id"
+    static DOCUMENT_LINKS: RefCell<StableBTreeMap<String, DocumentLinks, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            memory::get(memory::DOCUMENT_LINKS)
+        )
+    );
+
+    // Keyed by "collection_id:document_id"
+    static DOCUMENT_OUTLINES: RefCell<StableBTreeMap<String, DocumentOutline, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            memory::get(memory::DOCUMENT_OUTLINES)
+        )
+    );
+
+    // Keyed by "collection_id:week_index", week index zero-padded so keys sort by time
+    static WEEKLY_ROLLUPS: RefCell<StableBTreeMap<String, WeeklyRollup, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            memory::get(memory::WEEKLY_ROLLUPS)
+        )
+    );
+
+    // Keyed by "collection_id:normalized_query"
+    static QUERY_LOG: RefCell<StableBTreeMap<String, QueryLogEntry, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            memory::get(memory::QUERY_LOG)
+        )
+    );
+
+    // Allowlisted embedding proxy URL prefixes -> time added
+    static PROXY_ALLOWLIST: RefCell<StableBTreeMap<String, u64, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            memory::get(memory::PROXY_ALLOWLIST)
+        )
+    );
+
+    static CANISTER_SECRET: RefCell<StableBTreeMap<(), Vec<u8>, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            memory::get(memory::CANISTER_SECRET)
+        )
+    );
+
+    // Keyed by "collection_id:secret_name"
+    static COLLECTION_SECRETS: RefCell<StableBTreeMap<String, SealedSecret, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            memory::get(memory::COLLECTION_SECRETS)
+        )
+    );
+
+    // Keyed by "collection_id:term"
+    static KEYWORD_POSTINGS: RefCell<StableBTreeMap<String, KeywordPostings, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            memory::get(memory::KEYWORD_POSTINGS)
+        )
+    );
+
+    // Further shards of KEYWORD_POSTINGS, keyed alike; see SHARDS below
+    static KEYWORD_POSTINGS_1: RefCell<StableBTreeMap<String, KeywordPostings, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            memory::get(memory::KEYWORD_POSTINGS_1)
+        )
+    );
+
+    static KEYWORD_POSTINGS_2: RefCell<StableBTreeMap<String, KeywordPostings, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            memory::get(memory::KEYWORD_POSTINGS_2)
+        )
+    );
+
+    static KEYWORD_POSTINGS_3: RefCell<StableBTreeMap<String, KeywordPostings, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            memory::get(memory::KEYWORD_POSTINGS_3)
+        )
+    );
+
+    // Keyed by "collection_id:document_id"
+    static KEYWORD_DOCUMENTS: RefCell<StableBTreeMap<String, IndexedDocument, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            memory::get(memory::KEYWORD_DOCUMENTS)
+        )
+    );
+
+    // Further shards of KEYWORD_DOCUMENTS, keyed alike
+    static KEYWORD_DOCUMENTS_1: RefCell<StableBTreeMap<String, IndexedDocument, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            memory::get(memory::KEYWORD_DOCUMENTS_1)
+        )
+    );
+
+    static KEYWORD_DOCUMENTS_2: RefCell<StableBTreeMap<String, IndexedDocument, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            memory::get(memory::KEYWORD_DOCUMENTS_2)
+        )
+    );
+
+    static KEYWORD_DOCUMENTS_3: RefCell<StableBTreeMap<String, IndexedDocument, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            memory::get(memory::KEYWORD_DOCUMENTS_3)
+        )
+    );
+
+    // Keyed by "collection_id:document_id"
+    static TRASHED_DOCUMENTS: RefCell<StableBTreeMap<String, TrashInfo, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            memory::get(memory::TRASHED_DOCUMENTS)
+        )
+    );
+
+    static STORAGE_CAPS: RefCell<StableBTreeMap<(), StorageCaps, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            memory::get(memory::STORAGE_CAPS)
+        )
+    );
+
+    // Keyed by receipt sequence number
+    static VOTE_RECEIPTS: RefCell<StableBTreeMap<u64, VoteReceipt, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            memory::get(memory::VOTE_RECEIPTS)
+        )
+    );
+
+    static CREATION_POLICY: RefCell<StableBTreeMap<(), CreationPolicy, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            memory::get(memory::CREATION_POLICY)
+        )
+    );
+
+    // Keyed by request id
+    static CREATION_REQUESTS: RefCell<StableBTreeMap<u64, CreationRequest, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            memory::get(memory::CREATION_REQUESTS)
+        )
+    );
+
+    // Keyed by collection ID; only collections whose creation has not finished
+    static PENDING_CREATIONS: RefCell<StableBTreeMap<String, PendingCreation, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            memory::get(memory::PENDING_CREATIONS)
+        )
+    );
+
+    static BLUEBAND_RECONCILIATION: RefCell<StableBTreeMap<(), BluebandReconcileState, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            memory::get(memory::BLUEBAND_RECONCILIATION)
+        )
+    );
+
+    static BACKUP_STATE: RefCell<StableBTreeMap<(), BackupState, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            memory::get(memory::BACKUP_STATE)
+        )
+    );
+
+    // Keyed by backup ID
+    static BACKUP_HISTORY: RefCell<StableBTreeMap<u64, BackupSummary, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            memory::get(memory::BACKUP_HISTORY)
+        )
+    );
+
+    // Keyed by "section index:entry key" -> SHA-256 of the entry as last backed up
+    static BACKUP_DIGESTS: RefCell<StableBTreeMap<String, Vec<u8>, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            memory::get(memory::BACKUP_DIGESTS)
+        )
+    );
+
+    static READ_REPLICAS: RefCell<StableBTreeMap<Principal, ReadReplica, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            memory::get(memory::READ_REPLICAS)
+        )
+    );
+
+    // Keyed by event sequence number
+    static EVENT_LOG: RefCell<StableBTreeMap<u64, EventLogEntry, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            memory::get(memory::EVENT_LOG)
+        )
+    );
+
+    // Further shards of EVENT_LOG, keyed alike
+    static EVENT_LOG_1: RefCell<StableBTreeMap<u64, EventLogEntry, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            memory::get(memory::EVENT_LOG_1)
+        )
+    );
+
+    static EVENT_LOG_2: RefCell<StableBTreeMap<u64, EventLogEntry, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            memory::get(memory::EVENT_LOG_2)
+        )
+    );
+
+    static EVENT_LOG_3: RefCell<StableBTreeMap<u64, EventLogEntry, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            memory::get(memory::EVENT_LOG_3)
+        )
+    );
+
+    static SHARD_REBALANCE: RefCell<StableBTreeMap<(), ShardRebalance, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            memory::get(memory::SHARD_REBALANCE)
+        )
+    );
+
+    // Keyed by job id
+    static EXTRACTION_JOBS: RefCell<StableBTreeMap<u64, ExtractionJob, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            memory::get(memory::EXTRACTION_JOBS)
+        )
+    );
+
+    // Keyed by "collection_id:job_id:index", with the index zero-padded to keep order
+    static JOB_DOCUMENTS: RefCell<StableBTreeMap<String, AddDocumentRequest, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            memory::get(memory::JOB_DOCUMENTS)
+        )
+    );
+
+    // Keyed like JOB_DOCUMENTS; what adding each queued document came to
+    static JOB_OUTCOMES: RefCell<StableBTreeMap<String, AdditionOutcome, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            memory::get(memory::JOB_OUTCOMES)
+        )
+    );
+
+    // Keyed by "principal:id", with the id zero-padded to keep order
+    static NOTIFICATIONS: RefCell<StableBTreeMap<String, Notification, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            memory::get(memory::NOTIFICATIONS)
+        )
+    );
+
+    // Keyed by "collection_id:document_id" -> views since the document was first viewed
+    static DOCUMENT_VIEWS: RefCell<StableBTreeMap<String, u64, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            memory::get(memory::DOCUMENT_VIEWS)
+        )
+    );
+
+    // Keyed by "collection_id:day:document_id", day zero-padded so a collection's days sort;
+    // days older than the longest trending window are dropped
+    static DAILY_VIEWS: RefCell<StableBTreeMap<String, u64, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            memory::get(memory::DAILY_VIEWS)
+        )
+    );
+
+    // Slug -> collection ID; the collection holds its own slug
+    static COLLECTION_SLUGS: RefCell<StableBTreeMap<String, CollectionId, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            memory::get(memory::COLLECTION_SLUGS)
+        )
+    );
+
+    // Keyed by "collection_id:slug" -> document ID; the document's overlay holds its slug
+    static DOCUMENT_SLUGS: RefCell<StableBTreeMap<String, DocumentId, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            memory::get(memory::DOCUMENT_SLUGS)
+        )
+    );
+
+    // Keyed by "collection_id:d:document_id" or "collection_id:p:proposal_id"
+    static REACTION_COUNTS: RefCell<StableBTreeMap<String, ReactionCounts, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            memory::get(memory::REACTION_COUNTS)
+        )
+    );
+
+    // The reaction counters key plus ":principal" -> one bit per reaction given
+    static REACTIONS: RefCell<StableBTreeMap<String, u8, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            memory::get(memory::REACTIONS)
+        )
+    );
+
+    // Keyed by "collection_id:canister"
+    static GOVERNANCE_SUBSCRIBERS: RefCell<StableBTreeMap<String, GovernanceSubscriber, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            memory::get(memory::GOVERNANCE_SUBSCRIBERS)
+        )
+    );
+
+    // Keyed by "principal:sequence", the sequence zero-padded so entries sort in order
+    static ACTIVITY: RefCell<StableBTreeMap<String, ActivityEntry, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            memory::get(memory::ACTIVITY)
+        )
+    );
+
+    // Keyed by "collection_id:bot"
+    static CHAT_BOTS: RefCell<StableBTreeMap<String, ChatBot, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            memory::get(memory::CHAT_BOTS)
+        )
+    );
+
+    // Keyed by "collection_id:bot:voter"
+    static BOT_DELEGATIONS: RefCell<StableBTreeMap<String, BotDelegation, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            memory::get(memory::BOT_DELEGATIONS)
+        )
+    );
+
+    static SITE_CONFIG: RefCell<StableBTreeMap<(), SiteConfig, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            memory::get(memory::SITE_CONFIG)
+        )
+    );
+
+    // Keyed by "collection_id:document_id"; one entry per document in the keyword index
+    static SITEMAP_ENTRIES: RefCell<StableBTreeMap<String, SitemapEntry, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            memory::get(memory::SITEMAP_ENTRIES)
+        )
+    );
+
+    // One record per principal, so a reader's lists load in a single read
+    static BOOKMARKS: RefCell<StableBTreeMap<Principal, ReaderBookmarks, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            memory::get(memory::BOOKMARKS)
+        )
+    );
+
+    // Keyed by "collection_id:document_id"
+    static DOCUMENT_NFTS: RefCell<StableBTreeMap<String, DocumentNft, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            memory::get(memory::DOCUMENT_NFTS)
+        )
+    );
+
+    // Keyed by change sequence number
+    static CHANGE_LOG: RefCell<StableBTreeMap<u64, ChangeRecord, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            memory::get(memory::CHANGE_LOG)
+        )
+    );
+
+    // Keyed by "collection_id:proposal_id:index", with the index zero-padded
+    static PROPOSAL_ATTACHMENTS: RefCell<StableBTreeMap<String, ProposalAttachment, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            memory::get(memory::PROPOSAL_ATTACHMENTS)
+        )
+    );
+
+    // Keyed by "collection_id:proposal_id:index:chunk", both numbers zero-padded
+    static ATTACHMENT_CHUNKS: RefCell<StableBTreeMap<String, Vec<u8>, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            memory::get(memory::ATTACHMENT_CHUNKS)
+        )
+    );
+
+    static OUTCALL_BUDGET: RefCell<StableBTreeMap<(), OutcallBudget, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            memory::get(memory::OUTCALL_BUDGET)
+        )
+    );
+
+    // Keyed by URL
+    static FETCH_CACHE: RefCell<StableBTreeMap<String, CachedFetch, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            memory::get(memory::FETCH_CACHE)
+        )
+    );
+
+    // Keyed by URL, like FETCH_CACHE
+    static FETCH_CACHE_BODIES: RefCell<StableBTreeMap<String, String, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            memory::get(memory::FETCH_CACHE_BODIES)
+        )
+    );
+
+    static PERMAWEB_GATEWAYS: RefCell<StableBTreeMap<(), PermawebGateways, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            memory::get(memory::PERMAWEB_GATEWAYS)
+        )
+    );
+
+    // Keyed by "collection_id:position", so a collection's pins list in display order
+    static PINNED_DOCUMENTS: RefCell<StableBTreeMap<String, PinnedDocument, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            memory::get(memory::PINNED_DOCUMENTS)
+        )
+    );
+
+    // Keyed by "collection_id:alias words", lowercased and joined by single spaces
+    static QUERY_ALIASES: RefCell<StableBTreeMap<String, QueryAlias, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            memory::get(memory::QUERY_ALIASES)
+        )
+    );
+
+    // Keyed by "collection_id:dimension:value:created_at:proposal_id", one entry per
+    // dimension a proposal can be searched by; see `proposal_index_keys`
+    static PROPOSAL_INDEX: RefCell<StableBTreeMap<String, (), Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            memory::get(memory::PROPOSAL_INDEX)
+        )
+    );
+
+    // Keyed by "collection_id:proposal_id"; kept after the proposal is removed
+    static EXECUTION_RECEIPTS: RefCell<StableBTreeMap<String, ExecutionReceipt, Memory>> =
+        RefCell::new(
+            StableBTreeMap::init(
+                memory::get(memory::EXECUTION_RECEIPTS)
+            )
+        );
+
+    // Keyed by "collection_id:document_id"
+    static ARCHIVED_DOCUMENTS: RefCell<StableBTreeMap<String, ArchivedDocument, Memory>> =
+        RefCell::new(
+            StableBTreeMap::init(
+                memory::get(memory::ARCHIVED_DOCUMENTS)
+            )
+        );
+
+    // Keyed by "collection_id:document_id" of the collection holding the content, which
+    // Blueband never changes
+    static CONTENT_STRUCTURES: RefCell<StableBTreeMap<String, ContentStructure, Memory>> =
+        RefCell::new(
+            StableBTreeMap::init(
+                memory::get(memory::CONTENT_STRUCTURES)
+            )
+        );
+
+    // Keyed by configuration version
+    static CONFIG_HISTORY: RefCell<StableBTreeMap<u64, ConfigChange, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            memory::get(memory::CONFIG_HISTORY)
+        )
+    );
+
+    // Keyed by "canister:method"
+    static CALL_ALLOWLIST: RefCell<StableBTreeMap<String, CallAllowlistEntry, Memory>> =
+        RefCell::new(
+            StableBTreeMap::init(
+                memory::get(memory::CALL_ALLOWLIST)
+            )
+        );
+
+    // Keyed by "collection_id:proposal_id"
+    static UNSETTLED_BONDS: RefCell<StableBTreeMap<String, UnsettledBond, Memory>> =
+        RefCell::new(
+            StableBTreeMap::init(
+                memory::get(memory::UNSETTLED_BONDS)
+            )
+        );
+
+    static PENDING_CONFIG_CHANGES: RefCell<StableBTreeMap<u64, PendingConfigChange, Memory>> =
+        RefCell::new(
+            StableBTreeMap::init(
+                memory::get(memory::PENDING_CONFIG_CHANGES)
+            )
+        );
+
+    static PROPOSAL_INDEX_BACKFILL: RefCell<StableBTreeMap<(), ProposalIndexBackfill, Memory>> =
+        RefCell::new(
+            StableBTreeMap::init(
+                memory::get(memory::PROPOSAL_INDEX_BACKFILL)
+            )
+        );
+
+    static OUTCALL_MIRRORS: RefCell<StableBTreeMap<(), OutcallMirrors, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            memory::get(memory::OUTCALL_MIRRORS)
+        )
+    );
+
+    // Keyed by "collection_id:document_id"; hex SHA-256 of the content sent to Blueband
+    static DOCUMENT_CHECKSUMS: RefCell<StableBTreeMap<String, String, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            memory::get(memory::DOCUMENT_CHECKSUMS)
+        )
+    );
+
+    // Content bytes of each document as added, keyed by "collection_id:document_id"
+    static DOCUMENT_SIZES: RefCell<StableBTreeMap<String, u64, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            memory::get(memory::DOCUMENT_SIZES)
+        )
+    );
+
+    // Keyed by "collection_id:document_id"
+    static DOCUMENT_PROVENANCE: RefCell<StableBTreeMap<String, DocumentProvenance, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            memory::get(memory::DOCUMENT_PROVENANCE)
+        )
+    );
+
+    // Keyed by "collection_id:document_id" of the collection the document is linked into
+    static CROSS_POSTS: RefCell<StableBTreeMap<String, CrossPost, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            memory::get(memory::CROSS_POSTS)
+        )
+    );
+
+    // Keyed by "collection_id:proposal_id" -> executed_at; executed proposals leave
+    // PROPOSALS, and dependent proposals still need to know they ran
+    static EXECUTED_PROPOSALS: RefCell<StableBTreeMap<String, u64, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            memory::get(memory::EXECUTED_PROPOSALS)
+        )
+    );
+
+    // Keyed by "collection_id:called_at:proposal_id", called_at zero-padded so entries sort
+    // by time
+    static EXTERNAL_CALLS: RefCell<StableBTreeMap<String, ExternalCallRecord, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            memory::get(memory::EXTERNAL_CALLS)
+        )
+    );
+
+    static LOG_LEVEL: RefCell<StableBTreeMap<(), LogLevel, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            memory::get(memory::LOG_LEVEL)
+        )
+    );
+
+    // Node count set by a controller for outcall pricing
+    static SUBNET_SIZE: RefCell<StableBTreeMap<(), u32, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            memory::get(memory::SUBNET_SIZE)
+        )
+    );
+
+    // Keyed by log sequence number; the oldest entries are dropped past MAX_LOG_ENTRIES
+    static LOGS: RefCell<StableBTreeMap<u64, LogEntry, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            memory::get(memory::LOGS)
+        )
+    );
+}
+
+fn document_key(collection_id: &str, document_id: &str) -> String {
+    format!("{}:{}", collection_id, document_id)
+}
+
+type CollectionScopedMap<V> = LocalKey<RefCell<StableBTreeMap<String, V, Memory>>>;
+
+// Remove every "collection_id:..." entry from a collection-scoped map
+fn remove_collection_entries<V: Storable>(map: &'static CollectionScopedMap<V>, collection_id: &str) {
+    remove_prefixed_entries(map, &format!("{}:", collection_id));
+}
+
+fn remove_prefixed_entries<V: Storable>(map: &'static CollectionScopedMap<V>, prefix: &str) {
+    let keys: Vec<String> = map.with(|m| {
+        m.borrow()
+            .range(prefix.to_string()..)
+            .take_while(|(key, _)| key.starts_with(prefix))
+            .map(|(k, _)| k)
+            .collect()
+    });
+
+    map.with(|m| {
+        let mut m = m.borrow_mut();
+        for key in keys {
+            m.remove(&key);
+        }
+    });
+}
+
+// All "collection_id:..." entries of a collection-scoped map, keyed by the part after the prefix
+fn list_collection_entries<V: Storable>(
+    map: &'static CollectionScopedMap<V>,
+    collection_id: &str,
+) -> Vec<(String, V)> {
+    let prefix = format!("{}:", collection_id);
+    map.with(|m| {
+        m.borrow()
+            .range(prefix.clone()..)
+            .take_while(|(key, _)| key.starts_with(&prefix))
+            .map(|(key, value)| (key[prefix.len()..].to_string(), value))
+            .collect()
+    })
+}
+
+// Remove entries whose collection no longer exists, returning how many went
+fn remove_orphaned_entries<V: Storable>(map: &'static CollectionScopedMap<V>) -> u64 {
+    let keys: Vec<String> = map.with(|m| {
+        m.borrow()
+            .iter()
+            .map(|(key, _)| key)
+            .filter(|key| match key.split_once(':') {
+                Some((collection_id, _)) => {
+                    !COLLECTIONS.with(|c| c.borrow().contains_key(&collection_id.to_string()))
+                }
+                None => true,
+            })
+            .collect()
+    });
+
+    map.with(|m| {
+        let mut m = m.borrow_mut();
+        for key in &keys {
+            m.remove(key);
+        }
+    });
+    keys.len() as u64
+}
+
+// ============================
+// COLLECTION OPERATIONS
+// ============================
+
+pub fn create_collection(
+    collection_id: &CollectionId,
+    mut config: CollectionConfig,
+    creator: Principal,
+) -> ClanopediaResult<()> {
+    if COLLECTIONS.with(|c| c.borrow().contains_key(collection_id)) {
+        return Err(ClanopediaError::AlreadyExists(format!(
+            "Collection {} already exists",
+            collection_id
+        )));
+    }
+
+    // Convert string representations to Principal objects
+    let admins: Result<Vec<Principal>, _> = config
+        .admins
+        .into_iter()
+        .map(Principal::from_text)
+        .collect();
+
+    let governance_token = config
+        .governance_token
+        .map(Principal::from_text)
+        .transpose()
+        .map_err(|e| {
+            ClanopediaError::InvalidInput(format!("Invalid governance token principal: {}", e))
+        })?;
+
+    let sns_governance_canister = config
+        .sns_governance_canister
+        .map(Principal::from_text)
+        .transpose()
+        .map_err(|e| {
+            ClanopediaError::InvalidInput(format!("Invalid SNS governance canister principal: {}", e))
+        })?;
+
+    let collection = Collection {
+        id: collection_id.clone(),
+        name: config.name,
+        description: config.description,
+        admins: admins.unwrap_or_else(|_| vec![creator]), // Fall back to just the creator if any principal is invalid
+        threshold: config.threshold,
+        governance_token,
+        sns_governance_canister,
+        governance_model: config.governance_model,
+        blueband_collection_id: String::new(),
+        cycles_balance: 0,
+        proposals: HashMap::new(),
+        proposal_counter: 0,
+        created_at: time(),
+        creator,
+        updated_at: time(),
+        quorum_threshold: config.quorum_threshold,
+        is_permissionless: config.is_permissionless,
+        proposal_bond: config.proposal_bond,
+        proposer_eligibility: config.proposer_eligibility,
+        embed_concurrency: config.embed_concurrency,
+        embedding_proxy_url: config.embedding_proxy.map(|p| p.url.trim().to_string()),
+        encryption: encryption::new_collection_encryption(config.encryption),
+        pipeline: config.pipeline,
+        auto_tag: config.auto_tag,
+        summarization: config.summarization,
+        ocr: config.ocr,
+        nft_minting: config.nft_minting,
+        license: config.license,
+        forked_from: None,
+        trashed: None,
+        paused: None,
+        archived: None,
+        vote_reminder_hours: config.vote_reminder_hours,
+        timelock_hours: config.timelock_hours,
+        custom_proposals: config.custom_proposals,
+        call_policy: config.call_policy,
+        operators: None,
+        review_policy: config.review_policy,
+        slug: None,
+        content_limits: config.content_limits,
+        retry_policy: config.retry_policy,
+        spam_protection: None,
+        source_policy: None,
+    };
+
+    COLLECTIONS.with(|c| {
+        c.borrow_mut().insert(collection_id.clone(), collection);
+    });
+    append_change(collection_id, ChangeKind::CollectionCreated);
+
+    Ok(())
+}
+
+pub fn get_collection(collection_id: &CollectionId) -> ClanopediaResult<Collection> {
+    COLLECTIONS.with(|c| {
+        c.borrow().get(collection_id).ok_or_else(|| {
+            ClanopediaError::NotFound(format!("Collection {} not found", collection_id))
+        })
+    })
+}
+
+pub fn update_collection(
+    collection_id: &CollectionId,
+    collection: &Collection,
+) -> ClanopediaResult<()> {
+    let stored = get_collection(collection_id)?;
+
+    // Embedded proposals only change through the proposal operations below; the caller's
+    // copy may predate a migration round and must not bring migrated proposals back
+    let mut collection = collection.clone();
+    collection.proposals = stored.proposals;
+    COLLECTIONS.with(|c| {
+        c.borrow_mut().insert(collection_id.clone(), collection);
+    });
+    append_change(collection_id, ChangeKind::CollectionUpdated);
+
+    Ok(())
+}
+
+pub fn delete_collection(collection_id: &CollectionId) -> ClanopediaResult<()> {
+    if !COLLECTIONS.with(|c| c.borrow().contains_key(collection_id)) {
+        return Err(ClanopediaError::NotFound(format!(
+            "Collection {} not found",
+            collection_id
+        )));
+    }
+
+    // Recorded first, while the collection can still be checked for encryption
+    append_change(collection_id, ChangeKind::CollectionDeleted);
+
+    if let Some(slug) = COLLECTIONS.with(|c| c.borrow().get(collection_id).and_then(|c| c.slug)) {
+        remove_collection_slug(&slug);
+    }
+
+    // Delete all proposals for this collection
+    remove_collection_entries(&PROPOSALS, collection_id);
+    remove_collection_entries(&PROPOSAL_INDEX, collection_id);
+    remove_collection_entries(&EXECUTED_PROPOSALS, collection_id);
+    remove_collection_entries(&EXTERNAL_CALLS, collection_id);
+    remove_collection_entries(&EXECUTION_RECEIPTS, collection_id);
+
+    // Delete per-document records
+    remove_collection_entries(&DOCUMENT_OVERLAYS, collection_id);
+    remove_collection_entries(&DOCUMENT_LINKS, collection_id);
+    remove_collection_entries(&DOCUMENT_OUTLINES, collection_id);
+    remove_collection_entries(&CONTENT_STRUCTURES, collection_id);
+    remove_collection_entries(&DOCUMENT_CHECKSUMS, collection_id);
+    remove_collection_entries(&ARCHIVED_DOCUMENTS, collection_id);
+    remove_collection_entries(&DOCUMENT_SIZES, collection_id);
+    remove_collection_entries(&PINNED_DOCUMENTS, collection_id);
+    remove_collection_entries(&QUERY_ALIASES, collection_id);
+    remove_collection_entries(&DOCUMENT_PROVENANCE, collection_id);
+    remove_collection_entries(&CROSS_POSTS, collection_id);
+    remove_collection_entries(&WEEKLY_ROLLUPS, collection_id);
+    remove_collection_entries(&QUERY_LOG, collection_id);
+    remove_collection_entries(&DOCUMENT_VIEWS, collection_id);
+    remove_collection_entries(&DAILY_VIEWS, collection_id);
+    remove_collection_entries(&SITEMAP_ENTRIES, collection_id);
+    remove_collection_entries(&DOCUMENT_SLUGS, collection_id);
+    remove_collection_entries(&REACTION_COUNTS, collection_id);
+    remove_collection_entries(&REACTIONS, collection_id);
+    remove_collection_entries(&GOVERNANCE_SUBSCRIBERS, collection_id);
+    remove_collection_entries(&CHAT_BOTS, collection_id);
+    remove_collection_entries(&BOT_DELEGATIONS, collection_id);
+    remove_collection_entries(&COLLECTION_SECRETS, collection_id);
+    for shard in keyword_posting_shards() {
+        remove_collection_entries(shard, collection_id);
+    }
+    for shard in keyword_document_shards() {
+        remove_collection_entries(shard, collection_id);
+    }
+    remove_collection_entries(&TRASHED_DOCUMENTS, collection_id);
+    remove_collection_entries(&JOB_DOCUMENTS, collection_id);
+    remove_collection_entries(&JOB_OUTCOMES, collection_id);
+    remove_collection_entries(&DOCUMENT_NFTS, collection_id);
+    remove_collection_entries(&PROPOSAL_ATTACHMENTS, collection_id);
+    remove_collection_entries(&ATTACHMENT_CHUNKS, collection_id);
+
+    // Delete collection
+    COLLECTIONS.with(|c| {
+        c.borrow_mut().remove(collection_id);
+    });
+
+    Ok(())
+}
+
+pub fn list_collections() -> Vec<Collection> {
+    COLLECTIONS.with(|c| {
+        c.borrow()
+            .iter()
+            .map(|(_, collection)| collection)
+            .collect()
+    })
+}
+
+// ============================
+// PROPOSAL OPERATIONS
+// ============================
+
+pub fn update_proposal_in_storage(
+    collection_id: &CollectionId,
+    proposal: &Proposal,
+) -> ClanopediaResult<()> {
+    get_collection(collection_id)?;
+
+    // Update or remove proposal based on status
+    if proposal.status == ProposalStatus::Executed
+        || proposal.status == ProposalStatus::Rejected
+        || proposal.status == ProposalStatus::Expired
+    {
+        let previous = get_proposal(collection_id, &proposal.id).map(|p| p.status);
+        if proposal.status == ProposalStatus::Executed {
+            EXECUTED_PROPOSALS.with(|e| {
+                e.borrow_mut().insert(
+                    document_key(collection_id, &proposal.id),
+                    proposal.executed_at.unwrap_or_else(time),
+                );
+            });
+        }
+        remove_proposal(collection_id, &proposal.id);
+        remove_proposal_attachments(collection_id, &proposal.id);
+        record_proposal_change(collection_id, previous, proposal);
+    } else {
+        put_proposal(collection_id, proposal);
+    }
+    Ok(())
+}
+
+// Proposals are moving out of `Collection::proposals` into PROPOSALS, keyed
+// "collection_id:proposal_id". Until the migration timer has drained a collection's
+// embedded map, reads check PROPOSALS first and fall back to the embedded copy; writes
+// always go to PROPOSALS and drop any embedded copy.
+
+pub fn get_proposal(collection_id: &str, proposal_id: &str) -> Option<Proposal> {
+    PROPOSALS
+        .with(|p| p.borrow().get(&document_key(collection_id, proposal_id)))
+        .or_else(|| {
+            COLLECTIONS
+                .with(|c| c.borrow().get(&collection_id.to_string()))
+                .and_then(|mut c| c.proposals.remove(proposal_id))
+        })
+}
+
+pub fn list_proposals(collection_id: &str) -> HashMap<ProposalId, Proposal> {
+    let mut proposals = COLLECTIONS
+        .with(|c| c.borrow().get(&collection_id.to_string()))
+        .map(|c| c.proposals)
+        .unwrap_or_default();
+    proposals.extend(list_collection_entries(&PROPOSALS, collection_id));
+    proposals
+}
+
+pub fn is_proposal_executed(collection_id: &str, proposal_id: &str) -> bool {
+    EXECUTED_PROPOSALS.with(|e| e.borrow().contains_key(&document_key(collection_id, proposal_id)))
+}
+
+pub fn put_proposal(collection_id: &str, proposal: &Proposal) {
+    let previous = get_proposal(collection_id, &proposal.id);
+    PROPOSALS.with(|p| {
+        p.borrow_mut()
+            .insert(document_key(collection_id, &proposal.id), proposal.clone());
+    });
+    reindex_proposal(collection_id, previous.as_ref(), Some(proposal));
+    remove_embedded_proposal(collection_id, &proposal.id);
+    record_proposal_change(collection_id, previous.map(|p| p.status), proposal);
+}
+
+fn record_proposal_change(
+    collection_id: &str,
+    previous: Option<ProposalStatus>,
+    proposal: &Proposal,
+) {
+    let kind = match previous {
+        None => ChangeKind::ProposalCreated {
+            proposal_id: proposal.id.clone(),
+        },
+        Some(status) if status != proposal.status => ChangeKind::ProposalStatusChanged {
+            proposal_id: proposal.id.clone(),
+            status: proposal.status.clone(),
+        },
+        Some(_) => return,
+    };
+    append_change(collection_id, kind);
+}
+
+pub fn remove_proposal(collection_id: &str, proposal_id: &str) {
+    let previous = PROPOSALS.with(|p| {
+        p.borrow_mut()
+            .remove(&document_key(collection_id, proposal_id))
+    });
+    reindex_proposal(collection_id, previous.as_ref(), None);
+    remove_embedded_proposal(collection_id, proposal_id);
+}
+
+fn remove_embedded_proposal(collection_id: &str, proposal_id: &str) {
+    COLLECTIONS.with(|c| {
+        let mut c = c.borrow_mut();
+        let key = collection_id.to_string();
+        if let Some(mut collection) = c.get(&key) {
+            if collection.proposals.remove(proposal_id).is_some() {
+                c.insert(key, collection);
+            }
+        }
+    });
+}
+
+/// Move up to `batch` embedded proposals into PROPOSALS. A copy already in PROPOSALS is
+/// newer than the embedded one and is kept. Returns the number of proposals moved.
+pub fn migrate_proposals(batch: usize) -> usize {
+    let pending: Vec<CollectionId> = COLLECTIONS.with(|c| {
+        c.borrow()
+            .iter()
+            .filter(|(_, collection)| !collection.proposals.is_empty())
+            .map(|(id, _)| id)
+            .collect()
+    });
+
+    let mut moved = 0;
+    for collection_id in pending {
+        if moved >= batch {
+            break;
+        }
+        let Ok(mut collection) = get_collection(&collection_id) else {
+            continue;
+        };
+        let ids: Vec<ProposalId> = collection
+            .proposals
+            .keys()
+            .take(batch - moved)
+            .cloned()
+            .collect();
+        for id in ids {
+            if let Some(proposal) = collection.proposals.remove(&id) {
+                let key = document_key(&collection_id, &id);
+                let inserted = PROPOSALS.with(|p| {
+                    let mut p = p.borrow_mut();
+                    !p.contains_key(&key) && p.insert(key, proposal.clone()).is_none()
+                });
+                if inserted {
+                    reindex_proposal(&collection_id, None, Some(&proposal));
+                }
+                moved += 1;
+            }
+        }
+        COLLECTIONS.with(|c| c.borrow_mut().insert(collection_id, collection));
+    }
+    moved
+}
+
+pub fn proposal_migration_progress() -> MigrationProgress {
+    let migrated = PROPOSALS.with(|p| p.borrow().len());
+    let remaining: u64 = COLLECTIONS.with(|c| {
+        c.borrow()
+            .iter()
+            .map(|(_, collection)| collection.proposals.len() as u64)
+            .sum()
+    });
+    MigrationProgress {
+        migrated,
+        remaining,
+        complete: remaining == 0,
+    }
+}
+
+// PROPOSAL_INDEX lets `search_proposals` read the proposals of one type, creator or status
+// in creation order, or all of a collection's proposals by creation time, without loading
+// the rest. Entries are written with the proposal; proposals stored before the index existed
+// are indexed by the timer, and searches scan every proposal until that is done.
+
+fn proposal_index_prefix(collection_id: &str, dimension: &str, value: Option<&str>) -> String {
+    match value {
+        Some(value) => format!("{}:{}:{}:", collection_id, dimension, value),
+        None => format!("{}:{}:", collection_id, dimension),
+    }
+}
+
+fn proposal_index_keys(collection_id: &str, proposal: &Proposal) -> Vec<String> {
+    let creator = proposal.creator.to_text();
+    let status = format!("{:?}", proposal.status);
+    [
+        proposal_index_prefix(collection_id, "d", None),
+        proposal_index_prefix(collection_id, "t", Some(proposal.proposal_type.name())),
+        proposal_index_prefix(collection_id, "c", Some(&creator)),
+        proposal_index_prefix(collection_id, "s", Some(&status)),
+    ]
+    .into_iter()
+    .map(|prefix| format!("{}{:020}:{}", prefix, proposal.created_at, proposal.id))
+    .collect()
+}
+
+// Swap the index entries of `previous` for those of `current`
+fn reindex_proposal(collection_id: &str, previous: Option<&Proposal>, current: Option<&Proposal>) {
+    let old = previous.map_or_else(Vec::new, |p| proposal_index_keys(collection_id, p));
+    let new = current.map_or_else(Vec::new, |p| proposal_index_keys(collection_id, p));
+    PROPOSAL_INDEX.with(|index| {
+        let mut index = index.borrow_mut();
+        for key in old.iter().filter(|key| !new.contains(key)) {
+            index.remove(key);
+        }
+        for key in new {
+            index.insert(key, ());
+        }
+    });
+}
+
+/// IDs of a collection's indexed proposals created in `[after, before)`, oldest first.
+/// `dimension` is "d" for all of them, or "t", "c" or "s" with a type name, creator or
+/// status `value`.
+pub fn indexed_proposal_ids(
+    collection_id: &str,
+    dimension: &str,
+    value: Option<&str>,
+    after: Option<u64>,
+    before: Option<u64>,
+) -> Vec<ProposalId> {
+    let prefix = proposal_index_prefix(collection_id, dimension, value);
+    let start = format!("{}{:020}", prefix, after.unwrap_or(0));
+    let end = match before {
+        Some(before) => Bound::Excluded(format!("{}{:020}", prefix, before)),
+        None => Bound::Unbounded,
+    };
+    PROPOSAL_INDEX.with(|index| {
+        index
+            .borrow()
+            .range((Bound::Included(start), end))
+            .take_while(|(key, _)| key.starts_with(&prefix))
+            .filter_map(|(key, _)| {
+                // The 20-digit creation time and its separator precede the ID
+                key.get(prefix.len() + 21..).map(str::to_string)
+            })
+            .collect()
+    })
+}
+
+fn get_proposal_index_backfill() -> ProposalIndexBackfill {
+    PROPOSAL_INDEX_BACKFILL
+        .with(|b| b.borrow().get(&()))
+        .unwrap_or_default()
+}
+
+/// Whether every stored proposal is in PROPOSAL_INDEX
+pub fn proposal_index_ready() -> bool {
+    get_proposal_index_backfill().complete && proposal_migration_progress().complete
+}
+
+/// Index up to `batch` proposals stored before PROPOSAL_INDEX existed. Returns the number of
+/// proposals indexed.
+pub fn backfill_proposal_index(batch: usize) -> usize {
+    let mut state = get_proposal_index_backfill();
+    if state.complete {
+        return 0;
+    }
+    let entries = entries_after(&PROPOSALS, state.after_key.as_deref(), batch);
+    for (key, proposal) in &entries {
+        let collection_id = key.split_once(':').map_or(key.as_str(), |(id, _)| id);
+        reindex_proposal(collection_id, None, Some(proposal));
+    }
+    state.indexed += entries.len() as u64;
+    state.complete = entries.len() < batch;
+    state.after_key = entries.last().map(|(key, _)| key.clone());
+    PROPOSAL_INDEX_BACKFILL.with(|b| b.borrow_mut().insert((), state));
+    entries.len()
+}
+
+// ============================
+// DOCUMENT METADATA OVERLAYS
+// ============================
+
+pub fn get_document_overlay(
+    collection_id: &str,
+    document_id: &str,
+) -> Option<DocumentMetadataOverlay> {
+    DOCUMENT_OVERLAYS.with(|o| o.borrow().get(&document_key(collection_id, document_id)))
+}
+
+pub fn set_document_overlay(
+    collection_id: &str,
+    document_id: &str,
+    overlay: DocumentMetadataOverlay,
+) {
+    DOCUMENT_OVERLAYS.with(|o| {
+        o.borrow_mut()
+            .insert(document_key(collection_id, document_id), overlay);
+    });
+}
+
+pub fn remove_document_overlay(collection_id: &str, document_id: &str) {
+    DOCUMENT_OVERLAYS.with(|o| o.borrow_mut().remove(&document_key(collection_id, document_id)));
+}
+
+// ============================
+// DOCUMENT LINKS
+// ============================
+
+pub fn set_document_links(collection_id: &str, document_id: &str, links: DocumentLinks) {
+    DOCUMENT_LINKS.with(|l| {
+        l.borrow_mut()
+            .insert(document_key(collection_id, document_id), links);
+    });
+}
+
+pub fn list_document_links(collection_id: &str) -> Vec<(String, DocumentLinks)> {
+    list_collection_entries(&DOCUMENT_LINKS, collection_id)
+}
+
+// ============================
+// DOCUMENT OUTLINES
+// ============================
+
+pub fn get_document_outline(collection_id: &str, document_id: &str) -> Option<DocumentOutline> {
+    DOCUMENT_OUTLINES.with(|o| o.borrow().get(&document_key(collection_id, document_id)))
+}
+
+pub fn set_document_outline(collection_id: &str, document_id: &str, outline: DocumentOutline) {
+    DOCUMENT_OUTLINES.with(|o| {
+        o.borrow_mut()
+            .insert(document_key(collection_id, document_id), outline);
+    });
+}
+
+pub fn get_content_structure(collection_id: &str, document_id: &str) -> Option<ContentStructure> {
+    CONTENT_STRUCTURES.with(|c| c.borrow().get(&document_key(collection_id, document_id)))
+}
+
+pub fn set_content_structure(collection_id: &str, document_id: &str, structure: ContentStructure) {
+    CONTENT_STRUCTURES.with(|c| {
+        c.borrow_mut()
+            .insert(document_key(collection_id, document_id), structure);
+    });
+}
+
+// ============================
+// ANALYTICS ROLLUPS
+// ============================
+
+fn rollup_key(collection_id: &str, week: u64) -> String {
+    format!("{}:{:012}", collection_id, week)
+}
+
+pub fn get_weekly_rollup(collection_id: &str, week: u64) -> Option<WeeklyRollup> {
+    WEEKLY_ROLLUPS.with(|r| r.borrow().get(&rollup_key(collection_id, week)))
+}
+
+pub fn set_weekly_rollup(collection_id: &str, week: u64, rollup: WeeklyRollup) {
+    WEEKLY_ROLLUPS.with(|r| {
+        r.borrow_mut().insert(rollup_key(collection_id, week), rollup);
+    });
+}
+
+pub fn list_weekly_rollups(collection_id: &str) -> Vec<WeeklyRollup> {
+    list_collection_entries(&WEEKLY_ROLLUPS, collection_id)
+        .into_iter()
+        .map(|(_, rollup)| rollup)
+        .collect()
+}
+
+// ============================
+// DOCUMENT VIEWS
+// ============================
+
+fn daily_views_prefix(collection_id: &str, day: u64) -> String {
+    format!("{}:{:08}:", collection_id, day)
+}
+
+/// Count one view of a document on `day`
+pub fn add_view(collection_id: &str, document_id: &str, day: u64) {
+    let total_key = document_key(collection_id, document_id);
+    DOCUMENT_VIEWS.with(|v| {
+        let mut v = v.borrow_mut();
+        let total = v.get(&total_key).unwrap_or(0);
+        v.insert(total_key, total + 1);
+    });
+    let day_key = format!("{}{}", daily_views_prefix(collection_id, day), document_id);
+    DAILY_VIEWS.with(|v| {
+        let mut v = v.borrow_mut();
+        let count = v.get(&day_key).unwrap_or(0);
+        v.insert(day_key, count + 1);
+    });
+}
+
+pub fn get_total_views(collection_id: &str, document_id: &str) -> u64 {
+    DOCUMENT_VIEWS
+        .with(|v| v.borrow().get(&document_key(collection_id, document_id)))
+        .unwrap_or(0)
+}
+
+/// Views per document on days from `first_day` on
+pub fn sum_daily_views(collection_id: &str, first_day: u64) -> HashMap<DocumentId, u64> {
+    let collection_prefix = format!("{}:", collection_id);
+    let mut views: HashMap<DocumentId, u64> = HashMap::new();
+    DAILY_VIEWS.with(|v| {
+        for (key, count) in v
+            .borrow()
+            .range(daily_views_prefix(collection_id, first_day)..)
+            .take_while(|(key, _)| key.starts_with(&collection_prefix))
+        {
+            // The day is fixed-width, so the document ID follows at a known offset
+            let document_id = key[collection_prefix.len() + 9..].to_string();
+            *views.entry(document_id).or_default() += count;
+        }
+    });
+    views
+}
+
+/// Drop a collection's daily counts from before `first_kept_day`
+pub fn prune_daily_views(collection_id: &str, first_kept_day: u64) {
+    let collection_prefix = format!("{}:", collection_id);
+    let end = daily_views_prefix(collection_id, first_kept_day);
+    let keys: Vec<String> = DAILY_VIEWS.with(|v| {
+        v.borrow()
+            .range(collection_prefix.clone()..end)
+            .map(|(key, _)| key)
+            .collect()
+    });
+    DAILY_VIEWS.with(|v| {
+        let mut v = v.borrow_mut();
+        for key in keys {
+            v.remove(&key);
+        }
+    });
+}
+
+// ============================
+// SEARCH QUERY LOG
+// ============================
+
+pub fn get_query_log_entry(collection_id: &str, query: &str) -> Option<QueryLogEntry> {
+    QUERY_LOG.with(|q| q.borrow().get(&document_key(collection_id, query)))
+}
+
+pub fn set_query_log_entry(collection_id: &str, entry: QueryLogEntry) {
+    QUERY_LOG.with(|q| {
+        q.borrow_mut()
+            .insert(document_key(collection_id, &entry.query), entry);
+    });
+}
+
+pub fn list_query_log(collection_id: &str) -> Vec<QueryLogEntry> {
+    list_collection_entries(&QUERY_LOG, collection_id)
+        .into_iter()
+        .map(|(_, entry)| entry)
+        .collect()
+}
+
+// ============================
+// EXTERNAL CALL AUDIT LOG
+// ============================
+
+const MAX_EXTERNAL_CALLS_PER_COLLECTION: usize = 1_000;
+
+/// Record a call, dropping the collection's oldest records past the limit
+pub fn record_external_call(collection_id: &str, record: ExternalCallRecord) {
+    let key = format!(
+        "{}:{:020}:{}",
+        collection_id, record.called_at, record.proposal_id
+    );
+    EXTERNAL_CALLS.with(|c| c.borrow_mut().insert(key, record));
+
+    let keys: Vec<String> = list_collection_entries(&EXTERNAL_CALLS, collection_id)
+        .into_iter()
+        .map(|(key, _)| key)
+        .collect();
+    let excess = keys.len().saturating_sub(MAX_EXTERNAL_CALLS_PER_COLLECTION);
+    for key in keys.into_iter().take(excess) {
+        EXTERNAL_CALLS.with(|c| c.borrow_mut().remove(&document_key(collection_id, &key)));
+    }
+}
+
+/// The collection's external calls, oldest first
+pub fn get_execution_receipt(collection_id: &str, proposal_id: &str) -> Option<ExecutionReceipt> {
+    EXECUTION_RECEIPTS.with(|r| r.borrow().get(&document_key(collection_id, proposal_id)))
+}
+
+pub fn put_execution_receipt(collection_id: &str, proposal_id: &str, receipt: ExecutionReceipt) {
+    EXECUTION_RECEIPTS.with(|r| {
+        r.borrow_mut()
+            .insert(document_key(collection_id, proposal_id), receipt)
+    });
+}
+
+pub fn list_external_calls(collection_id: &str) -> Vec<ExternalCallRecord> {
+    list_collection_entries(&EXTERNAL_CALLS, collection_id)
+        .into_iter()
+        .map(|(_, record)| record)
+        .collect()
+}
+
+// ============================
+// EMBEDDING PROXIES
+// ============================
+
+pub fn add_proxy_allowlist_entry(url_prefix: String) {
+    PROXY_ALLOWLIST.with(|a| {
+        a.borrow_mut().insert(url_prefix, time());
+    });
+}
+
+pub fn remove_proxy_allowlist_entry(url_prefix: &str) {
+    PROXY_ALLOWLIST.with(|a| {
+        a.borrow_mut().remove(&url_prefix.to_string());
+    });
+}
+
+pub fn list_proxy_allowlist() -> Vec<String> {
+    PROXY_ALLOWLIST.with(|a| a.borrow().iter().map(|(prefix, _)| prefix).collect())
+}
+
+fn call_key(canister: &Principal, method: &str) -> String {
+    format!("{}:{}", canister, method)
+}
+
+pub fn put_call_allowlist_entry(entry: CallAllowlistEntry) {
+    CALL_ALLOWLIST.with(|a| {
+        a.borrow_mut()
+            .insert(call_key(&entry.canister, &entry.method), entry);
+    });
+}
+
+pub fn remove_call_allowlist_entry(canister: &Principal, method: &str) -> Option<CallAllowlistEntry> {
+    CALL_ALLOWLIST.with(|a| a.borrow_mut().remove(&call_key(canister, method)))
+}
+
+pub fn get_call_allowlist_entry(canister: &Principal, method: &str) -> Option<CallAllowlistEntry> {
+    CALL_ALLOWLIST.with(|a| a.borrow().get(&call_key(canister, method)))
+}
+
+pub fn list_call_allowlist() -> Vec<CallAllowlistEntry> {
+    CALL_ALLOWLIST.with(|a| a.borrow().iter().map(|(_, entry)| entry).collect())
+}
+
+// ============================
+// UNSETTLED BONDS
+// ============================
+
+pub fn put_unsettled_bond(bond: UnsettledBond) {
+    UNSETTLED_BONDS.with(|b| {
+        b.borrow_mut()
+            .insert(document_key(&bond.collection_id, &bond.proposal_id), bond);
+    });
+}
+
+pub fn remove_unsettled_bond(collection_id: &str, proposal_id: &str) -> Option<UnsettledBond> {
+    UNSETTLED_BONDS.with(|b| b.borrow_mut().remove(&document_key(collection_id, proposal_id)))
+}
+
+pub fn get_unsettled_bond(collection_id: &str, proposal_id: &str) -> Option<UnsettledBond> {
+    UNSETTLED_BONDS.with(|b| b.borrow().get(&document_key(collection_id, proposal_id)))
+}
+
+pub fn list_unsettled_bonds() -> Vec<UnsettledBond> {
+    UNSETTLED_BONDS.with(|b| b.borrow().iter().map(|(_, bond)| bond).collect())
+}
+
+// ============================
+// CANISTER SECRET
+// ============================
+
+pub fn get_canister_secret() -> Option<Vec<u8>> {
+    CANISTER_SECRET.with(|s| s.borrow().get(&()))
+}
+
+pub fn set_canister_secret(secret: Vec<u8>) {
+    CANISTER_SECRET.with(|s| {
+        s.borrow_mut().insert((), secret);
+    });
+}
+
+// ============================
+// COLLECTION SECRETS
+// ============================
+
+pub fn get_secret(collection_id: &str, name: &str) -> Option<SealedSecret> {
+    COLLECTION_SECRETS.with(|s| s.borrow().get(&document_key(collection_id, name)))
+}
+
+pub fn set_secret(collection_id: &str, name: &str, secret: SealedSecret) {
+    COLLECTION_SECRETS.with(|s| {
+        s.borrow_mut().insert(document_key(collection_id, name), secret);
+    });
+}
+
+pub fn remove_secret(collection_id: &str, name: &str) {
+    COLLECTION_SECRETS.with(|s| {
+        s.borrow_mut().remove(&document_key(collection_id, name));
+    });
+}
+
+pub fn list_secrets(collection_id: &str) -> Vec<(String, SealedSecret)> {
+    list_collection_entries(&COLLECTION_SECRETS, collection_id)
+}
+
+// ============================
+// SHARDS
+// ============================
+
+// The keyword index and the event log are split over SHARD_COUNT maps, each owning an equal
+// range of key hashes, so a collection with tens of thousands of documents does not pile
+// into one map and a common term's postings stay within an entry's bound. Shard 0 is the map
+// each lived in before sharding. The timer moves entries written before then to their shard;
+// until it is done, a key missing from its shard is looked up in shard 0.
+pub const SHARD_COUNT: usize = 4;
+
+type Shards<K, V> = [&'static LocalKey<RefCell<StableBTreeMap<K, V, Memory>>>; SHARD_COUNT];
+
+fn keyword_document_shards() -> Shards<String, IndexedDocument> {
+    [
+        &KEYWORD_DOCUMENTS,
+        &KEYWORD_DOCUMENTS_1,
+        &KEYWORD_DOCUMENTS_2,
+        &KEYWORD_DOCUMENTS_3,
+    ]
+}
+
+fn keyword_posting_shards() -> Shards<String, KeywordPostings> {
+    [
+        &KEYWORD_POSTINGS,
+        &KEYWORD_POSTINGS_1,
+        &KEYWORD_POSTINGS_2,
+        &KEYWORD_POSTINGS_3,
+    ]
+}
+
+fn event_log_shards() -> Shards<u64, EventLogEntry> {
+    [&EVENT_LOG, &EVENT_LOG_1, &EVENT_LOG_2, &EVENT_LOG_3]
+}
+
+// The shard owning a key. FNV-1a, unlike the standard library's hasher, is the same in
+// every build, so keys stay where they were written across upgrades.
+fn shard_of(key: &[u8]) -> usize {
+    let hash = key.iter().fold(0xcbf2_9ce4_8422_2325u64, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x0100_0000_01b3)
+    });
+    ((hash as u128 * SHARD_COUNT as u128) >> 64) as usize
+}
+
+fn sharded_get<V: Storable>(shards: &Shards<String, V>, key: &str) -> Option<V> {
+    let key = key.to_string();
+    let home = shard_of(key.as_bytes());
+    let get = |shard: usize| shards[shard].with(|m| m.borrow().get(&key));
+    get(home).or_else(|| (home != 0).then(|| get(0)).flatten())
+}
+
+fn sharded_insert<V: Storable>(shards: &Shards<String, V>, key: String, value: V) {
+    let home = shard_of(key.as_bytes());
+    if home != 0 {
+        shards[0].with(|m| m.borrow_mut().remove(&key));
+    }
+    shards[home].with(|m| m.borrow_mut().insert(key, value));
+}
+
+fn sharded_remove<V: Storable>(shards: &Shards<String, V>, key: &str) {
+    let key = key.to_string();
+    for shard in [0, shard_of(key.as_bytes())] {
+        shards[shard].with(|m| m.borrow_mut().remove(&key));
+    }
+}
+
+// Entries of every shard after `after_key`, in key order
+fn sharded_entries_after<V: Storable>(
+    shards: &Shards<String, V>,
+    after_key: Option<&str>,
+    limit: usize,
+) -> Vec<(String, V)> {
+    let mut entries: Vec<(String, V)> = shards
+        .iter()
+        .flat_map(|&shard| entries_after(shard, after_key, limit))
+        .collect();
+    entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+    entries.truncate(limit);
+    entries
+}
+
+fn sharded_collection_entries<V: Storable>(
+    shards: &Shards<String, V>,
+    collection_id: &str,
+) -> Vec<(String, V)> {
+    let mut entries: Vec<(String, V)> = shards
+        .iter()
+        .flat_map(|&shard| list_collection_entries(shard, collection_id))
+        .collect();
+    entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+    entries
+}
+
+fn shard_lens<K: Storable + Ord + Clone, V: Storable>(shards: &Shards<K, V>) -> [u64; SHARD_COUNT] {
+    shards.map(|shard| shard.with(|m| m.borrow().len()))
+}
+
+fn shard_memory_ids(map: StorageMap) -> Option<[u8; SHARD_COUNT]> {
+    match map {
+        StorageMap::KeywordDocuments => Some([
+            memory::KEYWORD_DOCUMENTS,
+            memory::KEYWORD_DOCUMENTS_1,
+            memory::KEYWORD_DOCUMENTS_2,
+            memory::KEYWORD_DOCUMENTS_3,
+        ]),
+        StorageMap::KeywordPostings => Some([
+            memory::KEYWORD_POSTINGS,
+            memory::KEYWORD_POSTINGS_1,
+            memory::KEYWORD_POSTINGS_2,
+            memory::KEYWORD_POSTINGS_3,
+        ]),
+        StorageMap::EventLog => Some([
+            memory::EVENT_LOG,
+            memory::EVENT_LOG_1,
+            memory::EVENT_LOG_2,
+            memory::EVENT_LOG_3,
+        ]),
+        _ => None,
+    }
+}
+
+// Entries in each shard of a sharded map
+fn shard_entry_counts(map: StorageMap) -> Option<[u64; SHARD_COUNT]> {
+    match map {
+        StorageMap::KeywordDocuments => Some(shard_lens(&keyword_document_shards())),
+        StorageMap::KeywordPostings => Some(shard_lens(&keyword_posting_shards())),
+        StorageMap::EventLog => Some(shard_lens(&event_log_shards())),
+        _ => None,
+    }
+}
+
+fn get_shard_rebalance() -> ShardRebalance {
+    SHARD_REBALANCE
+        .with(|r| r.borrow().get(&()))
+        .unwrap_or_default()
+}
+
+pub fn shards_rebalanced() -> bool {
+    get_shard_rebalance().map.is_none()
+}
+
+/// Move up to `batch` entries written before sharding from shard 0 to their own shard.
+/// Returns the number of entries moved.
+pub fn rebalance_shards(batch: usize) -> usize {
+    let mut state = get_shard_rebalance();
+    let Some(map) = state.map else {
+        return 0;
+    };
+    let after_key = state.after_key.as_deref();
+    let (scanned, last_key, moved) = match map {
+        StorageMap::KeywordDocuments => rebalance_documents(after_key, batch),
+        StorageMap::KeywordPostings => rebalance_postings(after_key, batch),
+        _ => rebalance_events(after_key, batch),
+    };
+
+    state.moved += moved as u64;
+    if scanned < batch {
+        state.map = match map {
+            StorageMap::KeywordDocuments => Some(StorageMap::KeywordPostings),
+            StorageMap::KeywordPostings => Some(StorageMap::EventLog),
+            _ => None,
+        };
+        state.after_key = None;
+    } else {
+        state.after_key = last_key;
+    }
+    SHARD_REBALANCE.with(|r| r.borrow_mut().insert((), state));
+    moved
+}
+
+// Each returns how many entries of shard 0 it scanned, the last key scanned, and how many
+// entries it moved
+fn rebalance_documents(after_key: Option<&str>, batch: usize) -> (usize, Option<String>, usize) {
+    let shards = keyword_document_shards();
+    let entries = entries_after(shards[0], after_key, batch);
+    let scanned = entries.len();
+    let last_key = entries.last().map(|(key, _)| key.clone());
+    let mut moved = 0;
+    for (key, document) in entries {
+        if shard_of(key.as_bytes()) != 0 {
+            sharded_insert(&shards, key, document);
+            moved += 1;
+        }
+    }
+    (scanned, last_key, moved)
+}
+
+fn rebalance_postings(after_key: Option<&str>, batch: usize) -> (usize, Option<String>, usize) {
+    let entries = entries_after(keyword_posting_shards()[0], after_key, batch);
+    let scanned = entries.len();
+    let last_key = entries.last().map(|(key, _)| key.clone());
+    let mut moved = 0;
+    for (key, postings) in entries {
+        let collection_id = key.split_once(':').map_or(key.as_str(), |(id, _)| id);
+        if postings
+            .document_ids
+            .iter()
+            .any(|id| shard_of(document_key(collection_id, id).as_bytes()) != 0)
+        {
+            let mut document_ids = merged_postings(&key)
+                .map(|p| p.document_ids)
+                .unwrap_or_default();
+            document_ids.extend(postings.document_ids);
+            set_term_postings(&key, document_ids);
+            moved += 1;
+        }
+    }
+    (scanned, last_key, moved)
+}
+
+fn rebalance_events(after_key: Option<&str>, batch: usize) -> (usize, Option<String>, usize) {
+    let shards = event_log_shards();
+    let start = after_key
+        .and_then(|key| key.parse::<u64>().ok())
+        .map_or(Bound::Unbounded, Bound::Excluded);
+    let events: Vec<EventLogEntry> = shards[0].with(|log| {
+        log.borrow()
+            .range((start, Bound::Unbounded))
+            .take(batch)
+            .map(|(_, event)| event)
+            .collect()
+    });
+    let scanned = events.len();
+    let last_key = events.last().map(|event| event.sequence.to_string());
+    let mut moved = 0;
+    for event in events {
+        let home = shard_of(&event.sequence.to_be_bytes());
+        if home != 0 {
+            shards[0].with(|log| log.borrow_mut().remove(&event.sequence));
+            shards[home].with(|log| log.borrow_mut().insert(event.sequence, event));
+            moved += 1;
+        }
+    }
+    (scanned, last_key, moved)
+}
+
+// ============================
+// KEYWORD INDEX
+// ============================
+
+// A term's postings are split by document: each shard lists the documents it owns under
+// "collection_id:term"
+
+// Every shard's postings under `key`, once each
+fn merged_postings(key: &String) -> Option<KeywordPostings> {
+    let mut seen = HashSet::new();
+    let mut document_ids = Vec::new();
+    for shard in keyword_posting_shards() {
+        if let Some(postings) = shard.with(|m| m.borrow().get(key)) {
+            // Shard 0 may still list documents that have since moved to their shard
+            document_ids.extend(
+                postings
+                    .document_ids
+                    .into_iter()
+                    .filter(|id| seen.insert(id.clone())),
+            );
+        }
+    }
+    (!document_ids.is_empty()).then_some(KeywordPostings { document_ids })
+}
+
+// Replace the postings under `key` in every shard, each document going to its own
+fn set_term_postings(key: &str, document_ids: Vec<DocumentId>) {
+    let collection_id = key.split_once(':').map_or(key, |(id, _)| id);
+    let mut split: [Vec<DocumentId>; SHARD_COUNT] = Default::default();
+    let mut seen = HashSet::new();
+    for id in document_ids {
+        if seen.insert(id.clone()) {
+            split[shard_of(document_key(collection_id, &id).as_bytes())].push(id);
+        }
+    }
+    for (shard, document_ids) in keyword_posting_shards().into_iter().zip(split) {
+        shard.with(|m| {
+            let mut m = m.borrow_mut();
+            if document_ids.is_empty() {
+                m.remove(&key.to_string());
+            } else {
+                m.insert(key.to_string(), KeywordPostings { document_ids });
+            }
+        });
+    }
+}
+
+fn remove_posting(
+    shard: &'static CollectionScopedMap<KeywordPostings>,
+    key: &String,
+    document_id: &str,
+) {
+    shard.with(|m| {
+        let mut m = m.borrow_mut();
+        let Some(mut postings) = m.get(key) else {
+            return;
+        };
+        let before = postings.document_ids.len();
+        postings.document_ids.retain(|id| id != document_id);
+        if postings.document_ids.is_empty() {
+            m.remove(key);
+        } else if postings.document_ids.len() < before {
+            m.insert(key.clone(), postings);
+        }
+    });
+}
+
+pub fn get_keyword_postings(collection_id: &str, term: &str) -> Option<KeywordPostings> {
+    merged_postings(&document_key(collection_id, term))
+}
+
+/// List a document under a term, unless its shard already lists `max_per_shard` documents
+/// there
+pub fn add_keyword_posting(
+    collection_id: &str,
+    term: &str,
+    document_id: &str,
+    max_per_shard: usize,
+) {
+    let shards = keyword_posting_shards();
+    let key = document_key(collection_id, term);
+    let home = shard_of(document_key(collection_id, document_id).as_bytes());
+    let listed = shards[home].with(|m| {
+        let mut m = m.borrow_mut();
+        let mut postings = m.get(&key).unwrap_or_default();
+        if postings.document_ids.iter().any(|id| id == document_id) {
+            return true;
+        }
+        if postings.document_ids.len() >= max_per_shard {
+            return false;
+        }
+        postings.document_ids.push(document_id.to_string());
+        m.insert(key.clone(), postings);
+        true
+    });
+    if listed && home != 0 {
+        remove_posting(shards[0], &key, document_id);
+    }
+}
+
+pub fn remove_keyword_posting(collection_id: &str, term: &str, document_id: &str) {
+    let shards = keyword_posting_shards();
+    let key = document_key(collection_id, term);
+    for shard in [0, shard_of(document_key(collection_id, document_id).as_bytes())] {
+        remove_posting(shards[shard], &key, document_id);
+    }
+}
+
+pub fn get_indexed_document(collection_id: &str, document_id: &str) -> Option<IndexedDocument> {
+    sharded_get(
+        &keyword_document_shards(),
+        &document_key(collection_id, document_id),
+    )
+}
+
+pub fn remove_indexed_document(collection_id: &str, document_id: &str) {
+    sharded_remove(
+        &keyword_document_shards(),
+        &document_key(collection_id, document_id),
+    );
+}
+
+pub fn set_indexed_document(collection_id: &str, document_id: &str, document: IndexedDocument) {
+    sharded_insert(
+        &keyword_document_shards(),
+        document_key(collection_id, document_id),
+        document,
+    );
+}
+
+// ============================
+// DOCUMENT CHECKSUMS
+// ============================
+
+pub fn set_document_checksum(collection_id: &str, document_id: &str, checksum: String) {
+    DOCUMENT_CHECKSUMS.with(|c| {
+        c.borrow_mut()
+            .insert(document_key(collection_id, document_id), checksum);
+    });
+}
+
+pub fn get_document_checksum(collection_id: &str, document_id: &str) -> Option<String> {
+    DOCUMENT_CHECKSUMS.with(|c| c.borrow().get(&document_key(collection_id, document_id)))
+}
+
+pub fn list_document_checksums(collection_id: &str) -> Vec<(DocumentId, String)> {
+    list_collection_entries(&DOCUMENT_CHECKSUMS, collection_id)
+}
+
+pub fn remove_document_checksum(collection_id: &str, document_id: &str) {
+    DOCUMENT_CHECKSUMS.with(|c| c.borrow_mut().remove(&document_key(collection_id, document_id)));
+}
+
+// ============================
+// DOCUMENT SIZES
+// ============================
+
+pub fn set_document_size(collection_id: &str, document_id: &str, bytes: u64) {
+    DOCUMENT_SIZES.with(|s| {
+        s.borrow_mut()
+            .insert(document_key(collection_id, document_id), bytes);
+    });
+}
+
+pub fn remove_document_size(collection_id: &str, document_id: &str) {
+    DOCUMENT_SIZES.with(|s| s.borrow_mut().remove(&document_key(collection_id, document_id)));
+}
+
+/// Content bytes of every document of the collection with a recorded size
+pub fn total_document_bytes(collection_id: &str) -> u64 {
+    list_collection_entries(&DOCUMENT_SIZES, collection_id)
+        .into_iter()
+        .map(|(_, bytes)| bytes)
+        .sum()
+}
+
+// ============================
+// ARCHIVED DOCUMENTS
+// ============================
+
+pub fn get_archived_document(collection_id: &str, document_id: &str) -> Option<ArchivedDocument> {
+    ARCHIVED_DOCUMENTS.with(|a| a.borrow().get(&document_key(collection_id, document_id)))
+}
+
+pub fn set_archived_document(collection_id: &str, archived: ArchivedDocument) {
+    ARCHIVED_DOCUMENTS.with(|a| {
+        a.borrow_mut()
+            .insert(document_key(collection_id, &archived.document_id), archived)
+    });
+}
+
+pub fn remove_archived_document(collection_id: &str, document_id: &str) -> Option<ArchivedDocument> {
+    ARCHIVED_DOCUMENTS.with(|a| a.borrow_mut().remove(&document_key(collection_id, document_id)))
+}
+
+pub fn list_archived_documents(collection_id: &str) -> Vec<ArchivedDocument> {
+    list_collection_entries(&ARCHIVED_DOCUMENTS, collection_id)
+        .into_iter()
+        .map(|(_, archived)| archived)
+        .collect()
+}
+
+// ============================
+// PINNED DOCUMENTS
+// ============================
+
+pub fn list_pinned_documents(collection_id: &str) -> Vec<PinnedDocument> {
+    list_collection_entries(&PINNED_DOCUMENTS, collection_id)
+        .into_iter()
+        .map(|(_, pinned)| pinned)
+        .collect()
+}
+
+/// Replace the collection's pinned set, keeping the given order
+pub fn set_pinned_documents(collection_id: &str, pinned: &[PinnedDocument]) {
+    remove_collection_entries(&PINNED_DOCUMENTS, collection_id);
+    PINNED_DOCUMENTS.with(|p| {
+        let mut p = p.borrow_mut();
+        for (position, document) in pinned.iter().enumerate() {
+            p.insert(format!("{}:{:04}", collection_id, position), document.clone());
+        }
+    });
+}
+
+// ============================
+// QUERY ALIASES
+// ============================
+
+/// The collection's aliases under their keys, in key order
+pub fn list_query_aliases(collection_id: &str) -> Vec<(String, QueryAlias)> {
+    list_collection_entries(&QUERY_ALIASES, collection_id)
+}
+
+pub fn put_query_alias(collection_id: &str, key: &str, alias: QueryAlias) {
+    QUERY_ALIASES.with(|a| {
+        a.borrow_mut().insert(format!("{}:{}", collection_id, key), alias);
+    });
+}
+
+pub fn remove_query_alias(collection_id: &str, key: &str) {
+    QUERY_ALIASES.with(|a| a.borrow_mut().remove(&format!("{}:{}", collection_id, key)));
+}
+
+// ============================
+// DOCUMENT PROVENANCE
+// ============================
+
+pub fn get_document_provenance(
+    collection_id: &str,
+    document_id: &str,
+) -> Option<DocumentProvenance> {
+    DOCUMENT_PROVENANCE.with(|p| p.borrow().get(&document_key(collection_id, document_id)))
+}
+
+pub fn set_document_provenance(
+    collection_id: &str,
+    document_id: &str,
+    provenance: DocumentProvenance,
+) {
+    DOCUMENT_PROVENANCE.with(|p| {
+        p.borrow_mut()
+            .insert(document_key(collection_id, document_id), provenance);
+    });
+}
+
+// ============================
+// CROSS-POSTS
+// ============================
+
+pub fn get_cross_post(collection_id: &str, document_id: &str) -> Option<CrossPost> {
+    CROSS_POSTS.with(|c| c.borrow().get(&document_key(collection_id, document_id)))
+}
+
+pub fn set_cross_post(collection_id: &str, document_id: &str, cross_post: CrossPost) {
+    CROSS_POSTS.with(|c| {
+        c.borrow_mut()
+            .insert(document_key(collection_id, document_id), cross_post);
+    });
+}
+
+pub fn list_cross_posts(collection_id: &str) -> Vec<(DocumentId, CrossPost)> {
+    list_collection_entries(&CROSS_POSTS, collection_id)
+}
+
+// Every link whose content lives in `source_collection`, as (collection_id, document_id)
+pub fn list_cross_posts_from(source_collection: &str) -> Vec<(CollectionId, DocumentId)> {
+    CROSS_POSTS.with(|c| {
+        c.borrow()
+            .iter()
+            .filter(|(_, link)| link.source_collection == source_collection)
+            .filter_map(|(key, _)| {
+                let (collection_id, document_id) = key.split_once(':')?;
+                Some((collection_id.to_string(), document_id.to_string()))
+            })
+            .collect()
+    })
+}
+
+// ============================
+// DOCUMENT LISTING
+// ============================
+
+/// Documents Clanopedia holds records for. Blueband has no listing call, so this is the
+/// union of the per-document maps; documents added before those maps existed are missing.
+pub fn list_known_document_ids(collection_id: &str) -> Vec<DocumentId> {
+    let mut ids: Vec<DocumentId> =
+        sharded_collection_entries(&keyword_document_shards(), collection_id)
+            .into_iter()
+            .map(|(id, _)| id)
+            .collect();
+    ids.extend(list_collection_keys(&DOCUMENT_OVERLAYS, collection_id));
+    ids.extend(list_collection_keys(&DOCUMENT_OUTLINES, collection_id));
+    ids.extend(list_collection_keys(&DOCUMENT_LINKS, collection_id));
+    ids.extend(list_collection_keys(&DOCUMENT_CHECKSUMS, collection_id));
+    ids.extend(list_collection_keys(&DOCUMENT_PROVENANCE, collection_id));
+    ids.extend(list_collection_keys(&CROSS_POSTS, collection_id));
+    ids.sort();
+    ids.dedup();
+    ids
+}
+
+fn list_collection_keys<V: Storable>(
+    map: &'static CollectionScopedMap<V>,
+    collection_id: &str,
+) -> Vec<String> {
+    list_collection_entries(map, collection_id)
+        .into_iter()
+        .map(|(key, _)| key)
+        .collect()
+}
+
+// ============================
+// TRASH
+// ============================
+
+pub fn get_trashed_document(collection_id: &str, document_id: &str) -> Option<TrashInfo> {
+    TRASHED_DOCUMENTS.with(|t| t.borrow().get(&document_key(collection_id, document_id)))
+}
+
+pub fn set_trashed_document(collection_id: &str, document_id: &str, trash: TrashInfo) {
+    TRASHED_DOCUMENTS.with(|t| {
+        t.borrow_mut()
+            .insert(document_key(collection_id, document_id), trash);
+    });
+}
+
+pub fn remove_trashed_document(collection_id: &str, document_id: &str) -> Option<TrashInfo> {
+    TRASHED_DOCUMENTS.with(|t| t.borrow_mut().remove(&document_key(collection_id, document_id)))
+}
+
+pub fn list_trashed_documents(collection_id: &str) -> Vec<(DocumentId, TrashInfo)> {
+    list_collection_entries(&TRASHED_DOCUMENTS, collection_id)
+}
+
+// Every trashed document as (collection_id, document_id, trash)
+pub fn list_all_trashed_documents() -> Vec<(CollectionId, DocumentId, TrashInfo)> {
+    TRASHED_DOCUMENTS.with(|t| {
+        t.borrow()
+            .iter()
+            .filter_map(|(key, trash)| {
+                let (collection_id, document_id) = key.split_once(':')?;
+                Some((collection_id.to_string(), document_id.to_string(), trash))
+            })
+            .collect()
+    })
+}
+
+/// Drop every Clanopedia-side record of a document
+pub fn remove_document_records(collection_id: &str, document_id: &str) {
+    let key = document_key(collection_id, document_id);
+    if let Some(slug) = get_document_overlay(collection_id, document_id).and_then(|o| o.slug) {
+        remove_document_slug(collection_id, &slug);
+    }
+    DOCUMENT_OVERLAYS.with(|o| o.borrow_mut().remove(&key));
+    DOCUMENT_LINKS.with(|l| l.borrow_mut().remove(&key));
+    DOCUMENT_OUTLINES.with(|o| o.borrow_mut().remove(&key));
+    CONTENT_STRUCTURES.with(|c| c.borrow_mut().remove(&key));
+    DOCUMENT_CHECKSUMS.with(|c| c.borrow_mut().remove(&key));
+    DOCUMENT_SIZES.with(|s| s.borrow_mut().remove(&key));
+    DOCUMENT_PROVENANCE.with(|p| p.borrow_mut().remove(&key));
+    CROSS_POSTS.with(|c| c.borrow_mut().remove(&key));
+    TRASHED_DOCUMENTS.with(|t| t.borrow_mut().remove(&key));
+    remove_target_reactions(collection_id, &format!("d:{}", document_id));
+    let pinned = list_pinned_documents(collection_id);
+    if pinned.iter().any(|p| p.document_id == document_id) {
+        let kept: Vec<PinnedDocument> =
+            pinned.into_iter().filter(|p| p.document_id != document_id).collect();
+        set_pinned_documents(collection_id, &kept);
+    }
+}
+
+/// Clear records left behind in collection-scoped maps by collections that are gone, e.g.
+/// after a delete that trapped halfway
+pub fn purge_orphaned_entries() -> Vec<MapPurge> {
+    let purge = |map, removed| MapPurge { map, removed };
+    vec![
+        purge(StorageMap::Proposals, remove_orphaned_entries(&PROPOSALS)),
+        purge(StorageMap::ExecutedProposals, remove_orphaned_entries(&EXECUTED_PROPOSALS)),
+        purge(StorageMap::ExternalCalls, remove_orphaned_entries(&EXTERNAL_CALLS)),
+        purge(StorageMap::DocumentOverlays, remove_orphaned_entries(&DOCUMENT_OVERLAYS)),
+        purge(StorageMap::DocumentLinks, remove_orphaned_entries(&DOCUMENT_LINKS)),
+        purge(StorageMap::DocumentOutlines, remove_orphaned_entries(&DOCUMENT_OUTLINES)),
+        purge(StorageMap::ContentStructures, remove_orphaned_entries(&CONTENT_STRUCTURES)),
+        purge(StorageMap::DocumentChecksums, remove_orphaned_entries(&DOCUMENT_CHECKSUMS)),
+        purge(StorageMap::DocumentSizes, remove_orphaned_entries(&DOCUMENT_SIZES)),
+        purge(StorageMap::PinnedDocuments, remove_orphaned_entries(&PINNED_DOCUMENTS)),
+        purge(StorageMap::QueryAliases, remove_orphaned_entries(&QUERY_ALIASES)),
+        purge(StorageMap::ProposalIndex, remove_orphaned_entries(&PROPOSAL_INDEX)),
+        purge(StorageMap::ExecutionReceipts, remove_orphaned_entries(&EXECUTION_RECEIPTS)),
+        purge(StorageMap::ArchivedDocuments, remove_orphaned_entries(&ARCHIVED_DOCUMENTS)),
+        purge(StorageMap::DocumentProvenance, remove_orphaned_entries(&DOCUMENT_PROVENANCE)),
+        purge(StorageMap::CrossPosts, remove_orphaned_entries(&CROSS_POSTS)),
+        purge(StorageMap::WeeklyRollups, remove_orphaned_entries(&WEEKLY_ROLLUPS)),
+        purge(StorageMap::QueryLog, remove_orphaned_entries(&QUERY_LOG)),
+        purge(StorageMap::DocumentViews, remove_orphaned_entries(&DOCUMENT_VIEWS)),
+        purge(StorageMap::DailyViews, remove_orphaned_entries(&DAILY_VIEWS)),
+        purge(StorageMap::SitemapEntries, remove_orphaned_entries(&SITEMAP_ENTRIES)),
+        purge(StorageMap::CollectionSlugs, remove_orphaned_collection_slugs()),
+        purge(StorageMap::DocumentSlugs, remove_orphaned_entries(&DOCUMENT_SLUGS)),
+        purge(StorageMap::ReactionCounts, remove_orphaned_entries(&REACTION_COUNTS)),
+        purge(StorageMap::Reactions, remove_orphaned_entries(&REACTIONS)),
+        purge(
+            StorageMap::GovernanceSubscribers,
+            remove_orphaned_entries(&GOVERNANCE_SUBSCRIBERS),
+        ),
+        purge(StorageMap::ChatBots, remove_orphaned_entries(&CHAT_BOTS)),
+        purge(StorageMap::BotDelegations, remove_orphaned_entries(&BOT_DELEGATIONS)),
+        purge(StorageMap::CollectionSecrets, remove_orphaned_entries(&COLLECTION_SECRETS)),
+        purge(
+            StorageMap::KeywordPostings,
+            keyword_posting_shards()
+                .into_iter()
+                .map(remove_orphaned_entries)
+                .sum(),
+        ),
+        purge(
+            StorageMap::KeywordDocuments,
+            keyword_document_shards()
+                .into_iter()
+                .map(remove_orphaned_entries)
+                .sum(),
+        ),
+        purge(StorageMap::TrashedDocuments, remove_orphaned_entries(&TRASHED_DOCUMENTS)),
+        purge(StorageMap::JobDocuments, remove_orphaned_entries(&JOB_DOCUMENTS)),
+        purge(StorageMap::JobOutcomes, remove_orphaned_entries(&JOB_OUTCOMES)),
+        purge(StorageMap::DocumentNfts, remove_orphaned_entries(&DOCUMENT_NFTS)),
+        purge(
+            StorageMap::ProposalAttachments,
+            remove_orphaned_entries(&PROPOSAL_ATTACHMENTS),
+        ),
+        purge(
+            StorageMap::AttachmentChunks,
+            remove_orphaned_entries(&ATTACHMENT_CHUNKS),
+        ),
+        purge(StorageMap::Bookmarks, remove_orphaned_bookmarks()),
+    ]
+}
+
+pub fn list_indexed_documents(collection_id: &str) -> Vec<(DocumentId, IndexedDocument)> {
+    sharded_collection_entries(&keyword_document_shards(), collection_id)
+}
+
+pub fn count_indexed_documents(collection_id: &str) -> u64 {
+    keyword_document_shards()
+        .iter()
+        .map(|&shard| list_collection_keys(shard, collection_id).len() as u64)
+        .sum()
+}
+
+// ============================
+// VOTE RECEIPTS
+// ============================
+
+pub fn append_vote_receipt(receipt: &VoteReceipt) {
+    VOTE_RECEIPTS.with(|r| {
+        r.borrow_mut().insert(receipt.sequence, receipt.clone());
+    });
+}
+
+pub fn get_vote_receipt(sequence: u64) -> Option<VoteReceipt> {
+    VOTE_RECEIPTS.with(|r| r.borrow().get(&sequence))
+}
+
+pub fn last_vote_receipt() -> Option<VoteReceipt> {
+    VOTE_RECEIPTS.with(|r| r.borrow().last_key_value().map(|(_, receipt)| receipt))
+}
+
+// Receipt hashes issued after `sequence`, oldest first
+pub fn list_receipt_hashes_after(sequence: u64, limit: usize) -> Vec<String> {
+    VOTE_RECEIPTS.with(|r| {
+        r.borrow()
+            .range(sequence + 1..)
+            .take(limit)
+            .map(|(_, receipt)| receipt.receipt_hash)
+            .collect()
+    })
+}
+
+// ============================
+// COLLECTION CREATION
+// ============================
+
+pub fn get_creation_policy() -> CreationPolicy {
+    CREATION_POLICY
+        .with(|p| p.borrow().get(&()))
+        .unwrap_or_default()
+}
+
+pub fn set_creation_policy(policy: CreationPolicy) {
+    CREATION_POLICY.with(|p| {
+        p.borrow_mut().insert((), policy);
+    });
+}
+
+pub fn next_creation_request_id() -> u64 {
+    CREATION_REQUESTS.with(|r| r.borrow().last_key_value().map_or(0, |(id, _)| id + 1))
+}
+
+pub fn put_creation_request(request: &CreationRequest) {
+    CREATION_REQUESTS.with(|r| {
+        r.borrow_mut().insert(request.id, request.clone());
+    });
+}
+
+pub fn get_creation_request(id: u64) -> Option<CreationRequest> {
+    CREATION_REQUESTS.with(|r| r.borrow().get(&id))
+}
+
+pub fn list_creation_requests() -> Vec<CreationRequest> {
+    CREATION_REQUESTS.with(|r| r.borrow().iter().map(|(_, request)| request).collect())
+}
+
+pub fn put_pending_creation(pending: &PendingCreation) {
+    PENDING_CREATIONS.with(|p| {
+        p.borrow_mut()
+            .insert(pending.collection_id.clone(), pending.clone());
+    });
+}
+
+pub fn remove_pending_creation(collection_id: &str) {
+    PENDING_CREATIONS.with(|p| {
+        p.borrow_mut().remove(&collection_id.to_string());
+    });
+}
+
+pub fn list_pending_creations() -> Vec<PendingCreation> {
+    PENDING_CREATIONS.with(|p| p.borrow().iter().map(|(_, pending)| pending).collect())
+}
+
+pub fn get_blueband_reconcile_state() -> BluebandReconcileState {
+    BLUEBAND_RECONCILIATION.with(|r| r.borrow().get(&()).unwrap_or_default())
+}
+
+pub fn set_blueband_reconcile_state(state: BluebandReconcileState) {
+    BLUEBAND_RECONCILIATION.with(|r| {
+        r.borrow_mut().insert((), state);
+    });
+}
+
+// ============================
+// BACKUPS
+// ============================
+
+pub fn get_backup_state() -> BackupState {
+    BACKUP_STATE.with(|b| b.borrow().get(&()).unwrap_or_default())
+}
+
+pub fn set_backup_state(state: BackupState) {
+    BACKUP_STATE.with(|b| {
+        b.borrow_mut().insert((), state);
+    });
+}
+
+pub fn get_backup_summary(id: u64) -> Option<BackupSummary> {
+    BACKUP_HISTORY.with(|h| h.borrow().get(&id))
+}
+
+/// Record a backup, dropping the oldest past `max_entries`
+pub fn put_backup_summary(summary: BackupSummary, max_entries: u64) {
+    BACKUP_HISTORY.with(|h| {
+        let mut h = h.borrow_mut();
+        h.insert(summary.id, summary);
+        while h.len() > max_entries {
+            let Some((oldest, _)) = h.first_key_value() else {
+                break;
+            };
+            h.remove(&oldest);
+        }
+    });
+}
+
+/// Backups, newest first
+pub fn list_backup_summaries() -> Vec<BackupSummary> {
+    BACKUP_HISTORY.with(|h| h.borrow().iter().rev().map(|(_, s)| s).collect())
+}
+
+// Entries after `after_key`, in key order
+fn entries_after<V: Storable>(
+    map: &'static CollectionScopedMap<V>,
+    after_key: Option<&str>,
+    limit: usize,
+) -> Vec<(String, V)> {
+    let start = after_key.map_or(Bound::Unbounded, |key| Bound::Excluded(key.to_string()));
+    map.with(|m| {
+        m.borrow()
+            .range((start, Bound::Unbounded))
+            .take(limit)
+            .collect()
+    })
+}
+
+// Entries after `after_key`, with values as kept in stable memory
+fn raw_entries<V: Storable>(
+    map: &'static CollectionScopedMap<V>,
+    after_key: Option<&str>,
+    limit: usize,
+) -> Vec<(String, Vec<u8>)> {
+    entries_after(map, after_key, limit)
+        .into_iter()
+        .map(|(key, value)| (key, value.to_bytes().into_owned()))
+        .collect()
+}
+
+fn restore_raw<V: Storable>(
+    map: &'static CollectionScopedMap<V>,
+    key: String,
+    value: Option<Vec<u8>>,
+) {
+    map.with(|m| {
+        let mut m = m.borrow_mut();
+        match value {
+            Some(bytes) => {
+                m.insert(key, V::from_bytes(Cow::Owned(bytes)));
+            }
+            None => {
+                m.remove(&key);
+            }
+        }
+    });
+}
+
+fn has_raw<V: Storable>(map: &'static CollectionScopedMap<V>, key: &str) -> bool {
+    map.with(|m| m.borrow().contains_key(&key.to_string()))
+}
+
+// Terms after `after_key` with their postings merged over the shards. Every shard holding
+// one of the first `limit` terms lists it within its own first `limit` keys.
+fn merged_postings_after(after_key: Option<&str>, limit: usize) -> Vec<(String, KeywordPostings)> {
+    let mut keys: Vec<String> = keyword_posting_shards()
+        .into_iter()
+        .flat_map(|shard| entries_after(shard, after_key, limit))
+        .map(|(key, _)| key)
+        .collect();
+    keys.sort();
+    keys.dedup();
+    keys.truncate(limit);
+    keys.into_iter()
+        .filter_map(|key| merged_postings(&key).map(|postings| (key, postings)))
+        .collect()
+}
+
+/// A section's entries after `after_key`, serialized as in stable memory
+pub fn backup_entries(
+    section: BackupSection,
+    after_key: Option<&str>,
+    limit: usize,
+) -> Vec<(String, Vec<u8>)> {
+    match section {
+        BackupSection::Collections => raw_entries(&COLLECTIONS, after_key, limit),
+        BackupSection::Proposals => raw_entries(&PROPOSALS, after_key, limit),
+        BackupSection::DocumentOverlays => raw_entries(&DOCUMENT_OVERLAYS, after_key, limit),
+        BackupSection::CollectionSlugs => raw_entries(&COLLECTION_SLUGS, after_key, limit),
+        BackupSection::DocumentSlugs => raw_entries(&DOCUMENT_SLUGS, after_key, limit),
+        BackupSection::KeywordDocuments => {
+            sharded_entries_after(&keyword_document_shards(), after_key, limit)
+                .into_iter()
+                .map(|(key, document)| (key, document.to_bytes().into_owned()))
+                .collect()
+        }
+        BackupSection::KeywordPostings => merged_postings_after(after_key, limit)
+            .into_iter()
+            .map(|(key, postings)| (key, postings.to_bytes().into_owned()))
+            .collect(),
+    }
+}
+
+pub fn has_backup_entry(section: BackupSection, key: &str) -> bool {
+    match section {
+        BackupSection::Collections => has_raw(&COLLECTIONS, key),
+        BackupSection::Proposals => has_raw(&PROPOSALS, key),
+        BackupSection::DocumentOverlays => has_raw(&DOCUMENT_OVERLAYS, key),
+        BackupSection::CollectionSlugs => has_raw(&COLLECTION_SLUGS, key),
+        BackupSection::DocumentSlugs => has_raw(&DOCUMENT_SLUGS, key),
+        BackupSection::KeywordDocuments => sharded_get(&keyword_document_shards(), key).is_some(),
+        BackupSection::KeywordPostings => merged_postings(&key.to_string()).is_some(),
+    }
+}
+
+/// Write back a backed-up entry, or delete it if `value` is unset
+pub fn restore_backup_entry(section: BackupSection, key: String, value: Option<Vec<u8>>) {
+    match section {
+        BackupSection::Collections => restore_raw(&COLLECTIONS, key, value),
+        BackupSection::Proposals => {
+            let collection_id = key.split_once(':').map_or(key.as_str(), |(id, _)| id);
+            let collection_id = collection_id.to_string();
+            let previous = PROPOSALS.with(|p| p.borrow().get(&key));
+            restore_raw(&PROPOSALS, key.clone(), value);
+            let current = PROPOSALS.with(|p| p.borrow().get(&key));
+            reindex_proposal(&collection_id, previous.as_ref(), current.as_ref());
+        }
+        BackupSection::DocumentOverlays => restore_raw(&DOCUMENT_OVERLAYS, key, value),
+        BackupSection::CollectionSlugs => restore_raw(&COLLECTION_SLUGS, key, value),
+        BackupSection::DocumentSlugs => restore_raw(&DOCUMENT_SLUGS, key, value),
+        BackupSection::KeywordDocuments => match value {
+            Some(bytes) => sharded_insert(
+                &keyword_document_shards(),
+                key,
+                IndexedDocument::from_bytes(Cow::Owned(bytes)),
+            ),
+            None => sharded_remove(&keyword_document_shards(), &key),
+        },
+        // Backups hold each term's postings merged over the shards
+        BackupSection::KeywordPostings => {
+            let document_ids = value.map_or_else(Vec::new, |bytes| {
+                KeywordPostings::from_bytes(Cow::Owned(bytes)).document_ids
+            });
+            set_term_postings(&key, document_ids);
+        }
+    }
+}
+
+pub fn get_backup_digest(key: &str) -> Option<Vec<u8>> {
+    BACKUP_DIGESTS.with(|d| d.borrow().get(&key.to_string()))
+}
+
+pub fn set_backup_digest(key: String, digest: Option<Vec<u8>>) {
+    BACKUP_DIGESTS.with(|d| {
+        let mut d = d.borrow_mut();
+        match digest {
+            Some(digest) => {
+                d.insert(key, digest);
+            }
+            None => {
+                d.remove(&key);
+            }
+        }
+    });
+}
+
+/// Digest keys after `after_key`, in order
+pub fn backup_digest_keys(after_key: Option<&str>, limit: usize) -> Vec<String> {
+    let start = after_key.map_or(Bound::Unbounded, |key| Bound::Excluded(key.to_string()));
+    BACKUP_DIGESTS.with(|d| {
+        d.borrow()
+            .range((start, Bound::Unbounded))
+            .take(limit)
+            .map(|(key, _)| key)
+            .collect()
+    })
+}
+
+/// Forget what was backed up, so the next backup is a full one
+pub fn clear_backup_digests() {
+    let keys = backup_digest_keys(None, usize::MAX);
+    BACKUP_DIGESTS.with(|d| {
+        let mut d = d.borrow_mut();
+        for key in keys {
+            d.remove(&key);
+        }
+    });
+}
+
+// ============================
+// READ REPLICAS
+// ============================
+
+pub fn get_read_replica(canister: &Principal) -> Option<ReadReplica> {
+    READ_REPLICAS.with(|r| r.borrow().get(canister))
+}
+
+pub fn put_read_replica(replica: ReadReplica) {
+    READ_REPLICAS.with(|r| {
+        r.borrow_mut().insert(replica.canister, replica);
+    });
+}
+
+pub fn remove_read_replica(canister: &Principal) -> bool {
+    READ_REPLICAS.with(|r| r.borrow_mut().remove(canister).is_some())
+}
+
+pub fn list_read_replicas() -> Vec<ReadReplica> {
+    READ_REPLICAS.with(|r| r.borrow().iter().map(|(_, replica)| replica).collect())
+}
+
+/// Collections after `after_key`, keyed by ID
+pub fn collections_after(after_key: Option<&str>, limit: usize) -> Vec<(String, Collection)> {
+    entries_after(&COLLECTIONS, after_key, limit)
+}
+
+/// Proposals after `after_key`, keyed by "collection_id:proposal_id"
+pub fn proposals_after(after_key: Option<&str>, limit: usize) -> Vec<(String, Proposal)> {
+    entries_after(&PROPOSALS, after_key, limit)
+}
+
+/// Document overlays after `after_key`, keyed by "collection_id:document_id"
+pub fn document_overlays_after(
+    after_key: Option<&str>,
+    limit: usize,
+) -> Vec<(String, DocumentMetadataOverlay)> {
+    entries_after(&DOCUMENT_OVERLAYS, after_key, limit)
+}
+
+/// Keyword-indexed documents after `after_key`, keyed by "collection_id:document_id"
+pub fn indexed_documents_after(
+    after_key: Option<&str>,
+    limit: usize,
+) -> Vec<(String, IndexedDocument)> {
+    sharded_entries_after(&keyword_document_shards(), after_key, limit)
+}
+
+// ============================
+// OUTCALL BUDGET
+// ============================
+
+pub fn get_outcall_budget() -> OutcallBudget {
+    OUTCALL_BUDGET
+        .with(|b| b.borrow().get(&()))
+        .unwrap_or_default()
+}
+
+pub fn set_outcall_budget(budget: OutcallBudget) {
+    OUTCALL_BUDGET.with(|b| {
+        b.borrow_mut().insert((), budget);
+    });
+}
+
+// ============================
+// SUBNET SIZE
+// ============================
+
+pub fn get_subnet_size() -> Option<u32> {
+    SUBNET_SIZE.with(|s| s.borrow().get(&()))
+}
+
+pub fn set_subnet_size(nodes: Option<u32>) {
+    SUBNET_SIZE.with(|s| {
+        let mut s = s.borrow_mut();
+        match nodes {
+            Some(nodes) => s.insert((), nodes),
+            None => s.remove(&()),
+        };
+    });
+}
+
+// ============================
+// PERMAWEB GATEWAYS
+// ============================
+
+pub fn get_permaweb_gateways() -> PermawebGateways {
+    PERMAWEB_GATEWAYS
+        .with(|g| g.borrow().get(&()))
+        .unwrap_or_default()
+}
+
+pub fn set_permaweb_gateways(gateways: PermawebGateways) {
+    PERMAWEB_GATEWAYS.with(|g| {
+        g.borrow_mut().insert((), gateways);
+    });
+}
+
+// ============================
+// OUTCALL MIRRORS
+// ============================
+
+pub fn get_outcall_mirrors() -> OutcallMirrors {
+    OUTCALL_MIRRORS
+        .with(|m| m.borrow().get(&()))
+        .unwrap_or_default()
+}
+
+pub fn set_outcall_mirrors(mirrors: OutcallMirrors) {
+    OUTCALL_MIRRORS.with(|m| {
+        m.borrow_mut().insert((), mirrors);
+    });
+}
+
+// ============================
+// SLUGS
+// ============================
+
+pub fn get_collection_by_slug(slug: &str) -> Option<CollectionId> {
+    COLLECTION_SLUGS.with(|s| s.borrow().get(&slug.to_string()))
+}
+
+pub fn set_collection_slug(slug: &str, collection_id: &str) {
+    COLLECTION_SLUGS.with(|s| {
+        s.borrow_mut()
+            .insert(slug.to_string(), collection_id.to_string());
+    });
+}
+
+pub fn remove_collection_slug(slug: &str) {
+    COLLECTION_SLUGS.with(|s| s.borrow_mut().remove(&slug.to_string()));
+}
+
+pub fn get_document_by_slug(collection_id: &str, slug: &str) -> Option<DocumentId> {
+    DOCUMENT_SLUGS.with(|s| s.borrow().get(&document_key(collection_id, slug)))
+}
+
+pub fn set_document_slug(collection_id: &str, slug: &str, document_id: &str) {
+    DOCUMENT_SLUGS.with(|s| {
+        s.borrow_mut()
+            .insert(document_key(collection_id, slug), document_id.to_string());
+    });
+}
+
+pub fn remove_document_slug(collection_id: &str, slug: &str) {
+    DOCUMENT_SLUGS.with(|s| s.borrow_mut().remove(&document_key(collection_id, slug)));
+}
+
+// Slugs of collections that are gone, returning how many went
+fn remove_orphaned_collection_slugs() -> u64 {
+    let slugs: Vec<String> = COLLECTION_SLUGS.with(|s| {
+        s.borrow()
+            .iter()
+            .filter(|(_, collection_id)| {
+                !COLLECTIONS.with(|c| c.borrow().contains_key(collection_id))
+            })
+            .map(|(slug, _)| slug)
+            .collect()
+    });
+    for slug in &slugs {
+        remove_collection_slug(slug);
+    }
+    slugs.len() as u64
+}
+
+// ============================
+// GOVERNANCE SUBSCRIBERS
+// ============================
+
+pub fn get_governance_subscriber(
+    collection_id: &str,
+    canister: &Principal,
+) -> Option<GovernanceSubscriber> {
+    GOVERNANCE_SUBSCRIBERS.with(|s| {
+        s.borrow()
+            .get(&document_key(collection_id, &canister.to_string()))
+    })
+}
+
+pub fn put_governance_subscriber(collection_id: &str, subscriber: &GovernanceSubscriber) {
+    let key = document_key(collection_id, &subscriber.canister.to_string());
+    GOVERNANCE_SUBSCRIBERS.with(|s| {
+        s.borrow_mut().insert(key, subscriber.clone());
+    });
+}
+
+pub fn remove_governance_subscriber(collection_id: &str, canister: &Principal) -> bool {
+    GOVERNANCE_SUBSCRIBERS.with(|s| {
+        s.borrow_mut()
+            .remove(&document_key(collection_id, &canister.to_string()))
+            .is_some()
+    })
+}
+
+pub fn list_governance_subscribers(collection_id: &str) -> Vec<GovernanceSubscriber> {
+    list_collection_entries(&GOVERNANCE_SUBSCRIBERS, collection_id)
+        .into_iter()
+        .map(|(_, subscriber)| subscriber)
+        .collect()
+}
+
+// ============================
+// CHAT BOTS
+// ============================
+
+pub fn get_chat_bot(collection_id: &str, bot: &Principal) -> Option<ChatBot> {
+    CHAT_BOTS.with(|b| b.borrow().get(&document_key(collection_id, &bot.to_string())))
+}
+
+pub fn put_chat_bot(collection_id: &str, bot: &ChatBot) {
+    let key = document_key(collection_id, &bot.bot.to_string());
+    CHAT_BOTS.with(|b| {
+        b.borrow_mut().insert(key, bot.clone());
+    });
+}
+
+/// Remove a bot with every delegation to it
+pub fn remove_chat_bot(collection_id: &str, bot: &Principal) -> bool {
+    remove_prefixed_entries(&BOT_DELEGATIONS, &format!("{}:{}:", collection_id, bot));
+    CHAT_BOTS.with(|b| {
+        b.borrow_mut()
+            .remove(&document_key(collection_id, &bot.to_string()))
+            .is_some()
+    })
+}
+
+pub fn list_chat_bots(collection_id: &str) -> Vec<ChatBot> {
+    list_collection_entries(&CHAT_BOTS, collection_id)
+        .into_iter()
+        .map(|(_, bot)| bot)
+        .collect()
+}
+
+fn bot_delegation_key(collection_id: &str, bot: &Principal, voter: &Principal) -> String {
+    format!("{}:{}:{}", collection_id, bot, voter)
+}
+
+pub fn get_bot_delegation(
+    collection_id: &str,
+    bot: &Principal,
+    voter: &Principal,
+) -> Option<BotDelegation> {
+    BOT_DELEGATIONS.with(|d| d.borrow().get(&bot_delegation_key(collection_id, bot, voter)))
+}
+
+pub fn put_bot_delegation(collection_id: &str, delegation: &BotDelegation) {
+    let key = bot_delegation_key(collection_id, &delegation.bot, &delegation.voter);
+    BOT_DELEGATIONS.with(|d| {
+        d.borrow_mut().insert(key, delegation.clone());
+    });
+}
+
+pub fn remove_bot_delegation(collection_id: &str, bot: &Principal, voter: &Principal) -> bool {
+    BOT_DELEGATIONS.with(|d| {
+        d.borrow_mut()
+            .remove(&bot_delegation_key(collection_id, bot, voter))
+            .is_some()
+    })
+}
+
+// ============================
+// REACTIONS
+// ============================
+
+// `target` is "d:document_id" or "p:proposal_id"
+pub fn get_reaction_counts(collection_id: &str, target: &str) -> ReactionCounts {
+    REACTION_COUNTS
+        .with(|r| r.borrow().get(&document_key(collection_id, target)))
+        .unwrap_or_default()
+}
+
+pub fn set_reaction_counts(collection_id: &str, target: &str, counts: ReactionCounts) {
+    let key = document_key(collection_id, target);
+    REACTION_COUNTS.with(|r| {
+        let mut r = r.borrow_mut();
+        if counts.counts.iter().all(|c| *c == 0) {
+            r.remove(&key);
+        } else {
+            r.insert(key, counts);
+        }
+    });
+}
+
+/// A collection's reaction counters by target
+pub fn list_reaction_counts(collection_id: &str) -> Vec<(String, ReactionCounts)> {
+    list_collection_entries(&REACTION_COUNTS, collection_id)
+}
+
+fn given_reactions_key(collection_id: &str, target: &str, principal: &Principal) -> String {
+    format!("{}:{}:{}", collection_id, target, principal)
+}
+
+pub fn get_given_reactions(collection_id: &str, target: &str, principal: &Principal) -> u8 {
+    REACTIONS
+        .with(|r| r.borrow().get(&given_reactions_key(collection_id, target, principal)))
+        .unwrap_or(0)
+}
+
+pub fn set_given_reactions(collection_id: &str, target: &str, principal: &Principal, bits: u8) {
+    let key = given_reactions_key(collection_id, target, principal);
+    REACTIONS.with(|r| {
+        let mut r = r.borrow_mut();
+        if bits == 0 {
+            r.remove(&key);
+        } else {
+            r.insert(key, bits);
+        }
+    });
+}
+
+pub fn remove_target_reactions(collection_id: &str, target: &str) {
+    let key = document_key(collection_id, target);
+    REACTION_COUNTS.with(|r| r.borrow_mut().remove(&key));
+    remove_prefixed_entries(&REACTIONS, &format!("{}:", key));
+}
+
+// ============================
+// SITE AND SITEMAP
+// ============================
+
+pub fn get_site_config() -> SiteConfig {
+    SITE_CONFIG
+        .with(|c| c.borrow().get(&()))
+        .unwrap_or_default()
+}
+
+pub fn set_site_config(config: SiteConfig) {
+    SITE_CONFIG.with(|c| {
+        c.borrow_mut().insert((), config);
+    });
+}
+
+pub fn set_sitemap_entry(collection_id: &str, document_id: &str, entry: SitemapEntry) {
+    SITEMAP_ENTRIES.with(|m| {
+        m.borrow_mut()
+            .insert(document_key(collection_id, document_id), entry);
+    });
+}
+
+pub fn remove_sitemap_entry(collection_id: &str, document_id: &str) {
+    SITEMAP_ENTRIES.with(|m| {
+        m.borrow_mut().remove(&document_key(collection_id, document_id));
+    });
+}
+
+/// Up to `limit` of a collection's sitemap entries, in document ID order
+pub fn list_sitemap_entries(collection_id: &str, limit: usize) -> Vec<(DocumentId, SitemapEntry)> {
+    let prefix = format!("{}:", collection_id);
+    SITEMAP_ENTRIES.with(|m| {
+        m.borrow()
+            .range(prefix.clone()..)
+            .take_while(|(key, _)| key.starts_with(&prefix))
+            .take(limit)
+            .map(|(key, entry)| (key[prefix.len()..].to_string(), entry))
+            .collect()
+    })
+}
+
+pub fn has_sitemap_entries() -> bool {
+    SITEMAP_ENTRIES.with(|m| !m.borrow().is_empty())
+}
+
+// ============================
+// FETCH CACHE
+// ============================
+
+pub fn get_cached_fetch(url: &str) -> Option<CachedFetch> {
+    FETCH_CACHE.with(|c| c.borrow().get(&url.to_string()))
+}
+
+pub fn get_cached_body(url: &str) -> Option<String> {
+    FETCH_CACHE_BODIES.with(|b| b.borrow().get(&url.to_string()))
+}
+
+pub fn put_cached_fetch(entry: CachedFetch, body: String) {
+    FETCH_CACHE_BODIES.with(|b| {
+        b.borrow_mut().insert(entry.url.clone(), body);
+    });
+    FETCH_CACHE.with(|c| {
+        c.borrow_mut().insert(entry.url.clone(), entry);
+    });
+}
+
+pub fn remove_cached_fetch(url: &str) {
+    FETCH_CACHE.with(|c| c.borrow_mut().remove(&url.to_string()));
+    FETCH_CACHE_BODIES.with(|b| b.borrow_mut().remove(&url.to_string()));
+}
+
+pub fn list_cached_fetches() -> Vec<CachedFetch> {
+    FETCH_CACHE.with(|c| c.borrow().iter().map(|(_, entry)| entry).collect())
+}
+
+// ============================
+// EVENT LOG
+// ============================
+
+pub fn append_event(actor: Principal, kind: EventKind) {
+    let sequence = event_log_shards()
+        .iter()
+        .filter_map(|shard| shard.with(|log| log.borrow().last_key_value().map(|(s, _)| s)))
+        .max()
+        .map_or(0, |sequence| sequence + 1);
+    event_log_shards()[shard_of(&sequence.to_be_bytes())].with(|log| {
+        log.borrow_mut().insert(
+            sequence,
+            EventLogEntry {
+                sequence,
+                timestamp: time(),
+                actor,
+                kind,
+            },
+        );
+    });
+}
+
+// Events after `after_sequence`, or from the start when unset, oldest first
+pub fn list_events(after_sequence: Option<u64>, limit: usize) -> Vec<EventLogEntry> {
+    let start = after_sequence.map_or(0, |sequence| sequence + 1);
+    let mut events: Vec<EventLogEntry> = event_log_shards()
+        .iter()
+        .flat_map(|shard| {
+            shard.with(|log| {
+                log.borrow()
+                    .range(start..)
+                    .take(limit)
+                    .map(|(_, event)| event)
+                    .collect::<Vec<_>>()
+            })
+        })
+        .collect();
+    events.sort_by_key(|event| event.sequence);
+    events.truncate(limit);
+    events
+}
+
+// ============================
+// CONFIG HISTORY
+// ============================
+
+pub fn append_config_change(change: &ConfigChange) {
+    CONFIG_HISTORY.with(|h| {
+        h.borrow_mut().insert(change.version, change.clone());
+    });
+}
+
+pub fn last_config_change() -> Option<ConfigChange> {
+    CONFIG_HISTORY.with(|h| h.borrow().last_key_value().map(|(_, change)| change))
+}
+
+// Changes after `after_version`, or from the first when unset, oldest first
+pub fn list_config_changes(after_version: Option<u64>, limit: usize) -> Vec<ConfigChange> {
+    let start = after_version.map_or(0, |version| version + 1);
+    CONFIG_HISTORY.with(|h| {
+        h.borrow()
+            .range(start..)
+            .take(limit)
+            .map(|(_, change)| change)
+            .collect()
+    })
+}
+
+pub fn next_pending_config_change_id() -> u64 {
+    PENDING_CONFIG_CHANGES.with(|p| p.borrow().last_key_value().map_or(0, |(id, _)| id + 1))
+}
+
+pub fn put_pending_config_change(pending: &PendingConfigChange) {
+    PENDING_CONFIG_CHANGES.with(|p| {
+        p.borrow_mut().insert(pending.id, pending.clone());
+    });
+}
+
+pub fn take_pending_config_change(id: u64) -> Option<PendingConfigChange> {
+    PENDING_CONFIG_CHANGES.with(|p| p.borrow_mut().remove(&id))
+}
+
+pub fn list_pending_config_changes() -> Vec<PendingConfigChange> {
+    PENDING_CONFIG_CHANGES.with(|p| p.borrow().iter().map(|(_, pending)| pending).collect())
+}
 
 // ============================
-// STABLE STORAGE
+// LOGS
 // ============================
 
-type Memory = VirtualMemory<DefaultMemoryImpl>;
+const MAX_LOG_ENTRIES: u64 = 10_000;
 
-thread_local! {
-    static MEMORY_MANAGER: RefCell<MemoryManager<DefaultMemoryImpl>> = RefCell::new(
-        MemoryManager::init(DefaultMemoryImpl::default())
-    );
+pub fn get_log_level() -> LogLevel {
+    LOG_LEVEL.with(|l| l.borrow().get(&())).unwrap_or_default()
+}
 
-    static COLLECTIONS: RefCell<StableBTreeMap<CollectionId, Collection, Memory>> = RefCell::new(
-        StableBTreeMap::init(
-            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(0)))
-        )
-    );
+pub fn set_log_level(level: LogLevel) {
+    LOG_LEVEL.with(|l| {
+        l.borrow_mut().insert((), level);
+    });
+}
 
-    static PROPOSALS: RefCell<StableBTreeMap<String, Proposal, Memory>> = RefCell::new(
-        StableBTreeMap::init(
-            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(1)))
-        )
-    );
+/// Append to the ring buffer, dropping the oldest entry once it is full
+pub fn append_log(mut entry: LogEntry) {
+    LOGS.with(|logs| {
+        let mut logs = logs.borrow_mut();
+        entry.sequence = logs.last_key_value().map_or(0, |(sequence, _)| sequence + 1);
+        if logs.len() >= MAX_LOG_ENTRIES {
+            if let Some((oldest, _)) = logs.first_key_value() {
+                logs.remove(&oldest);
+            }
+        }
+        logs.insert(entry.sequence, entry);
+    });
+}
+
+// Entries after `after_sequence`, or from the oldest kept when unset, that `matches` accepts
+pub fn list_logs(
+    after_sequence: Option<u64>,
+    limit: usize,
+    matches: impl Fn(&LogEntry) -> bool,
+) -> Vec<LogEntry> {
+    let start = after_sequence.map_or(0, |sequence| sequence + 1);
+    LOGS.with(|logs| {
+        logs.borrow()
+            .range(start..)
+            .map(|(_, entry)| entry)
+            .filter(|entry| matches(entry))
+            .take(limit)
+            .collect()
+    })
 }
 
 // ============================
-// COLLECTION OPERATIONS
+// PROPOSAL ATTACHMENTS
 // ============================
 
-pub fn create_collection(
-    collection_id: &CollectionId,
-    mut config: CollectionConfig,
-    creator: Principal,
-) -> ClanopediaResult<()> {
-    if COLLECTIONS.with(|c| c.borrow().contains_key(collection_id)) {
-        return Err(ClanopediaError::AlreadyExists(format!(
-            "Collection {} already exists",
-            collection_id
-        )));
+fn attachment_key(collection_id: &str, proposal_id: &str, index: u32) -> String {
+    format!("{}:{}:{:04}", collection_id, proposal_id, index)
+}
+
+pub fn list_proposal_attachments(collection_id: &str, proposal_id: &str) -> Vec<ProposalAttachment> {
+    let prefix = format!("{}:{}:", collection_id, proposal_id);
+    PROPOSAL_ATTACHMENTS.with(|a| {
+        a.borrow()
+            .range(prefix.clone()..)
+            .take_while(|(key, _)| key.starts_with(&prefix))
+            .map(|(_, attachment)| attachment)
+            .collect()
+    })
+}
+
+pub fn get_proposal_attachment(
+    collection_id: &str,
+    proposal_id: &str,
+    index: u32,
+) -> Option<ProposalAttachment> {
+    PROPOSAL_ATTACHMENTS.with(|a| a.borrow().get(&attachment_key(collection_id, proposal_id, index)))
+}
+
+pub fn put_proposal_attachment(
+    collection_id: &str,
+    proposal_id: &str,
+    attachment: &ProposalAttachment,
+    chunks: Vec<Vec<u8>>,
+) {
+    let key = attachment_key(collection_id, proposal_id, attachment.index);
+    ATTACHMENT_CHUNKS.with(|c| {
+        let mut c = c.borrow_mut();
+        for (i, chunk) in chunks.into_iter().enumerate() {
+            c.insert(format!("{}:{:04}", key, i), chunk);
+        }
+    });
+    PROPOSAL_ATTACHMENTS.with(|a| {
+        a.borrow_mut().insert(key, attachment.clone());
+    });
+}
+
+pub fn get_attachment_chunk(
+    collection_id: &str,
+    proposal_id: &str,
+    index: u32,
+    chunk: u32,
+) -> Option<Vec<u8>> {
+    let key = format!("{}:{:04}", attachment_key(collection_id, proposal_id, index), chunk);
+    ATTACHMENT_CHUNKS.with(|c| c.borrow().get(&key))
+}
+
+/// Drop a proposal's attachments once it is archived
+pub fn remove_proposal_attachments(collection_id: &str, proposal_id: &str) {
+    let prefix = format!("{}:{}:", collection_id, proposal_id);
+    remove_prefixed_entries(&ATTACHMENT_CHUNKS, &prefix);
+    remove_prefixed_entries(&PROPOSAL_ATTACHMENTS, &prefix);
+}
+
+// ============================
+// CHANGE LOG
+// ============================
+
+// Oldest changes are dropped past this, so indexers must keep up or resync
+const MAX_CHANGE_LOG_ENTRIES: u64 = 100_000;
+
+/// Add to the public change stream. Encrypted collections are left out, since their
+/// document and proposal ids are not public.
+pub fn append_change(collection_id: &str, kind: ChangeKind) {
+    let encrypted = COLLECTIONS
+        .with(|c| c.borrow().get(&collection_id.to_string()))
+        .is_some_and(|c| c.encryption.is_some());
+    if encrypted {
+        return;
     }
 
-    // Convert string representations to Principal objects
-    let admins: Result<Vec<Principal>, _> = config
-        .admins
-        .into_iter()
-        .map(Principal::from_text)
-        .collect();
+    CHANGE_LOG.with(|log| {
+        let mut log = log.borrow_mut();
+        let sequence = log.last_key_value().map_or(0, |(sequence, _)| sequence + 1);
+        log.insert(
+            sequence,
+            ChangeRecord {
+                sequence,
+                timestamp: time(),
+                collection_id: collection_id.to_string(),
+                kind,
+            },
+        );
+        if log.len() > MAX_CHANGE_LOG_ENTRIES {
+            if let Some((oldest, _)) = log.first_key_value() {
+                log.remove(&oldest);
+            }
+        }
+    });
+}
 
-    let governance_token = config
-        .governance_token
-        .map(Principal::from_text)
-        .transpose()
-        .map_err(|e| {
-            ClanopediaError::InvalidInput(format!("Invalid governance token principal: {}", e))
-        })?;
+pub fn first_change_sequence() -> Option<u64> {
+    CHANGE_LOG.with(|log| log.borrow().first_key_value().map(|(sequence, _)| sequence))
+}
 
-    let sns_governance_canister = config
-        .sns_governance_canister
-        .map(Principal::from_text)
-        .transpose()
-        .map_err(|e| {
-            ClanopediaError::InvalidInput(format!("Invalid SNS governance canister principal: {}", e))
-        })?;
+pub fn last_change_sequence() -> Option<u64> {
+    CHANGE_LOG.with(|log| log.borrow().last_key_value().map(|(sequence, _)| sequence))
+}
 
-    let collection = Collection {
-        id: collection_id.clone(),
-        name: config.name,
-        description: config.description,
-        admins: admins.unwrap_or_else(|_| vec![creator]), // Fall back to just the creator if any principal is invalid
-        threshold: config.threshold,
-        governance_token,
-        sns_governance_canister,
-        governance_model: config.governance_model,
-        blueband_collection_id: String::new(),
-        cycles_balance: 0,
-        proposals: HashMap::new(),
-        proposal_counter: 0,
-        created_at: time(),
-        creator,
-        updated_at: time(),
-        quorum_threshold: config.quorum_threshold,
-        is_permissionless: config.is_permissionless,
-    };
+pub fn list_changes(start: u64, limit: usize) -> Vec<ChangeRecord> {
+    CHANGE_LOG.with(|log| {
+        log.borrow()
+            .range(start..)
+            .take(limit)
+            .map(|(_, change)| change)
+            .collect()
+    })
+}
 
-    COLLECTIONS.with(|c| {
-        c.borrow_mut().insert(collection_id.clone(), collection);
+// ============================
+// EXTRACTION JOBS
+// ============================
+
+pub fn next_extraction_job_id() -> u64 {
+    EXTRACTION_JOBS.with(|j| j.borrow().last_key_value().map_or(0, |(id, _)| id + 1))
+}
+
+pub fn put_extraction_job(job: &ExtractionJob) {
+    EXTRACTION_JOBS.with(|j| {
+        j.borrow_mut().insert(job.id, job.clone());
     });
+}
 
-    Ok(())
+pub fn get_extraction_job(id: u64) -> Option<ExtractionJob> {
+    EXTRACTION_JOBS.with(|j| j.borrow().get(&id))
 }
 
-pub fn get_collection(collection_id: &CollectionId) -> ClanopediaResult<Collection> {
-    COLLECTIONS.with(|c| {
-        c.borrow().get(collection_id).ok_or_else(|| {
-            ClanopediaError::NotFound(format!("Collection {} not found", collection_id))
-        })
+pub fn remove_extraction_job(id: u64) {
+    EXTRACTION_JOBS.with(|j| j.borrow_mut().remove(&id));
+}
+
+pub fn list_extraction_jobs() -> Vec<ExtractionJob> {
+    EXTRACTION_JOBS.with(|j| j.borrow().iter().map(|(_, job)| job).collect())
+}
+
+fn job_documents_prefix(collection_id: &str, job_id: u64) -> String {
+    format!("{}:{}:", collection_id, job_id)
+}
+
+// Store documents after the `first_index` already held for the job
+pub fn append_job_documents(
+    collection_id: &str,
+    job_id: u64,
+    first_index: u32,
+    documents: Vec<AddDocumentRequest>,
+) {
+    let prefix = job_documents_prefix(collection_id, job_id);
+    JOB_DOCUMENTS.with(|d| {
+        let mut d = d.borrow_mut();
+        for (offset, document) in documents.into_iter().enumerate() {
+            d.insert(
+                format!("{}{:08}", prefix, first_index as usize + offset),
+                document,
+            );
+        }
+    });
+}
+
+pub fn list_job_documents(
+    collection_id: &str,
+    job_id: u64,
+    offset: usize,
+    limit: usize,
+) -> Vec<AddDocumentRequest> {
+    let prefix = job_documents_prefix(collection_id, job_id);
+    JOB_DOCUMENTS.with(|d| {
+        d.borrow()
+            .range(prefix.clone()..)
+            .take_while(|(key, _)| key.starts_with(&prefix))
+            .skip(offset)
+            .take(limit)
+            .map(|(_, document)| document)
+            .collect()
     })
 }
 
-pub fn update_collection(
-    collection_id: &CollectionId,
-    collection: &Collection,
-) -> ClanopediaResult<()> {
-    if !COLLECTIONS.with(|c| c.borrow().contains_key(collection_id)) {
-        return Err(ClanopediaError::NotFound(format!(
-            "Collection {} not found",
-            collection_id
-        )));
-    }
+pub fn remove_job_documents(collection_id: &str, job_id: u64) {
+    let prefix = job_documents_prefix(collection_id, job_id);
+    remove_prefixed_entries(&JOB_DOCUMENTS, &prefix);
+    remove_prefixed_entries(&JOB_OUTCOMES, &prefix);
+}
 
-    COLLECTIONS.with(|c| {
-        c.borrow_mut()
-            .insert(collection_id.clone(), collection.clone());
+pub fn set_job_outcome(collection_id: &str, job_id: u64, index: u32, outcome: AdditionOutcome) {
+    let key = format!("{}{:08}", job_documents_prefix(collection_id, job_id), index);
+    JOB_OUTCOMES.with(|o| {
+        o.borrow_mut().insert(key, outcome);
     });
+}
 
-    Ok(())
+// Outcomes of a job's documents as (index, outcome), in document order
+pub fn list_job_outcomes(collection_id: &str, job_id: u64) -> Vec<(u32, AdditionOutcome)> {
+    let prefix = job_documents_prefix(collection_id, job_id);
+    JOB_OUTCOMES.with(|o| {
+        o.borrow()
+            .range(prefix.clone()..)
+            .take_while(|(key, _)| key.starts_with(&prefix))
+            .filter_map(|(key, outcome)| Some((key[prefix.len()..].parse().ok()?, outcome)))
+            .collect()
+    })
 }
 
-pub fn delete_collection(collection_id: &CollectionId) -> ClanopediaResult<()> {
-    if !COLLECTIONS.with(|c| c.borrow().contains_key(collection_id)) {
-        return Err(ClanopediaError::NotFound(format!(
-            "Collection {} not found",
-            collection_id
-        )));
+// ============================
+// BOOKMARKS
+// ============================
+
+pub fn get_bookmarks(principal: &Principal) -> ReaderBookmarks {
+    BOOKMARKS
+        .with(|b| b.borrow().get(principal))
+        .unwrap_or_default()
+}
+
+pub fn set_bookmarks(principal: &Principal, bookmarks: ReaderBookmarks) {
+    BOOKMARKS.with(|b| {
+        let mut b = b.borrow_mut();
+        if bookmarks == ReaderBookmarks::default() {
+            b.remove(principal);
+        } else {
+            b.insert(*principal, bookmarks);
+        }
+    });
+}
+
+// Bookmarks are not removed with their collection, which would mean scanning every
+// reader; they are dropped here instead. Returns the number of bookmarks removed.
+fn remove_orphaned_bookmarks() -> u64 {
+    let principals: Vec<Principal> =
+        BOOKMARKS.with(|b| b.borrow().iter().map(|(principal, _)| principal).collect());
+    let mut removed = 0;
+    for principal in principals {
+        let mut bookmarks = get_bookmarks(&principal);
+        let before = bookmark_count(&bookmarks);
+        let exists =
+            |b: &Bookmark| COLLECTIONS.with(|c| c.borrow().contains_key(&b.collection_id));
+        bookmarks.bookmarks.retain(exists);
+        for list in bookmarks.reading_lists.iter_mut() {
+            list.items.retain(exists);
+        }
+        let after = bookmark_count(&bookmarks);
+        if after < before {
+            removed += (before - after) as u64;
+            set_bookmarks(&principal, bookmarks);
+        }
     }
+    removed
+}
 
-    // Delete all proposals for this collection
-    let proposal_keys: Vec<String> = PROPOSALS.with(|p| {
-        p.borrow()
+fn bookmark_count(bookmarks: &ReaderBookmarks) -> usize {
+    bookmarks.bookmarks.len()
+        + bookmarks
+            .reading_lists
             .iter()
-            .filter(|(key, _)| key.starts_with(&format!("{}:", collection_id)))
-            .map(|(k, _)| k.clone())
+            .map(|l| l.items.len())
+            .sum::<usize>()
+}
+
+// ============================
+// NOTIFICATIONS
+// ============================
+
+fn notification_prefix(principal: &Principal) -> String {
+    format!("{}:", principal)
+}
+
+fn notification_key(principal: &Principal, id: u64) -> String {
+    format!("{}{:020}", notification_prefix(principal), id)
+}
+
+// A principal's notifications, oldest first
+pub fn list_notifications(principal: &Principal) -> Vec<Notification> {
+    let prefix = notification_prefix(principal);
+    NOTIFICATIONS.with(|n| {
+        n.borrow()
+            .range(prefix.clone()..)
+            .take_while(|(key, _)| key.starts_with(&prefix))
+            .map(|(_, notification)| notification)
             .collect()
-    });
+    })
+}
 
-    for key in proposal_keys {
-        PROPOSALS.with(|p| {
-            p.borrow_mut().remove(&key);
-        });
-    }
+/// Append to a principal's inbox, dropping its oldest entries past `max_entries`
+pub fn push_notification(
+    principal: &Principal,
+    collection_id: &str,
+    kind: NotificationKind,
+    message: String,
+    max_entries: usize,
+) {
+    let existing = list_notifications(principal);
+    let id = existing.last().map_or(0, |n| n.id + 1);
+    let excess = (existing.len() + 1).saturating_sub(max_entries);
+    NOTIFICATIONS.with(|n| {
+        let mut n = n.borrow_mut();
+        for old in existing.iter().take(excess) {
+            n.remove(&notification_key(principal, old.id));
+        }
+        n.insert(
+            notification_key(principal, id),
+            Notification {
+                id,
+                collection_id: collection_id.to_string(),
+                kind,
+                message,
+                created_at: time(),
+                read: false,
+            },
+        );
+    });
+}
 
-    // Delete collection
-    COLLECTIONS.with(|c| {
-        c.borrow_mut().remove(collection_id);
+pub fn put_notification(principal: &Principal, notification: &Notification) {
+    NOTIFICATIONS.with(|n| {
+        n.borrow_mut().insert(
+            notification_key(principal, notification.id),
+            notification.clone(),
+        );
     });
+}
 
-    Ok(())
+// ============================
+// ACTIVITY
+// ============================
+
+fn activity_key(principal: &Principal, sequence: u64) -> String {
+    format!("{}:{:020}", principal, sequence)
 }
 
-pub fn list_collections() -> Vec<Collection> {
-    COLLECTIONS.with(|c| {
-        c.borrow()
-            .iter()
-            .map(|(_, collection)| collection)
+// Bounds of a principal's keys; ';' sorts right after ':'
+fn activity_range(principal: &Principal) -> std::ops::Range<String> {
+    format!("{}:", principal)..format!("{};", principal)
+}
+
+/// Append to a principal's activity log, dropping its oldest entry past `max_entries`
+pub fn append_activity(
+    principal: &Principal,
+    method: &str,
+    collection_id: Option<String>,
+    max_entries: u64,
+) {
+    ACTIVITY.with(|a| {
+        let mut a = a.borrow_mut();
+        let sequence = a
+            .range(activity_range(principal))
+            .next_back()
+            .map_or(0, |(_, entry)| entry.sequence + 1);
+        if sequence >= max_entries {
+            a.remove(&activity_key(principal, sequence - max_entries));
+        }
+        a.insert(
+            activity_key(principal, sequence),
+            ActivityEntry {
+                sequence,
+                timestamp: time(),
+                method: method.to_string(),
+                collection_id,
+            },
+        );
+    });
+}
+
+/// A principal's entries before `before_sequence`, or its latest, newest first
+pub fn list_activity(
+    principal: &Principal,
+    before_sequence: Option<u64>,
+    limit: usize,
+) -> Vec<ActivityEntry> {
+    let range = activity_range(principal);
+    let end = before_sequence.map_or(range.end, |before| activity_key(principal, before));
+    ACTIVITY.with(|a| {
+        a.borrow()
+            .range(range.start..end)
+            .rev()
+            .take(limit)
+            .map(|(_, entry)| entry)
             .collect()
     })
 }
 
 // ============================
-// PROPOSAL OPERATIONS
+// DOCUMENT NFTS
 // ============================
 
-pub fn update_proposal_in_storage(
-    collection_id: &CollectionId,
-    proposal: &Proposal,
-) -> ClanopediaResult<()> {
-    let mut collection = get_collection(collection_id)?;
+pub fn put_document_nft(nft: &DocumentNft) {
+    DOCUMENT_NFTS.with(|n| {
+        n.borrow_mut()
+            .insert(document_key(&nft.collection_id, &nft.document_id), nft.clone());
+    });
+}
 
-    // Update or remove proposal based on status
-    if proposal.status == ProposalStatus::Executed
-        || proposal.status == ProposalStatus::Rejected
-        || proposal.status == ProposalStatus::Expired
-    {
-        collection.proposals.remove(&proposal.id);
-    } else {
-        collection
-            .proposals
-            .insert(proposal.id.clone(), proposal.clone());
+pub fn get_document_nft(collection_id: &str, document_id: &str) -> Option<DocumentNft> {
+    DOCUMENT_NFTS.with(|n| n.borrow().get(&document_key(collection_id, document_id)))
+}
+
+pub fn list_document_nfts(collection_id: &str) -> Vec<DocumentNft> {
+    list_collection_entries(&DOCUMENT_NFTS, collection_id)
+        .into_iter()
+        .map(|(_, nft)| nft)
+        .collect()
+}
+
+// ============================
+// STORAGE ACCOUNTING
+// ============================
+
+const WASM_PAGE_BYTES: u64 = 64 * 1024;
+
+// Default overall budget, well below the per-canister stable memory limit so that writes
+// fail with an error while upgrades and cleanup still have room
+const DEFAULT_STABLE_MEMORY_BUDGET: u64 = 64 * 1024 * 1024 * 1024;
+
+pub fn get_storage_caps() -> StorageCaps {
+    STORAGE_CAPS
+        .with(|c| c.borrow().get(&()))
+        .unwrap_or(StorageCaps {
+            max_stable_memory_bytes: Some(DEFAULT_STABLE_MEMORY_BUDGET),
+            map_caps: Vec::new(),
+        })
+}
+
+pub fn set_storage_caps(caps: StorageCaps) {
+    STORAGE_CAPS.with(|c| {
+        c.borrow_mut().insert((), caps);
+    });
+}
+
+fn entry_count(map: StorageMap) -> u64 {
+    match map {
+        StorageMap::Collections => COLLECTIONS.with(|m| m.borrow().len()),
+        StorageMap::Proposals => PROPOSALS.with(|m| m.borrow().len()),
+        StorageMap::DocumentOverlays => DOCUMENT_OVERLAYS.with(|m| m.borrow().len()),
+        StorageMap::DocumentLinks => DOCUMENT_LINKS.with(|m| m.borrow().len()),
+        StorageMap::DocumentOutlines => DOCUMENT_OUTLINES.with(|m| m.borrow().len()),
+        StorageMap::ContentStructures => CONTENT_STRUCTURES.with(|m| m.borrow().len()),
+        StorageMap::ConfigHistory => CONFIG_HISTORY.with(|m| m.borrow().len()),
+        StorageMap::PendingConfigChanges => PENDING_CONFIG_CHANGES.with(|m| m.borrow().len()),
+        StorageMap::CallAllowlist => CALL_ALLOWLIST.with(|m| m.borrow().len()),
+        StorageMap::UnsettledBonds => UNSETTLED_BONDS.with(|m| m.borrow().len()),
+        StorageMap::WeeklyRollups => WEEKLY_ROLLUPS.with(|m| m.borrow().len()),
+        StorageMap::QueryLog => QUERY_LOG.with(|m| m.borrow().len()),
+        StorageMap::ProxyAllowlist => PROXY_ALLOWLIST.with(|m| m.borrow().len()),
+        StorageMap::ExtractionProgress => crate::extractor::progress_entry_count(),
+        StorageMap::CollectionSecrets => COLLECTION_SECRETS.with(|m| m.borrow().len()),
+        StorageMap::KeywordPostings => shard_lens(&keyword_posting_shards()).iter().sum(),
+        StorageMap::KeywordDocuments => shard_lens(&keyword_document_shards()).iter().sum(),
+        StorageMap::TrashedDocuments => TRASHED_DOCUMENTS.with(|m| m.borrow().len()),
+        StorageMap::VoteReceipts => VOTE_RECEIPTS.with(|m| m.borrow().len()),
+        StorageMap::CreationRequests => CREATION_REQUESTS.with(|m| m.borrow().len()),
+        StorageMap::EventLog => shard_lens(&event_log_shards()).iter().sum(),
+        StorageMap::ExtractionJobs => EXTRACTION_JOBS.with(|m| m.borrow().len()),
+        StorageMap::JobDocuments => JOB_DOCUMENTS.with(|m| m.borrow().len()),
+        StorageMap::Notifications => NOTIFICATIONS.with(|m| m.borrow().len()),
+        StorageMap::DocumentNfts => DOCUMENT_NFTS.with(|m| m.borrow().len()),
+        StorageMap::ChangeLog => CHANGE_LOG.with(|m| m.borrow().len()),
+        StorageMap::ProposalAttachments => PROPOSAL_ATTACHMENTS.with(|m| m.borrow().len()),
+        StorageMap::AttachmentChunks => ATTACHMENT_CHUNKS.with(|m| m.borrow().len()),
+        StorageMap::FetchCache => FETCH_CACHE.with(|m| m.borrow().len()),
+        StorageMap::FetchCacheBodies => FETCH_CACHE_BODIES.with(|m| m.borrow().len()),
+        StorageMap::DocumentChecksums => DOCUMENT_CHECKSUMS.with(|m| m.borrow().len()),
+        StorageMap::DocumentProvenance => DOCUMENT_PROVENANCE.with(|m| m.borrow().len()),
+        StorageMap::CrossPosts => CROSS_POSTS.with(|m| m.borrow().len()),
+        StorageMap::ExecutedProposals => EXECUTED_PROPOSALS.with(|m| m.borrow().len()),
+        StorageMap::ExternalCalls => EXTERNAL_CALLS.with(|m| m.borrow().len()),
+        StorageMap::Logs => LOGS.with(|m| m.borrow().len()),
+        StorageMap::JobOutcomes => JOB_OUTCOMES.with(|m| m.borrow().len()),
+        StorageMap::Bookmarks => BOOKMARKS.with(|m| m.borrow().len()),
+        StorageMap::DocumentViews => DOCUMENT_VIEWS.with(|m| m.borrow().len()),
+        StorageMap::DailyViews => DAILY_VIEWS.with(|m| m.borrow().len()),
+        StorageMap::SitemapEntries => SITEMAP_ENTRIES.with(|m| m.borrow().len()),
+        StorageMap::CollectionSlugs => COLLECTION_SLUGS.with(|m| m.borrow().len()),
+        StorageMap::DocumentSlugs => DOCUMENT_SLUGS.with(|m| m.borrow().len()),
+        StorageMap::ReactionCounts => REACTION_COUNTS.with(|m| m.borrow().len()),
+        StorageMap::Reactions => REACTIONS.with(|m| m.borrow().len()),
+        StorageMap::GovernanceSubscribers => GOVERNANCE_SUBSCRIBERS.with(|m| m.borrow().len()),
+        StorageMap::ChatBots => CHAT_BOTS.with(|m| m.borrow().len()),
+        StorageMap::BotDelegations => BOT_DELEGATIONS.with(|m| m.borrow().len()),
+        StorageMap::Activity => ACTIVITY.with(|m| m.borrow().len()),
+        StorageMap::DocumentSizes => DOCUMENT_SIZES.with(|m| m.borrow().len()),
+        StorageMap::PinnedDocuments => PINNED_DOCUMENTS.with(|m| m.borrow().len()),
+        StorageMap::QueryAliases => QUERY_ALIASES.with(|m| m.borrow().len()),
+        StorageMap::ProposalIndex => PROPOSAL_INDEX.with(|m| m.borrow().len()),
+        StorageMap::ExecutionReceipts => EXECUTION_RECEIPTS.with(|m| m.borrow().len()),
+        StorageMap::ArchivedDocuments => ARCHIVED_DOCUMENTS.with(|m| m.borrow().len()),
+    }
+}
+
+fn memory_bytes(memory_id: u8) -> u64 {
+    memory::get(memory_id).size() * WASM_PAGE_BYTES
+}
+
+fn allocated_bytes(map: StorageMap) -> u64 {
+    match shard_memory_ids(map) {
+        Some(ids) => ids.into_iter().map(memory_bytes).sum(),
+        None => memory_bytes(map.memory_id()),
+    }
+}
+
+fn shard_usage(map: StorageMap) -> Vec<ShardUsage> {
+    let (Some(ids), Some(counts)) = (shard_memory_ids(map), shard_entry_counts(map)) else {
+        return Vec::new();
+    };
+    ids.into_iter()
+        .zip(counts)
+        .enumerate()
+        .map(|(shard, (memory_id, entries))| ShardUsage {
+            shard: shard as u32,
+            memory_id,
+            entries,
+            allocated_bytes: memory_bytes(memory_id),
+        })
+        .collect()
+}
+
+fn stable_memory_bytes() -> u64 {
+    ic_cdk::api::stable::stable_size() * WASM_PAGE_BYTES
+}
+
+pub fn storage_report() -> StorageReport {
+    let caps = get_storage_caps();
+    let maps = StorageMap::ALL
+        .into_iter()
+        .map(|map| MapUsage {
+            map,
+            memory_id: map.memory_id(),
+            entries: entry_count(map),
+            allocated_bytes: allocated_bytes(map),
+            max_entries: caps
+                .map_caps
+                .iter()
+                .find(|c| c.map == map)
+                .map(|c| c.max_entries),
+            shards: shard_usage(map),
+        })
+        .collect();
+
+    StorageReport {
+        maps,
+        stable_memory_bytes: stable_memory_bytes(),
+        max_stable_memory_bytes: caps.max_stable_memory_bytes,
+        shards_rebalanced: shards_rebalanced(),
+    }
+}
+
+/// Fail if stable memory is over budget
+pub fn ensure_stable_memory() -> ClanopediaResult<()> {
+    let used = stable_memory_bytes();
+    match get_storage_caps().max_stable_memory_bytes {
+        Some(max) if used >= max => Err(ClanopediaError::StorageError(format!(
+            "Stable memory budget exhausted: {} of {} bytes in use",
+            used, max
+        ))),
+        _ => Ok(()),
     }
+}
+
+/// Fail if adding `additional` entries to `map` would pass its cap, or stable memory is
+/// over budget
+pub fn ensure_capacity(map: StorageMap, additional: u64) -> ClanopediaResult<()> {
+    ensure_stable_memory()?;
+    let caps = get_storage_caps();
+    let Some(cap) = caps.map_caps.iter().find(|c| c.map == map) else {
+        return Ok(());
+    };
 
-    update_collection(collection_id, &collection)
+    let entries = entry_count(map);
+    if entries + additional > cap.max_entries {
+        return Err(ClanopediaError::StorageError(format!(
+            "{:?} is at capacity: {} of {} entries used, {} more requested",
+            map, entries, cap.max_entries, additional
+        )));
+    }
+    Ok(())
 }
@@ -0,0 +1,177 @@
+// src/clanopedia_backend/src/subscriptions.rs
+//
+// Other canisters, such as a chat bridge or a treasury, can follow a collection's proposals
+// without polling. A collection's admins allow a canister first; the canister then subscribes
+// to the events it wants and names the method to call. Each event is a one-way call with a
+// `GovernanceEventNotice`: nothing waits for a reply, and a subscriber that rejects or traps
+// does not affect governance. The notice carries IDs only; subscribers read the proposal.
+
+use candid::Principal;
+use ic_cdk::api::{call::notify, time};
+
+use crate::{
+    auth, storage,
+    types::{
+        ClanopediaError, ClanopediaResult, GovernanceEventNotice, GovernanceSubscriber,
+        GovernanceSubscription, Proposal, ProposalEvent, StorageMap,
+    },
+};
+
+const MAX_SUBSCRIBERS_PER_COLLECTION: usize = 10; // Every event costs a call to each
+const MAX_METHOD_CHARS: usize = 64;
+const MAX_ERROR_CHARS: usize = 256;
+
+/// Let `canister` subscribe to the collection's governance events; admins only
+pub fn allow_subscriber(
+    caller: Principal,
+    collection_id: &str,
+    canister: Principal,
+) -> ClanopediaResult<()> {
+    let collection = storage::get_collection(&collection_id.to_string())?;
+    auth::require_admin(&collection, caller)?;
+    if canister == Principal::anonymous() || canister == Principal::management_canister() {
+        return Err(ClanopediaError::InvalidInput(format!(
+            "{} cannot subscribe",
+            canister
+        )));
+    }
+    if storage::get_governance_subscriber(collection_id, &canister).is_some() {
+        return Ok(());
+    }
+    if storage::list_governance_subscribers(collection_id).len() >= MAX_SUBSCRIBERS_PER_COLLECTION {
+        return Err(ClanopediaError::InvalidOperation(format!(
+            "A collection allows at most {} subscribers",
+            MAX_SUBSCRIBERS_PER_COLLECTION
+        )));
+    }
+    storage::ensure_capacity(StorageMap::GovernanceSubscribers, 1)?;
+
+    storage::put_governance_subscriber(
+        collection_id,
+        &GovernanceSubscriber {
+            canister,
+            allowed_by: caller,
+            allowed_at: time(),
+            subscription: None,
+            delivered: 0,
+            failed_deliveries: 0,
+            last_error: None,
+        },
+    );
+    Ok(())
+}
+
+/// Take `canister` off the allowlist, ending its subscription; admins only
+pub fn disallow_subscriber(
+    caller: Principal,
+    collection_id: &str,
+    canister: Principal,
+) -> ClanopediaResult<()> {
+    let collection = storage::get_collection(&collection_id.to_string())?;
+    auth::require_admin(&collection, caller)?;
+    if !storage::remove_governance_subscriber(collection_id, &canister) {
+        return Err(ClanopediaError::NotFound(format!(
+            "{} is not an allowed subscriber",
+            canister
+        )));
+    }
+    Ok(())
+}
+
+pub fn list_subscribers(
+    caller: Principal,
+    collection_id: &str,
+) -> ClanopediaResult<Vec<GovernanceSubscriber>> {
+    let collection = storage::get_collection(&collection_id.to_string())?;
+    auth::require_admin(&collection, caller)?;
+    Ok(storage::list_governance_subscribers(collection_id))
+}
+
+fn validate_method(method: &str) -> ClanopediaResult<()> {
+    if method.is_empty()
+        || method.len() > MAX_METHOD_CHARS
+        || !method
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'_')
+    {
+        return Err(ClanopediaError::InvalidInput(format!(
+            "Method names must be 1 to {} letters, digits and underscores",
+            MAX_METHOD_CHARS
+        )));
+    }
+    Ok(())
+}
+
+/// Subscribe the calling canister, replacing its previous subscription
+pub fn subscribe(
+    caller: Principal,
+    collection_id: &str,
+    events: Vec<ProposalEvent>,
+    method: String,
+) -> ClanopediaResult<()> {
+    storage::get_collection(&collection_id.to_string())?;
+    let mut subscriber = storage::get_governance_subscriber(collection_id, &caller)
+        .ok_or(ClanopediaError::NotAuthorized)?;
+    validate_method(&method)?;
+    let mut unique: Vec<ProposalEvent> = Vec::new();
+    for event in events {
+        if !unique.contains(&event) {
+            unique.push(event);
+        }
+    }
+    if unique.is_empty() {
+        return Err(ClanopediaError::InvalidInput(
+            "Subscribe to at least one event".to_string(),
+        ));
+    }
+
+    subscriber.subscription = Some(GovernanceSubscription {
+        events: unique,
+        method,
+        subscribed_at: time(),
+    });
+    storage::put_governance_subscriber(collection_id, &subscriber);
+    Ok(())
+}
+
+/// End the calling canister's subscription; it stays allowed to subscribe again
+pub fn unsubscribe(caller: Principal, collection_id: &str) -> ClanopediaResult<()> {
+    let mut subscriber = storage::get_governance_subscriber(collection_id, &caller)
+        .ok_or(ClanopediaError::NotAuthorized)?;
+    subscriber.subscription = None;
+    storage::put_governance_subscriber(collection_id, &subscriber);
+    Ok(())
+}
+
+/// Notify every subscriber of `event`. Best effort: a call that cannot be queued is
+/// counted against the subscriber and not retried.
+pub fn publish(collection_id: &str, proposal: &Proposal, event: ProposalEvent) {
+    let notice = GovernanceEventNotice {
+        collection_id: collection_id.to_string(),
+        proposal_id: proposal.id.clone(),
+        event,
+        status: proposal.status.clone(),
+        occurred_at: time(),
+    };
+    for mut subscriber in storage::list_governance_subscribers(collection_id) {
+        let Some(subscription) = &subscriber.subscription else {
+            continue;
+        };
+        if !subscription.events.contains(&event) {
+            continue;
+        }
+        match notify(subscriber.canister, &subscription.method, (notice.clone(),)) {
+            Ok(()) => subscriber.delivered += 1,
+            Err(code) => {
+                subscriber.failed_deliveries += 1;
+                subscriber.last_error = Some(
+                    format!("{:?}", code)
+                        .chars()
+                        .take(MAX_ERROR_CHARS)
+                        .collect(),
+                );
+            }
+        }
+        storage::put_governance_subscriber(collection_id, &subscriber);
+    }
+}
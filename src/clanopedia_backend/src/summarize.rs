@@ -0,0 +1,212 @@
+// src/clanopedia_backend/src/summarize.rs
+
+use ic_cdk::api::management_canister::http_request::{
+    CanisterHttpRequestArgument, HttpHeader, HttpMethod, HttpResponse, TransformArgs,
+    TransformContext, TransformFunc,
+};
+use ic_cdk::query;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    cycles,
+    outcall_pricing::{self, http_request},
+    proxy, secrets,
+    types::{
+        ClanopediaError, ClanopediaResult, Collection, SummarizationConfig, SummarizationOutcome,
+    },
+    AddDocumentRequest,
+};
+
+pub const DEFAULT_SUMMARY_INPUT_CHARS: u32 = 8_000;
+const MAX_SUMMARY_INPUT_CHARS: u32 = 32_000;
+const MAX_SUMMARIES_PER_CALL: usize = 20;
+const SUMMARY_SENTENCES: u32 = 3;
+const SUMMARY_MAX_RESPONSE_BYTES: u64 = 4_096;
+const MAX_SUMMARY_CHARS: usize = 1_000;
+
+pub fn validate_summarization(
+    config: &Option<SummarizationConfig>,
+    encrypted: bool,
+) -> ClanopediaResult<()> {
+    let Some(config) = config else {
+        return Ok(());
+    };
+
+    // The proxy would receive the plaintext the collection is meant to keep private
+    if encrypted {
+        return Err(ClanopediaError::InvalidOperation(
+            "Encrypted collections cannot use summarization".to_string(),
+        ));
+    }
+
+    proxy::validate_proxy_url(config.proxy_url.trim())?;
+
+    if let Some(max) = config.max_input_chars {
+        if max == 0 || max > MAX_SUMMARY_INPUT_CHARS {
+            return Err(ClanopediaError::InvalidInput(format!(
+                "max_input_chars must be between 1 and {}",
+                MAX_SUMMARY_INPUT_CHARS
+            )));
+        }
+    }
+    Ok(())
+}
+
+fn input_chars(config: &SummarizationConfig) -> u32 {
+    config
+        .max_input_chars
+        .unwrap_or(DEFAULT_SUMMARY_INPUT_CHARS)
+}
+
+/// Cycles for one summary outcall, with a 2x buffer
+fn outcall_cycles(input_chars: u32) -> u128 {
+    // UTF-8 worst case for the text, plus JSON framing and headers
+    let request_size = input_chars as u128 * 4 + 1_000;
+    outcall_pricing::http_fee(request_size, SUMMARY_MAX_RESPONSE_BYTES) * 2
+}
+
+/// Upper bound on the cycles needed to summarize `document_count` documents
+pub fn estimate_cost(config: &SummarizationConfig, document_count: u32) -> u128 {
+    let summarized = (document_count as usize).min(MAX_SUMMARIES_PER_CALL) as u128;
+    outcall_cycles(input_chars(config)) * summarized
+}
+
+/// Summarize extracted documents in place. Returns `None` when the collection has no
+/// summarization configured. Failures never fail the extraction: documents simply keep
+/// `summary: None` and the outcome says why.
+pub async fn summarize_documents(
+    collection: &Collection,
+    documents: &mut [AddDocumentRequest],
+) -> Option<SummarizationOutcome> {
+    let config = collection.summarization.as_ref()?;
+
+    let count = documents.len().min(MAX_SUMMARIES_PER_CALL);
+    let cost = estimate_cost(config, count as u32);
+    let mut outcome = SummarizationOutcome {
+        summarized: 0,
+        failed: 0,
+        skipped: (documents.len() - count) as u32,
+        estimated_cycles: cost as u64,
+        error: None,
+    };
+
+    let api_key = cycles::validate_optional_spend(cost)
+        .and_then(|_| secrets::resolve_secret(&collection.id, config.api_key_secret.as_deref()));
+    let api_key = match api_key {
+        Ok(api_key) => api_key,
+        Err(e) => {
+            outcome.skipped = documents.len() as u32;
+            outcome.error = Some(e.to_string());
+            return Some(outcome);
+        }
+    };
+
+    for document in documents.iter_mut().take(count) {
+        match request_summary(config, api_key.as_deref(), document).await {
+            Ok(summary) => {
+                document.summary = Some(summary);
+                outcome.summarized += 1;
+            }
+            Err(e) => {
+                outcome.failed += 1;
+                outcome.error = Some(e.to_string());
+            }
+        }
+    }
+
+    Some(outcome)
+}
+
+// The proxy must answer identically on every replica, so the request carries an
+// idempotency key the proxy can cache its first response under
+async fn request_summary(
+    config: &SummarizationConfig,
+    api_key: Option<&str>,
+    document: &AddDocumentRequest,
+) -> ClanopediaResult<String> {
+    let text: String = document
+        .content
+        .chars()
+        .take(input_chars(config) as usize)
+        .collect();
+    let body = serde_json::to_vec(&json!({
+        "title": document.title,
+        "text": text,
+        "max_sentences": SUMMARY_SENTENCES,
+    }))
+    .map_err(|e| ClanopediaError::InvalidInput(format!("Failed to encode request: {}", e)))?;
+
+    let mut headers = vec![
+        HttpHeader {
+            name: "Content-Type".to_string(),
+            value: "application/json".to_string(),
+        },
+        HttpHeader {
+            name: "User-Agent".to_string(),
+            value: "IC-Clanopedia/1.0".to_string(),
+        },
+        HttpHeader {
+            name: "Idempotency-Key".to_string(),
+            value: hex::encode(Sha256::digest(&body)),
+        },
+    ];
+    if let Some(key) = api_key {
+        headers.push(HttpHeader {
+            name: "Authorization".to_string(),
+            value: format!("Bearer {}", key),
+        });
+    }
+
+    let request = CanisterHttpRequestArgument {
+        url: config.proxy_url.trim().to_string(),
+        method: HttpMethod::POST,
+        body: Some(body),
+        max_response_bytes: Some(SUMMARY_MAX_RESPONSE_BYTES),
+        transform: Some(TransformContext {
+            function: TransformFunc(candid::Func {
+                principal: ic_cdk::api::id(),
+                method: "transform_summary_response".to_string(),
+            }),
+            context: vec![],
+        }),
+        headers,
+    };
+
+    let (response,) = http_request(request, outcall_cycles(input_chars(config)))
+        .await
+        .map_err(|(code, message)| {
+            ClanopediaError::ExternalCallError(format!(
+                "Summary request failed: {:?} - {}",
+                code, message
+            ))
+        })?;
+
+    let status = response.status.to_string().parse::<u32>().unwrap_or(0);
+    if !(200..300).contains(&status) {
+        return Err(ClanopediaError::ExternalCallError(format!(
+            "Summary proxy returned {}",
+            response.status
+        )));
+    }
+
+    let json: Value = serde_json::from_slice(&response.body).map_err(|e| {
+        ClanopediaError::ExternalCallError(format!("Invalid summary response: {}", e))
+    })?;
+    json.get("summary")
+        .and_then(Value::as_str)
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(|s| s.chars().take(MAX_SUMMARY_CHARS).collect())
+        .ok_or_else(|| {
+            ClanopediaError::ExternalCallError("Summary response has no summary".to_string())
+        })
+}
+
+/// Only the body is used, so every header is dropped to keep replicas in agreement
+#[query]
+fn transform_summary_response(args: TransformArgs) -> HttpResponse {
+    let mut response = args.response;
+    response.headers.clear();
+    response
+}
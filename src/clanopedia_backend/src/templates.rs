@@ -0,0 +1,135 @@
+// src/clanopedia_backend/src/templates.rs
+
+use crate::{
+    extractor::PipelineStep, AutoTagConfig, ClanopediaError, ClanopediaResult, CollectionConfig,
+    CollectionTemplate, CollectionTemplateInfo, GovernanceModel, TemplateOverrides,
+};
+
+const MULTISIG_THRESHOLD: u32 = 2;
+const TOKEN_DAO_QUORUM: u32 = 20;
+const SNS_QUORUM: u32 = 10;
+
+// Archives take documents as-is; wikis and knowledge bases get cleaned, deduplicated and
+// tagged on the way in
+fn archive_pipeline() -> Vec<PipelineStep> {
+    vec![PipelineStep::Sanitize, PipelineStep::Dedupe]
+}
+
+fn wiki_pipeline() -> Vec<PipelineStep> {
+    vec![
+        PipelineStep::Sanitize,
+        PipelineStep::DetectLanguage,
+        PipelineStep::Dedupe,
+    ]
+}
+
+pub fn list_templates() -> Vec<CollectionTemplateInfo> {
+    [
+        CollectionTemplate::MultisigDocsArchive,
+        CollectionTemplate::TokenDaoWiki,
+        CollectionTemplate::SnsKnowledgeBase,
+    ]
+    .into_iter()
+    .map(|template| {
+        let (name, description) = match template {
+            CollectionTemplate::MultisigDocsArchive => (
+                "Multisig docs archive",
+                "A small group of admins approves every change with a 2-of-N multisig",
+            ),
+            CollectionTemplate::TokenDaoWiki => (
+                "Token DAO wiki",
+                "Token holders vote on changes; needs the DAO's governance token",
+            ),
+            CollectionTemplate::SnsKnowledgeBase => (
+                "SNS knowledge base",
+                "Changes go through SNS proposals; needs the SNS governance canister",
+            ),
+        };
+        CollectionTemplateInfo {
+            template,
+            name: name.to_string(),
+            description: description.to_string(),
+        }
+    })
+    .collect()
+}
+
+/// Build the config for a new collection from a template. Overrides replace the
+/// template's values; the governance principal a template depends on is required.
+pub fn template_config(
+    template: CollectionTemplate,
+    overrides: TemplateOverrides,
+) -> ClanopediaResult<CollectionConfig> {
+    let mut config = CollectionConfig {
+        name: overrides.name,
+        description: overrides.description,
+        admins: overrides.admins.unwrap_or_default(),
+        threshold: 1,
+        governance_token: None,
+        sns_governance_canister: None,
+        governance_model: GovernanceModel::Multisig,
+        quorum_threshold: 0,
+        is_permissionless: false,
+        proposal_bond: None,
+        proposer_eligibility: None,
+        embed_concurrency: None,
+        embedding_proxy: overrides.embedding_proxy,
+        encryption: None,
+        pipeline: None,
+        auto_tag: None,
+        summarization: None,
+        ocr: None,
+        nft_minting: None,
+        license: None,
+        vote_reminder_hours: None,
+        timelock_hours: None,
+        custom_proposals: None,
+        call_policy: None,
+        review_policy: None,
+        content_limits: None,
+        retry_policy: None,
+    };
+
+    match template {
+        CollectionTemplate::MultisigDocsArchive => {
+            // A lone admin could never reach a 2-of-N threshold
+            config.threshold = MULTISIG_THRESHOLD.min(config.admins.len().max(1) as u32);
+            config.pipeline = Some(archive_pipeline());
+        }
+        CollectionTemplate::TokenDaoWiki => {
+            config.governance_token = Some(overrides.governance_token.ok_or_else(|| {
+                ClanopediaError::InvalidInput(
+                    "The Token DAO wiki template needs a governance token".to_string(),
+                )
+            })?);
+            config.governance_model = GovernanceModel::TokenBased;
+            config.quorum_threshold = TOKEN_DAO_QUORUM;
+            config.pipeline = Some(wiki_pipeline());
+            config.auto_tag = Some(AutoTagConfig { max_tags: 5 });
+        }
+        CollectionTemplate::SnsKnowledgeBase => {
+            config.sns_governance_canister =
+                Some(overrides.sns_governance_canister.ok_or_else(|| {
+                    ClanopediaError::InvalidInput(
+                        "The SNS knowledge base template needs an SNS governance canister"
+                            .to_string(),
+                    )
+                })?);
+            config.governance_model = GovernanceModel::SnsIntegrated;
+            config.quorum_threshold = SNS_QUORUM;
+            config.pipeline = Some(wiki_pipeline());
+            config.auto_tag = Some(AutoTagConfig { max_tags: 5 });
+        }
+    }
+
+    if let Some(threshold) = overrides.threshold {
+        config.threshold = threshold;
+    }
+    if let Some(quorum) = overrides.quorum_threshold {
+        config.quorum_threshold = quorum;
+    }
+    if let Some(concurrency) = overrides.embed_concurrency {
+        config.embed_concurrency = Some(concurrency);
+    }
+    Ok(config)
+}
@@ -0,0 +1,125 @@
+// src/clanopedia_backend/src/timelock.rs
+//
+// A collection can hold approved changes to who governs it (admins, threshold, quorum,
+// governance model and the collection config) for `timelock_hours` before they execute.
+// Until a held proposal executes, a VetoProposal proposal can cancel it, so a rushed or
+// captured vote cannot take over a collection before its community can react. Other
+// proposals are not delayed.
+
+use ic_cdk::api::time;
+
+use crate::{
+    notifications, storage, subscriptions,
+    types::{
+        ClanopediaError, ClanopediaResult, Collection, Proposal, ProposalEvent, ProposalStatus,
+        ProposalType, PROPOSAL_DURATION_NANOS,
+    },
+};
+
+const HOUR_NANOS: u64 = 60 * 60 * 1_000_000_000;
+
+/// Proposals that change governance and wait out the collection's timelock
+pub fn is_timelocked(proposal_type: &ProposalType) -> bool {
+    match proposal_type {
+        ProposalType::AddAdmin { .. }
+        | ProposalType::RemoveAdmin { .. }
+        | ProposalType::ChangeThreshold { .. }
+        | ProposalType::UpdateQuorum { .. }
+        | ProposalType::ChangeGovernanceModel { .. }
+        | ProposalType::UpdateCollection { .. } => true,
+        ProposalType::Composite { actions } => actions.iter().any(is_timelocked),
+        _ => false,
+    }
+}
+
+/// Start the timelock of a proposal that was just approved
+pub fn on_approved(collection: &Collection, proposal: &mut Proposal) {
+    let hours = collection.timelock_hours.unwrap_or(0);
+    if hours == 0 || !is_timelocked(&proposal.proposal_type) {
+        return;
+    }
+    let executable_at = time() + hours as u64 * HOUR_NANOS;
+    proposal.executable_at = Some(executable_at);
+    // A full voting period to execute once the timelock ends
+    proposal.expires_at = proposal
+        .expires_at
+        .max(executable_at + PROPOSAL_DURATION_NANOS);
+}
+
+pub fn ensure_unlocked(proposal: &Proposal) -> ClanopediaResult<()> {
+    match proposal.executable_at {
+        Some(executable_at) if executable_at > time() => {
+            Err(ClanopediaError::InvalidProposalState(format!(
+                "Proposal {} is timelocked for another {} hours",
+                proposal.id,
+                (executable_at - time()).div_ceil(HOUR_NANOS)
+            )))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Return the proposal a veto would cancel: approved, held by a timelock and not executed
+pub fn validate_veto(collection_id: &str, proposal_id: &str) -> ClanopediaResult<Proposal> {
+    let target = storage::get_proposal(collection_id, proposal_id).ok_or_else(|| {
+        ClanopediaError::NotFound(format!(
+            "Proposal {} not found in collection {}",
+            proposal_id, collection_id
+        ))
+    })?;
+    if target.status != ProposalStatus::Approved
+        || target.executed
+        || target.executable_at.is_none()
+    {
+        return Err(ClanopediaError::InvalidProposalState(format!(
+            "Proposal {} is not an approved, timelocked proposal awaiting execution",
+            proposal_id
+        )));
+    }
+    Ok(target)
+}
+
+pub fn execute_veto(
+    collection_id: &str,
+    veto: &Proposal,
+    proposal_id: &str,
+) -> ClanopediaResult<()> {
+    let mut target = validate_veto(collection_id, proposal_id)?;
+    target.status = ProposalStatus::Rejected;
+    storage::update_proposal_in_storage(&collection_id.to_string(), &target)?;
+    notifications::proposal_failed(
+        collection_id,
+        &target,
+        format!("Vetoed by proposal {}", veto.id),
+    );
+    subscriptions::publish(collection_id, &target, ProposalEvent::Failed);
+    Ok(())
+}
+
+/// Admins of a timelocked collection cannot change its governance settings directly;
+/// those changes go through proposals and wait out the timelock
+pub fn ensure_direct_update_allowed(
+    current: &Collection,
+    updated: &Collection,
+) -> ClanopediaResult<()> {
+    if current.timelock_hours.unwrap_or(0) == 0 {
+        return Ok(());
+    }
+    let changed = current.admins != updated.admins
+        || current.threshold != updated.threshold
+        || current.quorum_threshold != updated.quorum_threshold
+        || current.governance_model != updated.governance_model
+        || current.governance_token != updated.governance_token
+        || current.is_permissionless != updated.is_permissionless
+        || current.timelock_hours != updated.timelock_hours
+        || current.custom_proposals != updated.custom_proposals
+        || current.call_policy != updated.call_policy
+        || current.review_policy != updated.review_policy;
+    if changed {
+        return Err(ClanopediaError::InvalidOperation(
+            "This collection is timelocked; change its governance settings through a proposal"
+                .to_string(),
+        ));
+    }
+    Ok(())
+}
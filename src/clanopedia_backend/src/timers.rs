@@ -0,0 +1,97 @@
+// src/clanopedia_backend/src/timers.rs
+
+use std::cell::Cell;
+
+use ic_cdk::api::time;
+
+use crate::{backup, bonds, creation, extractor::jobs, logging, reconcile, reminders, storage, trash};
+
+// A round moves this many embedded proposals; small enough to stay well inside the
+// instruction limit even for large collections
+const MIGRATION_BATCH: usize = 50;
+// Entries moved to their shard per round; each is a single read and write
+const REBALANCE_BATCH: usize = 500;
+// Proposals indexed per round; each is a read and four index writes
+const PROPOSAL_INDEX_BATCH: usize = 200;
+const MIGRATION_INTERVAL_NANOS: u64 = 5 * 1_000_000_000;
+
+thread_local! {
+    // Set while a run is awaiting; the run in flight schedules the next one when it ends
+    static RUNNING: Cell<bool> = const { Cell::new(false) };
+}
+
+struct RunGuard;
+
+impl RunGuard {
+    fn acquire() -> Option<Self> {
+        (!RUNNING.with(|r| r.replace(true))).then_some(RunGuard)
+    }
+}
+
+impl Drop for RunGuard {
+    fn drop(&mut self) {
+        RUNNING.with(|r| r.set(false));
+    }
+}
+
+/// The canister has a single global timer; point it at whichever job is due first, or
+/// clear it when there is nothing left to do
+pub fn schedule() {
+    let migrating = !storage::proposal_migration_progress().complete
+        || !storage::shards_rebalanced()
+        || !storage::proposal_index_ready();
+    let migration = migrating.then(|| time() + MIGRATION_INTERVAL_NANOS);
+    let next = [
+        migration,
+        trash::next_purge_at(),
+        jobs::next_slice_at(),
+        reminders::next_reminder_at(),
+        creation::next_reconcile_at(),
+        reconcile::next_check_at(),
+        backup::next_backup_at(),
+        bonds::next_retry_at(),
+    ]
+        .into_iter()
+        .flatten()
+        .min()
+        .unwrap_or(0);
+    ic_cdk::api::set_global_timer(next);
+}
+
+pub async fn run() {
+    let Some(_guard) = RunGuard::acquire() else {
+        return;
+    };
+    let moved = storage::migrate_proposals(MIGRATION_BATCH);
+    if moved > 0 {
+        logging::info(
+            "timers",
+            None,
+            format!("Migrated {} proposals to indexed storage", moved),
+        );
+    }
+    let rebalanced = storage::rebalance_shards(REBALANCE_BATCH);
+    if rebalanced > 0 {
+        logging::info(
+            "timers",
+            None,
+            format!("Moved {} entries to their storage shard", rebalanced),
+        );
+    }
+    let indexed = storage::backfill_proposal_index(PROPOSAL_INDEX_BATCH);
+    if indexed > 0 {
+        logging::info(
+            "timers",
+            None,
+            format!("Indexed {} proposals for search", indexed),
+        );
+    }
+    trash::purge_expired().await;
+    jobs::run_slice().await;
+    reminders::send_due_reminders();
+    creation::reconcile_stale().await;
+    reconcile::run_due().await;
+    backup::run_due().await;
+    bonds::retry_due().await;
+    schedule();
+}
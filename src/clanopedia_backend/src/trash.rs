@@ -0,0 +1,312 @@
+// src/clanopedia_backend/src/trash.rs
+
+use std::{cell::RefCell, collections::HashSet};
+
+use candid::Principal;
+use ic_cdk::api::time;
+
+use crate::{
+    archive, auth, crosspost,
+    external::blueband,
+    keyword_index, logging, storage, timers,
+    types::{
+        Capability, ChangeKind, ClanopediaError, ClanopediaResult, Collection, StorageMap,
+        TrashInfo, TrashedDocument,
+    },
+};
+
+const TRASH_RETENTION_NANOS: u64 = 30 * 24 * 60 * 60 * 1_000_000_000; // 30 days
+const PURGE_RETRY_NANOS: u64 = 60 * 60 * 1_000_000_000; // Retry failed purges hourly
+const PURGE_BATCH: usize = 20; // Blueband deletions per timer run
+
+thread_local! {
+    // "collection_id" or "collection_id:document_id" of every purge awaiting Blueband
+    static PURGING: RefCell<HashSet<String>> = RefCell::new(HashSet::new());
+}
+
+// Held while trash is being deleted from Blueband; it can no longer be restored then
+struct PurgeGuard {
+    key: String,
+}
+
+impl PurgeGuard {
+    fn acquire(key: String) -> Option<Self> {
+        PURGING
+            .with(|p| p.borrow_mut().insert(key.clone()))
+            .then_some(Self { key })
+    }
+}
+
+impl Drop for PurgeGuard {
+    fn drop(&mut self) {
+        PURGING.with(|p| {
+            p.borrow_mut().remove(&self.key);
+        });
+    }
+}
+
+fn purge_key(collection_id: &str, document_id: Option<&str>) -> String {
+    match document_id {
+        Some(document_id) => format!("{}:{}", collection_id, document_id),
+        None => collection_id.to_string(),
+    }
+}
+
+fn ensure_not_purging(collection_id: &str, document_id: Option<&str>) -> ClanopediaResult<()> {
+    let purging = PURGING.with(|p| {
+        let p = p.borrow();
+        p.contains(&purge_key(collection_id, None))
+            || document_id.is_some_and(|d| p.contains(&purge_key(collection_id, Some(d))))
+    });
+    if purging {
+        return Err(ClanopediaError::InvalidOperation(
+            "This is being purged from the trash and can no longer be restored".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+fn new_trash(caller: Principal) -> TrashInfo {
+    let now = time();
+    TrashInfo {
+        trashed_by: caller,
+        trashed_at: now,
+        purge_at: now + TRASH_RETENTION_NANOS,
+    }
+}
+
+/// Trashed collections are read-only; writes and searches fail until the collection is restored
+pub fn ensure_not_trashed(collection: &Collection) -> ClanopediaResult<()> {
+    match collection.trashed {
+        Some(_) => Err(ClanopediaError::InvalidOperation(format!(
+            "Collection {} is in the trash",
+            collection.id
+        ))),
+        None => Ok(()),
+    }
+}
+
+pub fn is_document_trashed(collection_id: &str, document_id: &str) -> bool {
+    storage::get_trashed_document(collection_id, document_id).is_some()
+}
+
+// ============================
+// TRASH AND RESTORE
+// ============================
+
+pub fn trash_collection(collection_id: &str, caller: Principal) -> ClanopediaResult<()> {
+    let mut collection = storage::get_collection(&collection_id.to_string())?;
+    ensure_not_trashed(&collection)?;
+
+    collection.trashed = Some(new_trash(caller));
+    storage::update_collection(&collection_id.to_string(), &collection)?;
+    timers::schedule();
+    Ok(())
+}
+
+pub fn trash_document(
+    collection_id: &str,
+    document_id: &str,
+    caller: Principal,
+) -> ClanopediaResult<()> {
+    let collection = storage::get_collection(&collection_id.to_string())?;
+    auth::authorize(&collection, caller, Capability::TrashDocuments)?;
+    ensure_not_trashed(&collection)?;
+    archive::ensure_not_archived(&collection)?;
+    if is_document_trashed(collection_id, document_id) {
+        return Err(ClanopediaError::InvalidOperation(format!(
+            "Document {} is already in the trash",
+            document_id
+        )));
+    }
+
+    move_to_trash(collection_id, document_id, caller)
+}
+
+/// Trash a document on behalf of `caller` without checking their capabilities; callers
+/// have already been authorized, or act on an executed proposal
+pub fn move_to_trash(
+    collection_id: &str,
+    document_id: &str,
+    caller: Principal,
+) -> ClanopediaResult<()> {
+    storage::ensure_capacity(StorageMap::TrashedDocuments, 1)?;
+    storage::set_trashed_document(collection_id, document_id, new_trash(caller));
+    storage::append_change(
+        collection_id,
+        ChangeKind::DocumentRemoved {
+            document_id: document_id.to_string(),
+        },
+    );
+    timers::schedule();
+    Ok(())
+}
+
+/// Restore a trashed document, or the collection itself when `document_id` is unset
+pub fn restore_from_trash(
+    collection_id: &str,
+    document_id: Option<&str>,
+    caller: Principal,
+) -> ClanopediaResult<()> {
+    let mut collection = storage::get_collection(&collection_id.to_string())?;
+    match document_id {
+        Some(_) => auth::authorize(&collection, caller, Capability::TrashDocuments)?,
+        None => auth::require_admin(&collection, caller)?,
+    }
+    ensure_not_purging(collection_id, document_id)?;
+
+    match document_id {
+        Some(document_id) => {
+            archive::ensure_not_archived(&collection)?;
+            storage::remove_trashed_document(collection_id, document_id).ok_or_else(|| {
+                ClanopediaError::NotFound(format!("Document {} is not in the trash", document_id))
+            })?;
+            storage::append_change(
+                collection_id,
+                ChangeKind::DocumentAdded {
+                    document_id: document_id.to_string(),
+                },
+            );
+        }
+        None => {
+            if collection.trashed.take().is_none() {
+                return Err(ClanopediaError::InvalidOperation(format!(
+                    "Collection {} is not in the trash",
+                    collection_id
+                )));
+            }
+            collection.updated_at = time();
+            storage::update_collection(&collection_id.to_string(), &collection)?;
+        }
+    }
+    timers::schedule();
+    Ok(())
+}
+
+pub fn list_trash(collection_id: &str) -> ClanopediaResult<Vec<TrashedDocument>> {
+    storage::get_collection(&collection_id.to_string())?;
+    Ok(storage::list_trashed_documents(collection_id)
+        .into_iter()
+        .map(|(document_id, trash)| TrashedDocument {
+            title: storage::get_indexed_document(collection_id, &document_id).map(|d| d.title),
+            document_id,
+            trash,
+        })
+        .collect())
+}
+
+// ============================
+// PURGING
+// ============================
+
+/// When the earliest pending purge is due, if anything is in the trash
+pub fn next_purge_at() -> Option<u64> {
+    let collections = storage::list_collections()
+        .into_iter()
+        .filter_map(|c| c.trashed.map(|t| t.purge_at));
+    let documents = storage::list_all_trashed_documents()
+        .into_iter()
+        .map(|(_, _, t)| t.purge_at);
+
+    collections.chain(documents).min()
+}
+
+/// Permanently delete trash past its retention window. Failed Blueband deletions are
+/// retried later; the batch limit keeps each run within the instruction limit.
+pub async fn purge_expired() {
+    let now = time();
+    let mut budget = PURGE_BATCH;
+
+    let expired_collections: Vec<Collection> = storage::list_collections()
+        .into_iter()
+        .filter(|c| c.trashed.as_ref().is_some_and(|t| t.purge_at <= now))
+        .take(budget)
+        .collect();
+    budget -= expired_collections.len();
+
+    for collection in expired_collections {
+        // Earlier purges awaited Blueband; it may have been restored or deleted meanwhile
+        let due = storage::get_collection(&collection.id)
+            .is_ok_and(|c| c.trashed.is_some_and(|t| t.purge_at <= now));
+        if !due {
+            continue;
+        }
+        let Some(_guard) = PurgeGuard::acquire(purge_key(&collection.id, None)) else {
+            continue;
+        };
+        match blueband::delete_collection(&collection.blueband_collection_id).await {
+            Ok(()) => {
+                let _ = storage::delete_collection(&collection.id);
+                crosspost::remove_links_from(&collection.id, None);
+            }
+            Err(e) => {
+                logging::warn(
+                    "trash",
+                    Some(&collection.id),
+                    format!("Purging collection failed: {}", e),
+                );
+                postpone_collection(&collection.id);
+            }
+        }
+    }
+
+    let expired_documents: Vec<_> = storage::list_all_trashed_documents()
+        .into_iter()
+        .filter(|(_, _, trash)| trash.purge_at <= now)
+        .take(budget)
+        .collect();
+
+    for (collection_id, document_id, _) in expired_documents {
+        // Read again: earlier purges awaited Blueband, and it may have been restored meanwhile
+        let Some(trash) = storage::get_trashed_document(&collection_id, &document_id)
+            .filter(|trash| trash.purge_at <= now)
+        else {
+            continue;
+        };
+        let Ok(collection) = storage::get_collection(&collection_id) else {
+            storage::remove_document_records(&collection_id, &document_id);
+            continue;
+        };
+        // A linked document's content belongs to its source collection; only the link goes
+        if storage::get_cross_post(&collection_id, &document_id).is_some() {
+            keyword_index::remove_document(&collection_id, &document_id);
+            storage::remove_document_records(&collection_id, &document_id);
+            continue;
+        }
+        let Some(_guard) = PurgeGuard::acquire(purge_key(&collection_id, Some(&document_id)))
+        else {
+            continue;
+        };
+        match blueband::delete_document(&collection.blueband_collection_id, &document_id).await {
+            Ok(()) => {
+                keyword_index::remove_document(&collection_id, &document_id);
+                storage::remove_document_records(&collection_id, &document_id);
+                crosspost::remove_links_from(&collection_id, Some(&document_id));
+            }
+            Err(e) => {
+                logging::warn(
+                    "trash",
+                    Some(&collection_id),
+                    format!("Purging document {} failed: {}", document_id, e),
+                );
+                // Skip the retry if the collection was purged while the call was in flight
+                if is_document_trashed(&collection_id, &document_id) {
+                    let trash = TrashInfo {
+                        purge_at: time() + PURGE_RETRY_NANOS,
+                        ..trash
+                    };
+                    storage::set_trashed_document(&collection_id, &document_id, trash);
+                }
+            }
+        }
+    }
+}
+
+fn postpone_collection(collection_id: &String) {
+    if let Ok(mut collection) = storage::get_collection(collection_id) {
+        if let Some(trash) = collection.trashed.as_mut() {
+            trash.purge_at = time() + PURGE_RETRY_NANOS;
+            let _ = storage::update_collection(collection_id, &collection);
+        }
+    }
+}
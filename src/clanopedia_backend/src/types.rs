@@ -1,11 +1,14 @@
 // src/clanopedia_backend/src/types.rs
 
 use candid::{CandidType, Nat, Principal};
+use ic_cdk::api::management_canister::http_request::HttpHeader;
 use ic_stable_structures::storable::Storable;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fmt;
-use crate::external::blueband::ContentType;
+use crate::external::blueband::{ContentType, DocumentMetadata, VectorMatch};
+use crate::extractor::{ExtractionError, PipelineStep};
+use crate::memory;
 
 pub type CollectionId = String;
 pub type ProposalId = String;
@@ -31,6 +34,32 @@ pub struct Collection {
     pub proposals: HashMap<ProposalId, Proposal>,
     pub cycles_balance: u64,
     pub proposal_counter: u64,
+    pub proposal_bond: Option<ProposalBondConfig>,
+    pub proposer_eligibility: Option<ProposerEligibility>,
+    pub embed_concurrency: Option<u32>,
+    pub embedding_proxy_url: Option<String>, // Blueband's default proxy if unset
+    pub encryption: Option<CollectionEncryption>,
+    pub pipeline: Option<Vec<PipelineStep>>, // Steps run over extracted documents, in order
+    pub auto_tag: Option<AutoTagConfig>,
+    pub summarization: Option<SummarizationConfig>,
+    pub ocr: Option<OcrConfig>,
+    pub forked_from: Option<ForkOrigin>,
+    pub trashed: Option<TrashInfo>, // Hidden and read-only until restored or purged
+    pub paused: Option<PauseInfo>,  // Frozen by a controller until resumed
+    pub archived: Option<ArchiveInfo>, // Read-only until unarchived by governance
+    pub vote_reminder_hours: Option<u32>, // DEFAULT_VOTE_REMINDER_HOURS if unset; 0 disables
+    pub timelock_hours: Option<u32>, // Delay before approved governance changes execute
+    pub custom_proposals: Option<Vec<CustomProposalKind>>, // Each must fit the call policy
+    pub call_policy: Option<CallPolicy>, // No external calls if unset
+    pub operators: Option<Vec<OperatorGrant>>, // Granted directly by admins, not through config
+    pub nft_minting: Option<NftMintConfig>,
+    pub license: Option<LicensePolicy>,
+    pub review_policy: Option<ReviewPolicy>, // Proposals execute without review if unset
+    pub slug: Option<String>, // Set through SetSlug proposals
+    pub content_limits: Option<ContentLimits>,
+    pub retry_policy: Option<RetryPolicy>, // RetryPolicy::default() if unset
+    pub spam_protection: Option<SpamProtection>, // Set directly by admins, not through config
+    pub source_policy: Option<SourcePolicy>, // Set through SetSourcePolicy proposals
 }
 
 impl Default for Collection {
@@ -53,6 +82,32 @@ impl Default for Collection {
             proposals: HashMap::new(),
             cycles_balance: 0,
             proposal_counter: 0,
+            proposal_bond: None,
+            proposer_eligibility: None,
+            embed_concurrency: None,
+            embedding_proxy_url: None,
+            encryption: None,
+            pipeline: None,
+            auto_tag: None,
+            summarization: None,
+            ocr: None,
+            forked_from: None,
+            trashed: None,
+            paused: None,
+            archived: None,
+            vote_reminder_hours: None,
+            timelock_hours: None,
+            custom_proposals: None,
+            call_policy: None,
+            operators: None,
+            nft_minting: None,
+            license: None,
+            review_policy: None,
+            slug: None,
+            content_limits: None,
+            retry_policy: None,
+            spam_protection: None,
+            source_policy: None,
         }
     }
 }
@@ -68,6 +123,489 @@ pub struct CollectionConfig {
     pub governance_model: GovernanceModel,
     pub quorum_threshold: u32,
     pub is_permissionless: bool,
+    pub proposal_bond: Option<ProposalBondConfig>,
+    pub proposer_eligibility: Option<ProposerEligibility>,
+    pub embed_concurrency: Option<u32>, // Parallel Blueband embed calls; DEFAULT_EMBED_CONCURRENCY if unset
+    pub embedding_proxy: Option<EmbeddingProxyConfig>,
+    pub encryption: Option<EncryptionConfig>, // Fixed at creation; readers change through proposals
+    pub pipeline: Option<Vec<PipelineStep>>,
+    pub auto_tag: Option<AutoTagConfig>, // Apply suggested tags on extraction; suggest only if unset
+    pub summarization: Option<SummarizationConfig>, // Not available for encrypted collections
+    pub ocr: Option<OcrConfig>, // Not available for encrypted collections
+    pub nft_minting: Option<NftMintConfig>, // Not available for encrypted collections
+    pub license: Option<LicensePolicy>,
+    pub vote_reminder_hours: Option<u32>, // Hours before expiry to remind admins who have not voted
+    pub timelock_hours: Option<u32>, // Hours approved governance changes wait, open to veto
+    pub custom_proposals: Option<Vec<CustomProposalKind>>,
+    pub call_policy: Option<CallPolicy>,
+    pub review_policy: Option<ReviewPolicy>,
+    pub content_limits: Option<ContentLimits>,
+    pub retry_policy: Option<RetryPolicy>,
+}
+
+/// Soft-delete state of a collection or document
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct TrashInfo {
+    pub trashed_by: Principal,
+    pub trashed_at: u64,
+    pub purge_at: u64, // Permanently deleted from this time on
+}
+
+/// Set by a controller to stop all writes, searches and governance on a collection
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct PauseInfo {
+    pub paused_by: Principal,
+    pub paused_at: u64,
+    pub reason: String,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct TrashedDocument {
+    pub document_id: DocumentId,
+    pub title: Option<String>,
+    pub trash: TrashInfo,
+}
+
+/// Where a forked collection came from
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ForkOrigin {
+    pub collection_id: CollectionId,
+    pub forked_by: Principal,
+    pub forked_at: u64,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct ForkConfig {
+    pub name: Option<String>, // "<source name> (fork)" if unset
+    pub description: Option<String>,
+    pub copy_content: bool, // Re-add document content to the fork's own Blueband collection
+    pub copy_settings: bool, // Pipeline, auto-tagging and embed concurrency
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct ForkedDocument {
+    pub source_document_id: DocumentId,
+    pub document_id: Option<DocumentId>, // Set once content was re-added to the fork
+    pub title: String,
+    pub error: Option<String>,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct ForkResult {
+    pub collection_id: CollectionId,
+    pub documents: Vec<ForkedDocument>,
+    pub embed_proposal_id: Option<ProposalId>,
+}
+
+/// Preset collection setups for common community types
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum CollectionTemplate {
+    MultisigDocsArchive,
+    TokenDaoWiki,
+    SnsKnowledgeBase,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct CollectionTemplateInfo {
+    pub template: CollectionTemplate,
+    pub name: String,
+    pub description: String,
+}
+
+/// Values supplied when creating a collection from a template; unset fields keep the
+/// template's defaults
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct TemplateOverrides {
+    pub name: String,
+    pub description: String,
+    pub admins: Option<Vec<String>>,
+    pub threshold: Option<u32>,
+    pub quorum_threshold: Option<u32>,
+    pub governance_token: Option<String>, // Required by TokenDaoWiki
+    pub sns_governance_canister: Option<String>, // Required by SnsKnowledgeBase
+    pub embed_concurrency: Option<u32>,
+    pub embedding_proxy: Option<EmbeddingProxyConfig>,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct AutoTagConfig {
+    pub max_tags: u32,
+}
+
+/// How the summarization stage went for one extraction call
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct SummarizationOutcome {
+    pub summarized: u32,
+    pub failed: u32,
+    pub skipped: u32, // Over the per-call limit, or all of them when cycles ran short
+    pub estimated_cycles: u64,
+    pub error: Option<String>, // Last failure, if any
+}
+
+/// LLM proxy that writes a short abstract for each extracted document.
+/// The proxy URL must be allowlisted, like embedding proxies.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct SummarizationConfig {
+    pub proxy_url: String,
+    pub api_key_secret: Option<String>, // Name of a collection secret sent as a bearer token
+    pub max_input_chars: Option<u32>,   // DEFAULT_SUMMARY_INPUT_CHARS if unset
+}
+
+/// OCR proxy that reads the text of image-only PDF pages. Each page image is POSTed as the
+/// request body; the proxy answers `{"text": ..., "confidence": 0.0-1.0}`. The proxy URL
+/// must be allowlisted, like embedding proxies.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct OcrConfig {
+    pub proxy_url: String,
+    pub api_key_secret: Option<String>, // Name of a collection secret sent as a bearer token
+    pub max_pages: Option<u32>,         // DEFAULT_OCR_PAGES if unset
+}
+
+/// How OCR went for one image-only PDF
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct OcrOutcome {
+    pub recognized_pages: u32,
+    pub failed_pages: u32,
+    pub skipped_pages: u32, // Over max_pages, or without a JPEG or JPEG 2000 image
+    pub confidence: f32,    // Mean over recognized pages, weighted by text length
+    pub estimated_cycles: u64,
+    pub error: Option<String>, // Last failure, if any
+}
+
+/// An SPDX license expression such as "MIT" or "Apache-2.0 OR MIT", or free-form terms
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum License {
+    Spdx(String),
+    Custom(String),
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct LicensePolicy {
+    pub default_license: Option<License>, // Applies to documents that declare none
+    pub reject_unknown: bool,             // Refuse documents left without a license
+}
+
+/// Size caps on a collection's content; unset fields keep the defaults
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ContentLimits {
+    pub max_document_bytes: Option<u64>, // DEFAULT_MAX_DOCUMENT_BYTES if unset
+    pub max_total_bytes: Option<u64>,    // No cap on the whole collection if unset
+}
+
+/// How extraction outcalls that fail transiently (timeouts, 5xx responses) are retried
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct RetryPolicy {
+    pub max_retries: u32,          // Retries at once, within the call that failed
+    pub max_deferred_retries: u32, // Later attempts of a queued job, each on a timer
+    pub base_delay_secs: u32,      // Before the first deferred attempt; doubles, with jitter
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 2,
+            max_deferred_retries: 3,
+            base_delay_secs: 30,
+        }
+    }
+}
+
+/// The size caps in force for a collection, and what its content takes up so far
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct CollectionLimits {
+    pub max_document_bytes: u64,
+    pub max_total_bytes: Option<u64>,
+    pub total_bytes: u64, // Documents added before sizes were recorded are not counted
+    pub max_file_bytes: u64, // Uploaded files and record dumps, before extraction
+    pub max_playlist_videos: u32, // YouTube videos fetched per batch
+}
+
+/// Reviewers who must sign off on proposals before they can execute, on top of the vote
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ReviewPolicy {
+    pub reviewers: Vec<Principal>, // Those who may be assigned to review
+    pub required_approvals: u32,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReviewDecision {
+    Approve,
+    RequestChanges,
+    Comment, // A note without a verdict
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Review {
+    pub reviewer: Principal,
+    pub decision: ReviewDecision,
+    pub note: String,
+    pub submitted_at: u64,
+}
+
+/// The review stage of a proposal created under a review policy
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ProposalReview {
+    pub required_approvals: u32, // From the policy when the proposal was created
+    pub assigned: Vec<Principal>,
+    pub reviews: Vec<Review>, // Oldest first
+}
+
+/// Where a proposal's review stands; only each assigned reviewer's latest verdict counts
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ReviewStatus {
+    pub assigned: Vec<Principal>,
+    pub required_approvals: u32,
+    pub approvals: u32,
+    pub changes_requested: u32,
+    pub comments: u32,
+    pub signed_off: bool,
+}
+
+/// A named proposal kind a collection allows, bound to one method of another canister
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct CustomProposalKind {
+    pub name: String,
+    pub canister: Principal,
+    pub method: String,
+    pub cycles: Option<u64>, // Attached to each call; none if unset
+}
+
+/// The canister methods a collection's proposals may call, and limits on every call
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct CallPolicy {
+    pub allowed: Vec<AllowedCall>,
+    pub max_arg_bytes: Option<u32>, // MAX_CALL_ARG_BYTES if unset
+    pub max_cycles: Option<u64>,    // Calls cannot attach cycles if unset
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct AllowedCall {
+    pub canister: Principal,
+    pub method: String,
+}
+
+/// A canister method controllers allow custom proposals to call. Call policies can only pick
+/// from these, and no policy can exceed the entry's caps.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct CallAllowlistEntry {
+    pub canister: Principal,
+    pub method: String,
+    pub max_arg_bytes: u32,
+    pub max_cycles: u64,
+    pub added_by: Principal,
+    pub added_at: u64,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum ExternalCallOutcome {
+    Replied { reply_bytes: u64, cycles_refunded: u64 },
+    Rejected(String),
+}
+
+/// Audit record of one call a proposal made to another canister
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ExternalCallRecord {
+    pub proposal_id: ProposalId,
+    pub kind: String,
+    pub canister: Principal,
+    pub method: String,
+    pub arg_bytes: u64,
+    pub cycles: u64,
+    pub called_at: u64,
+    pub outcome: ExternalCallOutcome,
+}
+
+/// One inter-canister call made while a proposal executed
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct CallTrace {
+    pub canister: Principal,
+    pub method: String,
+    pub args_hash: String, // Hex sha256 of the Candid-encoded arguments
+    pub cycles: u64,       // Attached, less the refund
+    pub started_at: u64,
+    pub duration_nanos: u64,
+    pub outcome: ExternalCallOutcome,
+}
+
+/// One attempt to execute a proposal, including retries of failed embeds
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ExecutionAttempt {
+    pub executor: Principal,
+    pub started_at: u64,
+    pub finished_at: u64,
+    pub error: Option<String>, // Unset when the attempt succeeded
+    pub calls: Vec<CallTrace>,
+    pub calls_dropped: u32, // Made after the trace was full
+}
+
+/// Every recorded attempt to execute a proposal, oldest first
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ExecutionReceipt {
+    pub collection_id: CollectionId,
+    pub proposal_id: ProposalId,
+    pub attempts: Vec<ExecutionAttempt>,
+}
+
+/// A document whose content was moved out of Blueband to the backup target; its metadata
+/// stays under the same ID until a `RestoreArchivedDocument` proposal re-adds it
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct ArchivedDocument {
+    pub document_id: DocumentId,
+    pub title: String,
+    pub tags: Vec<String>,
+    pub content_type: ContentType,
+    pub source_url: Option<String>,
+    pub size: u64,
+    pub content_sha256: String, // Hex, checked when the content is read back
+    pub target: BackupTarget,
+    pub key: String,
+    pub archived_by: Principal,
+    pub archived_at: u64,
+}
+
+/// Mint an ICRC-7 token for every document an embed proposal approves. Clanopedia must be
+/// a minting account on the NFT canister.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct NftMintConfig {
+    pub nft_canister: Principal,
+    pub mint_to: Option<Principal>, // The proposal's creator if unset
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum NftMintState {
+    Pending,
+    Minted { minted_at: u64 },
+    Failed(String), // Retried by `mint_document_nfts`
+}
+
+/// The ICRC-7 token attesting that a document was approved into its collection
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct DocumentNft {
+    pub collection_id: CollectionId,
+    pub document_id: DocumentId,
+    pub proposal_id: ProposalId,
+    pub nft_canister: Principal,
+    pub token_id: Nat,
+    pub owner: Principal,
+    pub checksum: Option<String>, // Set once Blueband's metadata has been read
+    pub source_url: Option<String>,
+    pub state: NftMintState,
+}
+
+/// Makes a collection private: content is stored encrypted and keys are derived via vetKD
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct EncryptionConfig {
+    pub readers: Vec<Principal>,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct CollectionEncryption {
+    pub readers: Vec<Principal>, // Admins can always read
+    pub key_epoch: u32,          // Bumped on every rotation
+    pub rotated_at: u64,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct EncryptedCollectionKey {
+    pub encrypted_key: Vec<u8>,
+    pub key_epoch: u32,
+    pub derivation_input: Vec<u8>,
+}
+
+/// Collection-specific embedding proxy; the URL must be allowlisted by a canister controller.
+/// The API key is write-only: Clanopedia keeps it sealed, in the collection's secrets or in
+/// the proposal that sets it, and never returns it. Blueband is handed the key, since it
+/// authenticates to the proxy with it.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct EmbeddingProxyConfig {
+    pub url: String,
+    pub api_key: Option<String>,
+}
+
+/// Token deposit required from proposal creators in token-based collections
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ProposalBondConfig {
+    pub amount: Nat,
+    pub treasury: Option<Principal>, // Forfeited bonds go here; kept by the canister if unset
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ProposalBond {
+    pub token: Principal,
+    pub amount: Nat,
+    pub status: BondStatus,
+    pub subaccount: Option<Vec<u8>>, // The collection's escrow; the default account if unset
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum BondStatus {
+    Held,
+    Refunded,
+    Forfeited,
+}
+
+/// A bond refund or forfeit whose transfer failed. The timer retries it, and collection
+/// admins can with `settle_bond`, until it goes through. Every attempt sends the same
+/// `created_at_time` and memo, so the ledger never pays it twice. A transfer that cannot
+/// succeed as is, or that keeps failing, is parked and left to `settle_bond`.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct UnsettledBond {
+    pub collection_id: CollectionId,
+    pub proposal_id: ProposalId,
+    pub creator: Principal,
+    pub bond: ProposalBond,
+    pub outcome: BondStatus, // Refunded or Forfeited
+    pub attempts: u32,
+    pub last_error: String,
+    pub retry_at: u64,
+    pub created_at_time: u64,
+    pub parked: bool,
+}
+
+/// Which URLs may be extracted into the collection. A rule is a host with an optional path:
+/// `github.com/org/*` matches every URL under the path, `github.com/org/repo` that page and
+/// those below it, and `*.example.com` the domain and its subdomains. URLs are matched as
+/// given, so a YouTube channel is allowed by its channel or playlist URL. Deny rules win;
+/// when there are allow rules, a URL must match one of them.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct SourcePolicy {
+    pub allow: Vec<String>,
+    pub deny: Vec<String>,
+}
+
+/// A URL the collection's source policy refuses
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct SourcePolicyViolation {
+    pub url: String,
+    pub rule: Option<String>, // The deny rule it matched; None when it matched no allow rule
+}
+
+/// Throttles on proposal creation, set by admins. Admins themselves are never throttled.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct SpamProtection {
+    // Per principal; permissionless collections get a default limit if unset
+    pub max_proposals_per_hour: Option<u32>,
+    pub fee: Option<ProposalFee>, // Charged only while the collection is permissionless
+    pub blocklist: Vec<Principal>,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum ProposalFee {
+    Cycles { amount: u64 }, // Attached to the call that creates the proposal
+    // Pulled from an ICRC-2 approval; forwarded to the treasury if set
+    Tokens {
+        ledger: Principal,
+        amount: Nat,
+        treasury: Option<Principal>,
+    },
+}
+
+/// Who may create proposals, on top of the governance model's own rules
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum ProposerEligibility {
+    MinTokenBalance { amount: Nat },
+    NftHolder { nft_canister: Principal, token_id: Option<Nat> },
+    SnsNeuron { min_stake_e8s: u64 },
 }
 
 #[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
@@ -87,7 +625,93 @@ pub struct Proposal {
     pub executed_by: Option<Principal>,
     pub threshold: u32,
     pub threshold_met: bool,
-    pub sns_proposal_id: Option<u64>
+    pub sns_proposal_id: Option<u64>,
+    pub bond: Option<ProposalBond>,
+    pub execution_report: Option<ExecutionReport>,
+    pub reminded_at: Option<u64>, // When admins who had not voted were reminded
+    pub depends_on: Option<Vec<ProposalId>>, // Must all be executed before this one
+    pub executable_at: Option<u64>, // End of the timelock holding an approved proposal
+    pub review: Option<ProposalReview>, // Must be signed off before execution
+    pub sealed_voting: Option<SealedVoting>, // Votes are committed as hashes, then revealed
+    pub sealed_proxy_api_key: Option<Vec<u8>>, // Taken out of an UpdateCollection config
+}
+
+/// Commit–reveal voting on a proposal. Until `commit_ends_at` voters submit only a hash of
+/// their vote; from then until `reveal_ends_at` they reveal it, and only revealed votes count.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct SealedVoting {
+    pub commit_ends_at: u64,
+    pub reveal_ends_at: u64,
+    pub commitments: HashMap<Principal, VoteCommitment>,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct VoteCommitment {
+    pub hash: Vec<u8>, // sha256(voter principal bytes || "Yes", "No" or "Abstain" || salt)
+    pub committed_at: u64,
+    pub revealed_at: Option<u64>,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum ItemExecutionState {
+    Pending,
+    Succeeded,
+    Failed(String),
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ExecutionItem {
+    pub document_id: DocumentId,
+    pub state: ItemExecutionState,
+    pub attempts: u32,
+}
+
+/// Per-document execution state of an embed proposal, persisted as execution progresses
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ExecutionReport {
+    pub items: Vec<ExecutionItem>,
+    pub succeeded: u32,
+    pub failed: u32,
+    pub started_at: u64,
+    pub last_attempt_at: u64,
+}
+
+impl ExecutionReport {
+    pub fn new(document_ids: &[DocumentId], now: u64) -> Self {
+        Self {
+            items: document_ids
+                .iter()
+                .map(|id| ExecutionItem {
+                    document_id: id.clone(),
+                    state: ItemExecutionState::Pending,
+                    attempts: 0,
+                })
+                .collect(),
+            succeeded: 0,
+            failed: 0,
+            started_at: now,
+            last_attempt_at: now,
+        }
+    }
+
+    pub fn refresh_counts(&mut self) {
+        self.succeeded = self
+            .items
+            .iter()
+            .filter(|i| i.state == ItemExecutionState::Succeeded)
+            .count() as u32;
+        self.failed = self
+            .items
+            .iter()
+            .filter(|i| matches!(i.state, ItemExecutionState::Failed(_)))
+            .count() as u32;
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.items
+            .iter()
+            .all(|i| i.state == ItemExecutionState::Succeeded)
+    }
 }
 
 #[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
@@ -97,6 +721,72 @@ pub enum Vote {
     Abstain,
 }
 
+/// Yes/no counts and weights right after a vote was recorded. Weight is the voter's token
+/// balance for token-based collections and 1 otherwise.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct VoteTally {
+    pub yes_votes: u64,
+    pub no_votes: u64,
+    pub yes_weight: Nat,
+    pub no_weight: Nat,
+}
+
+/// Where a token-based proposal's vote stands against the quorum, so frontends need not
+/// redo the threshold math
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct VoteProjection {
+    pub proposal_id: ProposalId,
+    pub total_supply: Nat,
+    pub yes_weight: Nat,
+    pub no_weight: Nat,
+    pub abstain_weight: Nat,
+    pub remaining_weight: Nat, // Supply that has not voted yet
+    pub quorum_threshold: u32, // Percent of the supply voting yes that approves
+    pub quorum_weight: Nat,
+    pub yes_weight_needed: Nat,       // Zero once the quorum is met
+    pub quorum_progress_percent: u32, // Of the quorum weight, capped at 100
+    pub voting_ends_at: u64,
+    pub outcome: ProjectedOutcome,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProjectedOutcome {
+    Open,     // Could still go either way
+    Approved, // Yes weight meets the quorum, or the proposal was approved
+    // The remaining supply voting yes would fall short. Balances count as of each vote, so
+    // tokens moved after voting could in principle still vote again.
+    Unreachable,
+    Failed, // Voting ended without approval
+}
+
+/// Proof that a vote was counted. Receipts form a hash chain whose head is the canister's
+/// certified data; see `receipts` for how the hashes are built.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct VoteReceipt {
+    pub sequence: u64,
+    pub collection_id: CollectionId,
+    pub proposal_id: ProposalId,
+    pub voter: Principal,
+    pub vote: Vote,
+    pub weight: Nat,
+    pub timestamp: u64,
+    pub tally: VoteTally,
+    pub tally_hash: String,    // Hex SHA-256
+    pub receipt_hash: String,  // Hex SHA-256 over the fields above
+    pub previous_hash: String, // Chain head before this receipt
+    pub chain_hash: String,    // SHA-256(previous_hash || receipt_hash)
+}
+
+/// A receipt plus the receipt hashes issued after it, enough to walk the chain up to the
+/// certified head
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct VoteReceiptProof {
+    pub receipt: VoteReceipt,
+    pub later_receipt_hashes: Vec<String>,
+    pub head_hash: String,
+    pub certificate: Option<Vec<u8>>,
+}
+
 #[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
 pub enum GovernanceModel {
     Permissionless,
@@ -112,6 +802,7 @@ pub enum ProposalStatus {
     Rejected,
     Expired,
     Executed,
+    PartiallyExecuted, // Some embed items failed; retryable via retry_failed_items
 }
 
 #[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
@@ -122,9 +813,152 @@ pub enum ProposalType {
     RemoveAdmin { admin: Principal },
     ChangeThreshold { new_threshold: u32 },
     UpdateQuorum { new_percentage: u32 },
-    UpdateCollection { config: CollectionConfig },
+    UpdateCollection { config: Box<CollectionConfig> },
     ChangeGovernanceModel { model: GovernanceModel },
     DeleteCollection,
+    UpdateDocumentMetadata {
+        document_id: DocumentId,
+        title: Option<String>,
+        tags: Option<Vec<String>>,
+        author: Option<String>,
+        license: Option<License>,
+    },
+    AddReader { reader: Principal },
+    RemoveReader { reader: Principal },
+    RotateEncryptionKey,
+    LinkDocument {
+        source_collection: CollectionId,
+        document_id: DocumentId,
+    },
+    ArchiveCollection,
+    UnarchiveCollection,
+    Composite { actions: Vec<ProposalType> }, // Executed in order, all or nothing
+    VetoProposal { proposal_id: ProposalId }, // Cancels a proposal held by a timelock
+    Custom { kind: String, payload: Vec<u8> }, // Candid-encoded argument of the kind's method
+    // Embeds each replacement, then trashes the document it replaces
+    ResyncDocuments { replacements: Vec<DocumentReplacement> },
+    // The collection's slug when no document is given; no slug clears it
+    SetSlug {
+        document_id: Option<DocumentId>,
+        slug: Option<String>,
+    },
+    // Replaces the pinned set, in display order; empty unpins everything
+    SetPinnedDocuments { document_ids: Vec<DocumentId> },
+    SetAliases { aliases: Vec<AliasUpdate> },
+    // Re-adds and embeds an archived document; it gets a new ID
+    RestoreArchivedDocument { document_id: DocumentId },
+    // No policy lets every source be extracted
+    SetSourcePolicy { policy: Option<SourcePolicy> },
+}
+
+impl ProposalType {
+    /// Variant names, as `search_proposals` filters take them
+    pub const NAMES: [&'static str; 25] = [
+        "EmbedDocument",
+        "BatchEmbed",
+        "AddAdmin",
+        "RemoveAdmin",
+        "ChangeThreshold",
+        "UpdateQuorum",
+        "UpdateCollection",
+        "ChangeGovernanceModel",
+        "DeleteCollection",
+        "UpdateDocumentMetadata",
+        "AddReader",
+        "RemoveReader",
+        "RotateEncryptionKey",
+        "LinkDocument",
+        "ArchiveCollection",
+        "UnarchiveCollection",
+        "Composite",
+        "VetoProposal",
+        "Custom",
+        "ResyncDocuments",
+        "SetSlug",
+        "SetPinnedDocuments",
+        "SetAliases",
+        "RestoreArchivedDocument",
+        "SetSourcePolicy",
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ProposalType::EmbedDocument { .. } => "EmbedDocument",
+            ProposalType::BatchEmbed { .. } => "BatchEmbed",
+            ProposalType::AddAdmin { .. } => "AddAdmin",
+            ProposalType::RemoveAdmin { .. } => "RemoveAdmin",
+            ProposalType::ChangeThreshold { .. } => "ChangeThreshold",
+            ProposalType::UpdateQuorum { .. } => "UpdateQuorum",
+            ProposalType::UpdateCollection { .. } => "UpdateCollection",
+            ProposalType::ChangeGovernanceModel { .. } => "ChangeGovernanceModel",
+            ProposalType::DeleteCollection => "DeleteCollection",
+            ProposalType::UpdateDocumentMetadata { .. } => "UpdateDocumentMetadata",
+            ProposalType::AddReader { .. } => "AddReader",
+            ProposalType::RemoveReader { .. } => "RemoveReader",
+            ProposalType::RotateEncryptionKey => "RotateEncryptionKey",
+            ProposalType::LinkDocument { .. } => "LinkDocument",
+            ProposalType::ArchiveCollection => "ArchiveCollection",
+            ProposalType::UnarchiveCollection => "UnarchiveCollection",
+            ProposalType::Composite { .. } => "Composite",
+            ProposalType::VetoProposal { .. } => "VetoProposal",
+            ProposalType::Custom { .. } => "Custom",
+            ProposalType::ResyncDocuments { .. } => "ResyncDocuments",
+            ProposalType::SetSlug { .. } => "SetSlug",
+            ProposalType::SetPinnedDocuments { .. } => "SetPinnedDocuments",
+            ProposalType::SetAliases { .. } => "SetAliases",
+            ProposalType::RestoreArchivedDocument { .. } => "RestoreArchivedDocument",
+            ProposalType::SetSourcePolicy { .. } => "SetSourcePolicy",
+        }
+    }
+}
+
+/// One alias change in a `SetAliases` proposal; no expansion removes the alias
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct AliasUpdate {
+    pub alias: String,
+    pub expansion: Option<String>,
+}
+
+/// A search alias of a collection, expanded in queries that mention it
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct QueryAlias {
+    pub alias: String,
+    pub expansion: String,
+    pub updated_at: u64,
+    pub proposal_id: ProposalId, // The SetAliases proposal that last set it
+}
+
+/// An alias a search query mentioned, and the expansion searched along with it
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct AliasExpansion {
+    pub alias: String,
+    pub expansion: String,
+}
+
+/// A re-synced document staged for embedding, and the document it supersedes once embedded
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct DocumentReplacement {
+    pub document_id: DocumentId,
+    pub replaces: Option<DocumentId>, // None for documents new at the source
+}
+
+/// What `search_proposals` looks for; unset criteria match every proposal
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, Default)]
+pub struct ProposalFilter {
+    pub types: Vec<String>, // Names from `ProposalType::NAMES`; empty matches every type
+    pub creator: Option<Principal>,
+    pub created_after: Option<u64>,  // Inclusive
+    pub created_before: Option<u64>, // Exclusive
+    pub status: Option<ProposalStatus>,
+    pub offset: Option<u32>,
+    pub limit: Option<u32>,
+}
+
+/// Proposals matching a filter, newest first
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct ProposalSearchPage {
+    pub proposals: Vec<Proposal>,
+    pub total: u64, // Matches before `offset` and `limit` were applied
 }
 
 #[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
@@ -146,6 +980,18 @@ pub enum ClanopediaError {
     ProposalAlreadyExecuted,
     SnsError(String),
     SnsNotConfigured,
+    RateLimited(String),
+    ValidationFailed(Vec<FieldError>),
+    ExecutionInProgress(String),
+    ExtractionFailed(ExtractionError),
+    SourceNotAllowed(SourcePolicyViolation),
+}
+
+/// One rejected input field; nested fields are dotted, list entries indexed (`tags[2]`)
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
 }
 
 #[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
@@ -172,188 +1018,2582 @@ pub struct DocumentRequest {
     pub tags: Option<Vec<String>>,
 }
 
-#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
-pub struct SearchResult {
-    pub document_id: DocumentId,
-    pub title: String,
-    pub content: String,
-    pub score: f64,
-}
+pub const MAX_DOCUMENT_OVERLAY_BYTES: usize = 64 * 1024; // Encoded size of an overlay
 
-impl fmt::Display for ClanopediaError {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            ClanopediaError::NotFound(msg) => write!(f, "Not found: {}", msg),
-            ClanopediaError::NotAuthorized => write!(f, "Not authorized"),
-            ClanopediaError::InvalidProposalState(msg) => {
-                write!(f, "Invalid proposal state: {}", msg)
-            }
-            ClanopediaError::ProposalExpired => write!(f, "Proposal has expired"),
-            ClanopediaError::ThresholdNotMet => write!(f, "Voting threshold not met"),
-            ClanopediaError::InsufficientCycles(msg) => write!(f, "Insufficient cycles: {}", msg),
-            ClanopediaError::ExternalCallError(msg) => write!(f, "External call error: {}", msg),
-            ClanopediaError::StorageError(e) => write!(f, "Storage error: {:?}", e),
-            ClanopediaError::InvalidArgument(msg) => write!(f, "Invalid argument: {}", msg),
-            ClanopediaError::AlreadyExists(msg) => write!(f, "Already exists: {}", msg),
-            ClanopediaError::InvalidOperation(msg) => write!(f, "Invalid operation: {}", msg),
-            ClanopediaError::BluebandError(msg) => write!(f, "Blueband error: {}", msg),
-            ClanopediaError::Unauthorized(msg) => write!(f, "Unauthorized: {}", msg),
-            ClanopediaError::InvalidInput(msg) => write!(f, "Invalid input: {}", msg),
-            ClanopediaError::ProposalAlreadyExecuted => write!(f, "Proposal already executed"),
-            ClanopediaError::SnsError(msg) => write!(f, "SNS error: {}", msg),
-            ClanopediaError::SnsNotConfigured => write!(f, "SNS not configured"),
+/// Clanopedia-side metadata edits layered over Blueband's immutable document metadata
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct DocumentMetadataOverlay {
+    pub title: Option<String>,
+    pub tags: Option<Vec<String>>,
+    pub author: Option<String>,
+    pub summary: Option<String>,
+    pub license: Option<License>, // Overrides the collection's default license
+    pub slug: Option<String>,       // Set through SetSlug proposals
+    pub updated_at: u64,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct DocumentView {
+    pub metadata: DocumentMetadata,
+    pub tags: Option<Vec<String>>,
+    pub author: Option<String>,
+    pub summary: Option<String>,
+    pub license: Option<License>,
+    pub slug: Option<String>,
+    pub reactions: Vec<ReactionCount>,
+}
+
+/// A passage of a document as Blueband chunked it; `chunk_id` is the one search matches
+/// carry, and the character range locates the passage in the document's content
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct DocumentChunk {
+    pub chunk_id: String,
+    pub position: u32,
+    pub text: String,
+    pub char_start: u64,
+    pub char_end: u64,
+    pub token_count: Option<u32>,
+    pub embedded: bool, // Whether searches can match it yet
+}
+
+/// A page of a document's chunks in reading order
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct DocumentChunkPage {
+    pub document_id: DocumentId,
+    pub chunks: Vec<DocumentChunk>,
+    pub total: u64,
+}
+
+/// Outgoing links of one document, keyed by normalized target name
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct DocumentLinks {
+    pub title: String,
+    pub key: String,
+    pub outgoing: Vec<String>,
+    pub updated_at: u64,
+}
+
+/// A heading in a document, with its char offset into the stored content
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct OutlineEntry {
+    pub level: u8,
+    pub title: String,
+    pub offset: u64,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct DocumentOutline {
+    pub entries: Vec<OutlineEntry>,
+}
+
+/// A fenced code block; `offset` and `length` are in chars and cover the code between the
+/// fences
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct CodeBlock {
+    pub language: Option<String>,
+    pub offset: u64,
+    pub length: u64,
+}
+
+/// A markdown or `[[wiki-style]]` link, at the char offset of its opening bracket
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ContentLink {
+    pub text: String,
+    pub target: String,
+    pub offset: u64,
+    pub external: bool, // A URL rather than another document
+}
+
+/// The markdown structure of a document's content, parsed on its first read and cached
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ContentStructure {
+    pub headings: Vec<OutlineEntry>,
+    pub code_blocks: Vec<CodeBlock>,
+    pub links: Vec<ContentLink>,
+    pub parsed_at: u64,
+}
+
+/// A document's raw content with its parsed structure; encrypted collections get none
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct RenderedDocument {
+    pub content: String,
+    pub structure: Option<ContentStructure>,
+}
+
+/// Where and when an extracted document's content was retrieved, recorded at extraction
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct DocumentProvenance {
+    pub source_url: Option<String>, // The URL actually fetched; None for uploads
+    pub retrieved_at: u64,
+    pub http_status: Option<u16>,
+    pub headers: Vec<HttpHeader>, // Content headers that survived the outcall transform
+    pub extractor: String,        // e.g. "github", "google-docs", "pdf"
+    pub extractor_version: String,
+    pub retries: Option<u32>, // Outcalls repeated after transient failures; None for uploads
+    pub mirror: Option<String>, // Origin of the mirror that served it; None for the source
+}
+
+/// A document shown in one collection while its content stays in another's Blueband index
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct CrossPost {
+    pub source_collection: CollectionId,
+    pub linked_at: u64,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct LinkNode {
+    pub document_id: DocumentId,
+    pub title: String,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct LinkEdge {
+    pub source: DocumentId,
+    pub target: Option<DocumentId>, // None while no document matches target_name
+    pub target_name: String,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct LinkGraph {
+    pub nodes: Vec<LinkNode>,
+    pub edges: Vec<LinkEdge>,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct VoterCount {
+    pub voter: Principal,
+    pub votes: u64,
+}
+
+/// Per-collection counters for one week, keyed by week index
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct WeeklyRollup {
+    pub week_start: u64,
+    pub documents_added: u64,
+    pub proposals_created: u64,
+    pub searches: u64,
+    pub votes: Vec<VoterCount>,
+    pub outcall_retries: Option<u64>, // None in weeks recorded before retries were counted
+}
+
+/// Time range in nanoseconds; `end` defaults to now
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct AnalyticsRange {
+    pub start: u64,
+    pub end: Option<u64>,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct AnalyticsBucket {
+    pub week_start: u64,
+    pub documents_added: u64,
+    pub proposals_created: u64,
+    pub votes: u64,
+    pub searches: u64,
+    pub outcall_retries: u64,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct CollectionAnalytics {
+    pub collection_id: CollectionId,
+    pub buckets: Vec<AnalyticsBucket>,
+    pub documents_added: u64,
+    pub proposals_created: u64,
+    pub votes: u64,
+    pub searches: u64,
+    pub outcall_retries: u64,
+    pub votes_by_voter: Vec<VoterCount>,
+}
+
+/// Logged search query; searchers are kept only as truncated principal hashes
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct QueryLogEntry {
+    pub query: String,
+    pub count: u64,
+    pub zero_result_count: u64,
+    pub searcher_hashes: Vec<String>,
+    pub last_searched_at: u64,
+}
+
+/// Days counted by `get_trending_documents`, ending today
+#[derive(CandidType, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TrendingWindow {
+    Day,
+    Week,
+    Month,
+}
+
+impl TrendingWindow {
+    pub fn days(self) -> u64 {
+        match self {
+            TrendingWindow::Day => 1,
+            TrendingWindow::Week => 7,
+            TrendingWindow::Month => 30,
+        }
+    }
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct TrendingDocument {
+    pub document_id: DocumentId,
+    pub views: u64, // Within the window
+    pub total_views: u64,
+    pub reactions: Vec<ReactionCount>,
+}
+
+/// A document in a collection's curated "start here" set
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct PinnedDocument {
+    pub document_id: DocumentId,
+    pub pinned_at: u64,
+    pub proposal_id: ProposalId, // The SetPinnedDocuments proposal that first pinned it
+}
+
+/// A reaction readers leave on a document or proposal; each principal gives each kind once
+#[derive(CandidType, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Reaction {
+    ThumbsUp,
+    ThumbsDown,
+    Star,
+    Heart,
+    Confused,
+    Eyes,
+}
+
+impl Reaction {
+    pub const ALL: [Reaction; 6] = [
+        Reaction::ThumbsUp,
+        Reaction::ThumbsDown,
+        Reaction::Star,
+        Reaction::Heart,
+        Reaction::Confused,
+        Reaction::Eyes,
+    ];
+
+    /// Position in `ALL`, which stored counters and per-principal bits follow
+    pub fn index(self) -> usize {
+        self as usize
+    }
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum ReactionTarget {
+    Document { document_id: DocumentId },
+    Proposal { proposal_id: ProposalId },
+}
+
+/// Stored reaction counters of one target, indexed like `Reaction::ALL`
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct ReactionCounts {
+    pub counts: Vec<u32>,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ReactionCount {
+    pub reaction: Reaction,
+    pub count: u32,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct ReactionSummary {
+    pub target: ReactionTarget,
+    pub counts: Vec<ReactionCount>, // Only reactions given at least once
+    pub mine: Vec<Reaction>,        // The caller's own
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct QueryStat {
+    pub query: String,
+    pub count: u64,
+    pub zero_result_count: u64,
+    pub unique_searchers: u64,
+    pub last_searched_at: u64,
+}
+
+/// A collection secret sealed under the canister secret
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct SealedSecret {
+    pub ciphertext: Vec<u8>,
+    pub updated_at: u64,
+    pub updated_by: Principal,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct SecretInfo {
+    pub name: String,
+    pub updated_at: u64,
+    pub updated_by: Principal,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct DashboardCounts {
+    pub admins: u32,
+    pub readers: u32,
+    pub proposals: u64,
+    pub active_proposals: u32,
+    pub documents_added: u64,
+    pub votes: u64,
+    pub searches: u64,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct ProposalSummary {
+    pub id: ProposalId,
+    pub proposal_type: ProposalType,
+    pub description: String,
+    pub creator: Principal,
+    pub created_at: u64,
+    pub expires_at: u64,
+    pub yes_votes: u32,
+    pub no_votes: u32,
+    pub threshold: u32,
+    pub depends_on: Vec<ProposalDependency>,
+    pub review: Option<ReviewStatus>,
+    pub reactions: Vec<ReactionCount>,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum DependencyStatus {
+    Executed,
+    Pending(ProposalStatus),
+    Unavailable, // Rejected, expired or unknown; the dependent proposal cannot execute
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct ProposalDependency {
+    pub proposal_id: ProposalId,
+    pub status: DependencyStatus,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum CollectionEventKind {
+    ProposalCreated,
+    ProposalExecuted,
+    ProposalExpired,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct CollectionEvent {
+    pub kind: CollectionEventKind,
+    pub proposal_id: ProposalId,
+    pub timestamp: u64,
+}
+
+/// Embedding progress; Blueband fields are `None` when its metrics could not be read
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct EmbeddingStatus {
+    pub document_count: Option<u64>,
+    pub search_count: Option<u64>,
+    pub blueband_error: Option<String>,
+    pub pending_items: u32,
+    pub failed_items: u32,
+    pub proxy_url: Option<String>,
+}
+
+/// Everything a collection page needs to render, in one call
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct CollectionDashboard {
+    pub collection: Collection, // proposals are summarized separately and left empty here
+    pub counts: DashboardCounts,
+    pub active_proposals: Vec<ProposalSummary>,
+    pub recent_events: Vec<CollectionEvent>,
+    pub embedding: EmbeddingStatus,
+    pub cycles: crate::cycles::CyclesStatus,
+    pub dependencies: Vec<DependencyHealth>, // Cached; empty until a health check has run
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DependencyKind {
+    Blueband,
+    TokenLedger,
+    SnsGovernance,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct DependencyHealth {
+    pub kind: DependencyKind,
+    pub canister_id: Principal,
+    pub healthy: bool,
+    pub latency_nanos: u64, // Includes the consensus rounds the call spanned
+    pub error: Option<String>,
+    pub checked_at: u64,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct ExternalHealth {
+    pub dependencies: Vec<DependencyHealth>,
+    pub checked_at: u64,
+}
+
+/// Settings and usage as reported by the management canister
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct CanisterRuntimeStatus {
+    pub status: String,
+    pub memory_size: Nat,
+    pub module_hash: Option<String>, // Hex-encoded
+    pub freezing_threshold: Nat,
+    pub cycles: Nat,
+    pub idle_cycles_burned_per_day: Nat,
+    pub controllers: Vec<Principal>,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct SelfStatus {
+    pub canister_id: Principal,
+    pub runtime: Option<CanisterRuntimeStatus>, // None unless the canister controls itself
+    pub runtime_error: Option<String>,
+    pub cycles_balance: u64,
+    pub stable_memory_pages: u64,
+    pub collections: u64,
+    pub proposals: u64,
+    pub active_proposals: u64,
+    pub blueband_configured: bool,
+    pub external_health: Option<ExternalHealth>, // Cached from the last check_external_health
+}
+
+/// Stable maps covered by storage accounting
+#[derive(CandidType, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StorageMap {
+    Collections,
+    Proposals,
+    DocumentOverlays,
+    DocumentLinks,
+    DocumentOutlines,
+    WeeklyRollups,
+    QueryLog,
+    ProxyAllowlist,
+    ExtractionProgress,
+    CollectionSecrets,
+    KeywordPostings,
+    KeywordDocuments,
+    TrashedDocuments,
+    VoteReceipts,
+    CreationRequests,
+    EventLog,
+    ExtractionJobs,
+    JobDocuments,
+    Notifications,
+    DocumentNfts,
+    ChangeLog,
+    ProposalAttachments,
+    AttachmentChunks,
+    FetchCache,
+    FetchCacheBodies,
+    DocumentChecksums,
+    DocumentProvenance,
+    CrossPosts,
+    ExecutedProposals,
+    ExternalCalls,
+    Logs,
+    JobOutcomes,
+    Bookmarks,
+    DocumentViews,
+    DailyViews,
+    SitemapEntries,
+    CollectionSlugs,
+    DocumentSlugs,
+    ReactionCounts,
+    Reactions,
+    GovernanceSubscribers,
+    ChatBots,
+    BotDelegations,
+    Activity,
+    DocumentSizes,
+    PinnedDocuments,
+    QueryAliases,
+    ProposalIndex,
+    ExecutionReceipts,
+    ArchivedDocuments,
+    ContentStructures,
+    ConfigHistory,
+    PendingConfigChanges,
+    CallAllowlist,
+    UnsettledBonds,
+}
+
+impl StorageMap {
+    pub const ALL: [StorageMap; 55] = [
+        StorageMap::Collections,
+        StorageMap::Proposals,
+        StorageMap::DocumentOverlays,
+        StorageMap::DocumentLinks,
+        StorageMap::DocumentOutlines,
+        StorageMap::WeeklyRollups,
+        StorageMap::QueryLog,
+        StorageMap::ProxyAllowlist,
+        StorageMap::ExtractionProgress,
+        StorageMap::CollectionSecrets,
+        StorageMap::KeywordPostings,
+        StorageMap::KeywordDocuments,
+        StorageMap::TrashedDocuments,
+        StorageMap::VoteReceipts,
+        StorageMap::CreationRequests,
+        StorageMap::EventLog,
+        StorageMap::ExtractionJobs,
+        StorageMap::JobDocuments,
+        StorageMap::Notifications,
+        StorageMap::DocumentNfts,
+        StorageMap::ChangeLog,
+        StorageMap::ProposalAttachments,
+        StorageMap::AttachmentChunks,
+        StorageMap::FetchCache,
+        StorageMap::FetchCacheBodies,
+        StorageMap::DocumentChecksums,
+        StorageMap::DocumentProvenance,
+        StorageMap::CrossPosts,
+        StorageMap::ExecutedProposals,
+        StorageMap::ExternalCalls,
+        StorageMap::Logs,
+        StorageMap::JobOutcomes,
+        StorageMap::Bookmarks,
+        StorageMap::DocumentViews,
+        StorageMap::DailyViews,
+        StorageMap::SitemapEntries,
+        StorageMap::CollectionSlugs,
+        StorageMap::DocumentSlugs,
+        StorageMap::ReactionCounts,
+        StorageMap::Reactions,
+        StorageMap::GovernanceSubscribers,
+        StorageMap::ChatBots,
+        StorageMap::BotDelegations,
+        StorageMap::Activity,
+        StorageMap::DocumentSizes,
+        StorageMap::PinnedDocuments,
+        StorageMap::QueryAliases,
+        StorageMap::ProposalIndex,
+        StorageMap::ExecutionReceipts,
+        StorageMap::ArchivedDocuments,
+        StorageMap::ContentStructures,
+        StorageMap::ConfigHistory,
+        StorageMap::PendingConfigChanges,
+        StorageMap::CallAllowlist,
+        StorageMap::UnsettledBonds,
+    ];
+
+    pub fn memory_id(self) -> u8 {
+        match self {
+            StorageMap::Collections => memory::COLLECTIONS,
+            StorageMap::Proposals => memory::PROPOSALS,
+            StorageMap::DocumentOverlays => memory::DOCUMENT_OVERLAYS,
+            StorageMap::DocumentLinks => memory::DOCUMENT_LINKS,
+            StorageMap::DocumentOutlines => memory::DOCUMENT_OUTLINES,
+            StorageMap::WeeklyRollups => memory::WEEKLY_ROLLUPS,
+            StorageMap::QueryLog => memory::QUERY_LOG,
+            StorageMap::ProxyAllowlist => memory::PROXY_ALLOWLIST,
+            StorageMap::ExtractionProgress => memory::EXTRACTION_PROGRESS,
+            StorageMap::CollectionSecrets => memory::COLLECTION_SECRETS,
+            StorageMap::KeywordPostings => memory::KEYWORD_POSTINGS,
+            StorageMap::KeywordDocuments => memory::KEYWORD_DOCUMENTS,
+            StorageMap::TrashedDocuments => memory::TRASHED_DOCUMENTS,
+            StorageMap::VoteReceipts => memory::VOTE_RECEIPTS,
+            StorageMap::CreationRequests => memory::CREATION_REQUESTS,
+            StorageMap::EventLog => memory::EVENT_LOG,
+            StorageMap::ExtractionJobs => memory::EXTRACTION_JOBS,
+            StorageMap::JobDocuments => memory::JOB_DOCUMENTS,
+            StorageMap::Notifications => memory::NOTIFICATIONS,
+            StorageMap::DocumentNfts => memory::DOCUMENT_NFTS,
+            StorageMap::ChangeLog => memory::CHANGE_LOG,
+            StorageMap::ProposalAttachments => memory::PROPOSAL_ATTACHMENTS,
+            StorageMap::AttachmentChunks => memory::ATTACHMENT_CHUNKS,
+            StorageMap::FetchCache => memory::FETCH_CACHE,
+            StorageMap::FetchCacheBodies => memory::FETCH_CACHE_BODIES,
+            StorageMap::DocumentChecksums => memory::DOCUMENT_CHECKSUMS,
+            StorageMap::DocumentProvenance => memory::DOCUMENT_PROVENANCE,
+            StorageMap::CrossPosts => memory::CROSS_POSTS,
+            StorageMap::ExecutedProposals => memory::EXECUTED_PROPOSALS,
+            StorageMap::ExternalCalls => memory::EXTERNAL_CALLS,
+            StorageMap::Logs => memory::LOGS,
+            StorageMap::JobOutcomes => memory::JOB_OUTCOMES,
+            StorageMap::Bookmarks => memory::BOOKMARKS,
+            StorageMap::DocumentViews => memory::DOCUMENT_VIEWS,
+            StorageMap::DailyViews => memory::DAILY_VIEWS,
+            StorageMap::SitemapEntries => memory::SITEMAP_ENTRIES,
+            StorageMap::CollectionSlugs => memory::COLLECTION_SLUGS,
+            StorageMap::DocumentSlugs => memory::DOCUMENT_SLUGS,
+            StorageMap::ReactionCounts => memory::REACTION_COUNTS,
+            StorageMap::Reactions => memory::REACTIONS,
+            StorageMap::GovernanceSubscribers => memory::GOVERNANCE_SUBSCRIBERS,
+            StorageMap::ChatBots => memory::CHAT_BOTS,
+            StorageMap::BotDelegations => memory::BOT_DELEGATIONS,
+            StorageMap::Activity => memory::ACTIVITY,
+            StorageMap::DocumentSizes => memory::DOCUMENT_SIZES,
+            StorageMap::PinnedDocuments => memory::PINNED_DOCUMENTS,
+            StorageMap::QueryAliases => memory::QUERY_ALIASES,
+            StorageMap::ProposalIndex => memory::PROPOSAL_INDEX,
+            StorageMap::ExecutionReceipts => memory::EXECUTION_RECEIPTS,
+            StorageMap::ArchivedDocuments => memory::ARCHIVED_DOCUMENTS,
+            StorageMap::ContentStructures => memory::CONTENT_STRUCTURES,
+            StorageMap::ConfigHistory => memory::CONFIG_HISTORY,
+            StorageMap::PendingConfigChanges => memory::PENDING_CONFIG_CHANGES,
+            StorageMap::CallAllowlist => memory::CALL_ALLOWLIST,
+            StorageMap::UnsettledBonds => memory::UNSETTLED_BONDS,
         }
     }
 }
 
-impl From<ClanopediaError> for String {
-    fn from(err: ClanopediaError) -> String {
-        err.to_string()
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct MapCap {
+    pub map: StorageMap,
+    pub max_entries: u64,
+}
+
+/// Limits checked before writes that grow stable memory; maps without a cap are only bound
+/// by the overall budget
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct StorageCaps {
+    pub max_stable_memory_bytes: Option<u64>,
+    pub map_caps: Vec<MapCap>,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct MapUsage {
+    pub map: StorageMap,
+    pub memory_id: u8,
+    pub entries: u64,
+    pub allocated_bytes: u64, // Pages the map's virtual memories have grown to; never shrinks
+    pub max_entries: Option<u64>,
+    pub shards: Vec<ShardUsage>, // Empty unless the map is split over several memories
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct ShardUsage {
+    pub shard: u32,
+    pub memory_id: u8,
+    pub entries: u64,
+    pub allocated_bytes: u64,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct StorageReport {
+    pub maps: Vec<MapUsage>,
+    pub stable_memory_bytes: u64,
+    pub max_stable_memory_bytes: Option<u64>,
+    pub shards_rebalanced: bool, // Whether entries written before sharding reached their shard
+}
+
+/// Progress moving entries written before sharding out of shard 0
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct ShardRebalance {
+    pub map: Option<StorageMap>, // The map being rebalanced; unset once every map is done
+    pub after_key: Option<String>,
+    pub moved: u64,
+}
+
+impl Default for ShardRebalance {
+    fn default() -> Self {
+        Self {
+            map: Some(StorageMap::KeywordDocuments),
+            after_key: None,
+            moved: 0,
+        }
+    }
+}
+
+/// Progress indexing the proposals stored before proposal search existed
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, Default)]
+pub struct ProposalIndexBackfill {
+    pub after_key: Option<String>,
+    pub indexed: u64,
+    pub complete: bool,
+}
+
+/// Stored content hashes of a collection re-checked against what Blueband now holds
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, Default)]
+pub struct IntegrityReport {
+    pub checked: u32,
+    pub verified: u32,
+    pub mismatched: Vec<DocumentId>,
+    pub missing: Vec<DocumentId>, // Blueband no longer has the document or its content
+    pub failed: Vec<DocumentId>,  // The content could not be read; try again later
+    pub unrecorded: u32,          // Known documents added before checksums were stored
+}
+
+/// Token payment collected from whoever creates a collection, with ICRC-2 approve and pull
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct CreationFee {
+    pub ledger: Principal,
+    pub amount: Nat,
+    pub treasury: Option<Principal>, // Fees are forwarded here; kept by the canister if unset
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum CreationMode {
+    Open,
+    Allowlist, // Only principals on `CreationPolicy::allowlist`
+    FeeGated(CreationFee),
+    ProposalGated, // Requests wait for a controller or the root governance principal
+}
+
+/// Canister-wide rules for creating collections, managed by controllers and the root
+/// governance principal. Controllers are exempt from all of them.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct CreationPolicy {
+    pub mode: CreationMode,
+    pub allowlist: Vec<Principal>,
+    pub max_collections_per_principal: Option<u32>,
+    pub root_governance: Option<Principal>,
+}
+
+impl Default for CreationPolicy {
+    fn default() -> Self {
+        Self {
+            mode: CreationMode::Open,
+            allowlist: Vec::new(),
+            max_collections_per_principal: None,
+            root_governance: None,
+        }
+    }
+}
+
+/// Hourly HTTP outcall allowances for URL extraction, managed by controllers. Allowances
+/// refill continuously, so a collection may spend its hour's worth in a burst and then waits.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct OutcallBudget {
+    pub canister_per_hour: u32,
+    pub collection_per_hour: u32,
+    pub collection_overrides: Vec<(CollectionId, u32)>, // Replace collection_per_hour
+}
+
+impl Default for OutcallBudget {
+    fn default() -> Self {
+        Self {
+            canister_per_hour: 600,
+            collection_per_hour: 120,
+            collection_overrides: Vec::new(),
+        }
+    }
+}
+
+impl OutcallBudget {
+    pub fn collection_limit(&self, collection_id: &str) -> u32 {
+        self.collection_overrides
+            .iter()
+            .find(|(id, _)| id == collection_id)
+            .map_or(self.collection_per_hour, |(_, limit)| *limit)
+    }
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct OutcallBudgetStatus {
+    pub budget: OutcallBudget,
+    pub canister_available: u32,
+    pub collection_limit: Option<u32>, // Set when the status was asked for a collection
+    pub collection_available: Option<u32>,
+}
+
+/// Gateways tried in order for `ipfs://` and `ar://` content, managed by controllers. Each
+/// entry is an https origin such as `https://ipfs.io`.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct PermawebGateways {
+    pub ipfs: Vec<String>,
+    pub arweave: Vec<String>,
+}
+
+impl Default for PermawebGateways {
+    fn default() -> Self {
+        Self {
+            ipfs: vec!["https://ipfs.io".to_string(), "https://dweb.link".to_string()],
+            arweave: vec!["https://arweave.net".to_string(), "https://ar-io.net".to_string()],
+        }
+    }
+}
+
+/// Alternate origins for repository files, tried in order when the file's own host cannot
+/// be reached; managed by controllers. Each entry is an https URL template over `{owner}`,
+/// `{repo}`, `{ref}` and `{path}`, such as
+/// `https://cdn.jsdelivr.net/gh/{owner}/{repo}@{ref}/{path}`.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct OutcallMirrors {
+    pub github: Vec<String>,
+    pub gitlab: Vec<String>,
+    pub bitbucket: Vec<String>,
+}
+
+impl Default for OutcallMirrors {
+    fn default() -> Self {
+        Self {
+            github: vec!["https://cdn.jsdelivr.net/gh/{owner}/{repo}@{ref}/{path}".to_string()],
+            gitlab: Vec::new(),
+            bitbucket: Vec::new(),
+        }
+    }
+}
+
+/// Where the frontend is served and what it is called, for the sitemap and the OpenSearch
+/// description; managed by controllers. Without a URL, pages are linked on the canister's
+/// own HTTP origin.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct SiteConfig {
+    pub site_url: Option<String>, // An https origin, optionally with a path
+    pub site_name: String,
+}
+
+impl Default for SiteConfig {
+    fn default() -> Self {
+        Self {
+            site_url: None,
+            site_name: "Clanopedia".to_string(),
+        }
+    }
+}
+
+/// A document's page as listed in the sitemap, kept in step with the keyword index
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct SitemapEntry {
+    pub path: String, // Percent-encoded, relative to the collection's page
+    pub updated_at: Option<u64>, // None for documents indexed before the sitemap existed
+}
+
+/// A request from the HTTP gateway
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct GatewayRequest {
+    pub method: String,
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+#[derive(CandidType, Serialize, Clone, Debug)]
+pub struct GatewayResponse {
+    pub status_code: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+/// The node count HTTPS outcalls are priced for: set by a controller, else detected from
+/// what earlier outcalls were charged, else 13
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct SubnetSize {
+    pub configured: Option<u32>,
+    pub detected: Option<u32>,
+    pub effective: u32,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum CreationRequestStatus {
+    Pending,
+    Approved(CollectionId),
+    Rejected(String),
+}
+
+/// A collection waiting for approval under `CreationMode::ProposalGated`
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct CreationRequest {
+    pub id: u64,
+    pub requester: Principal,
+    pub config: CollectionConfig,
+    pub requested_at: u64,
+    pub status: CreationRequestStatus,
+    pub decided_by: Option<Principal>,
+    pub sealed_proxy_api_key: Option<Vec<u8>>, // Taken out of the config
+}
+
+/// A collection whose creation has started but not finished on both Clanopedia and Blueband.
+/// Recorded before either side is written and removed once both have the collection or
+/// neither does.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct PendingCreation {
+    pub collection_id: CollectionId,
+    pub creator: Principal,
+    pub started_at: u64,
+}
+
+/// What a reconciliation pass did with half-created collections
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, Default)]
+pub struct CreationReconciliation {
+    pub completed: Vec<CollectionId>,   // Both sides existed; the local record was linked
+    pub rolled_back: Vec<CollectionId>, // Whichever side existed was deleted
+    pub pending: Vec<PendingCreation>,  // Still in progress, or the repair failed
+}
+
+/// What an operator may do in a collection on its admins' behalf
+#[derive(CandidType, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Capability {
+    Extract,        // Run and manage extractions, using the collection's secrets
+    AddDocuments,   // Add documents and embed single documents
+    TrashDocuments, // Move documents to the trash and restore them
+    ViewAnalytics,  // Query logs, integrity checks and the external call log
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct OperatorGrant {
+    pub principal: Principal,
+    pub capabilities: Vec<Capability>,
+    pub granted_by: Principal,
+    pub granted_at: u64,
+    pub expires_at: u64,
+}
+
+/// Set by an executed ArchiveCollection proposal; the collection stays searchable
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ArchiveInfo {
+    pub archived_at: u64,
+    pub proposal_id: ProposalId,
+}
+
+/// One row of the controller console's collection listing
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct CollectionAdminSummary {
+    pub collection_id: CollectionId,
+    pub name: String,
+    pub creator: Principal,
+    pub admins: Vec<Principal>,
+    pub blueband_collection_id: String,
+    pub cycles_balance: u64,
+    pub proposal_count: u64,
+    pub indexed_documents: u64,
+    pub created_at: u64,
+    pub updated_at: u64,
+    pub trashed: bool,
+    pub paused: Option<PauseInfo>,
+    pub archived: bool,
+}
+
+/// Entries removed from one map by `admin_purge_orphaned_entries`
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct MapPurge {
+    pub map: StorageMap,
+    pub removed: u64,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum EventKind {
+    BluebandCanisterChanged {
+        previous: Option<Principal>,
+        new: Principal,
+    },
+    BluebandRepaired {
+        repair: BluebandRepair,
+    },
+    BackupRestored {
+        backup_id: u64,
+        records: u64,
+    },
+}
+
+/// A Blueband collection this canister administers that no local collection links to
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct BluebandOrphan {
+    pub blueband_collection_id: String,
+    pub name: String,
+    pub created_at: u64,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum BluebandLinkProblem {
+    Unlinked,      // The collection has no Blueband collection ID
+    RemoteMissing, // Blueband has no collection under its ID
+}
+
+/// A local collection that does not reach a Blueband collection
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct BrokenBluebandLink {
+    pub collection_id: CollectionId,
+    pub blueband_collection_id: String,
+    pub problem: BluebandLinkProblem,
+    pub relink_candidate: Option<String>, // An orphan with the collection's own ID
+}
+
+/// Local collections compared with the Blueband collections this canister administers
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct BluebandReconciliation {
+    pub checked_at: u64,
+    pub remote_collections: u32,
+    pub orphans: Vec<BluebandOrphan>,
+    pub broken_links: Vec<BrokenBluebandLink>,
+}
+
+/// A fix for a finding of `reconcile_with_blueband`
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum BluebandRepair {
+    // Point a local collection at an orphan
+    Relink {
+        collection_id: CollectionId,
+        blueband_collection_id: String,
+    },
+    // Create a local collection for an orphan, governed by its Blueband admins
+    Import { blueband_collection_id: String },
+    // Delete an orphan from Blueband, with its documents
+    DeleteRemote { blueband_collection_id: String },
+}
+
+/// The latest reconciliation and how often the timer repeats it
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, Default)]
+pub struct BluebandReconcileState {
+    pub interval_hours: Option<u32>, // Not run by the timer if unset
+    pub last_attempt_at: u64,
+    pub last: Option<BluebandReconciliation>,
+}
+
+/// State that scheduled backups copy to the backup target
+#[derive(CandidType, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BackupSection {
+    Collections,
+    Proposals,
+    DocumentOverlays,
+    CollectionSlugs,
+    DocumentSlugs,
+    KeywordDocuments,
+    KeywordPostings,
+}
+
+impl BackupSection {
+    pub const ALL: [BackupSection; 7] = [
+        BackupSection::Collections,
+        BackupSection::Proposals,
+        BackupSection::DocumentOverlays,
+        BackupSection::CollectionSlugs,
+        BackupSection::DocumentSlugs,
+        BackupSection::KeywordDocuments,
+        BackupSection::KeywordPostings,
+    ];
+
+    pub fn index(self) -> usize {
+        BackupSection::ALL
+            .iter()
+            .position(|s| *s == self)
+            .unwrap_or_default()
+    }
+}
+
+/// Where backups and archived documents go. A backup canister implements
+/// `store_backup_chunk(BackupChunk)` and `get_backup_chunk(nat64, nat32) -> (opt BackupChunk)`,
+/// and for archiving `store_archived_document(text, blob)` and
+/// `get_archived_document(text) -> (opt blob)`; an asset canister must let this canister
+/// commit assets.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum BackupTarget {
+    Canister(Principal),
+    AssetCanister(Principal),
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct BackupConfig {
+    pub target: BackupTarget,
+    pub interval_hours: u32,
+}
+
+/// An entry that changed since the previous backup; `value` is unset if it was deleted
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct BackupRecord {
+    pub section: BackupSection,
+    pub key: String,
+    pub value: Option<Vec<u8>>, // The entry as kept in stable memory
+}
+
+/// One call's worth of a backup. Chunks of a backup are numbered from 0; the last one may
+/// be empty.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct BackupChunk {
+    pub source: Principal,
+    pub backup_id: u64,
+    pub index: u32,
+    pub records: Vec<BackupRecord>,
+    pub last: bool,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum BackupStatus {
+    Running,
+    Completed,
+    Failed { error: String }, // The chunks sent before the failure still count
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct BackupSummary {
+    pub id: u64,
+    pub target: BackupTarget,
+    pub full: bool, // Every entry, rather than the changes since the previous backup
+    pub started_at: u64,
+    pub finished_at: Option<u64>,
+    pub status: BackupStatus,
+    pub chunks: u32,
+    pub records: u64,
+    pub bytes: u64,
+}
+
+/// How far the running backup got; the timer continues it
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct BackupRun {
+    pub id: u64,
+    pub section: u32, // Index into `BackupSection::ALL`; past the end when scanning deletions
+    pub after_key: Option<String>,
+    pub chunk: Vec<BackupRecord>,
+    pub chunk_bytes: u64,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, Default)]
+pub struct BackupState {
+    pub config: Option<BackupConfig>,
+    pub last_started_at: u64,
+    pub next_id: u64,
+    pub running: Option<BackupRun>,
+}
+
+/// One entry of the canister-wide event log
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct EventLogEntry {
+    pub sequence: u64,
+    pub timestamp: u64,
+    pub actor: Principal,
+    pub kind: EventKind,
+}
+
+/// A canister-level setting with the value it was set to
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub enum ConfigSetting {
+    BluebandCanister(Principal),
+    CreationPolicy(CreationPolicy),
+    OutcallBudget(OutcallBudget),
+    StorageCaps(StorageCaps),
+    BackupConfig(Option<BackupConfig>),
+    BluebandReconcileInterval(Option<u32>),
+    LogLevel(LogLevel),
+    SubnetSize(Option<u32>),
+    PermawebGateways(PermawebGateways),
+    OutcallMirrors(OutcallMirrors),
+    SiteConfig(SiteConfig),
+    CallAllowlist(Vec<CallAllowlistEntry>),
+    ProxyAllowlist(Vec<String>),
+    ReadReplicas(Vec<Principal>),
+}
+
+/// One version of the canister configuration. Changes form a hash chain; see
+/// `config_history` for how the hashes are built.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct ConfigChange {
+    pub version: u64,
+    pub setting: ConfigSetting,
+    pub changed_by: Principal,
+    pub confirmed_by: Option<Principal>, // The second controller of a destructive change
+    pub changed_at: u64,
+    pub value_hash: String,    // Hex SHA-256 of the candid-encoded setting
+    pub change_hash: String,   // Hex SHA-256 over the fields above
+    pub previous_hash: String, // Chain head before this change
+    pub chain_hash: String,    // SHA-256(previous_hash || change_hash)
+}
+
+/// A destructive configuration change waiting for a second controller to confirm it
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct PendingConfigChange {
+    pub id: u64,
+    pub setting: ConfigSetting,
+    pub requested_by: Principal,
+    pub requested_at: u64,
+    pub expires_at: u64,
+}
+
+#[derive(
+    CandidType, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Default,
+)]
+pub enum LogLevel {
+    Debug,
+    #[default]
+    Info,
+    Warn,
+    Error,
+}
+
+/// One entry of the canister log ring buffer
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct LogEntry {
+    pub sequence: u64,
+    pub timestamp: u64,
+    pub level: LogLevel,
+    pub module: String,
+    pub collection_id: Option<CollectionId>,
+    pub message: String,
+}
+
+/// Narrows `get_logs`; unset fields match every entry
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, Default)]
+pub struct LogFilter {
+    pub min_level: Option<LogLevel>,
+    pub module: Option<String>,
+    pub collection_id: Option<CollectionId>,
+}
+
+/// Evidence file attached to a proposal; the bytes are read in chunks
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct ProposalAttachment {
+    pub index: u32,
+    pub name: String,
+    pub content_type: String,
+    pub size: u64,
+    pub sha256: String, // Hex digest of the whole file
+    pub chunk_count: u32,
+    pub uploaded_by: Principal,
+    pub uploaded_at: u64,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct AttachmentChunk {
+    pub attachment: ProposalAttachment,
+    pub chunk_index: u32,
+    pub data: Vec<u8>,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum ChangeKind {
+    CollectionCreated,
+    CollectionUpdated,
+    CollectionDeleted,
+    DocumentAdded { document_id: DocumentId },
+    DocumentUpdated { document_id: DocumentId },
+    DocumentRemoved { document_id: DocumentId }, // Trashed; restoring adds it again
+    ProposalCreated { proposal_id: ProposalId },
+    ProposalStatusChanged {
+        proposal_id: ProposalId,
+        status: ProposalStatus,
+    },
+}
+
+/// A canister allowed to pull snapshots and deltas for serving reads
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct ReadReplica {
+    pub canister: Principal,
+    pub added_by: Principal,
+    pub added_at: u64,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReplicaSection {
+    Collections,      // Keyed by collection ID
+    Proposals,        // Keyed by "collection_id:proposal_id"
+    DocumentOverlays, // Keyed by "collection_id:document_id"
+    IndexedDocuments, // Keyed by "collection_id:document_id"
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub enum ReplicaEntry {
+    Collection(Box<Collection>),
+    Proposal(Box<Proposal>),
+    DocumentOverlay(DocumentMetadataOverlay),
+    IndexedDocument(IndexedDocument),
+}
+
+/// An entry as it now stands, or its removal when `entry` is unset
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct ReplicaRecord {
+    pub section: ReplicaSection,
+    pub key: String,
+    pub entry: Option<ReplicaEntry>,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct ReplicaSnapshotPage {
+    pub records: Vec<ReplicaRecord>,
+    pub next_key: Option<String>, // Pass back as `after_key`; unset after the last page
+    pub cursor: Option<u64>,      // The change sequence the page reflects at least
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct ReplicaDeltaPage {
+    pub records: Vec<ReplicaRecord>,
+    pub cursor: Option<u64>, // Pass back as `since_cursor`
+    pub caught_up: bool,
+}
+
+/// One entry of the public change stream; sequences are contiguous until pruned
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct ChangeRecord {
+    pub sequence: u64,
+    pub timestamp: u64,
+    pub collection_id: CollectionId,
+    pub kind: ChangeKind,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct ChangePage {
+    pub changes: Vec<ChangeRecord>,
+    pub next_sequence: Option<u64>, // Pass back as `since_sequence`; unset when nothing was new
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum NotificationKind {
+    ProposalCreated { proposal_id: String },
+    ProposalApproved { proposal_id: String },
+    ProposalExecuted { proposal_id: String },
+    ProposalFailed { proposal_id: String, error: String },
+    CyclesAlert,
+    QuotaAlert,
+    VoteReminder { proposal_id: String, expires_at: u64 },
+    ReviewRequested { proposal_id: String },
+}
+
+/// A point in a proposal's life other canisters can subscribe to
+#[derive(CandidType, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProposalEvent {
+    Created,
+    Approved,
+    Executed,
+    Failed, // Execution failed, partly failed, or the proposal was vetoed
+    Expired,
+}
+
+/// A canister a collection's admins allow to receive its governance events, with its
+/// subscription once it has made one
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct GovernanceSubscriber {
+    pub canister: Principal,
+    pub allowed_by: Principal,
+    pub allowed_at: u64,
+    pub subscription: Option<GovernanceSubscription>,
+    pub delivered: u64,
+    pub failed_deliveries: u64,
+    pub last_error: Option<String>,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct GovernanceSubscription {
+    pub events: Vec<ProposalEvent>,
+    pub method: String, // Called with one `GovernanceEventNotice`
+    pub subscribed_at: u64,
+}
+
+/// The argument of a one-way call to a subscriber
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct GovernanceEventNotice {
+    pub collection_id: CollectionId,
+    pub proposal_id: ProposalId,
+    pub event: ProposalEvent,
+    pub status: ProposalStatus,
+    pub occurred_at: u64,
+}
+
+/// A chat bot an admin connected to a collection. It signs the votes it relays with a key
+/// issued at registration; voters delegate to it before it can vote for them.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct ChatBot {
+    pub bot: Principal,
+    pub name: String,
+    pub registered_by: Principal,
+    pub registered_at: u64,
+}
+
+/// A voter's permission for a bot to vote on their behalf in one collection
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct BotDelegation {
+    pub voter: Principal,
+    pub bot: Principal,
+    pub granted_at: u64,
+    pub expires_at: u64,
+    pub last_nonce: u64, // Payloads must use a higher nonce, so none can be replayed
+}
+
+/// A vote a bot relays. The signature is HMAC-SHA256 under the bot's key of
+/// `BotVotePayload::signing_text`.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct BotVotePayload {
+    pub bot: Principal,
+    pub voter: Principal,
+    pub collection_id: CollectionId,
+    pub proposal_id: ProposalId,
+    pub vote: Vote,
+    pub nonce: u64,
+    pub expires_at: u64,
+}
+
+impl BotVotePayload {
+    pub fn signing_text(&self) -> String {
+        let vote = match self.vote {
+            Vote::Yes => "yes",
+            Vote::No => "no",
+            Vote::Abstain => "abstain",
+        };
+        format!(
+            "clanopedia-bot-vote\n{}\n{}\n{}\n{}\n{}\n{}\n{}",
+            self.bot,
+            self.voter,
+            self.collection_id,
+            self.proposal_id,
+            vote,
+            self.nonce,
+            self.expires_at
+        )
+    }
+}
+
+/// A search result short enough for a chat message
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct BotSearchHit {
+    pub document_id: DocumentId,
+    pub title: String,
+    pub snippet: String,
+}
+
+/// An active proposal close to expiry, for "needs your vote" prompts
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct ExpiringProposal {
+    pub collection_id: CollectionId,
+    pub collection_name: String,
+    pub proposal_id: ProposalId,
+    pub description: String,
+    pub expires_at: u64,
+    pub pending_admins: Vec<Principal>, // Admins who have not voted
+    pub caller_voted: bool,
+}
+
+/// A call that changed state, in the caller's activity log. Sequences increase per principal.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct ActivityEntry {
+    pub sequence: u64,
+    pub timestamp: u64,
+    pub method: String,
+    pub collection_id: Option<CollectionId>,
+}
+
+/// One entry in a principal's inbox. Ids increase per principal.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct Notification {
+    pub id: u64,
+    pub collection_id: CollectionId,
+    pub kind: NotificationKind,
+    pub message: String,
+    pub created_at: u64,
+    pub read: bool,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct NotificationPage {
+    pub notifications: Vec<Notification>,
+    pub unread_count: u64,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Bookmark {
+    pub collection_id: CollectionId,
+    pub document_id: DocumentId,
+    pub added_at: u64,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ReadingList {
+    pub name: String,
+    pub items: Vec<Bookmark>, // In the order they were added
+    pub created_at: u64,
+}
+
+/// A principal's bookmarks and named reading lists, across collections
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct ReaderBookmarks {
+    pub bookmarks: Vec<Bookmark>,
+    pub reading_lists: Vec<ReadingList>,
+}
+
+/// How far proposals have moved from `Collection::proposals` into their own map
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct MigrationProgress {
+    pub migrated: u64,
+    pub remaining: u64,
+    pub complete: bool,
+}
+
+/// Title and tags of a document as indexed for keyword search
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct IndexedDocument {
+    pub title: String,
+    pub tags: Vec<String>,
+}
+
+/// Documents whose title or tags contain a term
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct KeywordPostings {
+    pub document_ids: Vec<DocumentId>,
+}
+
+/// Where query terms occur in a search hit's chunk. Offsets count characters (Unicode
+/// scalar values) into `snippet`, end exclusive.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct SearchHighlight {
+    pub snippet: String,
+    pub ranges: Vec<HighlightRange>,
+    pub truncated_start: bool, // The snippet starts after the beginning of the chunk
+    pub truncated_end: bool,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct HighlightRange {
+    pub start: u32,
+    pub end: u32,
+}
+
+/// A hybrid search result, ranked by reciprocal rank fusion of vector and keyword ranks
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct HybridMatch {
+    pub document_id: DocumentId,
+    pub document_title: Option<String>,
+    pub score: f64,
+    pub vector_match: Option<VectorMatch>, // Best-ranked chunk, if vector search found the document
+    pub matched_terms: Vec<String>,
+    pub applied_aliases: Vec<AliasExpansion>,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct SearchResult {
+    pub document_id: DocumentId,
+    pub title: String,
+    pub content: String,
+    pub score: f64,
+}
+
+impl fmt::Display for ClanopediaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClanopediaError::NotFound(msg) => write!(f, "Not found: {}", msg),
+            ClanopediaError::NotAuthorized => write!(f, "Not authorized"),
+            ClanopediaError::InvalidProposalState(msg) => {
+                write!(f, "Invalid proposal state: {}", msg)
+            }
+            ClanopediaError::ProposalExpired => write!(f, "Proposal has expired"),
+            ClanopediaError::ThresholdNotMet => write!(f, "Voting threshold not met"),
+            ClanopediaError::InsufficientCycles(msg) => write!(f, "Insufficient cycles: {}", msg),
+            ClanopediaError::ExternalCallError(msg) => write!(f, "External call error: {}", msg),
+            ClanopediaError::StorageError(e) => write!(f, "Storage error: {:?}", e),
+            ClanopediaError::InvalidArgument(msg) => write!(f, "Invalid argument: {}", msg),
+            ClanopediaError::AlreadyExists(msg) => write!(f, "Already exists: {}", msg),
+            ClanopediaError::InvalidOperation(msg) => write!(f, "Invalid operation: {}", msg),
+            ClanopediaError::BluebandError(msg) => write!(f, "Blueband error: {}", msg),
+            ClanopediaError::Unauthorized(msg) => write!(f, "Unauthorized: {}", msg),
+            ClanopediaError::InvalidInput(msg) => write!(f, "Invalid input: {}", msg),
+            ClanopediaError::ProposalAlreadyExecuted => write!(f, "Proposal already executed"),
+            ClanopediaError::SnsError(msg) => write!(f, "SNS error: {}", msg),
+            ClanopediaError::SnsNotConfigured => write!(f, "SNS not configured"),
+            ClanopediaError::RateLimited(msg) => write!(f, "Rate limited: {}", msg),
+            ClanopediaError::ValidationFailed(errors) => {
+                let fields: Vec<String> = errors
+                    .iter()
+                    .map(|e| format!("{} {}", e.field, e.message))
+                    .collect();
+                write!(f, "Validation failed: {}", fields.join("; "))
+            }
+            ClanopediaError::ExecutionInProgress(msg) => write!(f, "Execution in progress: {}", msg),
+            ClanopediaError::ExtractionFailed(e) => write!(f, "Extraction failed: {}", e),
+            ClanopediaError::SourceNotAllowed(violation) => match &violation.rule {
+                Some(rule) => write!(
+                    f,
+                    "Source not allowed: {} matches the deny rule {}",
+                    violation.url, rule
+                ),
+                None => write!(
+                    f,
+                    "Source not allowed: {} matches none of the allowed sources",
+                    violation.url
+                ),
+            },
+        }
+    }
+}
+
+impl ClanopediaError {
+    /// The typed cause, for errors raised by an extractor
+    pub fn extraction_error(&self) -> Option<ExtractionError> {
+        match self {
+            ClanopediaError::ExtractionFailed(e) => Some(e.clone()),
+            _ => None,
+        }
+    }
+}
+
+impl From<ExtractionError> for ClanopediaError {
+    fn from(err: ExtractionError) -> Self {
+        ClanopediaError::ExtractionFailed(err)
+    }
+}
+
+impl From<ClanopediaError> for String {
+    fn from(err: ClanopediaError) -> String {
+        err.to_string()
+    }
+}
+
+impl From<String> for ClanopediaError {
+    fn from(err: String) -> Self {
+        ClanopediaError::NotFound(err)
+    }
+}
+
+impl From<&str> for ClanopediaError {
+    fn from(err: &str) -> Self {
+        ClanopediaError::NotFound(err.to_string())
+    }
+}
+
+impl From<StorageError> for ClanopediaError {
+    fn from(err: StorageError) -> Self {
+        match err {
+            StorageError::NotFound => ClanopediaError::NotFound("Resource not found".to_string()),
+            StorageError::AlreadyExists => {
+                ClanopediaError::AlreadyExists("Resource already exists".to_string())
+            }
+            StorageError::Other(msg) => ClanopediaError::StorageError(msg),
+        }
+    }
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct BluebandDocument {
+    pub id: DocumentId,
+    pub title: String,
+    pub content: String,
+    pub embedded: bool,
+    pub created_at: u64,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct BluebandConfig {
+    pub collection: String,
+    pub api_key: Option<String>,
+}
+
+// Constants
+pub const PROPOSAL_DURATION_NANOS: u64 = 7 * 24 * 60 * 60 * 1_000_000_000; // 7 days
+pub const DEFAULT_EMBED_CONCURRENCY: u32 = 5;
+pub const DEFAULT_VOTE_REMINDER_HOURS: u32 = 24;
+pub const MAX_PROPOSAL_DEPENDENCIES: usize = 10;
+pub const MAX_VOTE_REMINDER_HOURS: u32 = 7 * 24; // The whole voting period
+pub const MAX_TIMELOCK_HOURS: u32 = 30 * 24;
+pub const MAX_CALL_ARG_BYTES: u32 = 64 * 1024;
+pub const MAX_EMBED_CONCURRENCY: u32 = 20;
+pub const DEFAULT_MAX_DOCUMENT_BYTES: u64 = 10 * 1024 * 1024; // Blueband's own limit
+pub const MIN_DOCUMENT_BYTES: u64 = 1024;
+pub const MAX_OUTCALL_RETRIES: u32 = 5;
+pub const MAX_DEFERRED_RETRIES: u32 = 10;
+pub const MAX_RETRY_DELAY_SECS: u32 = 6 * 60 * 60;
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
+pub struct GovernanceModelConfig {
+    pub is_permissionless: bool,
+    pub governance_token: Option<Principal>,
+    pub quorum_threshold: u32,
+    pub threshold: u32,
+}
+
+impl Default for GovernanceModel {
+    fn default() -> Self {
+        GovernanceModel::Permissionless
+    }
+}
+
+#[derive(CandidType, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CyclesStatus {
+    pub clanopedia_balance: u64,
+    pub blueband_balance: u64,
+    pub clanopedia_healthy: bool,
+    pub blueband_healthy: bool,
+}
+
+#[derive(CandidType, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OperationCost {
+    pub base_cost: u64,
+    pub per_doc_cost: u64,
+    pub buffer_percentage: u32,
+}
+
+#[derive(CandidType, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CostMetrics {
+    pub base_cost: u64,
+    pub total_cost: u64,
+    pub per_doc_cost: u64,
+    pub buffer_amount: u64,
+}
+
+impl Storable for Proposal {
+    fn to_bytes(&self) -> std::borrow::Cow<[u8]> {
+        std::borrow::Cow::Owned(candid::encode_one(self).unwrap())
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<[u8]>) -> Self {
+        candid::decode_one(&bytes).unwrap_or_else(|_| Proposal {
+            id: String::new(),
+            collection_id: String::new(),
+            proposal_type: ProposalType::EmbedDocument { documents: vec![] },
+            creator: Principal::anonymous(),
+            description: String::new(),
+            created_at: 0,
+            expires_at: 0,
+            status: ProposalStatus::Active,
+            votes: HashMap::new(),
+            token_votes: HashMap::new(),
+            executed: false,
+            executed_at: None,
+            executed_by: None,
+            threshold: 0,
+            threshold_met: false,
+            sns_proposal_id: None,
+            bond: None,
+            execution_report: None,
+            reminded_at: None,
+            depends_on: None,
+            executable_at: None,
+            review: None,
+            sealed_voting: None,
+            sealed_proxy_api_key: None,
+        })
+    }
+
+    const BOUND: ic_stable_structures::storable::Bound =
+        ic_stable_structures::storable::Bound::Bounded {
+            max_size: 1024 * 1024, // 1MB max size for a proposal
+            is_fixed_size: false,
+        };
+}
+
+impl Storable for Collection {
+    fn to_bytes(&self) -> std::borrow::Cow<[u8]> {
+        std::borrow::Cow::Owned(candid::encode_one(self).unwrap())
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<[u8]>) -> Self {
+        candid::decode_one(&bytes).unwrap_or_else(|_| Collection {
+            id: String::new(),
+            name: String::new(),
+            description: String::new(),
+            creator: Principal::anonymous(),
+            created_at: 0,
+            updated_at: 0,
+            admins: Vec::new(),
+            threshold: 0,
+            governance_token: None,
+            sns_governance_canister: None,
+            governance_model: GovernanceModel::Permissionless,
+            quorum_threshold: 0,
+            is_permissionless: false,
+            blueband_collection_id: String::new(),
+            proposals: HashMap::new(),
+            cycles_balance: 0,
+            proposal_counter: 0,
+            proposal_bond: None,
+            proposer_eligibility: None,
+            embed_concurrency: None,
+            embedding_proxy_url: None,
+            encryption: None,
+            pipeline: None,
+            auto_tag: None,
+            summarization: None,
+            ocr: None,
+            forked_from: None,
+            trashed: None,
+            paused: None,
+            archived: None,
+            vote_reminder_hours: None,
+            timelock_hours: None,
+            custom_proposals: None,
+            call_policy: None,
+            operators: None,
+            nft_minting: None,
+            license: None,
+            review_policy: None,
+            slug: None,
+            content_limits: None,
+            retry_policy: None,
+            spam_protection: None,
+            source_policy: None,
+        })
+    }
+
+    const BOUND: ic_stable_structures::storable::Bound =
+        ic_stable_structures::storable::Bound::Bounded {
+            max_size: 2 * 1024 * 1024, // 2MB max size for a collection
+            is_fixed_size: false,
+        };
+}
+
+impl Storable for DocumentMetadataOverlay {
+    fn to_bytes(&self) -> std::borrow::Cow<'_, [u8]> {
+        std::borrow::Cow::Owned(candid::encode_one(self).unwrap())
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<'_, [u8]>) -> Self {
+        candid::decode_one(&bytes).unwrap_or(DocumentMetadataOverlay {
+            title: None,
+            tags: None,
+            author: None,
+            summary: None,
+            license: None,
+            slug: None,
+            updated_at: 0,
+        })
+    }
+
+    const BOUND: ic_stable_structures::storable::Bound =
+        ic_stable_structures::storable::Bound::Bounded {
+            max_size: MAX_DOCUMENT_OVERLAY_BYTES as u32,
+            is_fixed_size: false,
+        };
+}
+
+impl Storable for DocumentLinks {
+    fn to_bytes(&self) -> std::borrow::Cow<'_, [u8]> {
+        std::borrow::Cow::Owned(candid::encode_one(self).unwrap())
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<'_, [u8]>) -> Self {
+        candid::decode_one(&bytes).unwrap_or(DocumentLinks {
+            title: String::new(),
+            key: String::new(),
+            outgoing: Vec::new(),
+            updated_at: 0,
+        })
+    }
+
+    const BOUND: ic_stable_structures::storable::Bound =
+        ic_stable_structures::storable::Bound::Bounded {
+            max_size: 256 * 1024, // 256KB max size for a document's links
+            is_fixed_size: false,
+        };
+}
+
+impl Storable for DocumentProvenance {
+    fn to_bytes(&self) -> std::borrow::Cow<'_, [u8]> {
+        std::borrow::Cow::Owned(candid::encode_one(self).unwrap())
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<'_, [u8]>) -> Self {
+        candid::decode_one(&bytes).unwrap()
+    }
+
+    const BOUND: ic_stable_structures::storable::Bound =
+        ic_stable_structures::storable::Bound::Bounded {
+            max_size: 8 * 1024,
+            is_fixed_size: false,
+        };
+}
+
+impl Storable for CrossPost {
+    fn to_bytes(&self) -> std::borrow::Cow<'_, [u8]> {
+        std::borrow::Cow::Owned(candid::encode_one(self).unwrap())
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<'_, [u8]>) -> Self {
+        candid::decode_one(&bytes).unwrap()
+    }
+
+    const BOUND: ic_stable_structures::storable::Bound =
+        ic_stable_structures::storable::Bound::Bounded {
+            max_size: 512,
+            is_fixed_size: false,
+        };
+}
+
+impl Storable for ExecutionReceipt {
+    fn to_bytes(&self) -> std::borrow::Cow<'_, [u8]> {
+        std::borrow::Cow::Owned(candid::encode_one(self).unwrap())
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<'_, [u8]>) -> Self {
+        candid::decode_one(&bytes).unwrap()
+    }
+
+    const BOUND: ic_stable_structures::storable::Bound =
+        ic_stable_structures::storable::Bound::Unbounded;
+}
+
+impl Storable for ConfigChange {
+    fn to_bytes(&self) -> std::borrow::Cow<'_, [u8]> {
+        std::borrow::Cow::Owned(candid::encode_one(self).unwrap())
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<'_, [u8]>) -> Self {
+        candid::decode_one(&bytes).unwrap()
+    }
+
+    const BOUND: ic_stable_structures::storable::Bound =
+        ic_stable_structures::storable::Bound::Unbounded;
+}
+
+impl Storable for UnsettledBond {
+    fn to_bytes(&self) -> std::borrow::Cow<'_, [u8]> {
+        std::borrow::Cow::Owned(candid::encode_one(self).unwrap())
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<'_, [u8]>) -> Self {
+        candid::decode_one(&bytes).unwrap()
+    }
+
+    const BOUND: ic_stable_structures::storable::Bound =
+        ic_stable_structures::storable::Bound::Unbounded;
+}
+
+impl Storable for CallAllowlistEntry {
+    fn to_bytes(&self) -> std::borrow::Cow<'_, [u8]> {
+        std::borrow::Cow::Owned(candid::encode_one(self).unwrap())
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<'_, [u8]>) -> Self {
+        candid::decode_one(&bytes).unwrap()
+    }
+
+    const BOUND: ic_stable_structures::storable::Bound =
+        ic_stable_structures::storable::Bound::Unbounded;
+}
+
+impl Storable for PendingConfigChange {
+    fn to_bytes(&self) -> std::borrow::Cow<'_, [u8]> {
+        std::borrow::Cow::Owned(candid::encode_one(self).unwrap())
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<'_, [u8]>) -> Self {
+        candid::decode_one(&bytes).unwrap()
+    }
+
+    const BOUND: ic_stable_structures::storable::Bound =
+        ic_stable_structures::storable::Bound::Unbounded;
+}
+
+impl Storable for ContentStructure {
+    fn to_bytes(&self) -> std::borrow::Cow<'_, [u8]> {
+        std::borrow::Cow::Owned(candid::encode_one(self).unwrap())
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<'_, [u8]>) -> Self {
+        candid::decode_one(&bytes).unwrap()
+    }
+
+    const BOUND: ic_stable_structures::storable::Bound =
+        ic_stable_structures::storable::Bound::Unbounded;
+}
+
+impl Storable for ArchivedDocument {
+    fn to_bytes(&self) -> std::borrow::Cow<'_, [u8]> {
+        std::borrow::Cow::Owned(candid::encode_one(self).unwrap())
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<'_, [u8]>) -> Self {
+        candid::decode_one(&bytes).unwrap()
+    }
+
+    const BOUND: ic_stable_structures::storable::Bound =
+        ic_stable_structures::storable::Bound::Unbounded;
+}
+
+impl Storable for ExternalCallRecord {
+    fn to_bytes(&self) -> std::borrow::Cow<'_, [u8]> {
+        std::borrow::Cow::Owned(candid::encode_one(self).unwrap())
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<'_, [u8]>) -> Self {
+        candid::decode_one(&bytes).unwrap()
+    }
+
+    const BOUND: ic_stable_structures::storable::Bound =
+        ic_stable_structures::storable::Bound::Bounded {
+            max_size: 2048,
+            is_fixed_size: false,
+        };
+}
+
+impl Storable for DocumentOutline {
+    fn to_bytes(&self) -> std::borrow::Cow<'_, [u8]> {
+        std::borrow::Cow::Owned(candid::encode_one(self).unwrap())
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<'_, [u8]>) -> Self {
+        candid::decode_one(&bytes).unwrap_or(DocumentOutline {
+            entries: Vec::new(),
+        })
+    }
+
+    const BOUND: ic_stable_structures::storable::Bound =
+        ic_stable_structures::storable::Bound::Bounded {
+            max_size: 256 * 1024, // 256KB max size for a document's outline
+            is_fixed_size: false,
+        };
+}
+
+impl Storable for WeeklyRollup {
+    fn to_bytes(&self) -> std::borrow::Cow<'_, [u8]> {
+        std::borrow::Cow::Owned(candid::encode_one(self).unwrap())
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<'_, [u8]>) -> Self {
+        candid::decode_one(&bytes).unwrap_or_default()
+    }
+
+    const BOUND: ic_stable_structures::storable::Bound =
+        ic_stable_structures::storable::Bound::Bounded {
+            max_size: 512 * 1024, // 512KB max size for a week's rollup
+            is_fixed_size: false,
+        };
+}
+
+impl Storable for QueryLogEntry {
+    fn to_bytes(&self) -> std::borrow::Cow<'_, [u8]> {
+        std::borrow::Cow::Owned(candid::encode_one(self).unwrap())
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<'_, [u8]>) -> Self {
+        candid::decode_one(&bytes).unwrap_or_default()
+    }
+
+    const BOUND: ic_stable_structures::storable::Bound =
+        ic_stable_structures::storable::Bound::Bounded {
+            max_size: 64 * 1024, // 64KB max size for a logged query
+            is_fixed_size: false,
+        };
+}
+
+impl Storable for SealedSecret {
+    fn to_bytes(&self) -> std::borrow::Cow<'_, [u8]> {
+        std::borrow::Cow::Owned(candid::encode_one(self).unwrap())
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<'_, [u8]>) -> Self {
+        candid::decode_one(&bytes).unwrap_or(SealedSecret {
+            ciphertext: Vec::new(),
+            updated_at: 0,
+            updated_by: Principal::anonymous(),
+        })
+    }
+
+    const BOUND: ic_stable_structures::storable::Bound =
+        ic_stable_structures::storable::Bound::Bounded {
+            max_size: 8 * 1024, // 8KB max size for a sealed secret
+            is_fixed_size: false,
+        };
+}
+
+impl Storable for IndexedDocument {
+    fn to_bytes(&self) -> std::borrow::Cow<'_, [u8]> {
+        std::borrow::Cow::Owned(candid::encode_one(self).unwrap())
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<'_, [u8]>) -> Self {
+        candid::decode_one(&bytes).unwrap_or_default()
+    }
+
+    const BOUND: ic_stable_structures::storable::Bound =
+        ic_stable_structures::storable::Bound::Bounded {
+            max_size: 16 * 1024, // 16KB max size for an indexed title and tags
+            is_fixed_size: false,
+        };
+}
+
+impl Storable for StorageCaps {
+    fn to_bytes(&self) -> std::borrow::Cow<'_, [u8]> {
+        std::borrow::Cow::Owned(candid::encode_one(self).unwrap())
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<'_, [u8]>) -> Self {
+        candid::decode_one(&bytes).unwrap()
+    }
+
+    const BOUND: ic_stable_structures::storable::Bound =
+        ic_stable_structures::storable::Bound::Bounded {
+            max_size: 4 * 1024,
+            is_fixed_size: false,
+        };
+}
+
+impl Storable for TrashInfo {
+    fn to_bytes(&self) -> std::borrow::Cow<'_, [u8]> {
+        std::borrow::Cow::Owned(candid::encode_one(self).unwrap())
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<'_, [u8]>) -> Self {
+        candid::decode_one(&bytes).unwrap()
+    }
+
+    const BOUND: ic_stable_structures::storable::Bound =
+        ic_stable_structures::storable::Bound::Bounded {
+            max_size: 256,
+            is_fixed_size: false,
+        };
+}
+
+impl Storable for KeywordPostings {
+    fn to_bytes(&self) -> std::borrow::Cow<'_, [u8]> {
+        std::borrow::Cow::Owned(candid::encode_one(self).unwrap())
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<'_, [u8]>) -> Self {
+        candid::decode_one(&bytes).unwrap_or_default()
+    }
+
+    const BOUND: ic_stable_structures::storable::Bound =
+        ic_stable_structures::storable::Bound::Bounded {
+            max_size: 128 * 1024, // 128KB max size for one term's postings
+            is_fixed_size: false,
+        };
+}
+
+impl Storable for VoteReceipt {
+    fn to_bytes(&self) -> std::borrow::Cow<'_, [u8]> {
+        std::borrow::Cow::Owned(candid::encode_one(self).unwrap())
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<'_, [u8]>) -> Self {
+        candid::decode_one(&bytes).unwrap()
+    }
+
+    const BOUND: ic_stable_structures::storable::Bound =
+        ic_stable_structures::storable::Bound::Bounded {
+            max_size: 2048,
+            is_fixed_size: false,
+        };
+}
+
+impl Storable for CreationPolicy {
+    fn to_bytes(&self) -> std::borrow::Cow<'_, [u8]> {
+        std::borrow::Cow::Owned(candid::encode_one(self).unwrap())
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<'_, [u8]>) -> Self {
+        candid::decode_one(&bytes).unwrap_or_default()
+    }
+
+    const BOUND: ic_stable_structures::storable::Bound =
+        ic_stable_structures::storable::Bound::Bounded {
+            max_size: 64 * 1024, // 64KB max size, mostly the allowlist
+            is_fixed_size: false,
+        };
+}
+
+impl Storable for OutcallBudget {
+    fn to_bytes(&self) -> std::borrow::Cow<'_, [u8]> {
+        std::borrow::Cow::Owned(candid::encode_one(self).unwrap())
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<'_, [u8]>) -> Self {
+        candid::decode_one(&bytes).unwrap_or_default()
+    }
+
+    const BOUND: ic_stable_structures::storable::Bound =
+        ic_stable_structures::storable::Bound::Bounded {
+            max_size: 64 * 1024, // Mostly the overrides
+            is_fixed_size: false,
+        };
+}
+
+impl Storable for LogLevel {
+    fn to_bytes(&self) -> std::borrow::Cow<'_, [u8]> {
+        std::borrow::Cow::Owned(candid::encode_one(self).unwrap())
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<'_, [u8]>) -> Self {
+        candid::decode_one(&bytes).unwrap_or_default()
+    }
+
+    const BOUND: ic_stable_structures::storable::Bound =
+        ic_stable_structures::storable::Bound::Bounded {
+            max_size: 64,
+            is_fixed_size: false,
+        };
+}
+
+impl Storable for LogEntry {
+    fn to_bytes(&self) -> std::borrow::Cow<'_, [u8]> {
+        std::borrow::Cow::Owned(candid::encode_one(self).unwrap())
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<'_, [u8]>) -> Self {
+        candid::decode_one(&bytes).unwrap()
+    }
+
+    const BOUND: ic_stable_structures::storable::Bound =
+        ic_stable_structures::storable::Bound::Bounded {
+            max_size: 4 * 1024, // Messages are truncated to fit
+            is_fixed_size: false,
+        };
+}
+
+impl Storable for PermawebGateways {
+    fn to_bytes(&self) -> std::borrow::Cow<'_, [u8]> {
+        std::borrow::Cow::Owned(candid::encode_one(self).unwrap())
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<'_, [u8]>) -> Self {
+        candid::decode_one(&bytes).unwrap_or_default()
+    }
+
+    const BOUND: ic_stable_structures::storable::Bound =
+        ic_stable_structures::storable::Bound::Bounded {
+            max_size: 8 * 1024,
+            is_fixed_size: false,
+        };
+}
+
+impl Storable for PinnedDocument {
+    fn to_bytes(&self) -> std::borrow::Cow<'_, [u8]> {
+        std::borrow::Cow::Owned(candid::encode_one(self).unwrap())
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<'_, [u8]>) -> Self {
+        candid::decode_one(&bytes).unwrap()
+    }
+
+    const BOUND: ic_stable_structures::storable::Bound =
+        ic_stable_structures::storable::Bound::Bounded {
+            max_size: 1024,
+            is_fixed_size: false,
+        };
+}
+
+impl Storable for QueryAlias {
+    fn to_bytes(&self) -> std::borrow::Cow<'_, [u8]> {
+        std::borrow::Cow::Owned(candid::encode_one(self).unwrap())
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<'_, [u8]>) -> Self {
+        candid::decode_one(&bytes).unwrap()
+    }
+
+    const BOUND: ic_stable_structures::storable::Bound =
+        ic_stable_structures::storable::Bound::Bounded {
+            max_size: 2048,
+            is_fixed_size: false,
+        };
+}
+
+impl Storable for OutcallMirrors {
+    fn to_bytes(&self) -> std::borrow::Cow<'_, [u8]> {
+        std::borrow::Cow::Owned(candid::encode_one(self).unwrap())
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<'_, [u8]>) -> Self {
+        candid::decode_one(&bytes).unwrap_or_default()
+    }
+
+    const BOUND: ic_stable_structures::storable::Bound =
+        ic_stable_structures::storable::Bound::Bounded {
+            max_size: 8 * 1024,
+            is_fixed_size: false,
+        };
+}
+
+impl Storable for SiteConfig {
+    fn to_bytes(&self) -> std::borrow::Cow<'_, [u8]> {
+        std::borrow::Cow::Owned(candid::encode_one(self).unwrap())
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<'_, [u8]>) -> Self {
+        candid::decode_one(&bytes).unwrap_or_default()
+    }
+
+    const BOUND: ic_stable_structures::storable::Bound =
+        ic_stable_structures::storable::Bound::Bounded {
+            max_size: 2 * 1024,
+            is_fixed_size: false,
+        };
+}
+
+impl Storable for SitemapEntry {
+    fn to_bytes(&self) -> std::borrow::Cow<'_, [u8]> {
+        std::borrow::Cow::Owned(candid::encode_one(self).unwrap())
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<'_, [u8]>) -> Self {
+        candid::decode_one(&bytes).unwrap_or_else(|_| SitemapEntry {
+            path: String::new(),
+            updated_at: None,
+        })
+    }
+
+    const BOUND: ic_stable_structures::storable::Bound =
+        ic_stable_structures::storable::Bound::Bounded {
+            max_size: 4 * 1024, // Encoded IDs are capped and the slug is truncated
+            is_fixed_size: false,
+        };
+}
+
+impl Storable for BluebandReconcileState {
+    fn to_bytes(&self) -> std::borrow::Cow<'_, [u8]> {
+        std::borrow::Cow::Owned(candid::encode_one(self).unwrap())
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<'_, [u8]>) -> Self {
+        candid::decode_one(&bytes).unwrap_or_default()
+    }
+
+    const BOUND: ic_stable_structures::storable::Bound =
+        ic_stable_structures::storable::Bound::Bounded {
+            max_size: 1024 * 1024, // Reports list at most 1,000 findings of each kind
+            is_fixed_size: false,
+        };
+}
+
+impl Storable for GovernanceSubscriber {
+    fn to_bytes(&self) -> std::borrow::Cow<'_, [u8]> {
+        std::borrow::Cow::Owned(candid::encode_one(self).unwrap())
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<'_, [u8]>) -> Self {
+        candid::decode_one(&bytes).unwrap()
     }
+
+    const BOUND: ic_stable_structures::storable::Bound =
+        ic_stable_structures::storable::Bound::Bounded {
+            max_size: 2 * 1024,
+            is_fixed_size: false,
+        };
 }
 
-impl From<String> for ClanopediaError {
-    fn from(err: String) -> Self {
-        ClanopediaError::NotFound(err)
+impl Storable for ChatBot {
+    fn to_bytes(&self) -> std::borrow::Cow<'_, [u8]> {
+        std::borrow::Cow::Owned(candid::encode_one(self).unwrap())
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<'_, [u8]>) -> Self {
+        candid::decode_one(&bytes).unwrap()
     }
+
+    const BOUND: ic_stable_structures::storable::Bound =
+        ic_stable_structures::storable::Bound::Bounded {
+            max_size: 512,
+            is_fixed_size: false,
+        };
 }
 
-impl From<&str> for ClanopediaError {
-    fn from(err: &str) -> Self {
-        ClanopediaError::NotFound(err.to_string())
+impl Storable for BotDelegation {
+    fn to_bytes(&self) -> std::borrow::Cow<'_, [u8]> {
+        std::borrow::Cow::Owned(candid::encode_one(self).unwrap())
     }
+
+    fn from_bytes(bytes: std::borrow::Cow<'_, [u8]>) -> Self {
+        candid::decode_one(&bytes).unwrap()
+    }
+
+    const BOUND: ic_stable_structures::storable::Bound =
+        ic_stable_structures::storable::Bound::Bounded {
+            max_size: 256,
+            is_fixed_size: false,
+        };
 }
 
-impl From<StorageError> for ClanopediaError {
-    fn from(err: StorageError) -> Self {
-        match err {
-            StorageError::NotFound => ClanopediaError::NotFound("Resource not found".to_string()),
-            StorageError::AlreadyExists => {
-                ClanopediaError::AlreadyExists("Resource already exists".to_string())
-            }
-            StorageError::Other(msg) => ClanopediaError::StorageError(msg),
-        }
+impl Storable for ActivityEntry {
+    fn to_bytes(&self) -> std::borrow::Cow<'_, [u8]> {
+        std::borrow::Cow::Owned(candid::encode_one(self).unwrap())
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<'_, [u8]>) -> Self {
+        candid::decode_one(&bytes).unwrap()
     }
+
+    const BOUND: ic_stable_structures::storable::Bound =
+        ic_stable_structures::storable::Bound::Bounded {
+            max_size: 512,
+            is_fixed_size: false,
+        };
 }
 
-#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
-pub struct BluebandDocument {
-    pub id: DocumentId,
-    pub title: String,
-    pub content: String,
-    pub embedded: bool,
-    pub created_at: u64,
+impl Storable for BackupState {
+    fn to_bytes(&self) -> std::borrow::Cow<'_, [u8]> {
+        std::borrow::Cow::Owned(candid::encode_one(self).unwrap())
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<'_, [u8]>) -> Self {
+        candid::decode_one(&bytes).unwrap()
+    }
+
+    // Holds the chunk being filled, about a megabyte at most
+    const BOUND: ic_stable_structures::storable::Bound =
+        ic_stable_structures::storable::Bound::Unbounded;
 }
 
-#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
-pub struct BluebandConfig {
-    pub collection: String,
-    pub api_key: Option<String>,
+impl Storable for ShardRebalance {
+    fn to_bytes(&self) -> std::borrow::Cow<'_, [u8]> {
+        std::borrow::Cow::Owned(candid::encode_one(self).unwrap())
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<'_, [u8]>) -> Self {
+        candid::decode_one(&bytes).unwrap()
+    }
+
+    const BOUND: ic_stable_structures::storable::Bound =
+        ic_stable_structures::storable::Bound::Bounded {
+            max_size: 4 * 1024, // Holds one key of a sharded map
+            is_fixed_size: false,
+        };
 }
 
-// Constants
-pub const PROPOSAL_DURATION_NANOS: u64 = 7 * 24 * 60 * 60 * 1_000_000_000; // 7 days
+impl Storable for ProposalIndexBackfill {
+    fn to_bytes(&self) -> std::borrow::Cow<'_, [u8]> {
+        std::borrow::Cow::Owned(candid::encode_one(self).unwrap())
+    }
 
-#[derive(CandidType, Serialize, Deserialize, Clone, Debug)]
-pub struct GovernanceModelConfig {
-    pub is_permissionless: bool,
-    pub governance_token: Option<Principal>,
-    pub quorum_threshold: u32,
-    pub threshold: u32,
+    fn from_bytes(bytes: std::borrow::Cow<'_, [u8]>) -> Self {
+        candid::decode_one(&bytes).unwrap()
+    }
+
+    const BOUND: ic_stable_structures::storable::Bound =
+        ic_stable_structures::storable::Bound::Bounded {
+            max_size: 1024, // Holds one "collection_id:proposal_id" key
+            is_fixed_size: false,
+        };
 }
 
-impl Default for GovernanceModel {
-    fn default() -> Self {
-        GovernanceModel::Permissionless
+impl Storable for BackupSummary {
+    fn to_bytes(&self) -> std::borrow::Cow<'_, [u8]> {
+        std::borrow::Cow::Owned(candid::encode_one(self).unwrap())
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<'_, [u8]>) -> Self {
+        candid::decode_one(&bytes).unwrap()
     }
+
+    const BOUND: ic_stable_structures::storable::Bound =
+        ic_stable_structures::storable::Bound::Bounded {
+            max_size: 2 * 1024,
+            is_fixed_size: false,
+        };
 }
 
-#[derive(CandidType, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
-pub struct CyclesStatus {
-    pub clanopedia_balance: u64,
-    pub blueband_balance: u64,
-    pub clanopedia_healthy: bool,
-    pub blueband_healthy: bool,
+impl Storable for ReadReplica {
+    fn to_bytes(&self) -> std::borrow::Cow<'_, [u8]> {
+        std::borrow::Cow::Owned(candid::encode_one(self).unwrap())
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<'_, [u8]>) -> Self {
+        candid::decode_one(&bytes).unwrap()
+    }
+
+    const BOUND: ic_stable_structures::storable::Bound =
+        ic_stable_structures::storable::Bound::Bounded {
+            max_size: 256,
+            is_fixed_size: false,
+        };
 }
 
-#[derive(CandidType, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
-pub struct OperationCost {
-    pub base_cost: u64,
-    pub per_doc_cost: u64,
-    pub buffer_percentage: u32,
+impl Storable for PendingCreation {
+    fn to_bytes(&self) -> std::borrow::Cow<'_, [u8]> {
+        std::borrow::Cow::Owned(candid::encode_one(self).unwrap())
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<'_, [u8]>) -> Self {
+        candid::decode_one(&bytes).unwrap()
+    }
+
+    const BOUND: ic_stable_structures::storable::Bound =
+        ic_stable_structures::storable::Bound::Bounded {
+            max_size: 1024,
+            is_fixed_size: false,
+        };
 }
 
-#[derive(CandidType, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
-pub struct CostMetrics {
-    pub base_cost: u64,
-    pub total_cost: u64,
-    pub per_doc_cost: u64,
-    pub buffer_amount: u64,
+impl Storable for CreationRequest {
+    fn to_bytes(&self) -> std::borrow::Cow<'_, [u8]> {
+        std::borrow::Cow::Owned(candid::encode_one(self).unwrap())
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<'_, [u8]>) -> Self {
+        candid::decode_one(&bytes).unwrap()
+    }
+
+    const BOUND: ic_stable_structures::storable::Bound =
+        ic_stable_structures::storable::Bound::Bounded {
+            max_size: 256 * 1024, // 256KB max size for a requested config
+            is_fixed_size: false,
+        };
 }
 
-impl Storable for Proposal {
-    fn to_bytes(&self) -> std::borrow::Cow<[u8]> {
+impl Storable for ProposalAttachment {
+    fn to_bytes(&self) -> std::borrow::Cow<'_, [u8]> {
         std::borrow::Cow::Owned(candid::encode_one(self).unwrap())
     }
 
-    fn from_bytes(bytes: std::borrow::Cow<[u8]>) -> Self {
-        candid::decode_one(&bytes).unwrap_or_else(|_| Proposal {
-            id: String::new(),
-            collection_id: String::new(),
-            proposal_type: ProposalType::EmbedDocument { documents: vec![] },
-            creator: Principal::anonymous(),
-            description: String::new(),
-            created_at: 0,
-            expires_at: 0,
-            status: ProposalStatus::Active,
-            votes: HashMap::new(),
-            token_votes: HashMap::new(),
-            executed: false,
-            executed_at: None,
-            executed_by: None,
-            threshold: 0,
-            threshold_met: false,
-            sns_proposal_id: None
-        })
+    fn from_bytes(bytes: std::borrow::Cow<'_, [u8]>) -> Self {
+        candid::decode_one(&bytes).unwrap()
     }
 
     const BOUND: ic_stable_structures::storable::Bound =
         ic_stable_structures::storable::Bound::Bounded {
-            max_size: 1024 * 1024, // 1MB max size for a proposal
+            max_size: 1024,
             is_fixed_size: false,
         };
 }
 
-impl Storable for Collection {
-    fn to_bytes(&self) -> std::borrow::Cow<[u8]> {
+impl Storable for ChangeRecord {
+    fn to_bytes(&self) -> std::borrow::Cow<'_, [u8]> {
         std::borrow::Cow::Owned(candid::encode_one(self).unwrap())
     }
 
-    fn from_bytes(bytes: std::borrow::Cow<[u8]>) -> Self {
-        candid::decode_one(&bytes).unwrap_or_else(|_| Collection {
-            id: String::new(),
-            name: String::new(),
-            description: String::new(),
-            creator: Principal::anonymous(),
-            created_at: 0,
-            updated_at: 0,
-            admins: Vec::new(),
-            threshold: 0,
-            governance_token: None,
-            sns_governance_canister: None,
-            governance_model: GovernanceModel::Permissionless,
-            quorum_threshold: 0,
-            is_permissionless: false,
-            blueband_collection_id: String::new(),
-            proposals: HashMap::new(),
-            cycles_balance: 0,
-            proposal_counter: 0,
-        })
+    fn from_bytes(bytes: std::borrow::Cow<'_, [u8]>) -> Self {
+        candid::decode_one(&bytes).unwrap()
     }
 
     const BOUND: ic_stable_structures::storable::Bound =
         ic_stable_structures::storable::Bound::Bounded {
-            max_size: 2 * 1024 * 1024, // 2MB max size for a collection
+            max_size: 1024,
+            is_fixed_size: false,
+        };
+}
+
+impl Storable for DocumentNft {
+    fn to_bytes(&self) -> std::borrow::Cow<'_, [u8]> {
+        std::borrow::Cow::Owned(candid::encode_one(self).unwrap())
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<'_, [u8]>) -> Self {
+        candid::decode_one(&bytes).unwrap()
+    }
+
+    const BOUND: ic_stable_structures::storable::Bound =
+        ic_stable_structures::storable::Bound::Bounded {
+            max_size: 4 * 1024,
+            is_fixed_size: false,
+        };
+}
+
+impl Storable for Notification {
+    fn to_bytes(&self) -> std::borrow::Cow<'_, [u8]> {
+        std::borrow::Cow::Owned(candid::encode_one(self).unwrap())
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<'_, [u8]>) -> Self {
+        candid::decode_one(&bytes).unwrap()
+    }
+
+    const BOUND: ic_stable_structures::storable::Bound =
+        ic_stable_structures::storable::Bound::Bounded {
+            max_size: 4 * 1024,
+            is_fixed_size: false,
+        };
+}
+
+impl Storable for ReaderBookmarks {
+    fn to_bytes(&self) -> std::borrow::Cow<'_, [u8]> {
+        std::borrow::Cow::Owned(candid::encode_one(self).unwrap())
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<'_, [u8]>) -> Self {
+        candid::decode_one(&bytes).unwrap_or_default()
+    }
+
+    const BOUND: ic_stable_structures::storable::Bound =
+        ic_stable_structures::storable::Bound::Bounded {
+            max_size: 128 * 1024,
+            is_fixed_size: false,
+        };
+}
+
+impl Storable for ReactionCounts {
+    fn to_bytes(&self) -> std::borrow::Cow<'_, [u8]> {
+        std::borrow::Cow::Owned(candid::encode_one(self).unwrap())
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<'_, [u8]>) -> Self {
+        candid::decode_one(&bytes).unwrap_or_default()
+    }
+
+    const BOUND: ic_stable_structures::storable::Bound =
+        ic_stable_structures::storable::Bound::Bounded {
+            max_size: 256,
+            is_fixed_size: false,
+        };
+}
+
+impl Storable for EventLogEntry {
+    fn to_bytes(&self) -> std::borrow::Cow<'_, [u8]> {
+        std::borrow::Cow::Owned(candid::encode_one(self).unwrap())
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<'_, [u8]>) -> Self {
+        candid::decode_one(&bytes).unwrap()
+    }
+
+    const BOUND: ic_stable_structures::storable::Bound =
+        ic_stable_structures::storable::Bound::Bounded {
+            max_size: 4 * 1024,
             is_fixed_size: false,
         };
 }
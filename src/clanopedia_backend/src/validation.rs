@@ -0,0 +1,389 @@
+// src/clanopedia_backend/src/validation.rs
+
+use candid::Principal;
+
+use crate::{
+    AddDocumentRequest, ClanopediaError, ClanopediaResult, CollectionConfig, FieldError, License,
+    ProposalType, MAX_TIMELOCK_HOURS, MAX_VOTE_REMINDER_HOURS,
+};
+use crate::types::{
+    DEFAULT_MAX_DOCUMENT_BYTES, MAX_DEFERRED_RETRIES, MAX_OUTCALL_RETRIES, MAX_RETRY_DELAY_SECS,
+    MIN_DOCUMENT_BYTES,
+};
+
+const MAX_NAME_CHARS: usize = 100;
+const MAX_COLLECTION_DESCRIPTION_CHARS: usize = 2_000;
+const MAX_PROPOSAL_DESCRIPTION_CHARS: usize = 4_000;
+const MAX_TITLE_CHARS: usize = 300;
+const MAX_AUTHOR_CHARS: usize = 200;
+const MAX_TAGS: usize = 32;
+const MAX_TAG_CHARS: usize = 64;
+const MAX_URL_CHARS: usize = 2_048;
+const MAX_PRINCIPALS: usize = 100;
+const MAX_SPDX_CHARS: usize = 200;
+const MAX_LICENSE_TEXT_CHARS: usize = 4_000;
+
+/// Collects every problem with an input before failing, so callers can fix all fields at once.
+/// Text fields are sanitized in place: control characters and bidi overrides are removed and
+/// surrounding whitespace trimmed before lengths are checked.
+#[derive(Default)]
+struct Validator {
+    errors: Vec<FieldError>,
+}
+
+impl Validator {
+    fn error(&mut self, field: &str, message: String) {
+        self.errors.push(FieldError {
+            field: field.to_string(),
+            message,
+        });
+    }
+
+    fn single_line(&mut self, field: &str, value: &mut String, max_chars: usize, required: bool) {
+        *value = sanitize(value, false);
+        self.check_length(field, value, max_chars, required);
+    }
+
+    fn multi_line(&mut self, field: &str, value: &mut String, max_chars: usize, required: bool) {
+        *value = sanitize(value, true);
+        self.check_length(field, value, max_chars, required);
+    }
+
+    fn check_length(&mut self, field: &str, value: &str, max_chars: usize, required: bool) {
+        let len = value.chars().count();
+        if required && len == 0 {
+            self.error(field, "must not be empty".to_string());
+        } else if len > max_chars {
+            self.error(
+                field,
+                format!("is {} characters, the limit is {}", len, max_chars),
+            );
+        }
+    }
+
+    fn optional_line(&mut self, field: &str, value: &mut Option<String>, max_chars: usize) {
+        if let Some(inner) = value {
+            self.single_line(field, inner, max_chars, false);
+            if inner.is_empty() {
+                *value = None;
+            }
+        }
+    }
+
+    fn url(&mut self, field: &str, value: &mut Option<String>) {
+        let Some(url) = value else {
+            return;
+        };
+        *url = url.trim().to_string();
+        if url.is_empty() {
+            *value = None;
+        } else if let Err(message) = check_url(url) {
+            self.error(field, message);
+        }
+    }
+
+    fn tags(&mut self, field: &str, tags: &mut [String]) {
+        if tags.len() > MAX_TAGS {
+            self.error(
+                field,
+                format!("has {} tags, the limit is {}", tags.len(), MAX_TAGS),
+            );
+        }
+        for (i, tag) in tags.iter_mut().enumerate() {
+            self.single_line(&format!("{}[{}]", field, i), tag, MAX_TAG_CHARS, true);
+        }
+    }
+
+    // SPDX expressions are ids joined by AND, OR and WITH, optionally in parentheses
+    fn license(&mut self, field: &str, license: &mut License) {
+        match license {
+            License::Spdx(expression) => {
+                self.single_line(field, expression, MAX_SPDX_CHARS, true);
+                let valid = expression.chars().all(|c| {
+                    c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '+' | ':' | '(' | ')' | ' ')
+                });
+                if !valid {
+                    self.error(field, "is not a valid SPDX license expression".to_string());
+                }
+            }
+            License::Custom(text) => self.multi_line(field, text, MAX_LICENSE_TEXT_CHARS, true),
+        }
+    }
+
+    fn finish(self) -> ClanopediaResult<()> {
+        if self.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(ClanopediaError::ValidationFailed(self.errors))
+        }
+    }
+}
+
+// Strip characters that are invisible or reorder text; newlines and tabs survive in
+// multi-line fields
+fn sanitize(value: &str, multi_line: bool) -> String {
+    value
+        .chars()
+        .filter(|c| {
+            let allowed_control = multi_line && matches!(c, '\n' | '\t');
+            (!c.is_control() || allowed_control) && !is_bidi_control(*c)
+        })
+        .collect::<String>()
+        .trim()
+        .to_string()
+}
+
+fn is_bidi_control(c: char) -> bool {
+    matches!(c, '\u{200E}' | '\u{200F}' | '\u{202A}'..='\u{202E}' | '\u{2066}'..='\u{2069}')
+}
+
+fn check_url(url: &str) -> Result<(), String> {
+    if url.len() > MAX_URL_CHARS {
+        return Err(format!("is longer than {} bytes", MAX_URL_CHARS));
+    }
+    let Some(rest) = url
+        .strip_prefix("https://")
+        .or_else(|| url.strip_prefix("http://"))
+    else {
+        return Err("must be an http or https URL".to_string());
+    };
+    let host = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    if host.is_empty() {
+        return Err("has no host".to_string());
+    }
+    if url.chars().any(|c| c.is_whitespace() || c.is_control()) {
+        return Err("must not contain whitespace".to_string());
+    }
+    Ok(())
+}
+
+// ============================
+// ENDPOINT INPUTS
+// ============================
+
+pub fn validate_collection_config(config: &mut CollectionConfig) -> ClanopediaResult<()> {
+    let mut v = Validator::default();
+    collection_config_fields(&mut v, "", config);
+    v.finish()
+}
+
+fn collection_config_fields(v: &mut Validator, prefix: &str, config: &mut CollectionConfig) {
+    v.single_line(
+        &format!("{}name", prefix),
+        &mut config.name,
+        MAX_NAME_CHARS,
+        true,
+    );
+    v.multi_line(
+        &format!("{}description", prefix),
+        &mut config.description,
+        MAX_COLLECTION_DESCRIPTION_CHARS,
+        false,
+    );
+    if config.admins.len() > MAX_PRINCIPALS {
+        v.error(
+            &format!("{}admins", prefix),
+            format!(
+                "has {} entries, the limit is {}",
+                config.admins.len(),
+                MAX_PRINCIPALS
+            ),
+        );
+    }
+    for admin in config.admins.iter_mut() {
+        *admin = admin.trim().to_string();
+    }
+    if let Some(license) = config
+        .license
+        .as_mut()
+        .and_then(|l| l.default_license.as_mut())
+    {
+        v.license(&format!("{}license.default_license", prefix), license);
+    }
+    if let Some(hours) = config.vote_reminder_hours {
+        if hours > MAX_VOTE_REMINDER_HOURS {
+            v.error(
+                &format!("{}vote_reminder_hours", prefix),
+                format!("must be at most {}", MAX_VOTE_REMINDER_HOURS),
+            );
+        }
+    }
+    if let Some(hours) = config.timelock_hours {
+        if hours > MAX_TIMELOCK_HOURS {
+            v.error(
+                &format!("{}timelock_hours", prefix),
+                format!("must be at most {}", MAX_TIMELOCK_HOURS),
+            );
+        }
+    }
+    if let Some(policy) = config.review_policy.as_mut() {
+        let field = format!("{}review_policy", prefix);
+        policy.reviewers.sort();
+        policy.reviewers.dedup();
+        if policy.reviewers.len() > MAX_PRINCIPALS {
+            v.error(
+                &format!("{}.reviewers", field),
+                format!(
+                    "has {} entries, the limit is {}",
+                    policy.reviewers.len(),
+                    MAX_PRINCIPALS
+                ),
+            );
+        }
+        if policy.reviewers.contains(&Principal::anonymous()) {
+            v.error(
+                &format!("{}.reviewers", field),
+                "must not include the anonymous principal".to_string(),
+            );
+        }
+        // One more reviewer than required, so a reviewer's own proposals can still be reviewed
+        if policy.required_approvals == 0
+            || policy.reviewers.len() <= policy.required_approvals as usize
+        {
+            v.error(
+                &format!("{}.required_approvals", field),
+                "must be at least 1 and less than the number of reviewers".to_string(),
+            );
+        }
+    }
+    if let Some(limits) = &config.content_limits {
+        let field = format!("{}content_limits", prefix);
+        let max_document = limits
+            .max_document_bytes
+            .unwrap_or(DEFAULT_MAX_DOCUMENT_BYTES);
+        if !(MIN_DOCUMENT_BYTES..=DEFAULT_MAX_DOCUMENT_BYTES).contains(&max_document) {
+            v.error(
+                &format!("{}.max_document_bytes", field),
+                format!(
+                    "must be from {} to {}",
+                    MIN_DOCUMENT_BYTES, DEFAULT_MAX_DOCUMENT_BYTES
+                ),
+            );
+        }
+        if limits.max_total_bytes.is_some_and(|total| total < max_document) {
+            v.error(
+                &format!("{}.max_total_bytes", field),
+                "must be at least max_document_bytes".to_string(),
+            );
+        }
+    }
+    if let Some(policy) = &config.retry_policy {
+        let field = format!("{}retry_policy", prefix);
+        if policy.max_retries > MAX_OUTCALL_RETRIES {
+            v.error(
+                &format!("{}.max_retries", field),
+                format!("must be at most {}", MAX_OUTCALL_RETRIES),
+            );
+        }
+        if policy.max_deferred_retries > MAX_DEFERRED_RETRIES {
+            v.error(
+                &format!("{}.max_deferred_retries", field),
+                format!("must be at most {}", MAX_DEFERRED_RETRIES),
+            );
+        }
+        if policy.base_delay_secs == 0 || policy.base_delay_secs > MAX_RETRY_DELAY_SECS {
+            v.error(
+                &format!("{}.base_delay_secs", field),
+                format!("must be from 1 to {}", MAX_RETRY_DELAY_SECS),
+            );
+        }
+    }
+    if let Some(encryption) = &config.encryption {
+        if encryption.readers.len() > MAX_PRINCIPALS {
+            v.error(
+                &format!("{}encryption.readers", prefix),
+                format!(
+                    "has {} entries, the limit is {}",
+                    encryption.readers.len(),
+                    MAX_PRINCIPALS
+                ),
+            );
+        }
+    }
+}
+
+/// Validate a proposal's description and the user-supplied text inside its payload
+pub fn validate_proposal(
+    proposal_type: &mut ProposalType,
+    description: &mut String,
+) -> ClanopediaResult<()> {
+    let mut v = Validator::default();
+    v.multi_line(
+        "description",
+        description,
+        MAX_PROPOSAL_DESCRIPTION_CHARS,
+        false,
+    );
+
+    proposal_fields(&mut v, "", proposal_type);
+    v.finish()
+}
+
+fn proposal_fields(v: &mut Validator, prefix: &str, proposal_type: &mut ProposalType) {
+    match proposal_type {
+        ProposalType::UpdateCollection { config } => {
+            collection_config_fields(v, &format!("{}config.", prefix), config);
+        }
+        ProposalType::UpdateDocumentMetadata {
+            title,
+            tags,
+            author,
+            license,
+            ..
+        } => {
+            if let Some(title) = title {
+                v.single_line(&format!("{}title", prefix), title, MAX_TITLE_CHARS, true);
+            }
+            if let Some(tags) = tags {
+                v.tags(&format!("{}tags", prefix), tags);
+            }
+            v.optional_line(&format!("{}author", prefix), author, MAX_AUTHOR_CHARS);
+            if let Some(license) = license {
+                v.license(&format!("{}license", prefix), license);
+            }
+        }
+        ProposalType::Composite { actions } => {
+            for (i, action) in actions.iter_mut().enumerate() {
+                proposal_fields(v, &format!("{}actions[{}].", prefix, i), action);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Validate documents about to be embedded. Content size is checked separately by the
+/// extractor; encrypted content is ciphertext and is left untouched.
+pub fn validate_documents(documents: &mut [AddDocumentRequest]) -> ClanopediaResult<()> {
+    let mut v = Validator::default();
+    for (i, document) in documents.iter_mut().enumerate() {
+        document_fields(&mut v, &format!("documents[{}].", i), document);
+    }
+    v.finish()
+}
+
+pub fn validate_document(document: &mut AddDocumentRequest) -> ClanopediaResult<()> {
+    let mut v = Validator::default();
+    document_fields(&mut v, "", document);
+    v.finish()
+}
+
+fn document_fields(v: &mut Validator, prefix: &str, document: &mut AddDocumentRequest) {
+    v.single_line(
+        &format!("{}title", prefix),
+        &mut document.title,
+        MAX_TITLE_CHARS,
+        true,
+    );
+    v.url(&format!("{}source_url", prefix), &mut document.source_url);
+    v.optional_line(
+        &format!("{}author", prefix),
+        &mut document.author,
+        MAX_AUTHOR_CHARS,
+    );
+    if let Some(tags) = &mut document.tags {
+        v.tags(&format!("{}tags", prefix), tags);
+    }
+    if let Some(license) = &mut document.license {
+        v.license(&format!("{}license", prefix), license);
+    }
+}
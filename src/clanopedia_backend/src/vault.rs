@@ -0,0 +1,205 @@
+// src/clanopedia_backend/src/vault.rs
+//
+// Values are sealed with XChaCha20-Poly1305 under a key derived from the canister secret.
+// A sealed value is `FORMAT || nonce || ciphertext || tag`. Values sealed under the earlier
+// hash-based scheme are re-sealed on upgrade; that scheme is kept only to read them.
+
+use chacha20poly1305::{
+    aead::{Aead, KeyInit},
+    XChaCha20Poly1305, XNonce,
+};
+use ic_cdk::api::management_canister::main::raw_rand;
+use sha2::{Digest, Sha256};
+use subtle::ConstantTimeEq;
+
+use crate::{
+    logging, storage,
+    types::{ClanopediaError, ClanopediaResult},
+};
+
+const FORMAT: &[u8] = b"cpv2";
+const NONCE_LEN: usize = 24;
+const LEGACY_NONCE_LEN: usize = 16;
+const LEGACY_TAG_LEN: usize = 32;
+
+// ============================
+// CANISTER SECRET
+// ============================
+
+pub async fn random_bytes() -> ClanopediaResult<Vec<u8>> {
+    raw_rand()
+        .await
+        .map(|(bytes,)| bytes)
+        .map_err(|(_, e)| ClanopediaError::ExternalCallError(format!("raw_rand failed: {}", e)))
+}
+
+// The secret is drawn from the management canister once and kept in stable memory
+async fn canister_secret() -> ClanopediaResult<Vec<u8>> {
+    if let Some(secret) = storage::get_canister_secret() {
+        return Ok(secret);
+    }
+
+    let secret = random_bytes().await?;
+
+    // Another call may have initialized it while we were waiting
+    if let Some(existing) = storage::get_canister_secret() {
+        return Ok(existing);
+    }
+    storage::set_canister_secret(secret.clone());
+    Ok(secret)
+}
+
+fn stored_secret() -> ClanopediaResult<Vec<u8>> {
+    storage::get_canister_secret().ok_or_else(|| {
+        ClanopediaError::InvalidOperation("Canister secret not initialized".to_string())
+    })
+}
+
+// ============================
+// SEALING
+// ============================
+
+fn cipher(secret: &[u8]) -> XChaCha20Poly1305 {
+    let key = Sha256::new()
+        .chain_update(b"clanopedia-vault-key")
+        .chain_update(secret)
+        .finalize();
+    XChaCha20Poly1305::new(&key)
+}
+
+fn seal_with_nonce(secret: &[u8], nonce: &[u8], plaintext: &[u8]) -> ClanopediaResult<Vec<u8>> {
+    let ciphertext = cipher(secret)
+        .encrypt(XNonce::from_slice(nonce), plaintext)
+        .map_err(|_| ClanopediaError::InvalidOperation("Sealing failed".to_string()))?;
+
+    let mut sealed = FORMAT.to_vec();
+    sealed.extend_from_slice(nonce);
+    sealed.extend(ciphertext);
+    Ok(sealed)
+}
+
+/// Encrypt `plaintext` under the canister secret with a random nonce
+pub async fn seal(plaintext: &[u8]) -> ClanopediaResult<Vec<u8>> {
+    let secret = canister_secret().await?;
+    let random = random_bytes().await?;
+    let nonce = random.get(..NONCE_LEN).ok_or_else(|| {
+        ClanopediaError::ExternalCallError("raw_rand returned too few bytes".to_string())
+    })?;
+    seal_with_nonce(&secret, nonce, plaintext)
+}
+
+/// Seal without drawing randomness, for upgrade hooks, which cannot make calls. The nonce is
+/// derived from `nonce_context`, so no context may be sealed under twice.
+pub fn seal_in_upgrade(plaintext: &[u8], nonce_context: &[u8]) -> ClanopediaResult<Vec<u8>> {
+    let secret = stored_secret()?;
+    let nonce = Sha256::new()
+        .chain_update(b"clanopedia-vault-nonce")
+        .chain_update(nonce_context)
+        .finalize();
+    seal_with_nonce(&secret, &nonce[..NONCE_LEN], plaintext)
+}
+
+/// Decrypt a value produced by `seal`, rejecting it if it was tampered with
+pub fn open(sealed: &[u8]) -> ClanopediaResult<Vec<u8>> {
+    let secret = stored_secret()?;
+    let body = sealed
+        .strip_prefix(FORMAT)
+        .filter(|body| body.len() >= NONCE_LEN)
+        .ok_or_else(|| ClanopediaError::InvalidInput("Sealed value is malformed".to_string()))?;
+
+    let (nonce, ciphertext) = body.split_at(NONCE_LEN);
+    cipher(&secret)
+        .decrypt(XNonce::from_slice(nonce), ciphertext)
+        .map_err(|_| {
+            ClanopediaError::InvalidInput("Sealed value failed authentication".to_string())
+        })
+}
+
+// ============================
+// LEGACY SCHEME
+// ============================
+
+/// Re-seal collection secrets still in the legacy format; run on upgrade
+pub fn migrate_legacy_secrets() {
+    let Some(secret) = storage::get_canister_secret() else {
+        return; // Nothing can have been sealed yet
+    };
+
+    for collection in storage::list_collections() {
+        for (name, mut sealed) in storage::list_secrets(&collection.id) {
+            if open(&sealed.ciphertext).is_ok() {
+                continue;
+            }
+            let resealed = open_legacy(&secret, &sealed.ciphertext).and_then(|plaintext| {
+                let mut context = b"legacy:".to_vec();
+                context.extend_from_slice(&sealed.ciphertext[..LEGACY_NONCE_LEN]);
+                seal_in_upgrade(&plaintext, &context)
+            });
+            match resealed {
+                Ok(ciphertext) => {
+                    sealed.ciphertext = ciphertext;
+                    storage::set_secret(&collection.id, &name, sealed);
+                }
+                Err(e) => logging::warn(
+                    "vault",
+                    Some(&collection.id),
+                    format!("Secret {} could not be re-sealed: {}", name, e),
+                ),
+            }
+        }
+    }
+}
+
+// Legacy layout: `nonce || ciphertext || tag`, a SHA-256 keystream with a nested-hash tag
+fn open_legacy(secret: &[u8], sealed: &[u8]) -> ClanopediaResult<Vec<u8>> {
+    if sealed.len() < LEGACY_NONCE_LEN + LEGACY_TAG_LEN {
+        return Err(ClanopediaError::InvalidInput(
+            "Sealed value is too short".to_string(),
+        ));
+    }
+
+    let (nonce, rest) = sealed.split_at(LEGACY_NONCE_LEN);
+    let (ciphertext, tag) = rest.split_at(rest.len() - LEGACY_TAG_LEN);
+
+    if !bool::from(legacy_tag(secret, nonce, ciphertext).ct_eq(tag)) {
+        return Err(ClanopediaError::InvalidInput(
+            "Sealed value failed authentication".to_string(),
+        ));
+    }
+
+    Ok(legacy_keystream(secret, nonce, ciphertext))
+}
+
+fn legacy_keystream(secret: &[u8], nonce: &[u8], data: &[u8]) -> Vec<u8> {
+    data.chunks(32)
+        .enumerate()
+        .flat_map(|(block, chunk)| {
+            let keystream = Sha256::new()
+                .chain_update(b"clanopedia-vault-enc")
+                .chain_update(secret)
+                .chain_update(nonce)
+                .chain_update((block as u64).to_be_bytes())
+                .finalize();
+            chunk
+                .iter()
+                .zip(keystream)
+                .map(|(byte, key)| byte ^ key)
+                .collect::<Vec<u8>>()
+        })
+        .collect()
+}
+
+fn legacy_tag(secret: &[u8], nonce: &[u8], ciphertext: &[u8]) -> Vec<u8> {
+    let inner = Sha256::new()
+        .chain_update(b"clanopedia-vault-mac-inner")
+        .chain_update(secret)
+        .chain_update(nonce)
+        .chain_update(ciphertext)
+        .finalize();
+    Sha256::new()
+        .chain_update(b"clanopedia-vault-mac-outer")
+        .chain_update(secret)
+        .chain_update(inner)
+        .finalize()
+        .to_vec()
+}
@@ -0,0 +1,139 @@
+// src/clanopedia_backend/src/views.rs
+//
+// Document views, counted when a reader opens a document through `get_document_endpoint`
+// or when a frontend that caches content calls `record_view`. A principal's views of one
+// document count once per hour, and anonymous readers count as one principal, so reloading a
+// page does not make a document trend. Views are kept as a running total plus one counter
+// per document per day; days older than the longest trending window are dropped.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use candid::Principal;
+use ic_cdk::api::time;
+
+use crate::{
+    reactions, storage, trash,
+    types::{
+        ClanopediaError, ClanopediaResult, DocumentId, StorageMap, TrendingDocument, TrendingWindow,
+    },
+};
+
+const DAY_NANOS: u64 = 24 * 60 * 60 * 1_000_000_000;
+const REPEAT_VIEW_NANOS: u64 = 60 * 60 * 1_000_000_000;
+const MAX_RECENT_VIEWS: usize = 10_000; // Heap entries before old ones are pruned
+const RETAINED_DAYS: u64 = 30; // The longest trending window
+const MAX_TRENDING: usize = 20;
+const MAX_ID_CHARS: usize = 256;
+
+thread_local! {
+    // When each principal's view of a document last counted
+    static RECENT_VIEWS: RefCell<HashMap<(Principal, String), u64>> = RefCell::new(HashMap::new());
+    // Collections whose old daily counts were pruned today
+    static PRUNED: RefCell<(u64, Vec<String>)> = const { RefCell::new((0, Vec::new())) };
+}
+
+// Whether this view counts, remembering it if so
+fn is_new_view(viewer: Principal, key: String, now: u64) -> bool {
+    RECENT_VIEWS.with(|recent| {
+        let mut recent = recent.borrow_mut();
+        if recent
+            .get(&(viewer, key.clone()))
+            .is_some_and(|last| now.saturating_sub(*last) < REPEAT_VIEW_NANOS)
+        {
+            return false;
+        }
+        if recent.len() >= MAX_RECENT_VIEWS {
+            recent.retain(|_, last| now.saturating_sub(*last) < REPEAT_VIEW_NANOS);
+        }
+        recent.insert((viewer, key), now);
+        true
+    })
+}
+
+// Drop the collection's expired daily counts, at most once a day
+fn prune_once_a_day(collection_id: &str, today: u64) {
+    let due = PRUNED.with(|p| {
+        let mut p = p.borrow_mut();
+        if p.0 != today {
+            *p = (today, Vec::new());
+        }
+        if p.1.iter().any(|id| id == collection_id) {
+            return false;
+        }
+        p.1.push(collection_id.to_string());
+        true
+    });
+    if due {
+        storage::prune_daily_views(collection_id, today.saturating_sub(RETAINED_DAYS - 1));
+    }
+}
+
+/// Count a view of a document. Best effort: repeated views and views past the storage caps
+/// are not counted.
+pub fn count_view(collection_id: &str, document_id: &str, viewer: Principal) {
+    let now = time();
+    if !is_new_view(viewer, format!("{}:{}", collection_id, document_id), now) {
+        return;
+    }
+    let new_document = storage::get_total_views(collection_id, document_id) == 0;
+    if (new_document && storage::ensure_capacity(StorageMap::DocumentViews, 1).is_err())
+        || storage::ensure_capacity(StorageMap::DailyViews, 1).is_err()
+    {
+        return;
+    }
+    let today = now / DAY_NANOS;
+    storage::add_view(collection_id, document_id, today);
+    prune_once_a_day(collection_id, today);
+}
+
+/// Count a view from a frontend that read the document some other way, e.g. from its cache
+pub fn record_view(
+    caller: Principal,
+    collection_id: &str,
+    document_id: &str,
+) -> ClanopediaResult<()> {
+    let collection = storage::get_collection(&collection_id.to_string())?;
+    trash::ensure_not_trashed(&collection)?;
+    if document_id.is_empty() || document_id.len() > MAX_ID_CHARS {
+        return Err(ClanopediaError::InvalidInput(
+            "Invalid document ID".to_string(),
+        ));
+    }
+    if trash::is_document_trashed(collection_id, document_id) {
+        return Err(ClanopediaError::NotFound(format!(
+            "Document {} not found",
+            document_id
+        )));
+    }
+    count_view(collection_id, document_id, caller);
+    Ok(())
+}
+
+/// The most viewed documents of the last `window`, most viewed first
+pub fn get_trending_documents(
+    collection_id: &str,
+    window: TrendingWindow,
+) -> ClanopediaResult<Vec<TrendingDocument>> {
+    let collection = storage::get_collection(&collection_id.to_string())?;
+    trash::ensure_not_trashed(&collection)?;
+
+    let today = time() / DAY_NANOS;
+    let first_day = today.saturating_sub(window.days() - 1);
+    let mut trending: Vec<(DocumentId, u64)> = storage::sum_daily_views(collection_id, first_day)
+        .into_iter()
+        .filter(|(document_id, _)| !trash::is_document_trashed(collection_id, document_id))
+        .collect();
+    trending.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    trending.truncate(MAX_TRENDING);
+
+    Ok(trending
+        .into_iter()
+        .map(|(document_id, views)| TrendingDocument {
+            total_views: storage::get_total_views(collection_id, &document_id),
+            reactions: reactions::document_reactions(collection_id, &document_id),
+            document_id,
+            views,
+        })
+        .collect())
+}
@@ -0,0 +1,103 @@
+// src/clanopedia_backend/src/vote_projection.rs
+//
+// Projects a token-based proposal's outcome from the votes so far: yes, no and abstain
+// weight, the supply that has not voted, how far the yes weight is toward the quorum, and
+// whether the result can still change. The math is `governance::threshold_met`'s, against
+// the ledger's current total supply. Sealed proposals only count revealed votes, so
+// committed ones stay part of the remaining supply.
+
+use candid::Nat;
+use ic_cdk::api::time;
+
+use crate::{
+    external::token,
+    governance, storage,
+    types::{
+        ClanopediaError, ClanopediaResult, GovernanceModel, ProjectedOutcome, ProposalStatus, Vote,
+        VoteProjection,
+    },
+};
+
+pub async fn project_vote_outcome(
+    collection_id: &str,
+    proposal_id: &str,
+) -> ClanopediaResult<VoteProjection> {
+    let proposal = governance::get_proposal(collection_id, proposal_id)?;
+    let collection = storage::get_collection(&collection_id.to_string())?;
+    if collection.governance_model != GovernanceModel::TokenBased {
+        return Err(ClanopediaError::InvalidOperation(
+            "Vote projections are only available for token-based collections".to_string(),
+        ));
+    }
+    let ledger = collection
+        .governance_token
+        .map(token::ledger)
+        .ok_or_else(|| {
+            ClanopediaError::InvalidOperation(
+                "Token-based governance requires a governance token".to_string(),
+            )
+        })?;
+    let total_supply = token::get_token_total_supply(&ledger).await?;
+
+    let zero = || Nat::from(0u64);
+    let (mut yes_weight, mut no_weight, mut abstain_weight) = (zero(), zero(), zero());
+    for (voter, vote) in &proposal.votes {
+        let weight = proposal
+            .token_votes
+            .get(voter)
+            .cloned()
+            .unwrap_or_else(zero);
+        match vote {
+            Vote::Yes => yes_weight += weight,
+            Vote::No => no_weight += weight,
+            Vote::Abstain => abstain_weight += weight,
+        }
+    }
+    let voted = yes_weight.clone() + no_weight.clone() + abstain_weight.clone();
+    let remaining_weight = if total_supply > voted {
+        total_supply.clone() - voted
+    } else {
+        zero()
+    };
+
+    let quorum_weight = governance::token_quorum(&total_supply, collection.quorum_threshold);
+    let yes_weight_needed = if quorum_weight > yes_weight {
+        quorum_weight.clone() - yes_weight.clone()
+    } else {
+        zero()
+    };
+    let quorum_progress_percent = if quorum_weight == 0u64 {
+        100
+    } else {
+        let percent = (yes_weight.clone() * Nat::from(100u32)) / quorum_weight.clone();
+        u32::try_from(&percent.0).unwrap_or(100).min(100)
+    };
+
+    let outcome = match proposal.status {
+        ProposalStatus::Approved | ProposalStatus::Executed | ProposalStatus::PartiallyExecuted => {
+            ProjectedOutcome::Approved
+        }
+        ProposalStatus::Rejected | ProposalStatus::Expired => ProjectedOutcome::Failed,
+        ProposalStatus::Active if yes_weight_needed == 0u64 => ProjectedOutcome::Approved,
+        ProposalStatus::Active if proposal.expires_at < time() => ProjectedOutcome::Failed,
+        ProposalStatus::Active if remaining_weight < yes_weight_needed => {
+            ProjectedOutcome::Unreachable
+        }
+        ProposalStatus::Active => ProjectedOutcome::Open,
+    };
+
+    Ok(VoteProjection {
+        proposal_id: proposal.id,
+        total_supply,
+        yes_weight,
+        no_weight,
+        abstain_weight,
+        remaining_weight,
+        quorum_threshold: collection.quorum_threshold,
+        quorum_weight,
+        yes_weight_needed,
+        quorum_progress_percent,
+        voting_ends_at: proposal.expires_at,
+        outcome,
+    })
+}
@@ -0,0 +1,179 @@
+// src/clanopedia_backend/tests/flow.rs
+//
+// The whole path a document takes, against a PocketIC replica: create a collection, extract
+// a file, add its documents, vote their embedding proposal through, execute it and search.
+//
+// It needs the canister built with its in-memory stand-ins, and the PocketIC server:
+//
+//   cargo build --target wasm32-unknown-unknown --release --features mock-blueband,mock-services
+//   POCKET_IC_BIN=/path/to/pocket-ic cargo test --test flow -- --ignored
+//
+// CLANOPEDIA_WASM points at a wasm built elsewhere.
+
+use candid::types::value::IDLValue;
+use candid::{decode_one, encode_args, CandidType, Deserialize, Principal};
+use pocket_ic::{PocketIc, WasmResult};
+
+const DEFAULT_WASM: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/target/wasm32-unknown-unknown/release/clanopedia_backend.wasm"
+);
+const CYCLES: u128 = 100_000_000_000_000;
+
+// Subsets of the canister's types; candid fills the omitted opt fields with null and skips
+// the reply fields left out
+
+#[derive(CandidType)]
+enum GovernanceModel {
+    Multisig,
+}
+
+#[derive(CandidType)]
+struct CollectionConfig {
+    name: String,
+    description: String,
+    admins: Vec<String>,
+    threshold: u32,
+    quorum_threshold: u32,
+    is_permissionless: bool,
+    governance_model: GovernanceModel,
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug)]
+struct AddDocumentRequest {
+    title: String,
+    content: String,
+    collection_id: String,
+    tags: Option<Vec<String>>,
+}
+
+#[derive(CandidType, Deserialize, Debug)]
+struct ExtractionResponse {
+    documents: Vec<AddDocumentRequest>,
+}
+
+#[derive(CandidType, Deserialize, Debug)]
+struct AddDocumentsResult {
+    document_ids: Vec<String>,
+    proposal_id: Option<String>,
+}
+
+#[derive(CandidType, Deserialize, Debug)]
+enum Vote {
+    Yes,
+}
+
+#[derive(CandidType, Deserialize, Debug)]
+struct VectorMatch {
+    document_id: String,
+    score: f64,
+}
+
+// Errors are decoded loosely so a failing step shows the whole ClanopediaError
+type Reply<T> = Result<T, IDLValue>;
+
+struct Canister {
+    pic: PocketIc,
+    id: Principal,
+    admin: Principal,
+}
+
+impl Canister {
+    fn install() -> Self {
+        let wasm_path = std::env::var("CLANOPEDIA_WASM").unwrap_or(DEFAULT_WASM.to_string());
+        let wasm = std::fs::read(&wasm_path)
+            .unwrap_or_else(|e| panic!("Reading {} failed: {}", wasm_path, e));
+
+        let pic = PocketIc::new();
+        let admin = Principal::from_slice(&[7; 29]);
+        let id = pic.create_canister_with_settings(Some(admin), None);
+        pic.add_cycles(id, CYCLES);
+        pic.install_canister(id, wasm, Vec::new(), Some(admin));
+        Canister { pic, id, admin }
+    }
+
+    fn update<T: for<'de> Deserialize<'de> + CandidType>(&self, method: &str, arg: Vec<u8>) -> T {
+        let result = self
+            .pic
+            .update_call(self.id, self.admin, method, arg)
+            .unwrap_or_else(|e| panic!("{} failed: {:?}", method, e));
+        match result {
+            WasmResult::Reply(bytes) => decode_one(&bytes)
+                .unwrap_or_else(|e| panic!("{} replied with something else: {}", method, e)),
+            WasmResult::Reject(message) => panic!("{} was rejected: {}", method, message),
+        }
+    }
+}
+
+fn ok<T>(step: &str, reply: Reply<T>) -> T {
+    reply.unwrap_or_else(|e| panic!("{} failed: {}", step, e))
+}
+
+#[test]
+#[ignore = "needs the PocketIC server and a mock-blueband,mock-services wasm"]
+fn create_extract_add_vote_execute_search() {
+    let canister = Canister::install();
+
+    let config = CollectionConfig {
+        name: "Field notes".to_string(),
+        description: "Notes kept by the garden club".to_string(),
+        admins: vec![canister.admin.to_text()],
+        threshold: 1,
+        quorum_threshold: 1,
+        is_permissionless: false,
+        governance_model: GovernanceModel::Multisig,
+    };
+    let collection_id: String = ok(
+        "create_collection_endpoint",
+        canister.update("create_collection_endpoint", encode_args((config,)).unwrap()),
+    );
+
+    let file = b"# Composting\n\nTurn the heap every week so the compost breathes and \
+                 heats evenly.\n"
+        .to_vec();
+    let extracted: ExtractionResponse = ok(
+        "extract_from_file",
+        canister.update(
+            "extract_from_file",
+            encode_args((file, "composting.md", &collection_id)).unwrap(),
+        ),
+    );
+    assert!(!extracted.documents.is_empty(), "the file produced no documents");
+
+    let added: AddDocumentsResult = ok(
+        "add_extracted_documents",
+        canister.update(
+            "add_extracted_documents",
+            encode_args((&collection_id, extracted.documents)).unwrap(),
+        ),
+    );
+    let proposal_id = added
+        .proposal_id
+        .expect("adding documents creates an embedding proposal");
+
+    let _receipt: IDLValue = ok(
+        "vote_on_proposal_endpoint",
+        canister.update(
+            "vote_on_proposal_endpoint",
+            encode_args((&collection_id, &proposal_id, Vote::Yes)).unwrap(),
+        ),
+    );
+    ok::<()>(
+        "execute_proposal_endpoint",
+        canister.update(
+            "execute_proposal_endpoint",
+            encode_args((&collection_id, &proposal_id)).unwrap(),
+        ),
+    );
+
+    let matches: Vec<VectorMatch> = ok(
+        "search_collection",
+        canister.update(
+            "search_collection",
+            encode_args((&collection_id, "compost heap", Some(5u32))).unwrap(),
+        ),
+    );
+    let top = matches.first().expect("search found nothing");
+    assert!(added.document_ids.contains(&top.document_id));
+    assert!(top.score > 0.0);
+}
@@ -102,6 +102,10 @@ export const Collections: React.FC = () => {
                     errorMessage = 'You are not authorized to perform this action';
                 } else if ('InvalidOperation' in errorObj) {
                     errorMessage = errorObj.InvalidOperation;
+                } else if ('ValidationFailed' in errorObj) {
+                    errorMessage = errorObj.ValidationFailed
+                        .map((e: { field: string; message: string }) => `${e.field} ${e.message}`)
+                        .join('; ');
                 }
                 throw new Error(errorMessage);
             }
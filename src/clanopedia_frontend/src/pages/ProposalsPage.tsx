@@ -242,7 +242,7 @@ export function ProposalsPage() {
         try {
             const result = await ClanopediaActor.vote_on_proposal_endpoint(collectionId, proposalId, { [vote]: null });
             if ('Ok' in result) {
-                toast({ title: 'Vote submitted', description: `You voted ${vote}` });
+                toast({ title: 'Vote submitted', description: `You voted ${vote} (receipt #${result.Ok.sequence})` });
                 // Refresh proposals
                 const updatedResult = await ClanopediaActor.get_proposals_endpoint(collectionId);
                 if ('Ok' in updatedResult) setProposals(updatedResult.Ok);